@@ -0,0 +1,20 @@
+use rocket::{serde::json::Json, get};
+use tasks_db_lib::activity::{self, Activity};
+use tasks_db_lib::pagination::{CursorPage, DEFAULT_PER_PAGE, MAX_PER_PAGE};
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::audit::parse_since;
+
+// What has happened on the tenant's tasks, oldest first: e.g. GET /api/activity?since=2026-10-01,
+// then keep passing next_cursor back as ?cursor= to read on. Unlike GET /audit this is open to
+// everyone signed in, since it only covers tasks, assignments and comments.
+#[get("/activity?<since>&<cursor>&<per_page>")]
+pub async fn get_activity(since: Option<&str>, cursor: Option<i32>, per_page: Option<i64>, db: TenantDb) -> Result<Json<CursorPage<Activity>>, ApiError> {
+    let since = since.map(parse_since).transpose()?;
+    let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+    if !(1..=MAX_PER_PAGE).contains(&per_page) {
+        return Err(ApiError::BadRequest(format!("per_page must be between 1 and {}", MAX_PER_PAGE)));
+    }
+    let mut conn = db.get().await?;
+    Ok(Json(activity::read_feed(&mut conn, since, cursor, per_page).await?))
+}
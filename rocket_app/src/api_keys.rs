@@ -0,0 +1,178 @@
+use rocket::{serde::json::Json, State, get, post, put, delete};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::{Deserialize, Serialize};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use tasks_db_lib::models::{ApiKey, NewApiKey};
+use tasks_db_lib::quotas::{self, Usage};
+use crate::error::ApiError;
+use crate::auth::AuthenticatedUser;
+use crate::validation::{Validate, Validator, MAX_NAME_LEN};
+
+use crate::tenancy::DbPool;
+
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+const KEY_PREFIX_LEN: usize = 8;
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiKeyInput {
+    pub name: String,
+    // requests the key may make per UTC day and per calendar month; left out or null is no limit
+    pub daily_quota: Option<i32>,
+    pub monthly_quota: Option<i32>,
+}
+
+impl Validate for ApiKeyInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new()
+            .text("name", &self.name, MAX_NAME_LEN)
+            .limit("daily_quota", self.daily_quota)
+            .limit("monthly_quota", self.monthly_quota)
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiKeyQuotasInput {
+    pub daily_quota: Option<i32>,
+    pub monthly_quota: Option<i32>,
+}
+
+impl Validate for ApiKeyQuotasInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new()
+            .limit("daily_quota", self.daily_quota)
+            .limit("monthly_quota", self.monthly_quota)
+            .finish()
+    }
+}
+
+// The raw key is only ever returned here, at creation time; the database keeps a SHA-256 hash.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreatedApiKey {
+    pub api_key: String,
+    pub key: ApiKey,
+}
+
+pub fn generate_key() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+pub fn hash_key(raw_key: &str) -> String {
+    to_hex(&Sha256::digest(raw_key.as_bytes()))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Request guard for machine-to-machine callers that send `X-Api-Key: <key>`. Each request counts
+// once against the key's quotas, however many guards ask for the caller; past a quota it's 429.
+#[derive(Debug)]
+pub struct ApiKeyUser {
+    pub user_id: i32,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiKeyUser {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let raw_key = match req.headers().get_one(API_KEY_HEADER) {
+            Some(raw_key) => raw_key,
+            None => return ApiError::Unauthorized("Missing API key".to_string()).guard_failure(req),
+        };
+        let pool = match req.rocket().state::<DbPool>() {
+            Some(pool) => pool,
+            None => return ApiError::Internal("DbPool is not managed".to_string()).guard_failure(req),
+        };
+        let caller = req.local_cache_async(async { authenticate(pool, raw_key).await }).await;
+        match caller {
+            Ok(user_id) => Outcome::Success(ApiKeyUser { user_id: *user_id }),
+            Err(e) => e.clone().guard_failure(req),
+        }
+    }
+}
+
+// The owner of an active key, once the request has been counted against the key's quotas.
+async fn authenticate(pool: &DbPool, raw_key: &str) -> Result<i32, ApiError> {
+    let mut conn = pool.get().await?;
+    let Some(api_key) = ApiKey::find_active_by_hash(&mut conn, &hash_key(raw_key)).await? else {
+        return Err(ApiError::Unauthorized("Invalid or revoked API key".to_string()));
+    };
+    quotas::record_request(&mut conn, &api_key, chrono::Utc::now().date_naive()).await?;
+    Ok(api_key.user_id)
+}
+
+#[get("/api_keys")]
+pub async fn get_api_keys(pool: &State<DbPool>, auth: AuthenticatedUser) -> Result<Json<Vec<ApiKey>>, ApiError> {
+    let mut conn = pool.get().await?;
+    let api_keys = ApiKey::read_all_for_user(&mut conn, auth.user_id).await?;
+    Ok(Json(api_keys))
+}
+
+#[post("/api_keys", data = "<api_key>")]
+pub async fn create_api_key(pool: &State<DbPool>, auth: AuthenticatedUser, api_key: Json<ApiKeyInput>) -> Result<Json<CreatedApiKey>, ApiError> {
+    api_key.validate()?;
+    let mut conn = pool.get().await?;
+    let raw_key = generate_key();
+    let new_api_key = NewApiKey {
+        user_id: auth.user_id,
+        name: &api_key.name,
+        key_prefix: &raw_key[..KEY_PREFIX_LEN],
+        key_hash: &hash_key(&raw_key),
+        daily_quota: api_key.daily_quota,
+        monthly_quota: api_key.monthly_quota,
+    };
+    let key = ApiKey::create(&mut conn, new_api_key).await?;
+    Ok(Json(CreatedApiKey { api_key: raw_key, key }))
+}
+
+#[delete("/api_keys/<id>")]
+pub async fn revoke_api_key(id: i32, pool: &State<DbPool>, auth: AuthenticatedUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = pool.get().await?;
+    match ApiKey::revoke(&mut conn, id, auth.user_id).await? {
+        0 => Err(ApiError::not_found("API key")),
+        count => Ok(Json(count)),
+    }
+}
+
+#[put("/api_keys/<id>/quotas", data = "<quotas>")]
+pub async fn update_api_key_quotas(id: i32, pool: &State<DbPool>, auth: AuthenticatedUser, quotas: Json<ApiKeyQuotasInput>) -> Result<Json<ApiKey>, ApiError> {
+    quotas.validate()?;
+    let mut conn = pool.get().await?;
+    ApiKey::set_quotas(&mut conn, id, auth.user_id, quotas.daily_quota, quotas.monthly_quota).await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("API key"))
+}
+
+// Today's and this month's requests (UTC) with one of the signed-in user's keys.
+#[get("/api_keys/<id>/usage")]
+pub async fn get_api_key_usage(id: i32, pool: &State<DbPool>, auth: AuthenticatedUser) -> Result<Json<Usage>, ApiError> {
+    let mut conn = pool.get().await?;
+    let api_key = ApiKey::read_for_user(&mut conn, id, auth.user_id).await?.ok_or_else(|| ApiError::not_found("API key"))?;
+    Ok(Json(quotas::usage(&mut conn, &api_key, chrono::Utc::now().date_naive()).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_random_hex() {
+        let (one, two) = (generate_key(), generate_key());
+        assert_eq!(one.len(), 64);
+        assert!(one.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(one, two);
+    }
+
+    #[test]
+    fn hashes_are_sha256_hex() {
+        assert_eq!(hash_key("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
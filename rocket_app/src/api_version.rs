@@ -0,0 +1,117 @@
+use rocket::{Data, Request, Response};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::http::uri::Origin;
+
+// Versions with routes mounted under /api/v<N>. A v2 gets added here once it exists.
+pub const SUPPORTED_VERSIONS: &[u32] = &[1];
+pub const CURRENT_VERSION: u32 = 1;
+
+// Which API version a request was routed to. Unsupported versions still end up here so the
+// 404 catcher can answer 406 instead of "no route".
+#[derive(Clone, Copy)]
+pub struct ApiVersion(pub Option<u32>);
+
+impl ApiVersion {
+    pub fn unsupported(&self) -> Option<u32> {
+        self.0.filter(|version| !SUPPORTED_VERSIONS.contains(version))
+    }
+}
+
+// Routes live under /api/v<N>. Before routing, this fairing picks the version for every
+// /api request and rewrites the path to match:
+//   - `Accept: application/vnd.tasks.v<N>+json` wins when present,
+//   - otherwise a /api/v<N>/... path keeps its version,
+//   - otherwise (plain /api/...) the request gets CURRENT_VERSION, so older clients keep working.
+// Responses say which version answered in an Api-Version header.
+pub struct ApiVersioning;
+
+fn path_version(segment: &str) -> Option<u32> {
+    segment.strip_prefix('v')?.parse().ok()
+}
+
+fn accept_version(req: &Request<'_>) -> Option<u32> {
+    req.accept()?.iter()
+        .filter(|media_type| media_type.top() == "application")
+        .find_map(|media_type| media_type.sub().as_str().strip_prefix("vnd.tasks.v")?.strip_suffix("+json")?.parse().ok())
+}
+
+#[rocket::async_trait]
+impl Fairing for ApiVersioning {
+    fn info(&self) -> Info {
+        Info { name: "API versioning", kind: Kind::Request | Kind::Response }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        let path = req.uri().path().as_str().to_string();
+        let Some(rest) = path.strip_prefix("/api").filter(|rest| rest.is_empty() || rest.starts_with('/')) else {
+            return;
+        };
+        let trimmed = rest.trim_start_matches('/');
+        let (first, tail) = match trimmed.split_once('/') {
+            Some((first, tail)) => (first, format!("/{}", tail)),
+            None => (trimmed, String::new()),
+        };
+        let (from_path, rest) = match path_version(first) {
+            Some(version) => (Some(version), tail),
+            None => (None, rest.to_string()),
+        };
+        let version = accept_version(req).or(from_path).unwrap_or(CURRENT_VERSION);
+        let query = req.uri().query().map(|query| format!("?{}", query)).unwrap_or_default();
+        if let Ok(uri) = Origin::parse_owned(format!("/api/v{}{}{}", version, rest, query)) {
+            req.set_uri(uri);
+        }
+        req.local_cache(|| ApiVersion(Some(version)));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let routed = *req.local_cache(|| ApiVersion(None));
+        if let (Some(version), None) = (routed.0, routed.unsupported()) {
+            res.set_header(Header::new("Api-Version", version.to_string()));
+            res.adjoin_header(Header::new("Vary", "Accept"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::{Accept, MediaType, Status};
+    use rocket::local::blocking::Client;
+    use rocket::{catchers, get, routes};
+
+    #[get("/ping?<n>")]
+    fn ping(n: Option<i32>) -> String {
+        format!("pong {}", n.unwrap_or_default())
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .attach(ApiVersioning)
+            .mount("/api/v1", routes![ping])
+            .register("/", catchers![crate::catchers::not_found]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    #[test]
+    fn unversioned_paths_get_the_current_version() {
+        let client = client();
+        for path in ["/api/ping?n=2", "/api/v1/ping?n=2"] {
+            let response = client.get(path).dispatch();
+            assert_eq!(response.status(), Status::Ok, "{}", path);
+            assert_eq!(response.headers().get_one("Api-Version"), Some("1"));
+            assert_eq!(response.into_string().unwrap(), "pong 2");
+        }
+    }
+
+    #[test]
+    fn an_unsupported_version_is_not_acceptable() {
+        let client = client();
+        let v2 = Accept::from(MediaType::new("application", "vnd.tasks.v2+json"));
+        let response = client.get("/api/v1/ping").header(v2).dispatch();
+        assert_eq!(response.status(), Status::NotAcceptable);
+        assert!(response.headers().get_one("Api-Version").is_none());
+
+        assert_eq!(client.get("/api/v3/ping").dispatch().status(), Status::NotAcceptable);
+    }
+}
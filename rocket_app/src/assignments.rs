@@ -1,10 +1,24 @@
-use rocket::{serde::json::Json, State, get, post, put, delete};
-use diesel::r2d2::{self, ConnectionManager};
-use diesel::sqlite::SqliteConnection;
-use tasks_db_lib::models::{UserTask, NewUserTask};
-use tasks_db_lib::crud::CrudOperations;
-
-pub type DbPool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+use std::collections::HashMap;
+use rocket::{State, get, post, put, patch, delete};
+use tasks_db_lib::models::{AssignmentDetail, Task, TaskStatus, User, UserTask, NewUserTask, UserTaskChanges};
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::sorting::USER_TASK_SORT_COLUMNS;
+use tasks_db_lib::filters::AssignmentFilter;
+use crate::error::ApiError;
+use crate::msgpack::{Negotiated, Payload};
+use crate::repository::AssignmentRepository;
+use crate::conditional::{CacheValidators, Cached, IfMatch};
+use crate::auth::{AuthenticatedUser, ManagerUser};
+use tasks_db_lib::enums::UserRole;
+use crate::pagination::{Count, PageQuery};
+use crate::validation::{FieldError, Validate, Validator};
+use crate::bulk::{self, BulkResponse};
+use crate::fields::{Fields, Sparse, USER_TASK_FIELDS};
+use crate::links::{linked, linked_all, Linked};
+use crate::dependencies::check_not_blocked;
+use crate::teams::check_team_member;
+use crate::overdue::OverdueConfig;
+use crate::events::{EventBus, StatusChange, ASSIGNMENT_CREATED, ASSIGNMENT_DELETED, ASSIGNMENT_STATUS_CHANGED};
 
 #[derive(rocket::serde::Deserialize)]
 pub struct UserTaskInput {
@@ -13,45 +27,409 @@ pub struct UserTaskInput {
     pub task_status_id: i32,
 }
 
+// Identifies one assignment in DELETE /assignments/bulk.
+#[derive(rocket::serde::Deserialize, rocket::serde::Serialize, Clone, Copy)]
+pub struct AssignmentKey {
+    pub user_id: i32,
+    pub task_id: i32,
+}
+
+impl Validate for AssignmentKey {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new()
+            .id("user_id", self.user_id)
+            .id("task_id", self.task_id)
+            .finish()
+    }
+}
+
+// PATCH body: the key is in the path, so the status is the only thing left to change.
+#[derive(rocket::serde::Deserialize)]
+pub struct UserTaskPatch {
+    pub task_status_id: Option<i32>,
+}
+
+impl Validate for UserTaskPatch {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        if let Some(task_status_id) = self.task_status_id {
+            validator.id("task_status_id", task_status_id);
+        }
+        validator.finish()
+    }
+}
+
+// ?include=user,task,status embeds the rows an assignment points at, next to its ids.
+#[derive(Default)]
+pub struct Includes {
+    pub user: bool,
+    pub task: bool,
+    pub status: bool,
+}
+
+impl Includes {
+    pub fn parse(raw: Option<&str>) -> Result<Includes, ApiError> {
+        let mut includes = Includes::default();
+        for name in raw.into_iter().flat_map(|raw| raw.split(',')).map(str::trim).filter(|name| !name.is_empty()) {
+            match name {
+                "user" => includes.user = true,
+                "task" => includes.task = true,
+                "status" => includes.status = true,
+                other => return Err(ApiError::BadRequest(format!("Cannot include '{}'; expected user, task or status", other))),
+            }
+        }
+        Ok(includes)
+    }
+
+    fn any(&self) -> bool {
+        self.user || self.task || self.status
+    }
+}
+
+#[derive(rocket::serde::Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ExpandedUserTask {
+    #[serde(flatten)]
+    pub user_task: Linked<UserTask>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<Linked<Task>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TaskStatus>,
+}
+
+impl ExpandedUserTask {
+    fn bare(user_task: UserTask) -> ExpandedUserTask {
+        ExpandedUserTask { user_task: linked(user_task), user: None, task: None, status: None }
+    }
+}
+
+impl Validate for UserTaskInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new()
+            .id("user_id", self.user_id)
+            .id("task_id", self.task_id)
+            .id("task_status_id", self.task_status_id)
+            .finish()
+    }
+}
 
+// e.g. GET /api/assignments?user_id=3&task_status_id=2, or ?include=user,task,status to
+// embed the related rows (fetched with one join, not a lookup per row)
+#[get("/assignments?<user_id>&<task_id>&<task_status_id>&<project_id>&<include>&<fields>&<paging..>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_user_tasks(user_id: Option<i32>, task_id: Option<i32>, task_status_id: Option<i32>, project_id: Option<i32>, include: Option<&str>, fields: Option<&str>, assignments: &State<Box<dyn AssignmentRepository>>, user: AuthenticatedUser, paging: PageQuery) -> Result<Negotiated<Page<Sparse<ExpandedUserTask>>>, ApiError> {
+    let includes = Includes::parse(include)?;
+    let fields = Fields::parse(fields, USER_TASK_FIELDS)?;
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(USER_TASK_SORT_COLUMNS)?;
+    let filter = AssignmentFilter { user_id, task_id, task_status_id, project_id };
+    let user_tasks = if includes.any() {
+        let rows = assignments.read_page_joined(user.tenant_id, &filter, page, per_page, &sort).await?;
+        let items = rows.items.into_iter()
+            .map(|(user_task, user, task, status)| ExpandedUserTask {
+                user_task: linked(user_task),
+                user: includes.user.then_some(user),
+                task: includes.task.then(|| linked(task)),
+                status: includes.status.then_some(status),
+            })
+            .collect();
+        Page::new(items, rows.page, rows.per_page, rows.total)
+    } else {
+        let rows = assignments.read_page(user.tenant_id, &filter, page, per_page, &sort).await?;
+        Page::new(rows.items.into_iter().map(ExpandedUserTask::bare).collect(), rows.page, rows.per_page, rows.total)
+    };
+    Ok(Negotiated(fields.apply(user_tasks)))
+}
+
+// Takes the same filters as GET /assignments, e.g. GET /api/assignments/count?task_status_id=2
+#[get("/assignments/count?<user_id>&<task_id>&<task_status_id>&<project_id>")]
+pub async fn count_user_tasks(user_id: Option<i32>, task_id: Option<i32>, task_status_id: Option<i32>, project_id: Option<i32>, assignments: &State<Box<dyn AssignmentRepository>>, user: AuthenticatedUser) -> Result<Negotiated<Count>, ApiError> {
+    let filter = AssignmentFilter { user_id, task_id, task_status_id, project_id };
+    Ok(Negotiated(Count { count: assignments.count(user.tenant_id, &filter).await? }))
+}
 
-#[get("/assignments")]
-pub async fn get_user_tasks(pool: &State<DbPool>) -> Json<Vec<UserTask>> {
-    let mut conn = pool.get().expect("db connection");
-    let user_tasks = UserTask::read_all(&mut conn).unwrap_or_default();
-    Json(user_tasks)
+// e.g. GET /api/users/3/assignments?task_status_id=2 for one user's open work
+#[get("/users/<id>/assignments?<task_status_id>&<paging..>")]
+pub async fn get_user_assignments(id: i32, task_status_id: Option<i32>, assignments: &State<Box<dyn AssignmentRepository>>, user: AuthenticatedUser, paging: PageQuery) -> Result<Negotiated<Page<Linked<UserTask>>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(USER_TASK_SORT_COLUMNS)?;
+    if assignments.read_user(user.tenant_id, id).await?.is_none() {
+        return Err(ApiError::not_found("User"));
+    }
+    let user_tasks = assignments.read_by_user(user.tenant_id, id, task_status_id, page, per_page, &sort).await?;
+    Ok(Negotiated(Page::new(linked_all(user_tasks.items), user_tasks.page, user_tasks.per_page, user_tasks.total)))
+}
+
+// Who is on a task and where each of them is with it.
+#[get("/tasks/<id>/assignments?<task_status_id>&<paging..>")]
+pub async fn get_task_assignments(id: i32, task_status_id: Option<i32>, assignments: &State<Box<dyn AssignmentRepository>>, user: AuthenticatedUser, paging: PageQuery) -> Result<Negotiated<Page<Linked<UserTask>>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(USER_TASK_SORT_COLUMNS)?;
+    if assignments.read_task(user.tenant_id, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    let user_tasks = assignments.read_by_task(user.tenant_id, id, task_status_id, page, per_page, &sort).await?;
+    Ok(Negotiated(Page::new(linked_all(user_tasks.items), user_tasks.page, user_tasks.per_page, user_tasks.total)))
+}
+
+// Flat rows with user, task and status names already filled in, for list screens.
+#[get("/assignments/detailed?<user_id>&<task_id>&<task_status_id>&<project_id>&<paging..>")]
+pub async fn get_assignment_details(user_id: Option<i32>, task_id: Option<i32>, task_status_id: Option<i32>, project_id: Option<i32>, assignments: &State<Box<dyn AssignmentRepository>>, user: AuthenticatedUser, paging: PageQuery) -> Result<Negotiated<Page<AssignmentDetail>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(USER_TASK_SORT_COLUMNS)?;
+    let filter = AssignmentFilter { user_id, task_id, task_status_id, project_id };
+    Ok(Negotiated(assignments.read_details(user.tenant_id, &filter, page, per_page, &sort).await?))
 }
 
 #[get("/assignments/<user_id>/<task_id>")]
-pub async fn get_user_task(user_id: i32, task_id: i32, pool: &State<DbPool>) -> Option<Json<UserTask>> {
-    let mut conn = pool.get().ok()?;
-    UserTask::read(&mut conn, (user_id, task_id)).ok().flatten().map(Json)
+pub async fn get_user_task(user_id: i32, task_id: i32, assignments: &State<Box<dyn AssignmentRepository>>, user: AuthenticatedUser, validators: CacheValidators) -> Result<Cached<Linked<UserTask>>, ApiError> {
+    assignments.read(user.tenant_id, (user_id, task_id)).await?
+        .map(|row| validators.respond(linked(row)))
+        .ok_or_else(|| ApiError::not_found("Assignment"))
 }
 
 #[put("/assignments/<user_id>/<task_id>", data = "<user_task>")]
-pub async fn update_user_task(user_id: i32, task_id: i32, pool: &State<DbPool>, user_task: Json<UserTaskInput>) -> Option<Json<UserTask>> {
-    let mut conn = pool.get().ok()?;
+#[allow(clippy::too_many_arguments)]
+pub async fn update_user_task(user_id: i32, task_id: i32, assignments: &State<Box<dyn AssignmentRepository>>, config: &State<OverdueConfig>, events: &State<EventBus>, auth: AuthenticatedUser, if_match: IfMatch, user_task: Payload<UserTaskInput>) -> Result<Negotiated<Linked<UserTask>>, ApiError> {
+    // members may only move their own assignments
+    auth.require_self_or(user_id, UserRole::Manager)?;
+    user_task.validate()?;
+    if user_task.user_id != user_id || user_task.task_id != task_id {
+        return Err(ApiError::BadRequest("user_id and task_id in the body must match the path".to_string()));
+    }
+    check_status(assignments.as_ref(), auth.tenant_id, user_task.task_status_id).await?;
+    check_not_blocked(assignments.as_ref(), auth.tenant_id, config, task_id, user_task.task_status_id).await?;
     let updated_user_task = NewUserTask {
         user_id: user_task.user_id,
         task_id: user_task.task_id,
         task_status_id: user_task.task_status_id
     };
-    UserTask::update(&mut conn, (user_id, task_id), updated_user_task).ok().map(Json)
+    let from_task_status_id = current_status(assignments.as_ref(), auth.tenant_id, user_id, task_id).await?;
+    let saved = linked(assignments.update(auth.tenant_id, auth.user_id, (user_id, task_id), if_match.expected(), updated_user_task).await?);
+    publish_moved(events, auth.tenant_id, from_task_status_id, &saved);
+    Ok(Negotiated(saved))
+}
+
+#[patch("/assignments/<user_id>/<task_id>", data = "<user_task>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn patch_user_task(user_id: i32, task_id: i32, assignments: &State<Box<dyn AssignmentRepository>>, config: &State<OverdueConfig>, events: &State<EventBus>, auth: AuthenticatedUser, if_match: IfMatch, user_task: Payload<UserTaskPatch>) -> Result<Negotiated<Linked<UserTask>>, ApiError> {
+    auth.require_self_or(user_id, UserRole::Manager)?;
+    user_task.validate()?;
+    if let Some(task_status_id) = user_task.task_status_id {
+        check_status(assignments.as_ref(), auth.tenant_id, task_status_id).await?;
+        check_not_blocked(assignments.as_ref(), auth.tenant_id, config, task_id, task_status_id).await?;
+    }
+    let changes = UserTaskChanges {
+        task_status_id: user_task.task_status_id,
+    };
+    let from_task_status_id = current_status(assignments.as_ref(), auth.tenant_id, user_id, task_id).await?;
+    let saved = linked(assignments.update_partial(auth.tenant_id, auth.user_id, (user_id, task_id), if_match.expected(), changes).await?);
+    publish_moved(events, auth.tenant_id, from_task_status_id, &saved);
+    Ok(Negotiated(saved))
 }
 
 #[post("/assignments", data = "<user_task>")]
-pub async fn create_user_task(pool: &State<DbPool>, user_task: Json<UserTaskInput>) -> Option<Json<UserTask>> {
-    let mut conn = pool.get().ok()?;
-    let new_user_task = NewUserTask {
+pub async fn create_user_task(assignments: &State<Box<dyn AssignmentRepository>>, config: &State<OverdueConfig>, events: &State<EventBus>, manager: ManagerUser, user_task: Payload<UserTaskInput>) -> Result<Negotiated<Linked<UserTask>>, ApiError> {
+    user_task.validate()?;
+    check_assignable(assignments.as_ref(), manager.tenant_id, config, &user_task).await?;
+    let created = assignments.create(manager.tenant_id, manager.user_id, to_new_user_task(&user_task)).await
+        .map_err(|e| e.on_conflict(|| already_assigned(user_task.user_id, user_task.task_id)))?;
+    let created = linked(created);
+    events.publish(manager.tenant_id, ASSIGNMENT_CREATED, &created);
+    Ok(Negotiated(created))
+}
+
+// Idempotent create-or-update for sync jobs: PUT the same body twice and nothing changes.
+#[put("/assignments", data = "<user_task>")]
+pub async fn upsert_user_task(assignments: &State<Box<dyn AssignmentRepository>>, config: &State<OverdueConfig>, events: &State<EventBus>, manager: ManagerUser, user_task: Payload<UserTaskInput>) -> Result<Negotiated<Linked<UserTask>>, ApiError> {
+    user_task.validate()?;
+    check_references(assignments.as_ref(), manager.tenant_id, &user_task).await?;
+    // moving an existing assignment is fine even if the user has since left the project's team
+    let existing = assignments.read(manager.tenant_id, (user_task.user_id, user_task.task_id)).await?;
+    if existing.is_none() {
+        check_team_member(assignments.as_ref(), manager.tenant_id, user_task.user_id, user_task.task_id).await?;
+    }
+    check_not_blocked(assignments.as_ref(), manager.tenant_id, config, user_task.task_id, user_task.task_status_id).await?;
+    let saved = linked(assignments.upsert(manager.tenant_id, manager.user_id, to_new_user_task(&user_task)).await?);
+    // PUTting an assignment unchanged is a no-op and isn't announced
+    match existing {
+        None => events.publish(manager.tenant_id, ASSIGNMENT_CREATED, &saved),
+        Some(existing) => publish_moved(events, manager.tenant_id, existing.task_status_id, &saved),
+    }
+    Ok(Negotiated(saved))
+}
+
+fn already_assigned(user_id: i32, task_id: i32) -> String {
+    format!("User {} is already assigned to task {}", user_id, task_id)
+}
+
+fn to_new_user_task(user_task: &UserTaskInput) -> NewUserTask {
+    NewUserTask {
         user_id: user_task.user_id,
         task_id: user_task.task_id,
         task_status_id: user_task.task_status_id
-    };
-    UserTask::create(&mut conn, new_user_task).ok().map(Json)
+    }
+}
+
+// What a new assignment has to pass on top of validation: the user is allowed on the task's
+// project (check_team_member) and the status isn't held back by an open blocker.
+async fn check_assignable(assignments: &dyn AssignmentRepository, tenant_id: i32, config: &OverdueConfig, user_task: &UserTaskInput) -> Result<(), ApiError> {
+    check_references(assignments, tenant_id, user_task).await?;
+    check_team_member(assignments, tenant_id, user_task.user_id, user_task.task_id).await?;
+    check_not_blocked(assignments, tenant_id, config, user_task.task_id, user_task.task_status_id).await
+}
+
+async fn check_all_assignable(assignments: &dyn AssignmentRepository, tenant_id: i32, config: &OverdueConfig, user_tasks: &[&UserTaskInput]) -> bulk::Checks {
+    let mut checks = Vec::with_capacity(user_tasks.len());
+    for user_task in user_tasks {
+        checks.push(check_assignable(assignments, tenant_id, config, user_task).await);
+    }
+    checks
+}
+
+async fn check_all_not_blocked(assignments: &dyn AssignmentRepository, tenant_id: i32, config: &OverdueConfig, user_tasks: &[&UserTaskInput]) -> bulk::Checks {
+    let mut checks = Vec::with_capacity(user_tasks.len());
+    for user_task in user_tasks {
+        let check = match check_status(assignments, tenant_id, user_task.task_status_id).await {
+            Ok(()) => check_not_blocked(assignments, tenant_id, config, user_task.task_id, user_task.task_status_id).await,
+            Err(e) => Err(e),
+        };
+        checks.push(check);
+    }
+    checks
+}
+
+// Nothing in the schema stops an id from pointing into another tenant, so the user, task and
+// status are looked up through the caller's tenant before anything links them.
+async fn check_references(assignments: &dyn AssignmentRepository, tenant_id: i32, user_task: &UserTaskInput) -> Result<(), ApiError> {
+    let mut validator = Validator::new();
+    if assignments.read_user(tenant_id, user_task.user_id).await?.is_none() {
+        validator.error("user_id", "must be an existing user");
+    }
+    if assignments.read_task(tenant_id, user_task.task_id).await?.is_none() {
+        validator.error("task_id", "must be an existing task");
+    }
+    if assignments.read_status(tenant_id, user_task.task_status_id).await?.is_none() {
+        validator.error("task_status_id", "must be an existing status");
+    }
+    validator.finish()
+}
+
+async fn check_status(assignments: &dyn AssignmentRepository, tenant_id: i32, task_status_id: i32) -> Result<(), ApiError> {
+    match assignments.read_status(tenant_id, task_status_id).await? {
+        Some(_) => Ok(()),
+        None => Err(ApiError::Validation(vec![FieldError { field: "task_status_id", message: "must be an existing status".to_string() }])),
+    }
+}
+
+// Bulk variants take a JSON array and run it in one transaction; see crate::bulk for the response.
+// Items that would finish a blocked task fail on their own, like any other per-item error.
+#[post("/assignments/bulk", data = "<user_tasks>")]
+pub async fn bulk_create_user_tasks(assignments: &State<Box<dyn AssignmentRepository>>, config: &State<OverdueConfig>, events: &State<EventBus>, manager: ManagerUser, user_tasks: Payload<Vec<UserTaskInput>>) -> Result<Negotiated<BulkResponse<Linked<UserTask>>>, ApiError> {
+    let (checks, valid) = bulk::validate(&user_tasks)?;
+    let assignable = check_all_assignable(assignments.as_ref(), manager.tenant_id, config, &valid).await;
+    let passed = bulk::passed(valid, &assignable);
+    let new_user_tasks = passed.iter().map(|user_task| to_new_user_task(user_task)).collect();
+    let outcomes = assignments.create_many(manager.tenant_id, manager.user_id, new_user_tasks).await?.into_iter().zip(passed)
+        .map(|(outcome, user_task)| outcome.map(linked).map_err(|e| ApiError::from(e).on_conflict(|| already_assigned(user_task.user_id, user_task.task_id))))
+        .collect();
+    let response = bulk::respond(checks, bulk::merge(assignable, outcomes));
+    publish_all(events, manager.tenant_id, ASSIGNMENT_CREATED, &response);
+    Ok(Negotiated(response))
+}
+
+#[put("/assignments/bulk", data = "<user_tasks>")]
+pub async fn bulk_update_user_tasks(assignments: &State<Box<dyn AssignmentRepository>>, config: &State<OverdueConfig>, events: &State<EventBus>, manager: ManagerUser, user_tasks: Payload<Vec<UserTaskInput>>) -> Result<Negotiated<BulkResponse<Linked<UserTask>>>, ApiError> {
+    let mut from_task_status_ids = HashMap::new();
+    let (checks, valid) = bulk::validate(&user_tasks)?;
+    let not_blocked = check_all_not_blocked(assignments.as_ref(), manager.tenant_id, config, &valid).await;
+    let passed = bulk::passed(valid, &not_blocked);
+    for user_task in &passed {
+        if let Some(existing) = assignments.read(manager.tenant_id, (user_task.user_id, user_task.task_id)).await? {
+            from_task_status_ids.insert((existing.user_id, existing.task_id), existing.task_status_id);
+        }
+    }
+    let updated_user_tasks = passed.iter().map(|user_task| to_new_user_task(user_task)).collect();
+    let outcomes = assignments.update_many(manager.tenant_id, manager.user_id, updated_user_tasks).await?.into_iter()
+        .map(|outcome| outcome.map(linked).map_err(ApiError::from))
+        .collect();
+    let response = bulk::respond(checks, bulk::merge(not_blocked, outcomes));
+    for saved in response.results.iter().filter_map(|result| result.item.as_ref()) {
+        if let Some(&from_task_status_id) = from_task_status_ids.get(&(saved.item.user_id, saved.item.task_id)) {
+            publish_moved(events, manager.tenant_id, from_task_status_id, saved);
+        }
+    }
+    Ok(Negotiated(response))
+}
+
+#[delete("/assignments/bulk", data = "<keys>")]
+pub async fn bulk_delete_user_tasks(assignments: &State<Box<dyn AssignmentRepository>>, events: &State<EventBus>, manager: ManagerUser, keys: Payload<Vec<AssignmentKey>>) -> Result<Negotiated<BulkResponse<AssignmentKey>>, ApiError> {
+    let (checks, valid) = bulk::validate(&keys)?;
+    let ids = valid.iter().map(|key| (key.user_id, key.task_id)).collect();
+    let outcomes = assignments.delete_many(manager.tenant_id, manager.user_id, ids).await?.into_iter().zip(valid)
+        .map(|(outcome, key)| match outcome {
+            Ok(0) => Err(ApiError::not_found("Assignment")),
+            Ok(_) => Ok(*key),
+            Err(e) => Err(ApiError::from(e)),
+        })
+        .collect();
+    let response = bulk::respond(checks, outcomes);
+    publish_all(events, manager.tenant_id, ASSIGNMENT_DELETED, &response);
+    Ok(Negotiated(response))
 }
 
 #[delete("/assignments/<user_id>/<task_id>")]
-pub async fn delete_user_task(user_id: i32, task_id: i32, pool: &State<DbPool>) -> Option<Json<usize>> {
-    let mut conn = pool.get().ok()?;
-    UserTask::delete(&mut conn, (user_id, task_id)).ok().map(Json)
-}
\ No newline at end of file
+pub async fn delete_user_task(user_id: i32, task_id: i32, assignments: &State<Box<dyn AssignmentRepository>>, events: &State<EventBus>, manager: ManagerUser) -> Result<Negotiated<usize>, ApiError> {
+    match assignments.delete(manager.tenant_id, manager.user_id, (user_id, task_id)).await? {
+        0 => Err(ApiError::not_found("Assignment")),
+        count => {
+            events.publish(manager.tenant_id, ASSIGNMENT_DELETED, &AssignmentKey { user_id, task_id });
+            Ok(Negotiated(count))
+        }
+    }
+}
+
+#[post("/assignments/<user_id>/<task_id>/restore")]
+pub async fn restore_user_task(user_id: i32, task_id: i32, assignments: &State<Box<dyn AssignmentRepository>>, events: &State<EventBus>, manager: ManagerUser) -> Result<Negotiated<Linked<UserTask>>, ApiError> {
+    let restored = assignments.restore(manager.tenant_id, manager.user_id, (user_id, task_id)).await?
+        .map(linked)
+        .ok_or_else(|| ApiError::not_found("Deleted assignment"))?;
+    events.publish(manager.tenant_id, ASSIGNMENT_CREATED, &restored);
+    Ok(Negotiated(restored))
+}
+
+// The status an assignment is in before it is updated, so the update can tell whether it moved.
+async fn current_status(assignments: &dyn AssignmentRepository, tenant_id: i32, user_id: i32, task_id: i32) -> Result<i32, ApiError> {
+    assignments.read(tenant_id, (user_id, task_id)).await?
+        .map(|existing| existing.task_status_id)
+        .ok_or_else(|| ApiError::not_found("Assignment"))
+}
+
+// Saving an assignment with the status it already had is not a move and isn't announced.
+fn publish_moved(events: &EventBus, tenant_id: i32, from_task_status_id: i32, saved: &Linked<UserTask>) {
+    if saved.item.task_status_id != from_task_status_id {
+        events.publish(tenant_id, ASSIGNMENT_STATUS_CHANGED, &StatusChange { assignment: saved, from_task_status_id });
+    }
+}
+
+// One event per item that went through.
+fn publish_all<T: rocket::serde::Serialize>(events: &EventBus, tenant_id: i32, event_type: &'static str, response: &BulkResponse<T>) {
+    for item in response.results.iter().filter_map(|result| result.item.as_ref()) {
+        events.publish(tenant_id, event_type, item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes_name_the_related_rows() {
+        let includes = Includes::parse(Some("status, user")).unwrap();
+        assert!(includes.user && includes.status && !includes.task);
+        assert!(!Includes::parse(None).unwrap().any());
+        assert!(matches!(Includes::parse(Some("user,project")), Err(ApiError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,174 @@
+use rocket::{serde::json::Json, State, get, post, delete, FromForm};
+use rocket::form::Form;
+use rocket::fs::TempFile;
+use rocket::http::{ContentType, Header};
+use rocket::response::{self, Responder, Response};
+use rocket::Request;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand::RngCore;
+use tasks_db_lib::models::{Attachment, NewAttachment, Task};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::enums::UserRole;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::AuthenticatedUser;
+use crate::validation::{Validator, MAX_FILE_NAME_LEN};
+use crate::storage::{AttachmentStorage, StoredFile};
+
+const DEFAULT_MAX_MB: u64 = 10;
+const DEFAULT_CONTENT_TYPES: &str = "image/png,image/jpeg,image/gif,application/pdf,text/plain";
+
+// Where files are kept is configured separately; see storage::from_env.
+// ATTACHMENT_MAX_MB caps the size of one file.
+// ATTACHMENT_CONTENT_TYPES is a comma-separated list of the media types that may be uploaded.
+pub struct AttachmentConfig {
+    pub max_bytes: u64,
+    pub content_types: Vec<String>,
+}
+
+impl AttachmentConfig {
+    pub fn from_env() -> AttachmentConfig {
+        let max_mb = std::env::var("ATTACHMENT_MAX_MB")
+            .ok()
+            .and_then(|m| m.parse().ok())
+            .filter(|m: &u64| *m > 0)
+            .unwrap_or(DEFAULT_MAX_MB);
+        let content_types = std::env::var("ATTACHMENT_CONTENT_TYPES")
+            .unwrap_or_else(|_| DEFAULT_CONTENT_TYPES.to_string())
+            .split(',')
+            .map(|name| name.trim().to_ascii_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+        AttachmentConfig { max_bytes: max_mb * 1024 * 1024, content_types }
+    }
+}
+
+// multipart/form-data body of POST /tasks/<id>/attachments: one part named "file".
+#[derive(FromForm)]
+pub struct AttachmentUpload<'r> {
+    pub file: TempFile<'r>,
+}
+
+// The name the client gave the file, without any directory part. Falls back to "attachment"
+// when the client didn't send one.
+fn file_name(file: &TempFile<'_>) -> String {
+    let raw = file.raw_name().map(|name| name.dangerous_unsafe_unsanitized_raw().as_str()).unwrap_or_default();
+    let name: String = raw.rsplit(['/', '\\']).next().unwrap_or_default().chars().filter(|c| !c.is_control()).collect();
+    match name.trim() {
+        "" => "attachment".to_string(),
+        name => name.to_string(),
+    }
+}
+
+// Media type without parameters, e.g. "text/plain" for "text/plain; charset=utf-8".
+fn media_type(file: &TempFile<'_>) -> Option<String> {
+    file.content_type().map(|content_type| format!("{}/{}", content_type.top(), content_type.sub()).to_ascii_lowercase())
+}
+
+fn new_storage_key() -> String {
+    let mut bytes = [0u8; 24];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Streams an attachment back with the type it was uploaded as. `attachment` disposition so
+// browsers download it rather than render uploaded HTML or SVG in our origin.
+pub struct AttachmentDownload {
+    attachment: Attachment,
+    file: StoredFile,
+}
+
+impl<'r> Responder<'r, 'static> for AttachmentDownload {
+    fn respond_to(self, _req: &'r Request<'_>) -> response::Result<'static> {
+        let content_type = ContentType::parse_flexible(&self.attachment.content_type).unwrap_or(ContentType::Binary);
+        let file_name = self.attachment.file_name.replace(['"', '\\'], "_");
+        Response::build()
+            .header(content_type)
+            .header(Header::new("Content-Disposition", format!("attachment; filename=\"{}\"", file_name)))
+            .header(Header::new("X-Content-Type-Options", "nosniff"))
+            .streamed_body(self.file)
+            .ok()
+    }
+}
+
+#[get("/tasks/<id>/attachments")]
+pub async fn get_task_attachments(id: i32, db: TenantDb) -> Result<Json<Vec<Attachment>>, ApiError> {
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    Ok(Json(Attachment::read_for_task(&mut conn, id).await?))
+}
+
+// Anyone signed in may attach a file; the caller is recorded as the uploader.
+#[post("/tasks/<id>/attachments", data = "<upload>")]
+pub async fn upload_attachment(id: i32, db: TenantDb, config: &State<AttachmentConfig>, storage: &State<Box<dyn AttachmentStorage>>, auth: AuthenticatedUser, mut upload: Form<AttachmentUpload<'_>>) -> Result<Json<Attachment>, ApiError> {
+    let file_name = file_name(&upload.file);
+    let content_type = media_type(&upload.file);
+    let mut validator = Validator::new();
+    if upload.file.len() == 0 {
+        validator.error("file", "must not be empty");
+    } else if upload.file.len() > config.max_bytes {
+        validator.error("file", format!("must be at most {} bytes", config.max_bytes));
+    }
+    match &content_type {
+        Some(content_type) if config.content_types.contains(content_type) => {}
+        _ => { validator.error("file", format!("must be one of: {}", config.content_types.join(", "))); }
+    }
+    if file_name.chars().count() > MAX_FILE_NAME_LEN {
+        validator.error("file", format!("name must be at most {} characters", MAX_FILE_NAME_LEN));
+    }
+    validator.finish()?;
+    let content_type = content_type.unwrap_or_default();
+
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    let storage_key = new_storage_key();
+    storage.put(&storage_key, &content_type, &mut upload.file).await?;
+    let new_attachment = NewAttachment {
+        task_id: id,
+        uploaded_by: auth.user_id,
+        file_name: &file_name,
+        content_type: &content_type,
+        size_bytes: upload.file.len() as i64,
+        storage_key: &storage_key,
+    };
+    match Attachment::create(&mut conn, new_attachment).await {
+        Ok(attachment) => Ok(Json(attachment)),
+        Err(e) => {
+            let _ = storage.delete(&storage_key).await;
+            Err(e.into())
+        }
+    }
+}
+
+#[get("/attachments/<id>")]
+pub async fn download_attachment(id: i32, db: TenantDb, storage: &State<Box<dyn AttachmentStorage>>) -> Result<AttachmentDownload, ApiError> {
+    let mut conn = db.get().await?;
+    let attachment = Attachment::read(&mut conn, id).await?.ok_or_else(|| ApiError::not_found("Attachment"))?;
+    let file = storage.get(&attachment.storage_key).await?;
+    Ok(AttachmentDownload { attachment, file })
+}
+
+// Only the uploader, or a manager, may delete an attachment. The stored file goes with it.
+#[delete("/attachments/<id>")]
+pub async fn delete_attachment(id: i32, db: TenantDb, storage: &State<Box<dyn AttachmentStorage>>, auth: AuthenticatedUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    let attachment = Attachment::read(&mut conn, id).await?.ok_or_else(|| ApiError::not_found("Attachment"))?;
+    auth.require_self_or(attachment.uploaded_by, UserRole::Manager)?;
+    let count = Attachment::delete(&mut conn, id).await?;
+    remove_stored(storage.inner().as_ref(), &[attachment]).await;
+    Ok(Json(count))
+}
+
+// Best effort: the rows are already gone, so a file that can't be removed is only logged.
+pub async fn remove_stored(storage: &dyn AttachmentStorage, attachments: &[Attachment]) {
+    for attachment in attachments {
+        if let Err(e) = storage.delete(&attachment.storage_key).await {
+            tracing::warn!("Could not remove attachment file {}: {}", attachment.storage_key, e);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use rocket::{serde::json::Json, get};
+use rocket::serde::json::serde_json;
+use rocket::serde::Serialize;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use tasks_db_lib::audit::ENTITIES;
+use tasks_db_lib::filters::AuditFilter;
+use tasks_db_lib::models::AuditEntry;
+use tasks_db_lib::pagination::Page;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::AdminUser;
+use crate::pagination::PageQuery;
+
+// The stored before/after JSON is sent back as JSON, not as strings of JSON.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuditEntryView {
+    pub audit_id: i32,
+    pub actor_user_id: Option<i32>,
+    pub action: String,
+    pub entity: String,
+    pub entity_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<AuditEntry> for AuditEntryView {
+    fn from(entry: AuditEntry) -> AuditEntryView {
+        let parse = |json: Option<String>| json.and_then(|json| serde_json::from_str(&json).ok());
+        AuditEntryView {
+            audit_id: entry.audit_id,
+            actor_user_id: entry.actor_user_id,
+            action: entry.action,
+            entity: entry.entity,
+            entity_id: entry.entity_id,
+            before: parse(entry.before_json),
+            after: parse(entry.after_json),
+            created_at: entry.created_at,
+        }
+    }
+}
+
+// Accepts an RFC 3339 timestamp, a naive "2026-10-14T09:30:00" (UTC), or a plain date.
+pub fn parse_since(raw: &str) -> Result<NaiveDateTime, ApiError> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(datetime.naive_utc());
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S") {
+        return Ok(datetime);
+    }
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).expect("midnight is a valid time"))
+        .map_err(|_| ApiError::BadRequest(format!("since must be a date or RFC 3339 timestamp, got '{}'", raw)))
+}
+
+// e.g. GET /api/audit?entity=assignment&since=2026-10-01, newest first
+#[get("/audit?<entity>&<entity_id>&<since>&<paging..>")]
+pub async fn get_audit_log(entity: Option<&str>, entity_id: Option<&str>, since: Option<&str>, paging: PageQuery, db: TenantDb, _admin: AdminUser) -> Result<Json<Page<AuditEntryView>>, ApiError> {
+    if let Some(entity) = entity && !ENTITIES.contains(&entity) {
+        return Err(ApiError::BadRequest(format!("entity must be one of: {}", ENTITIES.join(", "))));
+    }
+    let filter = AuditFilter {
+        entity: entity.map(str::to_string),
+        entity_id: entity_id.map(str::to_string),
+        since: since.map(parse_since).transpose()?,
+    };
+    let (page, per_page) = paging.resolve()?;
+    let mut conn = db.get().await?;
+    let entries = AuditEntry::read_page_filtered(&mut conn, &filter, page, per_page).await?;
+    let items = entries.items.into_iter().map(AuditEntryView::from).collect();
+    Ok(Json(Page::new(items, entries.page, entries.per_page, entries.total)))
+}
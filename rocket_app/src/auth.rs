@@ -0,0 +1,481 @@
+use rocket::{serde::json::Json, State, get, post};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::{Deserialize, Serialize};
+use tasks_db_lib::DbConnection;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use std::ops::Deref;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tasks_db_lib::models::{Credential, NewRefreshToken, NewUser, RefreshToken, RevokedToken, User};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::enums::UserRole;
+use tasks_db_lib::tenancy;
+use crate::error::ApiError;
+use crate::validation::{Validate, Validator, MAX_EMAIL_LEN, MAX_NAME_LEN};
+use crate::api_keys::{generate_key, hash_key, ApiKeyUser, API_KEY_HEADER};
+use crate::tenancy::{DbPool, TenantDb};
+
+const DEFAULT_TOKEN_MINUTES: u64 = 60;
+const DEFAULT_REFRESH_TOKEN_DAYS: i64 = 30;
+pub(crate) const MIN_PASSWORD_LEN: usize = 8;
+// argon2 hashes whatever it is given; cap it so a huge body can't pin a worker
+pub(crate) const MAX_PASSWORD_LEN: usize = 128;
+
+// Signing settings, read once at launch and kept in managed state.
+pub struct AuthConfig {
+    pub secret: String,
+    pub token_minutes: u64,
+    pub refresh_token_days: i64,
+}
+
+impl AuthConfig {
+    pub fn from_env() -> AuthConfig {
+        let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+        let token_minutes = std::env::var("JWT_TOKEN_MINUTES")
+            .ok()
+            .and_then(|m| m.parse().ok())
+            .unwrap_or(DEFAULT_TOKEN_MINUTES);
+        let refresh_token_days = std::env::var("REFRESH_TOKEN_DAYS")
+            .ok()
+            .and_then(|d| d.parse().ok())
+            .unwrap_or(DEFAULT_REFRESH_TOKEN_DAYS);
+        AuthConfig { secret, token_minutes, refresh_token_days }
+    }
+
+    pub fn issue_token(&self, user: &User) -> Result<String, ApiError> {
+        let now = now_secs();
+        let claims = Claims {
+            sub: user.user_id,
+            email: user.email.clone(),
+            jti: generate_key(),
+            iat: now,
+            exp: now + self.token_minutes * 60,
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(self.secret.as_bytes()))
+            .map_err(|e| ApiError::Internal(format!("Could not issue token: {}", e)))
+    }
+
+    // A fresh access token plus a new refresh token saved for this user.
+    pub async fn token_response(&self, conn: &mut DbConnection, user: &User) -> Result<TokenResponse, ApiError> {
+        let refresh_token = generate_key();
+        let token_hash = hash_key(&refresh_token);
+        RefreshToken::create(conn, NewRefreshToken {
+            user_id: user.user_id,
+            token_hash: &token_hash,
+            expires_at: self.refresh_expiry(),
+        }).await?;
+        self.token_pair(user, refresh_token)
+    }
+
+    fn token_pair(&self, user: &User, refresh_token: String) -> Result<TokenResponse, ApiError> {
+        Ok(TokenResponse {
+            access_token: self.issue_token(user)?,
+            token_type: "Bearer".to_string(),
+            expires_in: self.token_minutes * 60,
+            refresh_token,
+        })
+    }
+
+    fn refresh_expiry(&self) -> chrono::NaiveDateTime {
+        chrono::Utc::now().naive_utc() + chrono::Duration::days(self.refresh_token_days)
+    }
+
+    pub fn verify_token(&self, token: &str) -> Result<Claims, ApiError> {
+        decode::<Claims>(token, &DecodingKey::from_secret(self.secret.as_bytes()), &Validation::default())
+            .map(|data| data.claims)
+            .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Claims {
+    pub sub: i32,      // user_id
+    pub email: String,
+    pub jti: String,   // unique per token so a single token can be revoked
+    pub iat: u64,
+    pub exp: u64,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// Request guard: add `auth: AuthenticatedUser` to a handler and it only runs
+// when the request carries `Authorization: Bearer <token>` with a valid token,
+// or an `X-Api-Key` header holding an active API key.
+// The role and tenant are read from the database on every request so role changes apply
+// immediately; the lookup is cached for the rest of the request, so stacking guards is free.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub user_id: i32,
+    pub role: UserRole,
+    pub tenant_id: i32,   // every tenant-scoped query runs inside this one; see crate::tenancy
+    pub access_token: Option<Claims>,   // None when authenticated with an API key
+}
+
+impl AuthenticatedUser {
+    pub fn require(&self, minimum: UserRole) -> Result<(), ApiError> {
+        if self.role.at_least(minimum) {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden(format!("Requires the {} role", minimum.as_str())))
+        }
+    }
+
+    // For resources a member owns, e.g. their own assignments: allowed for the owner or `minimum` and above.
+    pub fn require_self_or(&self, owner_id: i32, minimum: UserRole) -> Result<(), ApiError> {
+        if self.user_id == owner_id {
+            Ok(())
+        } else {
+            self.require(minimum)
+        }
+    }
+}
+
+// Works out which user is calling, from either a bearer token or an API key.
+async fn caller(req: &Request<'_>) -> Result<(i32, Option<Claims>), ApiError> {
+    let config = req.rocket().state::<AuthConfig>()
+        .ok_or_else(|| ApiError::Internal("AuthConfig is not managed".to_string()))?;
+    match req.headers().get_one("Authorization").and_then(|h| h.strip_prefix("Bearer ")) {
+        Some(token) => config.verify_token(token).map(|claims| (claims.sub, Some(claims))),
+        None if req.headers().contains(API_KEY_HEADER) => match req.guard::<ApiKeyUser>().await {
+            Outcome::Success(key) => Ok((key.user_id, None)),
+            Outcome::Error((_, e)) => Err(e),
+            Outcome::Forward(_) => Err(ApiError::Unauthorized("Invalid API key".to_string())),
+        },
+        None => Err(ApiError::Unauthorized("Missing bearer token".to_string())),
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuthenticatedUser {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match req.local_cache_async(authenticate(req)).await.clone() {
+            Ok(auth) => Outcome::Success(auth),
+            Err(e) => e.guard_failure(req),
+        }
+    }
+}
+
+// The user a guard already authenticated for this request, if any. It never authenticates
+// anyone itself, so it's safe to call after the handler has run.
+pub fn authenticated_user_id(req: &Request<'_>) -> Option<i32> {
+    req.local_cache(|| Err::<AuthenticatedUser, ApiError>(ApiError::Unauthorized(String::new())))
+        .as_ref()
+        .ok()
+        .map(|auth| auth.user_id)
+}
+
+async fn authenticate(req: &Request<'_>) -> Result<AuthenticatedUser, ApiError> {
+    let (user_id, access_token) = caller(req).await?;
+    let pool = req.rocket().state::<DbPool>()
+        .ok_or_else(|| ApiError::Internal("DbPool is not managed".to_string()))?;
+    let mut conn = pool.get().await?;
+    // logged-out access tokens stay cryptographically valid until they expire
+    if let Some(claims) = &access_token
+        && RevokedToken::is_revoked(&mut conn, &claims.jti).await? {
+        return Err(ApiError::Unauthorized("Token has been revoked".to_string()));
+    }
+    let (role, tenant_id) = User::read_access(&mut conn, user_id).await?
+        .ok_or_else(|| ApiError::Unauthorized("Account is inactive or no longer exists".to_string()))?;
+    Ok(AuthenticatedUser { user_id, role, tenant_id, access_token })
+}
+
+async fn authenticated_with_role(req: &Request<'_>, minimum: UserRole) -> Outcome<AuthenticatedUser, ApiError> {
+    let auth = match req.guard::<AuthenticatedUser>().await {
+        Outcome::Success(auth) => auth,
+        other => return other,
+    };
+    match auth.require(minimum) {
+        Ok(()) => Outcome::Success(auth),
+        Err(e) => e.guard_failure(req),
+    }
+}
+
+// Route-level role guards, e.g. `_admin: AdminUser` on a handler only admins may call.
+#[derive(Debug)]
+pub struct AdminUser(pub AuthenticatedUser);
+
+#[derive(Debug)]
+pub struct ManagerUser(pub AuthenticatedUser);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminUser {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        authenticated_with_role(req, UserRole::Admin).await.map(AdminUser)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ManagerUser {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        authenticated_with_role(req, UserRole::Manager).await.map(ManagerUser)
+    }
+}
+
+impl Deref for AdminUser {
+    type Target = AuthenticatedUser;
+    fn deref(&self) -> &AuthenticatedUser {
+        &self.0
+    }
+}
+
+impl Deref for ManagerUser {
+    type Target = AuthenticatedUser;
+    fn deref(&self) -> &AuthenticatedUser {
+        &self.0
+    }
+}
+
+// Argon2 is slow on purpose, so hashing and checking run on the blocking threads rather than
+// holding up an async worker.
+pub async fn hash_password(password: &str) -> Result<String, ApiError> {
+    let password = password.to_string();
+    rocket::tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| ApiError::Internal(format!("Could not hash password: {}", e)))
+    }).await.map_err(|e| ApiError::Internal(format!("Could not hash password: {}", e)))?
+}
+
+async fn verify_password(password: &str, password_hash: &str) -> bool {
+    let (password, password_hash) = (password.to_string(), password_hash.to_string());
+    rocket::tokio::task::spawn_blocking(move || PasswordHash::new(&password_hash)
+        .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false))
+        .await
+        .unwrap_or(false)
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LoginInput {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RegisterInput {
+    pub name: String,
+    pub email: String,
+    pub password: String,
+    pub organization: Option<String>,   // set up a new tenant of this name instead of joining the default one
+}
+
+// Only shape checks here; wrong credentials are still a plain 401 from login.
+impl Validate for LoginInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new()
+            .text("email", &self.email, MAX_EMAIL_LEN)
+            .length("password", &self.password, 1, MAX_PASSWORD_LEN)
+            .finish()
+    }
+}
+
+impl Validate for RegisterInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        validator
+            .text("name", &self.name, MAX_NAME_LEN)
+            .email("email", &self.email)
+            .length("password", &self.password, MIN_PASSWORD_LEN, MAX_PASSWORD_LEN);
+        if let Some(organization) = &self.organization {
+            validator.text("organization", organization, MAX_NAME_LEN);
+        }
+        validator.finish()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,   // seconds
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RefreshInput {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LogoutInput {
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub all_sessions: bool,
+}
+
+// New accounts are active members of the default tenant; an admin can promote them with
+// PUT /users/<id>/role. With `organization`, a new tenant is set up instead and the
+// registrant becomes its admin.
+#[post("/users/register", data = "<registration>")]
+pub async fn register(pool: &State<DbPool>, registration: Json<RegisterInput>) -> Result<Json<User>, ApiError> {
+    registration.validate()?;
+    let mut conn = pool.get_in_tenant(tenancy::DEFAULT_TENANT).await?;
+    // a new organization starts empty, so only joining the default tenant can collide
+    if registration.organization.is_none() && User::read_by_email(&mut conn, &registration.email).await?.is_some() {
+        return Err(ApiError::Conflict(format!("A user with email {} already exists", registration.email)));
+    }
+    let password_hash = hash_password(&registration.password).await?;
+    let new_user = NewUser {
+        name: &registration.name,
+        email: &registration.email,
+        active: true,
+    };
+    let user = match &registration.organization {
+        Some(organization) => Credential::register_organization(&mut conn, organization, new_user, &password_hash).await
+            .map_err(|e| ApiError::from(e).on_conflict(|| format!("An organization named {} already exists", organization)))?
+            .1,
+        None => Credential::register(&mut conn, new_user, &password_hash).await?,
+    };
+    Ok(Json(user))
+}
+
+#[post("/login", data = "<login>")]
+pub async fn login(pool: &State<DbPool>, config: &State<AuthConfig>, login: Json<LoginInput>) -> Result<Json<TokenResponse>, ApiError> {
+    // one message for every failure so the endpoint can't be used to probe which emails exist
+    login.validate()?;
+    let invalid = || ApiError::Unauthorized("Invalid email or password".to_string());
+    let mut conn = pool.get().await?;
+    // the same address can belong to users in several tenants; the password picks which one
+    for user in User::read_active_by_email_in_any_tenant(&mut conn, &login.email).await? {
+        let Some(credential) = Credential::read(&mut conn, user.user_id).await? else {
+            continue;
+        };
+        if verify_password(&login.password, &credential.password_hash).await {
+            return Ok(Json(config.token_response(&mut conn, &user).await?));
+        }
+    }
+    Err(invalid())
+}
+
+// Trades a refresh token for a new access/refresh pair. Each refresh token works once.
+#[post("/token/refresh", data = "<refresh>")]
+pub async fn refresh_token(pool: &State<DbPool>, config: &State<AuthConfig>, refresh: Json<RefreshInput>) -> Result<Json<TokenResponse>, ApiError> {
+    let invalid = || ApiError::Unauthorized("Invalid or expired refresh token".to_string());
+    let now = chrono::Utc::now().naive_utc();
+    let old_hash = hash_key(&refresh.refresh_token);
+    let mut conn = pool.get().await?;
+    let current = RefreshToken::find_active_by_hash(&mut conn, &old_hash, now).await?.ok_or_else(invalid)?;
+    let (_, tenant_id) = User::read_access(&mut conn, current.user_id).await?.ok_or_else(invalid)?;
+    let mut conn = pool.get_in_tenant(tenant_id).await?;
+    let user = User::read(&mut conn, current.user_id).await?
+        .filter(|user| user.active)
+        .ok_or_else(invalid)?;
+    let new_token = generate_key();
+    let new_hash = hash_key(&new_token);
+    let replacement = NewRefreshToken { user_id: user.user_id, token_hash: &new_hash, expires_at: config.refresh_expiry() };
+    RefreshToken::rotate(&mut conn, &old_hash, replacement, now).await?.ok_or_else(invalid)?;
+    Ok(Json(config.token_pair(&user, new_token)?))
+}
+
+// Revokes the access token used for this request, plus the given refresh token
+// (or every refresh token the user has when all_sessions is true).
+#[post("/logout", data = "<logout>")]
+pub async fn logout(pool: &State<DbPool>, auth: AuthenticatedUser, logout: Json<LogoutInput>) -> Result<Json<usize>, ApiError> {
+    let now = chrono::Utc::now().naive_utc();
+    let mut conn = pool.get().await?;
+    let mut revoked = 0;
+    if let Some(claims) = &auth.access_token {
+        let expires_at = chrono::DateTime::from_timestamp(claims.exp as i64, 0)
+            .map(|exp| exp.naive_utc())
+            .unwrap_or(now);
+        RevokedToken::revoke(&mut conn, RevokedToken { jti: claims.jti.clone(), expires_at }, now).await?;
+        revoked += 1;
+    }
+    if logout.all_sessions {
+        revoked += RefreshToken::revoke_all_for_user(&mut conn, auth.user_id).await?;
+    } else if let Some(refresh_token) = &logout.refresh_token {
+        revoked += RefreshToken::revoke_by_hash(&mut conn, &hash_key(refresh_token), auth.user_id).await?;
+    }
+    Ok(Json(revoked))
+}
+
+#[get("/me")]
+pub async fn me(db: TenantDb, auth: AuthenticatedUser) -> Result<Json<User>, ApiError> {
+    let mut conn = db.get().await?;
+    User::read(&mut conn, auth.user_id).await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("User"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(secret: &str) -> AuthConfig {
+        AuthConfig { secret: secret.to_string(), token_minutes: 5, refresh_token_days: 1 }
+    }
+
+    fn user() -> User {
+        let now = chrono::Utc::now().naive_utc();
+        User { user_id: 7, name: "Grace".to_string(), email: "grace@example.com".to_string(), active: true, role_id: 3, tenant_id: 1, created_at: now, updated_at: now, version: 1 }
+    }
+
+    #[test]
+    fn a_token_names_the_user_it_was_issued_to() {
+        let config = config("secret");
+        let claims = config.verify_token(&config.issue_token(&user()).unwrap()).unwrap();
+        assert_eq!(claims.sub, 7);
+        assert_eq!(claims.email, "grace@example.com");
+        assert_eq!(claims.exp - claims.iat, 5 * 60);
+        // every token gets its own id, so one can be revoked without the others
+        assert_ne!(config.verify_token(&config.issue_token(&user()).unwrap()).unwrap().jti, claims.jti);
+    }
+
+    #[test]
+    fn a_token_signed_with_another_secret_is_unauthorized() {
+        let token = config("one secret").issue_token(&user()).unwrap();
+        assert!(matches!(config("another").verify_token(&token), Err(ApiError::Unauthorized(_))));
+        assert!(matches!(config("one secret").verify_token("not.a.token"), Err(ApiError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn an_expired_token_is_unauthorized() {
+        let past = now_secs() - 3600;
+        let claims = Claims { sub: 7, email: "grace@example.com".to_string(), jti: "old".to_string(), iat: past, exp: past + 60 };
+        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(b"secret")).unwrap();
+        assert!(matches!(config("secret").verify_token(&token), Err(ApiError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn roles_admit_their_own_level_and_the_ones_below() {
+        let manager = AuthenticatedUser { user_id: 2, role: UserRole::Manager, access_token: None, tenant_id: 1 };
+        assert!(manager.require(UserRole::Member).is_ok());
+        assert!(manager.require(UserRole::Manager).is_ok());
+        assert!(matches!(manager.require(UserRole::Admin), Err(ApiError::Forbidden(_))));
+    }
+
+    #[test]
+    fn members_may_act_on_what_they_own() {
+        let member = AuthenticatedUser { user_id: 5, role: UserRole::Member, access_token: None, tenant_id: 1 };
+        assert!(member.require_self_or(5, UserRole::Manager).is_ok());
+        assert!(matches!(member.require_self_or(6, UserRole::Manager), Err(ApiError::Forbidden(_))));
+    }
+
+    #[rocket::async_test]
+    async fn a_password_verifies_only_against_its_own_hash() {
+        let hash = hash_password("correct horse").await.unwrap();
+        assert!(verify_password("correct horse", &hash).await);
+        assert!(!verify_password("wrong horse", &hash).await);
+        assert!(!verify_password("correct horse", "not a hash").await);
+        // salted, so the same password never hashes the same way twice
+        assert_ne!(hash, hash_password("correct horse").await.unwrap());
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+use rocket::{serde::json::Json, State, get, post};
+use rocket::data::{Data, Limits, ToByteUnit};
+use rocket::serde::Serialize;
+use rocket::serde::json::serde_json;
+use chrono::NaiveDateTime;
+use tasks_db_lib::backup::{self, Backup, Mismatch};
+use tasks_db_lib::tenancy;
+use crate::error::ApiError;
+use crate::auth::AdminUser;
+
+use crate::tenancy::DbPool;
+
+// How many rows went back into each table.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RestoredBackup {
+    pub exported_at: NaiveDateTime,
+    pub rows: BTreeMap<String, usize>,
+}
+
+// A backup holds every tenant's data, so only the admins of the default tenant (the ones who
+// run the server) may take or restore one.
+fn require_operator(admin: &AdminUser) -> Result<(), ApiError> {
+    if admin.tenant_id != tenancy::DEFAULT_TENANT {
+        return Err(ApiError::Forbidden("Backups are only for admins of the default tenant".to_string()));
+    }
+    Ok(())
+}
+
+// Every row of every table, as one JSON document POST /admin/import can put back.
+#[get("/admin/export")]
+pub async fn export_backup(pool: &State<DbPool>, admin: AdminUser) -> Result<Json<Backup>, ApiError> {
+    require_operator(&admin)?;
+    let mut conn = pool.get().await?;
+    Ok(Json(backup::export(&mut conn).await?))
+}
+
+// Replaces everything in the database with a document from GET /admin/export, all or nothing.
+// The backup must come from a server at the same migration (409 otherwise), and its rows must
+// hold together: a foreign key pointing at nothing is a 400 and changes nothing. The body can
+// be up to limits.backup in Rocket.toml, 64 MiB by default, well past the usual JSON limit.
+#[post("/admin/import", data = "<data>")]
+pub async fn import_backup(pool: &State<DbPool>, limits: &Limits, admin: AdminUser, data: Data<'_>) -> Result<Json<RestoredBackup>, ApiError> {
+    require_operator(&admin)?;
+    let limit = limits.get("backup").unwrap_or(64.mebibytes());
+    let text = data.open(limit).into_string().await?;
+    if !text.is_complete() {
+        return Err(ApiError::BadRequest(format!("The backup is larger than the {} limit", limit)));
+    }
+    let document: Backup = serde_json::from_str(&text)
+        .map_err(|e| ApiError::BadRequest(format!("The body isn't a backup: {}", e)))?;
+    let mut conn = pool.get().await?;
+    let rows = backup::restore(&mut conn, &document).await.map_err(|e| match e.downcast::<Mismatch>() {
+        Ok(mismatch) => ApiError::Conflict(mismatch.0),
+        Err(other) => ApiError::from(other),
+    })?;
+    Ok(Json(RestoredBackup { exported_at: document.exported_at, rows }))
+}
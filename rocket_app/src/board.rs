@@ -0,0 +1,109 @@
+use rocket::{serde::json::Json, State, Shutdown, get};
+use rocket::futures::{SinkExt, StreamExt};
+use rocket::serde::Serialize;
+use rocket::serde::json::serde_json;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket_ws::{Channel, Message, WebSocket};
+use tasks_db_lib::board::{self, BoardColumn};
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::AuthenticatedUser;
+use crate::events::{Change, EventBus, ASSIGNMENT_CREATED, ASSIGNMENT_DELETED, ASSIGNMENT_STATUS_CHANGED};
+
+// Everything a kanban view needs in one call: a column per status, each card listing the
+// people at that stage of the task.
+#[get("/board")]
+pub async fn get_board(db: TenantDb) -> Result<Json<Vec<BoardColumn>>, ApiError> {
+    let mut conn = db.get().await?;
+    Ok(Json(board::read_board(&mut conn, None).await?))
+}
+
+// What /ws/board sends, as JSON text frames tagged by "type".
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde", tag = "type", rename_all = "snake_case")]
+pub enum BoardUpdate {
+    // One assignee's card for the task is now in the task_status_id column; null when the
+    // assignment was deleted and the card left the board.
+    Moved { task_id: i32, user_id: i32, task_status_id: Option<i32> },
+    // Updates were missed; GET /board again.
+    Resync,
+}
+
+impl BoardUpdate {
+    // Only assignment changes move cards, and a card saved in the column it was already in
+    // hasn't moved.
+    fn from_change(change: &Change) -> Option<BoardUpdate> {
+        let id = |field: &str| change.data[field].as_i64().map(|id| id as i32);
+        let task_status_id = match change.event_type {
+            ASSIGNMENT_DELETED => None,
+            ASSIGNMENT_CREATED => Some(id("task_status_id")?),
+            ASSIGNMENT_STATUS_CHANGED if id("from_task_status_id") != id("task_status_id") => Some(id("task_status_id")?),
+            _ => return None,
+        };
+        Some(BoardUpdate::Moved { task_id: id("task_id")?, user_id: id("user_id")?, task_status_id })
+    }
+}
+
+// Live feed for a shared kanban view: load GET /board once, then apply what arrives here.
+// Only the caller's tenant is seen, and anything the client sends is ignored.
+#[get("/ws/board")]
+pub fn board_socket(ws: WebSocket, auth: AuthenticatedUser, bus: &State<EventBus>, mut shutdown: Shutdown) -> Channel<'static> {
+    let mut changes = bus.subscribe();
+    ws.channel(move |mut stream| Box::pin(async move {
+        loop {
+            let update = select! {
+                change = changes.recv() => match change {
+                    Ok(change) if change.tenant_id == auth.tenant_id => BoardUpdate::from_change(&change),
+                    Ok(_) => None,
+                    Err(RecvError::Lagged(_)) => Some(BoardUpdate::Resync),
+                    Err(RecvError::Closed) => break,
+                },
+                message = stream.next() => match message {
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Err(e)) => return Err(e),
+                    Some(Ok(_)) => None,
+                },
+                _ = &mut shutdown => break,
+            };
+            if let Some(update) = update {
+                let text = serde_json::to_string(&update).expect("BoardUpdate serializes");
+                stream.send(Message::Text(text)).await?;
+            }
+        }
+        Ok(())
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::serde::json::serde_json::json;
+
+    fn change(event_type: &'static str, data: serde_json::Value) -> Change {
+        Change { tenant_id: 1, event_type, data }
+    }
+
+    #[test]
+    fn changes_become_tagged_card_moves() {
+        let created = change(crate::events::ASSIGNMENT_CREATED, json!({ "user_id": 1, "task_id": 2, "task_status_id": 1 }));
+        let update = BoardUpdate::from_change(&created).unwrap();
+        assert_eq!(serde_json::to_value(&update).unwrap(), json!({ "type": "moved", "task_id": 2, "user_id": 1, "task_status_id": 1 }));
+        let deleted = change(ASSIGNMENT_DELETED, json!({ "user_id": 1, "task_id": 2 }));
+        assert_eq!(serde_json::to_value(BoardUpdate::from_change(&deleted).unwrap()).unwrap()["task_status_id"], json!(null));
+        assert!(BoardUpdate::from_change(&change(crate::events::ASSIGNMENT_CREATED, json!({ "user_id": 1 }))).is_none());
+        assert_eq!(serde_json::to_value(BoardUpdate::Resync).unwrap(), json!({ "type": "resync" }));
+    }
+
+    #[test]
+    fn only_real_moves_reach_the_board() {
+        let moved = change(ASSIGNMENT_STATUS_CHANGED, json!({ "user_id": 1, "task_id": 2, "task_status_id": 3, "from_task_status_id": 2 }));
+        assert!(matches!(BoardUpdate::from_change(&moved), Some(BoardUpdate::Moved { task_id: 2, user_id: 1, task_status_id: Some(3) })));
+        let stayed = change(ASSIGNMENT_STATUS_CHANGED, json!({ "user_id": 1, "task_id": 2, "task_status_id": 3, "from_task_status_id": 3 }));
+        assert!(BoardUpdate::from_change(&stayed).is_none());
+        let deleted = change(ASSIGNMENT_DELETED, json!({ "user_id": 1, "task_id": 2 }));
+        assert!(matches!(BoardUpdate::from_change(&deleted), Some(BoardUpdate::Moved { task_status_id: None, .. })));
+        let renamed = change(crate::events::TASK_STATUS_UPDATED, json!({ "task_status_id": 3, "status_name": "Done" }));
+        assert!(BoardUpdate::from_change(&renamed).is_none());
+    }
+}
@@ -0,0 +1,122 @@
+use rocket::serde::Serialize;
+use crate::error::ApiError;
+use crate::validation::{FieldError, Validate};
+
+pub const MAX_BULK_ITEMS: usize = 500;
+
+// One entry per item of a bulk request: whether it passed a check.
+pub type Checks = Vec<Result<(), ApiError>>;
+
+// Bulk endpoints always answer 200 with one entry per input item, in input order;
+// each entry carries the status code that item would have got on its own.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct BulkResponse<T> {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult<T>>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct BulkItemResult<T> {
+    pub index: usize,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl<T> BulkItemResult<T> {
+    fn new(index: usize, outcome: Result<T, ApiError>) -> BulkItemResult<T> {
+        match outcome {
+            Ok(item) => BulkItemResult { index, status: 200, item: Some(item), error: None, errors: Vec::new() },
+            Err(err) => {
+                let status = err.status().code;
+                let error = Some(err.message().to_string());
+                let errors = match err {
+                    ApiError::Validation(errors) => errors,
+                    _ => Vec::new(),
+                };
+                BulkItemResult { index, status, item: None, error, errors }
+            }
+        }
+    }
+}
+
+// Validates every item. The valid ones are for the handler's database work (one transaction,
+// one outcome per item it was given), which bulk::respond then merges back into request order.
+pub fn validate<I: Validate>(items: &[I]) -> Result<(Checks, Vec<&I>), ApiError> {
+    if items.is_empty() || items.len() > MAX_BULK_ITEMS {
+        return Err(ApiError::BadRequest(format!("A bulk request must contain between 1 and {} items", MAX_BULK_ITEMS)));
+    }
+    let checks: Checks = items.iter().map(Validate::validate).collect();
+    let valid = passed(items.iter().collect(), &checks);
+    Ok((checks, valid))
+}
+
+// For bulk handlers that also vet items against the database: `checks` has one result per
+// item, and only the items that passed go on to the database work.
+pub fn passed<'a, I>(items: Vec<&'a I>, checks: &[Result<(), ApiError>]) -> Vec<&'a I> {
+    items.into_iter().zip(checks)
+        .filter(|(_, check)| check.is_ok())
+        .map(|(item, _)| item)
+        .collect()
+}
+
+// The outcomes of the items that passed, with the failed checks put back in place.
+pub fn merge<T>(checks: Checks, outcomes: Vec<Result<T, ApiError>>) -> Vec<Result<T, ApiError>> {
+    let mut outcomes = outcomes.into_iter();
+    checks.into_iter()
+        .map(|check| check.and_then(|_| outcomes.next().expect("one outcome per item that passed")))
+        .collect()
+}
+
+pub fn respond<T>(checks: Checks, outcomes: Vec<Result<T, ApiError>>) -> BulkResponse<T> {
+    let results: Vec<BulkItemResult<T>> = merge(checks, outcomes).into_iter()
+        .enumerate()
+        .map(|(index, outcome)| BulkItemResult::new(index, outcome))
+        .collect();
+    let succeeded = results.iter().filter(|result| result.item.is_some()).count();
+    BulkResponse { succeeded, failed: results.len() - succeeded, results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Item(i32);
+
+    impl Validate for Item {
+        fn validate(&self) -> Result<(), ApiError> {
+            crate::validation::Validator::new().id("id", self.0).finish()
+        }
+    }
+
+    #[test]
+    fn results_come_back_in_request_order() {
+        let items = [Item(1), Item(0), Item(2)];
+        let (checks, valid) = validate(&items).unwrap();
+        assert_eq!(valid.len(), 2);
+        let outcomes = valid.into_iter().map(|item| match item.0 {
+            1 => Ok(item.0),
+            _ => Err(ApiError::Conflict("taken".to_string())),
+        }).collect();
+        let response = respond(checks, outcomes);
+        assert_eq!((response.succeeded, response.failed), (1, 2));
+        let statuses: Vec<u16> = response.results.iter().map(|result| result.status).collect();
+        assert_eq!(statuses, [200, 422, 409]);
+        assert_eq!(response.results[1].errors[0].field, "id");
+        assert_eq!(response.results[2].error.as_deref(), Some("taken"));
+    }
+
+    #[test]
+    fn an_empty_batch_is_a_bad_request() {
+        let items: [Item; 0] = [];
+        let err = validate(&items).err().unwrap();
+        assert_eq!(err.status(), rocket::http::Status::BadRequest);
+    }
+}
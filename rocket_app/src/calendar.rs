@@ -0,0 +1,157 @@
+use rocket::{serde::json::Json, State, get, post, delete};
+use rocket::http::ContentType;
+use rocket::serde::Serialize;
+use chrono::{NaiveDateTime, Utc};
+use tasks_db_lib::calendar::{self, CalendarEntry};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::enums::UserRole;
+use tasks_db_lib::models::{CalendarFeed, NewCalendarFeed, User};
+use crate::api_keys::{generate_key, hash_key};
+use crate::error::ApiError;
+use crate::tenancy::{DbPool, TenantDb};
+use crate::auth::AuthenticatedUser;
+
+// RFC 5545 lines are at most 75 octets; longer ones carry on after a CRLF and a space.
+const MAX_LINE_OCTETS: usize = 75;
+
+// The token is only ever returned here; the database keeps a SHA-256 hash, as for API keys.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreatedCalendarFeed {
+    pub token: String,
+    // what to paste into Outlook or Google Calendar, after the server's address
+    pub path: String,
+    pub feed: CalendarFeed,
+}
+
+// Text values escape backslashes, semicolons and commas, and newlines become a literal \n.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+// Appends the content line, folded so that no line is longer than MAX_LINE_OCTETS. Folds fall
+// between characters, never inside one.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn timestamp(at: NaiveDateTime) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// One all-day event per assignment on the task's due date. The UID stays the same as the
+// assignment changes, so calendar apps update the event rather than adding another.
+fn render(user_name: &str, entries: &[CalendarEntry], now: NaiveDateTime) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//rocket_app//Task due dates//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "METHOD:PUBLISH");
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape(&format!("Tasks for {}", user_name))));
+    for entry in entries {
+        let due = entry.due_date;
+        let next_day = due.succ_opt().unwrap_or(due);
+        push_line(&mut ics, "BEGIN:VEVENT");
+        push_line(&mut ics, &format!("UID:assignment-{}-{}@rocket_app", entry.user_id, entry.task_id));
+        push_line(&mut ics, &format!("DTSTAMP:{}", timestamp(now)));
+        push_line(&mut ics, &format!("LAST-MODIFIED:{}", timestamp(entry.updated_at.max(entry.task_updated_at))));
+        push_line(&mut ics, &format!("DTSTART;VALUE=DATE:{}", due.format("%Y%m%d")));
+        push_line(&mut ics, &format!("DTEND;VALUE=DATE:{}", next_day.format("%Y%m%d")));
+        push_line(&mut ics, &format!("SUMMARY:{}", escape(&entry.task_name)));
+        push_line(&mut ics, &format!("DESCRIPTION:{}", escape(&format!("Status: {}\nPriority: {}", entry.status_name, entry.priority.as_str()))));
+        push_line(&mut ics, "TRANSP:TRANSPARENT");
+        push_line(&mut ics, "END:VEVENT");
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+// Calendar apps subscribe by URL and can't sign in, so ?token= (from POST
+// /users/me/calendar_feed) stands in for the user; a wrong token, or another user's, reads as no
+// such feed. Without one, signed-in callers get their own feed, and managers anyone's.
+#[get("/users/<id>/calendar.ics?<token>")]
+pub async fn get_calendar(id: i32, token: Option<&str>, pool: &State<DbPool>, auth: Result<AuthenticatedUser, ApiError>) -> Result<(ContentType, String), ApiError> {
+    let tenant_id = match token {
+        Some(token) => {
+            let mut conn = pool.get().await?;
+            match CalendarFeed::read_by_token_hash(&mut conn, &hash_key(token)).await? {
+                Some((feed, tenant_id)) if feed.user_id == id => tenant_id,
+                _ => return Err(ApiError::not_found("Calendar feed")),
+            }
+        }
+        None => {
+            let auth = auth?;
+            auth.require_self_or(id, UserRole::Manager)?;
+            auth.tenant_id
+        }
+    };
+    let mut conn = pool.get_in_tenant(tenant_id).await?;
+    let user = User::read(&mut conn, id).await?.ok_or_else(|| ApiError::not_found("User"))?;
+    let entries = calendar::read_for_user(&mut conn, id).await?;
+    Ok((ContentType::Calendar, render(&user.name, &entries, Utc::now().naive_utc())))
+}
+
+// Turns the caller's feed on, or gives it a new token if the URL has leaked; the old URL stops
+// working either way.
+#[post("/users/me/calendar_feed")]
+pub async fn create_calendar_feed(db: TenantDb, auth: AuthenticatedUser) -> Result<Json<CreatedCalendarFeed>, ApiError> {
+    let mut conn = db.get().await?;
+    let token = generate_key();
+    let feed = CalendarFeed::save(&mut conn, NewCalendarFeed { user_id: auth.user_id, token_hash: &hash_key(&token) }).await?;
+    let path = format!("/api/v1/users/{}/calendar.ics?token={}", auth.user_id, token);
+    Ok(Json(CreatedCalendarFeed { token, path, feed }))
+}
+
+#[delete("/users/me/calendar_feed")]
+pub async fn delete_calendar_feed(db: TenantDb, auth: AuthenticatedUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match CalendarFeed::delete(&mut conn, auth.user_id).await? {
+        0 => Err(ApiError::not_found("Calendar feed")),
+        count => Ok(Json(count)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use tasks_db_lib::enums::TaskPriority;
+
+    #[test]
+    fn events_are_escaped_and_folded() {
+        let at = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap().and_hms_opt(9, 30, 0).unwrap();
+        let entry = CalendarEntry {
+            user_id: 1,
+            task_id: 7,
+            task_name: format!("Review; sign, send\\{}", "é".repeat(40)),
+            due_date: NaiveDate::from_ymd_opt(2026, 12, 31).unwrap(),
+            priority: TaskPriority::High,
+            status_name: "In Progress".to_string(),
+            task_updated_at: at,
+            updated_at: at,
+        };
+        let ics = render("Vera", &[entry], at);
+
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS), "{}", ics);
+        assert!(!ics.replace("\r\n", "").contains('\n'));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20261231\r\nDTEND;VALUE=DATE:20270101\r\n"));
+        assert!(ics.contains("DESCRIPTION:Status: In Progress\\nPriority: high\r\n"));
+        let summary = ics.replace("\r\n ", "");
+        assert!(summary.contains(&format!("SUMMARY:Review\\; sign\\, send\\\\{}\r\n", "é".repeat(40))));
+    }
+}
@@ -0,0 +1,99 @@
+use rocket::{catch, Request};
+use rocket::http::Status;
+use crate::api_version::{ApiVersion, SUPPORTED_VERSIONS};
+use crate::error::{GuardFailure, ProblemDetails};
+
+// Catchers run when no handler produced a response: unknown routes, request guards
+// or body parsing that failed, and panics. They answer with the same problem+json body as ApiError.
+
+fn error_body(status: Status, req: &Request<'_>, fallback: String) -> ProblemDetails {
+    let detail = req.local_cache(|| GuardFailure(None)).0.clone().unwrap_or(fallback);
+    ProblemDetails::new(status, detail, req)
+}
+
+// A request for an API version we don't serve finds no route; tell the client why.
+#[catch(404)]
+pub fn not_found(req: &Request<'_>) -> ProblemDetails {
+    if let Some(version) = req.local_cache(|| ApiVersion(None)).unsupported() {
+        let supported: Vec<String> = SUPPORTED_VERSIONS.iter().map(|v| format!("v{}", v)).collect();
+        let detail = format!("API version v{} is not available; supported versions: {}", version, supported.join(", "));
+        return ProblemDetails::new(Status::NotAcceptable, detail, req);
+    }
+    error_body(Status::NotFound, req, format!("No route for {} {}", req.method(), req.uri()))
+}
+
+#[catch(422)]
+pub fn unprocessable_entity(req: &Request<'_>) -> ProblemDetails {
+    error_body(Status::UnprocessableEntity, req,
+        "The request body is missing required fields or has fields of the wrong type".to_string())
+}
+
+#[catch(500)]
+pub fn internal_error(req: &Request<'_>) -> ProblemDetails {
+    error_body(Status::InternalServerError, req, "Internal server error".to_string())
+}
+
+// Everything else, e.g. 400 for malformed JSON and 401/403 from the auth guards.
+#[catch(default)]
+pub fn default_catcher(status: Status, req: &Request<'_>) -> ProblemDetails {
+    let fallback = match status.code {
+        400 => "The request could not be understood; check that the body is valid JSON".to_string(),
+        _ => status.reason_lossy().to_string(),
+    };
+    error_body(status, req, fallback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::{catchers, get, routes};
+    use rocket::local::blocking::Client;
+    use rocket::request::{FromRequest, Outcome};
+    use crate::error::ApiError;
+
+    struct Refuses;
+
+    #[rocket::async_trait]
+    impl<'r> FromRequest<'r> for Refuses {
+        type Error = ApiError;
+
+        async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+            ApiError::Forbidden("Not today".to_string()).guard_failure(req)
+        }
+    }
+
+    #[get("/guarded")]
+    fn guarded(_refuses: Refuses) -> &'static str {
+        "let in"
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .mount("/", routes![guarded])
+            .register("/", catchers![not_found, unprocessable_entity, internal_error, default_catcher]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    #[test]
+    fn a_failed_guard_reports_its_own_message() {
+        let client = client();
+        let response = client.get("/guarded").dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+        assert_eq!(response.content_type().unwrap().to_string(), "application/problem+json");
+        let body: serde_json::Value = response.into_json().unwrap();
+        assert_eq!(body["type"], "about:blank");
+        assert_eq!(body["title"], "Forbidden");
+        assert_eq!(body["status"], 403);
+        assert_eq!(body["detail"], "Not today");
+        assert_eq!(body["instance"], "/guarded");
+    }
+
+    #[test]
+    fn an_unknown_route_is_a_json_404() {
+        let client = client();
+        let response = client.get("/nowhere").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        let body: serde_json::Value = response.into_json().unwrap();
+        assert_eq!(body["detail"], "No route for GET /nowhere");
+    }
+}
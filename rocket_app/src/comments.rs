@@ -0,0 +1,136 @@
+use rocket::{serde::json::Json, get, post, put, delete};
+use tasks_db_lib::DbConnection;
+use tasks_db_lib::models::{Comment, CommentRevision, Mention, NewComment, Task, User};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::audit::AuditedCrud;
+use tasks_db_lib::enums::UserRole;
+use tasks_db_lib::mentions;
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::sorting::COMMENT_SORT_COLUMNS;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::conditional::IfMatch;
+use crate::auth::AuthenticatedUser;
+use crate::pagination::PageQuery;
+use crate::validation::{FieldError, Validate, Validator, MAX_COMMENT_LEN};
+
+// A comment as clients see it: the row plus whether it has been changed since it was posted.
+#[derive(rocket::serde::Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CommentView {
+    #[serde(flatten)]
+    pub comment: Comment,
+    pub edited: bool,
+}
+
+impl From<Comment> for CommentView {
+    fn from(comment: Comment) -> CommentView {
+        CommentView { edited: comment.version > 1, comment }
+    }
+}
+
+#[derive(rocket::serde::Deserialize)]
+pub struct CommentInput {
+    pub body: String,
+}
+
+impl Validate for CommentInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new().text("body", &self.body, MAX_COMMENT_LEN).finish()
+    }
+}
+
+// Every @handle in a comment has to name exactly one user, so a typo is caught when the
+// comment is posted instead of silently notifying nobody.
+async fn check_mentions(conn: &mut DbConnection, body: &str) -> Result<(), ApiError> {
+    let errors: Vec<FieldError> = mentions::unresolved_handles(conn, body).await?
+        .into_iter()
+        .map(|handle| FieldError { field: "body", message: format!("@{} does not name a user", handle) })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::Validation(errors))
+    }
+}
+
+// A task's discussion, oldest first by default.
+#[get("/tasks/<id>/comments?<paging..>")]
+pub async fn get_task_comments(id: i32, db: TenantDb, paging: PageQuery) -> Result<Json<Page<CommentView>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(COMMENT_SORT_COLUMNS)?;
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    let comments = Comment::read_page_for_task(&mut conn, id, page, per_page, &sort).await?;
+    Ok(Json(Page::new(comments.items.into_iter().map(CommentView::from).collect(), comments.page, comments.per_page, comments.total)))
+}
+
+// Anyone signed in may comment; the caller is recorded as the author. Users named with
+// @handle (the part of their email before the '@') get a notification.
+#[post("/tasks/<id>/comments", data = "<comment>")]
+pub async fn create_comment(id: i32, db: TenantDb, auth: AuthenticatedUser, comment: Json<CommentInput>) -> Result<Json<CommentView>, ApiError> {
+    comment.validate()?;
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    check_mentions(&mut conn, &comment.body).await?;
+    let new_comment = NewComment {
+        task_id: id,
+        author_id: auth.user_id,
+        body: &comment.body,
+    };
+    Ok(Json(Comment::create_audited(&mut conn, Some(auth.user_id), new_comment).await?.into()))
+}
+
+// Only the author, or a manager, may edit or delete a comment.
+async fn read_own_comment(conn: &mut DbConnection, auth: &AuthenticatedUser, id: i32) -> Result<Comment, ApiError> {
+    let comment = Comment::read(conn, id).await?.ok_or_else(|| ApiError::not_found("Comment"))?;
+    auth.require_self_or(comment.author_id, UserRole::Manager)?;
+    Ok(comment)
+}
+
+// The body being replaced is kept; see GET /comments/<id>/history.
+#[put("/comments/<id>", data = "<comment>")]
+pub async fn update_comment(id: i32, db: TenantDb, auth: AuthenticatedUser, if_match: IfMatch, comment: Json<CommentInput>) -> Result<Json<CommentView>, ApiError> {
+    comment.validate()?;
+    let mut conn = db.get().await?;
+    read_own_comment(&mut conn, &auth, id).await?;
+    check_mentions(&mut conn, &comment.body).await?;
+    Ok(Json(Comment::edit(&mut conn, Some(auth.user_id), id, if_match.expected(), &comment.body).await?.into()))
+}
+
+// Earlier versions of a comment, newest first. The current text is the comment itself.
+#[get("/comments/<id>/history?<paging..>")]
+pub async fn get_comment_history(id: i32, db: TenantDb, paging: PageQuery) -> Result<Json<Page<CommentRevision>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let mut conn = db.get().await?;
+    if Comment::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Comment"));
+    }
+    Ok(Json(CommentRevision::read_history(&mut conn, id, page, per_page).await?))
+}
+
+// Comments that mention the user, newest first. Only the user themselves or a manager may look.
+#[get("/users/<id>/mentions?<paging..>")]
+pub async fn get_user_mentions(id: i32, db: TenantDb, auth: AuthenticatedUser, paging: PageQuery) -> Result<Json<Page<Mention>>, ApiError> {
+    auth.require_self_or(id, UserRole::Manager)?;
+    let (page, per_page) = paging.resolve()?;
+    let mut conn = db.get().await?;
+    if User::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("User"));
+    }
+    Ok(Json(Mention::read_page_for_user(&mut conn, id, page, per_page).await?))
+}
+
+#[delete("/comments/<id>")]
+pub async fn delete_comment(id: i32, db: TenantDb, auth: AuthenticatedUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    read_own_comment(&mut conn, &auth, id).await?;
+    match Comment::delete_audited(&mut conn, Some(auth.user_id), id).await? {
+        0 => Err(ApiError::not_found("Comment")),
+        count => Ok(Json(count)),
+    }
+}
@@ -0,0 +1,147 @@
+use std::io::{Cursor, Write};
+use rocket::{Request, Response};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{Header, Status};
+use flate2::write::GzEncoder;
+
+// Smaller bodies aren't worth the CPU: the headers alone are a few hundred bytes.
+const DEFAULT_MIN_BYTES: usize = 1024;
+// Brotli quality runs 0-11; 5 is close to gzip's speed with noticeably smaller output.
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW_BITS: u32 = 22;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+}
+
+// JSON bodies (including problem+json) of at least COMPRESSION_MIN_BYTES are sent brotli- or
+// gzip-compressed when the client's Accept-Encoding allows it. Attached last, so it sees the
+// body the other fairings settled on.
+pub struct Compression {
+    min_bytes: usize,
+}
+
+impl Compression {
+    pub fn from_env() -> Compression {
+        let min_bytes = std::env::var("COMPRESSION_MIN_BYTES")
+            .ok()
+            .and_then(|b| b.parse().ok())
+            .unwrap_or(DEFAULT_MIN_BYTES);
+        Compression { min_bytes }
+    }
+}
+
+// The encoding Accept-Encoding rates highest, brotli on a tie. `identity` and anything we don't
+// do are ignored, as is a coding with q=0, which means "never".
+fn negotiate(accept_encoding: &str) -> Option<Encoding> {
+    let mut best: Option<(Encoding, f32)> = None;
+    for coding in accept_encoding.split(',') {
+        let mut parts = coding.split(';').map(str::trim);
+        let encoding = match parts.next().map(str::to_ascii_lowercase).as_deref() {
+            Some("br") => Encoding::Brotli,
+            Some("gzip" | "x-gzip") => Encoding::Gzip,
+            _ => continue,
+        };
+        let q = parts
+            .find_map(|param| param.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()))
+            .unwrap_or(1.0);
+        let better = match best {
+            None => true,
+            Some((current, best_q)) => q > best_q || (q == best_q && encoding == Encoding::Brotli && current != Encoding::Brotli),
+        };
+        if q > 0.0 && better {
+            best = Some((encoding, q));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+fn compress(encoding: Encoding, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+        Encoding::Brotli => {
+            let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, BROTLI_QUALITY, BROTLI_WINDOW_BITS);
+            encoder.write_all(bytes)?;
+            encoder.flush()?;
+            Ok(encoder.into_inner())
+        }
+    }
+}
+
+fn is_json(res: &Response<'_>) -> bool {
+    res.content_type().is_some_and(|content_type| {
+        content_type.top() == "application" && (content_type.sub() == "json" || content_type.sub().as_str().ends_with("+json"))
+    })
+}
+
+#[rocket::async_trait]
+impl Fairing for Compression {
+    fn info(&self) -> Info {
+        Info { name: "Response compression", kind: Kind::Response }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if !is_json(res) || res.status() == Status::NoContent || res.headers().contains("Content-Encoding") {
+            return;
+        }
+        res.adjoin_header(Header::new("Vary", "Accept-Encoding"));
+        let Some(encoding) = req.headers().get("Accept-Encoding").find_map(negotiate) else {
+            return;
+        };
+        let Ok(bytes) = res.body_mut().to_bytes().await else {
+            return;
+        };
+        let body = match bytes.len() >= self.min_bytes {
+            true => compress(encoding, &bytes).ok(),
+            false => None,
+        };
+        match body {
+            Some(compressed) => {
+                res.set_header(Header::new("Content-Encoding", encoding.as_str()));
+                res.set_sized_body(compressed.len(), Cursor::new(compressed));
+            }
+            None => res.set_sized_body(bytes.len(), Cursor::new(bytes)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use flate2::read::GzDecoder;
+
+    #[test]
+    fn the_best_accepted_encoding_wins_and_round_trips() {
+        assert_eq!(negotiate("gzip, deflate, br"), Some(Encoding::Brotli));
+        assert_eq!(negotiate("br;q=0.5, gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("br;q=0, gzip;q=0"), None);
+        assert_eq!(negotiate("identity, deflate"), None);
+
+        let json = br#"{"items":[{"task_id":1},{"task_id":1},{"task_id":1},{"task_id":1}]}"#.repeat(20);
+        let gzipped = compress(Encoding::Gzip, &json).unwrap();
+        let mut unzipped = Vec::new();
+        GzDecoder::new(&gzipped[..]).read_to_end(&mut unzipped).unwrap();
+        assert_eq!(unzipped, json);
+        let brotlied = compress(Encoding::Brotli, &json).unwrap();
+        assert!(brotlied.len() < json.len());
+        let mut unbrotlied = Vec::new();
+        brotli::Decompressor::new(&brotlied[..], 4096).read_to_end(&mut unbrotlied).unwrap();
+        assert_eq!(unbrotlied, json);
+    }
+}
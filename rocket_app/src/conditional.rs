@@ -0,0 +1,235 @@
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::Serialize;
+use chrono::NaiveDateTime;
+use tasks_db_lib::models::{Project, Tag, Task, TaskStatus, Team, User, UserTask};
+use tasks_db_lib::versioning::Versioned;
+use crate::error::ApiError;
+use crate::msgpack::Negotiated;
+
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+// A row's ETag is its version in quotes, e.g. "3".
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+// If-Match on PUT/PATCH: the ETag the client last saw, which is the row's version in
+// quotes. Required so two clients editing the same row can't silently overwrite each
+// other; a stale one gets 412 instead.
+pub enum IfMatch {
+    Any,
+    Version(i32),
+}
+
+impl IfMatch {
+    // The version the crud layer should insist on; `If-Match: *` skips the check.
+    pub fn expected(&self) -> Option<i32> {
+        match self {
+            IfMatch::Any => None,
+            IfMatch::Version(version) => Some(*version),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let value = match req.headers().get_one("If-Match") {
+            Some(value) => value.trim(),
+            None => return ApiError::PreconditionRequired(
+                "This request needs an If-Match header with the ETag from your last read".to_string()).guard_failure(req),
+        };
+        if value == "*" {
+            return Outcome::Success(IfMatch::Any);
+        }
+        // weak validators (W/"3") never match for If-Match, so only strong ones are accepted
+        let version = value.strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .and_then(|v| v.parse().ok());
+        match version {
+            Some(version) => Outcome::Success(IfMatch::Version(version)),
+            None => ApiError::BadRequest(format!("If-Match must be an ETag such as \"1\" or *, got {}", value)).guard_failure(req),
+        }
+    }
+}
+
+// Rows that single-resource GETs can answer with ETag and Last-Modified.
+pub trait Cacheable: Versioned + Serialize {
+    fn last_modified(&self) -> NaiveDateTime;
+}
+
+impl Cacheable for User {
+    fn last_modified(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+impl Cacheable for Task {
+    fn last_modified(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+impl Cacheable for TaskStatus {
+    fn last_modified(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+impl Cacheable for Project {
+    fn last_modified(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+impl Cacheable for Team {
+    fn last_modified(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+impl Cacheable for Tag {
+    fn last_modified(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+impl Cacheable for UserTask {
+    fn last_modified(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+// If-None-Match / If-Modified-Since from a GET. Malformed values are ignored, as RFC 9110 asks.
+pub struct CacheValidators {
+    if_none_match: Option<String>,
+    if_modified_since: Option<NaiveDateTime>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CacheValidators {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = req.headers();
+        Outcome::Success(CacheValidators {
+            if_none_match: headers.get_one("If-None-Match").map(str::to_string),
+            if_modified_since: headers.get_one("If-Modified-Since")
+                .and_then(|since| NaiveDateTime::parse_from_str(since, HTTP_DATE).ok()),
+        })
+    }
+}
+
+impl CacheValidators {
+    // If-None-Match wins when both are sent. It uses weak comparison, so W/"3" matches "3".
+    fn is_fresh(&self, version: i32, last_modified: NaiveDateTime) -> bool {
+        if let Some(if_none_match) = &self.if_none_match {
+            let current = etag(version);
+            return if_none_match.split(',')
+                .map(|tag| tag.trim())
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == current);
+        }
+        // HTTP dates only have whole seconds
+        match self.if_modified_since {
+            Some(since) => last_modified.and_utc().timestamp() <= since.and_utc().timestamp(),
+            None => false,
+        }
+    }
+
+    pub fn respond<T: Cacheable>(&self, item: T) -> Cached<T> {
+        let etag = etag(item.version());
+        let last_modified = item.last_modified();
+        let item = if self.is_fresh(item.version(), last_modified) { None } else { Some(item) };
+        Cached { item, etag, last_modified }
+    }
+}
+
+// The row (as JSON, or MessagePack if the client prefers it; see crate::msgpack), or an empty
+// 304 Not Modified when the client's copy is current. Both carry the row's validators.
+pub struct Cached<T> {
+    item: Option<T>,
+    etag: String,
+    last_modified: NaiveDateTime,
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Cached<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = match self.item {
+            Some(item) => Negotiated(item).respond_to(req)?,
+            None => Response::build().status(Status::NotModified).finalize(),
+        };
+        response.set_raw_header("ETag", self.etag);
+        response.set_raw_header("Last-Modified", self.last_modified.format(HTTP_DATE).to_string());
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::{Header, Status};
+    use rocket::local::blocking::Client;
+    use rocket::{catchers, get, routes};
+
+    #[get("/expected")]
+    fn expected(if_match: IfMatch) -> String {
+        format!("{:?}", if_match.expected())
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .mount("/", routes![expected])
+            .register("/", catchers![crate::catchers::default_catcher]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    fn send(client: &Client, if_match: Option<&'static str>) -> (Status, String) {
+        let mut request = client.get("/expected");
+        if let Some(value) = if_match {
+            request = request.header(Header::new("If-Match", value));
+        }
+        let response = request.dispatch();
+        (response.status(), response.into_string().unwrap_or_default())
+    }
+
+    #[test]
+    fn reads_a_strong_etag_or_a_star() {
+        let client = client();
+        assert_eq!(send(&client, Some("\"3\"")), (Status::Ok, "Some(3)".to_string()));
+        assert_eq!(send(&client, Some("*")), (Status::Ok, "None".to_string()));
+    }
+
+    #[test]
+    fn a_missing_or_weak_etag_is_refused() {
+        let client = client();
+        assert_eq!(send(&client, None).0, Status::PreconditionRequired);
+        assert_eq!(send(&client, Some("W/\"3\"")).0, Status::BadRequest);
+    }
+
+    fn validators(if_none_match: Option<&str>, if_modified_since: Option<&str>) -> CacheValidators {
+        CacheValidators {
+            if_none_match: if_none_match.map(str::to_string),
+            if_modified_since: if_modified_since.map(|since| NaiveDateTime::parse_from_str(since, HTTP_DATE).unwrap()),
+        }
+    }
+
+    #[test]
+    fn if_none_match_compares_weakly_and_wins_over_the_date() {
+        let modified = NaiveDateTime::parse_from_str("Wed, 14 Oct 2026 12:00:00 GMT", HTTP_DATE).unwrap();
+        assert!(validators(Some("\"2\", W/\"3\""), None).is_fresh(3, modified));
+        assert!(validators(Some("*"), None).is_fresh(3, modified));
+        assert!(!validators(Some("\"2\""), Some("Wed, 14 Oct 2026 13:00:00 GMT")).is_fresh(3, modified));
+    }
+
+    #[test]
+    fn if_modified_since_is_checked_to_the_second() {
+        let modified = NaiveDateTime::parse_from_str("Wed, 14 Oct 2026 12:00:00 GMT", HTTP_DATE).unwrap() + chrono::Duration::milliseconds(400);
+        assert!(validators(None, Some("Wed, 14 Oct 2026 12:00:00 GMT")).is_fresh(3, modified));
+        assert!(!validators(None, Some("Wed, 14 Oct 2026 11:59:59 GMT")).is_fresh(3, modified));
+        assert!(!validators(None, None).is_fresh(3, modified));
+    }
+}
@@ -0,0 +1,150 @@
+use std::io::Cursor;
+use rocket::{Request, Response};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{Header, Method, Status};
+
+const DEFAULT_METHODS: &str = "GET,POST,PUT,PATCH,DELETE";
+const DEFAULT_HEADERS: &str = "Authorization,Content-Type,Accept,If-Match,If-None-Match,If-Modified-Since,X-Request-Id,traceparent,tracestate";
+const DEFAULT_MAX_AGE_SECONDS: u32 = 600;
+// Response headers scripts on another origin may read, besides the CORS-safelisted ones.
+const EXPOSED_HEADERS: &str = "ETag,Last-Modified,Api-Version,Content-Disposition,Content-Encoding,Retry-After,X-Request-Id";
+
+// Which other origins' browser apps may call the API. Nobody but the API's own origin can until
+// CORS_ALLOWED_ORIGINS is set, to a comma-separated list like https://app.example.com or to *.
+// Credentials are bearer tokens, never cookies, so * is safe to send as is.
+#[derive(Debug)]
+pub struct CorsConfig {
+    origins: Vec<String>,
+    methods: Vec<String>,
+    headers: Vec<String>,
+    max_age_seconds: u32,
+}
+
+fn list(variable: &str, default: &str) -> Vec<String> {
+    std::env::var(variable)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl CorsConfig {
+    pub fn from_env() -> CorsConfig {
+        let max_age_seconds = std::env::var("CORS_MAX_AGE_SECONDS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MAX_AGE_SECONDS);
+        CorsConfig {
+            origins: list("CORS_ALLOWED_ORIGINS", ""),
+            methods: list("CORS_ALLOWED_METHODS", DEFAULT_METHODS).into_iter().map(|method| method.to_ascii_uppercase()).collect(),
+            headers: list("CORS_ALLOWED_HEADERS", DEFAULT_HEADERS),
+            max_age_seconds,
+        }
+    }
+
+    fn any_origin(&self) -> bool {
+        self.origins.iter().any(|origin| origin == "*")
+    }
+
+    // What Access-Control-Allow-Origin should say to `origin`, if it's allowed at all. Origins
+    // are compared without a trailing slash and case-insensitively, as browsers send them
+    // lowercased.
+    fn allow_origin(&self, origin: &str) -> Option<String> {
+        if self.any_origin() {
+            return Some("*".to_string());
+        }
+        self.origins.iter()
+            .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(origin))
+            .then(|| origin.to_string())
+    }
+
+    fn allows_method(&self, method: &str) -> bool {
+        self.methods.iter().any(|allowed| allowed.eq_ignore_ascii_case(method))
+    }
+
+    // Access-Control-Request-Headers lists every non-safelisted header the real request will
+    // carry; all of them have to be allowed.
+    fn allows_headers(&self, requested: &str) -> bool {
+        requested.split(',')
+            .map(str::trim)
+            .filter(|header| !header.is_empty())
+            .all(|header| self.headers.iter().any(|allowed| allowed.eq_ignore_ascii_case(header)))
+    }
+}
+
+// Adds CORS headers to responses for allowed origins, and answers pre-flight OPTIONS requests
+// itself: no route handles OPTIONS, so their 404 becomes a 204 carrying the allowed methods and
+// headers, or a 403 without any CORS headers (which the browser reports as blocked) when the
+// origin, method or a header isn't allowed.
+pub struct Cors(pub CorsConfig);
+
+#[rocket::async_trait]
+impl Fairing for Cors {
+    fn info(&self) -> Info {
+        Info { name: "CORS", kind: Kind::Response }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let config = &self.0;
+        // the answer depends on the caller's origin unless every origin gets the same one
+        if !config.origins.is_empty() && !config.any_origin() {
+            res.adjoin_header(Header::new("Vary", "Origin"));
+        }
+        let Some(origin) = req.headers().get_one("Origin") else {
+            return;
+        };
+        let allowed_origin = config.allow_origin(origin);
+        let preflight = req.headers().get_one("Access-Control-Request-Method");
+        if let (Method::Options, Some(method)) = (req.method(), preflight) {
+            let requested_headers = req.headers().get_one("Access-Control-Request-Headers").unwrap_or_default();
+            res.set_sized_body(0, Cursor::new(Vec::new()));
+            res.remove_header("Content-Type");
+            let allowed = allowed_origin.as_ref().filter(|_| config.allows_method(method) && config.allows_headers(requested_headers));
+            let Some(allowed_origin) = allowed else {
+                res.set_status(Status::Forbidden);
+                return;
+            };
+            res.set_status(Status::NoContent);
+            res.set_header(Header::new("Access-Control-Allow-Origin", allowed_origin.clone()));
+            res.set_header(Header::new("Access-Control-Allow-Methods", config.methods.join(", ")));
+            res.set_header(Header::new("Access-Control-Allow-Headers", config.headers.join(", ")));
+            res.set_header(Header::new("Access-Control-Max-Age", config.max_age_seconds.to_string()));
+            res.adjoin_header(Header::new("Vary", "Access-Control-Request-Method"));
+            res.adjoin_header(Header::new("Vary", "Access-Control-Request-Headers"));
+            return;
+        }
+        if let Some(allowed_origin) = allowed_origin {
+            res.set_header(Header::new("Access-Control-Allow-Origin", allowed_origin));
+            res.set_header(Header::new("Access-Control-Expose-Headers", EXPOSED_HEADERS));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(origins: &[&str]) -> CorsConfig {
+        CorsConfig {
+            origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            methods: vec!["GET".to_string(), "PUT".to_string()],
+            headers: vec!["Authorization".to_string(), "Content-Type".to_string()],
+            max_age_seconds: DEFAULT_MAX_AGE_SECONDS,
+        }
+    }
+
+    #[test]
+    fn only_listed_origins_methods_and_headers_are_allowed() {
+        let listed = config(&["https://app.example.com/"]);
+        assert_eq!(listed.allow_origin("https://app.example.com"), Some("https://app.example.com".to_string()));
+        assert_eq!(listed.allow_origin("https://evil.example.com"), None);
+        assert_eq!(config(&[]).allow_origin("https://app.example.com"), None);
+        assert_eq!(config(&["*"]).allow_origin("https://anyone.example"), Some("*".to_string()));
+
+        assert!(listed.allows_method("put") && !listed.allows_method("DELETE"));
+        assert!(listed.allows_headers("authorization, content-type") && listed.allows_headers(""));
+        assert!(!listed.allows_headers("Authorization, X-Debug"));
+    }
+}
@@ -0,0 +1,27 @@
+use tasks_db_lib::DbConnection;
+
+// `rocket_app --demo` serves the API from a database in memory instead of DATABASE_URL, filled
+// with the sample users, statuses, tasks and assignments (see tasks_db_lib::demo) and gone when
+// the server stops. alice@example.com administers and bob@example.com manages.
+pub fn requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--demo")
+}
+
+// Kept in managed state for the life of the server: the pool closes idle connections, and the
+// database would go with the last of them.
+pub struct DemoDatabase {
+    _conn: DbConnection,
+}
+
+// The URL to build the pool on, and what keeps the database there.
+#[cfg(feature = "sqlite")]
+pub async fn open() -> (String, DemoDatabase) {
+    let conn = tasks_db_lib::demo::open().await.expect("Failed to set up the demo database.");
+    tracing::info!(target: "demo", password = tasks_db_lib::seed::PASSWORD, "Serving sample data from memory; sign in as alice@example.com");
+    (tasks_db_lib::demo::DATABASE_URL.to_string(), DemoDatabase { _conn: conn })
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub async fn open() -> (String, DemoDatabase) {
+    panic!("--demo keeps its database in SQLite; build with the sqlite feature to use it")
+}
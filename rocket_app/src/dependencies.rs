@@ -0,0 +1,101 @@
+use rocket::{serde::json::Json, get, post, delete};
+use tasks_db_lib::DbConnection;
+use tasks_db_lib::models::{Task, TaskDependency};
+use tasks_db_lib::crud::CrudOperations;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::ManagerUser;
+use crate::links::{linked_all, Linked};
+use crate::overdue::OverdueConfig;
+use crate::repository::AssignmentRepository;
+use crate::validation::{FieldError, Validate, Validator};
+
+// Body of POST /tasks/<id>/dependencies: a task that has to be finished before this one.
+#[derive(rocket::serde::Deserialize)]
+pub struct DependencyInput {
+    pub blocking_task_id: i32,
+}
+
+impl Validate for DependencyInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new().id("blocking_task_id", self.blocking_task_id).finish()
+    }
+}
+
+#[derive(rocket::serde::Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TaskDependencies {
+    pub blocked_by: Vec<Linked<Task>>,
+    pub blocks: Vec<Linked<Task>>,
+}
+
+async fn read_dependencies(conn: &mut DbConnection, id: i32) -> Result<TaskDependencies, ApiError> {
+    Ok(TaskDependencies {
+        blocked_by: linked_all(TaskDependency::read_blockers(conn, id).await?),
+        blocks: linked_all(TaskDependency::read_blocked(conn, id).await?),
+    })
+}
+
+// An assignment can't move into a finished status (OVERDUE_TERMINAL_STATUSES) while any task
+// blocking its task is still open. Other status changes are always allowed.
+pub async fn check_not_blocked(assignments: &dyn AssignmentRepository, tenant_id: i32, config: &OverdueConfig, task_id: i32, task_status_id: i32) -> Result<(), ApiError> {
+    let Some(status) = assignments.read_status(tenant_id, task_status_id).await? else {
+        return Ok(());
+    };
+    if !config.terminal_statuses.contains(&status.status_name) {
+        return Ok(());
+    }
+    let open: Vec<String> = assignments.open_blocker_ids(tenant_id, task_id, &config.terminal_statuses).await?
+        .iter()
+        .map(i32::to_string)
+        .collect();
+    if open.is_empty() {
+        return Ok(());
+    }
+    Err(ApiError::Validation(vec![FieldError {
+        field: "task_status_id",
+        message: format!("task {} is blocked by open tasks {}", task_id, open.join(", ")),
+    }]))
+}
+
+// What the task is waiting on, and what is waiting on it.
+#[get("/tasks/<id>/dependencies")]
+pub async fn get_task_dependencies(id: i32, db: TenantDb) -> Result<Json<TaskDependencies>, ApiError> {
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    Ok(Json(read_dependencies(&mut conn, id).await?))
+}
+
+// Marks the task as blocked by another one. Linking the same pair twice is not an error;
+// a link that would close a loop is rejected. Responds with the task's dependencies.
+#[post("/tasks/<id>/dependencies", data = "<dependency>")]
+pub async fn add_task_dependency(id: i32, db: TenantDb, _manager: ManagerUser, dependency: Json<DependencyInput>) -> Result<Json<TaskDependencies>, ApiError> {
+    dependency.validate()?;
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    let message = if Task::read(&mut conn, dependency.blocking_task_id).await?.is_none() {
+        Some("must be an existing task")
+    } else if TaskDependency::would_create_cycle(&mut conn, dependency.blocking_task_id, id).await? {
+        Some("must not be the task itself or a task it already blocks")
+    } else {
+        None
+    };
+    if let Some(message) = message {
+        return Err(ApiError::Validation(vec![FieldError { field: "blocking_task_id", message: message.to_string() }]));
+    }
+    TaskDependency::link(&mut conn, dependency.blocking_task_id, id).await?;
+    Ok(Json(read_dependencies(&mut conn, id).await?))
+}
+
+#[delete("/tasks/<id>/dependencies/<blocking_task_id>")]
+pub async fn remove_task_dependency(id: i32, blocking_task_id: i32, db: TenantDb, _manager: ManagerUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match TaskDependency::unlink(&mut conn, blocking_task_id, id).await? {
+        0 => Err(ApiError::not_found("Task dependency")),
+        count => Ok(Json(count)),
+    }
+}
@@ -0,0 +1,299 @@
+use rocket::http::{ContentType, Status};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::Json;
+use rocket::serde::Serialize;
+use rocket::Request;
+use rocket::request::Outcome;
+use diesel_async::pooled_connection::PoolError;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use tasks_db_lib::quotas::QuotaExceeded;
+use tasks_db_lib::versioning::StaleVersion;
+use crate::request_id::RequestId;
+use crate::validation::FieldError;
+
+// Every handler returns Result<Json<T>, ApiError> so the client gets a real
+// status code plus a problem+json body explaining what went wrong.
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    BadRequest(String),    // 400
+    Unauthorized(String),  // 401
+    Forbidden(String),     // 403
+    NotFound(String),      // 404
+    Conflict(String),      // 409
+    PreconditionFailed(String),   // 412
+    Validation(Vec<FieldError>), // 422
+    PreconditionRequired(String), // 428
+    TooManyRequests(String),      // 429
+    Internal(String),      // 500
+}
+
+// RFC 7807 problem details, the one error body shape used by ApiError and the catchers.
+// We don't define problem types of our own, so `type` is always "about:blank"
+// and `title` is the status reason phrase.
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub instance: String,
+    // extension member: the X-Request-Id the failure was logged under
+    pub request_id: String,
+    // extension member: one entry per rejected field on 422 responses
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl ProblemDetails {
+    pub fn new(status: Status, detail: String, req: &Request<'_>) -> ProblemDetails {
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: status.reason_lossy().to_string(),
+            status: status.code,
+            detail,
+            instance: req.uri().to_string(),
+            request_id: RequestId::of(req).to_string(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ProblemDetails {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let status = Status::from_code(self.status).unwrap_or(Status::InternalServerError);
+        // server-side failures are ours to look into; a client reporting one quotes the request_id
+        if status.class().is_server_error() {
+            tracing::error!(method = req.method().as_str(), path = self.instance, request_id = self.request_id, "{}", self.detail);
+        }
+        Response::build_from(Json(self).respond_to(req)?)
+            .status(status)
+            .header(ContentType::new("application", "problem+json"))
+            .ok()
+    }
+}
+
+// Message from the first request guard that failed, if any.
+pub struct GuardFailure(pub Option<String>);
+
+impl ApiError {
+    pub fn status(&self) -> Status {
+        match self {
+            ApiError::BadRequest(_) => Status::BadRequest,
+            ApiError::Unauthorized(_) => Status::Unauthorized,
+            ApiError::Forbidden(_) => Status::Forbidden,
+            ApiError::NotFound(_) => Status::NotFound,
+            ApiError::Conflict(_) => Status::Conflict,
+            ApiError::PreconditionFailed(_) => Status::PreconditionFailed,
+            ApiError::Validation(_) => Status::UnprocessableEntity,
+            ApiError::PreconditionRequired(_) => Status::PreconditionRequired,
+            ApiError::TooManyRequests(_) => Status::TooManyRequests,
+            ApiError::Internal(_) => Status::InternalServerError,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::BadRequest(msg)
+            | ApiError::Unauthorized(msg)
+            | ApiError::Forbidden(msg)
+            | ApiError::NotFound(msg)
+            | ApiError::Conflict(msg)
+            | ApiError::PreconditionFailed(msg)
+            | ApiError::PreconditionRequired(msg)
+            | ApiError::TooManyRequests(msg)
+            | ApiError::Internal(msg) => msg,
+            ApiError::Validation(_) => "The request body failed validation",
+        }
+    }
+
+    pub fn not_found(what: &str) -> ApiError {
+        ApiError::NotFound(format!("{} not found", what))
+    }
+
+    // Lets a create handler swap the generic duplicate-key message for one naming the values,
+    // e.g. `.map_err(|e| ApiError::from(e).on_conflict(|| format!(...)))`.
+    pub fn on_conflict(self, detail: impl FnOnce() -> String) -> ApiError {
+        match self {
+            ApiError::Conflict(_) => ApiError::Conflict(detail()),
+            other => other,
+        }
+    }
+
+    // Request guards can't send a body themselves; Rocket hands the status to a catcher.
+    // Park the message in the request-local cache so the catcher can report it.
+    pub fn guard_failure<T>(self, req: &Request<'_>) -> Outcome<T, ApiError> {
+        let message = self.message().to_string();
+        req.local_cache(|| GuardFailure(Some(message)));
+        Outcome::Error((self.status(), self))
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut problem = ProblemDetails::new(self.status(), self.message().to_string(), req);
+        if let ApiError::Validation(errors) = self {
+            problem.errors = errors;
+        }
+        problem.respond_to(req)
+    }
+}
+
+// Diesel errors come back wrapped in anyhow from the crud layer, so look
+// inside to decide which status code the client should see.
+impl From<DieselError> for ApiError {
+    fn from(err: DieselError) -> ApiError {
+        match err {
+            DieselError::NotFound => ApiError::NotFound("Record not found".to_string()),
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                ApiError::Conflict(duplicate_key_detail(info.message(), info.table_name(), info.details()))
+            }
+            // pool connections enforce foreign keys; see tasks_db_lib::configure
+            DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => {
+                ApiError::BadRequest(info.message().to_string())
+            }
+            other => ApiError::Internal(other.to_string()),
+        }
+    }
+}
+
+// SQLite reports "UNIQUE constraint failed: user_tasks.user_id, user_tasks.task_id";
+// PostgreSQL names the table and details "Key (user_id, task_id)=(1, 2) already exists."
+// Turn either into which table and key columns clashed. Names PostgreSQL compares by
+// lower(name) (see migrations_postgres) read as the column itself, as they do in SQLite.
+fn duplicate_key_detail(message: &str, table_name: Option<&str>, details: Option<&str>) -> String {
+    let key = details
+        .and_then(|details| details.strip_prefix("Key ("))
+        .and_then(|details| details.split_once(")=("));
+    if let (Some(table), Some((columns, _))) = (table_name, key) {
+        let keys: Vec<&str> = columns.split(", ")
+            .map(|column| column.strip_prefix("lower(").and_then(|c| c.strip_suffix(')')).unwrap_or(column))
+            .collect();
+        return format!("A {} record with the same ({}) already exists", table, keys.join(", "));
+    }
+    let Some(columns) = message.strip_prefix("UNIQUE constraint failed: ") else {
+        return message.to_string();
+    };
+    let mut table = "";
+    let keys: Vec<&str> = columns.split(", ")
+        .map(|column| match column.split_once('.') {
+            Some((t, c)) => { table = t; c }
+            None => column,
+        })
+        .collect();
+    format!("A {} record with the same ({}) already exists", table, keys.join(", "))
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> ApiError {
+        if let Some(stale) = err.downcast_ref::<StaleVersion>() {
+            return ApiError::PreconditionFailed(format!(
+                "If-Match was \"{}\" but the current ETag is \"{}\"; fetch the resource again and retry", stale.expected, stale.current));
+        }
+        if let Some(exceeded) = err.downcast_ref::<QuotaExceeded>() {
+            return ApiError::TooManyRequests(format!("This API key's {}", exceeded));
+        }
+        match err.downcast::<DieselError>() {
+            Ok(diesel_err) => ApiError::from(diesel_err),
+            Err(other) => ApiError::Internal(other.to_string()),
+        }
+    }
+}
+
+impl From<bb8::RunError<PoolError>> for ApiError {
+    fn from(err: bb8::RunError<PoolError>) -> ApiError {
+        ApiError::Internal(format!("db connection: {}", err))
+    }
+}
+
+impl From<std::io::Error> for ApiError {
+    fn from(err: std::io::Error) -> ApiError {
+        ApiError::Internal(format!("file storage: {}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "sqlite")]
+    use diesel::{connection::SimpleConnection, prelude::*, sqlite::SqliteConnection};
+
+    // Real SQLite errors, from a throwaway database with one unique and one foreign key.
+    #[cfg(feature = "sqlite")]
+    fn database_error(sql: &str) -> DieselError {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute("
+            PRAGMA foreign_keys = ON;
+            CREATE TABLE parents (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+            CREATE TABLE children (id INTEGER PRIMARY KEY, parent_id INTEGER NOT NULL REFERENCES parents(id));
+            INSERT INTO parents (id, name) VALUES (1, 'one');
+        ").unwrap();
+        diesel::sql_query(sql).execute(&mut conn).unwrap_err()
+    }
+
+    #[test]
+    fn each_variant_has_its_status() {
+        let cases = [
+            (ApiError::BadRequest(String::new()), Status::BadRequest),
+            (ApiError::Unauthorized(String::new()), Status::Unauthorized),
+            (ApiError::Forbidden(String::new()), Status::Forbidden),
+            (ApiError::NotFound(String::new()), Status::NotFound),
+            (ApiError::Conflict(String::new()), Status::Conflict),
+            (ApiError::PreconditionFailed(String::new()), Status::PreconditionFailed),
+            (ApiError::Validation(Vec::new()), Status::UnprocessableEntity),
+            (ApiError::PreconditionRequired(String::new()), Status::PreconditionRequired),
+            (ApiError::TooManyRequests(String::new()), Status::TooManyRequests),
+            (ApiError::Internal(String::new()), Status::InternalServerError),
+        ];
+        for (error, status) in cases {
+            assert_eq!(error.status(), status, "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn a_missing_row_is_not_found() {
+        assert_eq!(ApiError::from(DieselError::NotFound).status(), Status::NotFound);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn a_duplicate_key_is_a_conflict_naming_the_columns() {
+        let error = ApiError::from(database_error("INSERT INTO parents (id, name) VALUES (2, 'one')"));
+        assert_eq!(error.status(), Status::Conflict);
+        assert_eq!(error.message(), "A parents record with the same (name) already exists");
+    }
+
+    #[test]
+    fn a_postgres_duplicate_key_names_the_columns_too() {
+        let detail = duplicate_key_detail(
+            "duplicate key value violates unique constraint \"tags_tag_name_key\"",
+            Some("tags"),
+            Some("Key (tenant_id, lower(tag_name))=(1, urgent) already exists."),
+        );
+        assert_eq!(detail, "A tags record with the same (tenant_id, tag_name) already exists");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn a_missing_reference_is_a_bad_request() {
+        let error = ApiError::from(database_error("INSERT INTO children (id, parent_id) VALUES (1, 99)"));
+        assert_eq!(error.status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn errors_from_the_crud_layer_are_unwrapped() {
+        let stale = anyhow::Error::from(StaleVersion { expected: 1, current: 2 });
+        assert_eq!(ApiError::from(stale).status(), Status::PreconditionFailed);
+        assert_eq!(ApiError::from(anyhow::Error::from(DieselError::NotFound)).status(), Status::NotFound);
+        assert_eq!(ApiError::from(anyhow::anyhow!("disk on fire")).status(), Status::InternalServerError);
+    }
+
+    #[test]
+    fn on_conflict_only_rewords_conflicts() {
+        let reworded = ApiError::Conflict("generic".to_string()).on_conflict(|| "specific".to_string());
+        assert_eq!(reworded.message(), "specific");
+        let untouched = ApiError::NotFound("missing".to_string()).on_conflict(|| "specific".to_string());
+        assert_eq!(untouched.message(), "missing");
+    }
+}
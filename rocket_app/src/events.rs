@@ -0,0 +1,119 @@
+use rocket::{State, Shutdown, get};
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::Serialize;
+use rocket::serde::json::serde_json::{self, Value};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::{self, error::RecvError};
+use crate::auth::AuthenticatedUser;
+
+// how many changes a slow subscriber can fall behind before it starts missing them
+const CHANNEL_CAPACITY: usize = 1024;
+
+// Event names on the stream. An assignment is only announced as changed when it moves to another
+// status, and the data then carries the status it came from as `from_task_status_id`. Restored
+// tasks, assignments and statuses are announced as created; so are tasks made by cloning or from
+// a template.
+pub const TASK_CREATED: &str = "task.created";
+pub const ASSIGNMENT_CREATED: &str = "assignment.created";
+pub const ASSIGNMENT_STATUS_CHANGED: &str = "assignment.status_changed";
+pub const ASSIGNMENT_DELETED: &str = "assignment.deleted";
+pub const TASK_STATUS_CREATED: &str = "task_status.created";
+pub const TASK_STATUS_UPDATED: &str = "task_status.updated";
+pub const TASK_STATUS_DELETED: &str = "task_status.deleted";
+
+#[derive(Clone)]
+pub struct Change {
+    pub tenant_id: i32,
+    pub event_type: &'static str,
+    pub data: Value,
+}
+
+// In-process fan-out from the handlers that create tasks and change assignments or statuses to
+// every open stream and socket. Nothing is stored: a client that connects late, or falls too far
+// behind, refetches the list endpoints.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Change>,
+}
+
+impl Default for EventBus {
+    fn default() -> EventBus {
+        EventBus { sender: broadcast::channel(CHANNEL_CAPACITY).0 }
+    }
+}
+
+impl EventBus {
+    // Handlers call this once their change has committed. Having no subscribers is not an error.
+    pub fn publish(&self, tenant_id: i32, event_type: &'static str, data: &impl Serialize) {
+        match serde_json::to_value(data) {
+            Ok(data) => {
+                let _ = self.sender.send(Change { tenant_id, event_type, data });
+            }
+            Err(e) => tracing::error!("Failed to publish {}: {}", event_type, e),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Change> {
+        self.sender.subscribe()
+    }
+}
+
+// The data of an ASSIGNMENT_STATUS_CHANGED event: the assignment as saved plus where it moved from.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StatusChange<'a, T> {
+    #[serde(flatten)]
+    pub assignment: &'a T,
+    pub from_task_status_id: i32,
+}
+
+// Server-sent events for the caller's tenant: one event per new task, assignment change or status
+// change, named as above, with the task, assignment or status as JSON data (for deletes, just its
+// key). A subscriber that falls behind gets a `lagged` event with the number of changes it missed.
+#[get("/events/stream")]
+pub async fn stream_events(auth: AuthenticatedUser, bus: &State<EventBus>, mut shutdown: Shutdown) -> EventStream![] {
+    let mut changes = bus.subscribe();
+    EventStream! {
+        loop {
+            let change = select! {
+                change = changes.recv() => change,
+                _ = &mut shutdown => break,
+            };
+            match change {
+                Ok(change) if change.tenant_id == auth.tenant_id => yield Event::json(&change.data).event(change.event_type),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => yield Event::data(missed.to_string()).event("lagged"),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::serde::json::serde_json::json;
+
+    #[test]
+    fn subscribers_get_each_change_with_its_tenant() {
+        let bus = EventBus::default();
+        bus.publish(1, ASSIGNMENT_CREATED, &json!({ "user_id": 1, "task_id": 2 }));
+        let mut changes = bus.subscribe();
+        bus.publish(2, ASSIGNMENT_DELETED, &json!({ "user_id": 3, "task_id": 4 }));
+        let change = changes.try_recv().unwrap();
+        assert_eq!((change.tenant_id, change.event_type), (2, ASSIGNMENT_DELETED));
+        assert_eq!(change.data, json!({ "user_id": 3, "task_id": 4 }));
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn a_status_change_carries_the_status_it_came_from() {
+        let bus = EventBus::default();
+        let mut changes = bus.subscribe();
+        let assignment = json!({ "user_id": 1, "task_id": 2, "task_status_id": 3 });
+        bus.publish(1, ASSIGNMENT_STATUS_CHANGED, &StatusChange { assignment: &assignment, from_task_status_id: 2 });
+        let change = changes.try_recv().unwrap();
+        assert_eq!(change.event_type, ASSIGNMENT_STATUS_CHANGED);
+        assert_eq!(change.data, json!({ "user_id": 1, "task_id": 2, "task_status_id": 3, "from_task_status_id": 2 }));
+    }
+}
@@ -0,0 +1,186 @@
+use rocket::{get, Responder};
+use rocket::http::{ContentType, Header};
+use rocket::response::stream::ByteStream;
+use rocket::serde::json::serde_json;
+use tasks_db_lib::models::AssignmentDetail;
+use tasks_db_lib::sorting::{Sort, USER_TASK_SORT_COLUMNS};
+use tasks_db_lib::filters::AssignmentFilter;
+use crate::error::ApiError;
+use crate::request_id::RequestId;
+use crate::tenancy::TenantDb;
+
+// How many rows an export reads and sends at a time.
+const BATCH_ROWS: i64 = 500;
+
+pub const ASSIGNMENT_CSV_HEADER: &[&str] = &[
+    "user_id", "user_name", "user_email", "task_id", "task_name", "task_status_id", "status_name", "created_at", "updated_at",
+];
+
+// A file for the browser to save rather than show, e.g. assignments.csv.
+#[derive(Responder)]
+pub struct Download<T> {
+    body: (ContentType, T),
+    disposition: Header<'static>,
+}
+
+impl<T> Download<T> {
+    fn new(content_type: ContentType, file_name: &str, body: T) -> Download<T> {
+        Download { body: (content_type, body), disposition: Header::new("Content-Disposition", format!("attachment; filename=\"{}\"", file_name)) }
+    }
+}
+
+// Spreadsheets run a cell that starts with =, +, - or @ as a formula, so text from users (a task
+// named "=HYPERLINK(...)", say) gets a leading ' to keep it text.
+fn cell(text: &str) -> String {
+    match text.chars().next() {
+        Some('=' | '+' | '-' | '@' | '\t' | '\r') => format!("'{}", text),
+        _ => text.to_string(),
+    }
+}
+
+// CSV lines for the rows, quoted where needed, with the header first if `header` is set.
+fn assignment_rows(rows: &[AssignmentDetail], header: bool) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if header {
+        writer.write_record(ASSIGNMENT_CSV_HEADER)?;
+    }
+    for row in rows {
+        writer.write_record([
+            row.user_id.to_string(),
+            cell(&row.user_name),
+            cell(&row.user_email),
+            row.task_id.to_string(),
+            cell(&row.task_name),
+            row.task_status_id.to_string(),
+            cell(&row.status_name),
+            row.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            row.updated_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ])?;
+    }
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
+
+// One JSON object per row, each on its own line.
+fn ndjson_lines(rows: &[AssignmentDetail]) -> Result<Vec<u8>, serde_json::Error> {
+    let mut bytes = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut bytes, row)?;
+        bytes.push(b'\n');
+    }
+    Ok(bytes)
+}
+
+// The assignments GET /assignments would list for the same filters and ?sort=&order=, all of
+// them, one row each with the user, task and status names filled in. Rows are read and sent a
+// page at a time, so a big export doesn't sit in memory; an error part way through ends the
+// file early, since the 200 has already gone out.
+#[get("/assignments/export.csv?<user_id>&<task_id>&<task_status_id>&<project_id>&<sort>&<order>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_assignments_csv(user_id: Option<i32>, task_id: Option<i32>, task_status_id: Option<i32>, project_id: Option<i32>, sort: Option<&str>, order: Option<&str>, db: TenantDb, request_id: RequestId) -> Result<Download<ByteStream![Vec<u8>]>, ApiError> {
+    let sort = Sort::parse(sort, order, USER_TASK_SORT_COLUMNS).map_err(ApiError::BadRequest)?;
+    let filter = AssignmentFilter { user_id, task_id, task_status_id, project_id };
+    let mut conn = db.get().await?;
+    let first = AssignmentDetail::read_page(&mut conn, &filter, 1, BATCH_ROWS, &sort).await?;
+    drop(conn);
+    let body = ByteStream! {
+        let mut rows = first.items;
+        let mut page = 1;
+        let mut header = true;
+        loop {
+            match assignment_rows(&rows, header) {
+                Ok(bytes) => yield bytes,
+                Err(e) => {
+                    tracing::error!(request_id = request_id.0, "Assignment export failed: {}", e);
+                    break;
+                }
+            }
+            header = false;
+            if (rows.len() as i64) < BATCH_ROWS {
+                break;
+            }
+            page += 1;
+            let next: Result<_, ApiError> = async { Ok(AssignmentDetail::read_page(&mut *db.get().await?, &filter, page, BATCH_ROWS, &sort).await?) }.await;
+            match next {
+                Ok(next) => rows = next.items,
+                Err(e) => {
+                    tracing::error!(request_id = request_id.0, "Assignment export failed: {:?}", e);
+                    break;
+                }
+            }
+        }
+    };
+    Ok(Download::new(ContentType::CSV, "assignments.csv", body))
+}
+
+// One AssignmentDetail per line, as GET /assignments/detailed lists them, for every assignment
+// matching the filters, in (user_id, task_id) order. Batches pick up after the last row sent
+// rather than at an offset, so rows changing during a long export don't shift the rest, and
+// only one batch is in memory at a time. An error part way through ends the stream early.
+#[get("/assignments/export.ndjson?<user_id>&<task_id>&<task_status_id>&<project_id>")]
+pub async fn export_assignments_ndjson(user_id: Option<i32>, task_id: Option<i32>, task_status_id: Option<i32>, project_id: Option<i32>, db: TenantDb, request_id: RequestId) -> Result<Download<ByteStream![Vec<u8>]>, ApiError> {
+    let filter = AssignmentFilter { user_id, task_id, task_status_id, project_id };
+    let mut conn = db.get().await?;
+    let first = AssignmentDetail::read_batch(&mut conn, &filter, None, BATCH_ROWS).await?;
+    drop(conn);
+    let body = ByteStream! {
+        let mut rows = first;
+        loop {
+            match ndjson_lines(&rows) {
+                Ok(bytes) => yield bytes,
+                Err(e) => {
+                    tracing::error!(request_id = request_id.0, "Assignment export failed: {}", e);
+                    break;
+                }
+            }
+            let Some(last) = rows.last().filter(|_| rows.len() as i64 == BATCH_ROWS) else {
+                break;
+            };
+            let after = Some((last.user_id, last.task_id));
+            let next: Result<_, ApiError> = async { Ok(AssignmentDetail::read_batch(&mut *db.get().await?, &filter, after, BATCH_ROWS).await?) }.await;
+            match next {
+                Ok(next) => rows = next,
+                Err(e) => {
+                    tracing::error!(request_id = request_id.0, "Assignment export failed: {:?}", e);
+                    break;
+                }
+            }
+        }
+    };
+    Ok(Download::new(ContentType::new("application", "x-ndjson"), "assignments.ndjson", body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn csv_rows_are_quoted_and_formulas_stay_text_but_not_in_json() {
+        let at = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap().and_hms_opt(9, 30, 0).unwrap();
+        let row = AssignmentDetail {
+            user_id: 1,
+            user_name: "Vera \"V\" Lee".to_string(),
+            user_email: "vera@example.com".to_string(),
+            task_id: 7,
+            task_name: "=HYPERLINK(\"http://example.com\"), then ship".to_string(),
+            task_status_id: 2,
+            status_name: "In Progress".to_string(),
+            created_at: at,
+            updated_at: at,
+            version: 1,
+        };
+        let csv = String::from_utf8(assignment_rows(std::slice::from_ref(&row), true).unwrap()).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(ASSIGNMENT_CSV_HEADER.join(",").as_str()));
+        assert_eq!(
+            lines.next(),
+            Some("1,\"Vera \"\"V\"\" Lee\",vera@example.com,7,\"'=HYPERLINK(\"\"http://example.com\"\"), then ship\",2,In Progress,2026-10-15 09:30:00,2026-10-15 09:30:00"),
+        );
+        assert_eq!(assignment_rows(&[], false).unwrap(), Vec::<u8>::new());
+
+        let lines = String::from_utf8(ndjson_lines(std::slice::from_ref(&row)).unwrap()).unwrap();
+        assert!(lines.ends_with("}\n") && lines.matches('\n').count() == 1);
+        let first: serde_json::Value = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+        assert_eq!(first["task_name"], "=HYPERLINK(\"http://example.com\"), then ship");
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+use rocket::serde::{Serialize, Serializer};
+use rocket::serde::json::serde_json::{self, Value};
+use tasks_db_lib::pagination::Page;
+use crate::error::ApiError;
+
+// Field names each list route accepts in ?fields=, matching what its rows serialize to.
+pub const USER_FIELDS: &[&str] = &["user_id", "name", "email", "active", "role_id", "created_at", "updated_at", "version"];
+pub const TASK_FIELDS: &[&str] = &["task_id", "task_name", "created_at", "updated_at", "version", "due_date", "priority", "parent_task_id", "recurrence", "recurrence_paused", "next_occurrence_id", "project_id", "links"];
+pub const TASK_STATUS_FIELDS: &[&str] = &["task_status_id", "status_name", "created_at", "updated_at", "version"];
+pub const USER_TASK_FIELDS: &[&str] = &["user_id", "task_id", "task_status_id", "created_at", "updated_at", "version", "links", "user", "task", "status"];
+
+// ?fields=task_id,task_name keeps only those keys in each row, so clients on slow links
+// don't pay for columns they ignore. Without ?fields= rows go out whole.
+#[derive(Clone, Default)]
+pub struct Fields(Option<Arc<Vec<String>>>);
+
+impl Fields {
+    pub fn parse(raw: Option<&str>, allowed: &[&str]) -> Result<Fields, ApiError> {
+        let Some(raw) = raw else {
+            return Ok(Fields(None));
+        };
+        let fields: Vec<String> = raw.split(',')
+            .map(|field| field.trim())
+            .filter(|field| !field.is_empty())
+            .map(str::to_string)
+            .collect();
+        if fields.is_empty() {
+            return Err(ApiError::BadRequest("fields must list at least one field".to_string()));
+        }
+        if let Some(unknown) = fields.iter().find(|field| !allowed.contains(&field.as_str())) {
+            return Err(ApiError::BadRequest(format!("Unknown field '{}'; fields can be: {}", unknown, allowed.join(", "))));
+        }
+        Ok(Fields(Some(Arc::new(fields))))
+    }
+
+    pub fn apply<T>(&self, rows: Page<T>) -> Page<Sparse<T>> {
+        let items = rows.items.into_iter().map(|item| Sparse { item, fields: self.clone() }).collect();
+        Page::new(items, rows.page, rows.per_page, rows.total)
+    }
+}
+
+// A row that serializes with only the selected fields.
+pub struct Sparse<T> {
+    item: T,
+    fields: Fields,
+}
+
+impl<T: Serialize> Serialize for Sparse<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(fields) = &self.fields.0 else {
+            return self.item.serialize(serializer);
+        };
+        let mut value = serde_json::to_value(&self.item).map_err(rocket::serde::ser::Error::custom)?;
+        if let Value::Object(map) = &mut value {
+            map.retain(|key, _| fields.contains(key));
+        }
+        value.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::serde::json::serde_json::json;
+
+    fn page(fields: &Fields) -> Value {
+        let rows = Page::new(vec![json!({ "task_id": 1, "task_name": "Mop", "version": 2 })], 1, 10, 1);
+        serde_json::to_value(fields.apply(rows)).unwrap()
+    }
+
+    #[test]
+    fn keeps_only_the_named_fields() {
+        let fields = Fields::parse(Some("task_id, version"), TASK_FIELDS).unwrap();
+        assert_eq!(page(&fields)["items"][0], json!({ "task_id": 1, "version": 2 }));
+        assert_eq!(page(&Fields::default())["items"][0]["task_name"], "Mop");
+    }
+
+    #[test]
+    fn rejects_unknown_or_empty_field_lists() {
+        assert!(matches!(Fields::parse(Some("task_id,secret"), TASK_FIELDS), Err(ApiError::BadRequest(_))));
+        assert!(matches!(Fields::parse(Some(" , "), TASK_FIELDS), Err(ApiError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::Context;
+use tasks_db_lib::generate;
+
+// `rocket_app generate [--users N] [--tasks N] [--seed N]` adds made-up users, tasks and
+// assignments to the default tenant (see tasks_db_lib::generate) for load testing, then exits.
+// Each run adds more; the seed it prints makes a later run with --seed repeat it.
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub users: usize,
+    pub tasks: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { users: 1_000, tasks: 10_000, seed: None }
+    }
+}
+
+impl Options {
+    // The arguments after `generate`.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Options> {
+        let mut options = Options::default();
+        while let Some(flag) = args.next() {
+            let value = args.next().with_context(|| format!("{} needs a value", flag))?;
+            let number = || value.parse::<u64>().with_context(|| format!("{} must be a whole number, not '{}'", flag, value));
+            match flag.as_str() {
+                "--users" => options.users = number()? as usize,
+                "--tasks" => options.tasks = number()? as usize,
+                "--seed" => options.seed = Some(number()?),
+                _ => anyhow::bail!("unknown option {}; expected --users, --tasks or --seed", flag),
+            }
+        }
+        Ok(options)
+    }
+}
+
+pub async fn run(options: Options) -> anyhow::Result<()> {
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut conn = crate::seed::connect().await?;
+    println!("Generating {} users and {} tasks with seed {}", options.users, options.tasks, seed);
+    let generated = generate::run(&mut conn, options.users, options.tasks, seed).await?;
+    println!("Added {} users, {} tasks and {} assignments", generated.users, generated.tasks, generated.assignments);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Options> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags_override_the_defaults_and_bad_ones_are_refused() {
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+        assert_eq!(parse(&["--tasks", "50", "--seed", "7"]).unwrap(), Options { users: 1_000, tasks: 50, seed: Some(7) });
+        assert!(parse(&["--users"]).is_err());
+        assert!(parse(&["--users", "many"]).is_err());
+        assert!(parse(&["--projects", "3"]).is_err());
+    }
+}
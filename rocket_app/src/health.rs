@@ -0,0 +1,63 @@
+use std::time::Duration;
+use diesel_async::RunQueryDsl;
+use rocket::{State, get};
+use rocket::http::Status;
+use rocket::serde::Serialize;
+use rocket::serde::json::Json;
+use rocket::tokio::time::timeout;
+use tasks_db_lib::migrations;
+use crate::shutdown::Drain;
+use crate::tenancy::DbPool;
+
+// A readiness probe runs every few seconds; one stuck behind a busy pool should fail rather
+// than wait out the pool's 30 second default.
+const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Liveness {
+    pub status: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Readiness {
+    pub status: &'static str,
+    // "ok", or why the database can't be used
+    pub database: String,
+    // versions of migrations this build has that the database hasn't run
+    pub pending_migrations: Vec<String>,
+}
+
+// Liveness: the process is up and answering. Nothing else is checked, so a database outage
+// doesn't get the server restarted over and over.
+#[get("/health/live")]
+pub async fn get_liveness() -> Json<Liveness> {
+    Json(Liveness { status: "ok" })
+}
+
+// Readiness: a pooled connection answers `SELECT 1` and the schema is up to date, so requests
+// can be sent here. 503 otherwise, with what failed, and while the server is shutting down.
+#[get("/health/ready")]
+pub async fn get_readiness(pool: &State<DbPool>, drain: &State<Drain>) -> (Status, Json<Readiness>) {
+    if drain.is_draining() {
+        let readiness = Readiness { status: "draining", database: "not checked".to_string(), pending_migrations: Vec::new() };
+        return (Status::ServiceUnavailable, Json(readiness));
+    }
+    let checked = match timeout(READY_TIMEOUT, pool.get()).await {
+        Ok(Ok(mut conn)) => match diesel::sql_query("SELECT 1").execute(&mut *conn).await {
+            Ok(_) => migrations::pending(&mut conn).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+        Ok(Err(e)) => Err(format!("no connection: {:?}", e)),
+        Err(_) => Err("no connection: timed out waiting for one".to_string()),
+    };
+    let (database, pending_migrations) = match checked {
+        Ok(pending) => ("ok".to_string(), pending),
+        Err(problem) => (problem, Vec::new()),
+    };
+    let ready = database == "ok" && pending_migrations.is_empty();
+    let status = if ready { Status::Ok } else { Status::ServiceUnavailable };
+    let readiness = Readiness { status: if ready { "ready" } else { "unavailable" }, database, pending_migrations };
+    (status, Json(readiness))
+}
@@ -0,0 +1,388 @@
+use rocket::{serde::json::Json, State, post, FromForm};
+use rocket::form::Form;
+use rocket::fs::TempFile;
+use rocket::serde::{Deserialize, Serialize};
+use rocket::tokio::io::AsyncReadExt;
+use tasks_db_lib::DbConnection;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use tasks_db_lib::models::{NewTask, NewTaskStatus, Project, Task, TaskStatus, Team, User, UserTask};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::audit::AuditedCrud;
+use tasks_db_lib::enums::TaskPriority;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::ManagerUser;
+use crate::bulk::{self, BulkResponse};
+use crate::links::{linked, linked_all, Linked};
+use crate::events::{EventBus, ASSIGNMENT_CREATED, TASK_CREATED, TASK_STATUS_CREATED};
+use crate::validation::{Validate, Validator, MAX_STATUS_NAME_LEN, MAX_TASK_NAME_LEN};
+
+// multipart/form-data body of POST /tasks/import: one part named "file", holding the CSV.
+#[derive(FromForm)]
+pub struct CsvUpload<'r> {
+    pub file: TempFile<'r>,
+}
+
+// What wrote the CSV: our own columns (see ImportRow), or another tool's export, whose columns
+// and values are mapped onto ours.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImportFormat {
+    Csv,
+    // Trello's board export: Card Name, List Name (the card's list becomes its status), Due Date
+    Trello,
+    // Jira's issue export: Summary, Status, Priority, Due Date, Assignee
+    Jira,
+}
+
+impl ImportFormat {
+    pub const NAMES: &'static [&'static str] = &["csv", "trello", "jira"];
+
+    pub fn parse(raw: Option<&str>) -> Result<ImportFormat, ApiError> {
+        match raw.map(str::to_ascii_lowercase).as_deref() {
+            None | Some("csv") => Ok(ImportFormat::Csv),
+            Some("trello") => Ok(ImportFormat::Trello),
+            Some("jira") => Ok(ImportFormat::Jira),
+            Some(other) => Err(ApiError::BadRequest(format!("format must be one of {}, not '{}'", ImportFormat::NAMES.join(", "), other))),
+        }
+    }
+
+    // Our column for one of the CSV's headers, already lowercased with spaces as _. Another
+    // tool's columns we have no use for map to "", which is ignored.
+    fn column(self, header: &str) -> String {
+        let column = match (self, header) {
+            (ImportFormat::Csv, _) => return header.to_string(),
+            (ImportFormat::Trello, "card_name") | (ImportFormat::Jira, "summary") => "task_name",
+            (ImportFormat::Trello, "list_name") | (ImportFormat::Jira, "status") => "status",
+            (ImportFormat::Trello | ImportFormat::Jira, "due_date") => "due_date",
+            (ImportFormat::Jira, "priority") => "priority",
+            (ImportFormat::Jira, "assignee") => "assignees",
+            _ => "",
+        };
+        column.to_string()
+    }
+
+    // The header the task name comes from, as the tool writes it.
+    fn name_header(self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "task_name",
+            ImportFormat::Trello => "Card Name",
+            ImportFormat::Jira => "Summary",
+        }
+    }
+
+    // Statuses are how the other tools lay a board out, so the ones a file names are created
+    // rather than turning its rows away.
+    fn creates_statuses(self) -> bool {
+        self != ImportFormat::Csv
+    }
+
+    // Rewrites the tool's values into ours where they differ. Anything that can't be mapped is
+    // left as it was, for validate() to report.
+    fn adapt(self, row: &mut ImportRow) {
+        if self == ImportFormat::Csv {
+            return;
+        }
+        row.due_date = row.due_date.take().map(|raw| foreign_date(&raw).map(|date| date.to_string()).unwrap_or(raw));
+        if let Some(priority) = &row.priority {
+            match priority.to_ascii_lowercase().as_str() {
+                "highest" | "blocker" | "critical" => row.priority = Some("urgent".to_string()),
+                "lowest" | "minor" | "trivial" => row.priority = Some("low".to_string()),
+                "major" => row.priority = Some("high".to_string()),
+                _ => {}
+            }
+        }
+        // Jira exports a display name here unless it's set up to export emails, and only an email
+        // can be matched to one of our users.
+        if row.assignees.as_deref().is_some_and(|assignee| !assignee.contains('@')) {
+            row.assignees = None;
+        }
+    }
+}
+
+// Trello writes due dates as 2026-11-30T17:00:00.000Z, Jira as 30/Nov/26 5:00 PM; both are
+// also read without the time.
+fn foreign_date(raw: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d").ok()
+        .or_else(|| DateTime::parse_from_rfc3339(raw).ok().map(|at| at.date_naive()))
+        .or_else(|| NaiveDateTime::parse_from_str(raw, "%d/%b/%y %I:%M %p").ok().map(|at| at.date()))
+        .or_else(|| NaiveDate::parse_from_str(raw, "%d/%b/%y").ok())
+}
+
+// One data row of the CSV. Columns are matched by their header, in any order and any case
+// ("Task Name" is task_name); only task_name is required, and other columns are ignored.
+#[derive(Deserialize, Default)]
+#[serde(crate = "rocket::serde", default)]
+pub struct ImportRow {
+    pub task_name: String,
+    // 2026-11-30
+    pub due_date: Option<String>,
+    // low, medium, high or urgent; medium when empty
+    pub priority: Option<String>,
+    pub project_id: Option<String>,
+    // emails of the users to assign, separated by semicolons
+    pub assignees: Option<String>,
+    // the status name the assignments start in; the first status by id when empty
+    pub status: Option<String>,
+    // why the row couldn't be read at all, e.g. it has more fields than the header
+    #[serde(skip)]
+    unreadable: Option<String>,
+}
+
+impl ImportRow {
+    // The parsed columns are only meaningful after validate() has accepted them.
+    fn due_date(&self) -> Option<NaiveDate> {
+        self.due_date.as_deref().and_then(|raw| NaiveDate::parse_from_str(raw, "%Y-%m-%d").ok())
+    }
+
+    fn priority(&self) -> TaskPriority {
+        self.priority.as_deref().and_then(TaskPriority::from_name).unwrap_or_default()
+    }
+
+    fn project_id(&self) -> Option<i32> {
+        self.project_id.as_deref().and_then(|raw| raw.parse().ok())
+    }
+
+    fn assignees(&self) -> Vec<&str> {
+        self.assignees.as_deref().unwrap_or_default().split(';').map(str::trim).filter(|email| !email.is_empty()).collect()
+    }
+}
+
+impl Validate for ImportRow {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        if let Some(message) = &self.unreadable {
+            return validator.error("row", message.clone()).finish();
+        }
+        validator.text("task_name", &self.task_name, MAX_TASK_NAME_LEN);
+        if self.due_date.is_some() && self.due_date().is_none() {
+            validator.error("due_date", "must be a date like 2026-11-30");
+        }
+        if let Some(priority) = &self.priority && TaskPriority::from_name(priority).is_none() {
+            validator.error("priority", format!("must be one of {}", TaskPriority::NAMES.join(", ")));
+        }
+        match (&self.project_id, self.project_id()) {
+            (Some(_), None) => { validator.error("project_id", "must be a positive id"); }
+            (_, Some(project_id)) => { validator.id("project_id", project_id); }
+            _ => {}
+        }
+        for email in self.assignees() {
+            validator.email("assignees", email);
+        }
+        if let Some(status) = &self.status {
+            validator.text("status", status, MAX_STATUS_NAME_LEN);
+        }
+        validator.finish()
+    }
+}
+
+// What one row created.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ImportedTask {
+    pub task: Linked<Task>,
+    pub assignments: Vec<Linked<UserTask>>,
+}
+
+// Reads the rows under the header, in our columns whatever the format. A row that doesn't parse
+// (one with more or fewer fields than the header, say) is kept, marked unreadable, so the report
+// still has one entry per row.
+fn parse(text: &str, format: ImportFormat) -> Result<Vec<ImportRow>, ApiError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.strip_prefix('\u{feff}').unwrap_or(text).as_bytes());
+    let headers: csv::StringRecord = reader.headers()
+        .map_err(|e| ApiError::BadRequest(format!("The CSV header can't be read: {}", e)))?
+        .iter()
+        .map(|header| format.column(&header.to_lowercase().replace(' ', "_")))
+        .collect();
+    if !headers.iter().any(|header| header == "task_name") {
+        return Err(ApiError::BadRequest(format!("The first row must be a header naming the columns, including {}", format.name_header())));
+    }
+    reader.set_headers(headers);
+    Ok(reader.deserialize()
+        .map(|row| match row {
+            Ok(mut row) => {
+                format.adapt(&mut row);
+                row
+            }
+            Err(e) => ImportRow { unreadable: Some(e.to_string()), ..ImportRow::default() },
+        })
+        .collect())
+}
+
+// Creates the statuses the rows name that don't exist yet (matching names case-insensitively,
+// as resolve() does) and adds them to the end of `statuses`. Returns how many were created.
+async fn create_missing_statuses(conn: &mut DbConnection, actor: i32, statuses: &mut Vec<TaskStatus>, rows: &[&ImportRow]) -> Result<usize, ApiError> {
+    let mut created = 0;
+    for name in rows.iter().filter_map(|row| row.status.as_deref()) {
+        if statuses.iter().any(|status| status.status_name.eq_ignore_ascii_case(name)) {
+            continue;
+        }
+        let status = TaskStatus::create_audited(conn, Some(actor), NewTaskStatus { status_name: name }).await?;
+        statuses.push(status);
+        created += 1;
+    }
+    Ok(created)
+}
+
+// Looks the row's project, assignees and status up in the caller's tenant, giving the task to
+// create and its (user_id, task_status_id) assignments.
+async fn resolve<'a>(conn: &mut DbConnection, statuses: &[TaskStatus], row: &'a ImportRow) -> Result<(NewTask<'a>, Vec<(i32, i32)>), ApiError> {
+    let mut validator = Validator::new();
+    let project = match row.project_id() {
+        Some(project_id) => {
+            let project = Project::read(conn, project_id).await?;
+            if project.is_none() {
+                validator.error("project_id", "must be an existing project");
+            }
+            project
+        }
+        None => None,
+    };
+    let status = match row.status.as_deref() {
+        Some(name) => statuses.iter().find(|status| status.status_name.eq_ignore_ascii_case(name)),
+        None => statuses.iter().min_by_key(|status| status.task_status_id),
+    };
+    if status.is_none() && (row.status.is_some() || !row.assignees().is_empty()) {
+        validator.error("status", "must be the name of an existing status");
+    }
+    let mut user_ids = Vec::new();
+    for email in row.assignees() {
+        match User::read_by_email(conn, email).await? {
+            Some(user) if user_ids.contains(&user.user_id) => {}
+            Some(user) => {
+                if let Some(team_id) = project.as_ref().and_then(|project| project.team_id) && !Team::is_member(conn, team_id, user.user_id).await? {
+                    validator.error("assignees", format!("{} is not a member of the project's team", email));
+                }
+                user_ids.push(user.user_id);
+            }
+            None => { validator.error("assignees", format!("{} is not an existing user", email)); }
+        }
+    }
+    validator.finish()?;
+    let task_status_id = status.map(|status| status.task_status_id).unwrap_or_default();
+    let new_task = NewTask {
+        task_name: &row.task_name,
+        due_date: row.due_date(),
+        priority: row.priority(),
+        parent_task_id: None,
+        recurrence: None,
+        project_id: row.project_id(),
+    };
+    Ok((new_task, user_ids.into_iter().map(|user_id| (user_id, task_status_id)).collect()))
+}
+
+// For moving a spreadsheet in. The CSV's header names the columns (see ImportRow); each row
+// under it becomes a task, assigned to the users it lists. Rows are checked first, then the
+// good ones are created in one transaction. The answer is a bulk response (see crate::bulk)
+// with one entry per row, index 0 being the row under the header, so the rows that failed can
+// be fixed and sent again on their own. With ?format=trello or ?format=jira the file is that
+// tool's export instead (see ImportFormat), and statuses it names that don't exist are created
+// first, for the rows that passed validation.
+#[post("/tasks/import?<format>", data = "<upload>")]
+pub async fn import_tasks(format: Option<&str>, db: TenantDb, events: &State<EventBus>, manager: ManagerUser, upload: Form<CsvUpload<'_>>) -> Result<Json<BulkResponse<ImportedTask>>, ApiError> {
+    let format = ImportFormat::parse(format)?;
+    let mut bytes = Vec::new();
+    upload.file.open().await?.read_to_end(&mut bytes).await?;
+    let text = String::from_utf8(bytes).map_err(|_| ApiError::BadRequest("The CSV must be UTF-8".to_string()))?;
+    let rows = parse(&text, format)?;
+    let mut conn = db.get().await?;
+    let (checks, valid) = bulk::validate(&rows)?;
+    let mut statuses = TaskStatus::read_all(&mut conn).await?;
+    let mut created_statuses = 0;
+    if format.creates_statuses() {
+        created_statuses = create_missing_statuses(&mut conn, manager.user_id, &mut statuses, &valid).await?;
+    }
+    let mut resolved = Vec::new();
+    let mut ready = Vec::new();
+    for row in &valid {
+        resolved.push(resolve(&mut conn, &statuses, row).await.map(|new_task| ready.push(new_task)));
+    }
+    let outcomes = Task::create_many_with_assignments(&mut conn, Some(manager.user_id), ready).await?.into_iter()
+        .map(|outcome| outcome
+            .map(|(task, user_tasks)| ImportedTask { task: linked(task), assignments: linked_all(user_tasks) })
+            .map_err(ApiError::from))
+        .collect();
+    let response = bulk::respond(checks, bulk::merge(resolved, outcomes));
+    for status in &statuses[statuses.len() - created_statuses..] {
+        events.publish(db.tenant_id, TASK_STATUS_CREATED, status);
+    }
+    for imported in response.results.iter().filter_map(|result| result.item.as_ref()) {
+        events.publish(db.tenant_id, TASK_CREATED, &imported.task);
+        for assignment in &imported.assignments {
+            events.publish(db.tenant_id, ASSIGNMENT_CREATED, assignment);
+        }
+    }
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::FieldError;
+    use tasks_db_lib::test_support::conn;
+
+    fn errors(row: &ImportRow) -> Vec<FieldError> {
+        match row.validate() {
+            Err(ApiError::Validation(errors)) => errors,
+            _ => Vec::new(),
+        }
+    }
+
+    #[rocket::async_test]
+    async fn rows_are_read_by_header_and_checked_one_by_one() {
+        let csv = "\u{feff}Task Name,Due Date,Assignees,Notes\n\
+                   Write the report,2026-11-30,alice@example.com; charlie@example.com,ignored\n\
+                   ,someday,not-an-email,\n\
+                   Too,many,fields,for,the,header\n";
+        let rows = parse(csv, ImportFormat::Csv).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(errors(&rows[0]).is_empty());
+        assert_eq!(rows[0].assignees(), vec!["alice@example.com", "charlie@example.com"]);
+        let fields: Vec<&str> = errors(&rows[1]).iter().map(|error| error.field).collect();
+        assert_eq!(fields, vec!["task_name", "due_date", "assignees"]);
+        assert_eq!(errors(&rows[2])[0].field, "row");
+        assert!(matches!(parse("name,due_date\nx,2026-11-30\n", ImportFormat::Csv), Err(ApiError::BadRequest(_))));
+
+        let mut conn = conn().await;
+        let statuses = TaskStatus::read_all(&mut conn).await.unwrap();
+        let (new_task, assignees) = resolve(&mut conn, &statuses, &rows[0]).await.unwrap();
+        assert_eq!(new_task.due_date, NaiveDate::from_ymd_opt(2026, 11, 30));
+        assert_eq!(assignees, vec![(1, 1), (3, 1)]);
+        let stranger = ImportRow { task_name: "x".to_string(), assignees: Some("nobody@example.com".to_string()), status: Some("Parked".to_string()), ..ImportRow::default() };
+        let fields: Vec<&str> = match resolve(&mut conn, &statuses, &stranger).await {
+            Err(ApiError::Validation(errors)) => errors.iter().map(|error| error.field).collect(),
+            _ => Vec::new(),
+        };
+        assert_eq!(fields, vec!["status", "assignees"]);
+    }
+
+    #[rocket::async_test]
+    async fn trello_and_jira_exports_are_read_in_our_columns() {
+        let trello = "Card ID,Card Name,Card Description,List ID,List Name,Due Date,Members\n\
+                      5f1,Design the logo,,9a,Doing,2026-11-30T17:00:00.000Z,Vera Lee\n";
+        let rows = parse(trello, ImportFormat::Trello).unwrap();
+        assert!(errors(&rows[0]).is_empty());
+        assert_eq!((rows[0].task_name.as_str(), rows[0].status.as_deref()), ("Design the logo", Some("Doing")));
+        assert_eq!(rows[0].due_date(), NaiveDate::from_ymd_opt(2026, 11, 30));
+        assert!(rows[0].assignees().is_empty());
+
+        let jira = "Summary,Issue key,Status,Priority,Assignee,Due date\n\
+                    Fix the login,WEB-1,In Review,Highest,alice@example.com,30/Nov/26 5:00 PM\n\
+                    Tidy the docs,WEB-2,To Do,Lowest,Alice Smith,\n";
+        let rows = parse(jira, ImportFormat::Jira).unwrap();
+        assert!(errors(&rows[0]).is_empty() && errors(&rows[1]).is_empty());
+        assert_eq!((rows[0].priority(), rows[1].priority()), (TaskPriority::Urgent, TaskPriority::Low));
+        assert_eq!(rows[0].due_date(), NaiveDate::from_ymd_opt(2026, 11, 30));
+        assert_eq!((rows[0].assignees(), rows[1].assignees()), (vec!["alice@example.com"], vec![]));
+        assert!(matches!(parse(trello, ImportFormat::Jira), Err(ApiError::BadRequest(_))));
+
+        let mut conn = conn().await;
+        let mut statuses = TaskStatus::read_all(&mut conn).await.unwrap();
+        let count = statuses.len();
+        assert_eq!(create_missing_statuses(&mut conn, 1, &mut statuses, &[&rows[0], &rows[1], &rows[0]]).await.unwrap(), 2);
+        let names: Vec<&str> = statuses[count..].iter().map(|status| status.status_name.as_str()).collect();
+        assert_eq!(names, vec!["In Review", "To Do"]);
+        assert_eq!(resolve(&mut conn, &statuses, &rows[0]).await.unwrap().1, vec![(1, statuses[count].task_status_id)]);
+    }
+}
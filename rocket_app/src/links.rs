@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use rocket::serde::Serialize;
+use chrono::NaiveDateTime;
+use tasks_db_lib::models::{Task, UserTask};
+use tasks_db_lib::versioning::Versioned;
+use crate::api_version::CURRENT_VERSION;
+use crate::conditional::Cacheable;
+
+// Rows that know the URLs of themselves and the resources they point at.
+pub trait HasLinks {
+    fn links(&self) -> BTreeMap<&'static str, String>;
+}
+
+fn href(path: String) -> String {
+    format!("/api/v{}{}", CURRENT_VERSION, path)
+}
+
+impl HasLinks for Task {
+    fn links(&self) -> BTreeMap<&'static str, String> {
+        let mut links = BTreeMap::from([
+            ("self", href(format!("/tasks/{}", self.task_id))),
+            ("assignments", href(format!("/tasks/{}/assignments", self.task_id))),
+            ("history", href(format!("/tasks/{}/history", self.task_id))),
+            ("tags", href(format!("/tasks/{}/tags", self.task_id))),
+            ("comments", href(format!("/tasks/{}/comments", self.task_id))),
+            ("attachments", href(format!("/tasks/{}/attachments", self.task_id))),
+            ("subtasks", href(format!("/tasks/{}/subtasks", self.task_id))),
+            ("dependencies", href(format!("/tasks/{}/dependencies", self.task_id))),
+        ]);
+        if let Some(project_id) = self.project_id {
+            links.insert("project", href(format!("/projects/{}", project_id)));
+        }
+        links
+    }
+}
+
+impl HasLinks for UserTask {
+    fn links(&self) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("self", href(format!("/assignments/{}/{}", self.user_id, self.task_id))),
+            ("user", href(format!("/users/{}", self.user_id))),
+            ("task", href(format!("/tasks/{}", self.task_id))),
+            ("status", href(format!("/tasks_statuses/{}", self.task_status_id))),
+        ])
+    }
+}
+
+// The row's own fields with a `links` object next to them, e.g.
+// {"task_id": 1, "task_name": "...", "links": {"self": "/api/v1/tasks/1", ...}}
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Linked<T> {
+    #[serde(flatten)]
+    pub item: T,
+    pub links: BTreeMap<&'static str, String>,
+}
+
+pub fn linked<T: HasLinks>(item: T) -> Linked<T> {
+    let links = item.links();
+    Linked { item, links }
+}
+
+pub fn linked_all<T: HasLinks>(items: Vec<T>) -> Vec<Linked<T>> {
+    items.into_iter().map(linked).collect()
+}
+
+impl<T: Versioned> Versioned for Linked<T> {
+    fn version(&self) -> i32 {
+        self.item.version()
+    }
+}
+
+impl<T: Cacheable> Cacheable for Linked<T> {
+    fn last_modified(&self) -> NaiveDateTime {
+        self.item.last_modified()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_task() -> UserTask {
+        let now = chrono::Utc::now().naive_utc();
+        UserTask { user_id: 2, task_id: 5, task_status_id: 3, deleted_at: None, created_at: now, updated_at: now, version: 4, tenant_id: 1 }
+    }
+
+    #[test]
+    fn links_sit_next_to_the_rows_own_fields() {
+        let body = rocket::serde::json::serde_json::to_value(linked(user_task())).unwrap();
+        assert_eq!(body["user_id"], 2);
+        assert_eq!(body["version"], 4);
+        assert_eq!(body["links"]["self"], "/api/v1/assignments/2/5");
+        assert_eq!(body["links"]["status"], "/api/v1/tasks_statuses/3");
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::Instant;
+use rocket::{Data, Request, Response};
+use rocket::fairing::{Fairing, Info, Kind};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing::field::Empty;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
+use crate::auth;
+use crate::request_id::RequestId;
+use crate::telemetry::{self, RequestSpan};
+
+// Rocket's own line per request would repeat ours, so its server chatter is held to warnings.
+const DEFAULT_LEVEL: &str = "info,rocket::server=warn";
+
+// Writes everything logged, ours and Rocket's, to stdout as one JSON object per line. LOG_LEVEL
+// is a tracing filter: a level such as `warn`, or directives like `info,rocket=warn` to quieten
+// Rocket while keeping the request lines; a filter that doesn't parse gets the default. The
+// same filter decides which spans are exported (see telemetry.rs); the returned provider is
+// shut down with the server, so the last batch of spans isn't lost.
+pub fn init() -> Option<SdkTracerProvider> {
+    let level = std::env::var("LOG_LEVEL").unwrap_or_else(|_| DEFAULT_LEVEL.to_string());
+    let filter = EnvFilter::try_new(&level).unwrap_or_else(|_| EnvFilter::new(DEFAULT_LEVEL));
+    let (provider, problem) = match telemetry::tracer_provider() {
+        Ok(provider) => (provider, None),
+        Err(problem) => (None, Some(problem)),
+    };
+    // a second call (from tests, say) keeps the first subscriber
+    let _ = tracing_subscriber::registry()
+        .with(provider.as_ref().map(telemetry::layer))
+        .with(tracing_subscriber::fmt::layer().json().with_current_span(false))
+        .with(filter)
+        .try_init();
+    if let Some(problem) = problem {
+        tracing::error!("Tracing export is off: {}", problem);
+    }
+    provider
+}
+
+// When the request arrived, and the path it asked for before any fairing rewrote it.
+struct Arrival {
+    at: Instant,
+    path: String,
+}
+
+// Logs one line per request, at info (warn for 4xx, error for 5xx), with its method, path,
+// status, latency, signed-in user and request ID. Attached early, so the path is the one the
+// client sent and the latency covers the other fairings too. Each request also gets a span,
+// which lasts until the response has been sent and is what a trace starts from.
+pub struct RequestLogging;
+
+#[rocket::async_trait]
+impl Fairing for RequestLogging {
+    fn info(&self) -> Info {
+        Info { name: "Request logging", kind: Kind::Request | Kind::Response }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        let path = req.uri().path().to_string();
+        let method = req.method().as_str();
+        let span = tracing::info_span!(target: "request", "request", otel.name = method, otel.kind = "server",
+            http.request.method = method, url.path = path.as_str(), http.route = Empty,
+            http.response.status_code = Empty, otel.status_code = Empty, request_id = RequestId::of(req));
+        telemetry::continue_trace(&span, req);
+        req.local_cache(|| RequestSpan(span));
+        req.local_cache(|| Arrival { at: Instant::now(), path });
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let arrival = req.local_cache(|| Arrival { at: Instant::now(), path: req.uri().path().to_string() });
+        let method = req.method().as_str();
+        let path = arrival.path.as_str();
+        let status = res.status().code;
+        let latency_ms = arrival.at.elapsed().as_secs_f64() * 1000.0;
+        let user_id = auth::authenticated_user_id(req);
+        let request_id = RequestId::of(req);
+        let span = &req.local_cache(|| RequestSpan(tracing::Span::none())).0;
+        span.record("http.response.status_code", status);
+        if status >= 500 {
+            span.record("otel.status_code", "ERROR");
+        }
+        match status {
+            500.. => tracing::error!(target: "request", method, path, status, latency_ms, user_id, request_id, "request failed"),
+            400..=499 => tracing::warn!(target: "request", method, path, status, latency_ms, user_id, request_id, "request refused"),
+            _ => tracing::info!(target: "request", method, path, status, latency_ms, user_id, request_id, "request"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use rocket::local::blocking::Client;
+    use rocket::{get, routes};
+
+    // Collects what the subscriber writes, to read back as lines.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[get("/missing")]
+    fn missing() -> rocket::http::Status {
+        rocket::http::Status::NotFound
+    }
+
+    #[test]
+    fn each_request_is_one_json_line_leveled_by_its_status() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer(move || writer.clone())
+            .with_env_filter(EnvFilter::new("request=info"))
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let client = Client::tracked(rocket::build().attach(RequestLogging).mount("/", routes![missing])).unwrap();
+            client.get("/missing?secret=1").dispatch();
+        });
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "WARN");
+        assert_eq!(lines[0]["fields"]["path"], "/missing");
+        assert_eq!(lines[0]["fields"]["status"], 404);
+    }
+}
@@ -2,30 +2,234 @@ mod users;
 mod tasks;
 mod statuses;
 mod assignments;
+mod roles;
+mod error;
+mod catchers;
+mod auth;
+mod api_keys;
+mod oauth;
+mod pagination;
+mod validation;
+mod bulk;
+mod trash;
+mod audit;
+mod conditional;
+mod openapi;
+mod api_version;
+mod links;
+mod fields;
+mod stats;
+mod board;
+mod search;
+mod overdue;
+mod tags;
+mod comments;
+mod attachments;
+mod storage;
+mod repository;
+mod dependencies;
+mod recurrence;
+mod templates;
+mod projects;
+mod teams;
+mod tenancy;
+mod watchers;
+mod stars;
+mod activity;
+mod webhooks;
+mod events;
+mod mail;
+mod preferences;
+mod slack;
+mod push;
+mod notifications;
+mod calendar;
+mod export;
+mod import;
+mod backup;
+mod xml;
+mod msgpack;
+mod compression;
+mod cors;
+mod security;
+mod rate_limit;
+mod request_id;
+mod logging;
+mod telemetry;
+mod metrics;
+mod health;
+mod migrations;
+mod seed;
+mod generate;
+mod demo;
+mod shutdown;
 
-use rocket::{self, launch, routes};
-use diesel::r2d2::{self, ConnectionManager};
-use diesel::sqlite::SqliteConnection;
+use anyhow::Context;
+use rocket::{self, catchers, routes, Build, Rocket};
 
 use users::*;
 use tasks::*;
 use statuses::*;
 use assignments::*;
+use roles::*;
+use auth::*;
+use api_keys::*;
+use oauth::*;
+use trash::*;
+use audit::*;
+use stats::*;
+use board::*;
+use search::*;
+use overdue::*;
+use tags::*;
+use comments::*;
+use attachments::*;
+use dependencies::*;
+use recurrence::*;
+use templates::*;
+use projects::*;
+use teams::*;
+use watchers::*;
+use stars::*;
+use activity::*;
+use webhooks::*;
+use events::*;
+use mail::MailConfig;
+use preferences::*;
+use slack::*;
+use push::*;
+use notifications::*;
+use calendar::*;
+use export::*;
+use import::*;
+use backup::*;
+use rate_limit::*;
+use repository::{AssignmentRepository, DieselRepository, TaskRepository};
 
-type DbPool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
-
-#[launch]
-async fn rocket() -> _ {
+// `rocket_app` serves the API; `rocket_app seed` fills the database with sample data instead
+// (see seed.rs), and `rocket_app generate` with lots of made-up data (see generate.rs).
+#[rocket::main]
+async fn main() {
     dotenvy::dotenv().ok();
-    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let manager = ConnectionManager::<SqliteConnection>::new(database_url);
-    let pool = r2d2::Pool::builder().build(manager).expect("Failed to create pool.");
-    rocket::build()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("seed") => seed::run().await.context("Seeding failed"),
+        Some("generate") => match generate::Options::parse(args.into_iter().skip(1)) {
+            Ok(options) => generate::run(options).await.context("Generating failed"),
+            Err(e) => Err(e.context("Usage: rocket_app generate [--users N] [--tasks N] [--seed N]")),
+        },
+        _ => {
+            // a failed launch reports itself when the error is dropped
+            let _ = rocket().await.launch().await;
+            return;
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn rocket() -> Rocket<Build> {
+    let tracer_provider = logging::init();
+    let (database_url, demo_database) = if demo::requested() {
+        let (database_url, demo_database) = demo::open().await;
+        (database_url, Some(demo_database))
+    } else {
+        (std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"), None)
+    };
+    let metrics = metrics::Metrics::default();
+    let pool = tenancy::DbPool::connect(&database_url, metrics.pool_events())
+        .await
+        .expect("Failed to create pool.");
+    // Rocket's own upload limits follow ATTACHMENT_MAX_MB, with room for the rest of the form.
+    let attachment_config = AttachmentConfig::from_env();
+    let figment = rocket::Config::figment()
+        .merge(("limits.file", attachment_config.max_bytes))
+        .merge(("limits.data-form", attachment_config.max_bytes + 1024 * 1024));
+    let rocket_config = rocket::Config::from(&figment);
+    let task_repository: Box<dyn TaskRepository> = Box::new(DieselRepository::new(pool.clone()));
+    let assignment_repository: Box<dyn AssignmentRepository> = Box::new(DieselRepository::new(pool.clone()));
+    rocket::custom(figment)
         .manage(pool)
-        .mount("/api", routes![  //   /api/users
-            get_users, get_user, create_user, update_user, delete_user,
-            get_tasks, get_task, create_task, update_task, delete_task,
-            get_task_statuses, get_task_status, create_task_status, update_task_status, delete_task_status,
-            get_user_tasks, get_user_task, create_user_task, update_user_task, delete_user_task
+        .manage(demo_database)
+        .manage(task_repository)
+        .manage(assignment_repository)
+        .manage(RateLimitConfig::from_env())
+        .manage(RateLimiter::default())
+        .manage(metrics)
+        .manage(shutdown::Drain::default())
+        .manage(AuthConfig::from_env())
+        .manage(OAuthConfig::from_env(&rocket_config))
+        .manage(PendingLogins::default())
+        .manage(TrashConfig::from_env())
+        .manage(OverdueConfig::from_env())
+        .manage(OverdueTracker::default())
+        .manage(RecurrenceConfig::from_env())
+        .manage(WebhookConfig::from_env())
+        .manage(EventBus::default())
+        .manage(MailConfig::from_env())
+        .manage(SlackConfig::from_env())
+        .manage(PushConfig::from_env())
+        .manage(attachment_config)
+        .manage(storage::from_env())
+        .attach(migrations::fairing(database_url))
+        .attach(request_id::RequestIds)
+        .attach(shutdown::GracefulShutdown)
+        .attach(logging::RequestLogging)
+        .attach(metrics::RequestMetrics)
+        .attach(openapi::fairing())
+        .attach(api_version::ApiVersioning)
+        .attach(rate_limit::RateLimiting)
+        .attach(xml::XmlNegotiation)
+        .attach(roles::fairing())
+        .attach(overdue::fairing())
+        .attach(recurrence::fairing())
+        .attach(webhooks::fairing())
+        .attach(mail::fairing())
+        .attach(slack::fairing())
+        .attach(push::fairing())
+        .attach(cors::Cors(cors::CorsConfig::from_env()))
+        .attach(security::shield())
+        .attach(security::SecurityHeaders::from_env())
+        .attach(compression::Compression::from_env())
+        .attach(telemetry::shutdown(tracer_provider))
+        .mount("/api/v1", telemetry::traced(routes![  //   /api/v1/users
+            get_users, count_users, get_user, create_user, update_user, delete_user, update_user_role, reset_user_password,
+            get_roles,
+            get_tasks, count_tasks, get_task, create_task, update_task, delete_task, restore_task, get_task_history, revert_task, get_subtasks, clone_task, import_tasks,
+            pause_recurrence, resume_recurrence,
+            get_projects, get_project, create_project, update_project, delete_project, get_project_tasks, get_project_board,
+            get_slack_integration, put_slack_integration, delete_slack_integration,
+            get_teams, get_team, create_team, update_team, delete_team, get_team_members, add_team_members, remove_team_member,
+            get_task_templates, get_task_template, create_task_template, update_task_template, delete_task_template, create_task_from_template,
+            get_task_dependencies, add_task_dependency, remove_task_dependency,
+            watch_task, unwatch_task,
+            star_task, unstar_task, get_starred_tasks,
+            get_tags, get_tag, create_tag, update_tag, delete_tag, get_task_tags, tag_task, untag_task,
+            get_task_comments, create_comment, update_comment, delete_comment, get_comment_history, get_user_mentions,
+            get_task_attachments, upload_attachment, download_attachment, delete_attachment,
+            get_task_statuses, count_task_statuses, get_task_status, create_task_status, update_task_status, patch_task_status, delete_task_status, restore_task_status,
+            get_user_tasks, count_user_tasks, get_assignment_details, export_assignments_csv, export_assignments_ndjson, get_overdue_assignments, get_user_assignments, get_task_assignments, get_user_task, create_user_task, update_user_task, patch_user_task, upsert_user_task, delete_user_task, restore_user_task,
+            bulk_create_user_tasks, bulk_update_user_tasks, bulk_delete_user_tasks,
+            register, login, refresh_token, logout, me, oauth_login, oauth_callback,
+            get_api_keys, create_api_key, revoke_api_key, update_api_key_quotas, get_api_key_usage,
+            get_webhooks, create_webhook, delete_webhook,
+            get_notification_preferences, update_notification_preferences,
+            get_notifications, count_unread_notifications, read_notification,
+            get_push_public_key, get_push_subscriptions, create_push_subscription, delete_push_subscription,
+            get_calendar, create_calendar_feed, delete_calendar_feed,
+            get_trash, purge_trash,
+            get_audit_log,
+            export_backup, import_backup,
+            get_activity,
+            stream_events,
+            get_assignments_by_status, get_workload,
+            get_board, board_socket,
+            search_tasks, suggest_tasks
+        ]))
+        .mount("/", routes![openapi::openapi_json, openapi::swagger_ui, metrics::get_metrics, health::get_liveness, health::get_readiness])
+        .register("/", catchers![
+            catchers::not_found, catchers::unprocessable_entity, catchers::internal_error, catchers::default_catcher
         ])
-}
\ No newline at end of file
+}
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use rocket::{Data, Request, Response, State, get};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::ContentType;
+use crate::tenancy::DbPool;
+
+// Upper bounds of the histograms' buckets, in seconds: request latencies, and waits for a
+// pooled connection, which should be far shorter.
+const REQUEST_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+const CHECKOUT_BUCKETS: &[f64] = &[0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+// Requests no route matched share one label, so scanners can't add a series per made-up path.
+const UNMATCHED: &str = "unmatched";
+const DEFAULT_SLOW_CHECKOUT_MS: u64 = 100;
+
+struct Histogram {
+    bounds: &'static [f64],
+    // observations at or under each bound, cumulative as Prometheus wants them
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Histogram {
+        Histogram { bounds, buckets: vec![0; bounds.len()], sum: 0.0, count: 0 }
+    }
+
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(self.bounds) {
+            if seconds <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    // `labels` is empty or ends in a comma.
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        for (bound, count) in self.bounds.iter().zip(&self.buckets) {
+            let _ = writeln!(out, "{}_bucket{{{}le=\"{}\"}} {}", name, labels, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{{}le=\"+Inf\"}} {}", name, labels, self.count);
+        let labels = match labels.trim_end_matches(',') {
+            "" => String::new(),
+            labels => format!("{{{}}}", labels),
+        };
+        let _ = writeln!(out, "{}_sum{} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, labels, self.count);
+    }
+}
+
+// What DbPool::get reports about its checkouts. Connections opened and closed come from the
+// pool's own statistics.
+struct PoolStats {
+    checkout_wait: Mutex<Histogram>,
+    timeouts: AtomicU64,
+}
+
+impl Default for PoolStats {
+    fn default() -> PoolStats {
+        PoolStats {
+            checkout_wait: Mutex::new(Histogram::new(CHECKOUT_BUCKETS)),
+            timeouts: AtomicU64::new(0),
+        }
+    }
+}
+
+// Handed to DbPool::connect: records every checkout's wait and every timeout into the Metrics it
+// came from, and warns about checkouts slower than DB_POOL_SLOW_CHECKOUT_MS, which mean
+// requests are queueing for a connection.
+#[derive(Clone)]
+pub struct PoolEvents {
+    stats: Arc<PoolStats>,
+    slow_checkout: Duration,
+}
+
+impl PoolEvents {
+    pub fn checked_out(&self, waited: Duration) {
+        self.stats.checkout_wait.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).observe(waited.as_secs_f64());
+        if waited >= self.slow_checkout {
+            tracing::warn!(target: "db", waited_ms = waited.as_millis() as u64, "Slow database connection checkout");
+        }
+    }
+
+    pub fn timed_out(&self, waited: Duration) {
+        self.stats.timeouts.fetch_add(1, Ordering::Relaxed);
+        tracing::error!(target: "db", waited_ms = waited.as_millis() as u64, "No database connection became free in time");
+    }
+}
+
+#[derive(Default)]
+struct Collected {
+    // (method, route, status) -> requests
+    requests: BTreeMap<(String, String, u16), u64>,
+    // (method, route) -> how long they took
+    durations: BTreeMap<(String, String), Histogram>,
+}
+
+// Request counts and latencies per route since the server started, kept in managed state and
+// read by GET /metrics. Routes are labelled by their template (/api/v1/tasks/<id>), not the path.
+#[derive(Default)]
+pub struct Metrics {
+    collected: Mutex<Collected>,
+    pool: Arc<PoolStats>,
+}
+
+impl Metrics {
+    pub fn pool_events(&self) -> PoolEvents {
+        let slow_checkout_ms = std::env::var("DB_POOL_SLOW_CHECKOUT_MS")
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .unwrap_or(DEFAULT_SLOW_CHECKOUT_MS);
+        PoolEvents { stats: self.pool.clone(), slow_checkout: Duration::from_millis(slow_checkout_ms) }
+    }
+
+    fn observe(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        let mut collected = self.collected.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *collected.requests.entry((method.to_string(), route.to_string(), status)).or_default() += 1;
+        collected.durations.entry((method.to_string(), route.to_string()))
+            .or_insert_with(|| Histogram::new(REQUEST_BUCKETS))
+            .observe(seconds);
+    }
+
+    // The Prometheus text exposition format. Error rates come from the status label, e.g.
+    // rate(http_requests_total{status=~"5.."}[5m]).
+    fn render(&self, pool: Option<&DbPool>) -> String {
+        let collected = self.collected.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut out = String::new();
+        out.push_str("# HELP http_requests_total Requests answered, by method, route and status.\n");
+        out.push_str("# TYPE http_requests_total counter\n");
+        for ((method, route, status), count) in &collected.requests {
+            let _ = writeln!(out, "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}", label(method), label(route), status, count);
+        }
+        out.push_str("# HELP http_request_duration_seconds How long requests took to answer, by method and route.\n");
+        out.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for ((method, route), histogram) in &collected.durations {
+            let labels = format!("method=\"{}\",route=\"{}\",", label(method), label(route));
+            histogram.write(&mut out, "http_request_duration_seconds", &labels);
+        }
+        if let Some(pool) = pool {
+            let state = pool.state();
+            out.push_str("# HELP db_pool_connections Database connections the pool holds, by whether a request has one.\n");
+            out.push_str("# TYPE db_pool_connections gauge\n");
+            let _ = writeln!(out, "db_pool_connections{{state=\"idle\"}} {}", state.idle_connections);
+            let _ = writeln!(out, "db_pool_connections{{state=\"in_use\"}} {}", state.connections - state.idle_connections);
+            out.push_str("# HELP db_pool_max_connections The most connections the pool will open.\n");
+            out.push_str("# TYPE db_pool_max_connections gauge\n");
+            let _ = writeln!(out, "db_pool_max_connections {}", pool.max_size());
+            let statistics = state.statistics;
+            let closed = statistics.connections_closed_broken + statistics.connections_closed_invalid
+                + statistics.connections_closed_max_lifetime + statistics.connections_closed_idle_timeout;
+            out.push_str("# HELP db_pool_connections_opened_total Connections the pool has opened.\n");
+            out.push_str("# TYPE db_pool_connections_opened_total counter\n");
+            let _ = writeln!(out, "db_pool_connections_opened_total {}", statistics.connections_created);
+            out.push_str("# HELP db_pool_connections_closed_total Connections the pool has closed.\n");
+            out.push_str("# TYPE db_pool_connections_closed_total counter\n");
+            let _ = writeln!(out, "db_pool_connections_closed_total {}", closed);
+        }
+        out.push_str("# HELP db_pool_checkout_wait_seconds How long requests waited for a pooled connection.\n");
+        out.push_str("# TYPE db_pool_checkout_wait_seconds histogram\n");
+        self.pool.checkout_wait.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).write(&mut out, "db_pool_checkout_wait_seconds", "");
+        out.push_str("# HELP db_pool_checkout_timeouts_total Checkouts that gave up waiting for a connection.\n");
+        out.push_str("# TYPE db_pool_checkout_timeouts_total counter\n");
+        let _ = writeln!(out, "db_pool_checkout_timeouts_total {}", self.pool.timeouts.load(Ordering::Relaxed));
+        out
+    }
+}
+
+// Label values are quoted, so backslashes, quotes and newlines in them are escaped.
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+struct Started(Instant);
+
+// Counts and times every request into the Metrics in managed state.
+pub struct RequestMetrics;
+
+#[rocket::async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        Info { name: "Request metrics", kind: Kind::Request | Kind::Response }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        req.local_cache(|| Started(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(metrics) = req.rocket().state::<Metrics>() else {
+            return;
+        };
+        let seconds = req.local_cache(|| Started(Instant::now())).0.elapsed().as_secs_f64();
+        let route = req.route().map(|route| route.uri.to_string()).unwrap_or_else(|| UNMATCHED.to_string());
+        metrics.observe(req.method().as_str(), &route, res.status().code, seconds);
+    }
+}
+
+#[get("/metrics")]
+pub async fn get_metrics(metrics: &State<Metrics>, pool: &State<DbPool>) -> (ContentType, String) {
+    let text_format = ContentType::new("text", "plain").with_params([("version", "0.0.4"), ("charset", "utf-8")]);
+    (text_format, metrics.render(Some(pool)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_counted_and_bucketed_per_route() {
+        let metrics = Metrics::default();
+        metrics.observe("GET", "/api/v1/tasks/<id>", 200, 0.003);
+        metrics.observe("GET", "/api/v1/tasks/<id>", 200, 0.2);
+        metrics.observe("GET", "/api/v1/tasks/<id>", 404, 0.004);
+        metrics.observe("POST", "/say \"hi\"", 500, 20.0);
+        let text = metrics.render(None);
+
+        assert!(text.contains("http_requests_total{method=\"GET\",route=\"/api/v1/tasks/<id>\",status=\"200\"} 2\n"));
+        assert!(text.contains("http_requests_total{method=\"GET\",route=\"/api/v1/tasks/<id>\",status=\"404\"} 1\n"));
+        assert!(text.contains("http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/v1/tasks/<id>\",le=\"0.005\"} 2\n"));
+        assert!(text.contains("http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/v1/tasks/<id>\",le=\"0.25\"} 3\n"));
+        assert!(text.contains("http_request_duration_seconds_count{method=\"GET\",route=\"/api/v1/tasks/<id>\"} 3\n"));
+        assert!(text.contains("http_request_duration_seconds_bucket{method=\"POST\",route=\"/say \\\"hi\\\"\",le=\"10\"} 0\n"));
+        assert!(text.contains("http_request_duration_seconds_bucket{method=\"POST\",route=\"/say \\\"hi\\\"\",le=\"+Inf\"} 1\n"));
+        assert!(text.contains("http_request_duration_seconds_sum{method=\"POST\",route=\"/say \\\"hi\\\"\"} 20\n"));
+
+        let events = metrics.pool_events();
+        events.checked_out(Duration::from_millis(2));
+        events.timed_out(Duration::from_secs(30));
+        let text = metrics.render(None);
+        assert!(text.contains("db_pool_checkout_wait_seconds_bucket{le=\"0.001\"} 0\n"));
+        assert!(text.contains("db_pool_checkout_wait_seconds_bucket{le=\"0.005\"} 1\n"));
+        assert!(text.contains("db_pool_checkout_wait_seconds_count 1\n"));
+        assert!(text.contains("db_pool_checkout_timeouts_total 1\n"));
+    }
+}
@@ -0,0 +1,31 @@
+use rocket::fairing::AdHoc;
+use tasks_db_lib::migrations;
+
+// Brings the database up to date before the server takes requests, with the migrations built
+// into the binary, so there's no separate `diesel migration run` to forget. A failure stops
+// the launch. MIGRATE_ON_STARTUP=false leaves it to the operator; /health/ready then reports
+// whatever is still pending.
+pub fn fairing(database_url: String) -> AdHoc {
+    AdHoc::try_on_ignite("Migrations", |rocket| Box::pin(async move {
+        if !enabled() {
+            return Ok(rocket);
+        }
+        match migrations::run_pending(&database_url).await {
+            Ok(versions) => {
+                for version in versions {
+                    tracing::info!(target: "migrations", version, "Ran migration");
+                }
+                Ok(rocket)
+            }
+            Err(e) => {
+                tracing::error!(target: "migrations", "Migrating the database failed: {:?}", e);
+                Err(rocket)
+            }
+        }
+    }))
+}
+
+// Migrations run unless MIGRATE_ON_STARTUP=false; the seed command goes by it too.
+pub fn enabled() -> bool {
+    std::env::var("MIGRATE_ON_STARTUP").map_or(true, |migrate| migrate != "false")
+}
@@ -0,0 +1,120 @@
+use std::ops::Deref;
+use rocket::{Data, Request};
+use rocket::data::{self, FromData};
+use rocket::http::{ContentType, Status};
+use rocket::outcome::Outcome;
+use rocket::response::{self, Responder};
+use rocket::serde::{DeserializeOwned, Serialize};
+use rocket::serde::json::Json;
+use rocket::serde::msgpack::{self, MsgPack};
+use crate::error::{ApiError, GuardFailure};
+
+// Internal services call the assignment endpoints often enough that JSON's encoding shows up,
+// so those endpoints also speak MessagePack: a Payload body is read as MessagePack when its
+// Content-Type is application/msgpack, and a Negotiated response is written as MessagePack when
+// Accept prefers it. Everything else is JSON, as before. Maps keep their field names, so a
+// MessagePack document has the same shape as the JSON one. Errors are still problem+json.
+
+fn wants_msgpack(req: &Request<'_>) -> bool {
+    req.accept().is_some_and(|accept| accept.preferred().media_type() == ContentType::MsgPack.media_type())
+}
+
+// A request body in JSON or MessagePack; derefs to T like Json<T> does.
+pub struct Payload<T>(pub T);
+
+impl<T> Deref for Payload<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for Payload<T> {
+    type Error = ApiError;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        if req.content_type() != Some(&ContentType::MsgPack) {
+            return match Json::<T>::from_data(req, data).await {
+                Outcome::Success(Json(body)) => Outcome::Success(Payload(body)),
+                Outcome::Error((status, e)) => Outcome::Error((status, ApiError::BadRequest(e.to_string()))),
+                Outcome::Forward(forward) => Outcome::Forward(forward),
+            };
+        }
+        match MsgPack::<T>::from_data(req, data).await {
+            Outcome::Success(MsgPack(body)) => Outcome::Success(Payload(body)),
+            Outcome::Error((status, e)) => {
+                let message = format!("The MessagePack body can't be read: {}", e);
+                req.local_cache(|| GuardFailure(Some(message.clone())));
+                Outcome::Error((status, ApiError::BadRequest(message)))
+            }
+            Outcome::Forward(forward) => Outcome::Forward(forward),
+        }
+    }
+}
+
+// A response body in whichever of JSON and MessagePack the client prefers.
+pub struct Negotiated<T>(pub T);
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Negotiated<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        if !wants_msgpack(req) {
+            return Json(self.0).respond_to(req);
+        }
+        let bytes = msgpack::to_vec(&self.0).map_err(|e| {
+            tracing::error!("MessagePack response failed: {}", e);
+            Status::InternalServerError
+        })?;
+        (ContentType::MsgPack, bytes).respond_to(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::{Accept, Status};
+    use rocket::local::blocking::Client;
+    use rocket::serde::Deserialize;
+    use rocket::{catchers, post, routes};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(crate = "rocket::serde")]
+    struct Move {
+        user_id: i32,
+        task_status_id: i32,
+    }
+
+    #[post("/echo", data = "<body>")]
+    fn echo(body: Payload<Move>) -> Negotiated<Move> {
+        Negotiated(body.0)
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .mount("/", routes![echo])
+            .register("/", catchers![crate::catchers::default_catcher]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    #[test]
+    fn either_format_goes_in_and_the_preferred_one_comes_out() {
+        let client = client();
+        let sent = Move { user_id: 2, task_status_id: 3 };
+        let response = client.post("/echo").header(ContentType::MsgPack).body(msgpack::to_vec(&sent).unwrap()).dispatch();
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+        assert_eq!(response.into_json::<Move>().unwrap(), sent);
+
+        let response = client.post("/echo").header(ContentType::JSON).header(Accept::MsgPack).body(r#"{"user_id":2,"task_status_id":3}"#).dispatch();
+        assert_eq!(response.content_type(), Some(ContentType::MsgPack));
+        assert_eq!(msgpack::from_slice::<Move>(&response.into_bytes().unwrap()).unwrap(), sent);
+    }
+
+    #[test]
+    fn an_unreadable_body_is_refused_with_the_reason() {
+        let client = client();
+        let response = client.post("/echo").header(ContentType::MsgPack).body([0xc1]).dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert!(response.into_string().unwrap().contains("The MessagePack body can't be read"));
+    }
+}
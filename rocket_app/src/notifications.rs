@@ -0,0 +1,33 @@
+use rocket::{serde::json::Json, get, post};
+use tasks_db_lib::models::Notification;
+use tasks_db_lib::pagination::Page;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::AuthenticatedUser;
+use crate::pagination::{Count, PageQuery};
+
+// The caller's in-app inbox, newest first: their own assignments being made, moved or taken
+// away, changes on tasks they watch, and comments that mention them. ?unread=true leaves out
+// the ones already read.
+#[get("/users/me/notifications?<unread>&<paging..>")]
+pub async fn get_notifications(db: TenantDb, auth: AuthenticatedUser, unread: Option<bool>, paging: PageQuery) -> Result<Json<Page<Notification>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let mut conn = db.get().await?;
+    Ok(Json(Notification::read_page_for_user(&mut conn, auth.user_id, unread.unwrap_or(false), page, per_page).await?))
+}
+
+// For a badge; cheaper than fetching the unread page.
+#[get("/users/me/notifications/unread_count")]
+pub async fn count_unread_notifications(db: TenantDb, auth: AuthenticatedUser) -> Result<Json<Count>, ApiError> {
+    let mut conn = db.get().await?;
+    Ok(Json(Count { count: Notification::count_unread(&mut conn, auth.user_id).await? }))
+}
+
+// Marking a notification read again is harmless and keeps the time it was first read.
+#[post("/notifications/<id>/read")]
+pub async fn read_notification(id: i32, db: TenantDb, auth: AuthenticatedUser) -> Result<Json<Notification>, ApiError> {
+    let mut conn = db.get().await?;
+    Notification::mark_read(&mut conn, id, auth.user_id).await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("Notification"))
+}
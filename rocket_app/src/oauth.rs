@@ -0,0 +1,302 @@
+use rocket::{serde::json::Json, State, get};
+use rocket::response::Redirect;
+use rocket::serde::Deserialize;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tasks_db_lib::models::OAuthIdentity;
+use tasks_db_lib::tenancy;
+use crate::error::ApiError;
+use crate::auth::{AuthConfig, TokenResponse};
+use crate::tenancy::DbPool;
+use crate::api_version::CURRENT_VERSION;
+
+// How long a user has to finish signing in at the provider.
+const PENDING_LOGIN_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    GitHub,
+    Google,
+}
+
+pub struct OAuthProvider {
+    pub kind: ProviderKind,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorize_url: &'static str,
+    pub token_url: &'static str,
+    pub scopes: &'static str,
+}
+
+// Providers are only enabled when their client id and secret are set, e.g.
+// GITHUB_CLIENT_ID / GITHUB_CLIENT_SECRET or GOOGLE_CLIENT_ID / GOOGLE_CLIENT_SECRET.
+// OAUTH_REDIRECT_BASE is the public URL the /api/v1 routes are mounted on. Without it, it's
+// taken from Rocket's own address and port, which only suits local development, so it must
+// be set when Rocket listens on every interface (as the release profile does).
+pub struct OAuthConfig {
+    pub providers: HashMap<&'static str, OAuthProvider>,
+    pub redirect_base: String,
+    pub http: reqwest::Client,
+}
+
+impl OAuthConfig {
+    pub fn from_env(rocket_config: &rocket::Config) -> OAuthConfig {
+        let mut providers = HashMap::new();
+        if let (Ok(client_id), Ok(client_secret)) = (std::env::var("GITHUB_CLIENT_ID"), std::env::var("GITHUB_CLIENT_SECRET")) {
+            providers.insert("github", OAuthProvider {
+                kind: ProviderKind::GitHub,
+                client_id,
+                client_secret,
+                authorize_url: "https://github.com/login/oauth/authorize",
+                token_url: "https://github.com/login/oauth/access_token",
+                scopes: "read:user user:email",
+            });
+        }
+        if let (Ok(client_id), Ok(client_secret)) = (std::env::var("GOOGLE_CLIENT_ID"), std::env::var("GOOGLE_CLIENT_SECRET")) {
+            providers.insert("google", OAuthProvider {
+                kind: ProviderKind::Google,
+                client_id,
+                client_secret,
+                authorize_url: "https://accounts.google.com/o/oauth2/v2/auth",
+                token_url: "https://oauth2.googleapis.com/token",
+                scopes: "openid email profile",
+            });
+        }
+        let redirect_base = match std::env::var("OAUTH_REDIRECT_BASE") {
+            Ok(base) => base.trim_end_matches('/').to_string(),
+            Err(_) if !providers.is_empty() && rocket_config.address.is_unspecified() => {
+                panic!("OAUTH_REDIRECT_BASE must be set when Rocket listens on {}", rocket_config.address)
+            }
+            Err(_) => local_redirect_base(rocket_config),
+        };
+        let http = reqwest::Client::builder()
+            .user_agent("rocket_app")
+            .build()
+            .expect("Failed to build HTTP client.");
+        OAuthConfig { providers, redirect_base, http }
+    }
+
+    fn provider(&self, name: &str) -> Result<&OAuthProvider, ApiError> {
+        self.providers.get(name).ok_or_else(|| ApiError::NotFound(format!("OAuth provider '{}' is not configured", name)))
+    }
+
+    fn redirect_uri(&self, provider: &str) -> String {
+        format!("{}/oauth/{}/callback", self.redirect_base, provider)
+    }
+}
+
+fn local_redirect_base(rocket_config: &rocket::Config) -> String {
+    let address = std::net::SocketAddr::new(rocket_config.address, rocket_config.port);
+    format!("http://{}/api/v{}", address, CURRENT_VERSION)
+}
+
+// state -> PKCE verifier for logins that have been started but not finished.
+struct PendingLogin {
+    provider: String,
+    code_verifier: String,
+    started: Instant,
+}
+
+#[derive(Default)]
+pub struct PendingLogins(Mutex<HashMap<String, PendingLogin>>);
+
+impl PendingLogins {
+    fn insert(&self, state: String, login: PendingLogin) {
+        let mut pending = self.0.lock().expect("pending logins lock");
+        pending.retain(|_, login| login.started.elapsed() < PENDING_LOGIN_TTL);
+        pending.insert(state, login);
+    }
+
+    // A state can only be used once.
+    fn take(&self, state: &str, provider: &str) -> Option<PendingLogin> {
+        let login = self.0.lock().expect("pending logins lock").remove(state)?;
+        (login.provider == provider && login.started.elapsed() < PENDING_LOGIN_TTL).then_some(login)
+    }
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+// Step 1: send the browser to the provider's consent page.
+#[get("/oauth/<provider>/login")]
+pub async fn oauth_login(provider: &str, config: &State<OAuthConfig>, pending: &State<PendingLogins>) -> Result<Redirect, ApiError> {
+    let details = config.provider(provider)?;
+    let state = random_token();
+    let code_verifier = random_token();
+    let url = reqwest::Url::parse_with_params(details.authorize_url, &[
+        ("response_type", "code"),
+        ("client_id", details.client_id.as_str()),
+        ("redirect_uri", config.redirect_uri(provider).as_str()),
+        ("scope", details.scopes),
+        ("state", state.as_str()),
+        ("code_challenge", code_challenge(&code_verifier).as_str()),
+        ("code_challenge_method", "S256"),
+    ]).map_err(|e| ApiError::Internal(e.to_string()))?;
+    pending.insert(state, PendingLogin { provider: provider.to_string(), code_verifier, started: Instant::now() });
+    Ok(Redirect::to(url.to_string()))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct ProviderToken {
+    access_token: String,
+}
+
+// The bits of the provider's profile we need to find or create a local user.
+struct ProviderProfile {
+    subject: String,
+    email: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct GitHubUser {
+    id: i64,
+    login: String,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct GitHubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct GoogleUser {
+    sub: String,
+    email: String,
+    email_verified: bool,
+    name: Option<String>,
+}
+
+fn provider_error(e: reqwest::Error) -> ApiError {
+    ApiError::BadRequest(format!("OAuth provider request failed: {}", e))
+}
+
+async fn exchange_code(config: &OAuthConfig, provider: &str, details: &OAuthProvider, code: &str, code_verifier: &str) -> Result<String, ApiError> {
+    let redirect_uri = config.redirect_uri(provider);
+    let token = config.http.post(details.token_url)
+        .header("Accept", "application/json")
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("client_id", details.client_id.as_str()),
+            ("client_secret", details.client_secret.as_str()),
+            ("code_verifier", code_verifier),
+        ])
+        .send().await.map_err(provider_error)?
+        .error_for_status().map_err(provider_error)?
+        .json::<ProviderToken>().await.map_err(provider_error)?;
+    Ok(token.access_token)
+}
+
+async fn fetch_profile(config: &OAuthConfig, kind: ProviderKind, access_token: &str) -> Result<ProviderProfile, ApiError> {
+    match kind {
+        ProviderKind::GitHub => {
+            let user = config.http.get("https://api.github.com/user")
+                .bearer_auth(access_token)
+                .send().await.map_err(provider_error)?
+                .error_for_status().map_err(provider_error)?
+                .json::<GitHubUser>().await.map_err(provider_error)?;
+            // the public profile email is optional, so ask for the verified primary address
+            let emails = config.http.get("https://api.github.com/user/emails")
+                .bearer_auth(access_token)
+                .send().await.map_err(provider_error)?
+                .error_for_status().map_err(provider_error)?
+                .json::<Vec<GitHubEmail>>().await.map_err(provider_error)?;
+            let email = emails.into_iter()
+                .find(|e| e.primary && e.verified)
+                .ok_or_else(|| ApiError::BadRequest("GitHub account has no verified primary email".to_string()))?;
+            Ok(ProviderProfile { subject: user.id.to_string(), email: email.email, name: user.name.unwrap_or(user.login) })
+        }
+        ProviderKind::Google => {
+            let user = config.http.get("https://openidconnect.googleapis.com/v1/userinfo")
+                .bearer_auth(access_token)
+                .send().await.map_err(provider_error)?
+                .error_for_status().map_err(provider_error)?
+                .json::<GoogleUser>().await.map_err(provider_error)?;
+            if !user.email_verified {
+                return Err(ApiError::BadRequest("Google account email is not verified".to_string()));
+            }
+            let name = user.name.unwrap_or_else(|| user.email.clone());
+            Ok(ProviderProfile { subject: user.sub, email: user.email, name })
+        }
+    }
+}
+
+// Step 2: the provider redirects back here; swap the code for a profile and issue our own JWT.
+#[get("/oauth/<provider>/callback?<code>&<state>")]
+pub async fn oauth_callback(provider: &str, code: &str, state: &str, pool: &State<DbPool>, auth_config: &State<AuthConfig>, config: &State<OAuthConfig>, pending: &State<PendingLogins>) -> Result<Json<TokenResponse>, ApiError> {
+    let details = config.provider(provider)?;
+    let login = pending.take(state, provider)
+        .ok_or_else(|| ApiError::BadRequest("Unknown or expired OAuth state".to_string()))?;
+    let access_token = exchange_code(config, provider, details, code, &login.code_verifier).await?;
+    let profile = fetch_profile(config, details.kind, &access_token).await?;
+    // accounts seen for the first time join the default tenant, as with self-registration
+    let mut conn = pool.get_in_tenant(tenancy::DEFAULT_TENANT).await?;
+    let user = OAuthIdentity::find_or_link_user(&mut conn, provider, &profile.subject, &profile.email, &profile.name).await?;
+    if !user.active {
+        return Err(ApiError::Unauthorized("Account is inactive".to_string()));
+    }
+    Ok(Json(auth_config.token_response(&mut conn, &user).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(provider: &str) -> PendingLogin {
+        PendingLogin { provider: provider.to_string(), code_verifier: "verifier".to_string(), started: Instant::now() }
+    }
+
+    #[test]
+    fn the_code_challenge_is_the_s256_of_the_verifier() {
+        // the example from RFC 7636, appendix B
+        assert_eq!(code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"), "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    }
+
+    #[test]
+    fn a_login_state_is_good_once_and_for_its_provider() {
+        let logins = PendingLogins::default();
+        logins.insert("one".to_string(), pending("github"));
+        logins.insert("two".to_string(), pending("github"));
+        assert_eq!(logins.take("one", "github").unwrap().code_verifier, "verifier");
+        assert!(logins.take("one", "github").is_none());
+        assert!(logins.take("two", "google").is_none());
+        assert!(logins.take("two", "github").is_none());
+    }
+
+    #[test]
+    fn an_expired_login_state_is_refused() {
+        let logins = PendingLogins::default();
+        let started = Instant::now() - PENDING_LOGIN_TTL - Duration::from_secs(1);
+        logins.insert("stale".to_string(), PendingLogin { started, ..pending("github") });
+        assert!(logins.take("stale", "github").is_none());
+    }
+
+    #[test]
+    fn redirect_base_defaults_to_rockets_own_address() {
+        let config = rocket::Config { port: 8081, ..rocket::Config::debug_default() };
+        assert_eq!(local_redirect_base(&config), "http://127.0.0.1:8081/api/v1");
+        let config = rocket::Config { address: std::net::Ipv6Addr::LOCALHOST.into(), ..config };
+        assert_eq!(local_redirect_base(&config), "http://[::1]:8081/api/v1");
+    }
+}
@@ -0,0 +1,924 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use rocket::{get, State, Route, Responder};
+use rocket::http::{Header, Method};
+use rocket::fairing::AdHoc;
+use rocket::response::content::RawHtml;
+use rocket::serde::json::Json;
+use rocket::serde::json::serde_json::{json, Map, Value};
+use chrono::{NaiveDate, NaiveDateTime};
+use tasks_db_lib::enums::TaskPriority;
+use tasks_db_lib::models::{AssignmentDetail, Attachment, CalendarFeed, Comment, CommentRevision, Mention, Notification, NotificationPreference, Project, PushSubscription, Role, SlackIntegration, StarredTask, Tag, Task, TaskStatus, TaskWatcher, Team, User, UserTask, Webhook};
+use tasks_db_lib::activity::Activity;
+use tasks_db_lib::pagination::{CursorPage, Page};
+use tasks_db_lib::revisions::AssignmentSnapshot;
+use tasks_db_lib::stats::{StatusCount, UserWorkload};
+use tasks_db_lib::board::{Assignee, BoardCard, BoardColumn};
+use tasks_db_lib::search::{SearchHit, Suggestion};
+use tasks_db_lib::overdue::OverdueAssignment;
+use tasks_db_lib::backup::Backup;
+use crate::assignments::{AssignmentKey, ExpandedUserTask, UserTaskInput, UserTaskPatch};
+use crate::bulk::{BulkItemResult, BulkResponse};
+use crate::error::ProblemDetails;
+use crate::pagination::Count;
+use crate::fields::Sparse;
+use crate::links::Linked;
+use crate::overdue::OverdueReport;
+use crate::statuses::{TaskStatusInput, TaskStatusPatch};
+use crate::tags::{TagInput, TaskTagsInput};
+use crate::comments::{CommentInput, CommentView};
+use crate::attachments::AttachmentUpload;
+use crate::import::{CsvUpload, ImportFormat, ImportedTask};
+use crate::dependencies::{DependencyInput, TaskDependencies};
+use crate::tasks::{SubtaskList, TaskInput, TaskRevisionView};
+use crate::templates::{TaskTemplateInput, TaskTemplateView};
+use crate::projects::ProjectInput;
+use crate::teams::{TeamInput, TeamMembersInput};
+use crate::users::{PasswordInput, RoleInput, UserInput};
+use crate::webhooks::{CreatedWebhook, WebhookInput};
+use crate::slack::SlackIntegrationInput;
+use crate::preferences::NotificationPreferenceInput;
+use crate::calendar::CreatedCalendarFeed;
+use crate::backup::RestoredBackup;
+use crate::push::{PushSubscriptionInput, PushSubscriptionKeys, VapidPublicKey};
+use crate::validation::FieldError;
+
+// The OpenAPI document is built once at ignite from the routes Rocket actually mounted, so
+// paths, methods and parameters can't drift from the handlers. Schemas come from the
+// `schemas!` list below, which fails to compile if a listed struct gains, loses or retypes a field.
+pub struct OpenApiSpec(pub Value);
+
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("OpenAPI spec", |rocket| async move {
+        let spec = build(rocket.routes());
+        rocket.manage(OpenApiSpec(spec))
+    })
+}
+
+#[get("/openapi.json")]
+pub async fn openapi_json(spec: &State<OpenApiSpec>) -> Json<&Value> {
+    Json(&spec.0)
+}
+
+// The docs page runs Swagger UI's script and styles from its CDN, which the API's default
+// Content-Security-Policy (see crate::security) would block.
+const DOCS_CSP: &str = "default-src 'none'; script-src 'unsafe-inline' https://unpkg.com; style-src https://unpkg.com; \
+                        img-src 'self' data: https://unpkg.com; connect-src 'self'; frame-ancestors 'none'";
+
+#[derive(Responder)]
+pub struct DocsPage {
+    page: RawHtml<&'static str>,
+    csp: Header<'static>,
+}
+
+// Swagger UI is loaded from its CDN; the page only needs to point it at /openapi.json.
+#[get("/docs")]
+pub async fn swagger_ui() -> DocsPage {
+    let page = RawHtml(r##"<!DOCTYPE html>
+<html>
+<head>
+  <title>Tasks API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });</script>
+</body>
+</html>"##);
+    DocsPage { page, csp: Header::new("Content-Security-Policy", DOCS_CSP) }
+}
+
+// JSON Schema for a Rust type. Structs are referenced from components; everything else inline.
+pub trait SchemaType {
+    // false for Option<T>: the field may be left out
+    const REQUIRED: bool = true;
+    fn schema() -> Value;
+}
+
+impl SchemaType for i32 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "format": "int32" })
+    }
+}
+
+impl SchemaType for i64 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "format": "int64" })
+    }
+}
+
+impl SchemaType for f64 {
+    fn schema() -> Value {
+        json!({ "type": "number", "format": "double" })
+    }
+}
+
+impl SchemaType for usize {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0 })
+    }
+}
+
+impl SchemaType for u16 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0 })
+    }
+}
+
+impl SchemaType for bool {
+    fn schema() -> Value {
+        json!({ "type": "boolean" })
+    }
+}
+
+impl SchemaType for String {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl SchemaType for &'static str {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl SchemaType for Cow<'static, str> {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl SchemaType for NaiveDateTime {
+    fn schema() -> Value {
+        json!({ "type": "string", "format": "date-time" })
+    }
+}
+
+impl SchemaType for NaiveDate {
+    fn schema() -> Value {
+        json!({ "type": "string", "format": "date" })
+    }
+}
+
+impl SchemaType for TaskPriority {
+    fn schema() -> Value {
+        json!({ "type": "string", "enum": TaskPriority::NAMES })
+    }
+}
+
+impl<T: SchemaType> SchemaType for Option<T> {
+    const REQUIRED: bool = false;
+    fn schema() -> Value {
+        let mut schema = T::schema();
+        if let Some(object) = schema.as_object_mut() && !object.contains_key("$ref") {
+            object.insert("nullable".to_string(), json!(true));
+        }
+        schema
+    }
+}
+
+impl<T: SchemaType> SchemaType for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<T: SchemaType> SchemaType for Page<T> {
+    fn schema() -> Value {
+        object(vec![
+            ("items", Vec::<T>::schema(), true),
+            ("page", i64::schema(), true),
+            ("per_page", i64::schema(), true),
+            ("total", i64::schema(), true),
+            ("total_pages", i64::schema(), true),
+        ])
+    }
+}
+
+impl<T: SchemaType> SchemaType for CursorPage<T> {
+    fn schema() -> Value {
+        object(vec![
+            ("items", Vec::<T>::schema(), true),
+            ("next_cursor", Option::<i32>::schema(), false),
+        ])
+    }
+}
+
+impl<T: SchemaType> SchemaType for BulkResponse<T> {
+    fn schema() -> Value {
+        object(vec![
+            ("succeeded", usize::schema(), true),
+            ("failed", usize::schema(), true),
+            ("results", Vec::<BulkItemResult<T>>::schema(), true),
+        ])
+    }
+}
+
+impl<T: SchemaType> SchemaType for BulkItemResult<T> {
+    fn schema() -> Value {
+        object(vec![
+            ("index", usize::schema(), true),
+            ("status", u16::schema(), true),
+            ("item", T::schema(), false),
+            ("error", String::schema(), false),
+            ("errors", Vec::<FieldError>::schema(), false),
+        ])
+    }
+}
+
+// ?fields= can drop any property, so none of them are required.
+impl<T: SchemaType> SchemaType for Sparse<T> {
+    fn schema() -> Value {
+        json!({ "allOf": [T::schema()], "description": "Only the properties named in ?fields= when it is given" })
+    }
+}
+
+// The row's own schema plus a `links` map of relation name to URL.
+impl<T: SchemaType> SchemaType for Linked<T> {
+    fn schema() -> Value {
+        json!({
+            "allOf": [
+                T::schema(),
+                object(vec![("links", json!({ "type": "object", "additionalProperties": String::schema() }), true)]),
+            ]
+        })
+    }
+}
+
+// The related rows only appear when ?include= asks for them.
+impl SchemaType for ExpandedUserTask {
+    fn schema() -> Value {
+        json!({
+            "allOf": [
+                Linked::<UserTask>::schema(),
+                object(vec![
+                    ("user", User::schema(), false),
+                    ("task", Linked::<Task>::schema(), false),
+                    ("status", TaskStatus::schema(), false),
+                ]),
+            ]
+        })
+    }
+}
+
+impl SchemaType for CommentView {
+    fn schema() -> Value {
+        json!({ "allOf": [Comment::schema(), object(vec![("edited", bool::schema(), true)])] })
+    }
+}
+
+// Written out by hand because storage_key is never serialized.
+impl SchemaType for Attachment {
+    fn schema() -> Value {
+        component_ref("Attachment")
+    }
+}
+
+impl SchemaType for AttachmentUpload<'_> {
+    fn schema() -> Value {
+        object(vec![("file", binary(), true)])
+    }
+}
+
+impl SchemaType for CsvUpload<'_> {
+    fn schema() -> Value {
+        object(vec![("file", binary(), true)])
+    }
+}
+
+// Tables and columns are whatever the server's migrations made, so they're open-ended maps.
+impl SchemaType for Backup {
+    fn schema() -> Value {
+        let table = object(vec![
+            ("columns", Vec::<String>::schema(), true),
+            ("rows", json!({ "type": "array", "items": { "type": "object" } }), true),
+        ]);
+        object(vec![
+            ("format", i32::schema(), true),
+            ("exported_at", NaiveDateTime::schema(), true),
+            ("tables", json!({ "type": "object", "additionalProperties": table }), true),
+        ])
+    }
+}
+
+impl SchemaType for RestoredBackup {
+    fn schema() -> Value {
+        object(vec![
+            ("exported_at", NaiveDateTime::schema(), true),
+            ("rows", json!({ "type": "object", "additionalProperties": usize::schema() }), true),
+        ])
+    }
+}
+
+// Raw file contents, in a multipart part or as a whole response body.
+fn binary() -> Value {
+    json!({ "type": "string", "format": "binary" })
+}
+
+fn event_stream() -> Value {
+    json!({ "type": "string", "description": "server-sent events" })
+}
+
+fn object(fields: Vec<(&str, Value, bool)>) -> Value {
+    let required: Vec<&str> = fields.iter().filter(|(_, _, required)| *required).map(|(name, _, _)| *name).collect();
+    let properties: Map<String, Value> = fields.into_iter().map(|(name, schema, _)| (name.to_string(), schema)).collect();
+    json!({ "type": "object", "properties": properties, "required": required })
+}
+
+fn component_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+// Each entry implements SchemaType as a $ref and adds the full schema to components. The
+// closure destructures the struct without `..`, so the field list has to stay exact; fields
+// that are never serialized are listed after `; skip` so they're accounted for but left out.
+macro_rules! schemas {
+    ($($ty:ident { $($field:ident: $fty:ty),* $(,)? $(; skip $($skipped:ident),*)? })*) => {
+        $(
+            impl SchemaType for $ty {
+                fn schema() -> Value {
+                    component_ref(stringify!($ty))
+                }
+            }
+        )*
+
+        fn struct_schemas(components: &mut BTreeMap<&'static str, Value>) {
+            $(
+                let _ = |row: $ty| {
+                    let $ty { $($field,)* $($($skipped: _),*)? } = row;
+                    $(let _: $fty = $field;)*
+                };
+                components.insert(stringify!($ty), object(vec![
+                    $((stringify!($field), <$fty as SchemaType>::schema(), <$fty as SchemaType>::REQUIRED)),*
+                ]));
+            )*
+        }
+    };
+}
+
+schemas! {
+    User {
+        user_id: i32, name: String, email: String, active: bool, role_id: i32,
+        created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32;
+        skip tenant_id
+    }
+    Role { role_id: i32, role_name: String }
+    Task {
+        task_id: i32, task_name: String, deleted_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32, due_date: Option<NaiveDate>, priority: TaskPriority,
+        parent_task_id: Option<i32>, recurrence: Option<String>, recurrence_paused: bool, next_occurrence_id: Option<i32>,
+        project_id: Option<i32>;
+        skip tenant_id
+    }
+    TaskStatus {
+        task_status_id: i32, status_name: String, deleted_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32;
+        skip tenant_id
+    }
+    UserTask {
+        user_id: i32, task_id: i32, task_status_id: i32, deleted_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32;
+        skip tenant_id
+    }
+    AssignmentDetail {
+        user_id: i32, user_name: String, user_email: String, task_id: i32, task_name: String,
+        task_status_id: i32, status_name: String,
+        created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32,
+    }
+    TaskRevisionView {
+        version: i32, task_name: String, priority: Option<TaskPriority>, due_date: Option<NaiveDate>,
+        parent_task_id: Option<i32>, recurrence: Option<String>, project_id: Option<i32>, assignments: Vec<AssignmentSnapshot>,
+        actor_user_id: Option<i32>, created_at: NaiveDateTime,
+    }
+    AssignmentSnapshot { user_id: i32, task_status_id: i32 }
+    Tag { tag_id: i32, tag_name: String, created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32; skip tenant_id }
+    UserInput { name: String, email: String, active: bool }
+    RoleInput { role_id: i32 }
+    PasswordInput { password: String }
+    TaskInput { task_name: String, due_date: Option<NaiveDate>, priority: Option<String>, parent_task_id: Option<i32>, recurrence: Option<String>, project_id: Option<i32> }
+    SubtaskList { total: usize, completed: i64, subtasks: Vec<Linked<Task>> }
+    ImportedTask { task: Linked<Task>, assignments: Vec<Linked<UserTask>> }
+    DependencyInput { blocking_task_id: i32 }
+    TaskDependencies { blocked_by: Vec<Linked<Task>>, blocks: Vec<Linked<Task>> }
+    TaskStatusInput { status_name: String }
+    TaskStatusPatch { status_name: Option<String> }
+    TagInput { tag_name: String }
+    Project {
+        project_id: i32, project_name: String, description: Option<String>,
+        created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32, team_id: Option<i32>;
+        skip tenant_id
+    }
+    ProjectInput { project_name: String, description: Option<String>, team_id: Option<i32> }
+    Team { team_id: i32, team_name: String, created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32; skip tenant_id }
+    TeamInput { team_name: String }
+    TeamMembersInput { user_ids: Vec<i32> }
+    TaskTemplateView {
+        template_id: i32, template_name: String, task_name: String, priority: TaskPriority,
+        due_in_days: Option<i32>, recurrence: Option<String>, assignee_ids: Vec<i32>, subtask_names: Vec<String>,
+        created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32,
+    }
+    TaskTemplateInput {
+        template_name: String, task_name: String, priority: Option<String>, due_in_days: Option<i32>,
+        recurrence: Option<String>, assignee_ids: Vec<i32>, subtask_names: Vec<String>,
+    }
+    Comment {
+        comment_id: i32, task_id: i32, author_id: i32, body: String,
+        created_at: NaiveDateTime, updated_at: NaiveDateTime, version: i32,
+    }
+    CommentRevision {
+        comment_id: i32, version: i32, body: String, created_at: NaiveDateTime,
+        edited_by: Option<i32>, edited_at: NaiveDateTime,
+    }
+    CommentInput { body: String }
+    Mention { comment_id: i32, task_id: i32, author_id: i32, body: String, created_at: NaiveDateTime }
+    TaskWatcher { task_id: i32, user_id: i32, created_at: NaiveDateTime }
+    StarredTask { user_id: i32, task_id: i32, created_at: NaiveDateTime }
+    Webhook { webhook_id: i32, user_id: i32, url: String, event_type: String, created_at: NaiveDateTime; skip tenant_id, secret }
+    CreatedWebhook { secret: String, webhook: Webhook }
+    WebhookInput { url: String, event_type: String }
+    Notification {
+        notification_id: i32, user_id: i32, kind: String, task_id: Option<i32>, comment_id: Option<i32>, message: String,
+        read_at: Option<NaiveDateTime>, created_at: NaiveDateTime,
+    }
+    NotificationPreference { event_type: String, email: bool, webhook: bool, in_app: bool, push: bool; skip user_id }
+    NotificationPreferenceInput { event_type: String, email: bool, webhook: bool, in_app: bool, push: bool }
+    PushSubscription { push_subscription_id: i32, endpoint: String, created_at: NaiveDateTime; skip user_id, p256dh, auth }
+    PushSubscriptionInput { endpoint: String, keys: PushSubscriptionKeys }
+    PushSubscriptionKeys { p256dh: String, auth: String }
+    VapidPublicKey { public_key: String }
+    CalendarFeed { user_id: i32, created_at: NaiveDateTime; skip token_hash }
+    CreatedCalendarFeed { token: String, path: String, feed: CalendarFeed }
+    SlackIntegration { project_id: i32, webhook_url: String, created_at: NaiveDateTime, updated_at: NaiveDateTime }
+    SlackIntegrationInput { webhook_url: String }
+    Activity {
+        activity_id: i32, kind: Cow<'static, str>, actor_user_id: Option<i32>, task_id: Option<i32>, user_id: Option<i32>,
+        comment_id: Option<i32>, from_status_id: Option<i32>, to_status_id: Option<i32>, created_at: NaiveDateTime,
+    }
+    TaskTagsInput { tag_ids: Vec<i32> }
+    UserTaskInput { user_id: i32, task_id: i32, task_status_id: i32 }
+    UserTaskPatch { task_status_id: Option<i32> }
+    AssignmentKey { user_id: i32, task_id: i32 }
+    FieldError { field: &'static str, message: String }
+    Count { count: i64 }
+    StatusCount { task_status_id: i32, status_name: String, count: i64 }
+    UserWorkload { user_id: i32, name: String, total: i64, by_status: Vec<StatusCount> }
+    BoardColumn { task_status_id: i32, status_name: String, cards: Vec<BoardCard> }
+    BoardCard { task_id: i32, task_name: String, assignees: Vec<Assignee> }
+    Assignee { user_id: i32, name: String }
+    SearchHit { task_id: i32, task_name: String, score: f64 }
+    Suggestion { task_id: i32, task_name: String }
+    OverdueReport { checked_at: NaiveDateTime, assignments: Vec<OverdueAssignment> }
+    OverdueAssignment {
+        user_id: i32, user_name: String, task_id: i32, task_name: String, due_date: NaiveDate,
+        task_status_id: i32, status_name: String,
+    }
+}
+
+// Written out by hand for the serde rename and the `errors` member that's only sent on 422.
+impl SchemaType for ProblemDetails {
+    fn schema() -> Value {
+        component_ref("ProblemDetails")
+    }
+}
+
+fn components() -> BTreeMap<&'static str, Value> {
+    let mut components = BTreeMap::new();
+    struct_schemas(&mut components);
+    components.insert("ProblemDetails", object(vec![
+        ("type", String::schema(), true),
+        ("title", String::schema(), true),
+        ("status", u16::schema(), true),
+        ("detail", String::schema(), true),
+        ("instance", String::schema(), true),
+        ("request_id", String::schema(), true),
+        ("errors", Vec::<FieldError>::schema(), false),
+    ]));
+    components.insert("Attachment", object(vec![
+        ("attachment_id", i32::schema(), true),
+        ("task_id", i32::schema(), true),
+        ("uploaded_by", i32::schema(), true),
+        ("file_name", String::schema(), true),
+        ("content_type", String::schema(), true),
+        ("size_bytes", i64::schema(), true),
+        ("created_at", NaiveDateTime::schema(), true),
+    ]));
+    components
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Auth {
+    Public,
+    SignedIn,
+    Manager,
+    Admin,
+}
+
+// What the route table can't tell us about an operation.
+struct Doc {
+    summary: &'static str,
+    auth: Auth,
+    request: Option<fn() -> Value>,
+    request_type: &'static str,
+    response: Option<fn() -> Value>,
+    response_type: &'static str,
+    if_match: bool,
+    etag: bool,
+    msgpack: bool,
+}
+
+impl Doc {
+    fn new(summary: &'static str) -> Doc {
+        Doc {
+            summary, auth: Auth::Public,
+            request: None, request_type: "application/json",
+            response: None, response_type: "application/json",
+            if_match: false, etag: false, msgpack: false,
+        }
+    }
+
+    fn auth(mut self, auth: Auth) -> Doc {
+        self.auth = auth;
+        self
+    }
+
+    fn body<T: SchemaType>(mut self) -> Doc {
+        self.request = Some(T::schema);
+        self
+    }
+
+    fn returns<T: SchemaType>(mut self) -> Doc {
+        self.response = Some(T::schema);
+        self
+    }
+
+    // A multipart/form-data body instead of JSON.
+    fn upload<T: SchemaType>(mut self) -> Doc {
+        self.request_type = "multipart/form-data";
+        self.body::<T>()
+    }
+
+    // Responds with the raw bytes of a file rather than JSON.
+    fn download(mut self) -> Doc {
+        self.response = Some(binary);
+        self.response_type = "application/octet-stream";
+        self
+    }
+
+    // Responds with a CSV file to save rather than JSON.
+    fn csv(mut self) -> Doc {
+        self.response = Some(String::schema);
+        self.response_type = "text/csv";
+        self
+    }
+
+    // Responds with newline-delimited JSON, one T per line, rather than a JSON document.
+    fn lines<T: SchemaType>(mut self) -> Doc {
+        self.response = Some(T::schema);
+        self.response_type = "application/x-ndjson";
+        self
+    }
+
+    // Responds with an iCalendar (RFC 5545) document rather than JSON.
+    fn calendar(mut self) -> Doc {
+        self.response = Some(String::schema);
+        self.response_type = "text/calendar";
+        self
+    }
+
+    // Responds with a text/event-stream that stays open.
+    fn stream(mut self) -> Doc {
+        self.response = Some(event_stream);
+        self.response_type = "text/event-stream";
+        self
+    }
+
+    fn if_match(mut self) -> Doc {
+        self.if_match = true;
+        self
+    }
+
+    fn etag(mut self) -> Doc {
+        self.etag = true;
+        self
+    }
+
+    // Also takes and returns application/msgpack; see crate::msgpack.
+    fn msgpack(mut self) -> Doc {
+        self.msgpack = true;
+        self
+    }
+}
+
+// Keyed by handler name. Routes without an entry are still listed, with just their method and path.
+fn doc(handler: &str) -> Option<Doc> {
+    let doc = match handler {
+        "get_users" => Doc::new("List users, or a batch of them with ?ids=").auth(Auth::SignedIn).returns::<Page<Sparse<User>>>(),
+        "count_users" => Doc::new("Count users").auth(Auth::SignedIn).returns::<Count>(),
+        "get_user" => Doc::new("Fetch one user").auth(Auth::SignedIn).returns::<User>().etag(),
+        "create_user" => Doc::new("Create a user").auth(Auth::Admin).body::<UserInput>().returns::<User>(),
+        "update_user" => Doc::new("Replace a user (admins, or the user themselves)").auth(Auth::SignedIn).body::<UserInput>().returns::<User>().if_match(),
+        "delete_user" => Doc::new("Delete a user").auth(Auth::Admin).returns::<usize>(),
+        "update_user_role" => Doc::new("Change a user's role").auth(Auth::Admin).body::<RoleInput>().returns::<User>().if_match(),
+        "reset_user_password" => Doc::new("Set a user's password and sign them out everywhere").auth(Auth::Admin).body::<PasswordInput>().returns::<User>(),
+        "get_roles" => Doc::new("List roles").returns::<Vec<Role>>(),
+
+        "get_tasks" => Doc::new("List tasks, or a batch of them with ?ids=").auth(Auth::SignedIn).returns::<Page<Sparse<Linked<Task>>>>(),
+        "count_tasks" => Doc::new("Count tasks").auth(Auth::SignedIn).returns::<Count>(),
+        "get_task" => Doc::new("Fetch one task").auth(Auth::SignedIn).returns::<Linked<Task>>().etag(),
+        "create_task" => Doc::new("Create a task").auth(Auth::Manager).body::<TaskInput>().returns::<Linked<Task>>(),
+        "update_task" => Doc::new("Rename a task").auth(Auth::Manager).body::<TaskInput>().returns::<Linked<Task>>().if_match(),
+        "delete_task" => Doc::new("Move a task and its assignments to the trash").auth(Auth::Manager).returns::<usize>(),
+        "restore_task" => Doc::new("Bring a task back from the trash").auth(Auth::Manager).returns::<Linked<Task>>(),
+        "get_task_history" => Doc::new("List a task's revisions, newest first").auth(Auth::SignedIn).returns::<Page<TaskRevisionView>>(),
+        "revert_task" => Doc::new("Roll a task back to an earlier revision").auth(Auth::Manager).returns::<Linked<Task>>(),
+        "get_subtasks" => Doc::new("List a task's subtasks and how many are complete").auth(Auth::SignedIn).returns::<SubtaskList>(),
+        "import_tasks" => Doc::new("Create tasks and their assignments from a CSV with a header row (task_name, due_date, priority, project_id, assignees, status), or from a Trello or Jira export with ?format=, creating the statuses it names; one result per row").auth(Auth::Manager).upload::<CsvUpload>().returns::<BulkResponse<ImportedTask>>(),
+        "clone_task" => Doc::new("Copy a task with its tags and subtasks, and optionally its assignees").auth(Auth::Manager).returns::<Linked<Task>>(),
+        "pause_recurrence" => Doc::new("Stop a recurring task from creating its next occurrence").auth(Auth::Manager).returns::<Linked<Task>>(),
+        "resume_recurrence" => Doc::new("Let a paused recurring task create its next occurrence again").auth(Auth::Manager).returns::<Linked<Task>>(),
+        "get_task_dependencies" => Doc::new("List the tasks a task is blocked by and the tasks it blocks").auth(Auth::SignedIn).returns::<TaskDependencies>(),
+        "add_task_dependency" => Doc::new("Mark a task as blocked by another task").auth(Auth::Manager).body::<DependencyInput>().returns::<TaskDependencies>(),
+        "remove_task_dependency" => Doc::new("Remove a blocked-by link between two tasks").auth(Auth::Manager).returns::<usize>(),
+        "watch_task" => Doc::new("Watch a task to be notified when its assignments change").auth(Auth::SignedIn).returns::<TaskWatcher>(),
+        "unwatch_task" => Doc::new("Stop watching a task").auth(Auth::SignedIn).returns::<usize>(),
+        "star_task" => Doc::new("Star a task for the signed-in user").auth(Auth::SignedIn).returns::<StarredTask>(),
+        "unstar_task" => Doc::new("Remove the signed-in user's star from a task").auth(Auth::SignedIn).returns::<usize>(),
+        "get_starred_tasks" => Doc::new("List the tasks the signed-in user has starred").auth(Auth::SignedIn).returns::<Page<Linked<Task>>>(),
+
+        "get_tags" => Doc::new("List tags").auth(Auth::SignedIn).returns::<Page<Tag>>(),
+        "get_tag" => Doc::new("Fetch one tag").auth(Auth::SignedIn).returns::<Tag>().etag(),
+        "create_tag" => Doc::new("Create a tag").auth(Auth::Manager).body::<TagInput>().returns::<Tag>(),
+        "update_tag" => Doc::new("Rename a tag").auth(Auth::Manager).body::<TagInput>().returns::<Tag>().if_match(),
+        "delete_tag" => Doc::new("Delete a tag and take it off every task").auth(Auth::Manager).returns::<usize>(),
+        "get_task_tags" => Doc::new("List a task's tags").auth(Auth::SignedIn).returns::<Vec<Tag>>(),
+        "tag_task" => Doc::new("Add tags to a task").auth(Auth::Manager).body::<TaskTagsInput>().returns::<Vec<Tag>>(),
+        "untag_task" => Doc::new("Take a tag off a task").auth(Auth::Manager).returns::<usize>(),
+
+        "get_projects" => Doc::new("List projects").auth(Auth::SignedIn).returns::<Page<Project>>(),
+        "get_project" => Doc::new("Fetch one project").auth(Auth::SignedIn).returns::<Project>().etag(),
+        "create_project" => Doc::new("Create a project").auth(Auth::Manager).body::<ProjectInput>().returns::<Project>(),
+        "update_project" => Doc::new("Rename or redescribe a project").auth(Auth::Manager).body::<ProjectInput>().returns::<Project>().if_match(),
+        "delete_project" => Doc::new("Delete a project, leaving its tasks without one").auth(Auth::Manager).returns::<usize>(),
+        "get_project_tasks" => Doc::new("List a project's tasks").auth(Auth::SignedIn).returns::<Page<Linked<Task>>>(),
+        "get_project_board" => Doc::new("The kanban board for one project's tasks").auth(Auth::SignedIn).returns::<Vec<BoardColumn>>(),
+        "get_slack_integration" => Doc::new("The Slack webhook a project's new tasks and assignment moves are posted to").auth(Auth::Manager).returns::<SlackIntegration>(),
+        "put_slack_integration" => Doc::new("Post a project's new tasks and assignment moves to a Slack webhook").auth(Auth::Manager).body::<SlackIntegrationInput>().returns::<SlackIntegration>(),
+        "delete_slack_integration" => Doc::new("Stop posting a project's events to Slack").auth(Auth::Manager).returns::<usize>(),
+
+        "get_teams" => Doc::new("List teams").auth(Auth::SignedIn).returns::<Page<Team>>(),
+        "get_team" => Doc::new("Fetch one team").auth(Auth::SignedIn).returns::<Team>().etag(),
+        "create_team" => Doc::new("Create a team").auth(Auth::Manager).body::<TeamInput>().returns::<Team>(),
+        "update_team" => Doc::new("Rename a team").auth(Auth::Manager).body::<TeamInput>().returns::<Team>().if_match(),
+        "delete_team" => Doc::new("Delete a team and open its projects to everyone").auth(Auth::Manager).returns::<usize>(),
+        "get_team_members" => Doc::new("List a team's members").auth(Auth::SignedIn).returns::<Vec<User>>(),
+        "add_team_members" => Doc::new("Add users to a team").auth(Auth::Manager).body::<TeamMembersInput>().returns::<Vec<User>>(),
+        "remove_team_member" => Doc::new("Take a user off a team").auth(Auth::Manager).returns::<usize>(),
+
+        "get_task_templates" => Doc::new("List task templates").auth(Auth::SignedIn).returns::<Page<TaskTemplateView>>(),
+        "get_task_template" => Doc::new("Fetch one task template").auth(Auth::SignedIn).returns::<TaskTemplateView>(),
+        "create_task_template" => Doc::new("Create a task template").auth(Auth::Manager).body::<TaskTemplateInput>().returns::<TaskTemplateView>(),
+        "update_task_template" => Doc::new("Replace a task template").auth(Auth::Manager).body::<TaskTemplateInput>().returns::<TaskTemplateView>().if_match(),
+        "delete_task_template" => Doc::new("Delete a task template").auth(Auth::Manager).returns::<usize>(),
+        "create_task_from_template" => Doc::new("Create a task, with its subtasks and assignments, from a template").auth(Auth::Manager).returns::<Linked<Task>>(),
+
+        "get_task_comments" => Doc::new("List the comments on a task, oldest first").auth(Auth::SignedIn).returns::<Page<CommentView>>(),
+        "create_comment" => Doc::new("Comment on a task as the signed-in user").auth(Auth::SignedIn).body::<CommentInput>().returns::<CommentView>(),
+        "update_comment" => Doc::new("Edit a comment (its author, or managers)").auth(Auth::SignedIn).body::<CommentInput>().returns::<CommentView>().if_match(),
+        "get_comment_history" => Doc::new("List a comment's earlier versions, newest first").auth(Auth::SignedIn).returns::<Page<CommentRevision>>(),
+        "delete_comment" => Doc::new("Delete a comment (its author, or managers)").auth(Auth::SignedIn).returns::<usize>(),
+        "get_user_mentions" => Doc::new("List comments that @-mention a user, newest first (the user, or managers)").auth(Auth::SignedIn).returns::<Page<Mention>>(),
+
+        "get_task_attachments" => Doc::new("List the files attached to a task").auth(Auth::SignedIn).returns::<Vec<Attachment>>(),
+        "upload_attachment" => Doc::new("Attach a file to a task as the signed-in user").auth(Auth::SignedIn).upload::<AttachmentUpload>().returns::<Attachment>(),
+        "download_attachment" => Doc::new("Download an attached file").auth(Auth::SignedIn).download(),
+        "delete_attachment" => Doc::new("Delete an attachment (its uploader, or managers)").auth(Auth::SignedIn).returns::<usize>(),
+
+        "get_task_statuses" => Doc::new("List task statuses, or a batch of them with ?ids=").auth(Auth::SignedIn).returns::<Page<Sparse<TaskStatus>>>(),
+        "count_task_statuses" => Doc::new("Count task statuses").auth(Auth::SignedIn).returns::<Count>(),
+        "get_task_status" => Doc::new("Fetch one task status").auth(Auth::SignedIn).returns::<TaskStatus>().etag(),
+        "create_task_status" => Doc::new("Create a task status").auth(Auth::Manager).body::<TaskStatusInput>().returns::<TaskStatus>(),
+        "update_task_status" => Doc::new("Rename a task status").auth(Auth::Manager).body::<TaskStatusInput>().returns::<TaskStatus>().if_match(),
+        "patch_task_status" => Doc::new("Change some fields of a task status").auth(Auth::Manager).body::<TaskStatusPatch>().returns::<TaskStatus>().if_match(),
+        "delete_task_status" => Doc::new("Move an unused task status to the trash").auth(Auth::Admin).returns::<usize>(),
+        "restore_task_status" => Doc::new("Bring a task status back from the trash").auth(Auth::Admin).returns::<TaskStatus>(),
+
+        "get_user_tasks" => Doc::new("List assignments, optionally filtered").auth(Auth::SignedIn).returns::<Page<Sparse<ExpandedUserTask>>>().msgpack(),
+        "count_user_tasks" => Doc::new("Count assignments, with the same filters as the list").auth(Auth::SignedIn).returns::<Count>().msgpack(),
+        "get_user_assignments" => Doc::new("List one user's assignments, optionally in one status").auth(Auth::SignedIn).returns::<Page<Linked<UserTask>>>().msgpack(),
+        "get_task_assignments" => Doc::new("List everyone assigned to one task, optionally in one status").auth(Auth::SignedIn).returns::<Page<Linked<UserTask>>>().msgpack(),
+        "get_overdue_assignments" => Doc::new("Assignments past their task's due date and not in a finished status, as of the last scan").auth(Auth::SignedIn).returns::<OverdueReport>(),
+        "get_assignment_details" => Doc::new("List assignments with user, task and status names filled in").auth(Auth::SignedIn).returns::<Page<AssignmentDetail>>().msgpack(),
+        "export_assignments_ndjson" => Doc::new("Stream every assignment matching the filters as newline-delimited JSON, one AssignmentDetail per line").auth(Auth::SignedIn).lines::<AssignmentDetail>(),
+        "export_assignments_csv" => Doc::new("Download every assignment matching the filters as CSV, with user, task and status names").auth(Auth::SignedIn).csv(),
+        "get_user_task" => Doc::new("Fetch one assignment").auth(Auth::SignedIn).returns::<Linked<UserTask>>().etag().msgpack(),
+        "create_user_task" => Doc::new("Assign a user to a task").auth(Auth::Manager).body::<UserTaskInput>().returns::<Linked<UserTask>>().msgpack(),
+        "update_user_task" => Doc::new("Replace an assignment (managers, or the assigned user)").auth(Auth::SignedIn).body::<UserTaskInput>().returns::<Linked<UserTask>>().if_match().msgpack(),
+        "patch_user_task" => Doc::new("Change some fields of an assignment (managers, or the assigned user)").auth(Auth::SignedIn).body::<UserTaskPatch>().returns::<Linked<UserTask>>().if_match().msgpack(),
+        "upsert_user_task" => Doc::new("Create an assignment or move an existing one to a new status").auth(Auth::Manager).body::<UserTaskInput>().returns::<Linked<UserTask>>().msgpack(),
+        "delete_user_task" => Doc::new("Move an assignment to the trash").auth(Auth::Manager).returns::<usize>().msgpack(),
+        "restore_user_task" => Doc::new("Bring an assignment back from the trash").auth(Auth::Manager).returns::<Linked<UserTask>>().msgpack(),
+        "bulk_create_user_tasks" => Doc::new("Create many assignments; each item succeeds or fails on its own").auth(Auth::Manager).body::<Vec<UserTaskInput>>().returns::<BulkResponse<Linked<UserTask>>>().msgpack(),
+        "bulk_update_user_tasks" => Doc::new("Update many assignments; each item succeeds or fails on its own").auth(Auth::Manager).body::<Vec<UserTaskInput>>().returns::<BulkResponse<Linked<UserTask>>>().msgpack(),
+        "bulk_delete_user_tasks" => Doc::new("Delete many assignments; each item succeeds or fails on its own").auth(Auth::Manager).body::<Vec<AssignmentKey>>().returns::<BulkResponse<AssignmentKey>>().msgpack(),
+
+        "get_assignments_by_status" => Doc::new("Count live assignments in each status, optionally for one user").auth(Auth::SignedIn).returns::<Vec<StatusCount>>(),
+        "get_workload" => Doc::new("Each user's open assignments counted per status").auth(Auth::SignedIn).returns::<Vec<UserWorkload>>(),
+        "get_board" => Doc::new("Tasks grouped into one column per status, with who is at each stage").auth(Auth::SignedIn).returns::<Vec<BoardColumn>>(),
+        "board_socket" => Doc::new("WebSocket that sends each card moving between board columns as JSON").auth(Auth::SignedIn),
+        "search_tasks" => Doc::new("Full-text search over task names, best matches first; fuzzy=true tolerates typos").auth(Auth::SignedIn).returns::<Page<SearchHit>>(),
+        "suggest_tasks" => Doc::new("Tasks whose name has words starting with each word of q, for typeahead").auth(Auth::SignedIn).returns::<Vec<Suggestion>>(),
+        "get_webhooks" => Doc::new("List the signed-in user's webhooks").auth(Auth::SignedIn).returns::<Vec<Webhook>>(),
+        "create_webhook" => Doc::new("Register a URL to be POSTed each task or assignment event of one type, signed with the returned secret").auth(Auth::Manager).body::<WebhookInput>().returns::<CreatedWebhook>(),
+        "delete_webhook" => Doc::new("Delete one of the signed-in user's webhooks").auth(Auth::SignedIn).returns::<usize>(),
+        "get_notification_preferences" => Doc::new("The signed-in user's email, webhook, in-app and push settings for every event type").auth(Auth::SignedIn).returns::<Vec<NotificationPreference>>(),
+        "get_notifications" => Doc::new("The signed-in user's in-app notifications, newest first; unread=true for just the unread ones").auth(Auth::SignedIn).returns::<Page<Notification>>(),
+        "count_unread_notifications" => Doc::new("How many of the signed-in user's notifications are unread").auth(Auth::SignedIn).returns::<Count>(),
+        "read_notification" => Doc::new("Mark one of the signed-in user's notifications read").auth(Auth::SignedIn).returns::<Notification>(),
+        "get_push_public_key" => Doc::new("The VAPID public key browsers subscribe to Web Push with (the applicationServerKey)").returns::<VapidPublicKey>(),
+        "get_push_subscriptions" => Doc::new("List the browsers the signed-in user gets Web Push notifications in").auth(Auth::SignedIn).returns::<Vec<PushSubscription>>(),
+        "create_push_subscription" => Doc::new("Register a browser's PushSubscription for the signed-in user's assignment changes and due date reminders").auth(Auth::SignedIn).body::<PushSubscriptionInput>().returns::<PushSubscription>(),
+        "delete_push_subscription" => Doc::new("Stop pushing to one of the signed-in user's browsers").auth(Auth::SignedIn).returns::<usize>(),
+        "get_calendar" => Doc::new("The user's assigned tasks with due dates as an iCalendar feed; sign in (as the user, or a manager) or pass the feed's ?token=").calendar(),
+        "create_calendar_feed" => Doc::new("Turn on the signed-in user's calendar feed, or replace its token; returns the URL for calendar apps").auth(Auth::SignedIn).returns::<CreatedCalendarFeed>(),
+        "delete_calendar_feed" => Doc::new("Turn off the signed-in user's calendar feed").auth(Auth::SignedIn).returns::<usize>(),
+        "update_notification_preferences" => Doc::new("Replace the signed-in user's notification settings; event types left out are reset to all on").auth(Auth::SignedIn).body::<Vec<NotificationPreferenceInput>>().returns::<Vec<NotificationPreference>>(),
+        "export_backup" => Doc::new("Every row of every table, across all tenants, as one JSON document (admins of the default tenant)").auth(Auth::Admin).returns::<Backup>(),
+        "import_backup" => Doc::new("Replace all data with a document from GET /admin/export, in one transaction; 409 if it's from a different migration (admins of the default tenant)").auth(Auth::Admin).body::<Backup>().returns::<RestoredBackup>(),
+        "get_activity" => Doc::new("What has happened on tasks, assignments and comments, oldest first; page on with ?cursor=").auth(Auth::SignedIn).returns::<CursorPage<Activity>>(),
+        "stream_events" => Doc::new("Live task, assignment and status changes in the caller's tenant: task.created, assignment.created, assignment.status_changed, assignment.deleted and task_status.created, task_status.updated, task_status.deleted").auth(Auth::SignedIn).stream(),
+        _ => return None,
+    };
+    Some(doc)
+}
+
+fn parameter(name: &str, location: &str, schema: Value, required: bool) -> Value {
+    json!({ "name": name, "in": location, "required": required, "schema": schema })
+}
+
+fn query_parameters(query: &str) -> Vec<Value> {
+    let mut parameters = Vec::new();
+    for segment in query.split('&') {
+        let Some(name) = segment.strip_prefix('<').and_then(|s| s.strip_suffix('>')) else {
+            continue;
+        };
+        match name.strip_suffix("..") {
+            // every <paging..> is a PageQuery
+            Some(_) => {
+                parameters.push(parameter("page", "query", json!({ "type": "integer", "minimum": 1 }), false));
+                parameters.push(parameter("per_page", "query", json!({ "type": "integer", "minimum": 1 }), false));
+                parameters.push(parameter("sort", "query", String::schema(), false));
+                parameters.push(parameter("order", "query", json!({ "type": "string", "enum": ["asc", "desc"] }), false));
+            }
+            None => {
+                let schema = match name {
+                    "user_id" | "task_id" | "task_status_id" | "days" => i32::schema(),
+                    "priority" => TaskPriority::schema(),
+                    "format" => json!({ "type": "string", "enum": ImportFormat::NAMES }),
+                    "ids" => json!({ "type": "string", "description": "comma-separated ids, e.g. 1,2,3" }),
+                    _ => String::schema(),
+                };
+                parameters.push(parameter(name, "query", schema, false));
+            }
+        }
+    }
+    parameters
+}
+
+// "/api/tasks/<id>" becomes "/api/tasks/{id}" plus an integer path parameter named id.
+fn path_template(path: &str) -> (String, Vec<Value>) {
+    let mut parameters = Vec::new();
+    let segments: Vec<String> = path.split('/')
+        .map(|segment| match segment.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+            Some(name) => {
+                let schema = if name == "provider" { String::schema() } else { i32::schema() };
+                parameters.push(parameter(name, "path", schema, true));
+                format!("{{{}}}", name)
+            }
+            None => segment.to_string(),
+        })
+        .collect();
+    (segments.join("/"), parameters)
+}
+
+fn json_content(content_type: &str, schema: Value) -> Value {
+    let mut content = Map::new();
+    content.insert(content_type.to_string(), json!({ "schema": schema }));
+    Value::Object(content)
+}
+
+fn operation(route: &Route) -> Value {
+    let name = route.name.as_deref().unwrap_or_default();
+    let (_, mut parameters) = path_template(route.uri.path());
+    parameters.extend(route.uri.query().map(query_parameters).unwrap_or_default());
+    let doc = doc(name).unwrap_or_else(|| Doc::new(""));
+    let tag = route.uri.path().trim_start_matches(route.uri.base()).trim_start_matches('/')
+        .split('/').next().unwrap_or_default().to_string();
+
+    let mut responses = Map::new();
+    let mut ok = match doc.response {
+        Some(schema) => json!({ "description": "OK", "content": json_content(doc.response_type, schema()) }),
+        None => json!({ "description": "OK" }),
+    };
+    // crate::xml rewrites JSON GET responses for clients that Accept application/xml
+    if let (Some(schema), true) = (doc.response, route.method == Method::Get && doc.response_type == "application/json") {
+        ok["content"]["application/xml"] = json!({ "schema": schema() });
+    }
+    if let (Some(schema), true) = (doc.response, doc.msgpack) {
+        ok["content"]["application/msgpack"] = json!({ "schema": schema() });
+    }
+    responses.insert("200".to_string(), ok);
+    if doc.etag {
+        parameters.push(parameter("If-None-Match", "header", String::schema(), false));
+        parameters.push(parameter("If-Modified-Since", "header", String::schema(), false));
+        responses.insert("304".to_string(), json!({ "description": "Not Modified: the client's copy is current" }));
+    }
+    if doc.if_match {
+        parameters.push(parameter("If-Match", "header", json!({ "type": "string", "description": "ETag from the last read, or *" }), true));
+        responses.insert("412".to_string(), json!({ "description": "Precondition Failed: the row changed since that ETag" }));
+        responses.insert("428".to_string(), json!({ "description": "Precondition Required: If-Match is missing" }));
+    }
+    responses.insert("default".to_string(), json!({
+        "description": "Error",
+        "content": json_content("application/problem+json", ProblemDetails::schema()),
+    }));
+
+    let summary = if doc.summary.is_empty() { name } else { doc.summary };
+    let mut operation = json!({
+        "operationId": name,
+        "summary": summary,
+        "tags": [tag],
+        "parameters": parameters,
+        "responses": responses,
+    });
+    if let Some(schema) = doc.request {
+        operation["requestBody"] = json!({ "required": true, "content": json_content(doc.request_type, schema()) });
+        if doc.msgpack {
+            operation["requestBody"]["content"]["application/msgpack"] = json!({ "schema": schema() });
+        }
+    }
+    match doc.auth {
+        Auth::Public => {}
+        auth => {
+            operation["security"] = json!([{ "bearerAuth": [] }]);
+            let role = match auth {
+                Auth::Manager => "Requires the manager or admin role.",
+                Auth::Admin => "Requires the admin role.",
+                _ => "Requires a signed-in user.",
+            };
+            operation["description"] = json!(role);
+        }
+    }
+    operation
+}
+
+fn build<'a>(routes: impl Iterator<Item = &'a Route>) -> Value {
+    let mut paths: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+    for route in routes.filter(|route| route.uri.path().starts_with("/api")) {
+        let (template, _) = path_template(route.uri.path());
+        paths.entry(template)
+            .or_default()
+            .insert(route.method.as_str().to_ascii_lowercase(), operation(route));
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": "Tasks API", "version": env!("CARGO_PKG_VERSION") },
+        "paths": paths,
+        "components": {
+            "schemas": components(),
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_parameters_become_templates() {
+        let (template, parameters) = path_template("/api/assignments/<user_id>/<task_id>");
+        assert_eq!(template, "/api/assignments/{user_id}/{task_id}");
+        let names: Vec<&str> = parameters.iter().map(|parameter| parameter["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["user_id", "task_id"]);
+        assert_eq!(parameters[0]["schema"]["type"], "integer");
+        assert_eq!(parameters[0]["required"], true);
+    }
+
+    #[test]
+    fn paging_expands_into_its_query_parameters() {
+        let parameters = query_parameters("<ids>&<paging..>");
+        let names: Vec<&str> = parameters.iter().map(|parameter| parameter["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["ids", "page", "per_page", "sort", "order"]);
+        assert!(parameters.iter().all(|parameter| parameter["required"] == false));
+    }
+
+    #[test]
+    fn optional_fields_are_nullable() {
+        assert_eq!(Option::<i32>::schema()["nullable"], true);
+        assert_eq!(Vec::<String>::schema()["items"]["type"], "string");
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use rocket::{serde::json::Json, State, get};
+use rocket::fairing::AdHoc;
+use rocket::serde::Serialize;
+use tasks_db_lib::DbConnection;
+use chrono::{NaiveDateTime, Utc};
+use tasks_db_lib::models::Tenant;
+use tasks_db_lib::overdue::{self, OverdueAssignment};
+use crate::error::ApiError;
+use crate::shutdown::Drain;
+use crate::tenancy::{DbPool, TenantDb};
+
+const DEFAULT_SCAN_MINUTES: u64 = 15;
+const DEFAULT_TERMINAL_STATUSES: &str = "Completed";
+
+// OVERDUE_SCAN_MINUTES is how often the background job looks for overdue assignments.
+// OVERDUE_TERMINAL_STATUSES is a comma-separated list of status names that count as finished.
+#[derive(Clone)]
+pub struct OverdueConfig {
+    pub scan_every: Duration,
+    pub terminal_statuses: Vec<String>,
+}
+
+impl OverdueConfig {
+    pub fn from_env() -> OverdueConfig {
+        let minutes = std::env::var("OVERDUE_SCAN_MINUTES")
+            .ok()
+            .and_then(|m| m.parse().ok())
+            .filter(|m: &u64| *m > 0)
+            .unwrap_or(DEFAULT_SCAN_MINUTES);
+        let terminal_statuses = std::env::var("OVERDUE_TERMINAL_STATUSES")
+            .unwrap_or_else(|_| DEFAULT_TERMINAL_STATUSES.to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect();
+        OverdueConfig { scan_every: Duration::from_secs(minutes * 60), terminal_statuses }
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct OverdueReport {
+    pub checked_at: NaiveDateTime,
+    pub assignments: Vec<OverdueAssignment>,
+}
+
+// The result of the latest scan of each tenant, shared between the background job and the handler.
+#[derive(Clone, Default)]
+pub struct OverdueTracker {
+    latest: Arc<RwLock<HashMap<i32, OverdueReport>>>,
+}
+
+impl OverdueTracker {
+    async fn scan(&self, conn: &mut DbConnection, tenant_id: i32, config: &OverdueConfig) -> Result<OverdueReport, ApiError> {
+        let now = Utc::now();
+        let report = OverdueReport {
+            checked_at: now.naive_utc(),
+            assignments: overdue::find_overdue(conn, now.date_naive(), &config.terminal_statuses).await?,
+        };
+        self.latest.write().unwrap_or_else(|e| e.into_inner()).insert(tenant_id, report.clone());
+        Ok(report)
+    }
+
+    // One tenant at a time, so a failure in one doesn't hold up the rest.
+    async fn scan_all(&self, pool: &DbPool, config: &OverdueConfig) -> Result<(), ApiError> {
+        for tenant_id in Tenant::read_all_ids(&mut *pool.get().await?).await? {
+            let scanned = async { self.scan(&mut *pool.get_in_tenant(tenant_id).await?, tenant_id, config).await }.await;
+            if let Err(e) = scanned {
+                tracing::error!("Overdue scan of tenant {} failed: {:?}", tenant_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn latest(&self, tenant_id: i32) -> Option<OverdueReport> {
+        self.latest.read().unwrap_or_else(|e| e.into_inner()).get(&tenant_id).cloned()
+    }
+}
+
+// Starts the scan once the server is up, then repeats it every OVERDUE_SCAN_MINUTES.
+// A failed scan keeps the previous report and is retried on the next tick.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Overdue scan", |rocket| Box::pin(async move {
+        let (Some(pool), Some(config), Some(tracker), Some(drain)) = (
+            rocket.state::<DbPool>().cloned(),
+            rocket.state::<OverdueConfig>().cloned(),
+            rocket.state::<OverdueTracker>().cloned(),
+            rocket.state::<Drain>(),
+        ) else {
+            return;
+        };
+        let mut stop = drain.stop();
+        drain.spawn("Overdue scan", async move {
+            let mut interval = rocket::tokio::time::interval(config.scan_every);
+            while stop.tick(&mut interval).await {
+                if let Err(e) = tracker.scan_all(&pool, &config).await {
+                    tracing::error!("Overdue scan failed: {:?}", e);
+                }
+            }
+        });
+    }))
+}
+
+// The caller's tenant's assignments whose task is past due and not yet in a finished status,
+// as of the last scan. If the job hasn't scanned the tenant yet, one is run for this request.
+#[get("/assignments/overdue")]
+pub async fn get_overdue_assignments(db: TenantDb, config: &State<OverdueConfig>, tracker: &State<OverdueTracker>) -> Result<Json<OverdueReport>, ApiError> {
+    match tracker.latest(db.tenant_id) {
+        Some(report) => Ok(Json(report)),
+        None => {
+            let mut conn = db.get().await?;
+            Ok(Json(tracker.scan(&mut conn, db.tenant_id, config).await?))
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use rocket::FromForm;
+use rocket::serde::Serialize;
+use tasks_db_lib::pagination::{DEFAULT_PER_PAGE, MAX_PER_PAGE};
+use tasks_db_lib::sorting::Sort;
+use crate::error::ApiError;
+
+// Body of the /count routes: how many rows the matching list route would page through.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Count {
+    pub count: i64,
+}
+
+// ?page=&per_page=&sort=&order= on every list route,
+// e.g. GET /api/tasks?page=2&per_page=10&sort=task_name&order=desc
+#[derive(FromForm, Debug, Default)]
+pub struct PageQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+}
+
+impl PageQuery {
+    // Returns (page, per_page) with defaults applied, rejecting values that can't produce a page.
+    pub fn resolve(&self) -> Result<(i64, i64), ApiError> {
+        self.resolve_with_default(DEFAULT_PER_PAGE)
+    }
+
+    // ?ids=1,2,3 narrows a list route to those rows. per_page defaults to the number
+    // of ids so the whole batch comes back as one page. Returns (ids, page, per_page).
+    pub fn resolve_ids(&self, raw_ids: &str) -> Result<(Vec<i32>, i64, i64), ApiError> {
+        let ids = raw_ids.split(',')
+            .map(|id| id.trim().parse::<i32>())
+            .collect::<Result<Vec<i32>, _>>()
+            .map_err(|_| ApiError::BadRequest("ids must be a comma-separated list of integers".to_string()))?;
+        if ids.len() as i64 > MAX_PER_PAGE {
+            return Err(ApiError::BadRequest(format!("At most {} ids can be requested at once", MAX_PER_PAGE)));
+        }
+        let (page, per_page) = self.resolve_with_default(ids.len() as i64)?;
+        Ok((ids, page, per_page))
+    }
+
+    fn resolve_with_default(&self, default_per_page: i64) -> Result<(i64, i64), ApiError> {
+        let page = self.page.unwrap_or(1);
+        let per_page = self.per_page.unwrap_or(default_per_page);
+        if page < 1 {
+            return Err(ApiError::BadRequest("page must be 1 or greater".to_string()));
+        }
+        if !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(ApiError::BadRequest(format!("per_page must be between 1 and {}", MAX_PER_PAGE)));
+        }
+        // the page has to start at a row number SQLite can count to
+        if (page - 1).checked_mul(per_page).is_none() {
+            return Err(ApiError::BadRequest("page is too large".to_string()));
+        }
+        Ok((page, per_page))
+    }
+
+    // Checks ?sort= against the sortable columns tasks_db_lib allows for this entity.
+    pub fn sort(&self, allowed: &[&str]) -> Result<Sort, ApiError> {
+        Sort::parse(self.sort.as_deref(), self.order.as_deref(), allowed).map_err(ApiError::BadRequest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(page: Option<i64>, per_page: Option<i64>) -> PageQuery {
+        PageQuery { page, per_page, ..PageQuery::default() }
+    }
+
+    #[test]
+    fn defaults_to_the_first_page() {
+        assert_eq!(query(None, None).resolve().unwrap(), (1, DEFAULT_PER_PAGE));
+    }
+
+    #[test]
+    fn rejects_pages_before_the_first() {
+        assert!(matches!(query(Some(0), None).resolve(), Err(ApiError::BadRequest(_))));
+        assert!(matches!(query(Some(-1), None).resolve(), Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn rejects_per_page_outside_its_bounds() {
+        assert!(matches!(query(None, Some(0)).resolve(), Err(ApiError::BadRequest(_))));
+        assert!(matches!(query(None, Some(MAX_PER_PAGE + 1)).resolve(), Err(ApiError::BadRequest(_))));
+        assert_eq!(query(None, Some(MAX_PER_PAGE)).resolve().unwrap(), (1, MAX_PER_PAGE));
+    }
+
+    #[test]
+    fn an_unknown_sort_column_is_a_bad_request() {
+        let query = PageQuery { sort: Some("secret".to_string()), ..PageQuery::default() };
+        assert!(matches!(query.sort(&["task_id"]), Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn rejects_a_page_whose_offset_overflows() {
+        assert!(matches!(query(Some(i64::MAX), None).resolve(), Err(ApiError::BadRequest(_))));
+        assert!(matches!(query(Some(i64::MAX / 2), Some(MAX_PER_PAGE)).resolve(), Err(ApiError::BadRequest(_))));
+        let last = i64::MAX / DEFAULT_PER_PAGE + 1;
+        assert_eq!(query(Some(last), None).resolve().unwrap(), (last, DEFAULT_PER_PAGE));
+    }
+
+    #[test]
+    fn ids_default_per_page_to_the_batch() {
+        assert_eq!(PageQuery::default().resolve_ids("3, 1,2").unwrap(), (vec![3, 1, 2], 1, 3));
+        assert!(matches!(PageQuery::default().resolve_ids("1,x"), Err(ApiError::BadRequest(_))));
+    }
+}
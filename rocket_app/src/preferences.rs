@@ -0,0 +1,65 @@
+use rocket::{serde::json::Json, get, put};
+use rocket::serde::Deserialize;
+use tasks_db_lib::models::{NewNotificationPreference, NotificationPreference};
+use tasks_db_lib::preferences::NOTIFICATION_EVENT_TYPES;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::AuthenticatedUser;
+use crate::validation::{Validate, Validator};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NotificationPreferenceInput {
+    // one of tasks_db_lib::preferences::NOTIFICATION_EVENT_TYPES
+    pub event_type: String,
+    pub email: bool,
+    pub webhook: bool,
+    pub in_app: bool,
+    // added after the others, so clients that don't know about it leave it on
+    #[serde(default = "on")]
+    pub push: bool,
+}
+
+fn on() -> bool {
+    true
+}
+
+impl Validate for Vec<NotificationPreferenceInput> {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        for (index, preference) in self.iter().enumerate() {
+            if !NOTIFICATION_EVENT_TYPES.contains(&preference.event_type.as_str()) {
+                validator.error("event_type", format!("must be one of: {}", NOTIFICATION_EVENT_TYPES.join(", ")));
+            } else if self[..index].iter().any(|earlier| earlier.event_type == preference.event_type) {
+                validator.error("event_type", format!("{} is listed more than once", preference.event_type));
+            }
+        }
+        validator.finish()
+    }
+}
+
+// Every event type with the caller's email, webhook, in-app and push settings; all on by default.
+#[get("/users/me/notification_preferences")]
+pub async fn get_notification_preferences(db: TenantDb, auth: AuthenticatedUser) -> Result<Json<Vec<NotificationPreference>>, ApiError> {
+    let mut conn = db.get().await?;
+    Ok(Json(NotificationPreference::read_all_for_user(&mut conn, auth.user_id).await?))
+}
+
+// Takes the same list GET returns, and replaces the caller's settings with it: event types
+// left out go back to every channel on.
+#[put("/users/me/notification_preferences", data = "<preferences>")]
+pub async fn update_notification_preferences(db: TenantDb, auth: AuthenticatedUser, preferences: Json<Vec<NotificationPreferenceInput>>) -> Result<Json<Vec<NotificationPreference>>, ApiError> {
+    preferences.validate()?;
+    let mut conn = db.get().await?;
+    let new_preferences = preferences.iter()
+        .map(|preference| NewNotificationPreference {
+            user_id: auth.user_id,
+            event_type: &preference.event_type,
+            email: preference.email,
+            webhook: preference.webhook,
+            in_app: preference.in_app,
+            push: preference.push,
+        })
+        .collect();
+    Ok(Json(NotificationPreference::replace_all(&mut conn, auth.user_id, new_preferences).await?))
+}
@@ -0,0 +1,143 @@
+use rocket::{serde::json::Json, get, post, put, delete};
+use tasks_db_lib::DbConnection;
+use tasks_db_lib::models::{NewProject, Project, Task, Team};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::audit::AuditedCrud;
+use tasks_db_lib::board::{self, BoardColumn};
+use tasks_db_lib::filters::TaskFilter;
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::sorting::{PROJECT_SORT_COLUMNS, TASK_SORT_COLUMNS};
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::conditional::{CacheValidators, Cached, IfMatch};
+use crate::auth::ManagerUser;
+use crate::links::{linked_all, Linked};
+use crate::pagination::PageQuery;
+use crate::validation::{FieldError, Validate, Validator, MAX_DESCRIPTION_LEN, MAX_PROJECT_NAME_LEN};
+
+#[derive(rocket::serde::Deserialize)]
+pub struct ProjectInput {
+    pub project_name: String,
+    // free text; left out (or null) for none
+    pub description: Option<String>,
+    // only the team's members may be assigned the project's tasks; left out (or null) for anyone
+    pub team_id: Option<i32>,
+}
+
+impl ProjectInput {
+    // A blank description is stored as none.
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref().map(str::trim).filter(|description| !description.is_empty())
+    }
+}
+
+impl Validate for ProjectInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        validator.text("project_name", &self.project_name, MAX_PROJECT_NAME_LEN);
+        if let Some(description) = &self.description && description.chars().count() > MAX_DESCRIPTION_LEN {
+            validator.error("description", format!("must be at most {} characters", MAX_DESCRIPTION_LEN));
+        }
+        if let Some(team_id) = self.team_id {
+            validator.id("team_id", team_id);
+        }
+        validator.finish()
+    }
+}
+
+async fn check_team(conn: &mut DbConnection, team_id: Option<i32>) -> Result<(), ApiError> {
+    match team_id {
+        Some(team_id) if Team::read(conn, team_id).await?.is_none() => {
+            Err(ApiError::Validation(vec![FieldError { field: "team_id", message: "must be an existing team".to_string() }]))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn duplicate_name(project_name: &str) -> String {
+    format!("A project named '{}' already exists", project_name)
+}
+
+async fn require_project(conn: &mut DbConnection, id: i32) -> Result<(), ApiError> {
+    match Project::read(conn, id).await? {
+        Some(_) => Ok(()),
+        None => Err(ApiError::not_found("Project")),
+    }
+}
+
+// Sorted by name unless ?sort= says otherwise.
+#[get("/projects?<paging..>")]
+pub async fn get_projects(db: TenantDb, paging: PageQuery) -> Result<Json<Page<Project>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(PROJECT_SORT_COLUMNS)?;
+    let mut conn = db.get().await?;
+    Ok(Json(Project::read_page(&mut conn, page, per_page, &sort).await?))
+}
+
+#[get("/projects/<id>")]
+pub async fn get_project(id: i32, db: TenantDb, validators: CacheValidators) -> Result<Cached<Project>, ApiError> {
+    let mut conn = db.get().await?;
+    Project::read(&mut conn, id).await?
+        .map(|row| validators.respond(row))
+        .ok_or_else(|| ApiError::not_found("Project"))
+}
+
+#[post("/projects", data = "<project>")]
+pub async fn create_project(db: TenantDb, manager: ManagerUser, project: Json<ProjectInput>) -> Result<Json<Project>, ApiError> {
+    project.validate()?;
+    let mut conn = db.get().await?;
+    check_team(&mut conn, project.team_id).await?;
+    let new_project = NewProject {
+        project_name: project.project_name.trim(),
+        description: project.description(),
+        team_id: project.team_id,
+    };
+    let saved = Project::create_audited(&mut conn, Some(manager.user_id), new_project).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(project.project_name.trim())))?;
+    Ok(Json(saved))
+}
+
+#[put("/projects/<id>", data = "<project>")]
+pub async fn update_project(id: i32, db: TenantDb, manager: ManagerUser, if_match: IfMatch, project: Json<ProjectInput>) -> Result<Json<Project>, ApiError> {
+    project.validate()?;
+    let mut conn = db.get().await?;
+    check_team(&mut conn, project.team_id).await?;
+    let updated_project = NewProject {
+        project_name: project.project_name.trim(),
+        description: project.description(),
+        team_id: project.team_id,
+    };
+    let saved = Project::update_audited(&mut conn, Some(manager.user_id), id, if_match.expected(), updated_project).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(project.project_name.trim())))?;
+    Ok(Json(saved))
+}
+
+// The project's tasks stay, just without a project.
+#[delete("/projects/<id>")]
+pub async fn delete_project(id: i32, db: TenantDb, manager: ManagerUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match Project::delete_audited(&mut conn, Some(manager.user_id), id).await? {
+        0 => Err(ApiError::not_found("Project")),
+        count => Ok(Json(count)),
+    }
+}
+
+// Same ordering as GET /tasks: most urgent first unless ?sort= says otherwise.
+#[get("/projects/<id>/tasks?<paging..>")]
+pub async fn get_project_tasks(id: i32, db: TenantDb, paging: PageQuery) -> Result<Json<Page<Linked<Task>>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(TASK_SORT_COLUMNS)?;
+    let mut conn = db.get().await?;
+    require_project(&mut conn, id).await?;
+    let filter = TaskFilter { project_id: Some(id), ..TaskFilter::default() };
+    let tasks = Task::read_page_filtered(&mut conn, &filter, page, per_page, &sort).await?;
+    Ok(Json(Page::new(linked_all(tasks.items), tasks.page, tasks.per_page, tasks.total)))
+}
+
+// GET /board limited to the project's tasks; every status still gets a column.
+#[get("/projects/<id>/board")]
+pub async fn get_project_board(id: i32, db: TenantDb) -> Result<Json<Vec<BoardColumn>>, ApiError> {
+    let mut conn = db.get().await?;
+    require_project(&mut conn, id).await?;
+    Ok(Json(board::read_board(&mut conn, Some(id)).await?))
+}
@@ -0,0 +1,509 @@
+use std::sync::Arc;
+use std::time::Duration;
+use rocket::{serde::json::Json, State, get, post, delete};
+use rocket::fairing::AdHoc;
+use rocket::serde::{Deserialize, Serialize};
+use rocket::serde::json::serde_json::{self, json};
+use rocket::tokio::sync::broadcast::error::RecvError;
+use tasks_db_lib::DbConnection;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use reqwest::{StatusCode, Url};
+use reqwest::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE};
+use ring::{aead, agreement, hkdf};
+use ring::error::Unspecified;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{EcdsaKeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::models::{NewPushSubscription, NotificationPreference, PushSubscription, Task, TaskStatus, Tenant, User};
+use tasks_db_lib::overdue;
+use tasks_db_lib::preferences::{self, Channel};
+use crate::error::ApiError;
+use crate::shutdown::Drain;
+use crate::events::{Change, EventBus, ASSIGNMENT_CREATED, ASSIGNMENT_DELETED, ASSIGNMENT_STATUS_CHANGED};
+use crate::overdue::OverdueConfig;
+use crate::tenancy::{DbPool, TenantDb};
+use crate::auth::AuthenticatedUser;
+use crate::validation::{FieldError, Validate, Validator, MAX_URL_LEN};
+use crate::webhooks::{refuse_private_host, PublicResolver};
+
+const DEFAULT_SUBJECT: &str = "mailto:tasks@localhost";
+const DEFAULT_TTL_SECONDS: u64 = 24 * 60 * 60;
+const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+const DEFAULT_SCAN_MINUTES: u64 = 60;
+const DEFAULT_DUE_SOON_DAYS: u64 = 1;
+// how long each VAPID token is good for; push services refuse ones over 24 hours
+const TOKEN_LIFETIME_SECONDS: i64 = 12 * 60 * 60;
+// the encrypted body is a single record, which has to fit in what push services accept
+const RECORD_SIZE: u32 = 4096;
+const MAX_PAYLOAD_LEN: usize = RECORD_SIZE as usize - 103;
+
+// VAPID_PUBLIC_KEY and VAPID_PRIVATE_KEY are the server's P-256 key pair, base64url-encoded the
+// way `npx web-push generate-vapid-keys` prints them. Without them no push is sent at all.
+// VAPID_SUBJECT is how a push service can reach whoever runs the server, a mailto: or https: URL.
+// PUSH_TTL_SECONDS is how long a push service keeps a message for a browser that is offline.
+// PUSH_TIMEOUT_SECONDS is how long one push may take before it counts as failed.
+// PUSH_SCAN_MINUTES is how often to look for assignments coming due.
+// PUSH_DUE_SOON_DAYS is how many days ahead of the due date the reminder goes out.
+// PUSH_ALLOW_PRIVATE_HOSTS=true lets subscriptions point at loopback and private addresses, for
+// trying them out against a receiver on the same machine; leave it off anywhere else.
+#[derive(Clone)]
+pub struct PushConfig {
+    pub vapid: Option<Arc<Vapid>>,
+    pub ttl_seconds: u64,
+    pub scan_every: Duration,
+    pub due_soon_days: u64,
+    pub allow_private_hosts: bool,
+    pub http: reqwest::Client,
+}
+
+impl PushConfig {
+    pub fn from_env() -> PushConfig {
+        let number = |name: &str, default: u64| std::env::var(name)
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default);
+        let vapid = match (std::env::var("VAPID_PUBLIC_KEY"), std::env::var("VAPID_PRIVATE_KEY")) {
+            (Ok(public_key), Ok(private_key)) if !public_key.is_empty() && !private_key.is_empty() => {
+                let subject = std::env::var("VAPID_SUBJECT").unwrap_or_else(|_| DEFAULT_SUBJECT.to_string());
+                let vapid = Vapid::new(&public_key, &private_key, &subject)
+                    .unwrap_or_else(|e| panic!("VAPID_PUBLIC_KEY and VAPID_PRIVATE_KEY {}", e));
+                Some(Arc::new(vapid))
+            }
+            _ => None,
+        };
+        let allow_private_hosts = std::env::var("PUSH_ALLOW_PRIVATE_HOSTS").is_ok_and(|allow| allow == "true");
+        // Redirects aren't followed, since a public host could send the request on to a private one.
+        let mut http = reqwest::Client::builder()
+            .user_agent("rocket_app")
+            .timeout(Duration::from_secs(number("PUSH_TIMEOUT_SECONDS", DEFAULT_TIMEOUT_SECONDS).max(1)))
+            .redirect(reqwest::redirect::Policy::none());
+        if !allow_private_hosts {
+            http = http.dns_resolver(Arc::new(PublicResolver));
+        }
+        PushConfig {
+            vapid,
+            ttl_seconds: number("PUSH_TTL_SECONDS", DEFAULT_TTL_SECONDS),
+            scan_every: Duration::from_secs(number("PUSH_SCAN_MINUTES", DEFAULT_SCAN_MINUTES).max(1) * 60),
+            due_soon_days: number("PUSH_DUE_SOON_DAYS", DEFAULT_DUE_SOON_DAYS),
+            allow_private_hosts,
+            http: http.build().expect("Failed to build HTTP client."),
+        }
+    }
+
+    async fn refuse_host(&self, url: &Url) -> Option<&'static str> {
+        if self.allow_private_hosts {
+            return None;
+        }
+        refuse_private_host(url).await
+    }
+
+    // Sends the message to every browser the user has subscribed, and returns whether any push
+    // service took it. Subscriptions the push service says are gone are removed; other failures
+    // are logged rather than passed up, so one bad browser doesn't hold up the rest.
+    async fn send(&self, pool: &DbPool, tenant_id: i32, user_id: i32, message: &PushMessage) -> Result<bool, ApiError> {
+        let Some(vapid) = &self.vapid else {
+            return Ok(false);
+        };
+        let subscriptions = {
+            let mut conn = pool.get_in_tenant(tenant_id).await?;
+            PushSubscription::read_all_for_user(&mut conn, user_id).await?
+        };
+        let payload = serde_json::to_vec(message).map_err(|e| ApiError::Internal(e.to_string()))?;
+        let mut delivered = false;
+        for subscription in subscriptions {
+            match self.push(vapid, &subscription, &payload).await {
+                Ok(status) if status.is_success() => delivered = true,
+                Ok(StatusCode::NOT_FOUND | StatusCode::GONE) => {
+                    let mut conn = pool.get_in_tenant(tenant_id).await?;
+                    PushSubscription::delete_by_endpoint(&mut conn, &subscription.endpoint).await?;
+                }
+                Ok(status) => tracing::warn!("Push to subscription {} was refused: {}", subscription.push_subscription_id, status),
+                Err(e) => tracing::error!("Push to subscription {} failed: {}", subscription.push_subscription_id, e),
+            }
+        }
+        Ok(delivered)
+    }
+
+    async fn push(&self, vapid: &Vapid, subscription: &PushSubscription, payload: &[u8]) -> Result<StatusCode, String> {
+        let endpoint = Url::parse(&subscription.endpoint).map_err(|e| e.to_string())?;
+        let decode = |key: &str| URL_SAFE_NO_PAD.decode(key.trim_end_matches('=')).map_err(|e| e.to_string());
+        let body = encrypt(&decode(&subscription.p256dh)?, &decode(&subscription.auth)?, payload)
+            .map_err(|_| "the subscription's keys can't be encrypted for".to_string())?;
+        let response = self.http.post(endpoint.clone())
+            .header(AUTHORIZATION, vapid.authorization(&endpoint)?)
+            .header(CONTENT_ENCODING, "aes128gcm")
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header("TTL", self.ttl_seconds)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(response.status())
+    }
+}
+
+// The server's identity to push services (RFC 8292): every push carries a token signed with
+// the private key, and the public key is what browsers subscribe with.
+pub struct Vapid {
+    key_pair: EcdsaKeyPair,
+    public_key: String,
+    subject: String,
+}
+
+impl Vapid {
+    pub fn new(public_key: &str, private_key: &str, subject: &str) -> Result<Vapid, String> {
+        let decode = |key: &str| URL_SAFE_NO_PAD.decode(key.trim_end_matches('=')).map_err(|_| "must be base64url".to_string());
+        let key_pair = EcdsaKeyPair::from_private_key_and_public_key(&ECDSA_P256_SHA256_FIXED_SIGNING, &decode(private_key)?, &decode(public_key)?, &SystemRandom::new())
+            .map_err(|e| format!("must be a P-256 key pair ({})", e))?;
+        Ok(Vapid {
+            key_pair,
+            public_key: public_key.trim_end_matches('=').to_string(),
+            subject: subject.to_string(),
+        })
+    }
+
+    // `Authorization: vapid t=<JWT>, k=<public key>`, with the token for the push service the
+    // endpoint belongs to.
+    fn authorization(&self, endpoint: &Url) -> Result<String, String> {
+        let header = URL_SAFE_NO_PAD.encode(json!({ "typ": "JWT", "alg": "ES256" }).to_string());
+        let claims = URL_SAFE_NO_PAD.encode(json!({
+            "aud": endpoint.origin().ascii_serialization(),
+            "exp": Utc::now().timestamp() + TOKEN_LIFETIME_SECONDS,
+            "sub": self.subject,
+        }).to_string());
+        let signing_input = format!("{}.{}", header, claims);
+        let signature = self.key_pair.sign(&SystemRandom::new(), signing_input.as_bytes())
+            .map_err(|_| "signing the VAPID token failed".to_string())?;
+        Ok(format!("vapid t={}.{}, k={}", signing_input, URL_SAFE_NO_PAD.encode(signature.as_ref()), self.public_key))
+    }
+}
+
+struct Len(usize);
+
+impl hkdf::KeyType for Len {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+fn hkdf_sha256(salt: &[u8], secret: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, Unspecified> {
+    let mut okm = vec![0; len];
+    hkdf::Salt::new(hkdf::HKDF_SHA256, salt).extract(secret).expand(&[info], Len(len))?.fill(&mut okm)?;
+    Ok(okm)
+}
+
+// Encrypts the payload for one browser as RFC 8291 lays out: a fresh key pair is agreed with
+// the browser's p256dh key and mixed with its auth secret, and the result is a single
+// aes128gcm record (RFC 8188) whose header carries the salt and the fresh public key.
+fn encrypt(p256dh: &[u8], auth: &[u8], payload: &[u8]) -> Result<Vec<u8>, Unspecified> {
+    if payload.len() > MAX_PAYLOAD_LEN {
+        return Err(Unspecified);
+    }
+    let rng = SystemRandom::new();
+    let private_key = agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng)?;
+    let public_key = private_key.compute_public_key()?;
+    let browser_key = agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, p256dh);
+    let key_info = [b"WebPush: info\0".as_slice(), p256dh, public_key.as_ref()].concat();
+    let ikm = agreement::agree_ephemeral(private_key, &browser_key, |shared| hkdf_sha256(auth, shared, &key_info, 32))??;
+    let mut salt = [0u8; 16];
+    rng.fill(&mut salt)?;
+    let (key, nonce) = content_key(&salt, &ikm)?;
+    // the 0x02 delimiter marks the last (and only) record, with no padding after it
+    let mut record = [payload, &[2]].concat();
+    key.seal_in_place_append_tag(nonce, aead::Aad::empty(), &mut record)?;
+    Ok([&salt, RECORD_SIZE.to_be_bytes().as_slice(), &[public_key.as_ref().len() as u8], public_key.as_ref(), &record].concat())
+}
+
+fn content_key(salt: &[u8], ikm: &[u8]) -> Result<(aead::LessSafeKey, aead::Nonce), Unspecified> {
+    let cek = hkdf_sha256(salt, ikm, b"Content-Encoding: aes128gcm\0", 16)?;
+    let nonce = hkdf_sha256(salt, ikm, b"Content-Encoding: nonce\0", 12)?;
+    let key = aead::LessSafeKey::new(aead::UnboundKey::new(&aead::AES_128_GCM, &cek)?);
+    Ok((key, aead::Nonce::try_assume_unique_for_key(&nonce)?))
+}
+
+// What the service worker gets in its push event, as JSON.
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct PushMessage {
+    pub event_type: &'static str,
+    pub title: String,
+    pub body: String,
+    pub task_id: i32,
+}
+
+// The body of POST /users/me/push_subscriptions, as the browser's PushSubscription.toJSON()
+// gives it (expirationTime and anything else it adds are ignored).
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PushSubscriptionInput {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+impl Validate for PushSubscriptionInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        let scheme = Url::parse(&self.endpoint).map(|url| url.scheme().to_string());
+        if !matches!(scheme.as_deref(), Ok("http" | "https")) {
+            validator.error("endpoint", "must be an http or https URL");
+        } else if self.endpoint.len() > MAX_URL_LEN {
+            validator.error("endpoint", format!("must be at most {} characters", MAX_URL_LEN));
+        }
+        let decoded_len = |key: &str| URL_SAFE_NO_PAD.decode(key.trim_end_matches('=')).ok().map(|bytes| (bytes.len(), bytes.first().copied()));
+        if decoded_len(&self.keys.p256dh) != Some((65, Some(4))) {
+            validator.error("keys.p256dh", "must be an uncompressed P-256 public key, base64url-encoded");
+        }
+        if !matches!(decoded_len(&self.keys.auth), Some((16, _))) {
+            validator.error("keys.auth", "must be 16 bytes, base64url-encoded");
+        }
+        validator.finish()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VapidPublicKey {
+    pub public_key: String,
+}
+
+// The applicationServerKey for PushManager.subscribe().
+#[get("/push/public_key")]
+pub async fn get_push_public_key(config: &State<PushConfig>) -> Result<Json<VapidPublicKey>, ApiError> {
+    config.vapid.as_ref()
+        .map(|vapid| Json(VapidPublicKey { public_key: vapid.public_key.clone() }))
+        .ok_or_else(|| ApiError::NotFound("Web Push is not set up on this server".to_string()))
+}
+
+#[get("/users/me/push_subscriptions")]
+pub async fn get_push_subscriptions(db: TenantDb, auth: AuthenticatedUser) -> Result<Json<Vec<PushSubscription>>, ApiError> {
+    let mut conn = db.get().await?;
+    Ok(Json(PushSubscription::read_all_for_user(&mut conn, auth.user_id).await?))
+}
+
+// Registering a browser that already is (as someone else, say) moves it to the caller.
+#[post("/users/me/push_subscriptions", data = "<subscription>")]
+pub async fn create_push_subscription(db: TenantDb, config: &State<PushConfig>, auth: AuthenticatedUser, subscription: Json<PushSubscriptionInput>) -> Result<Json<PushSubscription>, ApiError> {
+    subscription.validate()?;
+    let url = Url::parse(&subscription.endpoint).map_err(|_| ApiError::BadRequest("endpoint is not a URL".to_string()))?;
+    if let Some(message) = config.refuse_host(&url).await {
+        return Err(ApiError::Validation(vec![FieldError { field: "endpoint", message: message.to_string() }]));
+    }
+    let mut conn = db.get().await?;
+    let new_subscription = NewPushSubscription {
+        user_id: auth.user_id,
+        endpoint: &subscription.endpoint,
+        p256dh: subscription.keys.p256dh.trim_end_matches('='),
+        auth: subscription.keys.auth.trim_end_matches('='),
+    };
+    Ok(Json(PushSubscription::save(&mut conn, new_subscription).await?))
+}
+
+#[delete("/users/me/push_subscriptions/<id>")]
+pub async fn delete_push_subscription(id: i32, db: TenantDb, auth: AuthenticatedUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match PushSubscription::delete(&mut conn, id, auth.user_id).await? {
+        0 => Err(ApiError::not_found("Push subscription")),
+        count => Ok(Json(count)),
+    }
+}
+
+// Who to push to about a change and what to tell them: the assignee hears about their
+// assignment being made, moved or taken away, if they left push on for assignment.created,
+// assignment.updated or assignment.deleted respectively.
+async fn message_for(conn: &mut DbConnection, change: &Change) -> Result<Option<(i32, PushMessage)>, ApiError> {
+    let id = |field: &str| change.data[field].as_i64().map(|id| id as i32);
+    let (Some(user_id), Some(task_id)) = (id("user_id"), id("task_id")) else {
+        return Ok(None);
+    };
+    let (Some(assignee), Some(task)) = (User::read(conn, user_id).await?, Task::read(conn, task_id).await?) else {
+        return Ok(None);
+    };
+    if !assignee.active {
+        return Ok(None);
+    }
+    let (event_type, title, body) = match change.event_type {
+        ASSIGNMENT_CREATED => ("assignment.created", "New assignment", format!("You've been assigned to {}", task.task_name)),
+        ASSIGNMENT_STATUS_CHANGED if id("from_task_status_id") != id("task_status_id") => {
+            let status = match id("task_status_id") {
+                Some(task_status_id) => TaskStatus::read(conn, task_status_id).await?,
+                None => None,
+            };
+            let status_name = status.map(|status| status.status_name).unwrap_or_default();
+            ("assignment.updated", "Assignment moved", format!("{} is now {}", task.task_name, status_name))
+        }
+        ASSIGNMENT_DELETED => ("assignment.deleted", "Assignment removed", format!("You're no longer assigned to {}", task.task_name)),
+        _ => return Ok(None),
+    };
+    if NotificationPreference::allowed(conn, vec![user_id], event_type, Channel::Push).await?.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((user_id, PushMessage { event_type, title: title.to_string(), body, task_id })))
+}
+
+async fn on_change(pool: &DbPool, config: &PushConfig, change: &Change) -> Result<(), ApiError> {
+    let message = {
+        let mut conn = pool.get_in_tenant(change.tenant_id).await?;
+        message_for(&mut conn, change).await?
+    };
+    if let Some((user_id, message)) = message {
+        config.send(pool, change.tenant_id, user_id, &message).await?;
+    }
+    Ok(())
+}
+
+// Each assignee is reminded once per due date, like by email; a reminder that reaches none of
+// their browsers is tried again on the next scan.
+async fn remind_tenant(pool: &DbPool, config: &PushConfig, terminal_statuses: &[String], tenant_id: i32) -> Result<(), ApiError> {
+    let today = Utc::now().date_naive();
+    let until = today + chrono::Days::new(config.due_soon_days);
+    let due = {
+        let mut conn = pool.get_in_tenant(tenant_id).await?;
+        let due = overdue::find_due_soon(&mut conn, today, until, terminal_statuses, Channel::Push).await?;
+        let allowed = NotificationPreference::allowed(&mut conn, due.iter().map(|assignment| assignment.user_id).collect(), preferences::DUE_SOON, Channel::Push).await?;
+        due.into_iter().filter(|assignment| allowed.contains(&assignment.user_id)).collect::<Vec<_>>()
+    };
+    for assignment in due {
+        let message = PushMessage {
+            event_type: preferences::DUE_SOON,
+            title: "Due soon".to_string(),
+            body: format!("{} is due {}", assignment.task_name, assignment.due_date),
+            task_id: assignment.task_id,
+        };
+        if config.send(pool, tenant_id, assignment.user_id, &message).await? {
+            let mut conn = pool.get_in_tenant(tenant_id).await?;
+            overdue::mark_reminded(&mut conn, assignment.task_id, assignment.user_id, assignment.due_date, Channel::Push).await?;
+        }
+    }
+    Ok(())
+}
+
+// One tenant at a time, so a failure in one doesn't hold up the rest.
+async fn remind_due_soon(pool: &DbPool, config: &PushConfig, terminal_statuses: &[String]) -> Result<(), ApiError> {
+    let tenant_ids = Tenant::read_all_ids(&mut *pool.get().await?).await?;
+    for tenant_id in tenant_ids {
+        if let Err(e) = remind_tenant(pool, config, terminal_statuses, tenant_id).await {
+            tracing::error!("Due date pushes of tenant {} failed: {:?}", tenant_id, e);
+        }
+    }
+    Ok(())
+}
+
+// Pushes assignment changes as they come in on the EventBus, and due date reminders every
+// PUSH_SCAN_MINUTES. Does nothing unless the VAPID keys are set. Like email, changes made while
+// the server is down, or that the listener falls behind on, are not pushed.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Web Push", |rocket| Box::pin(async move {
+        let (Some(pool), Some(config), Some(overdue_config), Some(bus), Some(drain)) = (
+            rocket.state::<DbPool>().cloned(),
+            rocket.state::<PushConfig>().cloned(),
+            rocket.state::<OverdueConfig>().cloned(),
+            rocket.state::<EventBus>(),
+            rocket.state::<Drain>(),
+        ) else {
+            return;
+        };
+        if config.vapid.is_none() {
+            return;
+        }
+        let terminal_statuses = overdue_config.terminal_statuses;
+        let mut changes = bus.subscribe();
+        let (listener_pool, listener_config) = (pool.clone(), config.clone());
+        let (mut listener_stop, mut reminder_stop) = (drain.stop(), drain.stop());
+        drain.spawn("Web Push listener", async move {
+            while let Some(change) = listener_stop.recv(&mut changes).await {
+                match change {
+                    Ok(change) => {
+                        if let Err(e) = on_change(&listener_pool, &listener_config, &change).await {
+                            tracing::error!("Pushes for {} failed: {:?}", change.event_type, e);
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => tracing::warn!("Web Push skipped {} changes", missed),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+        drain.spawn("Web Push reminders", async move {
+            let mut interval = rocket::tokio::time::interval(config.scan_every);
+            while reminder_stop.tick(&mut interval).await {
+                if let Err(e) = remind_due_soon(&pool, &config, &terminal_statuses).await {
+                    tracing::error!("Due date pushes failed: {:?}", e);
+                }
+            }
+        });
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+    use tasks_db_lib::models::{NewUserTask, UserTask};
+    use tasks_db_lib::test_support::{conn, create_task};
+
+    const PUBLIC_KEY: &str = "BPrks9X8TC8OZJYN0p4j91MGbZYmvrCbi_KNL1E4RLgYKM3mtFLR235qZGH71nJ1n47gLOfVAaZMaG4QdL5v_o4";
+    const PRIVATE_KEY: &str = "Frzjqhz717180rfVTAtFcFvVDli7Dd0WCIb6B4N3k-g";
+
+    #[test]
+    fn the_browser_can_decrypt_what_is_sent() {
+        let rng = SystemRandom::new();
+        let browser_key = agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng).unwrap();
+        let p256dh = browser_key.compute_public_key().unwrap().as_ref().to_vec();
+        let auth = [7u8; 16];
+        let body = encrypt(&p256dh, &auth, b"{\"title\":\"Hi\"}").unwrap();
+
+        // the receiving side of RFC 8291, from the header fields out
+        let (salt, rest) = body.split_at(16);
+        assert_eq!(rest[..4], RECORD_SIZE.to_be_bytes());
+        let key_len = rest[4] as usize;
+        let (server_key, record) = rest[5..].split_at(key_len);
+        let key_info = [b"WebPush: info\0".as_slice(), &p256dh, server_key].concat();
+        let server_key = agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, server_key);
+        let ikm = agreement::agree_ephemeral(browser_key, &server_key, |shared| hkdf_sha256(&auth, shared, &key_info, 32)).unwrap().unwrap();
+        let (key, nonce) = content_key(salt, &ikm).unwrap();
+        let mut record = record.to_vec();
+        let plaintext = key.open_in_place(nonce, aead::Aad::empty(), &mut record).unwrap();
+        assert_eq!(plaintext, b"{\"title\":\"Hi\"}\x02");
+    }
+
+    #[test]
+    fn the_vapid_token_is_signed_for_the_push_service() {
+        let vapid = Vapid::new(PUBLIC_KEY, PRIVATE_KEY, "mailto:ops@example.com").unwrap();
+        let header = vapid.authorization(&Url::parse("https://fcm.googleapis.com/fcm/send/abc").unwrap()).unwrap();
+        let (token, key) = header.strip_prefix("vapid t=").unwrap().split_once(", k=").unwrap();
+        assert_eq!(key, PUBLIC_KEY);
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        let public_key = UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, URL_SAFE_NO_PAD.decode(PUBLIC_KEY).unwrap());
+        public_key.verify(signing_input.as_bytes(), &URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(signing_input.split_once('.').unwrap().1).unwrap()).unwrap();
+        assert_eq!(claims["aud"], "https://fcm.googleapis.com");
+        assert_eq!(claims["sub"], "mailto:ops@example.com");
+
+        assert!(Vapid::new(PUBLIC_KEY, "not a key", "mailto:ops@example.com").is_err());
+    }
+
+    #[rocket::async_test]
+    async fn assignees_hear_about_their_own_assignments() {
+        let mut conn = conn().await;
+        let task = create_task(&mut conn, "Ship it").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 1, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        let change = |event_type, from: i32, to: i32| Change {
+            tenant_id: 1,
+            event_type,
+            data: json!({ "user_id": 1, "task_id": task.task_id, "task_status_id": to, "from_task_status_id": from }),
+        };
+
+        let (user_id, message) = message_for(&mut conn, &change(ASSIGNMENT_STATUS_CHANGED, 1, 2)).await.unwrap().unwrap();
+        assert_eq!((user_id, message.body.as_str()), (1, "Ship it is now In Progress"));
+        assert!(message_for(&mut conn, &change(ASSIGNMENT_STATUS_CHANGED, 2, 2)).await.unwrap().is_none());
+        assert_eq!(message_for(&mut conn, &change(ASSIGNMENT_CREATED, 1, 1)).await.unwrap().unwrap().1.event_type, "assignment.created");
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use rocket::{Data, Request, Response};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Header, Status};
+use rocket::http::uri::Origin;
+use rocket::serde::json::serde_json;
+use crate::api_keys::{hash_key, API_KEY_HEADER};
+use crate::error::ProblemDetails;
+
+const DEFAULT_PER_MINUTE: u32 = 300;
+const DEFAULT_BURST: u32 = 60;
+// Past this many clients, buckets that have filled back up are forgotten: a full bucket is what
+// a client we've never seen would get anyway.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+// Rate-limited requests are routed here, where nothing is mounted, so no handler runs for them.
+const LIMITED_PATH: &str = "/rate-limited";
+
+// RATE_LIMIT_PER_MINUTE is the steady rate each client may keep up; RATE_LIMIT_BURST is how many
+// requests it can make at once after being idle. 0 per minute turns limiting off.
+pub struct RateLimitConfig {
+    per_minute: u32,
+    burst: u32,
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> RateLimitConfig {
+        let per_minute = std::env::var("RATE_LIMIT_PER_MINUTE")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(DEFAULT_PER_MINUTE);
+        let burst = std::env::var("RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .filter(|n: &u32| *n > 0)
+            .unwrap_or(DEFAULT_BURST);
+        RateLimitConfig { per_minute, burst }
+    }
+
+    fn tokens_per_second(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+// One token bucket per client, in memory: each request takes a token, and tokens come back at
+// the configured rate up to the burst size. Counts start over when the server restarts.
+#[derive(Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    // Takes a token from the client's bucket, or says how long until there will be one.
+    fn take(&self, config: &RateLimitConfig, client: &str, now: Instant) -> Result<(), Duration> {
+        let rate = config.tokens_per_second();
+        let capacity = f64::from(config.burst);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate < capacity);
+        }
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket { tokens: capacity, updated: now });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+    }
+}
+
+// Who a request counts against: the API key it carries (hashed, as keys are everywhere else),
+// otherwise the address it came from.
+fn client(req: &Request<'_>) -> Option<String> {
+    if let Some(raw_key) = req.headers().get_one(API_KEY_HEADER) {
+        return Some(format!("key:{}", hash_key(raw_key)));
+    }
+    req.client_ip().map(|ip| format!("ip:{}", ip))
+}
+
+// For a request over the limit: how many seconds the client was told to wait, and the URI it
+// asked for before being routed away.
+struct Limited(Option<(u64, String)>);
+
+// Limits every /api request with the RateLimiter in managed state. A client over its limit gets
+// 429 Too Many Requests with Retry-After, and the request never reaches a handler.
+pub struct RateLimiting;
+
+#[rocket::async_trait]
+impl Fairing for RateLimiting {
+    fn info(&self) -> Info {
+        Info { name: "Rate limiting", kind: Kind::Request | Kind::Response }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        let (Some(config), Some(limiter)) = (req.rocket().state::<RateLimitConfig>(), req.rocket().state::<RateLimiter>()) else {
+            return;
+        };
+        if config.per_minute == 0 || !req.uri().path().starts_with("/api") {
+            return;
+        }
+        let Some(client) = client(req) else {
+            return;
+        };
+        if let Err(wait) = limiter.take(config, &client, Instant::now()) {
+            let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            let requested = req.uri().to_string();
+            req.local_cache(|| Limited(Some((seconds, requested))));
+            req.set_uri(Origin::parse(LIMITED_PATH).expect("valid path"));
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Limited(Some((seconds, requested))) = req.local_cache(|| Limited(None)) else {
+            return;
+        };
+        let mut problem = ProblemDetails::new(Status::TooManyRequests, format!("Too many requests; try again in {} seconds", seconds), req);
+        problem.instance = requested.clone();
+        let body = serde_json::to_vec(&problem).unwrap_or_default();
+        res.set_status(Status::TooManyRequests);
+        res.set_header(ContentType::new("application", "problem+json"));
+        res.set_header(Header::new("Retry-After", seconds.to_string()));
+        res.set_sized_body(body.len(), std::io::Cursor::new(body));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_bucket_allows_a_burst_then_refills_at_the_rate() {
+        let config = RateLimitConfig { per_minute: 60, burst: 2 };
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        assert!(limiter.take(&config, "ip:10.0.0.1", start).is_ok());
+        assert!(limiter.take(&config, "ip:10.0.0.1", start).is_ok());
+        let wait = limiter.take(&config, "ip:10.0.0.1", start).unwrap_err();
+        assert_eq!(wait.as_secs_f64().round(), 1.0);
+        assert!(limiter.take(&config, "ip:10.0.0.2", start).is_ok());
+
+        let later = start + Duration::from_secs(1);
+        assert!(limiter.take(&config, "ip:10.0.0.1", later).is_ok());
+        assert!(limiter.take(&config, "ip:10.0.0.1", later).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+use rocket::{serde::json::Json, post};
+use rocket::fairing::AdHoc;
+use chrono::Utc;
+use tasks_db_lib::models::{Task, Tenant};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::recurrence;
+use crate::error::ApiError;
+use crate::shutdown::Drain;
+use crate::auth::ManagerUser;
+use crate::links::{linked, Linked};
+use crate::overdue::OverdueConfig;
+use crate::tenancy::{DbPool, TenantDb};
+
+const DEFAULT_SCAN_MINUTES: u64 = 5;
+
+// RECURRENCE_SCAN_MINUTES is how often the background job looks for finished recurring tasks.
+#[derive(Clone)]
+pub struct RecurrenceConfig {
+    pub scan_every: Duration,
+}
+
+impl RecurrenceConfig {
+    pub fn from_env() -> RecurrenceConfig {
+        let minutes = std::env::var("RECURRENCE_SCAN_MINUTES")
+            .ok()
+            .and_then(|m| m.parse().ok())
+            .filter(|m: &u64| *m > 0)
+            .unwrap_or(DEFAULT_SCAN_MINUTES);
+        RecurrenceConfig { scan_every: Duration::from_secs(minutes * 60) }
+    }
+}
+
+// One tenant at a time, so a failure in one doesn't hold up the rest.
+async fn materialize(pool: &DbPool, overdue: &OverdueConfig) -> Result<usize, ApiError> {
+    let mut created = 0;
+    for tenant_id in Tenant::read_all_ids(&mut *pool.get().await?).await? {
+        let materialized: Result<_, ApiError> = async {
+            Ok(recurrence::materialize_next(&mut *pool.get_in_tenant(tenant_id).await?, Utc::now().date_naive(), &overdue.terminal_statuses).await?)
+        }.await;
+        match materialized {
+            Ok(tasks) => created += tasks.len(),
+            Err(e) => tracing::error!("Recurring task scan of tenant {} failed: {:?}", tenant_id, e),
+        }
+    }
+    Ok(created)
+}
+
+// Every RECURRENCE_SCAN_MINUTES, creates the next occurrence of each recurring task that has
+// been finished (by OVERDUE_TERMINAL_STATUSES). A failed run is retried on the next tick.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Recurring tasks", |rocket| Box::pin(async move {
+        let (Some(pool), Some(config), Some(overdue), Some(drain)) = (
+            rocket.state::<DbPool>().cloned(),
+            rocket.state::<RecurrenceConfig>().cloned(),
+            rocket.state::<OverdueConfig>().cloned(),
+            rocket.state::<Drain>(),
+        ) else {
+            return;
+        };
+        let mut stop = drain.stop();
+        drain.spawn("Recurring tasks", async move {
+            let mut interval = rocket::tokio::time::interval(config.scan_every);
+            while stop.tick(&mut interval).await {
+                if let Err(e) = materialize(&pool, &overdue).await {
+                    tracing::error!("Recurring task scan failed: {:?}", e);
+                }
+            }
+        });
+    }))
+}
+
+async fn set_paused(db: TenantDb, actor: i32, id: i32, paused: bool) -> Result<Json<Linked<Task>>, ApiError> {
+    let mut conn = db.get().await?;
+    let task = Task::read(&mut conn, id).await?.ok_or_else(|| ApiError::not_found("Task"))?;
+    if task.recurrence.is_none() {
+        return Err(ApiError::Conflict(format!("Task {} does not recur", id)));
+    }
+    Task::set_recurrence_paused(&mut conn, Some(actor), id, paused).await?
+        .map(|task| Json(linked(task)))
+        .ok_or_else(|| ApiError::not_found("Task"))
+}
+
+// While paused, finishing the task doesn't create the next one. Resuming picks the series
+// up again, including a task that was finished in the meantime.
+#[post("/tasks/<id>/recurrence/pause")]
+pub async fn pause_recurrence(id: i32, db: TenantDb, manager: ManagerUser) -> Result<Json<Linked<Task>>, ApiError> {
+    set_paused(db, manager.user_id, id, true).await
+}
+
+#[post("/tasks/<id>/recurrence/resume")]
+pub async fn resume_recurrence(id: i32, db: TenantDb, manager: ManagerUser) -> Result<Json<Linked<Task>>, ApiError> {
+    set_paused(db, manager.user_id, id, false).await
+}
@@ -0,0 +1,262 @@
+use tasks_db_lib::models::{AssignmentDetail, NewTask, NewUserTask, Project, Task, TaskDependency, TaskRevision, TaskStatus, Team, User, UserTask, UserTaskChanges};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::audit::AuditedCrud;
+use tasks_db_lib::filters::{AssignmentFilter, TaskFilter};
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::sorting::Sort;
+use crate::error::ApiError;
+use crate::tenancy::DbPool;
+
+// What the task and assignment handlers read and write, kept in managed state as
+// `Box<dyn TaskRepository>` and `Box<dyn AssignmentRepository>` so another backend or a test
+// double can stand in for the database without the handlers changing. Every method works
+// inside the tenant it is given, and `actor` is the user the audit log records.
+#[rocket::async_trait]
+pub trait TaskRepository: Send + Sync {
+    async fn read_page(&self, tenant_id: i32, filter: &TaskFilter, page: i64, per_page: i64, sort: &Sort) -> Result<Page<Task>, ApiError>;
+    async fn count(&self, tenant_id: i32) -> Result<i64, ApiError>;
+    async fn read(&self, tenant_id: i32, id: i32) -> Result<Option<Task>, ApiError>;
+    async fn create(&self, tenant_id: i32, actor: i32, new_task: NewTask<'_>) -> Result<Task, ApiError>;
+    async fn update(&self, tenant_id: i32, actor: i32, id: i32, expected_version: Option<i32>, updated_task: NewTask<'_>) -> Result<Task, ApiError>;
+    // How many rows went to the trash: 0 when there was no such task.
+    async fn delete(&self, tenant_id: i32, actor: i32, id: i32) -> Result<usize, ApiError>;
+    async fn restore(&self, tenant_id: i32, actor: i32, id: i32) -> Result<Option<Task>, ApiError>;
+    async fn duplicate(&self, tenant_id: i32, actor: i32, id: i32, with_assignments: bool) -> Result<Option<Task>, ApiError>;
+    async fn read_subtasks(&self, tenant_id: i32, id: i32) -> Result<Vec<Task>, ApiError>;
+    async fn count_completed_subtasks(&self, tenant_id: i32, id: i32, terminal_statuses: &[String]) -> Result<i64, ApiError>;
+    async fn read_history(&self, tenant_id: i32, id: i32, page: i64, per_page: i64) -> Result<Page<TaskRevision>, ApiError>;
+    async fn revert(&self, tenant_id: i32, actor: i32, id: i32, version: i32) -> Result<Option<Task>, ApiError>;
+    // Whether making `parent_task_id` the parent of `id` would put the task under itself.
+    async fn would_create_cycle(&self, tenant_id: i32, id: i32, parent_task_id: i32) -> Result<bool, ApiError>;
+    async fn project_exists(&self, tenant_id: i32, project_id: i32) -> Result<bool, ApiError>;
+}
+
+// Assignments are keyed by (user_id, task_id). The bulk methods report each item on its own,
+// as the crud layer does, and only fail as a whole when the batch couldn't run at all.
+#[rocket::async_trait]
+pub trait AssignmentRepository: Send + Sync {
+    async fn read_page(&self, tenant_id: i32, filter: &AssignmentFilter, page: i64, per_page: i64, sort: &Sort) -> Result<Page<UserTask>, ApiError>;
+    async fn read_page_joined(&self, tenant_id: i32, filter: &AssignmentFilter, page: i64, per_page: i64, sort: &Sort) -> Result<Page<(UserTask, User, Task, TaskStatus)>, ApiError>;
+    async fn read_details(&self, tenant_id: i32, filter: &AssignmentFilter, page: i64, per_page: i64, sort: &Sort) -> Result<Page<AssignmentDetail>, ApiError>;
+    async fn count(&self, tenant_id: i32, filter: &AssignmentFilter) -> Result<i64, ApiError>;
+    async fn read_by_user(&self, tenant_id: i32, user_id: i32, task_status_id: Option<i32>, page: i64, per_page: i64, sort: &Sort) -> Result<Page<UserTask>, ApiError>;
+    async fn read_by_task(&self, tenant_id: i32, task_id: i32, task_status_id: Option<i32>, page: i64, per_page: i64, sort: &Sort) -> Result<Page<UserTask>, ApiError>;
+    async fn read(&self, tenant_id: i32, id: (i32, i32)) -> Result<Option<UserTask>, ApiError>;
+    async fn create(&self, tenant_id: i32, actor: i32, user_task: NewUserTask) -> Result<UserTask, ApiError>;
+    async fn update(&self, tenant_id: i32, actor: i32, id: (i32, i32), expected_version: Option<i32>, user_task: NewUserTask) -> Result<UserTask, ApiError>;
+    async fn update_partial(&self, tenant_id: i32, actor: i32, id: (i32, i32), expected_version: Option<i32>, changes: UserTaskChanges) -> Result<UserTask, ApiError>;
+    async fn upsert(&self, tenant_id: i32, actor: i32, user_task: NewUserTask) -> Result<UserTask, ApiError>;
+    async fn delete(&self, tenant_id: i32, actor: i32, id: (i32, i32)) -> Result<usize, ApiError>;
+    async fn restore(&self, tenant_id: i32, actor: i32, id: (i32, i32)) -> Result<Option<UserTask>, ApiError>;
+    async fn create_many(&self, tenant_id: i32, actor: i32, user_tasks: Vec<NewUserTask>) -> Result<Vec<anyhow::Result<UserTask>>, ApiError>;
+    async fn update_many(&self, tenant_id: i32, actor: i32, user_tasks: Vec<NewUserTask>) -> Result<Vec<anyhow::Result<UserTask>>, ApiError>;
+    async fn delete_many(&self, tenant_id: i32, actor: i32, ids: Vec<(i32, i32)>) -> Result<Vec<anyhow::Result<usize>>, ApiError>;
+
+    // The rows an assignment points at, for the checks made before it is saved.
+    async fn read_user(&self, tenant_id: i32, user_id: i32) -> Result<Option<User>, ApiError>;
+    async fn read_task(&self, tenant_id: i32, task_id: i32) -> Result<Option<Task>, ApiError>;
+    async fn read_status(&self, tenant_id: i32, task_status_id: i32) -> Result<Option<TaskStatus>, ApiError>;
+    // Ids of the tasks blocking this one that aren't finished yet.
+    async fn open_blocker_ids(&self, tenant_id: i32, task_id: i32, terminal_statuses: &[String]) -> Result<Vec<i32>, ApiError>;
+    // The team the task's project belongs to, if it has one.
+    async fn team_for_task(&self, tenant_id: i32, task_id: i32) -> Result<Option<Team>, ApiError>;
+    async fn is_team_member(&self, tenant_id: i32, team_id: i32, user_id: i32) -> Result<bool, ApiError>;
+}
+
+// The default for both: the crud layer over a connection from the pool, checked out in the
+// tenant for each call.
+pub struct DieselRepository {
+    pool: DbPool,
+}
+
+impl DieselRepository {
+    pub fn new(pool: DbPool) -> DieselRepository {
+        DieselRepository { pool }
+    }
+}
+
+#[rocket::async_trait]
+impl TaskRepository for DieselRepository {
+    async fn read_page(&self, tenant_id: i32, filter: &TaskFilter, page: i64, per_page: i64, sort: &Sort) -> Result<Page<Task>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::read_page_filtered(&mut conn, filter, page, per_page, sort).await?)
+    }
+
+    async fn count(&self, tenant_id: i32) -> Result<i64, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::count(&mut conn).await?)
+    }
+
+    async fn read(&self, tenant_id: i32, id: i32) -> Result<Option<Task>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::read(&mut conn, id).await?)
+    }
+
+    async fn create(&self, tenant_id: i32, actor: i32, new_task: NewTask<'_>) -> Result<Task, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::create_audited(&mut conn, Some(actor), new_task).await?)
+    }
+
+    async fn update(&self, tenant_id: i32, actor: i32, id: i32, expected_version: Option<i32>, updated_task: NewTask<'_>) -> Result<Task, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::update_audited(&mut conn, Some(actor), id, expected_version, updated_task).await?)
+    }
+
+    async fn delete(&self, tenant_id: i32, actor: i32, id: i32) -> Result<usize, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::delete_audited(&mut conn, Some(actor), id).await?)
+    }
+
+    async fn restore(&self, tenant_id: i32, actor: i32, id: i32) -> Result<Option<Task>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::restore(&mut conn, Some(actor), id).await?)
+    }
+
+    async fn duplicate(&self, tenant_id: i32, actor: i32, id: i32, with_assignments: bool) -> Result<Option<Task>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::duplicate(&mut conn, Some(actor), id, with_assignments).await?)
+    }
+
+    async fn read_subtasks(&self, tenant_id: i32, id: i32) -> Result<Vec<Task>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::read_subtasks(&mut conn, id).await?)
+    }
+
+    async fn count_completed_subtasks(&self, tenant_id: i32, id: i32, terminal_statuses: &[String]) -> Result<i64, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::count_completed_subtasks(&mut conn, id, terminal_statuses).await?)
+    }
+
+    async fn read_history(&self, tenant_id: i32, id: i32, page: i64, per_page: i64) -> Result<Page<TaskRevision>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(TaskRevision::read_history(&mut conn, id, page, per_page).await?)
+    }
+
+    async fn revert(&self, tenant_id: i32, actor: i32, id: i32, version: i32) -> Result<Option<Task>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(TaskRevision::revert(&mut conn, Some(actor), id, version).await?)
+    }
+
+    async fn would_create_cycle(&self, tenant_id: i32, id: i32, parent_task_id: i32) -> Result<bool, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::would_create_cycle(&mut conn, id, parent_task_id).await?)
+    }
+
+    async fn project_exists(&self, tenant_id: i32, project_id: i32) -> Result<bool, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Project::read(&mut conn, project_id).await?.is_some())
+    }
+}
+
+#[rocket::async_trait]
+impl AssignmentRepository for DieselRepository {
+    async fn read_page(&self, tenant_id: i32, filter: &AssignmentFilter, page: i64, per_page: i64, sort: &Sort) -> Result<Page<UserTask>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::read_page_filtered(&mut conn, filter, page, per_page, sort).await?)
+    }
+
+    async fn read_page_joined(&self, tenant_id: i32, filter: &AssignmentFilter, page: i64, per_page: i64, sort: &Sort) -> Result<Page<(UserTask, User, Task, TaskStatus)>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::read_page_joined(&mut conn, filter, page, per_page, sort).await?)
+    }
+
+    async fn read_details(&self, tenant_id: i32, filter: &AssignmentFilter, page: i64, per_page: i64, sort: &Sort) -> Result<Page<AssignmentDetail>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(AssignmentDetail::read_page(&mut conn, filter, page, per_page, sort).await?)
+    }
+
+    async fn count(&self, tenant_id: i32, filter: &AssignmentFilter) -> Result<i64, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::count_filtered(&mut conn, filter).await?)
+    }
+
+    async fn read_by_user(&self, tenant_id: i32, user_id: i32, task_status_id: Option<i32>, page: i64, per_page: i64, sort: &Sort) -> Result<Page<UserTask>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::read_by_user(&mut conn, user_id, task_status_id, page, per_page, sort).await?)
+    }
+
+    async fn read_by_task(&self, tenant_id: i32, task_id: i32, task_status_id: Option<i32>, page: i64, per_page: i64, sort: &Sort) -> Result<Page<UserTask>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::read_by_task(&mut conn, task_id, task_status_id, page, per_page, sort).await?)
+    }
+
+    async fn read(&self, tenant_id: i32, id: (i32, i32)) -> Result<Option<UserTask>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::read(&mut conn, id).await?)
+    }
+
+    async fn create(&self, tenant_id: i32, actor: i32, user_task: NewUserTask) -> Result<UserTask, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::create_audited(&mut conn, Some(actor), user_task).await?)
+    }
+
+    async fn update(&self, tenant_id: i32, actor: i32, id: (i32, i32), expected_version: Option<i32>, user_task: NewUserTask) -> Result<UserTask, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::update_audited(&mut conn, Some(actor), id, expected_version, user_task).await?)
+    }
+
+    async fn update_partial(&self, tenant_id: i32, actor: i32, id: (i32, i32), expected_version: Option<i32>, changes: UserTaskChanges) -> Result<UserTask, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::update_partial(&mut conn, Some(actor), id, expected_version, changes).await?)
+    }
+
+    async fn upsert(&self, tenant_id: i32, actor: i32, user_task: NewUserTask) -> Result<UserTask, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::upsert(&mut conn, Some(actor), user_task).await?)
+    }
+
+    async fn delete(&self, tenant_id: i32, actor: i32, id: (i32, i32)) -> Result<usize, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::delete_audited(&mut conn, Some(actor), id).await?)
+    }
+
+    async fn restore(&self, tenant_id: i32, actor: i32, id: (i32, i32)) -> Result<Option<UserTask>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::restore(&mut conn, Some(actor), id).await?)
+    }
+
+    async fn create_many(&self, tenant_id: i32, actor: i32, user_tasks: Vec<NewUserTask>) -> Result<Vec<anyhow::Result<UserTask>>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::create_many(&mut conn, Some(actor), user_tasks).await?)
+    }
+
+    async fn update_many(&self, tenant_id: i32, actor: i32, user_tasks: Vec<NewUserTask>) -> Result<Vec<anyhow::Result<UserTask>>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::update_many(&mut conn, Some(actor), user_tasks).await?)
+    }
+
+    async fn delete_many(&self, tenant_id: i32, actor: i32, ids: Vec<(i32, i32)>) -> Result<Vec<anyhow::Result<usize>>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(UserTask::delete_many(&mut conn, Some(actor), ids).await?)
+    }
+
+    async fn read_user(&self, tenant_id: i32, user_id: i32) -> Result<Option<User>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(User::read(&mut conn, user_id).await?)
+    }
+
+    async fn read_task(&self, tenant_id: i32, task_id: i32) -> Result<Option<Task>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Task::read(&mut conn, task_id).await?)
+    }
+
+    async fn read_status(&self, tenant_id: i32, task_status_id: i32) -> Result<Option<TaskStatus>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(TaskStatus::read(&mut conn, task_status_id).await?)
+    }
+
+    async fn open_blocker_ids(&self, tenant_id: i32, task_id: i32, terminal_statuses: &[String]) -> Result<Vec<i32>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(TaskDependency::open_blocker_ids(&mut conn, task_id, terminal_statuses).await?)
+    }
+
+    async fn team_for_task(&self, tenant_id: i32, task_id: i32) -> Result<Option<Team>, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Team::for_task(&mut conn, task_id).await?)
+    }
+
+    async fn is_team_member(&self, tenant_id: i32, team_id: i32, user_id: i32) -> Result<bool, ApiError> {
+        let mut conn = self.pool.get_in_tenant(tenant_id).await?;
+        Ok(Team::is_member(&mut conn, team_id, user_id).await?)
+    }
+}
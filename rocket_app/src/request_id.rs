@@ -0,0 +1,80 @@
+use std::convert::Infallible;
+use rand::RngCore;
+use rocket::{Data, Request, Response};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::request::{FromRequest, Outcome};
+use crate::api_keys::to_hex;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+const MAX_LEN: usize = 128;
+
+// The ID a request goes by in logs, error bodies and the X-Request-Id response header. A caller
+// (or a proxy in front of us) that already gave the request one keeps it, so a failure can be
+// followed from service to service; otherwise it's 16 random bytes in hex.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    // Settled the first time anything asks, and the same for the rest of the request.
+    pub fn of<'a>(req: &'a Request<'_>) -> &'a str {
+        &req.local_cache(|| {
+            let given = req.headers().get_one(REQUEST_ID_HEADER).filter(|id| acceptable(id));
+            RequestId(given.map(str::to_string).unwrap_or_else(generate))
+        }).0
+    }
+}
+
+// IDs end up in logs and headers, so only short runs of printable ASCII without spaces are taken.
+fn acceptable(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+fn generate() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestId(RequestId::of(req).to_string()))
+    }
+}
+
+// Gives every request its ID as it comes in, and sends the ID back on the response.
+pub struct RequestIds;
+
+#[rocket::async_trait]
+impl Fairing for RequestIds {
+    fn info(&self) -> Info {
+        Info { name: "Request IDs", kind: Kind::Request | Kind::Response }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        RequestId::of(req);
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        res.set_header(Header::new(REQUEST_ID_HEADER, RequestId::of(req).to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_short_printable_ids_are_taken_from_the_caller() {
+        assert!(acceptable("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert!(acceptable("req:2026-10-15/abc_1"));
+        assert!(!acceptable(""));
+        assert!(!acceptable("has space"));
+        assert!(!acceptable("line\nbreak"));
+        assert!(!acceptable(&"a".repeat(MAX_LEN + 1)));
+        assert_eq!(generate().len(), 32);
+    }
+}
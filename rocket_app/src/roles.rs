@@ -0,0 +1,37 @@
+use rocket::{serde::json::Json, State, get};
+use rocket::fairing::AdHoc;
+use tasks_db_lib::models::{Role, User};
+use tasks_db_lib::tenancy;
+use crate::error::ApiError;
+use crate::tenancy::DbPool;
+
+// Roles are fixed (admin, manager, member) because the auth guards depend on them,
+// so this module is read-only. Use PUT /users/<id>/role to change a user's role.
+#[get("/roles")]
+pub async fn get_roles(pool: &State<DbPool>) -> Result<Json<Vec<Role>>, ApiError> {
+    let mut conn = pool.get().await?;
+    let roles = Role::read_all(&mut conn).await?;
+    Ok(Json(roles))
+}
+
+// Nobody is an admin until one is named: with ADMIN_EMAIL set, the default tenant's user with
+// that email is made one at startup (register them first). Tenants set up through
+// registration get their own admin, the registrant.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Admin bootstrap", |rocket| Box::pin(async move {
+        let (Some(pool), Ok(email)) = (rocket.state::<DbPool>(), std::env::var("ADMIN_EMAIL")) else {
+            return;
+        };
+        if email.is_empty() {
+            return;
+        }
+        let promoted: Result<_, ApiError> = async {
+            Ok(User::bootstrap_admin(&mut *pool.get_in_tenant(tenancy::DEFAULT_TENANT).await?, &email).await?)
+        }.await;
+        match promoted {
+            Ok(Some(_)) => {}
+            Ok(None) => tracing::warn!("ADMIN_EMAIL {} matches no user in the default tenant", email),
+            Err(e) => tracing::error!("Admin bootstrap failed: {:?}", e),
+        }
+    }))
+}
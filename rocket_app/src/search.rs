@@ -0,0 +1,41 @@
+use rocket::{serde::json::Json, get};
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::search::{self, SearchHit, Suggestion};
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::pagination::PageQuery;
+
+pub const DEFAULT_SUGGESTIONS: i64 = 10;
+pub const MAX_SUGGESTIONS: i64 = 20;
+
+// e.g. GET /api/search?q=database schema. Every word has to appear in the task name;
+// the best matches come first. &fuzzy=true also accepts words with a typo or two.
+#[get("/search?<q>&<fuzzy>&<paging..>")]
+pub async fn search_tasks(q: &str, fuzzy: Option<bool>, paging: PageQuery, db: TenantDb) -> Result<Json<Page<SearchHit>>, ApiError> {
+    if q.trim().is_empty() {
+        return Err(ApiError::BadRequest("q must not be empty".to_string()));
+    }
+    if paging.sort.is_some() || paging.order.is_some() {
+        return Err(ApiError::BadRequest("Search results are ordered by relevance and can't be sorted".to_string()));
+    }
+    let (page, per_page) = paging.resolve()?;
+    let mut conn = db.get().await?;
+    let hits = if fuzzy.unwrap_or(false) {
+        search::fuzzy_search_tasks(&mut conn, q, page, per_page).await?
+    } else {
+        search::search_tasks(&mut conn, q, page, per_page).await?
+    };
+    Ok(Json(hits))
+}
+
+// For task pickers, e.g. GET /api/tasks/suggest?q=rep&limit=5: ids and names only, no paging.
+// An empty q gives an empty list rather than an error, since pickers call this on every keystroke.
+#[get("/tasks/suggest?<q>&<limit>")]
+pub async fn suggest_tasks(q: &str, limit: Option<i64>, db: TenantDb) -> Result<Json<Vec<Suggestion>>, ApiError> {
+    let limit = limit.unwrap_or(DEFAULT_SUGGESTIONS);
+    if !(1..=MAX_SUGGESTIONS).contains(&limit) {
+        return Err(ApiError::BadRequest(format!("limit must be between 1 and {}", MAX_SUGGESTIONS)));
+    }
+    let mut conn = db.get().await?;
+    Ok(Json(search::suggest_tasks(&mut conn, q, limit).await?))
+}
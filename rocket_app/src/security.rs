@@ -0,0 +1,76 @@
+use rocket::{Request, Response};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::shield::{Permission, Shield};
+
+// The API only ever sends JSON and files, so pages have nothing to load and nobody to frame them.
+const DEFAULT_CSP: &str = "default-src 'none'; frame-ancestors 'none'";
+
+// Sets X-Content-Type-Options, X-Frame-Options, Referrer-Policy and Content-Security-Policy on
+// every response. CONTENT_SECURITY_POLICY replaces the default policy; a handler that needs a
+// different one (the Swagger UI page, say) sets its own and keeps it.
+pub struct SecurityHeaders {
+    content_security_policy: String,
+}
+
+impl SecurityHeaders {
+    pub fn from_env() -> SecurityHeaders {
+        let content_security_policy = std::env::var("CONTENT_SECURITY_POLICY")
+            .ok()
+            .filter(|policy| !policy.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_CSP.to_string());
+        SecurityHeaders { content_security_policy }
+    }
+}
+
+// Rocket's own Shield sets some of the same headers; this keeps only the ones SecurityHeaders
+// doesn't, so each header has one source.
+pub fn shield() -> Shield {
+    Shield::new().enable(Permission::default())
+}
+
+#[rocket::async_trait]
+impl Fairing for SecurityHeaders {
+    fn info(&self) -> Info {
+        Info { name: "Security headers", kind: Kind::Response }
+    }
+
+    async fn on_response<'r>(&self, _: &'r Request<'_>, res: &mut Response<'r>) {
+        res.set_header(Header::new("X-Content-Type-Options", "nosniff"));
+        res.set_header(Header::new("X-Frame-Options", "DENY"));
+        res.set_header(Header::new("Referrer-Policy", "no-referrer"));
+        if !res.headers().contains("Content-Security-Policy") {
+            res.set_header(Header::new("Content-Security-Policy", self.content_security_policy.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::blocking::Client;
+    use rocket::{get, routes};
+
+    #[get("/plain")]
+    fn plain() -> &'static str {
+        "ok"
+    }
+
+    #[test]
+    fn every_response_gets_the_headers_but_a_handlers_own_policy_stays() {
+        let rocket = rocket::build()
+            .attach(SecurityHeaders { content_security_policy: DEFAULT_CSP.to_string() })
+            .attach(shield())
+            .mount("/", routes![plain, crate::openapi::swagger_ui]);
+        let client = Client::tracked(rocket).unwrap();
+        let response = client.get("/plain").dispatch();
+        assert_eq!(response.headers().get_one("X-Frame-Options"), Some("DENY"));
+        assert_eq!(response.headers().get_one("X-Content-Type-Options"), Some("nosniff"));
+        assert_eq!(response.headers().get_one("Content-Security-Policy"), Some(DEFAULT_CSP));
+        assert_eq!(response.headers().get("X-Frame-Options").count(), 1);
+
+        let response = client.get("/docs").dispatch();
+        let policy = response.headers().get_one("Content-Security-Policy").unwrap();
+        assert!(policy.contains("script-src 'unsafe-inline' https://unpkg.com"), "{}", policy);
+    }
+}
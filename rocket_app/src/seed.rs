@@ -0,0 +1,32 @@
+use anyhow::Context;
+use diesel_async::AsyncConnection;
+use tasks_db_lib::{seed, tenancy, DbConnection};
+
+// `rocket_app seed` puts the standard statuses, sample users and tasks into the default tenant
+// (see tasks_db_lib::seed) and exits, migrating the database first as the server would. Running
+// it again only adds back what has gone missing. The sample users all sign in with the same
+// published password, so it's for development databases only.
+pub async fn run() -> anyhow::Result<()> {
+    let mut conn = connect().await?;
+    let seeded = seed::run(&mut conn).await?;
+    println!(
+        "Added {} statuses, {} users and {} tasks; {} users were given the password '{}'",
+        seeded.statuses, seeded.users, seeded.tasks, seeded.passwords, seed::PASSWORD,
+    );
+    Ok(())
+}
+
+// A connection into the default tenant of DATABASE_URL, migrated first unless MIGRATE_ON_STARTUP
+// says not to. The generate command starts the same way.
+pub async fn connect() -> anyhow::Result<DbConnection> {
+    let database_url = std::env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
+    if crate::migrations::enabled() {
+        for version in tasks_db_lib::migrations::run_pending(&database_url).await? {
+            println!("Ran migration {}", version);
+        }
+    }
+    let mut conn = DbConnection::establish(&database_url).await?;
+    tasks_db_lib::configure(&mut conn).await?;
+    tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await?;
+    Ok(conn)
+}
@@ -0,0 +1,193 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use rocket::{Data, Orbit, Request, Rocket};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::tokio::select;
+use rocket::tokio::sync::{Notify, watch};
+use rocket::tokio::sync::broadcast::{self, error::RecvError};
+use rocket::tokio::task::JoinHandle;
+use rocket::tokio::time::{Instant, Interval, timeout_at};
+use crate::tenancy::DbPool;
+
+#[derive(Default)]
+struct Requests {
+    running: AtomicUsize,
+    // woken whenever the last running request finishes
+    idle: Notify,
+}
+
+impl Requests {
+    async fn finished(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if self.running.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+// Kept in the request's local cache, so the request counts as running until Rocket drops it,
+// whether it was answered or the client went away.
+struct Running(Arc<Requests>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        if self.0.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+// Managed state: the requests being answered and the background workers (scans, the webhook
+// outbox, the EventBus listeners) the server waits for when it's told to stop.
+pub struct Drain {
+    draining: AtomicBool,
+    requests: Arc<Requests>,
+    stop: watch::Sender<bool>,
+    workers: Mutex<Vec<(&'static str, JoinHandle<()>)>>,
+}
+
+impl Default for Drain {
+    fn default() -> Drain {
+        Drain {
+            draining: AtomicBool::new(false),
+            requests: Arc::default(),
+            stop: watch::channel(false).0,
+            workers: Mutex::default(),
+        }
+    }
+}
+
+impl Drain {
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn stop(&self) -> Stop {
+        Stop(self.stop.subscribe())
+    }
+
+    // Runs background work the server lets finish before it exits. It should watch a Stop and
+    // return soon after it fires.
+    pub fn spawn(&self, name: &'static str, work: impl Future<Output = ()> + Send + 'static) {
+        let handle = rocket::tokio::spawn(work);
+        self.workers.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push((name, handle));
+    }
+}
+
+// A background worker's signal to wrap up. It fires once the last request has been answered,
+// so changes those requests made still reach the listeners and the outbox.
+#[derive(Clone)]
+pub struct Stop(watch::Receiver<bool>);
+
+impl Stop {
+    pub async fn requested(&mut self) {
+        // an Err means the Drain is gone, which is as good as being told to stop
+        let _ = self.0.wait_for(|stop| *stop).await;
+    }
+
+    // Waits for the interval's next tick; false instead once the worker should stop. A scan
+    // already under way isn't interrupted.
+    pub async fn tick(&mut self, interval: &mut Interval) -> bool {
+        select! {
+            biased;
+            _ = self.requested() => false,
+            _ = interval.tick() => true,
+        }
+    }
+
+    // The next change off the bus. Once the worker should stop, only the changes already
+    // queued, then None.
+    pub async fn recv<T: Clone>(&mut self, changes: &mut broadcast::Receiver<T>) -> Option<Result<T, RecvError>> {
+        select! {
+            biased;
+            change = changes.recv() => Some(change),
+            _ = self.requested() => None,
+        }
+    }
+}
+
+// On shutdown, after Rocket has stopped accepting connections: readiness starts failing, the
+// requests still running are waited for (their transactions commit or roll back as usual),
+// then the background workers are told to stop and waited for, the webhook dispatcher sending
+// what's left in the outbox. Requests get the shutdown grace period (ROCKET_SHUTDOWN's `grace`)
+// and workers the `mercy` after it; what doesn't finish by then is cut off. The pool's
+// connections close when Rocket drops it, once nothing is holding one.
+pub struct GracefulShutdown;
+
+#[rocket::async_trait]
+impl Fairing for GracefulShutdown {
+    fn info(&self) -> Info {
+        Info { name: "Graceful shutdown", kind: Kind::Request | Kind::Shutdown }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        if let Some(drain) = req.rocket().state::<Drain>() {
+            let requests = drain.requests.clone();
+            req.local_cache(move || {
+                requests.running.fetch_add(1, Ordering::SeqCst);
+                Running(requests)
+            });
+        }
+    }
+
+    async fn on_shutdown(&self, rocket: &Rocket<Orbit>) {
+        let Some(drain) = rocket.state::<Drain>() else {
+            return;
+        };
+        drain.draining.store(true, Ordering::SeqCst);
+        let shutdown = &rocket.config().shutdown;
+        let requests_by = Instant::now() + Duration::from_secs(shutdown.grace as u64);
+        let workers_by = requests_by + Duration::from_secs(shutdown.mercy as u64);
+
+        let running = drain.requests.running.load(Ordering::SeqCst);
+        tracing::info!(target: "shutdown", running, "Shutting down; waiting for running requests");
+        if timeout_at(requests_by, drain.requests.finished()).await.is_err() {
+            let running = drain.requests.running.load(Ordering::SeqCst);
+            tracing::warn!(target: "shutdown", running, "Requests still running after the grace period");
+        }
+
+        drain.stop.send_replace(true);
+        let workers = std::mem::take(&mut *drain.workers.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        for (name, handle) in workers {
+            match timeout_at(workers_by, handle).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => tracing::error!(target: "shutdown", worker = name, "Background worker failed: {}", e),
+                Err(_) => tracing::warn!(target: "shutdown", worker = name, "Background worker cut off before it finished"),
+            }
+        }
+
+        if let Some(pool) = rocket.state::<DbPool>() {
+            let state = pool.state();
+            tracing::info!(target: "shutdown", connections = state.connections, in_use = state.connections - state.idle_connections, "Closing the database pool");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rocket::async_test]
+    async fn workers_take_the_queued_changes_then_stop() {
+        let drain = Drain::default();
+        let mut stop = drain.stop();
+        let (sender, mut changes) = broadcast::channel(8);
+        let mut interval = rocket::tokio::time::interval(Duration::from_secs(3600));
+        sender.send(1).unwrap();
+        assert!(matches!(stop.recv(&mut changes).await, Some(Ok(1))));
+        assert!(stop.tick(&mut interval).await);
+
+        sender.send(2).unwrap();
+        sender.send(3).unwrap();
+        drain.stop.send_replace(true);
+        assert!(matches!(stop.recv(&mut changes).await, Some(Ok(2))));
+        assert!(matches!(stop.recv(&mut changes).await, Some(Ok(3))));
+        assert!(stop.recv(&mut changes).await.is_none());
+        assert!(!stop.tick(&mut interval).await);
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::Duration;
+use rocket::{serde::json::Json, get, put, delete};
+use rocket::fairing::AdHoc;
+use rocket::serde::Deserialize;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::serde::json::serde_json::json;
+use tasks_db_lib::DbConnection;
+use reqwest::StatusCode;
+use reqwest::header::RETRY_AFTER;
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::models::{NewSlackIntegration, Project, SlackIntegration, Task, TaskStatus, User};
+use crate::error::ApiError;
+use crate::shutdown::Drain;
+use crate::events::{Change, EventBus, ASSIGNMENT_STATUS_CHANGED, TASK_CREATED};
+use crate::tenancy::{DbPool, TenantDb};
+use crate::auth::ManagerUser;
+use crate::validation::{Validate, Validator, MAX_URL_LEN};
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+const DEFAULT_RETRY_SECONDS: u64 = 2;
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+// SLACK_TIMEOUT_SECONDS is how long one post may take before it counts as failed.
+// SLACK_RETRY_SECONDS is the wait after a first transient failure, doubling with each one after
+// it, unless Slack says how long to wait.
+// SLACK_MAX_ATTEMPTS is how many times a message is tried in all.
+#[derive(Clone)]
+pub struct SlackConfig {
+    pub retry_after: Duration,
+    pub max_attempts: u32,
+    pub http: reqwest::Client,
+}
+
+impl SlackConfig {
+    pub fn from_env() -> SlackConfig {
+        let number = |name: &str, default: u64| std::env::var(name)
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .filter(|n: &u64| *n > 0)
+            .unwrap_or(default);
+        let http = reqwest::Client::builder()
+            .user_agent("rocket_app")
+            .timeout(Duration::from_secs(number("SLACK_TIMEOUT_SECONDS", DEFAULT_TIMEOUT_SECONDS)))
+            .build()
+            .expect("Failed to build HTTP client.");
+        SlackConfig {
+            retry_after: Duration::from_secs(number("SLACK_RETRY_SECONDS", DEFAULT_RETRY_SECONDS)),
+            max_attempts: number("SLACK_MAX_ATTEMPTS", DEFAULT_MAX_ATTEMPTS as u64) as u32,
+            http,
+        }
+    }
+
+    // Posts the message, trying again after network errors, timeouts, rate limiting and server
+    // errors. Anything else (a revoked or mistyped URL, say) won't get better by waiting.
+    async fn post(&self, url: &str, text: &str) {
+        let body = json!({ "text": text });
+        for attempt in 1..=self.max_attempts {
+            let wait = match self.http.post(url).json(&body).send().await {
+                Ok(response) if response.status().is_success() => return,
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS || response.status().is_server_error() => {
+                    tracing::error!("Slack post failed (attempt {}): {}", attempt, response.status());
+                    response.headers().get(RETRY_AFTER)
+                        .and_then(|seconds| seconds.to_str().ok()?.parse().ok())
+                        .map(Duration::from_secs)
+                }
+                Ok(response) => {
+                    tracing::error!("Slack post rejected: {}", response.status());
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!("Slack post failed (attempt {}): {}", attempt, e);
+                    None
+                }
+            };
+            if attempt < self.max_attempts {
+                let backoff = self.retry_after.saturating_mul(2u32.saturating_pow(attempt - 1));
+                rocket::tokio::time::sleep(wait.unwrap_or(backoff)).await;
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SlackIntegrationInput {
+    // the incoming webhook URL Slack gave for the channel, https://hooks.slack.com/services/...
+    pub webhook_url: String,
+}
+
+impl Validate for SlackIntegrationInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        let scheme = reqwest::Url::parse(&self.webhook_url).map(|url| url.scheme().to_string());
+        if !matches!(scheme.as_deref(), Ok("http" | "https")) {
+            validator.error("webhook_url", "must be an http or https URL");
+        } else if self.webhook_url.len() > MAX_URL_LEN {
+            validator.error("webhook_url", format!("must be at most {} characters", MAX_URL_LEN));
+        }
+        validator.finish()
+    }
+}
+
+// Manager-only throughout: the URL is as good as a password for posting to the channel.
+#[get("/projects/<id>/slack")]
+pub async fn get_slack_integration(id: i32, db: TenantDb, _manager: ManagerUser) -> Result<Json<SlackIntegration>, ApiError> {
+    let mut conn = db.get().await?;
+    SlackIntegration::read(&mut conn, id).await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("Slack integration"))
+}
+
+// Turns the integration on, or points it at another channel.
+#[put("/projects/<id>/slack", data = "<integration>")]
+pub async fn put_slack_integration(id: i32, db: TenantDb, _manager: ManagerUser, integration: Json<SlackIntegrationInput>) -> Result<Json<SlackIntegration>, ApiError> {
+    integration.validate()?;
+    let mut conn = db.get().await?;
+    if Project::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Project"));
+    }
+    let new_integration = NewSlackIntegration {
+        project_id: id,
+        webhook_url: &integration.webhook_url,
+    };
+    Ok(Json(SlackIntegration::save(&mut conn, new_integration).await?))
+}
+
+#[delete("/projects/<id>/slack")]
+pub async fn delete_slack_integration(id: i32, db: TenantDb, _manager: ManagerUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match SlackIntegration::delete(&mut conn, id).await? {
+        0 => Err(ApiError::not_found("Slack integration")),
+        count => Ok(Json(count)),
+    }
+}
+
+// Slack reads &, < and > as markup in message text.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// The URL and text to post for a change, if it concerns a task in a project with Slack set up.
+// An assignment saved with the status it already had hasn't moved and isn't posted.
+async fn message_for(conn: &mut DbConnection, change: &Change) -> Result<Option<(String, String)>, ApiError> {
+    let id = |field: &str| change.data[field].as_i64().map(|id| id as i32);
+    let moved = change.event_type == ASSIGNMENT_STATUS_CHANGED && id("from_task_status_id") != id("task_status_id");
+    if change.event_type != TASK_CREATED && !moved {
+        return Ok(None);
+    }
+    let Some(task_id) = id("task_id") else {
+        return Ok(None);
+    };
+    let Some(task) = Task::read(conn, task_id).await? else {
+        return Ok(None);
+    };
+    let Some(project_id) = task.project_id else {
+        return Ok(None);
+    };
+    let (Some(integration), Some(project)) = (SlackIntegration::read(conn, project_id).await?, Project::read(conn, project_id).await?) else {
+        return Ok(None);
+    };
+    let text = match change.event_type {
+        TASK_CREATED => {
+            let due = task.due_date.map(|due_date| format!(", due {}", due_date)).unwrap_or_default();
+            format!("New task in *{}*: *{}*{}", escape(&project.project_name), escape(&task.task_name), due)
+        }
+        ASSIGNMENT_STATUS_CHANGED => {
+            let (Some(user_id), Some(task_status_id)) = (id("user_id"), id("task_status_id")) else {
+                return Ok(None);
+            };
+            let user_name = User::read(conn, user_id).await?.map(|user| user.name).unwrap_or_default();
+            let status_name = TaskStatus::read(conn, task_status_id).await?.map(|status| status.status_name).unwrap_or_default();
+            format!("*{}* in *{}*: {}'s assignment moved to _{}_", escape(&task.task_name), escape(&project.project_name), escape(&user_name), escape(&status_name))
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some((integration.webhook_url, text)))
+}
+
+// Each post runs on its own, so a slow or retrying channel doesn't hold up the next change.
+async fn on_change(pool: &DbPool, config: &SlackConfig, change: &Change) -> Result<(), ApiError> {
+    let message = {
+        let mut conn = pool.get_in_tenant(change.tenant_id).await?;
+        message_for(&mut conn, change).await?
+    };
+    if let Some((url, text)) = message {
+        let config = config.clone();
+        rocket::tokio::spawn(async move { config.post(&url, &text).await });
+    }
+    Ok(())
+}
+
+// Posts new tasks and assignment moves to the Slack channel of the task's project, for projects
+// that have one (see PUT /projects/<id>/slack). Like email, it follows the EventBus, so changes
+// made while the server is down are not posted.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Slack", |rocket| Box::pin(async move {
+        let (Some(pool), Some(config), Some(bus), Some(drain)) = (
+            rocket.state::<DbPool>().cloned(),
+            rocket.state::<SlackConfig>().cloned(),
+            rocket.state::<EventBus>(),
+            rocket.state::<Drain>(),
+        ) else {
+            return;
+        };
+        let mut changes = bus.subscribe();
+        let mut stop = drain.stop();
+        drain.spawn("Slack", async move {
+            while let Some(change) = stop.recv(&mut changes).await {
+                match change {
+                    Ok(change) => {
+                        if let Err(e) = on_change(&pool, &config, &change).await {
+                            tracing::error!("Slack message for {} failed: {:?}", change.event_type, e);
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => tracing::warn!("Slack skipped {} changes", missed),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::serde::json::serde_json::json;
+    use tasks_db_lib::models::{NewProject, NewTask};
+    use tasks_db_lib::test_support::{conn, new_task};
+
+    #[test]
+    fn markup_characters_are_escaped() {
+        assert_eq!(escape("Fix <b> & </b>"), "Fix &lt;b&gt; &amp; &lt;/b&gt;");
+    }
+
+    #[test]
+    fn only_web_urls_are_accepted() {
+        let input = |webhook_url: &str| SlackIntegrationInput { webhook_url: webhook_url.to_string() };
+        assert!(input("https://hooks.slack.com/services/T0/B0/x").validate().is_ok());
+        assert!(input("ftp://hooks.slack.com/services").validate().is_err());
+        assert!(input("not a url").validate().is_err());
+    }
+
+    #[rocket::async_test]
+    async fn posts_only_assignments_that_moved() {
+        let mut conn = conn().await;
+        let project = Project::create(&mut conn, NewProject { project_name: "Launch", description: None, team_id: None }).await.unwrap();
+        SlackIntegration::save(&mut conn, NewSlackIntegration { project_id: project.project_id, webhook_url: "https://hooks.slack.com/services/T/B/X" }).await.unwrap();
+        let task = Task::create(&mut conn, NewTask { project_id: Some(project.project_id), ..new_task("Ship it") }).await.unwrap();
+        let change = |from: i32, to: i32| Change {
+            tenant_id: 1,
+            event_type: ASSIGNMENT_STATUS_CHANGED,
+            data: json!({ "user_id": 1, "task_id": task.task_id, "task_status_id": to, "from_task_status_id": from }),
+        };
+
+        let (_, text) = message_for(&mut conn, &change(1, 2)).await.unwrap().expect("a move is posted");
+        assert!(text.ends_with("moved to _In Progress_"), "{}", text);
+        assert!(message_for(&mut conn, &change(2, 2)).await.unwrap().is_none());
+    }
+}
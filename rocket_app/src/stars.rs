@@ -0,0 +1,42 @@
+use rocket::{serde::json::Json, get, post, delete};
+use tasks_db_lib::models::{StarredTask, Task};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::filters::TaskFilter;
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::sorting::TASK_SORT_COLUMNS;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::AuthenticatedUser;
+use crate::links::{linked_all, Linked};
+use crate::pagination::PageQuery;
+
+// Stars are the caller's own; nobody else sees them. Starring twice is not an error.
+#[post("/tasks/<id>/star")]
+pub async fn star_task(id: i32, db: TenantDb, auth: AuthenticatedUser) -> Result<Json<StarredTask>, ApiError> {
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    Ok(Json(StarredTask::star(&mut conn, auth.user_id, id).await?))
+}
+
+#[delete("/tasks/<id>/star")]
+pub async fn unstar_task(id: i32, db: TenantDb, auth: AuthenticatedUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match StarredTask::unstar(&mut conn, auth.user_id, id).await? {
+        0 => Err(ApiError::not_found("Star")),
+        count => Ok(Json(count)),
+    }
+}
+
+// Same ordering as GET /tasks: most urgent first unless ?sort= says otherwise. Starred tasks
+// that are in the trash drop out until they are restored.
+#[get("/users/me/starred?<paging..>")]
+pub async fn get_starred_tasks(db: TenantDb, auth: AuthenticatedUser, paging: PageQuery) -> Result<Json<Page<Linked<Task>>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(TASK_SORT_COLUMNS)?;
+    let mut conn = db.get().await?;
+    let filter = TaskFilter { starred_by: Some(auth.user_id), ..TaskFilter::default() };
+    let tasks = Task::read_page_filtered(&mut conn, &filter, page, per_page, &sort).await?;
+    Ok(Json(Page::new(linked_all(tasks.items), tasks.page, tasks.per_page, tasks.total)))
+}
@@ -0,0 +1,25 @@
+use rocket::{serde::json::Json, State, get};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::models::User;
+use tasks_db_lib::stats::{self, StatusCount, UserWorkload};
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::overdue::OverdueConfig;
+
+// e.g. GET /api/stats/assignments_by_status?user_id=3 for one person's breakdown
+#[get("/stats/assignments_by_status?<user_id>")]
+pub async fn get_assignments_by_status(user_id: Option<i32>, db: TenantDb) -> Result<Json<Vec<StatusCount>>, ApiError> {
+    let mut conn = db.get().await?;
+    if let Some(user_id) = user_id && User::read(&mut conn, user_id).await?.is_none() {
+        return Err(ApiError::not_found("User"));
+    }
+    Ok(Json(stats::assignments_by_status(&mut conn, user_id).await?))
+}
+
+// Who has how much still on their plate, split by status, for balancing work across a team.
+// Statuses named in OVERDUE_TERMINAL_STATUSES count as done and are left out.
+#[get("/stats/workload")]
+pub async fn get_workload(db: TenantDb, config: &State<OverdueConfig>) -> Result<Json<Vec<UserWorkload>>, ApiError> {
+    let mut conn = db.get().await?;
+    Ok(Json(stats::workload(&mut conn, &config.terminal_statuses).await?))
+}
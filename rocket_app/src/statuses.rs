@@ -1,49 +1,143 @@
-use rocket::{serde::json::Json, State, get, post, put, delete};
-use diesel::r2d2::{self, ConnectionManager};
-use diesel::sqlite::SqliteConnection;
-use tasks_db_lib::models::{TaskStatus, NewTaskStatus};
+use rocket::{serde::json::Json, State, get, post, put, patch, delete};
+use rocket::serde::json::serde_json::json;
+use tasks_db_lib::models::{TaskStatus, NewTaskStatus, TaskStatusChanges, UserTask};
 use tasks_db_lib::crud::CrudOperations;
-
-pub type DbPool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+use tasks_db_lib::audit::AuditedCrud;
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::sorting::TASK_STATUS_SORT_COLUMNS;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::conditional::{CacheValidators, Cached, IfMatch};
+use crate::auth::{AdminUser, ManagerUser};
+use crate::fields::{Fields, Sparse, TASK_STATUS_FIELDS};
+use crate::pagination::{Count, PageQuery};
+use crate::validation::{Validate, Validator, MAX_STATUS_NAME_LEN};
+use crate::events::{EventBus, TASK_STATUS_CREATED, TASK_STATUS_DELETED, TASK_STATUS_UPDATED};
 
 #[derive(rocket::serde::Deserialize)]
 pub struct TaskStatusInput {
     pub status_name: String,
 }
 
-#[get("/tasks_statuses")]
-pub async fn get_task_statuses(pool: &State<DbPool>) -> Json<Vec<TaskStatus>> {
-    let mut conn = pool.get().expect("db connection");
-    let task_statuses = TaskStatus::read_all(&mut conn).unwrap_or_default();
-    Json(task_statuses)
+// PATCH body: only the fields that are present get changed.
+#[derive(rocket::serde::Deserialize)]
+pub struct TaskStatusPatch {
+    pub status_name: Option<String>,
+}
+
+impl Validate for TaskStatusPatch {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        if let Some(status_name) = &self.status_name {
+            validator.text("status_name", status_name, MAX_STATUS_NAME_LEN);
+        }
+        validator.finish()
+    }
+}
+
+impl Validate for TaskStatusInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new().text("status_name", &self.status_name, MAX_STATUS_NAME_LEN).finish()
+    }
+}
+
+// e.g. GET /api/tasks_statuses?ids=1,2,3 for a batch of specific rows
+#[get("/tasks_statuses?<ids>&<fields>&<paging..>")]
+pub async fn get_task_statuses(db: TenantDb, ids: Option<&str>, fields: Option<&str>, paging: PageQuery) -> Result<Json<Page<Sparse<TaskStatus>>>, ApiError> {
+    let fields = Fields::parse(fields, TASK_STATUS_FIELDS)?;
+    let sort = paging.sort(TASK_STATUS_SORT_COLUMNS)?;
+    let mut conn = db.get().await?;
+    let task_statuses = match ids {
+        Some(ids) => {
+            let (ids, page, per_page) = paging.resolve_ids(ids)?;
+            TaskStatus::read_page_by_ids(&mut conn, &ids, page, per_page, &sort).await?
+        }
+        None => {
+            let (page, per_page) = paging.resolve()?;
+            TaskStatus::read_page(&mut conn, page, per_page, &sort).await?
+        }
+    };
+    Ok(Json(fields.apply(task_statuses)))
+}
+
+#[get("/tasks_statuses/count")]
+pub async fn count_task_statuses(db: TenantDb) -> Result<Json<Count>, ApiError> {
+    let mut conn = db.get().await?;
+    Ok(Json(Count { count: TaskStatus::count(&mut conn).await? }))
 }
 
 #[get("/tasks_statuses/<id>")]
-pub async fn get_task_status(id: i32, pool: &State<DbPool>) -> Option<Json<TaskStatus>> {
-    let mut conn = pool.get().ok()?;
-    TaskStatus::read(&mut conn, id).ok().flatten().map(Json)
+pub async fn get_task_status(id: i32, db: TenantDb, validators: CacheValidators) -> Result<Cached<TaskStatus>, ApiError> {
+    let mut conn = db.get().await?;
+    TaskStatus::read(&mut conn, id).await?
+        .map(|row| validators.respond(row))
+        .ok_or_else(|| ApiError::not_found("Task status"))
 }
 
 #[put("/tasks_statuses/<id>", data = "<task_status>")]
-pub async fn update_task_status(id: i32, pool: &State<DbPool>, task_status: Json<TaskStatusInput> ) -> Option<Json<TaskStatus>> {
-    let mut conn = pool.get().ok()?;
+pub async fn update_task_status(id: i32, db: TenantDb, events: &State<EventBus>, manager: ManagerUser, if_match: IfMatch, task_status: Json<TaskStatusInput> ) -> Result<Json<TaskStatus>, ApiError> {
+    task_status.validate()?;
+    let mut conn = db.get().await?;
     let updated_task_status = NewTaskStatus {
         status_name: &task_status.status_name,
     };
-    TaskStatus::update(&mut conn, id, updated_task_status).ok().map(Json)
+    let saved = TaskStatus::update_audited(&mut conn, Some(manager.user_id), id, if_match.expected(), updated_task_status).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(&task_status.status_name)))?;
+    events.publish(db.tenant_id, TASK_STATUS_UPDATED, &saved);
+    Ok(Json(saved))
+}
+
+#[patch("/tasks_statuses/<id>", data = "<task_status>")]
+pub async fn patch_task_status(id: i32, db: TenantDb, events: &State<EventBus>, manager: ManagerUser, if_match: IfMatch, task_status: Json<TaskStatusPatch>) -> Result<Json<TaskStatus>, ApiError> {
+    task_status.validate()?;
+    let mut conn = db.get().await?;
+    let changes = TaskStatusChanges {
+        status_name: task_status.status_name.as_deref(),
+    };
+    let saved = TaskStatus::update_partial(&mut conn, Some(manager.user_id), id, if_match.expected(), changes).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(task_status.status_name.as_deref().unwrap_or_default())))?;
+    events.publish(db.tenant_id, TASK_STATUS_UPDATED, &saved);
+    Ok(Json(saved))
 }
 
 #[post("/tasks_statuses", data = "<task_status>")]
-pub async fn create_task_status( pool: &State<DbPool>, task_status: Json<TaskStatusInput>) -> Option<Json<TaskStatus>> {
-    let mut conn = pool.get().ok()?;
+pub async fn create_task_status(db: TenantDb, events: &State<EventBus>, manager: ManagerUser, task_status: Json<TaskStatusInput>) -> Result<Json<TaskStatus>, ApiError> {
+    task_status.validate()?;
+    let mut conn = db.get().await?;
     let new_task_status = NewTaskStatus {
         status_name: &task_status.status_name,
     };
-    TaskStatus::create(&mut conn, new_task_status).ok().map(Json)
+    let saved = TaskStatus::create_audited(&mut conn, Some(manager.user_id), new_task_status).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(&task_status.status_name)))?;
+    events.publish(db.tenant_id, TASK_STATUS_CREATED, &saved);
+    Ok(Json(saved))
+}
+
+fn duplicate_name(status_name: &str) -> String {
+    format!("A task status named '{}' already exists", status_name)
 }
 
 #[delete("/tasks_statuses/<id>")]
-pub async fn delete_task_status(id: i32, pool: &State<DbPool>) -> Option<Json<usize>> {
-    let mut conn = pool.get().ok()?;
-    TaskStatus::delete(&mut conn, id).ok().map(Json)
-}
\ No newline at end of file
+pub async fn delete_task_status(id: i32, db: TenantDb, events: &State<EventBus>, admin: AdminUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    let in_use = UserTask::count_with_status(&mut conn, id).await?;
+    if in_use > 0 {
+        return Err(ApiError::Conflict(format!("Task status {} is still used by {} assignment(s)", id, in_use)));
+    }
+    match TaskStatus::delete_audited(&mut conn, Some(admin.user_id), id).await? {
+        0 => Err(ApiError::not_found("Task status")),
+        count => {
+            events.publish(db.tenant_id, TASK_STATUS_DELETED, &json!({ "id": id }));
+            Ok(Json(count))
+        }
+    }
+}
+
+#[post("/tasks_statuses/<id>/restore")]
+pub async fn restore_task_status(id: i32, db: TenantDb, events: &State<EventBus>, admin: AdminUser) -> Result<Json<TaskStatus>, ApiError> {
+    let mut conn = db.get().await?;
+    let restored = TaskStatus::restore(&mut conn, Some(admin.user_id), id).await?
+        .ok_or_else(|| ApiError::not_found("Deleted task status"))?;
+    events.publish(db.tenant_id, TASK_STATUS_CREATED, &restored);
+    Ok(Json(restored))
+}
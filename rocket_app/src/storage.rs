@@ -0,0 +1,207 @@
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use rocket::fs::TempFile;
+use rocket::futures::stream;
+use rocket::tokio::io::{AsyncRead, AsyncReadExt};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use tokio_util::io::StreamReader;
+
+const DEFAULT_DIR: &str = "data/attachments";
+const DEFAULT_REGION: &str = "us-east-1";
+
+// The bytes of a stored file, read as they arrive.
+pub type StoredFile = Pin<Box<dyn AsyncRead + Send>>;
+
+// Where attachment contents live. The attachments table only holds the key a file was
+// stored under, so handlers don't care which backend is in use.
+#[rocket::async_trait]
+pub trait AttachmentStorage: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, file: &mut TempFile<'_>) -> io::Result<()>;
+    async fn get(&self, key: &str) -> io::Result<StoredFile>;
+    // Removing a key that isn't there is not an error.
+    async fn delete(&self, key: &str) -> io::Result<()>;
+}
+
+// ATTACHMENT_STORAGE picks the backend: "local" (the default) or "s3".
+//
+// local: files are written under ATTACHMENT_DIR.
+// s3: any S3-compatible service. S3_ENDPOINT (e.g. https://s3.us-east-1.amazonaws.com or
+// http://127.0.0.1:9000 for MinIO), S3_BUCKET, S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY are
+// required; S3_REGION defaults to us-east-1.
+pub fn from_env() -> Box<dyn AttachmentStorage> {
+    match std::env::var("ATTACHMENT_STORAGE").unwrap_or_default().trim() {
+        "" | "local" => {
+            let dir = std::env::var("ATTACHMENT_DIR").unwrap_or_else(|_| DEFAULT_DIR.to_string());
+            Box::new(LocalStorage { dir: PathBuf::from(dir) })
+        }
+        "s3" => {
+            let required = |name: &str| std::env::var(name).unwrap_or_else(|_| panic!("{} must be set when ATTACHMENT_STORAGE=s3", name));
+            let endpoint = reqwest::Url::parse(&required("S3_ENDPOINT")).expect("S3_ENDPOINT must be a URL");
+            let http = reqwest::Client::builder()
+                .user_agent("rocket_app")
+                .build()
+                .expect("Failed to build HTTP client.");
+            Box::new(S3Storage {
+                host: match endpoint.port() {
+                    Some(port) => format!("{}:{}", endpoint.host_str().unwrap_or_default(), port),
+                    None => endpoint.host_str().unwrap_or_default().to_string(),
+                },
+                endpoint,
+                bucket: required("S3_BUCKET"),
+                region: std::env::var("S3_REGION").unwrap_or_else(|_| DEFAULT_REGION.to_string()),
+                access_key_id: required("S3_ACCESS_KEY_ID"),
+                secret_access_key: required("S3_SECRET_ACCESS_KEY"),
+                http,
+            })
+        }
+        other => panic!("ATTACHMENT_STORAGE must be 'local' or 's3', not '{}'", other),
+    }
+}
+
+pub struct LocalStorage {
+    dir: PathBuf,
+}
+
+#[rocket::async_trait]
+impl AttachmentStorage for LocalStorage {
+    async fn put(&self, key: &str, _content_type: &str, file: &mut TempFile<'_>) -> io::Result<()> {
+        rocket::tokio::fs::create_dir_all(&self.dir).await?;
+        file.move_copy_to(self.dir.join(key)).await
+    }
+
+    async fn get(&self, key: &str) -> io::Result<StoredFile> {
+        Ok(Box::pin(rocket::tokio::fs::File::open(self.dir.join(key)).await?))
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        match rocket::tokio::fs::remove_file(self.dir.join(key)).await {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
+
+// Objects are addressed path-style ({endpoint}/{bucket}/{key}), which every S3-compatible
+// service accepts, and requests are signed with AWS Signature Version 4.
+pub struct S3Storage {
+    endpoint: reqwest::Url,
+    host: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    http: reqwest::Client,
+}
+
+impl S3Storage {
+    // Storage keys are URL-safe base64, so they go into the path without escaping.
+    fn request(&self, method: reqwest::Method, key: &str) -> reqwest::RequestBuilder {
+        let path = format!("/{}/{}", self.bucket, key);
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        // The body isn't part of the signature, so uploads don't have to be hashed first.
+        let payload_hash = "UNSIGNED-PAYLOAD";
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, self.host, payload_hash, amz_date, signed_headers, payload_hash);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, to_hex(&Sha256::digest(canonical_request.as_bytes())));
+        let mut signing_key = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes());
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac_sha256(&signing_key, part.as_bytes());
+        }
+        let signature = to_hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature);
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        self.http.request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header("Authorization", authorization)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder, key: &str) -> io::Result<reqwest::Response> {
+        let response = request.send().await.map_err(io::Error::other)?;
+        match response.status() {
+            status if status.is_success() => Ok(response),
+            reqwest::StatusCode::NOT_FOUND => Err(io::Error::new(io::ErrorKind::NotFound, format!("object '{}' not found", key))),
+            status => Err(io::Error::other(format!("object store answered {} for '{}'", status, key))),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl AttachmentStorage for S3Storage {
+    // Uploads are capped at ATTACHMENT_MAX_MB, so the file is sent as one buffered body.
+    async fn put(&self, key: &str, content_type: &str, file: &mut TempFile<'_>) -> io::Result<()> {
+        let mut body = Vec::with_capacity(file.len() as usize);
+        file.open().await?.read_to_end(&mut body).await?;
+        let request = self.request(reqwest::Method::PUT, key)
+            .header("Content-Type", content_type)
+            .body(body);
+        self.send(request, key).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> io::Result<StoredFile> {
+        let response = self.send(self.request(reqwest::Method::GET, key), key).await?;
+        let chunks = stream::unfold(response, |mut response| async move {
+            match response.chunk().await {
+                Ok(Some(chunk)) => Some((Ok(chunk), response)),
+                Ok(None) => None,
+                Err(e) => Some((Err(io::Error::other(e)), response)),
+            }
+        });
+        Ok(Box::pin(StreamReader::new(chunks)))
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        match self.send(self.request(reqwest::Method::DELETE, key), key).await {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result.map(|_| ()),
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner = Sha256::new().chain_update(block.map(|b| b ^ 0x36)).chain_update(message).finalize();
+    Sha256::new().chain_update(block.map(|b| b ^ 0x5c)).chain_update(inner).finalize().into()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4231 test cases 2 and 6
+    #[test]
+    fn hmac_matches_the_rfc_vectors() {
+        assert_eq!(to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+        let long_key = [0xaa; 131];
+        assert_eq!(to_hex(&hmac_sha256(&long_key, b"Test Using Larger Than Block-Size Key - Hash Key First")), "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+    }
+
+    #[rocket::async_test]
+    async fn deleting_a_missing_local_file_is_not_an_error() {
+        let storage = LocalStorage { dir: std::env::temp_dir().join("rocket_app_storage_test") };
+        assert!(storage.delete("never-stored").await.is_ok());
+        assert!(storage.get("never-stored").await.is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use rocket::{serde::json::Json, get, post, put, delete};
+use tasks_db_lib::models::{NewTag, Tag, Task};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::audit::AuditedCrud;
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::sorting::TAG_SORT_COLUMNS;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::conditional::{CacheValidators, Cached, IfMatch};
+use crate::auth::ManagerUser;
+use crate::pagination::PageQuery;
+use crate::validation::{Validate, Validator, MAX_TAG_NAME_LEN};
+
+#[derive(rocket::serde::Deserialize)]
+pub struct TagInput {
+    pub tag_name: String,
+}
+
+impl Validate for TagInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new().text("tag_name", &self.tag_name, MAX_TAG_NAME_LEN).finish()
+    }
+}
+
+// Body of POST /tasks/<id>/tags: the tags to add to the task.
+#[derive(rocket::serde::Deserialize)]
+pub struct TaskTagsInput {
+    pub tag_ids: Vec<i32>,
+}
+
+impl Validate for TaskTagsInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        if self.tag_ids.is_empty() {
+            validator.error("tag_ids", "must name at least one tag");
+        }
+        for &tag_id in &self.tag_ids {
+            validator.id("tag_ids", tag_id);
+        }
+        validator.finish()
+    }
+}
+
+fn duplicate_name(tag_name: &str) -> String {
+    format!("A tag named '{}' already exists", tag_name)
+}
+
+// Sorted by name unless ?sort= says otherwise.
+#[get("/tags?<paging..>")]
+pub async fn get_tags(db: TenantDb, paging: PageQuery) -> Result<Json<Page<Tag>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(TAG_SORT_COLUMNS)?;
+    let mut conn = db.get().await?;
+    Ok(Json(Tag::read_page(&mut conn, page, per_page, &sort).await?))
+}
+
+#[get("/tags/<id>")]
+pub async fn get_tag(id: i32, db: TenantDb, validators: CacheValidators) -> Result<Cached<Tag>, ApiError> {
+    let mut conn = db.get().await?;
+    Tag::read(&mut conn, id).await?
+        .map(|row| validators.respond(row))
+        .ok_or_else(|| ApiError::not_found("Tag"))
+}
+
+#[post("/tags", data = "<tag>")]
+pub async fn create_tag(db: TenantDb, manager: ManagerUser, tag: Json<TagInput>) -> Result<Json<Tag>, ApiError> {
+    tag.validate()?;
+    let mut conn = db.get().await?;
+    let new_tag = NewTag {
+        tag_name: tag.tag_name.trim(),
+    };
+    let saved = Tag::create_audited(&mut conn, Some(manager.user_id), new_tag).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(tag.tag_name.trim())))?;
+    Ok(Json(saved))
+}
+
+#[put("/tags/<id>", data = "<tag>")]
+pub async fn update_tag(id: i32, db: TenantDb, manager: ManagerUser, if_match: IfMatch, tag: Json<TagInput>) -> Result<Json<Tag>, ApiError> {
+    tag.validate()?;
+    let mut conn = db.get().await?;
+    let updated_tag = NewTag {
+        tag_name: tag.tag_name.trim(),
+    };
+    let saved = Tag::update_audited(&mut conn, Some(manager.user_id), id, if_match.expected(), updated_tag).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(tag.tag_name.trim())))?;
+    Ok(Json(saved))
+}
+
+// Deleting a tag takes it off every task that had it.
+#[delete("/tags/<id>")]
+pub async fn delete_tag(id: i32, db: TenantDb, manager: ManagerUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match Tag::delete_audited(&mut conn, Some(manager.user_id), id).await? {
+        0 => Err(ApiError::not_found("Tag")),
+        count => Ok(Json(count)),
+    }
+}
+
+#[get("/tasks/<id>/tags")]
+pub async fn get_task_tags(id: i32, db: TenantDb) -> Result<Json<Vec<Tag>>, ApiError> {
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    Ok(Json(Tag::read_for_task(&mut conn, id).await?))
+}
+
+// Adding a tag the task already has is not an error, so the same body can be sent twice.
+// Responds with all of the task's tags.
+#[post("/tasks/<id>/tags", data = "<tags>")]
+pub async fn tag_task(id: i32, db: TenantDb, _manager: ManagerUser, tags: Json<TaskTagsInput>) -> Result<Json<Vec<Tag>>, ApiError> {
+    tags.validate()?;
+    let mut tag_ids = tags.tag_ids.clone();
+    tag_ids.sort_unstable();
+    tag_ids.dedup();
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    if Tag::count_existing(&mut conn, &tag_ids).await? != tag_ids.len() as i64 {
+        return Err(ApiError::not_found("Tag"));
+    }
+    Ok(Json(Tag::attach_to_task(&mut conn, id, &tag_ids).await?))
+}
+
+#[delete("/tasks/<id>/tags/<tag_id>")]
+pub async fn untag_task(id: i32, tag_id: i32, db: TenantDb, _manager: ManagerUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match Tag::detach_from_task(&mut conn, id, tag_id).await? {
+        0 => Err(ApiError::not_found("Task tag")),
+        count => Ok(Json(count)),
+    }
+}
@@ -1,49 +1,287 @@
 use rocket::{serde::json::Json, State, get, post, put, delete};
-use diesel::r2d2::{self, ConnectionManager};
-use diesel::sqlite::SqliteConnection;
-use tasks_db_lib::models::{Task, NewTask};
-use tasks_db_lib::crud::CrudOperations;
+use chrono::NaiveDate;
+use tasks_db_lib::models::{Task, NewTask, TaskRevision};
+use tasks_db_lib::enums::TaskPriority;
+use tasks_db_lib::filters::TaskFilter;
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::recurrence::Recurrence;
+use tasks_db_lib::revisions::AssignmentSnapshot;
+use tasks_db_lib::sorting::TASK_SORT_COLUMNS;
+use crate::error::ApiError;
+use crate::repository::TaskRepository;
+use crate::conditional::{CacheValidators, Cached, IfMatch};
+use crate::auth::{AuthenticatedUser, ManagerUser};
+use crate::fields::{Fields, Sparse, TASK_FIELDS};
+use crate::links::{linked, linked_all, Linked};
+use crate::pagination::{Count, PageQuery};
+use crate::overdue::OverdueConfig;
+use crate::events::{EventBus, TASK_CREATED};
+use crate::validation::{FieldError, Validate, Validator, MAX_TASK_NAME_LEN};
 
-pub type DbPool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+#[derive(rocket::serde::Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TaskRevisionView {
+    pub version: i32,
+    pub task_name: String,
+    // null on revisions recorded before the whole task was kept
+    pub priority: Option<TaskPriority>,
+    pub due_date: Option<NaiveDate>,
+    pub parent_task_id: Option<i32>,
+    pub recurrence: Option<String>,
+    pub project_id: Option<i32>,
+    pub assignments: Vec<AssignmentSnapshot>,
+    pub actor_user_id: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl TryFrom<TaskRevision> for TaskRevisionView {
+    type Error = anyhow::Error;
+
+    fn try_from(revision: TaskRevision) -> anyhow::Result<TaskRevisionView> {
+        Ok(TaskRevisionView {
+            assignments: revision.assignments()?,
+            version: revision.version,
+            task_name: revision.task_name,
+            priority: revision.priority,
+            due_date: revision.due_date,
+            parent_task_id: revision.parent_task_id,
+            recurrence: revision.recurrence,
+            project_id: revision.project_id,
+            actor_user_id: revision.actor_user_id,
+            created_at: revision.created_at,
+        })
+    }
+}
+
+// Body of GET /tasks/<id>/subtasks: the children plus how many of them are done.
+#[derive(rocket::serde::Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SubtaskList {
+    pub total: usize,
+    pub completed: i64,
+    pub subtasks: Vec<Linked<Task>>,
+}
 
 #[derive(rocket::serde::Deserialize)]
 pub struct TaskInput {
     pub task_name: String,
+    // "2026-11-30"; leaving it out (or null) means no deadline
+    pub due_date: Option<NaiveDate>,
+    // low, medium, high or urgent; medium when left out
+    pub priority: Option<String>,
+    // makes this a subtask of that task; left out (or null) for a top-level task
+    pub parent_task_id: Option<i32>,
+    // "FREQ=WEEKLY;INTERVAL=2" makes the task repeat; see tasks_db_lib::recurrence
+    pub recurrence: Option<String>,
+    // the project the task belongs to; left out (or null) for none
+    pub project_id: Option<i32>,
+}
+
+impl TaskInput {
+    // Only meaningful after validate() has accepted the name.
+    fn priority(&self) -> TaskPriority {
+        self.priority.as_deref().and_then(TaskPriority::from_name).unwrap_or_default()
+    }
+
+    // The rule in its stored form. Only meaningful after validate() has accepted it.
+    fn recurrence(&self) -> Option<String> {
+        self.recurrence.as_deref().and_then(|rule| Recurrence::parse(rule).ok()).map(|rule| rule.to_string())
+    }
 }
 
-#[get("/tasks")]
-pub async fn get_tasks(pool: &State<DbPool>) -> Json<Vec<Task>> {
-    let mut conn = pool.get().expect("db connection");
-    let tasks = Task::read_all(&mut conn).unwrap_or_default();
-    Json(tasks)
+impl Validate for TaskInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        validator.text("task_name", &self.task_name, MAX_TASK_NAME_LEN);
+        if let Some(priority) = &self.priority && TaskPriority::from_name(priority).is_none() {
+            validator.error("priority", format!("must be one of {}", TaskPriority::NAMES.join(", ")));
+        }
+        if let Some(parent_task_id) = self.parent_task_id {
+            validator.id("parent_task_id", parent_task_id);
+        }
+        if let Some(project_id) = self.project_id {
+            validator.id("project_id", project_id);
+        }
+        if let Some(rule) = &self.recurrence && let Err(message) = Recurrence::parse(rule) {
+            validator.error("recurrence", message);
+        }
+        validator.finish()
+    }
 }
 
+// The parent has to be a live task, and can't be `task_id` itself or one of its subtasks.
+// `task_id` is None for a task that is still being created, which can't be in any loop yet.
+async fn check_parent(tasks: &dyn TaskRepository, tenant_id: i32, task_id: Option<i32>, parent_task_id: Option<i32>) -> Result<(), ApiError> {
+    let Some(parent_task_id) = parent_task_id else {
+        return Ok(());
+    };
+    let message = if tasks.read(tenant_id, parent_task_id).await?.is_none() {
+        "must be an existing task"
+    } else if let Some(task_id) = task_id && tasks.would_create_cycle(tenant_id, task_id, parent_task_id).await? {
+        "must not be the task itself or one of its subtasks"
+    } else {
+        return Ok(());
+    };
+    Err(ApiError::Validation(vec![FieldError { field: "parent_task_id", message: message.to_string() }]))
+}
+
+async fn check_project(tasks: &dyn TaskRepository, tenant_id: i32, project_id: Option<i32>) -> Result<(), ApiError> {
+    match project_id {
+        Some(project_id) if !tasks.project_exists(tenant_id, project_id).await? => {
+            Err(ApiError::Validation(vec![FieldError { field: "project_id", message: "must be an existing project".to_string() }]))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn parse_date(name: &str, raw: &str) -> Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest(format!("{} must be a date like 2026-11-30, got '{}'", name, raw)))
+}
+
+fn parse_priority(raw: &str) -> Result<TaskPriority, ApiError> {
+    TaskPriority::from_name(raw)
+        .ok_or_else(|| ApiError::BadRequest(format!("priority must be one of {}, got '{}'", TaskPriority::NAMES.join(", "), raw)))
+}
+
+// e.g. GET /api/tasks?ids=1,2,3 for a batch of specific rows, or ?fields=task_id,task_name to trim each row.
+// ?due_before= and ?due_after= are exclusive and skip tasks with no due date; ?priority=high
+// keeps one priority, ?tag=backend the tasks carrying that tag and ?project_id=2 one project's
+// tasks. Without ?sort= the most urgent tasks come first.
+#[get("/tasks?<ids>&<due_before>&<due_after>&<priority>&<tag>&<project_id>&<fields>&<paging..>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_tasks(tasks: &State<Box<dyn TaskRepository>>, user: AuthenticatedUser, ids: Option<&str>, due_before: Option<&str>, due_after: Option<&str>, priority: Option<&str>, tag: Option<&str>, project_id: Option<i32>, fields: Option<&str>, paging: PageQuery) -> Result<Json<Page<Sparse<Linked<Task>>>>, ApiError> {
+    let fields = Fields::parse(fields, TASK_FIELDS)?;
+    let sort = paging.sort(TASK_SORT_COLUMNS)?;
+    let mut filter = TaskFilter {
+        ids: None,
+        due_before: due_before.map(|raw| parse_date("due_before", raw)).transpose()?,
+        due_after: due_after.map(|raw| parse_date("due_after", raw)).transpose()?,
+        priority: priority.map(parse_priority).transpose()?,
+        tag: tag.map(|tag| tag.trim().to_string()),
+        project_id,
+        starred_by: None,
+    };
+    let (page, per_page) = match ids {
+        Some(ids) => {
+            let (ids, page, per_page) = paging.resolve_ids(ids)?;
+            filter.ids = Some(ids);
+            (page, per_page)
+        }
+        None => paging.resolve()?,
+    };
+    let tasks = tasks.read_page(user.tenant_id, &filter, page, per_page, &sort).await?;
+    Ok(Json(fields.apply(Page::new(linked_all(tasks.items), tasks.page, tasks.per_page, tasks.total))))
+}
+
+#[get("/tasks/count")]
+pub async fn count_tasks(tasks: &State<Box<dyn TaskRepository>>, user: AuthenticatedUser) -> Result<Json<Count>, ApiError> {
+    Ok(Json(Count { count: tasks.count(user.tenant_id).await? }))
+}
+
+// Polling clients can send the ETag back in If-None-Match and get a bodiless 304.
 #[get("/tasks/<id>")]
-pub async fn get_task(id: i32, pool: &State<DbPool>) -> Option<Json<Task>> {
-    let mut conn = pool.get().ok()?;
-    Task::read(&mut conn, id).ok().flatten().map(Json)
+pub async fn get_task(id: i32, tasks: &State<Box<dyn TaskRepository>>, user: AuthenticatedUser, validators: CacheValidators) -> Result<Cached<Linked<Task>>, ApiError> {
+    tasks.read(user.tenant_id, id).await?
+        .map(|row| validators.respond(linked(row)))
+        .ok_or_else(|| ApiError::not_found("Task"))
 }
 
 #[put("/tasks/<id>", data = "<task>")]
-pub async fn update_task(id: i32, pool: &State<DbPool>, task: Json<TaskInput>) -> Option<Json<Task>> {
-    let mut conn = pool.get().ok()?;
+pub async fn update_task(id: i32, tasks: &State<Box<dyn TaskRepository>>, manager: ManagerUser, if_match: IfMatch, task: Json<TaskInput>) -> Result<Json<Linked<Task>>, ApiError> {
+    task.validate()?;
+    check_parent(tasks.as_ref(), manager.tenant_id, Some(id), task.parent_task_id).await?;
+    check_project(tasks.as_ref(), manager.tenant_id, task.project_id).await?;
+    let recurrence = task.recurrence();
     let updated_task = NewTask {
         task_name: &task.task_name,
+        due_date: task.due_date,
+        priority: task.priority(),
+        parent_task_id: task.parent_task_id,
+        recurrence: recurrence.as_deref(),
+        project_id: task.project_id,
     };
-    Task::update(&mut conn, id, updated_task).ok().map(Json)
+    Ok(Json(linked(tasks.update(manager.tenant_id, manager.user_id, id, if_match.expected(), updated_task).await?)))
 }
 
 #[post("/tasks", data = "<task>")]
-pub async fn create_task(pool: &State<DbPool>, task: Json<TaskInput>) -> Option<Json<Task>> {
-    let mut conn = pool.get().ok()?;
+pub async fn create_task(tasks: &State<Box<dyn TaskRepository>>, events: &State<EventBus>, manager: ManagerUser, task: Json<TaskInput>) -> Result<Json<Linked<Task>>, ApiError> {
+    task.validate()?;
+    check_parent(tasks.as_ref(), manager.tenant_id, None, task.parent_task_id).await?;
+    check_project(tasks.as_ref(), manager.tenant_id, task.project_id).await?;
+    let recurrence = task.recurrence();
     let new_task = NewTask {
         task_name: &task.task_name,
+        due_date: task.due_date,
+        priority: task.priority(),
+        parent_task_id: task.parent_task_id,
+        recurrence: recurrence.as_deref(),
+        project_id: task.project_id,
     };
-    Task::create(&mut conn, new_task).ok().map(Json)
+    let created = linked(tasks.create(manager.tenant_id, manager.user_id, new_task).await?);
+    events.publish(manager.tenant_id, TASK_CREATED, &created);
+    Ok(Json(created))
+}
+
+// A subtask counts as complete once it is assigned and every assignment is in one of the
+// statuses OVERDUE_TERMINAL_STATUSES names as finished.
+#[get("/tasks/<id>/subtasks")]
+pub async fn get_subtasks(id: i32, tasks: &State<Box<dyn TaskRepository>>, user: AuthenticatedUser, config: &State<OverdueConfig>) -> Result<Json<SubtaskList>, ApiError> {
+    if tasks.read(user.tenant_id, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    let subtasks = tasks.read_subtasks(user.tenant_id, id).await?;
+    let completed = tasks.count_completed_subtasks(user.tenant_id, id, &config.terminal_statuses).await?;
+    Ok(Json(SubtaskList { total: subtasks.len(), completed, subtasks: linked_all(subtasks) }))
+}
+
+// Copies the task with its tags and subtasks; ?assignments=true also assigns the same users
+// to each copy, in the first status. See Task::duplicate for what isn't copied.
+#[post("/tasks/<id>/clone?<assignments>")]
+pub async fn clone_task(id: i32, assignments: Option<bool>, tasks: &State<Box<dyn TaskRepository>>, events: &State<EventBus>, manager: ManagerUser) -> Result<Json<Linked<Task>>, ApiError> {
+    let created = tasks.duplicate(manager.tenant_id, manager.user_id, id, assignments.unwrap_or(false)).await?
+        .map(linked)
+        .ok_or_else(|| ApiError::not_found("Task"))?;
+    events.publish(manager.tenant_id, TASK_CREATED, &created);
+    Ok(Json(created))
 }
 
 #[delete("/tasks/<id>")]
-pub async fn delete_task(id: i32, pool: &State<DbPool>) -> Option<Json<usize>> {
-    let mut conn = pool.get().ok()?;
-    Task::delete(&mut conn, id).ok().map(Json)
-}
\ No newline at end of file
+pub async fn delete_task(id: i32, tasks: &State<Box<dyn TaskRepository>>, manager: ManagerUser) -> Result<Json<usize>, ApiError> {
+    match tasks.delete(manager.tenant_id, manager.user_id, id).await? {
+        0 => Err(ApiError::not_found("Task")),
+        count => Ok(Json(count)),
+    }
+}
+
+// Deletes are soft; this undoes one, bringing back the task's assignments too.
+#[post("/tasks/<id>/restore")]
+pub async fn restore_task(id: i32, tasks: &State<Box<dyn TaskRepository>>, events: &State<EventBus>, manager: ManagerUser) -> Result<Json<Linked<Task>>, ApiError> {
+    let restored = tasks.restore(manager.tenant_id, manager.user_id, id).await?
+        .map(linked)
+        .ok_or_else(|| ApiError::not_found("Deleted task"))?;
+    events.publish(manager.tenant_id, TASK_CREATED, &restored);
+    Ok(Json(restored))
+}
+
+// Every change to the task or its assignments adds a version; newest first.
+#[get("/tasks/<id>/history?<paging..>")]
+pub async fn get_task_history(id: i32, paging: PageQuery, tasks: &State<Box<dyn TaskRepository>>, user: AuthenticatedUser) -> Result<Json<Page<TaskRevisionView>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    if tasks.read(user.tenant_id, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    let history = tasks.read_history(user.tenant_id, id, page, per_page).await?;
+    let items = history.items.into_iter()
+        .map(TaskRevisionView::try_from)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Json(Page::new(items, history.page, history.per_page, history.total)))
+}
+
+// Rolling back is itself a change, so it shows up in the history as the newest version.
+#[post("/tasks/<id>/revert/<version>")]
+pub async fn revert_task(id: i32, version: i32, tasks: &State<Box<dyn TaskRepository>>, manager: ManagerUser) -> Result<Json<Linked<Task>>, ApiError> {
+    tasks.revert(manager.tenant_id, manager.user_id, id, version).await?
+        .map(|task| Json(linked(task)))
+        .ok_or_else(|| ApiError::not_found("Task revision"))
+}
@@ -0,0 +1,150 @@
+use rocket::{serde::json::Json, get, post, put, delete};
+use tasks_db_lib::models::{NewTeam, Team, User};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::audit::AuditedCrud;
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::sorting::TEAM_SORT_COLUMNS;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::conditional::{CacheValidators, Cached, IfMatch};
+use crate::auth::ManagerUser;
+use crate::pagination::PageQuery;
+use crate::repository::AssignmentRepository;
+use crate::validation::{FieldError, Validate, Validator, MAX_TEAM_NAME_LEN};
+
+#[derive(rocket::serde::Deserialize)]
+pub struct TeamInput {
+    pub team_name: String,
+}
+
+impl Validate for TeamInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new().text("team_name", &self.team_name, MAX_TEAM_NAME_LEN).finish()
+    }
+}
+
+// Body of POST /teams/<id>/members: the users to add to the team.
+#[derive(rocket::serde::Deserialize)]
+pub struct TeamMembersInput {
+    pub user_ids: Vec<i32>,
+}
+
+impl Validate for TeamMembersInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        if self.user_ids.is_empty() {
+            validator.error("user_ids", "must name at least one user");
+        }
+        for &user_id in &self.user_ids {
+            validator.id("user_ids", user_id);
+        }
+        validator.finish()
+    }
+}
+
+fn duplicate_name(team_name: &str) -> String {
+    format!("A team named '{}' already exists", team_name)
+}
+
+// Assignments to a task in a project with a team are limited to that team's members; see
+// Team::for_task. Changing the project or its team later leaves existing assignments alone.
+pub async fn check_team_member(assignments: &dyn AssignmentRepository, tenant_id: i32, user_id: i32, task_id: i32) -> Result<(), ApiError> {
+    let Some(team) = assignments.team_for_task(tenant_id, task_id).await? else {
+        return Ok(());
+    };
+    if assignments.is_team_member(tenant_id, team.team_id, user_id).await? {
+        return Ok(());
+    }
+    Err(ApiError::Validation(vec![FieldError {
+        field: "user_id",
+        message: format!("user {} is not a member of team '{}', which task {}'s project belongs to", user_id, team.team_name, task_id),
+    }]))
+}
+
+// Sorted by name unless ?sort= says otherwise.
+#[get("/teams?<paging..>")]
+pub async fn get_teams(db: TenantDb, paging: PageQuery) -> Result<Json<Page<Team>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(TEAM_SORT_COLUMNS)?;
+    let mut conn = db.get().await?;
+    Ok(Json(Team::read_page(&mut conn, page, per_page, &sort).await?))
+}
+
+#[get("/teams/<id>")]
+pub async fn get_team(id: i32, db: TenantDb, validators: CacheValidators) -> Result<Cached<Team>, ApiError> {
+    let mut conn = db.get().await?;
+    Team::read(&mut conn, id).await?
+        .map(|row| validators.respond(row))
+        .ok_or_else(|| ApiError::not_found("Team"))
+}
+
+#[post("/teams", data = "<team>")]
+pub async fn create_team(db: TenantDb, manager: ManagerUser, team: Json<TeamInput>) -> Result<Json<Team>, ApiError> {
+    team.validate()?;
+    let mut conn = db.get().await?;
+    let new_team = NewTeam {
+        team_name: team.team_name.trim(),
+    };
+    let saved = Team::create_audited(&mut conn, Some(manager.user_id), new_team).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(team.team_name.trim())))?;
+    Ok(Json(saved))
+}
+
+#[put("/teams/<id>", data = "<team>")]
+pub async fn update_team(id: i32, db: TenantDb, manager: ManagerUser, if_match: IfMatch, team: Json<TeamInput>) -> Result<Json<Team>, ApiError> {
+    team.validate()?;
+    let mut conn = db.get().await?;
+    let updated_team = NewTeam {
+        team_name: team.team_name.trim(),
+    };
+    let saved = Team::update_audited(&mut conn, Some(manager.user_id), id, if_match.expected(), updated_team).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(team.team_name.trim())))?;
+    Ok(Json(saved))
+}
+
+// Projects the team owned are left without a team, so anyone may be assigned their tasks.
+#[delete("/teams/<id>")]
+pub async fn delete_team(id: i32, db: TenantDb, manager: ManagerUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match Team::delete_audited(&mut conn, Some(manager.user_id), id).await? {
+        0 => Err(ApiError::not_found("Team")),
+        count => Ok(Json(count)),
+    }
+}
+
+#[get("/teams/<id>/members")]
+pub async fn get_team_members(id: i32, db: TenantDb) -> Result<Json<Vec<User>>, ApiError> {
+    let mut conn = db.get().await?;
+    if Team::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Team"));
+    }
+    Ok(Json(Team::read_members(&mut conn, id).await?))
+}
+
+// Adding someone who is already a member is not an error, so the same body can be sent twice.
+// Responds with all of the team's members.
+#[post("/teams/<id>/members", data = "<members>")]
+pub async fn add_team_members(id: i32, db: TenantDb, _manager: ManagerUser, members: Json<TeamMembersInput>) -> Result<Json<Vec<User>>, ApiError> {
+    members.validate()?;
+    let mut user_ids = members.user_ids.clone();
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    let mut conn = db.get().await?;
+    if Team::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Team"));
+    }
+    if User::count_existing(&mut conn, &user_ids).await? != user_ids.len() as i64 {
+        return Err(ApiError::not_found("User"));
+    }
+    Ok(Json(Team::add_members(&mut conn, id, &user_ids).await?))
+}
+
+// The user keeps any assignments they already have in the team's projects.
+#[delete("/teams/<id>/members/<user_id>")]
+pub async fn remove_team_member(id: i32, user_id: i32, db: TenantDb, _manager: ManagerUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match Team::remove_member(&mut conn, id, user_id).await? {
+        0 => Err(ApiError::not_found("Team member")),
+        count => Ok(Json(count)),
+    }
+}
@@ -0,0 +1,98 @@
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use rocket::{Data, Request, Route};
+use rocket::fairing::AdHoc;
+use rocket::http::HeaderMap;
+use rocket::route::{Handler, Outcome};
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::Registry;
+
+const DEFAULT_SERVICE_NAME: &str = "tasks-api";
+
+// Spans are exported only when OTEL_EXPORTER_OTLP_ENDPOINT names an OTLP/HTTP collector (Jaeger,
+// Tempo, an OpenTelemetry Collector), e.g. http://localhost:4318; the exporter reads that and the
+// other OTEL_EXPORTER_OTLP_* settings itself. OTEL_SERVICE_NAME is what traces are filed under.
+pub fn tracer_provider() -> Result<Option<SdkTracerProvider>, String> {
+    if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").map_or(true, |endpoint| endpoint.trim().is_empty()) {
+        return Ok(None);
+    }
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .build()
+        .map_err(|e| format!("OTLP exporter: {}", e))?;
+    let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE_NAME.to_string());
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+    // a caller's `traceparent` header makes our spans part of its trace
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(Some(provider))
+}
+
+// Sends the spans still waiting in the batch when the server stops.
+pub fn shutdown(provider: Option<SdkTracerProvider>) -> AdHoc {
+    AdHoc::on_shutdown("Trace export", |_| Box::pin(async move {
+        if let Some(provider) = provider {
+            let _ = rocket::tokio::task::spawn_blocking(move || provider.shutdown()).await;
+        }
+    }))
+}
+
+pub fn layer(provider: &SdkTracerProvider) -> OpenTelemetryLayer<Registry, opentelemetry_sdk::trace::Tracer> {
+    tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+}
+
+struct Headers<'a>(&'a HeaderMap<'a>);
+
+impl Extractor for Headers<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get_one(key)
+    }
+
+    // only the W3C trace context headers are ever looked for
+    fn keys(&self) -> Vec<&str> {
+        ["traceparent", "tracestate"].into_iter().filter(|key| self.0.contains(*key)).collect()
+    }
+}
+
+// Continues the trace the caller started, if it sent W3C trace context headers.
+pub fn continue_trace(span: &Span, req: &Request<'_>) {
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| propagator.extract(&Headers(req.headers())));
+    let _ = span.set_parent(parent);
+}
+
+// The span RequestLogging opened for this request, which the handler's span goes under.
+pub struct RequestSpan(pub Span);
+
+// Runs a route's handler (and the request guards in it) inside a span named after the route, so
+// the queries it makes show up beneath it.
+#[derive(Clone)]
+struct Traced(Box<dyn Handler>);
+
+#[rocket::async_trait]
+impl Handler for Traced {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        let request_span = &req.local_cache(|| RequestSpan(Span::none())).0;
+        let route = req.route().map(|route| route.uri.to_string()).unwrap_or_default();
+        let name = req.route().and_then(|route| route.name.as_deref()).unwrap_or("handler");
+        request_span.record("http.route", route.as_str());
+        request_span.record("otel.name", format!("{} {}", req.method(), route));
+        let span = tracing::info_span!(target: "request", parent: request_span, "handler", otel.name = name, code.function.name = name);
+        self.0.handle(req, data).instrument(span).await
+    }
+}
+
+pub fn traced(routes: Vec<Route>) -> Vec<Route> {
+    routes.into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(Traced(route.handler));
+            route
+        })
+        .collect()
+}
@@ -0,0 +1,213 @@
+use rocket::{serde::json::Json, State, get, post, put, delete};
+use tasks_db_lib::DbConnection;
+use chrono::Utc;
+use tasks_db_lib::models::{NewTaskTemplate, Task, TaskTemplate, User};
+use tasks_db_lib::crud::CrudOperations;
+use tasks_db_lib::audit::AuditedCrud;
+use tasks_db_lib::enums::TaskPriority;
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::recurrence::Recurrence;
+use tasks_db_lib::sorting::TASK_TEMPLATE_SORT_COLUMNS;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::conditional::IfMatch;
+use crate::auth::ManagerUser;
+use crate::links::{linked, Linked};
+use crate::pagination::PageQuery;
+use crate::events::{EventBus, TASK_CREATED};
+use crate::validation::{FieldError, Validate, Validator, MAX_TAG_NAME_LEN, MAX_TASK_NAME_LEN};
+
+const MAX_DUE_IN_DAYS: i32 = 3650;
+const MAX_TEMPLATE_SUBTASKS: usize = 50;
+
+// A template as clients see it, with the JSON columns unpacked.
+#[derive(rocket::serde::Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TaskTemplateView {
+    pub template_id: i32,
+    pub template_name: String,
+    pub task_name: String,
+    pub priority: TaskPriority,
+    pub due_in_days: Option<i32>,
+    pub recurrence: Option<String>,
+    pub assignee_ids: Vec<i32>,
+    pub subtask_names: Vec<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+}
+
+impl TryFrom<TaskTemplate> for TaskTemplateView {
+    type Error = anyhow::Error;
+
+    fn try_from(template: TaskTemplate) -> anyhow::Result<TaskTemplateView> {
+        Ok(TaskTemplateView {
+            assignee_ids: template.assignee_ids()?,
+            subtask_names: template.subtask_names()?,
+            template_id: template.template_id,
+            template_name: template.template_name,
+            task_name: template.task_name,
+            priority: template.priority,
+            due_in_days: template.due_in_days,
+            recurrence: template.recurrence,
+            created_at: template.created_at,
+            updated_at: template.updated_at,
+            version: template.version,
+        })
+    }
+}
+
+#[derive(rocket::serde::Deserialize)]
+pub struct TaskTemplateInput {
+    pub template_name: String,
+    // what tasks made from the template are called
+    pub task_name: String,
+    // low, medium, high or urgent; medium when left out
+    pub priority: Option<String>,
+    // tasks made from the template are due this many days later; no deadline when left out
+    pub due_in_days: Option<i32>,
+    // copied onto the task, e.g. "FREQ=WEEKLY;INTERVAL=1"
+    pub recurrence: Option<String>,
+    // users to assign, in the first status
+    #[serde(default)]
+    pub assignee_ids: Vec<i32>,
+    // one subtask is created per name
+    #[serde(default)]
+    pub subtask_names: Vec<String>,
+}
+
+impl TaskTemplateInput {
+    // The rule in its stored form. Only meaningful after validate() has accepted it.
+    fn recurrence(&self) -> Option<String> {
+        self.recurrence.as_deref().and_then(|rule| Recurrence::parse(rule).ok()).map(|rule| rule.to_string())
+    }
+
+    // Assignees are stored sorted and without repeats.
+    fn to_new<'a>(&'a self, recurrence: Option<&'a str>) -> anyhow::Result<NewTaskTemplate<'a>> {
+        let mut assignee_ids = self.assignee_ids.clone();
+        assignee_ids.sort_unstable();
+        assignee_ids.dedup();
+        let subtask_names: Vec<&str> = self.subtask_names.iter().map(|name| name.trim()).collect();
+        Ok(NewTaskTemplate {
+            template_name: self.template_name.trim(),
+            task_name: self.task_name.trim(),
+            priority: self.priority.as_deref().and_then(TaskPriority::from_name).unwrap_or_default(),
+            due_in_days: self.due_in_days,
+            recurrence,
+            assignee_ids_json: serde_json::to_string(&assignee_ids)?,
+            subtask_names_json: serde_json::to_string(&subtask_names)?,
+        })
+    }
+}
+
+impl Validate for TaskTemplateInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        validator.text("template_name", &self.template_name, MAX_TAG_NAME_LEN);
+        validator.text("task_name", &self.task_name, MAX_TASK_NAME_LEN);
+        if let Some(priority) = &self.priority && TaskPriority::from_name(priority).is_none() {
+            validator.error("priority", format!("must be one of {}", TaskPriority::NAMES.join(", ")));
+        }
+        if let Some(days) = self.due_in_days && !(0..=MAX_DUE_IN_DAYS).contains(&days) {
+            validator.error("due_in_days", format!("must be from 0 to {}", MAX_DUE_IN_DAYS));
+        }
+        if let Some(rule) = &self.recurrence && let Err(message) = Recurrence::parse(rule) {
+            validator.error("recurrence", message);
+        }
+        for &user_id in &self.assignee_ids {
+            validator.id("assignee_ids", user_id);
+        }
+        if self.subtask_names.len() > MAX_TEMPLATE_SUBTASKS {
+            validator.error("subtask_names", format!("must have at most {} entries", MAX_TEMPLATE_SUBTASKS));
+        }
+        for name in &self.subtask_names {
+            validator.text("subtask_names", name, MAX_TASK_NAME_LEN);
+        }
+        validator.finish()
+    }
+}
+
+async fn check_assignees(conn: &mut DbConnection, assignee_ids: &[i32]) -> Result<(), ApiError> {
+    let mut ids = assignee_ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+    if User::count_existing(conn, &ids).await? == ids.len() as i64 {
+        return Ok(());
+    }
+    Err(ApiError::Validation(vec![FieldError { field: "assignee_ids", message: "must all be existing users".to_string() }]))
+}
+
+fn duplicate_name(template_name: &str) -> String {
+    format!("A template named '{}' already exists", template_name)
+}
+
+fn view(template: TaskTemplate) -> Result<Json<TaskTemplateView>, ApiError> {
+    Ok(Json(TaskTemplateView::try_from(template)?))
+}
+
+// Sorted by name unless ?sort= says otherwise.
+#[get("/task_templates?<paging..>")]
+pub async fn get_task_templates(db: TenantDb, paging: PageQuery) -> Result<Json<Page<TaskTemplateView>>, ApiError> {
+    let (page, per_page) = paging.resolve()?;
+    let sort = paging.sort(TASK_TEMPLATE_SORT_COLUMNS)?;
+    let mut conn = db.get().await?;
+    let templates = TaskTemplate::read_page(&mut conn, page, per_page, &sort).await?;
+    let items = templates.items.into_iter()
+        .map(TaskTemplateView::try_from)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Json(Page::new(items, templates.page, templates.per_page, templates.total)))
+}
+
+#[get("/task_templates/<id>")]
+pub async fn get_task_template(id: i32, db: TenantDb) -> Result<Json<TaskTemplateView>, ApiError> {
+    let mut conn = db.get().await?;
+    let template = TaskTemplate::read(&mut conn, id).await?.ok_or_else(|| ApiError::not_found("Task template"))?;
+    view(template)
+}
+
+#[post("/task_templates", data = "<template>")]
+pub async fn create_task_template(db: TenantDb, manager: ManagerUser, template: Json<TaskTemplateInput>) -> Result<Json<TaskTemplateView>, ApiError> {
+    template.validate()?;
+    let mut conn = db.get().await?;
+    check_assignees(&mut conn, &template.assignee_ids).await?;
+    let recurrence = template.recurrence();
+    let new_template = template.to_new(recurrence.as_deref())?;
+    let saved = TaskTemplate::create_audited(&mut conn, Some(manager.user_id), new_template).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(template.template_name.trim())))?;
+    view(saved)
+}
+
+#[put("/task_templates/<id>", data = "<template>")]
+pub async fn update_task_template(id: i32, db: TenantDb, manager: ManagerUser, if_match: IfMatch, template: Json<TaskTemplateInput>) -> Result<Json<TaskTemplateView>, ApiError> {
+    template.validate()?;
+    let mut conn = db.get().await?;
+    check_assignees(&mut conn, &template.assignee_ids).await?;
+    let recurrence = template.recurrence();
+    let updated_template = template.to_new(recurrence.as_deref())?;
+    let saved = TaskTemplate::update_audited(&mut conn, Some(manager.user_id), id, if_match.expected(), updated_template).await
+        .map_err(|e| ApiError::from(e).on_conflict(|| duplicate_name(template.template_name.trim())))?;
+    view(saved)
+}
+
+// Tasks already made from the template are left as they are.
+#[delete("/task_templates/<id>")]
+pub async fn delete_task_template(id: i32, db: TenantDb, manager: ManagerUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match TaskTemplate::delete_audited(&mut conn, Some(manager.user_id), id).await? {
+        0 => Err(ApiError::not_found("Task template")),
+        count => Ok(Json(count)),
+    }
+}
+
+// Creates the task, its subtasks and its assignments in one go; see TaskTemplate::instantiate.
+// Assignees who have since been removed are skipped. Ranked so that Rocket doesn't count the
+// path as colliding with POST /tasks/<id>/restore and friends.
+#[post("/tasks/from_template/<template_id>", rank = 1)]
+pub async fn create_task_from_template(template_id: i32, db: TenantDb, events: &State<EventBus>, manager: ManagerUser) -> Result<Json<Linked<Task>>, ApiError> {
+    let mut conn = db.get().await?;
+    let created = TaskTemplate::instantiate(&mut conn, Some(manager.user_id), template_id, Utc::now().date_naive()).await?
+        .map(linked)
+        .ok_or_else(|| ApiError::not_found("Task template"))?;
+    events.publish(db.tenant_id, TASK_CREATED, &created);
+    Ok(Json(created))
+}
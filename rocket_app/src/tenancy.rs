@@ -0,0 +1,120 @@
+use std::ops::{Deref, DerefMut};
+use std::time::Instant;
+use rocket::request::{FromRequest, Outcome, Request};
+use diesel_async::AsyncConnection;
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use tasks_db_lib::{tenancy, DbConnection};
+use tasks_db_lib::telemetry::QuerySpans;
+use tracing::Span;
+use crate::auth::AuthenticatedUser;
+use crate::error::ApiError;
+use crate::metrics::PoolEvents;
+
+type PooledConnection = bb8::PooledConnection<'static, AsyncDieselConnectionManager<DbConnection>>;
+
+// The most connections the pool opens at once, reported at /metrics.
+const MAX_CONNECTIONS: u32 = 10;
+
+// The connection pool, kept in managed state. Every checkout is timed into the Metrics the
+// PoolEvents came from.
+#[derive(Clone)]
+pub struct DbPool {
+    pool: tasks_db_lib::DbPool,
+    events: PoolEvents,
+}
+
+impl DbPool {
+    pub async fn connect(database_url: &str, events: PoolEvents) -> Result<DbPool, diesel_async::pooled_connection::PoolError> {
+        let pool = tasks_db_lib::DbPool::builder()
+            .max_size(MAX_CONNECTIONS)
+            .build(tasks_db_lib::connection_manager(database_url))
+            .await?;
+        Ok(DbPool { pool, events })
+    }
+
+    // A plain pooled connection, for what isn't tenant data (users signing in, API keys, the
+    // tenants themselves).
+    pub async fn get(&self) -> Result<DbConn, ApiError> {
+        let started = Instant::now();
+        let mut conn = self.pool.get_owned().await.inspect_err(|e| {
+            if let bb8::RunError::TimedOut = e {
+                self.events.timed_out(started.elapsed());
+            }
+        })?;
+        self.events.checked_out(started.elapsed());
+        // queries run on a blocking thread, where the caller's span isn't current
+        conn.set_instrumentation(QuerySpans::within(Span::current()));
+        Ok(DbConn(conn))
+    }
+
+    // A connection that only sees one tenant's rows, for callers with no signed-in user to go
+    // by: sign-in itself and the background jobs. The pool leaves the tenant again before it
+    // hands the connection to anyone else.
+    pub async fn get_in_tenant(&self, tenant_id: i32) -> Result<DbConn, ApiError> {
+        let mut conn = self.get().await?;
+        tenancy::enter(&mut conn, tenant_id).await?;
+        Ok(conn)
+    }
+
+    pub fn state(&self) -> bb8::State {
+        self.pool.state()
+    }
+
+    pub fn max_size(&self) -> u32 {
+        MAX_CONNECTIONS
+    }
+}
+
+// Request guard for handlers that touch tenant data: take `db: TenantDb` instead of the pool
+// and `db.get().await?` hands out a connection that only sees the caller's tenant. Implies
+// AuthenticatedUser, since the tenant comes from the signed-in user.
+pub struct TenantDb {
+    pool: DbPool,
+    pub tenant_id: i32,
+}
+
+impl TenantDb {
+    pub async fn get(&self) -> Result<DbConn, ApiError> {
+        self.pool.get_in_tenant(self.tenant_id).await
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for TenantDb {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let auth = match req.guard::<AuthenticatedUser>().await {
+            Outcome::Success(auth) => auth,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+        match req.rocket().state::<DbPool>() {
+            Some(pool) => Outcome::Success(TenantDb { pool: pool.clone(), tenant_id: auth.tenant_id }),
+            None => ApiError::Internal("DbPool is not managed".to_string()).guard_failure(req),
+        }
+    }
+}
+
+// A connection checked out of the pool, its query spans under the span it was checked out in.
+pub struct DbConn(PooledConnection);
+
+impl Deref for DbConn {
+    type Target = DbConnection;
+    fn deref(&self) -> &DbConnection {
+        &self.0
+    }
+}
+
+impl DerefMut for DbConn {
+    fn deref_mut(&mut self) -> &mut DbConnection {
+        &mut self.0
+    }
+}
+
+impl Drop for DbConn {
+    fn drop(&mut self) {
+        // an idle connection holding the request's span would keep it from ever closing
+        self.0.set_instrumentation(QuerySpans::default());
+    }
+}
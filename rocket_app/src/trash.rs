@@ -0,0 +1,77 @@
+use rocket::{serde::json::Json, State, get, delete};
+use rocket::serde::Serialize;
+use chrono::{Duration, NaiveDateTime, Utc};
+use tasks_db_lib::models::{Task, TaskStatus, UserTask};
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::{AdminUser, ManagerUser};
+use crate::attachments;
+use crate::storage::AttachmentStorage;
+
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+// TRASH_RETENTION_DAYS is how long soft-deleted rows are kept before a purge removes them.
+pub struct TrashConfig {
+    pub retention_days: i64,
+}
+
+impl TrashConfig {
+    pub fn from_env() -> TrashConfig {
+        let retention_days = std::env::var("TRASH_RETENTION_DAYS")
+            .ok()
+            .and_then(|d| d.parse().ok())
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        TrashConfig { retention_days }
+    }
+
+    fn cutoff(&self) -> NaiveDateTime {
+        (Utc::now() - Duration::days(self.retention_days)).naive_utc()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Trash {
+    pub tasks: Vec<Task>,
+    pub task_statuses: Vec<TaskStatus>,
+    pub assignments: Vec<UserTask>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PurgeResult {
+    pub older_than: NaiveDateTime,
+    pub tasks: usize,
+    pub task_statuses: usize,
+    pub assignments: usize,
+    pub attachments: usize,
+}
+
+// Everything deleted in the last `days` days (the retention period by default), newest first.
+#[get("/trash?<days>")]
+pub async fn get_trash(days: Option<i64>, db: TenantDb, config: &State<TrashConfig>, _manager: ManagerUser) -> Result<Json<Trash>, ApiError> {
+    let days = days.unwrap_or(config.retention_days);
+    if days < 1 {
+        return Err(ApiError::BadRequest("days must be 1 or greater".to_string()));
+    }
+    let since = (Utc::now() - Duration::days(days)).naive_utc();
+    let mut conn = db.get().await?;
+    Ok(Json(Trash {
+        tasks: Task::read_deleted(&mut conn, since).await?,
+        task_statuses: TaskStatus::read_deleted(&mut conn, since).await?,
+        assignments: UserTask::read_deleted(&mut conn, since).await?,
+    }))
+}
+
+// Assignments go first so the tasks and statuses they pointed at are free to be removed.
+// Files attached to purged tasks are deleted from attachment storage.
+#[delete("/trash/purge")]
+pub async fn purge_trash(db: TenantDb, config: &State<TrashConfig>, storage: &State<Box<dyn AttachmentStorage>>, _admin: AdminUser) -> Result<Json<PurgeResult>, ApiError> {
+    let older_than = config.cutoff();
+    let mut conn = db.get().await?;
+    let assignments = UserTask::purge_deleted(&mut conn, older_than).await?;
+    let (tasks, purged_attachments) = Task::purge_deleted(&mut conn, older_than).await?;
+    let task_statuses = TaskStatus::purge_deleted(&mut conn, older_than).await?;
+    attachments::remove_stored(storage.inner().as_ref(), &purged_attachments).await;
+    Ok(Json(PurgeResult { older_than, tasks, task_statuses, assignments, attachments: purged_attachments.len() }))
+}
@@ -1,10 +1,18 @@
-use rocket::{serde::json::Json, State, get, post, put, delete};
-use diesel::r2d2::{self, ConnectionManager};
-use diesel::sqlite::SqliteConnection;
-use tasks_db_lib::models::{User, NewUser};
+use rocket::{serde::json::Json, get, post, put, delete};
+use tasks_db_lib::DbConnection;
+use tasks_db_lib::models::{Credential, User, NewUser};
 use tasks_db_lib::crud::CrudOperations;
-
-pub type DbPool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+use tasks_db_lib::audit::AuditedCrud;
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::sorting::USER_SORT_COLUMNS;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::conditional::{CacheValidators, Cached, IfMatch};
+use crate::auth::{hash_password, AdminUser, AuthenticatedUser, MAX_PASSWORD_LEN, MIN_PASSWORD_LEN};
+use tasks_db_lib::enums::UserRole;
+use crate::fields::{Fields, Sparse, USER_FIELDS};
+use crate::pagination::{Count, PageQuery};
+use crate::validation::{Validate, Validator, MAX_NAME_LEN};
 
 #[derive(rocket::serde::Deserialize)]
 pub struct UserInput {
@@ -13,43 +21,139 @@ pub struct UserInput {
     pub active: bool,
 }
 
-#[get("/users")]
-pub async fn get_users(pool: &State<DbPool>) -> Json<Vec<User>> {
-    let mut conn = pool.get().expect("db connection");
-    let users = User::read_all(&mut conn).unwrap_or_default();
-    Json(users)
+#[derive(rocket::serde::Deserialize)]
+pub struct RoleInput {
+    pub role_id: i32,
+}
+
+#[derive(rocket::serde::Deserialize)]
+pub struct PasswordInput {
+    pub password: String,
+}
+
+impl Validate for UserInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new()
+            .text("name", &self.name, MAX_NAME_LEN)
+            .email("email", &self.email)
+            .finish()
+    }
+}
+
+impl Validate for RoleInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        if UserRole::from_id(self.role_id).is_none() {
+            validator.error("role_id", "must be 1 (admin), 2 (manager) or 3 (member)");
+        }
+        validator.finish()
+    }
+}
+
+impl Validate for PasswordInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        Validator::new()
+            .length("password", &self.password, MIN_PASSWORD_LEN, MAX_PASSWORD_LEN)
+            .finish()
+    }
+}
+
+// users.email has no unique index (the seed data already repeats some addresses, and other
+// tenants may use the same one), so duplicates within the tenant are caught here instead.
+async fn ensure_email_free(conn: &mut DbConnection, email: &str, except_user_id: Option<i32>) -> Result<(), ApiError> {
+    match User::read_by_email(conn, email).await? {
+        Some(existing) if Some(existing.user_id) != except_user_id => {
+            Err(ApiError::Conflict(format!("A user with email {} already exists", email)))
+        }
+        _ => Ok(()),
+    }
+}
+
+// e.g. GET /api/users?ids=1,2,3 for a batch of specific rows
+#[get("/users?<ids>&<fields>&<paging..>")]
+pub async fn get_users(db: TenantDb, ids: Option<&str>, fields: Option<&str>, paging: PageQuery) -> Result<Json<Page<Sparse<User>>>, ApiError> {
+    let fields = Fields::parse(fields, USER_FIELDS)?;
+    let sort = paging.sort(USER_SORT_COLUMNS)?;
+    let mut conn = db.get().await?;
+    let users = match ids {
+        Some(ids) => {
+            let (ids, page, per_page) = paging.resolve_ids(ids)?;
+            User::read_page_by_ids(&mut conn, &ids, page, per_page, &sort).await?
+        }
+        None => {
+            let (page, per_page) = paging.resolve()?;
+            User::read_page(&mut conn, page, per_page, &sort).await?
+        }
+    };
+    Ok(Json(fields.apply(users)))
+}
+
+#[get("/users/count")]
+pub async fn count_users(db: TenantDb) -> Result<Json<Count>, ApiError> {
+    let mut conn = db.get().await?;
+    Ok(Json(Count { count: User::count(&mut conn).await? }))
 }
 
 #[get("/users/<id>")]
-pub async fn get_user(id: i32, pool: &State<DbPool>) -> Option<Json<User>> {
-    let mut conn = pool.get().ok()?;
-    User::read(&mut conn, id).ok().flatten().map(Json)
+pub async fn get_user(id: i32, db: TenantDb, validators: CacheValidators) -> Result<Cached<User>, ApiError> {
+    let mut conn = db.get().await?;
+    User::read(&mut conn, id).await?
+        .map(|row| validators.respond(row))
+        .ok_or_else(|| ApiError::not_found("User"))
 }
 
 #[put("/users/<id>", data = "<user>")]
-pub async fn update_user(id: i32, pool: &State<DbPool>, user: Json<UserInput>) -> Option<Json<User>> {
-    let mut conn = pool.get().ok()?;
+pub async fn update_user(id: i32, db: TenantDb, auth: AuthenticatedUser, if_match: IfMatch, user: Json<UserInput>) -> Result<Json<User>, ApiError> {
+    auth.require_self_or(id, UserRole::Admin)?;
+    user.validate()?;
+    let mut conn = db.get().await?;
+    ensure_email_free(&mut conn, &user.email, Some(id)).await?;
     let updated_user = NewUser {
         name: &user.name,
         email: &user.email,
         active: user.active,
     };
-    User::update(&mut conn, id, updated_user).ok().map(Json)
+    Ok(Json(User::update_audited(&mut conn, Some(auth.user_id), id, if_match.expected(), updated_user).await?))
 }
 
 #[post("/users", data = "<user>")]
-pub async fn create_user(pool: &State<DbPool>, user: Json<UserInput>) -> Option<Json<User>> {
-    let mut conn = pool.get().ok()?;
+pub async fn create_user(db: TenantDb, admin: AdminUser, user: Json<UserInput>) -> Result<Json<User>, ApiError> {
+    user.validate()?;
+    let mut conn = db.get().await?;
+    ensure_email_free(&mut conn, &user.email, None).await?;
     let new_user = NewUser {
         name: &user.name,
         email: &user.email,
         active: user.active,
     };
-    User::create(&mut conn, new_user).ok().map(Json)
+    Ok(Json(User::create_audited(&mut conn, Some(admin.user_id), new_user).await?))
 }
 
 #[delete("/users/<id>")]
-pub async fn delete_user(id: i32, pool: &State<DbPool>) -> Option<Json<usize>> {
-    let mut conn = pool.get().ok()?;
-    User::delete(&mut conn, id).ok().map(Json)
-}
\ No newline at end of file
+pub async fn delete_user(id: i32, db: TenantDb, admin: AdminUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match User::delete_audited(&mut conn, Some(admin.user_id), id).await? {
+        0 => Err(ApiError::not_found("User")),
+        count => Ok(Json(count)),
+    }
+}
+
+#[put("/users/<id>/role", data = "<role>")]
+pub async fn update_user_role(id: i32, db: TenantDb, admin: AdminUser, if_match: IfMatch, role: Json<RoleInput>) -> Result<Json<User>, ApiError> {
+    role.validate()?;
+    let role = UserRole::from_id(role.role_id).expect("role_id was validated");
+    let mut conn = db.get().await?;
+    Ok(Json(User::set_role(&mut conn, Some(admin.user_id), id, if_match.expected(), role).await?))
+}
+
+// How an admin lets someone back in: users with no password yet (see the
+// drop_default_passwords migration) or who forgot theirs. Ends all of the user's sessions.
+#[put("/users/<id>/password", data = "<password>")]
+pub async fn reset_user_password(id: i32, db: TenantDb, _admin: AdminUser, password: Json<PasswordInput>) -> Result<Json<User>, ApiError> {
+    password.validate()?;
+    let password_hash = hash_password(&password.password).await?;
+    let mut conn = db.get().await?;
+    Credential::set_password(&mut conn, id, &password_hash).await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("User"))
+}
@@ -0,0 +1,137 @@
+use rocket::serde::Serialize;
+use crate::error::ApiError;
+
+pub const MAX_NAME_LEN: usize = 100;
+pub const MAX_EMAIL_LEN: usize = 254;
+pub const MAX_TASK_NAME_LEN: usize = 200;
+pub const MAX_STATUS_NAME_LEN: usize = 50;
+pub const MAX_TAG_NAME_LEN: usize = 50;
+pub const MAX_PROJECT_NAME_LEN: usize = 100;
+pub const MAX_TEAM_NAME_LEN: usize = 100;
+pub const MAX_DESCRIPTION_LEN: usize = 2000;
+pub const MAX_COMMENT_LEN: usize = 5000;
+pub const MAX_FILE_NAME_LEN: usize = 255;
+pub const MAX_URL_LEN: usize = 2048;
+
+// Input DTOs implement Validate and handlers call `input.validate()?` before touching
+// the database. Every rule that fails is collected so the client can fix them all at once.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ApiError>;
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    pub fn error(&mut self, field: &'static str, message: impl Into<String>) -> &mut Validator {
+        self.errors.push(FieldError { field, message: message.into() });
+        self
+    }
+
+    // Required text: not blank and at most `max` characters.
+    pub fn text(&mut self, field: &'static str, value: &str, max: usize) -> &mut Validator {
+        if value.trim().is_empty() {
+            self.error(field, "must not be empty");
+        } else if value.chars().count() > max {
+            self.error(field, format!("must be at most {} characters", max));
+        }
+        self
+    }
+
+    pub fn length(&mut self, field: &'static str, value: &str, min: usize, max: usize) -> &mut Validator {
+        let len = value.chars().count();
+        if len < min || len > max {
+            self.error(field, format!("must be between {} and {} characters", min, max));
+        }
+        self
+    }
+
+    // Deliberately loose: one '@' with something on both sides and a dot in the domain.
+    pub fn email(&mut self, field: &'static str, value: &str) -> &mut Validator {
+        let valid = match value.split_once('@') {
+            Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.') && !domain.contains('@'),
+            None => false,
+        };
+        if !valid {
+            self.error(field, "must be a valid email address");
+        } else if value.len() > MAX_EMAIL_LEN {
+            self.error(field, format!("must be at most {} characters", MAX_EMAIL_LEN));
+        }
+        self
+    }
+
+    pub fn id(&mut self, field: &'static str, value: i32) -> &mut Validator {
+        if value < 1 {
+            self.error(field, "must be a positive id");
+        }
+        self
+    }
+
+    // An optional limit: absent means none, otherwise it has to allow something.
+    pub fn limit(&mut self, field: &'static str, value: Option<i32>) -> &mut Validator {
+        if value.is_some_and(|value| value < 1) {
+            self.error(field, "must be at least 1, or null for no limit");
+        }
+        self
+    }
+
+    pub fn finish(&mut self) -> Result<(), ApiError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::Validation(std::mem::take(&mut self.errors)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(result: Result<(), ApiError>) -> Vec<&'static str> {
+        match result {
+            Ok(()) => Vec::new(),
+            Err(ApiError::Validation(errors)) => errors.into_iter().map(|error| error.field).collect(),
+            Err(other) => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn text_must_be_present_and_short_enough() {
+        assert!(fields(Validator::new().text("name", "Ada", 3).finish()).is_empty());
+        assert_eq!(fields(Validator::new().text("name", "   ", 3).finish()), ["name"]);
+        assert_eq!(fields(Validator::new().text("name", "Adam", 3).finish()), ["name"]);
+    }
+
+    #[test]
+    fn emails_need_a_local_part_and_a_dotted_domain() {
+        assert!(fields(Validator::new().email("email", "ada@example.com").finish()).is_empty());
+        for email in ["ada", "@example.com", "ada@example", "ada@.com", "ada@example.", "ada@x@y.com"] {
+            assert_eq!(fields(Validator::new().email("email", email).finish()), ["email"], "{}", email);
+        }
+    }
+
+    #[test]
+    fn every_failing_rule_is_reported() {
+        let result = Validator::new()
+            .text("name", "", MAX_NAME_LEN)
+            .id("user_id", 0)
+            .id("task_id", 4)
+            .length("password", "short", 8, 128)
+            .finish();
+        assert_eq!(fields(result), ["name", "user_id", "password"]);
+    }
+}
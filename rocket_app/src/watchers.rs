@@ -0,0 +1,26 @@
+use rocket::{serde::json::Json, post, delete};
+use tasks_db_lib::models::{Task, TaskWatcher};
+use tasks_db_lib::crud::CrudOperations;
+use crate::error::ApiError;
+use crate::tenancy::TenantDb;
+use crate::auth::AuthenticatedUser;
+
+// The caller starts hearing about the task's assignments: who is assigned or unassigned and
+// when an assignment moves to another status. Watching twice is not an error.
+#[post("/tasks/<id>/watch")]
+pub async fn watch_task(id: i32, db: TenantDb, auth: AuthenticatedUser) -> Result<Json<TaskWatcher>, ApiError> {
+    let mut conn = db.get().await?;
+    if Task::read(&mut conn, id).await?.is_none() {
+        return Err(ApiError::not_found("Task"));
+    }
+    Ok(Json(TaskWatcher::watch(&mut conn, id, auth.user_id).await?))
+}
+
+#[delete("/tasks/<id>/watch")]
+pub async fn unwatch_task(id: i32, db: TenantDb, auth: AuthenticatedUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match TaskWatcher::unwatch(&mut conn, id, auth.user_id).await? {
+        0 => Err(ApiError::not_found("Watch")),
+        count => Ok(Json(count)),
+    }
+}
@@ -0,0 +1,373 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use rocket::{serde::json::Json, State, get, post, delete};
+use rocket::fairing::AdHoc;
+use rocket::serde::{Deserialize, Serialize};
+use rocket::tokio::task::JoinSet;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use reqwest::Url;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::CONTENT_TYPE;
+use chrono::{NaiveDateTime, Utc};
+use tasks_db_lib::models::{NewWebhook, OutboxEntry, Tenant, Webhook};
+use tasks_db_lib::webhooks::EVENT_TYPES;
+use crate::error::ApiError;
+use crate::shutdown::Drain;
+use crate::tenancy::{DbPool, TenantDb};
+use crate::auth::{AuthenticatedUser, ManagerUser};
+use crate::api_keys::{generate_key, to_hex};
+use crate::validation::{FieldError, Validate, Validator, MAX_URL_LEN};
+
+const DEFAULT_POLL_SECONDS: u64 = 5;
+const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+const DEFAULT_RETRY_SECONDS: u64 = 30;
+const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+// deliveries read per tenant on each poll
+const BATCH_SIZE: i64 = 100;
+
+// WEBHOOK_POLL_SECONDS is how often the dispatcher drains the outbox.
+// WEBHOOK_TIMEOUT_SECONDS is how long one delivery may take before it counts as failed.
+// WEBHOOK_RETRY_SECONDS is the wait after a first failure, doubling with each one after it.
+// WEBHOOK_MAX_ATTEMPTS is how many failures a delivery gets before it is given up on.
+// WEBHOOK_ALLOW_PRIVATE_HOSTS=true lets webhooks point at loopback and private addresses, for
+// trying them out against a receiver on the same machine; leave it off anywhere else.
+#[derive(Clone)]
+pub struct WebhookConfig {
+    pub poll_every: Duration,
+    pub retry_after: Duration,
+    pub max_attempts: i32,
+    pub allow_private_hosts: bool,
+    pub http: reqwest::Client,
+}
+
+impl WebhookConfig {
+    pub fn from_env() -> WebhookConfig {
+        let seconds = |name: &str, default: u64| std::env::var(name)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|s: &u64| *s > 0)
+            .unwrap_or(default);
+        let max_attempts = std::env::var("WEBHOOK_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .filter(|n: &i32| *n > 0)
+            .unwrap_or(DEFAULT_MAX_ATTEMPTS);
+        let allow_private_hosts = std::env::var("WEBHOOK_ALLOW_PRIVATE_HOSTS").is_ok_and(|allow| allow == "true");
+        // Redirects aren't followed, since a public host could send the request on to a private one.
+        let mut http = reqwest::Client::builder()
+            .user_agent("rocket_app")
+            .timeout(Duration::from_secs(seconds("WEBHOOK_TIMEOUT_SECONDS", DEFAULT_TIMEOUT_SECONDS)))
+            .redirect(reqwest::redirect::Policy::none());
+        if !allow_private_hosts {
+            http = http.dns_resolver(Arc::new(PublicResolver));
+        }
+        WebhookConfig {
+            poll_every: Duration::from_secs(seconds("WEBHOOK_POLL_SECONDS", DEFAULT_POLL_SECONDS)),
+            retry_after: Duration::from_secs(seconds("WEBHOOK_RETRY_SECONDS", DEFAULT_RETRY_SECONDS)),
+            max_attempts,
+            allow_private_hosts,
+            http: http.build().expect("Failed to build HTTP client."),
+        }
+    }
+
+    async fn refuse_host(&self, url: &Url) -> Option<&'static str> {
+        if self.allow_private_hosts {
+            return None;
+        }
+        refuse_private_host(url).await
+    }
+
+    // When a delivery that has already failed `attempts` times should be tried again.
+    fn retry_at(&self, attempts: i32) -> NaiveDateTime {
+        let wait = self.retry_after.saturating_mul(2u32.saturating_pow(attempts as u32));
+        Utc::now().naive_utc() + chrono::Duration::from_std(wait).unwrap_or(chrono::Duration::MAX)
+    }
+}
+
+// Why the URL's host can't be sent to, if it can't. Names are resolved here as well as on every
+// request (by PublicResolver), since what a name points at can change after it's checked.
+pub(crate) async fn refuse_private_host(url: &Url) -> Option<&'static str> {
+    let host = url.host_str()?.trim_start_matches('[').trim_end_matches(']');
+    let addresses: Vec<IpAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![ip],
+        Err(_) => {
+            let name = host.trim_end_matches('.').to_ascii_lowercase();
+            if name == "localhost" || name.ends_with(".localhost") || METADATA_HOSTS.contains(&name.as_str()) {
+                return Some("must not point at this server or a metadata service");
+            }
+            match rocket::tokio::net::lookup_host((name.as_str(), 0)).await {
+                Ok(found) => found.map(|address| address.ip()).collect(),
+                Err(_) => return Some("must have a host name that resolves"),
+            }
+        }
+    };
+    if addresses.iter().all(|ip| is_public(*ip)) {
+        None
+    } else {
+        Some("must not point at a loopback, private or link-local address")
+    }
+}
+
+// Cloud metadata services answer on link-local addresses, which is_public already refuses;
+// these are the names they go by.
+const METADATA_HOSTS: &[&str] = &["metadata", "metadata.google.internal", "metadata.goog", "instance-data", "instance-data.ec2.internal"];
+
+// Whether an address is on the public internet, rather than this machine, a private network,
+// a link-local range (which includes the 169.254.169.254 metadata service) or otherwise special.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified()
+                || ip.is_broadcast() || ip.is_multicast() || ip.is_documentation()
+                || a == 0 || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(ip.into()),
+            None => {
+                let first = ip.segments()[0];
+                // fc00::/7 is unique local (private), fe80::/10 is link-local
+                !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast()
+                    || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+// Resolves hosts to their public addresses only, so a name that has been pointed at a private
+// address since it was registered fails to connect rather than reaching it.
+pub(crate) struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = rocket::tokio::net::lookup_host((name.as_str(), 0)).await?
+                .filter(|address| is_public(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            let addresses: Addrs = Box::new(addresses.into_iter());
+            Ok(addresses)
+        })
+    }
+}
+
+// `X-Webhook-Signature: sha256=<hex>`, the HMAC-SHA256 of the exact body with the webhook's
+// secret as the key. Receivers recompute it over the raw body they got and compare.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+pub fn signature(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes a key of any length");
+    mac.update(body.as_bytes());
+    format!("sha256={}", to_hex(&mac.finalize().into_bytes()))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookInput {
+    // an http or https URL
+    pub url: String,
+    // one of tasks_db_lib::webhooks::EVENT_TYPES, e.g. "task.created"
+    pub event_type: String,
+}
+
+impl Validate for WebhookInput {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::new();
+        let scheme = reqwest::Url::parse(&self.url).map(|url| url.scheme().to_string());
+        if !matches!(scheme.as_deref(), Ok("http" | "https")) {
+            validator.error("url", "must be an http or https URL");
+        } else if self.url.len() > MAX_URL_LEN {
+            validator.error("url", format!("must be at most {} characters", MAX_URL_LEN));
+        }
+        if !EVENT_TYPES.contains(&self.event_type.as_str()) {
+            validator.error("event_type", format!("must be one of: {}", EVENT_TYPES.join(", ")));
+        }
+        validator.finish()
+    }
+}
+
+// The secret is only ever returned here, when the webhook is registered.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreatedWebhook {
+    pub secret: String,
+    pub webhook: Webhook,
+}
+
+#[get("/webhooks")]
+pub async fn get_webhooks(db: TenantDb, auth: AuthenticatedUser) -> Result<Json<Vec<Webhook>>, ApiError> {
+    let mut conn = db.get().await?;
+    Ok(Json(Webhook::read_all_for_user(&mut conn, auth.user_id).await?))
+}
+
+// Register one URL per event type; the same URL can be registered for several. Manager-only,
+// since a webhook receives every task and assignment change in the tenant.
+#[post("/webhooks", data = "<webhook>")]
+pub async fn create_webhook(db: TenantDb, config: &State<WebhookConfig>, manager: ManagerUser, webhook: Json<WebhookInput>) -> Result<Json<CreatedWebhook>, ApiError> {
+    webhook.validate()?;
+    let url = Url::parse(&webhook.url).map_err(|_| ApiError::BadRequest("url is not a URL".to_string()))?;
+    if let Some(message) = config.refuse_host(&url).await {
+        return Err(ApiError::Validation(vec![FieldError { field: "url", message: message.to_string() }]));
+    }
+    let secret = generate_key();
+    let mut conn = db.get().await?;
+    let new_webhook = NewWebhook {
+        user_id: manager.user_id,
+        url: &webhook.url,
+        event_type: &webhook.event_type,
+        secret: &secret,
+    };
+    let webhook = Webhook::create(&mut conn, new_webhook).await?;
+    Ok(Json(CreatedWebhook { secret, webhook }))
+}
+
+#[delete("/webhooks/<id>")]
+pub async fn delete_webhook(id: i32, db: TenantDb, auth: AuthenticatedUser) -> Result<Json<usize>, ApiError> {
+    let mut conn = db.get().await?;
+    match Webhook::delete(&mut conn, id, auth.user_id).await? {
+        0 => Err(ApiError::not_found("Webhook")),
+        count => Ok(Json(count)),
+    }
+}
+
+// POSTs a webhook's deliveries in order and records how each went. The connection isn't held
+// while a request is in flight; a crash before the result is written means the delivery is
+// simply sent again.
+async fn deliver(pool: &DbPool, config: &WebhookConfig, tenant_id: i32, webhook: &Webhook, entries: Vec<OutboxEntry>) -> Result<(), ApiError> {
+    for entry in entries {
+        let sent = config.http.post(&webhook.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature(&webhook.secret, &entry.payload))
+            .body(entry.payload)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        let mut conn = pool.get_in_tenant(tenant_id).await?;
+        match sent {
+            Ok(_) => OutboxEntry::delivered(&mut conn, entry.outbox_id).await?,
+            Err(e) => {
+                tracing::error!("Webhook delivery {} to {} failed (attempt {}): {}", entry.outbox_id, webhook.url, entry.attempts + 1, e);
+                OutboxEntry::failed(&mut conn, entry.outbox_id, &e.to_string(), config.retry_at(entry.attempts)).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// What's due in the tenant, grouped by webhook, in the order read_due returns it.
+async fn due_by_webhook(pool: &DbPool, config: &WebhookConfig, tenant_id: i32) -> Result<Vec<(Webhook, Vec<OutboxEntry>)>, ApiError> {
+    let mut conn = pool.get_in_tenant(tenant_id).await?;
+    let mut grouped: Vec<(Webhook, Vec<OutboxEntry>)> = Vec::new();
+    for (entry, webhook) in OutboxEntry::read_due(&mut conn, Utc::now().naive_utc(), config.max_attempts, BATCH_SIZE).await? {
+        match grouped.iter_mut().find(|(seen, _)| seen.webhook_id == webhook.webhook_id) {
+            Some((_, entries)) => entries.push(entry),
+            None => grouped.push((webhook, vec![entry])),
+        }
+    }
+    Ok(grouped)
+}
+
+// Every webhook's deliveries run side by side, across all tenants, so a slow or unreachable
+// receiver only holds up its own queue. A failure in one doesn't stop the rest.
+async fn drain(pool: &DbPool, config: &WebhookConfig) -> Result<(), ApiError> {
+    let tenant_ids = Tenant::read_all_ids(&mut *pool.get().await?).await?;
+    let mut deliveries = JoinSet::new();
+    for tenant_id in tenant_ids {
+        let due = match due_by_webhook(pool, config, tenant_id).await {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::error!("Webhook deliveries of tenant {} failed: {:?}", tenant_id, e);
+                continue;
+            }
+        };
+        for (webhook, entries) in due {
+            let (pool, config) = (pool.clone(), config.clone());
+            deliveries.spawn(async move {
+                if let Err(e) = deliver(&pool, &config, tenant_id, &webhook, entries).await {
+                    tracing::error!("Webhook deliveries to {} failed: {:?}", webhook.url, e);
+                }
+            });
+        }
+    }
+    while deliveries.join_next().await.is_some() {}
+    Ok(())
+}
+
+// Every WEBHOOK_POLL_SECONDS, sends what's due in the outbox. Deliveries are queued in the
+// transaction of the change they announce (see tasks_db_lib::webhooks), so events raised while
+// the dispatcher is behind, or the server is down, go out once it catches up. On shutdown it
+// makes a last pass before the server exits.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Webhooks", |rocket| Box::pin(async move {
+        let (Some(pool), Some(config), Some(dispatcher)) = (
+            rocket.state::<DbPool>().cloned(),
+            rocket.state::<WebhookConfig>().cloned(),
+            rocket.state::<Drain>(),
+        ) else {
+            return;
+        };
+        let mut stop = dispatcher.stop();
+        dispatcher.spawn("Webhooks", async move {
+            let mut interval = rocket::tokio::time::interval(config.poll_every);
+            while stop.tick(&mut interval).await {
+                if let Err(e) = drain(&pool, &config).await {
+                    tracing::error!("Webhook dispatch failed: {:?}", e);
+                }
+            }
+            // one last pass on shutdown, for what the final requests queued
+            if let Err(e) = drain(&pool, &config).await {
+                tracing::error!("Webhook dispatch failed: {:?}", e);
+            }
+        });
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> WebhookConfig {
+        WebhookConfig {
+            poll_every: Duration::from_secs(1),
+            retry_after: Duration::from_secs(1),
+            max_attempts: 1,
+            allow_private_hosts: false,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    #[test]
+    fn only_public_addresses_are_public() {
+        for private in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00:ec2::254", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public(private.parse().unwrap()), "{}", private);
+        }
+        for public in ["93.184.216.34", "2606:4700:4700::1111"] {
+            assert!(is_public(public.parse().unwrap()), "{}", public);
+        }
+    }
+
+    #[rocket::async_test]
+    async fn refuses_internal_hosts() {
+        let config = config();
+        for url in ["http://127.0.0.1:8000/hook", "http://[::1]/hook", "http://169.254.169.254/latest/meta-data", "http://localhost/hook", "http://metadata.google.internal/", "http://api.localhost/"] {
+            assert!(config.refuse_host(&Url::parse(url).unwrap()).await.is_some(), "{}", url);
+        }
+        assert!(config.refuse_host(&Url::parse("https://93.184.216.34/hook").unwrap()).await.is_none());
+        let allowing = WebhookConfig { allow_private_hosts: true, ..config };
+        assert!(allowing.refuse_host(&Url::parse("http://127.0.0.1:8000/hook").unwrap()).await.is_none());
+    }
+
+    #[test]
+    fn signs_the_body_with_the_secret() {
+        // the RFC 4231 test case 2 vector
+        assert_eq!(
+            signature("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_ne!(signature("other", "what do ya want for nothing?"), signature("Jefe", "what do ya want for nothing?"));
+    }
+}
@@ -0,0 +1,147 @@
+use rocket::{Request, Response};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Method};
+use rocket::serde::json::serde_json::{self, Value};
+use std::io::Cursor;
+
+const XSI: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+// GETs whose Accept prefers application/xml (or text/xml) get the JSON body rewritten as XML
+// on the way out, so every handler keeps returning Json<T> and XML clients still get the same
+// fields. Objects become elements named after their keys, arrays repeat an <item> element, and
+// null is an empty element with xsi:nil="true". Errors come back as RFC 7807's
+// application/problem+xml. Other bodies (CSV, event streams, files) are left alone.
+pub struct XmlNegotiation;
+
+fn wants_xml(req: &Request<'_>) -> bool {
+    req.accept().is_some_and(|accept| {
+        let preferred = accept.preferred().media_type();
+        preferred.sub() == "xml" && (preferred.top() == "application" || preferred.top() == "text")
+    })
+}
+
+#[rocket::async_trait]
+impl Fairing for XmlNegotiation {
+    fn info(&self) -> Info {
+        Info { name: "XML content negotiation", kind: Kind::Response }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if req.method() != Method::Get || !wants_xml(req) {
+            return;
+        }
+        let problem = match res.content_type() {
+            Some(content_type) if content_type.top() == "application" && content_type.sub() == "json" => false,
+            Some(content_type) if content_type.top() == "application" && content_type.sub() == "problem+json" => true,
+            _ => return,
+        };
+        let Ok(bytes) = res.body_mut().to_bytes().await else {
+            return;
+        };
+        let xml = match serde_json::from_slice::<Value>(&bytes) {
+            Ok(value) if problem => to_xml("problem", Some("urn:ietf:rfc:7807"), &value),
+            Ok(value) => to_xml("response", None, &value),
+            // not JSON after all; send it as it was
+            Err(_) => {
+                res.set_sized_body(bytes.len(), Cursor::new(bytes));
+                return;
+            }
+        };
+        let content_type = if problem { ContentType::new("application", "problem+xml") } else { ContentType::new("application", "xml") };
+        res.set_header(content_type);
+        res.set_sized_body(xml.len(), Cursor::new(xml));
+    }
+}
+
+// The whole document, with `root` as the outermost element.
+pub fn to_xml(root: &str, namespace: Option<&str>, value: &Value) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    let mut attributes = format!(" xmlns:xsi=\"{}\"", XSI);
+    if let Some(namespace) = namespace {
+        attributes = format!(" xmlns=\"{}\"{}", namespace, attributes);
+    }
+    push_element(&mut xml, root, &attributes, value);
+    xml
+}
+
+// Element names have to start with a letter or _ and hold only letters, digits, _, - and .;
+// map keys that don't (ids, say, or names with spaces) are written as <entry key="...">.
+fn is_element_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !name.to_ascii_lowercase().starts_with("xml")
+}
+
+fn push_element(xml: &mut String, name: &str, attributes: &str, value: &Value) {
+    let (name, attributes) = if is_element_name(name) {
+        (name, attributes.to_string())
+    } else {
+        ("entry", format!(" key=\"{}\"{}", escape(name), attributes))
+    };
+    match value {
+        Value::Null => {
+            xml.push_str(&format!("<{}{} xsi:nil=\"true\"/>", name, attributes));
+            return;
+        }
+        _ => xml.push_str(&format!("<{}{}>", name, attributes)),
+    }
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                push_element(xml, key, "", field);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                push_element(xml, "item", "", item);
+            }
+        }
+        Value::String(text) => xml.push_str(&escape(text)),
+        other => xml.push_str(&other.to_string()),
+    }
+    xml.push_str(&format!("</{}>", name));
+}
+
+// Escapes markup characters and drops the control characters XML 1.0 can't carry at all.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::serde::json::serde_json::json;
+
+    #[test]
+    fn json_becomes_elements_named_after_its_keys() {
+        let value = json!({
+            "items": [{ "task_id": 7, "task_name": "Ship <v2> & \"party\"\u{7}", "due_date": null }],
+            "links": { "self": "/api/v1/tasks/7", "2026 plan": "x" },
+            "total": 1,
+        });
+        assert_eq!(
+            to_xml("response", None, &value),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <response xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\
+             <items><item><due_date xsi:nil=\"true\"/><task_id>7</task_id><task_name>Ship &lt;v2&gt; &amp; &quot;party&quot;</task_name></item></items>\
+             <links><entry key=\"2026 plan\">x</entry><self>/api/v1/tasks/7</self></links>\
+             <total>1</total>\
+             </response>",
+        );
+        let problem = to_xml("problem", Some("urn:ietf:rfc:7807"), &json!({ "status": 404 }));
+        assert!(problem.contains("<problem xmlns=\"urn:ietf:rfc:7807\" xmlns:xsi="));
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::Context;
+use argon2::password_hash::{PasswordHasher, SaltString};
+use argon2::Argon2;
+use clap::{Parser, Subcommand, ValueEnum};
+use diesel_async::AsyncConnection;
+use rand::distributions::{Alphanumeric, DistString};
+use rand::rngs::OsRng;
+use tasks_db_lib::enums::UserRole;
+use tasks_db_lib::models::{Credential, NewUser, Task, TaskStatus, User, UserTask};
+use tasks_db_lib::{tenancy, DbConnection};
+
+// `tasks-cli` works on the database directly, for operators fixing data by hand. It reads
+// DATABASE_URL (and .env) the way the API does and is built for the same backend feature.
+// Changes it makes are audited with no actor, like other system changes.
+
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+const GENERATED_PASSWORD_LEN: usize = 16;
+
+#[derive(Parser)]
+#[command(name = "tasks-cli", about = "Fix up task data without going through the API")]
+struct Cli {
+    /// The database to work on
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    database_url: String,
+    /// The tenant users, tasks and the trash are looked up in
+    #[arg(long, global = true, default_value_t = tenancy::DEFAULT_TENANT)]
+    tenant: i32,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(subcommand)]
+    User(UserCommand),
+    #[command(subcommand)]
+    Task(TaskCommand),
+    #[command(subcommand)]
+    Maintenance(MaintenanceCommand),
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    /// Adds a user with a generated password, which is printed once
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        #[arg(long, value_enum, default_value_t = Role::Member)]
+        role: Role,
+        /// Create the user switched off
+        #[arg(long)]
+        inactive: bool,
+    },
+    /// Gives the user a new generated password, printed once, and signs them out everywhere
+    ResetPassword {
+        email: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TaskCommand {
+    /// Hands all of one user's assignments to another, statuses and all
+    Reassign {
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum MaintenanceCommand {
+    /// Runs the migrations the database hasn't had yet
+    Migrate,
+    /// Deletes tasks, statuses and assignments that have been in the trash longer than the
+    /// retention period for good. Files attached to purged tasks are left for the API's own
+    /// purge, which can reach attachment storage.
+    PurgeTrash {
+        /// Defaults to TRASH_RETENTION_DAYS, as in the API
+        #[arg(long, env = "TRASH_RETENTION_DAYS", default_value_t = DEFAULT_RETENTION_DAYS)]
+        days: i64,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Role {
+    Admin,
+    Manager,
+    Member,
+}
+
+impl From<Role> for UserRole {
+    fn from(role: Role) -> UserRole {
+        match role {
+            Role::Admin => UserRole::Admin,
+            Role::Manager => UserRole::Manager,
+            Role::Member => UserRole::Member,
+        }
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    dotenvy::dotenv().ok();
+    if let Err(e) = run(Cli::parse()).await {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    if let Command::Maintenance(MaintenanceCommand::Migrate) = cli.command {
+        let versions = tasks_db_lib::migrations::run_pending(&cli.database_url).await?;
+        for version in &versions {
+            println!("Ran migration {}", version);
+        }
+        println!("{} migrations run", versions.len());
+        return Ok(());
+    }
+    let mut conn = DbConnection::establish(&cli.database_url).await
+        .with_context(|| format!("Could not connect to {}", cli.database_url))?;
+    tasks_db_lib::configure(&mut conn).await?;
+    tenancy::enter(&mut conn, cli.tenant).await?;
+    match cli.command {
+        Command::User(UserCommand::Create { name, email, role, inactive }) => {
+            if User::read_by_email(&mut conn, &email).await?.is_some() {
+                anyhow::bail!("{} already has an account", email);
+            }
+            let password = generate_password();
+            let user = Credential::register(&mut conn, NewUser { name: &name, email: &email, active: !inactive }, &hash_password(&password)?).await?;
+            let role = UserRole::from(role);
+            if user.role_id != role as i32 {
+                User::set_role(&mut conn, None, user.user_id, None, role).await?;
+            }
+            println!("Created {} <{}> (id {}) as {}; their password is {}", user.name, user.email, user.user_id, role.as_str(), password);
+        }
+        Command::User(UserCommand::ResetPassword { email }) => {
+            let user = find_user(&mut conn, &email).await?;
+            let password = generate_password();
+            Credential::set_password(&mut conn, user.user_id, &hash_password(&password)?).await?;
+            println!("{}'s password is now {}; their sessions have been signed out", user.email, password);
+        }
+        Command::Task(TaskCommand::Reassign { from, to }) => {
+            let from = find_user(&mut conn, &from).await?;
+            let to = find_user(&mut conn, &to).await?;
+            anyhow::ensure!(to.active, "{} is inactive; activate them before handing them work", to.email);
+            let count = UserTask::reassign_all(&mut conn, None, from.user_id, to.user_id).await?;
+            println!("Moved {} assignments from {} to {}", count, from.email, to.email);
+        }
+        Command::Maintenance(MaintenanceCommand::PurgeTrash { days }) => {
+            anyhow::ensure!(days >= 0, "--days must not be negative");
+            let older_than = (chrono::Utc::now() - chrono::Duration::days(days)).naive_utc();
+            // assignments first, so the tasks and statuses they pointed at are free to go
+            let assignments = UserTask::purge_deleted(&mut conn, older_than).await?;
+            let (tasks, attachments) = Task::purge_deleted(&mut conn, older_than).await?;
+            let task_statuses = TaskStatus::purge_deleted(&mut conn, older_than).await?;
+            println!("Purged {} tasks, {} statuses and {} assignments deleted before {}", tasks, task_statuses, assignments, older_than);
+            // the CLI has no attachment storage to reach, so the files are left for the operator
+            for attachment in &attachments {
+                println!("Attachment file {} is no longer referenced", attachment.storage_key);
+            }
+        }
+        Command::Maintenance(MaintenanceCommand::Migrate) => unreachable!("migrations run before connecting"),
+    }
+    Ok(())
+}
+
+async fn find_user(conn: &mut DbConnection, email: &str) -> anyhow::Result<User> {
+    User::read_by_email(conn, email).await?.with_context(|| format!("No user has the email {}", email))
+}
+
+fn generate_password() -> String {
+    Alphanumeric.sample_string(&mut OsRng, GENERATED_PASSWORD_LEN)
+}
+
+// The same argon2 settings the API signs in with.
+fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| anyhow::anyhow!("Could not hash password: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn the_command_line_is_well_formed() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from(["tasks-cli", "--database-url", "x.db", "task", "reassign", "--from", "a@example.com", "--to", "b@example.com", "--tenant", "2"]).unwrap();
+        assert_eq!(cli.tenant, 2);
+        assert!(Cli::try_parse_from(["tasks-cli", "--database-url", "x.db", "user", "create", "--email", "a@example.com"]).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use reqwest::{Method, StatusCode};
+use tasks_db_lib::backup::Backup;
+use tasks_db_lib::pagination::Page;
+use crate::types::*;
+use crate::{check, Client, PageQuery, Result};
+
+// The trash, the audit log and backups, plus the health checks and metrics served next to the
+// API.
+impl Client {
+    // What was deleted in the last `days` days, or the retention period when None.
+    pub async fn get_trash(&self, days: Option<i64>) -> Result<Trash> {
+        self.get_query("/trash", &[("days", days)]).await
+    }
+
+    // Deletes everything past the retention period for good, attachments included.
+    pub async fn purge_trash(&self) -> Result<PurgeResult> {
+        self.json(self.request(Method::DELETE, "/trash/purge")).await
+    }
+
+    // Newest first. `entity` is e.g. "task" or "assignment".
+    pub async fn get_audit_log(&self, entity: Option<&str>, entity_id: Option<&str>, since: Option<DateTime<Utc>>, paging: &PageQuery) -> Result<Page<AuditEntryView>> {
+        let since = since.map(|since| since.to_rfc3339());
+        let filter = [("entity", entity), ("entity_id", entity_id), ("since", since.as_deref())];
+        self.json(self.request(Method::GET, "/audit").query(&filter).query(paging)).await
+    }
+
+    // Every tenant's data; for admins of the default tenant only.
+    pub async fn export_backup(&self) -> Result<Backup> {
+        self.get("/admin/export").await
+    }
+
+    // Replaces everything in the database with the backup, all or nothing.
+    pub async fn import_backup(&self, backup: &Backup) -> Result<RestoredBackup> {
+        self.post("/admin/import", backup).await
+    }
+
+    pub async fn get_liveness(&self) -> Result<Liveness> {
+        self.json(self.root_request(Method::GET, "/health/live")).await
+    }
+
+    // Not being ready is an answer here rather than an error: the body says what's missing.
+    pub async fn get_readiness(&self) -> Result<Readiness> {
+        let response = self.root_request(Method::GET, "/health/ready").send().await?;
+        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return Ok(response.json().await?);
+        }
+        Ok(check(response).await?.json().await?)
+    }
+
+    // In Prometheus' text format.
+    pub async fn get_metrics(&self) -> Result<String> {
+        Ok(self.send(self.root_request(Method::GET, "/metrics")).await?.text().await?)
+    }
+}
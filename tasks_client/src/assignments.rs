@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use tasks_db_lib::activity::Activity;
+use tasks_db_lib::board::BoardColumn;
+use tasks_db_lib::models::{AssignmentDetail, TaskStatus, UserTask};
+use tasks_db_lib::pagination::{CursorPage, Page};
+use tasks_db_lib::stats::{StatusCount, UserWorkload};
+use crate::types::*;
+use crate::{Client, Error, Ids, PageQuery, Result};
+
+// Task statuses, assignments between users and tasks, and what is read off them: exports,
+// the overdue report, stats, the board and the activity feed.
+impl Client {
+    // All statuses, or only those in `ids` when it isn't empty.
+    pub async fn get_task_statuses(&self, ids: &[i32], paging: &PageQuery) -> Result<Page<TaskStatus>> {
+        self.json(self.request(Method::GET, "/tasks_statuses").query(&Ids { ids }).query(paging)).await
+    }
+
+    pub async fn count_task_statuses(&self) -> Result<i64> {
+        self.count("/tasks_statuses/count", &()).await
+    }
+
+    pub async fn get_task_status(&self, id: i32) -> Result<TaskStatus> {
+        self.get(&format!("/tasks_statuses/{}", id)).await
+    }
+
+    pub async fn create_task_status(&self, task_status: &TaskStatusInput) -> Result<TaskStatus> {
+        self.post("/tasks_statuses", task_status).await
+    }
+
+    pub async fn update_task_status(&self, id: i32, version: Option<i32>, task_status: &TaskStatusInput) -> Result<TaskStatus> {
+        self.update(Method::PUT, &format!("/tasks_statuses/{}", id), version, task_status).await
+    }
+
+    pub async fn patch_task_status(&self, id: i32, version: Option<i32>, task_status: &TaskStatusPatch) -> Result<TaskStatus> {
+        self.update(Method::PATCH, &format!("/tasks_statuses/{}", id), version, task_status).await
+    }
+
+    pub async fn delete_task_status(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/tasks_statuses/{}", id)).await
+    }
+
+    pub async fn restore_task_status(&self, id: i32) -> Result<TaskStatus> {
+        self.post_empty(&format!("/tasks_statuses/{}/restore", id)).await
+    }
+
+    // `include` embeds the user, task or status next to each assignment's ids.
+    pub async fn get_user_tasks(&self, filter: &AssignmentQuery, include: &[Include], paging: &PageQuery) -> Result<Page<ExpandedUserTask>> {
+        let mut request = self.request(Method::GET, "/assignments").query(filter).query(paging);
+        if !include.is_empty() {
+            let include: Vec<&str> = include.iter().map(|include| include.as_str()).collect();
+            request = request.query(&[("include", include.join(","))]);
+        }
+        self.json(request).await
+    }
+
+    pub async fn count_user_tasks(&self, filter: &AssignmentQuery) -> Result<i64> {
+        self.count("/assignments/count", filter).await
+    }
+
+    // Assignments with the user, task and status names filled in.
+    pub async fn get_assignment_details(&self, filter: &AssignmentQuery, paging: &PageQuery) -> Result<Page<AssignmentDetail>> {
+        self.json(self.request(Method::GET, "/assignments/detailed").query(filter).query(paging)).await
+    }
+
+    // Every matching assignment as CSV, header first. `paging` only sets the order.
+    pub async fn export_assignments_csv(&self, filter: &AssignmentQuery, paging: &PageQuery) -> Result<String> {
+        let request = self.request(Method::GET, "/assignments/export.csv").query(filter).query(&[("sort", &paging.sort), ("order", &paging.order)]);
+        Ok(self.send(request).await?.text().await?)
+    }
+
+    // Every matching assignment, read from the NDJSON export in one go.
+    pub async fn export_assignments_ndjson(&self, filter: &AssignmentQuery) -> Result<Vec<AssignmentDetail>> {
+        let text = self.send(self.request(Method::GET, "/assignments/export.ndjson").query(filter)).await?.text().await?;
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(Error::Decode))
+            .collect()
+    }
+
+    pub async fn get_overdue_assignments(&self) -> Result<OverdueReport> {
+        self.get("/assignments/overdue").await
+    }
+
+    pub async fn get_user_assignments(&self, id: i32, task_status_id: Option<i32>, paging: &PageQuery) -> Result<Page<UserTask>> {
+        self.json(self.request(Method::GET, &format!("/users/{}/assignments", id)).query(&[("task_status_id", task_status_id)]).query(paging)).await
+    }
+
+    pub async fn get_task_assignments(&self, id: i32, task_status_id: Option<i32>, paging: &PageQuery) -> Result<Page<UserTask>> {
+        self.json(self.request(Method::GET, &format!("/tasks/{}/assignments", id)).query(&[("task_status_id", task_status_id)]).query(paging)).await
+    }
+
+    pub async fn get_user_task(&self, user_id: i32, task_id: i32) -> Result<UserTask> {
+        self.get(&format!("/assignments/{}/{}", user_id, task_id)).await
+    }
+
+    pub async fn create_user_task(&self, user_task: &UserTaskInput) -> Result<UserTask> {
+        self.post("/assignments", user_task).await
+    }
+
+    // Creates the assignment or moves the existing one to the status; sending it twice changes
+    // nothing, which suits sync jobs.
+    pub async fn upsert_user_task(&self, user_task: &UserTaskInput) -> Result<UserTask> {
+        self.put("/assignments", user_task).await
+    }
+
+    pub async fn update_user_task(&self, user_id: i32, task_id: i32, version: Option<i32>, user_task: &UserTaskInput) -> Result<UserTask> {
+        self.update(Method::PUT, &format!("/assignments/{}/{}", user_id, task_id), version, user_task).await
+    }
+
+    pub async fn patch_user_task(&self, user_id: i32, task_id: i32, version: Option<i32>, user_task: &UserTaskPatch) -> Result<UserTask> {
+        self.update(Method::PATCH, &format!("/assignments/{}/{}", user_id, task_id), version, user_task).await
+    }
+
+    pub async fn delete_user_task(&self, user_id: i32, task_id: i32) -> Result<usize> {
+        self.delete(&format!("/assignments/{}/{}", user_id, task_id)).await
+    }
+
+    pub async fn restore_user_task(&self, user_id: i32, task_id: i32) -> Result<UserTask> {
+        self.post_empty(&format!("/assignments/{}/{}/restore", user_id, task_id)).await
+    }
+
+    // The bulk requests run in one transaction and answer 200 with an entry per item, which
+    // carries the status that item would have got on its own.
+    pub async fn bulk_create_user_tasks(&self, user_tasks: &[UserTaskInput]) -> Result<BulkResponse<UserTask>> {
+        self.post("/assignments/bulk", &user_tasks).await
+    }
+
+    pub async fn bulk_update_user_tasks(&self, user_tasks: &[UserTaskInput]) -> Result<BulkResponse<UserTask>> {
+        self.put("/assignments/bulk", &user_tasks).await
+    }
+
+    pub async fn bulk_delete_user_tasks(&self, keys: &[AssignmentKey]) -> Result<BulkResponse<AssignmentKey>> {
+        self.json(self.request(Method::DELETE, "/assignments/bulk").json(&keys)).await
+    }
+
+    pub async fn get_assignments_by_status(&self, user_id: Option<i32>) -> Result<Vec<StatusCount>> {
+        self.get_query("/stats/assignments_by_status", &[("user_id", user_id)]).await
+    }
+
+    pub async fn get_workload(&self) -> Result<Vec<UserWorkload>> {
+        self.get("/stats/workload").await
+    }
+
+    pub async fn get_board(&self) -> Result<Vec<BoardColumn>> {
+        self.get("/board").await
+    }
+
+    // What has happened on the tenant's tasks, oldest first. Pass next_cursor back as `cursor`
+    // to read on.
+    pub async fn get_activity(&self, since: Option<DateTime<Utc>>, cursor: Option<i32>, per_page: Option<i64>) -> Result<CursorPage<Activity>> {
+        let since = since.map(|since| since.to_rfc3339());
+        self.json(self.request(Method::GET, "/activity").query(&[("since", since)]).query(&[("cursor", cursor)]).query(&[("per_page", per_page)])).await
+    }
+}
@@ -0,0 +1,327 @@
+// A typed client for the tasks API, for other Rust services to call it with instead of
+// hand-rolling reqwest calls. Responses are read into tasks_db_lib's model structs, so a field
+// the API renames or retypes breaks the build here rather than a caller at runtime; the request
+// bodies and the few responses that only exist in rocket_app are mirrored in types.rs.
+//
+// Each endpoint is one method, named after its handler in rocket_app and grouped by area the
+// same way. Left out: the event stream and board socket (SSE and WebSocket), the OAuth
+// redirects meant for browsers, the /docs page, and ?fields=, which would make responses
+// partial.
+
+mod users;
+mod tasks;
+mod projects;
+mod assignments;
+mod admin;
+pub mod types;
+
+use std::fmt;
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize, Serializer};
+
+// The API's problem document (RFC 7807), as every failed request returns it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub instance: String,
+    // the X-Request-Id the server logged the failure under
+    pub request_id: String,
+    // one entry per rejected field on 422 responses
+    #[serde(default)]
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    // the request never got an answer, or the answer wasn't what the endpoint returns
+    Http(reqwest::Error),
+    // the API answered with an error status; boxed, as problems are large next to the rest
+    Api(Box<Problem>),
+    // a line of an NDJSON export didn't parse
+    Decode(serde_json::Error),
+}
+
+impl Error {
+    // The response status, when the API answered.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Http(e) => e.status().map(|status| status.as_u16()),
+            Error::Api(problem) => Some(problem.status),
+            Error::Decode(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "request failed: {}", e),
+            Error::Api(problem) => write!(f, "{} {}: {}", problem.status, problem.title, problem.detail),
+            Error::Decode(e) => write!(f, "unexpected response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(e) => Some(e),
+            Error::Api(_) => None,
+            Error::Decode(e) => Some(e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Error {
+        Error::Http(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone)]
+enum Auth {
+    Bearer(String),
+    ApiKey(String),
+}
+
+// ?page=, ?per_page=, ?sort= and ?order= on list endpoints; unset ones take the API's defaults.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_page: Option<i64>,
+    // a column of the listed rows, e.g. "due_date"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    // asc or desc
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Count {
+    count: i64,
+}
+
+// ?ids=1,2,3
+#[derive(Serialize)]
+struct Ids<'a> {
+    #[serde(serialize_with = "comma_separated", skip_serializing_if = "<[i32]>::is_empty")]
+    ids: &'a [i32],
+}
+
+fn comma_separated<S: Serializer>(ids: &[i32], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    let joined: Vec<String> = ids.iter().map(i32::to_string).collect();
+    serializer.serialize_str(&joined.join(","))
+}
+
+// The If-Match an update is sent with: the version it was read at, so a change made since is
+// refused with 412, or None to overwrite whatever is there.
+fn if_match(version: Option<i32>) -> String {
+    version.map_or_else(|| "*".to_string(), |version| format!("\"{}\"", version))
+}
+
+// Turns an error status into Error::Api.
+async fn check(response: Response) -> Result<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let text = response.text().await?;
+    // a proxy in front of the API may answer with something else
+    let problem = serde_json::from_str(&text).unwrap_or_else(|_| Problem {
+        problem_type: "about:blank".to_string(),
+        title: status.canonical_reason().unwrap_or("Error").to_string(),
+        status: status.as_u16(),
+        detail: text,
+        instance: String::new(),
+        request_id: String::new(),
+        errors: Vec::new(),
+    });
+    Err(Error::Api(Box::new(problem)))
+}
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    auth: Option<Auth>,
+}
+
+impl Client {
+    // `base_url` is where the server is, e.g. "http://localhost:8000"; requests go to its
+    // /api/v1 routes.
+    pub fn new(base_url: impl Into<String>) -> Client {
+        Client { http: reqwest::Client::new(), base_url: base_url.into().trim_end_matches('/').to_string(), auth: None }
+    }
+
+    // Sends an access token from login() as a Bearer token.
+    pub fn with_token(mut self, access_token: impl Into<String>) -> Client {
+        self.auth = Some(Auth::Bearer(access_token.into()));
+        self
+    }
+
+    // Sends an API key in X-Api-Key, as services usually authenticate.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Client {
+        self.auth = Some(Auth::ApiKey(api_key.into()));
+        self
+    }
+
+    // For callers that need their own timeouts, proxies or TLS settings.
+    pub fn with_http(mut self, http: reqwest::Client) -> Client {
+        self.http = http;
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.request_url(method, format!("{}/api/v1{}", self.base_url, path))
+    }
+
+    // Health checks and metrics are served outside /api.
+    fn root_request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.request_url(method, format!("{}{}", self.base_url, path))
+    }
+
+    fn request_url(&self, method: Method, url: String) -> RequestBuilder {
+        let request = self.http.request(method, url);
+        match &self.auth {
+            Some(Auth::Bearer(token)) => request.bearer_auth(token),
+            Some(Auth::ApiKey(key)) => request.header("X-Api-Key", key),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        check(request.send().await?).await
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.json(self.request(Method::GET, path)).await
+    }
+
+    async fn get_query<T: DeserializeOwned>(&self, path: &str, query: &impl Serialize) -> Result<T> {
+        self.json(self.request(Method::GET, path).query(query)).await
+    }
+
+    async fn get_page<T: DeserializeOwned>(&self, path: &str, paging: &PageQuery) -> Result<T> {
+        self.get_query(path, paging).await
+    }
+
+    async fn count(&self, path: &str, query: &impl Serialize) -> Result<i64> {
+        Ok(self.get_query::<Count>(path, query).await?.count)
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        self.json(self.request(Method::POST, path).json(body)).await
+    }
+
+    // POSTs that act on the path alone, like restoring or starring.
+    async fn post_empty<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.json(self.request(Method::POST, path)).await
+    }
+
+    async fn put<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        self.json(self.request(Method::PUT, path).json(body)).await
+    }
+
+    // PUT or PATCH on a versioned row, which the API refuses without If-Match.
+    async fn update<T: DeserializeOwned>(&self, method: Method, path: &str, version: Option<i32>, body: &impl Serialize) -> Result<T> {
+        self.json(self.request(method, path).header("If-Match", if_match(version)).json(body)).await
+    }
+
+    // Deletes answer with how many rows went.
+    async fn delete(&self, path: &str) -> Result<usize> {
+        self.json(self.request(Method::DELETE, path)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Answers one request with `status` and `body`, and hands back the request it got.
+    async fn serve_once(status: &'static str, body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // the bodies sent here are small enough to arrive with the headers
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let response = format!("HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", status, body.len(), body);
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (base_url, server)
+    }
+
+    #[tokio::test]
+    async fn updates_send_the_version_and_the_credentials() {
+        let (base_url, server) = serve_once("200 OK", r#"{"task_status_id":2,"status_name":"Done","created_at":"2026-10-01T09:00:00","updated_at":"2026-10-02T09:00:00","version":4}"#).await;
+        let client = Client::new(format!("{}/", base_url)).with_api_key("secret");
+        let status = client.patch_task_status(2, Some(3), &types::TaskStatusPatch { status_name: Some("Done".to_string()) }).await.unwrap();
+        assert_eq!((status.task_status_id, status.version), (2, 4));
+
+        let request = server.await.unwrap().to_lowercase();
+        assert!(request.starts_with("patch /api/v1/tasks_statuses/2 "), "{}", request);
+        assert!(request.contains("if-match: \"3\"\r\n"));
+        assert!(request.contains("x-api-key: secret\r\n"));
+    }
+
+    #[tokio::test]
+    async fn error_statuses_come_back_as_problems() {
+        let (base_url, server) = serve_once("422 Unprocessable Entity", r#"{"type":"about:blank","title":"Unprocessable Entity","status":422,"detail":"The request failed validation","instance":"/api/v1/tags","request_id":"abc","errors":[{"field":"tag_name","message":"must not be empty"}]}"#).await;
+        let client = Client::new(base_url).with_token("token");
+        let error = client.create_tag(&types::TagInput { tag_name: String::new() }).await.unwrap_err();
+        assert_eq!(error.status(), Some(422));
+        match error {
+            Error::Api(problem) => {
+                assert_eq!(problem.request_id, "abc");
+                assert_eq!(problem.errors[0].field, "tag_name");
+            }
+            other => panic!("expected a problem, got {:?}", other),
+        }
+        assert!(server.await.unwrap().to_lowercase().contains("authorization: bearer token\r\n"));
+
+        // anything that isn't a problem document keeps its text as the detail
+        let (base_url, _server) = serve_once("502 Bad Gateway", "upstream down").await;
+        match Client::new(base_url).count_tasks().await.unwrap_err() {
+            Error::Api(problem) => assert_eq!((problem.status, problem.detail.as_str()), (502, "upstream down")),
+            other => panic!("expected a problem, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn queries_join_ids_and_leave_out_unset_options() {
+        let client = Client::new("http://localhost:8000");
+        let url = |request: RequestBuilder| request.build().unwrap().url().to_string();
+        let paging = PageQuery { per_page: Some(10), ..PageQuery::default() };
+        assert_eq!(url(client.request(Method::GET, "/users").query(&Ids { ids: &[1, 2, 3] }).query(&paging)), "http://localhost:8000/api/v1/users?ids=1%2C2%2C3&per_page=10");
+        assert_eq!(url(client.request(Method::GET, "/users").query(&Ids { ids: &[] }).query(&[("unread", None::<bool>)])), "http://localhost:8000/api/v1/users");
+        assert_eq!(if_match(None), "*");
+    }
+}
@@ -0,0 +1,84 @@
+use reqwest::Method;
+use tasks_db_lib::board::BoardColumn;
+use tasks_db_lib::models::{Project, SlackIntegration, Task, Team, User};
+use tasks_db_lib::pagination::Page;
+use crate::types::*;
+use crate::{Client, PageQuery, Result};
+
+// Projects, the Slack channel each may post to, and the teams that staff them.
+impl Client {
+    pub async fn get_projects(&self, paging: &PageQuery) -> Result<Page<Project>> {
+        self.get_page("/projects", paging).await
+    }
+
+    pub async fn get_project(&self, id: i32) -> Result<Project> {
+        self.get(&format!("/projects/{}", id)).await
+    }
+
+    pub async fn create_project(&self, project: &ProjectInput) -> Result<Project> {
+        self.post("/projects", project).await
+    }
+
+    pub async fn update_project(&self, id: i32, version: Option<i32>, project: &ProjectInput) -> Result<Project> {
+        self.update(Method::PUT, &format!("/projects/{}", id), version, project).await
+    }
+
+    // The project's tasks stay, just without a project.
+    pub async fn delete_project(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/projects/{}", id)).await
+    }
+
+    pub async fn get_project_tasks(&self, id: i32, paging: &PageQuery) -> Result<Page<Task>> {
+        self.get_page(&format!("/projects/{}/tasks", id), paging).await
+    }
+
+    pub async fn get_project_board(&self, id: i32) -> Result<Vec<BoardColumn>> {
+        self.get(&format!("/projects/{}/board", id)).await
+    }
+
+    pub async fn get_slack_integration(&self, id: i32) -> Result<SlackIntegration> {
+        self.get(&format!("/projects/{}/slack", id)).await
+    }
+
+    // Turns the integration on, or points it at another channel.
+    pub async fn put_slack_integration(&self, id: i32, integration: &SlackIntegrationInput) -> Result<SlackIntegration> {
+        self.put(&format!("/projects/{}/slack", id), integration).await
+    }
+
+    pub async fn delete_slack_integration(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/projects/{}/slack", id)).await
+    }
+
+    pub async fn get_teams(&self, paging: &PageQuery) -> Result<Page<Team>> {
+        self.get_page("/teams", paging).await
+    }
+
+    pub async fn get_team(&self, id: i32) -> Result<Team> {
+        self.get(&format!("/teams/{}", id)).await
+    }
+
+    pub async fn create_team(&self, team: &TeamInput) -> Result<Team> {
+        self.post("/teams", team).await
+    }
+
+    pub async fn update_team(&self, id: i32, version: Option<i32>, team: &TeamInput) -> Result<Team> {
+        self.update(Method::PUT, &format!("/teams/{}", id), version, team).await
+    }
+
+    pub async fn delete_team(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/teams/{}", id)).await
+    }
+
+    pub async fn get_team_members(&self, id: i32) -> Result<Vec<User>> {
+        self.get(&format!("/teams/{}/members", id)).await
+    }
+
+    // Adds the users; the answer is all of the team's members.
+    pub async fn add_team_members(&self, id: i32, members: &TeamMembersInput) -> Result<Vec<User>> {
+        self.post(&format!("/teams/{}/members", id), members).await
+    }
+
+    pub async fn remove_team_member(&self, id: i32, user_id: i32) -> Result<usize> {
+        self.delete(&format!("/teams/{}/members/{}", id, user_id)).await
+    }
+}
@@ -0,0 +1,217 @@
+use reqwest::Method;
+use reqwest::multipart::{Form, Part};
+use tasks_db_lib::models::{Attachment, CommentRevision, Mention, StarredTask, Tag, Task, TaskWatcher};
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::search::{SearchHit, Suggestion};
+use crate::types::*;
+use crate::{Client, PageQuery, Result};
+
+// Tasks and what hangs off them: history, subtasks, recurrence, templates, dependencies,
+// watching and starring, tags, comments and attachments, plus search and import.
+impl Client {
+    pub async fn get_tasks(&self, query: &TaskQuery, paging: &PageQuery) -> Result<Page<Task>> {
+        self.json(self.request(Method::GET, "/tasks").query(query).query(paging)).await
+    }
+
+    pub async fn count_tasks(&self) -> Result<i64> {
+        self.count("/tasks/count", &()).await
+    }
+
+    pub async fn get_task(&self, id: i32) -> Result<Task> {
+        self.get(&format!("/tasks/{}", id)).await
+    }
+
+    pub async fn create_task(&self, task: &TaskInput) -> Result<Task> {
+        self.post("/tasks", task).await
+    }
+
+    pub async fn update_task(&self, id: i32, version: Option<i32>, task: &TaskInput) -> Result<Task> {
+        self.update(Method::PUT, &format!("/tasks/{}", id), version, task).await
+    }
+
+    // Moves the task to the trash.
+    pub async fn delete_task(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/tasks/{}", id)).await
+    }
+
+    pub async fn restore_task(&self, id: i32) -> Result<Task> {
+        self.post_empty(&format!("/tasks/{}/restore", id)).await
+    }
+
+    // The task's earlier versions, newest first.
+    pub async fn get_task_history(&self, id: i32, paging: &PageQuery) -> Result<Page<TaskRevisionView>> {
+        self.get_page(&format!("/tasks/{}/history", id), paging).await
+    }
+
+    // Puts the task back the way it was at `version`, as a new version.
+    pub async fn revert_task(&self, id: i32, version: i32) -> Result<Task> {
+        self.post_empty(&format!("/tasks/{}/revert/{}", id, version)).await
+    }
+
+    pub async fn get_subtasks(&self, id: i32) -> Result<SubtaskList> {
+        self.get(&format!("/tasks/{}/subtasks", id)).await
+    }
+
+    // Copies the task with its tags and subtasks; `assignments` also assigns the same users to
+    // each copy.
+    pub async fn clone_task(&self, id: i32, assignments: bool) -> Result<Task> {
+        self.json(self.request(Method::POST, &format!("/tasks/{}/clone", id)).query(&[("assignments", assignments)])).await
+    }
+
+    // `format` is csv (the default), trello or jira; `file` is the export's contents.
+    pub async fn import_tasks(&self, format: Option<&str>, file_name: &str, file: Vec<u8>) -> Result<BulkResponse<ImportedTask>> {
+        let form = Form::new().part("file", Part::bytes(file).file_name(file_name.to_string()).mime_str("text/csv")?);
+        self.json(self.request(Method::POST, "/tasks/import").query(&[("format", format)]).multipart(form)).await
+    }
+
+    pub async fn pause_recurrence(&self, id: i32) -> Result<Task> {
+        self.post_empty(&format!("/tasks/{}/recurrence/pause", id)).await
+    }
+
+    pub async fn resume_recurrence(&self, id: i32) -> Result<Task> {
+        self.post_empty(&format!("/tasks/{}/recurrence/resume", id)).await
+    }
+
+    pub async fn get_task_templates(&self, paging: &PageQuery) -> Result<Page<TaskTemplateView>> {
+        self.get_page("/task_templates", paging).await
+    }
+
+    pub async fn get_task_template(&self, id: i32) -> Result<TaskTemplateView> {
+        self.get(&format!("/task_templates/{}", id)).await
+    }
+
+    pub async fn create_task_template(&self, template: &TaskTemplateInput) -> Result<TaskTemplateView> {
+        self.post("/task_templates", template).await
+    }
+
+    pub async fn update_task_template(&self, id: i32, version: Option<i32>, template: &TaskTemplateInput) -> Result<TaskTemplateView> {
+        self.update(Method::PUT, &format!("/task_templates/{}", id), version, template).await
+    }
+
+    pub async fn delete_task_template(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/task_templates/{}", id)).await
+    }
+
+    pub async fn create_task_from_template(&self, template_id: i32) -> Result<Task> {
+        self.post_empty(&format!("/tasks/from_template/{}", template_id)).await
+    }
+
+    pub async fn get_task_dependencies(&self, id: i32) -> Result<TaskDependencies> {
+        self.get(&format!("/tasks/{}/dependencies", id)).await
+    }
+
+    pub async fn add_task_dependency(&self, id: i32, dependency: &DependencyInput) -> Result<TaskDependencies> {
+        self.post(&format!("/tasks/{}/dependencies", id), dependency).await
+    }
+
+    pub async fn remove_task_dependency(&self, id: i32, blocking_task_id: i32) -> Result<usize> {
+        self.delete(&format!("/tasks/{}/dependencies/{}", id, blocking_task_id)).await
+    }
+
+    pub async fn watch_task(&self, id: i32) -> Result<TaskWatcher> {
+        self.post_empty(&format!("/tasks/{}/watch", id)).await
+    }
+
+    pub async fn unwatch_task(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/tasks/{}/watch", id)).await
+    }
+
+    pub async fn star_task(&self, id: i32) -> Result<StarredTask> {
+        self.post_empty(&format!("/tasks/{}/star", id)).await
+    }
+
+    pub async fn unstar_task(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/tasks/{}/star", id)).await
+    }
+
+    pub async fn get_starred_tasks(&self, paging: &PageQuery) -> Result<Page<Task>> {
+        self.get_page("/users/me/starred", paging).await
+    }
+
+    pub async fn get_tags(&self, paging: &PageQuery) -> Result<Page<Tag>> {
+        self.get_page("/tags", paging).await
+    }
+
+    pub async fn get_tag(&self, id: i32) -> Result<Tag> {
+        self.get(&format!("/tags/{}", id)).await
+    }
+
+    pub async fn create_tag(&self, tag: &TagInput) -> Result<Tag> {
+        self.post("/tags", tag).await
+    }
+
+    pub async fn update_tag(&self, id: i32, version: Option<i32>, tag: &TagInput) -> Result<Tag> {
+        self.update(Method::PUT, &format!("/tags/{}", id), version, tag).await
+    }
+
+    pub async fn delete_tag(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/tags/{}", id)).await
+    }
+
+    pub async fn get_task_tags(&self, id: i32) -> Result<Vec<Tag>> {
+        self.get(&format!("/tasks/{}/tags", id)).await
+    }
+
+    // Adds the tags; the answer is all of the task's tags.
+    pub async fn tag_task(&self, id: i32, tags: &TaskTagsInput) -> Result<Vec<Tag>> {
+        self.post(&format!("/tasks/{}/tags", id), tags).await
+    }
+
+    pub async fn untag_task(&self, id: i32, tag_id: i32) -> Result<usize> {
+        self.delete(&format!("/tasks/{}/tags/{}", id, tag_id)).await
+    }
+
+    pub async fn get_task_comments(&self, id: i32, paging: &PageQuery) -> Result<Page<CommentView>> {
+        self.get_page(&format!("/tasks/{}/comments", id), paging).await
+    }
+
+    pub async fn create_comment(&self, id: i32, comment: &CommentInput) -> Result<CommentView> {
+        self.post(&format!("/tasks/{}/comments", id), comment).await
+    }
+
+    pub async fn update_comment(&self, id: i32, version: Option<i32>, comment: &CommentInput) -> Result<CommentView> {
+        self.update(Method::PUT, &format!("/comments/{}", id), version, comment).await
+    }
+
+    pub async fn delete_comment(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/comments/{}", id)).await
+    }
+
+    pub async fn get_comment_history(&self, id: i32, paging: &PageQuery) -> Result<Page<CommentRevision>> {
+        self.get_page(&format!("/comments/{}/history", id), paging).await
+    }
+
+    pub async fn get_user_mentions(&self, id: i32, paging: &PageQuery) -> Result<Page<Mention>> {
+        self.get_page(&format!("/users/{}/mentions", id), paging).await
+    }
+
+    pub async fn get_task_attachments(&self, id: i32) -> Result<Vec<Attachment>> {
+        self.get(&format!("/tasks/{}/attachments", id)).await
+    }
+
+    // `content_type` must be one the server's ATTACHMENT_CONTENT_TYPES allows.
+    pub async fn upload_attachment(&self, id: i32, file_name: &str, content_type: &str, file: Vec<u8>) -> Result<Attachment> {
+        let form = Form::new().part("file", Part::bytes(file).file_name(file_name.to_string()).mime_str(content_type)?);
+        self.json(self.request(Method::POST, &format!("/tasks/{}/attachments", id)).multipart(form)).await
+    }
+
+    // The file's contents; its name and type are on the Attachment.
+    pub async fn download_attachment(&self, id: i32) -> Result<Vec<u8>> {
+        Ok(self.send(self.request(Method::GET, &format!("/attachments/{}", id))).await?.bytes().await?.to_vec())
+    }
+
+    pub async fn delete_attachment(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/attachments/{}", id)).await
+    }
+
+    // Tasks with every word of `q` in their name, best match first; `fuzzy` also accepts words
+    // with a typo or two.
+    pub async fn search_tasks(&self, q: &str, fuzzy: bool, paging: &PageQuery) -> Result<Page<SearchHit>> {
+        self.json(self.request(Method::GET, "/search").query(&[("q", q)]).query(&[("fuzzy", fuzzy)]).query(paging)).await
+    }
+
+    // Ids and names only, for pickers; an empty `q` gives an empty list.
+    pub async fn suggest_tasks(&self, q: &str, limit: Option<i64>) -> Result<Vec<Suggestion>> {
+        self.json(self.request(Method::GET, "/tasks/suggest").query(&[("q", q)]).query(&[("limit", limit)])).await
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::BTreeMap;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use tasks_db_lib::enums::TaskPriority;
+use tasks_db_lib::models::{ApiKey, CalendarFeed, Comment, Task, TaskStatus, User, UserTask, Webhook};
+use tasks_db_lib::overdue::OverdueAssignment;
+use tasks_db_lib::revisions::AssignmentSnapshot;
+
+// Request bodies and the responses rocket_app builds itself rather than returning a
+// tasks_db_lib model. Each mirrors the struct of the same name in rocket_app; keep them in step.
+
+// --- request bodies ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserInput {
+    pub name: String,
+    pub email: String,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleInput {
+    // 1 admin, 2 manager, 3 member; see tasks_db_lib::enums::UserRole
+    pub role_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PasswordInput {
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskInput {
+    pub task_name: String,
+    // None means no deadline
+    pub due_date: Option<NaiveDate>,
+    // low, medium, high or urgent; medium when None
+    pub priority: Option<String>,
+    pub parent_task_id: Option<i32>,
+    // e.g. "FREQ=WEEKLY;INTERVAL=2"
+    pub recurrence: Option<String>,
+    pub project_id: Option<i32>,
+}
+
+// The filters on GET /tasks; unset ones don't filter.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskQuery {
+    #[serde(serialize_with = "crate::comma_separated", skip_serializing_if = "Vec::is_empty")]
+    pub ids: Vec<i32>,
+    // exclusive, and skipping tasks with no due date
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_before: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_after: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<TaskPriority>,
+    // a tag name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyInput {
+    pub blocking_task_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskStatusInput {
+    pub status_name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskStatusPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagInput {
+    pub tag_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskTagsInput {
+    pub tag_ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProjectInput {
+    pub project_name: String,
+    pub description: Option<String>,
+    // only the team's members may be assigned the project's tasks; None for anyone
+    pub team_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlackIntegrationInput {
+    // https://hooks.slack.com/services/...
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamInput {
+    pub team_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamMembersInput {
+    pub user_ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskTemplateInput {
+    pub template_name: String,
+    pub task_name: String,
+    pub priority: Option<String>,
+    pub due_in_days: Option<i32>,
+    pub recurrence: Option<String>,
+    pub assignee_ids: Vec<i32>,
+    pub subtask_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentInput {
+    // @bob mentions (the part of the email before the @) notify those users
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct UserTaskInput {
+    pub user_id: i32,
+    pub task_id: i32,
+    pub task_status_id: i32,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct UserTaskPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_status_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssignmentKey {
+    pub user_id: i32,
+    pub task_id: i32,
+}
+
+// The filters shared by the assignment listings, count and exports; unset ones don't filter.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct AssignmentQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_status_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<i32>,
+}
+
+// What ?include= embeds next to each assignment's ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Include {
+    User,
+    Task,
+    Status,
+}
+
+impl Include {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Include::User => "user",
+            Include::Task => "task",
+            Include::Status => "status",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterInput {
+    pub name: String,
+    pub email: String,
+    pub password: String,
+    // sets up a new organization of this name instead of joining the default one
+    pub organization: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoginInput {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RefreshInput {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogoutInput {
+    pub refresh_token: Option<String>,
+    // revokes every session of the user, not just this one
+    pub all_sessions: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKeyInput {
+    pub name: String,
+    // requests per UTC day and per calendar month; None is no limit
+    pub daily_quota: Option<i32>,
+    pub monthly_quota: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApiKeyQuotasInput {
+    pub daily_quota: Option<i32>,
+    pub monthly_quota: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookInput {
+    pub url: String,
+    // one of tasks_db_lib::webhooks::EVENT_TYPES, e.g. "task.created"
+    pub event_type: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationPreferenceInput {
+    // one of tasks_db_lib::preferences::NOTIFICATION_EVENT_TYPES
+    pub event_type: String,
+    pub email: bool,
+    pub webhook: bool,
+    pub in_app: bool,
+    pub push: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PushSubscriptionInput {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+// --- responses ---
+
+#[derive(Debug, Deserialize)]
+pub struct TaskRevisionView {
+    pub version: i32,
+    pub task_name: String,
+    // None on revisions recorded before the whole task was kept
+    pub priority: Option<TaskPriority>,
+    pub due_date: Option<NaiveDate>,
+    pub parent_task_id: Option<i32>,
+    pub recurrence: Option<String>,
+    pub project_id: Option<i32>,
+    pub assignments: Vec<AssignmentSnapshot>,
+    pub actor_user_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubtaskList {
+    pub total: usize,
+    pub completed: i64,
+    pub subtasks: Vec<Task>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskDependencies {
+    pub blocked_by: Vec<Task>,
+    pub blocks: Vec<Task>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskTemplateView {
+    pub template_id: i32,
+    pub template_name: String,
+    pub task_name: String,
+    pub priority: TaskPriority,
+    pub due_in_days: Option<i32>,
+    pub recurrence: Option<String>,
+    pub assignee_ids: Vec<i32>,
+    pub subtask_names: Vec<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentView {
+    #[serde(flatten)]
+    pub comment: Comment,
+    pub edited: bool,
+}
+
+// An assignment with whatever ?include= asked for.
+#[derive(Debug, Deserialize)]
+pub struct ExpandedUserTask {
+    #[serde(flatten)]
+    pub user_task: UserTask,
+    pub user: Option<User>,
+    pub task: Option<Task>,
+    pub status: Option<TaskStatus>,
+}
+
+// What a bulk request or import did with each item, in the order they were sent.
+#[derive(Debug, Deserialize)]
+pub struct BulkResponse<T> {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult<T>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkItemResult<T> {
+    pub index: usize,
+    pub status: u16,
+    pub item: Option<T>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub errors: Vec<crate::FieldError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportedTask {
+    pub task: Task,
+    pub assignments: Vec<UserTask>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverdueReport {
+    pub checked_at: NaiveDateTime,
+    pub assignments: Vec<OverdueAssignment>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    // seconds
+    pub expires_in: u64,
+    pub refresh_token: String,
+}
+
+// The raw key is only ever returned here.
+#[derive(Debug, Deserialize)]
+pub struct CreatedApiKey {
+    pub api_key: String,
+    pub key: ApiKey,
+}
+
+// The signing secret is only ever returned here.
+#[derive(Debug, Deserialize)]
+pub struct CreatedWebhook {
+    pub secret: String,
+    pub webhook: Webhook,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VapidPublicKey {
+    pub public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatedCalendarFeed {
+    pub token: String,
+    // the feed's URL after the server's address
+    pub path: String,
+    pub feed: CalendarFeed,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Trash {
+    pub tasks: Vec<Task>,
+    pub task_statuses: Vec<TaskStatus>,
+    pub assignments: Vec<UserTask>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeResult {
+    pub older_than: NaiveDateTime,
+    pub tasks: usize,
+    pub task_statuses: usize,
+    pub assignments: usize,
+    pub attachments: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditEntryView {
+    pub audit_id: i32,
+    pub actor_user_id: Option<i32>,
+    pub action: String,
+    pub entity: String,
+    pub entity_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoredBackup {
+    pub exported_at: NaiveDateTime,
+    // rows restored per table
+    pub rows: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Liveness {
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Readiness {
+    pub status: String,
+    // "ok", or why the database can't be used
+    pub database: String,
+    pub pending_migrations: Vec<String>,
+}
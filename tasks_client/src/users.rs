@@ -0,0 +1,154 @@
+use reqwest::Method;
+use tasks_db_lib::models::{ApiKey, Notification, NotificationPreference, PushSubscription, Role, User, Webhook};
+use tasks_db_lib::pagination::Page;
+use tasks_db_lib::quotas::Usage;
+use crate::types::*;
+use crate::{Client, Ids, PageQuery, Result};
+
+// Users and roles, signing in, and what each user keeps for themselves: API keys, webhooks,
+// notifications and their preferences, push subscriptions and the calendar feed.
+impl Client {
+    // All users, or only those in `ids` when it isn't empty.
+    pub async fn get_users(&self, ids: &[i32], paging: &PageQuery) -> Result<Page<User>> {
+        self.json(self.request(Method::GET, "/users").query(&Ids { ids }).query(paging)).await
+    }
+
+    pub async fn count_users(&self) -> Result<i64> {
+        self.count("/users/count", &()).await
+    }
+
+    pub async fn get_user(&self, id: i32) -> Result<User> {
+        self.get(&format!("/users/{}", id)).await
+    }
+
+    pub async fn create_user(&self, user: &UserInput) -> Result<User> {
+        self.post("/users", user).await
+    }
+
+    pub async fn update_user(&self, id: i32, version: Option<i32>, user: &UserInput) -> Result<User> {
+        self.update(Method::PUT, &format!("/users/{}", id), version, user).await
+    }
+
+    pub async fn delete_user(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/users/{}", id)).await
+    }
+
+    pub async fn update_user_role(&self, id: i32, version: Option<i32>, role: &RoleInput) -> Result<User> {
+        self.update(Method::PUT, &format!("/users/{}/role", id), version, role).await
+    }
+
+    pub async fn reset_user_password(&self, id: i32, password: &PasswordInput) -> Result<User> {
+        self.put(&format!("/users/{}/password", id), password).await
+    }
+
+    pub async fn get_roles(&self) -> Result<Vec<Role>> {
+        self.get("/roles").await
+    }
+
+    pub async fn register(&self, registration: &RegisterInput) -> Result<User> {
+        self.post("/users/register", registration).await
+    }
+
+    // Pass the access token to with_token() to act as the user.
+    pub async fn login(&self, login: &LoginInput) -> Result<TokenResponse> {
+        self.post("/login", login).await
+    }
+
+    pub async fn refresh_token(&self, refresh: &RefreshInput) -> Result<TokenResponse> {
+        self.post("/token/refresh", refresh).await
+    }
+
+    // How many sessions were signed out.
+    pub async fn logout(&self, logout: &LogoutInput) -> Result<usize> {
+        self.post("/logout", logout).await
+    }
+
+    pub async fn me(&self) -> Result<User> {
+        self.get("/me").await
+    }
+
+    pub async fn get_api_keys(&self) -> Result<Vec<ApiKey>> {
+        self.get("/api_keys").await
+    }
+
+    pub async fn create_api_key(&self, api_key: &ApiKeyInput) -> Result<CreatedApiKey> {
+        self.post("/api_keys", api_key).await
+    }
+
+    pub async fn revoke_api_key(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/api_keys/{}", id)).await
+    }
+
+    pub async fn update_api_key_quotas(&self, id: i32, quotas: &ApiKeyQuotasInput) -> Result<ApiKey> {
+        self.put(&format!("/api_keys/{}/quotas", id), quotas).await
+    }
+
+    pub async fn get_api_key_usage(&self, id: i32) -> Result<Usage> {
+        self.get(&format!("/api_keys/{}/usage", id)).await
+    }
+
+    pub async fn get_webhooks(&self) -> Result<Vec<Webhook>> {
+        self.get("/webhooks").await
+    }
+
+    pub async fn create_webhook(&self, webhook: &WebhookInput) -> Result<CreatedWebhook> {
+        self.post("/webhooks", webhook).await
+    }
+
+    pub async fn delete_webhook(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/webhooks/{}", id)).await
+    }
+
+    pub async fn get_notification_preferences(&self) -> Result<Vec<NotificationPreference>> {
+        self.get("/users/me/notification_preferences").await
+    }
+
+    pub async fn update_notification_preferences(&self, preferences: &[NotificationPreferenceInput]) -> Result<Vec<NotificationPreference>> {
+        self.put("/users/me/notification_preferences", &preferences).await
+    }
+
+    // The caller's notifications, newest first; `unread` keeps only those not read yet.
+    pub async fn get_notifications(&self, unread: Option<bool>, paging: &PageQuery) -> Result<Page<Notification>> {
+        self.json(self.request(Method::GET, "/users/me/notifications").query(&[("unread", unread)]).query(paging)).await
+    }
+
+    pub async fn count_unread_notifications(&self) -> Result<i64> {
+        self.count("/users/me/notifications/unread_count", &()).await
+    }
+
+    pub async fn read_notification(&self, id: i32) -> Result<Notification> {
+        self.post_empty(&format!("/notifications/{}/read", id)).await
+    }
+
+    // The key browsers subscribe with.
+    pub async fn get_push_public_key(&self) -> Result<VapidPublicKey> {
+        self.get("/push/public_key").await
+    }
+
+    pub async fn get_push_subscriptions(&self) -> Result<Vec<PushSubscription>> {
+        self.get("/users/me/push_subscriptions").await
+    }
+
+    pub async fn create_push_subscription(&self, subscription: &PushSubscriptionInput) -> Result<PushSubscription> {
+        self.post("/users/me/push_subscriptions", subscription).await
+    }
+
+    pub async fn delete_push_subscription(&self, id: i32) -> Result<usize> {
+        self.delete(&format!("/users/me/push_subscriptions/{}", id)).await
+    }
+
+    // The user's assignments as iCalendar text, read as the signed-in user or, without one,
+    // with the feed's token.
+    pub async fn get_calendar(&self, id: i32, token: Option<&str>) -> Result<String> {
+        let request = self.request(Method::GET, &format!("/users/{}/calendar.ics", id)).query(&[("token", token)]);
+        Ok(self.send(request).await?.text().await?)
+    }
+
+    pub async fn create_calendar_feed(&self) -> Result<CreatedCalendarFeed> {
+        self.post_empty("/users/me/calendar_feed").await
+    }
+
+    pub async fn delete_calendar_feed(&self) -> Result<usize> {
+        self.delete("/users/me/calendar_feed").await
+    }
+}
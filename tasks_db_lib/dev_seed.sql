@@ -0,0 +1,18 @@
+-- Development only: sets up the users from the seed_data migration for local work. Never
+-- run it against a real database. From tasks_db_lib/:
+--   sqlite3 ../rocket_app/data/tasks.db < dev_seed.sql
+-- or, on PostgreSQL:
+--   psql "$DATABASE_URL" -f dev_seed.sql
+-- or, on MySQL:
+--   mysql tasks < dev_seed.sql
+-- `cargo run -- seed` from rocket_app/ does the same on any backend, and puts back seeded
+-- statuses, users and tasks that have since been removed; see rocket_app/src/seed.rs.
+
+-- User 1 administers, user 2 manages, everyone else stays a member
+UPDATE users SET role_id = 1 WHERE user_id = 1;
+UPDATE users SET role_id = 2 WHERE user_id = 2;
+
+-- Every seeded user signs in with the password "password123"
+INSERT INTO credentials (user_id, password_hash)
+SELECT user_id, '$argon2id$v=19$m=19456,t=2,p=1$Ojet9BRO7av8DuJfAg6YWw$GvWzWvQ8D5a2hA10ArUJMDYzln0wFntmwkjkWYRYb3Q' FROM users WHERE user_id <= 10
+AND user_id NOT IN (SELECT user_id FROM credentials);
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE `api_keys`(
+	`api_key_id` INTEGER NOT NULL PRIMARY KEY,
+	`user_id` INTEGER NOT NULL,
+	`name` TEXT NOT NULL,
+	`key_prefix` TEXT NOT NULL,
+	`key_hash` TEXT NOT NULL UNIQUE,
+	`revoked` BOOL NOT NULL DEFAULT 0,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	FOREIGN KEY (`user_id`) REFERENCES `users`(`user_id`)
+);
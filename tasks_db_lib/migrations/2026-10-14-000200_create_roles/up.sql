@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE `roles`(
+	`role_id` INTEGER NOT NULL PRIMARY KEY,
+	`role_name` TEXT NOT NULL UNIQUE
+);
+
+INSERT INTO roles (role_id, role_name) VALUES (1, 'admin');
+INSERT INTO roles (role_id, role_name) VALUES (2, 'manager');
+INSERT INTO roles (role_id, role_name) VALUES (3, 'member');
+
+-- Everyone starts as a member. The first admin is named by ADMIN_EMAIL (see rocket_app's
+-- roles.rs), and dev_seed.sql sets up the seeded users' roles for local work.
+ALTER TABLE `users` ADD COLUMN `role_id` INTEGER NOT NULL DEFAULT 3 REFERENCES `roles`(`role_id`);
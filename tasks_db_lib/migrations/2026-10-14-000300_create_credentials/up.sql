@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE `credentials`(
+	`user_id` INTEGER NOT NULL PRIMARY KEY,
+	`password_hash` TEXT NOT NULL,
+	FOREIGN KEY (`user_id`) REFERENCES `users`(`user_id`)
+);
+
+-- Existing users have no password until they are given one; see dev_seed.sql for local work.
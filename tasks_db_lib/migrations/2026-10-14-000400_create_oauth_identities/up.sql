@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE `oauth_identities`(
+	`oauth_identity_id` INTEGER NOT NULL PRIMARY KEY,
+	`user_id` INTEGER NOT NULL,
+	`provider` TEXT NOT NULL,
+	`provider_user_id` TEXT NOT NULL,
+	UNIQUE(`provider`, `provider_user_id`),
+	FOREIGN KEY (`user_id`) REFERENCES `users`(`user_id`)
+);
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE `refresh_tokens`(
+	`refresh_token_id` INTEGER NOT NULL PRIMARY KEY,
+	`user_id` INTEGER NOT NULL,
+	`token_hash` TEXT NOT NULL UNIQUE,
+	`expires_at` TIMESTAMP NOT NULL,
+	`revoked` BOOL NOT NULL DEFAULT 0,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	FOREIGN KEY (`user_id`) REFERENCES `users`(`user_id`)
+);
+
+-- Access tokens (by jti) that were logged out before they expired
+CREATE TABLE `revoked_tokens`(
+	`jti` TEXT NOT NULL PRIMARY KEY,
+	`expires_at` TIMESTAMP NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+CREATE UNIQUE INDEX `task_statuses_status_name_key` ON `task_statuses`(`status_name`);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `task_statuses_status_name_key`;
+CREATE UNIQUE INDEX `task_statuses_status_name_key` ON `task_statuses`(`status_name`);
+
+ALTER TABLE `user_tasks` DROP COLUMN `deleted_at`;
+ALTER TABLE `task_statuses` DROP COLUMN `deleted_at`;
+ALTER TABLE `tasks` DROP COLUMN `deleted_at`;
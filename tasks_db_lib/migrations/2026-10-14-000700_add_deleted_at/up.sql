@@ -0,0 +1,8 @@
+-- Your SQL goes here
+ALTER TABLE `tasks` ADD COLUMN `deleted_at` TIMESTAMP;
+ALTER TABLE `task_statuses` ADD COLUMN `deleted_at` TIMESTAMP;
+ALTER TABLE `user_tasks` ADD COLUMN `deleted_at` TIMESTAMP;
+
+-- a deleted status shouldn't block reusing its name
+DROP INDEX `task_statuses_status_name_key`;
+CREATE UNIQUE INDEX `task_statuses_status_name_key` ON `task_statuses`(`status_name`) WHERE `deleted_at` IS NULL;
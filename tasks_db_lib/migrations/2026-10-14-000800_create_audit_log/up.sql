@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- actor_user_id is deliberately not a foreign key: the log has to outlive deleted users.
+CREATE TABLE `audit_log`(
+	`audit_id` INTEGER NOT NULL PRIMARY KEY,
+	`actor_user_id` INTEGER,
+	`action` TEXT NOT NULL,
+	`entity` TEXT NOT NULL,
+	`entity_id` TEXT NOT NULL,
+	`before_json` TEXT,
+	`after_json` TEXT,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX `audit_log_entity_created_at` ON `audit_log`(`entity`, `created_at`);
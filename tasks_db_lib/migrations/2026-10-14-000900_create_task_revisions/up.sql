@@ -0,0 +1,23 @@
+-- Your SQL goes here
+CREATE TABLE `task_revisions`(
+	`task_id` INTEGER NOT NULL REFERENCES `tasks`(`task_id`) ON DELETE CASCADE,
+	`version` INTEGER NOT NULL,
+	`task_name` TEXT NOT NULL,
+	`assignments_json` TEXT NOT NULL,
+	`actor_user_id` INTEGER,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(`task_id`, `version`)
+);
+
+-- Existing tasks start their history at version 1 with what they look like today.
+INSERT INTO `task_revisions`(`task_id`, `version`, `task_name`, `assignments_json`)
+SELECT `t`.`task_id`, 1, `t`.`task_name`, (
+	SELECT json_group_array(json_object('user_id', `a`.`user_id`, 'task_status_id', `a`.`task_status_id`))
+	FROM (
+		SELECT `user_id`, `task_status_id` FROM `user_tasks`
+		WHERE `task_id` = `t`.`task_id` AND `deleted_at` IS NULL
+		ORDER BY `user_id`
+	) AS `a`
+)
+FROM `tasks` AS `t`
+WHERE `t`.`deleted_at` IS NULL;
@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE `users` DROP COLUMN `created_at`;
+ALTER TABLE `users` DROP COLUMN `updated_at`;
+ALTER TABLE `tasks` DROP COLUMN `created_at`;
+ALTER TABLE `tasks` DROP COLUMN `updated_at`;
+ALTER TABLE `task_statuses` DROP COLUMN `created_at`;
+ALTER TABLE `task_statuses` DROP COLUMN `updated_at`;
+ALTER TABLE `user_tasks` DROP COLUMN `created_at`;
+ALTER TABLE `user_tasks` DROP COLUMN `updated_at`;
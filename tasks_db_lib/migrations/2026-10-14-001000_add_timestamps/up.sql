@@ -0,0 +1,18 @@
+-- Your SQL goes here
+-- SQLite only accepts a constant default in ADD COLUMN, so existing rows are stamped
+-- with the time of the migration and the crud layer sets both columns from then on.
+ALTER TABLE `users` ADD COLUMN `created_at` TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+ALTER TABLE `users` ADD COLUMN `updated_at` TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+UPDATE `users` SET `created_at` = CURRENT_TIMESTAMP, `updated_at` = CURRENT_TIMESTAMP;
+
+ALTER TABLE `tasks` ADD COLUMN `created_at` TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+ALTER TABLE `tasks` ADD COLUMN `updated_at` TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+UPDATE `tasks` SET `created_at` = CURRENT_TIMESTAMP, `updated_at` = CURRENT_TIMESTAMP;
+
+ALTER TABLE `task_statuses` ADD COLUMN `created_at` TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+ALTER TABLE `task_statuses` ADD COLUMN `updated_at` TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+UPDATE `task_statuses` SET `created_at` = CURRENT_TIMESTAMP, `updated_at` = CURRENT_TIMESTAMP;
+
+ALTER TABLE `user_tasks` ADD COLUMN `created_at` TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+ALTER TABLE `user_tasks` ADD COLUMN `updated_at` TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+UPDATE `user_tasks` SET `created_at` = CURRENT_TIMESTAMP, `updated_at` = CURRENT_TIMESTAMP;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE `users` DROP COLUMN `version`;
+ALTER TABLE `tasks` DROP COLUMN `version`;
+ALTER TABLE `task_statuses` DROP COLUMN `version`;
+ALTER TABLE `user_tasks` DROP COLUMN `version`;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+-- Optimistic locking: every update bumps `version`, and clients send it back in If-Match.
+ALTER TABLE `users` ADD COLUMN `version` INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE `tasks` ADD COLUMN `version` INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE `task_statuses` ADD COLUMN `version` INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE `user_tasks` ADD COLUMN `version` INTEGER NOT NULL DEFAULT 1;
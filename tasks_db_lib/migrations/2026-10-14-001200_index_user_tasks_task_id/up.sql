@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- The primary key (user_id, task_id) already covers lookups by user; this covers lookups by task.
+CREATE INDEX `user_tasks_task_id` ON `user_tasks`(`task_id`);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- Full-text index over live task names. rowid is the task_id; the crud layer keeps it
+-- in sync (tasks_db_lib::search), so trashed tasks drop out and restored ones come back.
+CREATE VIRTUAL TABLE `task_search` USING fts5(`task_name`, tokenize = 'unicode61 remove_diacritics 2');
+
+INSERT INTO `task_search`(`rowid`, `task_name`)
+SELECT `task_id`, `task_name` FROM `tasks` WHERE `deleted_at` IS NULL;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE `tasks` ADD COLUMN `due_date` DATE;
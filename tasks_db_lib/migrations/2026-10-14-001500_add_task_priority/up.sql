@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- 1 = urgent ... 4 = low, so ascending order puts the most pressing work first
+ALTER TABLE `tasks` ADD COLUMN `priority` INTEGER NOT NULL DEFAULT 3;
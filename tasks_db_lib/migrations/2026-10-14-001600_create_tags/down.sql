@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `task_tags_tag_id`;
+DROP TABLE IF EXISTS `task_tags`;
+DROP TABLE IF EXISTS `tags`;
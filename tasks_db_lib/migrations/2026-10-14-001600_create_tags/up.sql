@@ -0,0 +1,20 @@
+-- Your SQL goes here
+-- NOCASE so "Backend" and "backend" are the same tag, both for uniqueness and for ?tag=
+CREATE TABLE `tags`(
+	`tag_id` INTEGER NOT NULL PRIMARY KEY,
+	`tag_name` TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE TABLE `task_tags`(
+	`task_id` INTEGER NOT NULL,
+	`tag_id` INTEGER NOT NULL,
+	PRIMARY KEY (`task_id`, `tag_id`),
+	FOREIGN KEY (`task_id`) REFERENCES `tasks`(`task_id`),
+	FOREIGN KEY (`tag_id`) REFERENCES `tags`(`tag_id`)
+);
+
+-- ?tag= looks tasks up by tag; the primary key already covers lookups by task
+CREATE INDEX `task_tags_tag_id` ON `task_tags`(`tag_id`);
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE `comments`(
+	`comment_id` INTEGER NOT NULL PRIMARY KEY,
+	`task_id` INTEGER NOT NULL,
+	`author_id` INTEGER NOT NULL,
+	`body` TEXT NOT NULL,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY (`task_id`) REFERENCES `tasks`(`task_id`),
+	FOREIGN KEY (`author_id`) REFERENCES `users`(`user_id`)
+);
+
+-- a task's thread is always read by task_id, oldest first
+CREATE INDEX `comments_task_id` ON `comments`(`task_id`, `created_at`);
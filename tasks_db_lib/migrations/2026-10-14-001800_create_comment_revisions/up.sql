@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- One row per version a comment had before it was edited. created_at is when that version
+-- was written; edited_by/edited_at record who replaced it and when.
+CREATE TABLE `comment_revisions`(
+	`comment_id` INTEGER NOT NULL REFERENCES `comments`(`comment_id`) ON DELETE CASCADE,
+	`version` INTEGER NOT NULL,
+	`body` TEXT NOT NULL,
+	`created_at` TIMESTAMP NOT NULL,
+	`edited_by` INTEGER,
+	`edited_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(`comment_id`, `version`)
+);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `notifications_user_id`;
+DROP TABLE IF EXISTS `notifications`;
+DROP INDEX IF EXISTS `mentions_user_id`;
+DROP TABLE IF EXISTS `mentions`;
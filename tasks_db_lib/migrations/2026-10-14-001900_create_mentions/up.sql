@@ -0,0 +1,25 @@
+-- Your SQL goes here
+CREATE TABLE `mentions`(
+	`comment_id` INTEGER NOT NULL REFERENCES `comments`(`comment_id`) ON DELETE CASCADE,
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(`comment_id`, `user_id`)
+);
+
+-- GET /users/<id>/mentions reads by user, newest first
+CREATE INDEX `mentions_user_id` ON `mentions`(`user_id`, `created_at`);
+
+-- Something a user should hear about. `kind` says what happened ("mention", ...); task_id
+-- and comment_id point at what it happened to, when that applies.
+CREATE TABLE `notifications`(
+	`notification_id` INTEGER NOT NULL PRIMARY KEY,
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`kind` TEXT NOT NULL,
+	`task_id` INTEGER,
+	`comment_id` INTEGER,
+	`message` TEXT NOT NULL,
+	`read_at` TIMESTAMP,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX `notifications_user_id` ON `notifications`(`user_id`, `created_at`);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `attachments_task_id`;
+DROP TABLE IF EXISTS `attachments`;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+-- Metadata for files uploaded to a task. The bytes themselves live in attachment storage
+-- under `storage_key`; file_name is what the uploader called the file.
+CREATE TABLE `attachments`(
+	`attachment_id` INTEGER NOT NULL PRIMARY KEY,
+	`task_id` INTEGER NOT NULL REFERENCES `tasks`(`task_id`),
+	`uploaded_by` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`file_name` TEXT NOT NULL,
+	`content_type` TEXT NOT NULL,
+	`size_bytes` BIGINT NOT NULL,
+	`storage_key` TEXT NOT NULL UNIQUE,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX `attachments_task_id` ON `attachments`(`task_id`);
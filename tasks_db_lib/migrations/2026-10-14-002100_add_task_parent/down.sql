@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `tasks_parent_task_id`;
+ALTER TABLE `tasks` DROP COLUMN `parent_task_id`;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- NULL for top-level tasks; otherwise the task this one is a subtask of
+ALTER TABLE `tasks` ADD COLUMN `parent_task_id` INTEGER REFERENCES `tasks`(`task_id`);
+
+CREATE INDEX `tasks_parent_task_id` ON `tasks`(`parent_task_id`);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `task_dependencies_blocked_task_id`;
+DROP TABLE IF EXISTS `task_dependencies`;
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- blocking_task_id has to be finished before blocked_task_id can be.
+CREATE TABLE `task_dependencies`(
+	`blocking_task_id` INTEGER NOT NULL REFERENCES `tasks`(`task_id`),
+	`blocked_task_id` INTEGER NOT NULL REFERENCES `tasks`(`task_id`),
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(`blocking_task_id`, `blocked_task_id`),
+	CHECK(`blocking_task_id` <> `blocked_task_id`)
+);
+
+CREATE INDEX `task_dependencies_blocked_task_id` ON `task_dependencies`(`blocked_task_id`);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE `tasks` DROP COLUMN `next_occurrence_id`;
+ALTER TABLE `tasks` DROP COLUMN `recurrence_paused`;
+ALTER TABLE `tasks` DROP COLUMN `recurrence`;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- `recurrence` is a rule like FREQ=WEEKLY;INTERVAL=2, NULL for one-off tasks. Once an
+-- occurrence is complete the scheduler creates the next one and records it in
+-- next_occurrence_id, which is also how it knows not to create it twice.
+ALTER TABLE `tasks` ADD COLUMN `recurrence` TEXT;
+ALTER TABLE `tasks` ADD COLUMN `recurrence_paused` BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE `tasks` ADD COLUMN `next_occurrence_id` INTEGER REFERENCES `tasks`(`task_id`);
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+-- assignee_ids_json and subtask_names_json are JSON arrays, copied onto each task made from the template
+CREATE TABLE `task_templates`(
+	`template_id` INTEGER NOT NULL PRIMARY KEY,
+	`template_name` TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	`task_name` TEXT NOT NULL,
+	`priority` INTEGER NOT NULL DEFAULT 3,
+	`due_in_days` INTEGER,
+	`recurrence` TEXT,
+	`assignee_ids_json` TEXT NOT NULL DEFAULT '[]',
+	`subtask_names_json` TEXT NOT NULL DEFAULT '[]',
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1
+);
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE `projects`(
+	`project_id` INTEGER NOT NULL PRIMARY KEY,
+	`project_name` TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	`description` TEXT,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1
+);
+
+-- NULL for tasks that don't belong to any project
+ALTER TABLE `tasks` ADD COLUMN `project_id` INTEGER REFERENCES `projects`(`project_id`);
+
+CREATE INDEX `tasks_project_id` ON `tasks`(`project_id`);
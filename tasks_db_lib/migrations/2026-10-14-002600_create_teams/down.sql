@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE `projects` DROP COLUMN `team_id`;
+DROP INDEX IF EXISTS `team_members_user_id`;
+DROP TABLE IF EXISTS `team_members`;
+DROP TABLE IF EXISTS `teams`;
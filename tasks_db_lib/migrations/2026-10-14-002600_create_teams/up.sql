@@ -0,0 +1,23 @@
+-- Your SQL goes here
+CREATE TABLE `teams`(
+	`team_id` INTEGER NOT NULL PRIMARY KEY,
+	`team_name` TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE TABLE `team_members`(
+	`team_id` INTEGER NOT NULL,
+	`user_id` INTEGER NOT NULL,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY (`team_id`, `user_id`),
+	FOREIGN KEY (`team_id`) REFERENCES `teams`(`team_id`),
+	FOREIGN KEY (`user_id`) REFERENCES `users`(`user_id`)
+);
+
+-- a user's teams; the primary key already covers lookups by team
+CREATE INDEX `team_members_user_id` ON `team_members`(`user_id`);
+
+-- NULL for projects anyone may be assigned work in
+ALTER TABLE `projects` ADD COLUMN `team_id` INTEGER REFERENCES `teams`(`team_id`);
@@ -0,0 +1,72 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE `task_templates_old`(
+	`template_id` INTEGER NOT NULL PRIMARY KEY,
+	`template_name` TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	`task_name` TEXT NOT NULL,
+	`priority` INTEGER NOT NULL DEFAULT 3,
+	`due_in_days` INTEGER,
+	`recurrence` TEXT,
+	`assignee_ids_json` TEXT NOT NULL DEFAULT '[]',
+	`subtask_names_json` TEXT NOT NULL DEFAULT '[]',
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1
+);
+INSERT INTO `task_templates_old` (`template_id`, `template_name`, `task_name`, `priority`, `due_in_days`, `recurrence`, `assignee_ids_json`, `subtask_names_json`, `created_at`, `updated_at`, `version`)
+	SELECT `template_id`, `template_name`, `task_name`, `priority`, `due_in_days`, `recurrence`, `assignee_ids_json`, `subtask_names_json`, `created_at`, `updated_at`, `version` FROM `task_templates`;
+DROP TABLE `task_templates`;
+ALTER TABLE `task_templates_old` RENAME TO `task_templates`;
+
+CREATE TABLE `projects_old`(
+	`project_id` INTEGER NOT NULL PRIMARY KEY,
+	`project_name` TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	`description` TEXT,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1,
+	`team_id` INTEGER REFERENCES `teams`(`team_id`)
+);
+INSERT INTO `projects_old` (`project_id`, `project_name`, `description`, `created_at`, `updated_at`, `version`, `team_id`)
+	SELECT `project_id`, `project_name`, `description`, `created_at`, `updated_at`, `version`, `team_id` FROM `projects`;
+DROP TABLE `projects`;
+ALTER TABLE `projects_old` RENAME TO `projects`;
+
+CREATE TABLE `teams_old`(
+	`team_id` INTEGER NOT NULL PRIMARY KEY,
+	`team_name` TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1
+);
+INSERT INTO `teams_old` (`team_id`, `team_name`, `created_at`, `updated_at`, `version`)
+	SELECT `team_id`, `team_name`, `created_at`, `updated_at`, `version` FROM `teams`;
+DROP TABLE `teams`;
+ALTER TABLE `teams_old` RENAME TO `teams`;
+
+CREATE TABLE `tags_old`(
+	`tag_id` INTEGER NOT NULL PRIMARY KEY,
+	`tag_name` TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1
+);
+INSERT INTO `tags_old` (`tag_id`, `tag_name`, `created_at`, `updated_at`, `version`)
+	SELECT `tag_id`, `tag_name`, `created_at`, `updated_at`, `version` FROM `tags`;
+DROP TABLE `tags`;
+ALTER TABLE `tags_old` RENAME TO `tags`;
+
+DROP INDEX `task_statuses_status_name_key`;
+CREATE UNIQUE INDEX `task_statuses_status_name_key` ON `task_statuses`(`status_name`) WHERE `deleted_at` IS NULL;
+
+DROP INDEX IF EXISTS `audit_log_tenant_id`;
+DROP INDEX IF EXISTS `user_tasks_tenant_id`;
+DROP INDEX IF EXISTS `tasks_tenant_id`;
+DROP INDEX IF EXISTS `users_tenant_id`;
+
+ALTER TABLE `audit_log` DROP COLUMN `tenant_id`;
+ALTER TABLE `user_tasks` DROP COLUMN `tenant_id`;
+ALTER TABLE `task_statuses` DROP COLUMN `tenant_id`;
+ALTER TABLE `tasks` DROP COLUMN `tenant_id`;
+ALTER TABLE `users` DROP COLUMN `tenant_id`;
+
+DROP TABLE IF EXISTS `tenants`;
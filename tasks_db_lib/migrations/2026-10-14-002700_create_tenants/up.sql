@@ -0,0 +1,92 @@
+-- Your SQL goes here
+-- An organization. Every user, task, status, assignment, tag, project, team, template and
+-- audit entry belongs to exactly one, and queries only ever see the caller's.
+CREATE TABLE `tenants`(
+	`tenant_id` INTEGER NOT NULL PRIMARY KEY,
+	`tenant_name` TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- everything that exists so far, and self-registered users, live here
+INSERT INTO `tenants` (`tenant_id`, `tenant_name`) VALUES (1, 'Default');
+
+ALTER TABLE `users` ADD COLUMN `tenant_id` INTEGER NOT NULL DEFAULT 1 REFERENCES `tenants`(`tenant_id`);
+ALTER TABLE `tasks` ADD COLUMN `tenant_id` INTEGER NOT NULL DEFAULT 1 REFERENCES `tenants`(`tenant_id`);
+ALTER TABLE `task_statuses` ADD COLUMN `tenant_id` INTEGER NOT NULL DEFAULT 1 REFERENCES `tenants`(`tenant_id`);
+ALTER TABLE `user_tasks` ADD COLUMN `tenant_id` INTEGER NOT NULL DEFAULT 1 REFERENCES `tenants`(`tenant_id`);
+ALTER TABLE `audit_log` ADD COLUMN `tenant_id` INTEGER NOT NULL DEFAULT 1 REFERENCES `tenants`(`tenant_id`);
+
+CREATE INDEX `users_tenant_id` ON `users`(`tenant_id`);
+CREATE INDEX `tasks_tenant_id` ON `tasks`(`tenant_id`);
+CREATE INDEX `user_tasks_tenant_id` ON `user_tasks`(`tenant_id`);
+CREATE INDEX `audit_log_tenant_id` ON `audit_log`(`tenant_id`, `audit_id`);
+
+-- two organizations may both have an "In Progress"
+DROP INDEX `task_statuses_status_name_key`;
+CREATE UNIQUE INDEX `task_statuses_status_name_key` ON `task_statuses`(`tenant_id`, `status_name`) WHERE `deleted_at` IS NULL;
+
+-- Names of tags, projects, teams and templates are unique per tenant rather than overall.
+-- SQLite can't change a column's UNIQUE constraint in place, so those tables are rebuilt.
+CREATE TABLE `tags_new`(
+	`tag_id` INTEGER NOT NULL PRIMARY KEY,
+	`tag_name` TEXT NOT NULL COLLATE NOCASE,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1,
+	`tenant_id` INTEGER NOT NULL DEFAULT 1 REFERENCES `tenants`(`tenant_id`),
+	UNIQUE(`tenant_id`, `tag_name`)
+);
+INSERT INTO `tags_new` (`tag_id`, `tag_name`, `created_at`, `updated_at`, `version`)
+	SELECT `tag_id`, `tag_name`, `created_at`, `updated_at`, `version` FROM `tags`;
+DROP TABLE `tags`;
+ALTER TABLE `tags_new` RENAME TO `tags`;
+
+CREATE TABLE `teams_new`(
+	`team_id` INTEGER NOT NULL PRIMARY KEY,
+	`team_name` TEXT NOT NULL COLLATE NOCASE,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1,
+	`tenant_id` INTEGER NOT NULL DEFAULT 1 REFERENCES `tenants`(`tenant_id`),
+	UNIQUE(`tenant_id`, `team_name`)
+);
+INSERT INTO `teams_new` (`team_id`, `team_name`, `created_at`, `updated_at`, `version`)
+	SELECT `team_id`, `team_name`, `created_at`, `updated_at`, `version` FROM `teams`;
+DROP TABLE `teams`;
+ALTER TABLE `teams_new` RENAME TO `teams`;
+
+CREATE TABLE `projects_new`(
+	`project_id` INTEGER NOT NULL PRIMARY KEY,
+	`project_name` TEXT NOT NULL COLLATE NOCASE,
+	`description` TEXT,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1,
+	`team_id` INTEGER REFERENCES `teams`(`team_id`),
+	`tenant_id` INTEGER NOT NULL DEFAULT 1 REFERENCES `tenants`(`tenant_id`),
+	UNIQUE(`tenant_id`, `project_name`)
+);
+INSERT INTO `projects_new` (`project_id`, `project_name`, `description`, `created_at`, `updated_at`, `version`, `team_id`)
+	SELECT `project_id`, `project_name`, `description`, `created_at`, `updated_at`, `version`, `team_id` FROM `projects`;
+DROP TABLE `projects`;
+ALTER TABLE `projects_new` RENAME TO `projects`;
+
+CREATE TABLE `task_templates_new`(
+	`template_id` INTEGER NOT NULL PRIMARY KEY,
+	`template_name` TEXT NOT NULL COLLATE NOCASE,
+	`task_name` TEXT NOT NULL,
+	`priority` INTEGER NOT NULL DEFAULT 3,
+	`due_in_days` INTEGER,
+	`recurrence` TEXT,
+	`assignee_ids_json` TEXT NOT NULL DEFAULT '[]',
+	`subtask_names_json` TEXT NOT NULL DEFAULT '[]',
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`version` INTEGER NOT NULL DEFAULT 1,
+	`tenant_id` INTEGER NOT NULL DEFAULT 1 REFERENCES `tenants`(`tenant_id`),
+	UNIQUE(`tenant_id`, `template_name`)
+);
+INSERT INTO `task_templates_new` (`template_id`, `template_name`, `task_name`, `priority`, `due_in_days`, `recurrence`, `assignee_ids_json`, `subtask_names_json`, `created_at`, `updated_at`, `version`)
+	SELECT `template_id`, `template_name`, `task_name`, `priority`, `due_in_days`, `recurrence`, `assignee_ids_json`, `subtask_names_json`, `created_at`, `updated_at`, `version` FROM `task_templates`;
+DROP TABLE `task_templates`;
+ALTER TABLE `task_templates_new` RENAME TO `task_templates`;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `task_watchers_user_id`;
+DROP TABLE IF EXISTS `task_watchers`;
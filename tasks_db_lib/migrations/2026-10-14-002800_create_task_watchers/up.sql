@@ -0,0 +1,10 @@
+-- Your SQL goes here
+-- Users who asked to hear about changes to a task's assignments.
+CREATE TABLE `task_watchers`(
+	`task_id` INTEGER NOT NULL REFERENCES `tasks`(`task_id`),
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(`task_id`, `user_id`)
+);
+
+CREATE INDEX `task_watchers_user_id` ON `task_watchers`(`user_id`);
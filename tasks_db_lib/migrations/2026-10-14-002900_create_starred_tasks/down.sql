@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `starred_tasks_task_id`;
+DROP TABLE IF EXISTS `starred_tasks`;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+-- Tasks a user has pinned for themselves.
+CREATE TABLE `starred_tasks`(
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`task_id` INTEGER NOT NULL REFERENCES `tasks`(`task_id`),
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(`user_id`, `task_id`)
+);
+
+CREATE INDEX `starred_tasks_task_id` ON `starred_tasks`(`task_id`);
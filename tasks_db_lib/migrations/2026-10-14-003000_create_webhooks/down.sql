@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `webhooks_event_type`;
+DROP TABLE IF EXISTS `webhooks`;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- A URL to POST to whenever an event of `event_type` ("task.created", ...) happens in the tenant.
+CREATE TABLE `webhooks`(
+	`webhook_id` INTEGER NOT NULL PRIMARY KEY,
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`url` TEXT NOT NULL,
+	`event_type` TEXT NOT NULL,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`tenant_id` INTEGER NOT NULL REFERENCES `tenants`(`tenant_id`)
+);
+
+CREATE INDEX `webhooks_event_type` ON `webhooks`(`tenant_id`, `event_type`);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS `outbox_next_attempt_at`;
+DROP TABLE IF EXISTS `outbox`;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- Webhook deliveries waiting to be sent. A row is written in the same transaction as the change
+-- it announces and deleted once the webhook's URL has accepted it.
+CREATE TABLE `outbox`(
+	`outbox_id` INTEGER NOT NULL PRIMARY KEY,
+	`webhook_id` INTEGER NOT NULL REFERENCES `webhooks`(`webhook_id`),
+	`payload` TEXT NOT NULL,
+	`attempts` INTEGER NOT NULL DEFAULT 0,
+	`last_error` TEXT,
+	`next_attempt_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX `outbox_next_attempt_at` ON `outbox`(`next_attempt_at`);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+-- One row per assignment that has been emailed about its task coming due, for the due date it
+-- was reminded of; moving the due date makes the assignment due for another reminder.
+CREATE TABLE `due_reminders`(
+	`task_id` INTEGER NOT NULL REFERENCES `tasks`(`task_id`),
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`due_date` DATE NOT NULL,
+	`sent_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(`task_id`, `user_id`)
+);
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- Channels a user has set for one event type. Users without a row for an event type get
+-- everything, so only changed settings are stored.
+CREATE TABLE `notification_preferences`(
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`event_type` TEXT NOT NULL,
+	`email` BOOL NOT NULL,
+	`webhook` BOOL NOT NULL,
+	`in_app` BOOL NOT NULL,
+	PRIMARY KEY(`user_id`, `event_type`)
+);
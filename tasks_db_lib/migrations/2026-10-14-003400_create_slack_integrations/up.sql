@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- A Slack incoming webhook URL that hears about one project's tasks.
+CREATE TABLE `slack_integrations`(
+	`project_id` INTEGER NOT NULL PRIMARY KEY REFERENCES `projects`(`project_id`),
+	`webhook_url` TEXT NOT NULL,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	`updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- Databases migrated before create_credentials stopped doing so gave every existing user the
+-- same published dev password. Drop those credentials, and the sessions signed in with them,
+-- so the accounts need a new password (or an OAuth login) before they can be used again.
+DELETE FROM `refresh_tokens` WHERE `user_id` IN (
+	SELECT `user_id` FROM `credentials` WHERE `password_hash` = '$argon2id$v=19$m=19456,t=2,p=1$Ojet9BRO7av8DuJfAg6YWw$GvWzWvQ8D5a2hA10ArUJMDYzln0wFntmwkjkWYRYb3Q'
+);
+DELETE FROM `credentials` WHERE `password_hash` = '$argon2id$v=19$m=19456,t=2,p=1$Ojet9BRO7av8DuJfAg6YWw$GvWzWvQ8D5a2hA10ArUJMDYzln0wFntmwkjkWYRYb3Q';
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE `task_revisions` DROP COLUMN `project_id`;
+ALTER TABLE `task_revisions` DROP COLUMN `recurrence`;
+ALTER TABLE `task_revisions` DROP COLUMN `parent_task_id`;
+ALTER TABLE `task_revisions` DROP COLUMN `priority`;
+ALTER TABLE `task_revisions` DROP COLUMN `due_date`;
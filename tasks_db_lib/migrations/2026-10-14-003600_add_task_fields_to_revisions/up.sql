@@ -0,0 +1,18 @@
+-- Your SQL goes here
+-- Revisions now keep the whole task, not just its name. `priority` is never NULL on a task, so a
+-- NULL here marks a revision written before these columns existed; reverting to one of those
+-- leaves the other fields as they are.
+ALTER TABLE `task_revisions` ADD COLUMN `due_date` DATE;
+ALTER TABLE `task_revisions` ADD COLUMN `priority` INTEGER;
+ALTER TABLE `task_revisions` ADD COLUMN `parent_task_id` INTEGER;
+ALTER TABLE `task_revisions` ADD COLUMN `recurrence` TEXT;
+ALTER TABLE `task_revisions` ADD COLUMN `project_id` INTEGER;
+
+-- The newest revision of each task describes the task as it is today.
+UPDATE `task_revisions` SET
+	`due_date` = (SELECT `due_date` FROM `tasks` WHERE `tasks`.`task_id` = `task_revisions`.`task_id`),
+	`priority` = (SELECT `priority` FROM `tasks` WHERE `tasks`.`task_id` = `task_revisions`.`task_id`),
+	`parent_task_id` = (SELECT `parent_task_id` FROM `tasks` WHERE `tasks`.`task_id` = `task_revisions`.`task_id`),
+	`recurrence` = (SELECT `recurrence` FROM `tasks` WHERE `tasks`.`task_id` = `task_revisions`.`task_id`),
+	`project_id` = (SELECT `project_id` FROM `tasks` WHERE `tasks`.`task_id` = `task_revisions`.`task_id`)
+WHERE `version` = (SELECT MAX(`version`) FROM `task_revisions` AS `r` WHERE `r`.`task_id` = `task_revisions`.`task_id`);
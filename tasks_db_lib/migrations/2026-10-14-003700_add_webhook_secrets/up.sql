@@ -0,0 +1,6 @@
+-- Your SQL goes here
+-- The key every delivery to the webhook is signed with (the X-Webhook-Signature header). It is
+-- only shown when the webhook is registered, so the ones registered before signing get a random
+-- key nobody knows; their owners re-register them to verify signatures.
+ALTER TABLE `webhooks` ADD COLUMN `secret` TEXT NOT NULL DEFAULT '';
+UPDATE `webhooks` SET `secret` = lower(hex(randomblob(32)));
@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE `due_reminders_by_task`(
+	`task_id` INTEGER NOT NULL REFERENCES `tasks`(`task_id`),
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`due_date` DATE NOT NULL,
+	`sent_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(`task_id`, `user_id`)
+);
+INSERT INTO `due_reminders_by_task` (`task_id`, `user_id`, `due_date`, `sent_at`)
+	SELECT `task_id`, `user_id`, `due_date`, `sent_at` FROM `due_reminders` WHERE `channel` = 'email';
+DROP TABLE `due_reminders`;
+ALTER TABLE `due_reminders_by_task` RENAME TO `due_reminders`;
+
+ALTER TABLE `notification_preferences` DROP COLUMN `push`;
+
+DROP TABLE IF EXISTS `push_subscriptions`;
@@ -0,0 +1,29 @@
+-- Your SQL goes here
+-- A browser's Web Push subscription, as PushSubscription.toJSON() gives it: the push service
+-- URL to POST to, and the keys the payload is encrypted for. A browser has one endpoint, so
+-- subscribing it again (as another user, say) takes the row over.
+CREATE TABLE `push_subscriptions`(
+	`push_subscription_id` INTEGER NOT NULL PRIMARY KEY,
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`endpoint` TEXT NOT NULL UNIQUE,
+	`p256dh` TEXT NOT NULL,
+	`auth` TEXT NOT NULL,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX `push_subscriptions_user_id` ON `push_subscriptions`(`user_id`);
+
+ALTER TABLE `notification_preferences` ADD COLUMN `push` BOOL NOT NULL DEFAULT 1;
+
+-- Email and push each remind an assignee once per due date, independently of each other.
+CREATE TABLE `due_reminders_by_channel`(
+	`task_id` INTEGER NOT NULL REFERENCES `tasks`(`task_id`),
+	`user_id` INTEGER NOT NULL REFERENCES `users`(`user_id`),
+	`channel` TEXT NOT NULL,
+	`due_date` DATE NOT NULL,
+	`sent_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(`task_id`, `user_id`, `channel`)
+);
+INSERT INTO `due_reminders_by_channel` (`task_id`, `user_id`, `channel`, `due_date`, `sent_at`)
+	SELECT `task_id`, `user_id`, 'email', `due_date`, `sent_at` FROM `due_reminders`;
+DROP TABLE `due_reminders`;
+ALTER TABLE `due_reminders_by_channel` RENAME TO `due_reminders`;
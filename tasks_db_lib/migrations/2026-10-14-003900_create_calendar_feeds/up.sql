@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- A user's private iCalendar feed. Calendar apps subscribe by URL and can't send an
+-- Authorization header, so the URL carries a token of its own; only its SHA-256 hash is kept.
+CREATE TABLE `calendar_feeds`(
+	`user_id` INTEGER NOT NULL PRIMARY KEY REFERENCES `users`(`user_id`),
+	`token_hash` TEXT NOT NULL UNIQUE,
+	`created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
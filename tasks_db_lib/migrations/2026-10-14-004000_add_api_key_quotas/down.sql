@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS `api_key_usage`;
+ALTER TABLE `api_keys` DROP COLUMN `monthly_quota`;
+ALTER TABLE `api_keys` DROP COLUMN `daily_quota`;
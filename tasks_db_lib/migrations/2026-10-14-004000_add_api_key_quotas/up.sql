@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- How many requests an API key may make per UTC day and per calendar month; NULL is no limit.
+ALTER TABLE `api_keys` ADD COLUMN `daily_quota` INTEGER;
+ALTER TABLE `api_keys` ADD COLUMN `monthly_quota` INTEGER;
+
+-- Requests made with each key, one row per key per day; a month's usage is the sum of its days.
+CREATE TABLE `api_key_usage`(
+	`api_key_id` INTEGER NOT NULL REFERENCES `api_keys`(`api_key_id`),
+	`day` DATE NOT NULL,
+	`requests` INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY(`api_key_id`, `day`)
+);
@@ -0,0 +1,37 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS calendar_feeds;
+DROP TABLE IF EXISTS due_reminders;
+DROP TABLE IF EXISTS push_subscriptions;
+DROP TABLE IF EXISTS slack_integrations;
+DROP TABLE IF EXISTS notification_preferences;
+DROP TABLE IF EXISTS outbox;
+DROP TABLE IF EXISTS webhooks;
+DROP TABLE IF EXISTS starred_tasks;
+DROP TABLE IF EXISTS task_watchers;
+DROP TABLE IF EXISTS task_templates;
+DROP TABLE IF EXISTS team_members;
+DROP TABLE IF EXISTS task_dependencies;
+DROP TABLE IF EXISTS attachments;
+DROP TABLE IF EXISTS notifications;
+DROP TABLE IF EXISTS mentions;
+DROP TABLE IF EXISTS comment_revisions;
+DROP TABLE IF EXISTS comments;
+DROP TABLE IF EXISTS task_tags;
+DROP TABLE IF EXISTS tags;
+DROP TABLE IF EXISTS task_revisions;
+DROP TABLE IF EXISTS audit_log;
+DROP TABLE IF EXISTS revoked_tokens;
+DROP TABLE IF EXISTS refresh_tokens;
+DROP TABLE IF EXISTS oauth_identities;
+DROP TABLE IF EXISTS credentials;
+DROP TABLE IF EXISTS api_key_usage;
+DROP TABLE IF EXISTS api_keys;
+DROP TABLE IF EXISTS user_tasks;
+DROP TABLE IF EXISTS tasks;
+DROP TABLE IF EXISTS projects;
+DROP TABLE IF EXISTS teams;
+DROP TABLE IF EXISTS task_statuses;
+DROP TABLE IF EXISTS users;
+DROP TABLE IF EXISTS roles;
+DROP TABLE IF EXISTS tenants;
+DROP FUNCTION IF EXISTS current_tenant;
@@ -0,0 +1,514 @@
+-- Your SQL goes here
+-- The whole schema as the SQLite migrations in ../migrations leave it, for a new MySQL (8.0.16
+-- or later) database, with the same seed data. Later changes get a migration in every directory.
+--
+-- Differences from SQLite:
+-- * Text that is part of a key is VARCHAR, as MySQL can't index TEXT whole. Text compares
+--   case-sensitively (utf8mb4_bin), as in SQLite, except task names, which the full-text
+--   index needs to match in any case.
+-- * Names that SQLite compares with NOCASE are unique by lower(name) instead.
+-- * Only live statuses need unique names: live_status_name is NULL for trashed ones, which
+--   stands in for SQLite's partial index.
+-- * Foreign keys are declared on their own, as MySQL ignores REFERENCES on a column.
+-- * current_tenant() reads a user variable rather than being registered on the connection
+--   (see tasks_db_lib::tenancy).
+-- * Task search reads the tasks table through tasks_search rather than an FTS5 table.
+-- * Defaults are in the session's time zone, which tasks_db_lib::configure sets to UTC.
+
+CREATE FUNCTION current_tenant() RETURNS INTEGER DETERMINISTIC NO SQL
+	RETURN @tasks_tenant_id;
+
+CREATE TABLE tenants(
+	tenant_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	tenant_name VARCHAR(255) NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE UNIQUE INDEX tenants_tenant_name_key ON tenants((lower(tenant_name)));
+
+CREATE TABLE roles(
+	role_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	role_name VARCHAR(255) NOT NULL UNIQUE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE users(
+	user_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	name TEXT NOT NULL,
+	email TEXT NOT NULL,
+	active BOOLEAN NOT NULL,
+	role_id INTEGER NOT NULL DEFAULT 3,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY(role_id) REFERENCES roles(role_id),
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX users_tenant_id ON users(tenant_id);
+
+CREATE TABLE task_statuses(
+	task_status_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	status_name VARCHAR(255) NOT NULL,
+	deleted_at DATETIME(6),
+	live_status_name VARCHAR(255) GENERATED ALWAYS AS (IF(deleted_at IS NULL, status_name, NULL)) STORED,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE UNIQUE INDEX task_statuses_status_name_key ON task_statuses(tenant_id, live_status_name);
+
+CREATE TABLE teams(
+	team_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	team_name VARCHAR(255) NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE UNIQUE INDEX teams_team_name_key ON teams(tenant_id, (lower(team_name)));
+
+CREATE TABLE projects(
+	project_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	project_name VARCHAR(255) NOT NULL,
+	description TEXT,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	version INTEGER NOT NULL DEFAULT 1,
+	team_id INTEGER,
+	tenant_id INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY(team_id) REFERENCES teams(team_id),
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE UNIQUE INDEX projects_project_name_key ON projects(tenant_id, (lower(project_name)));
+
+CREATE TABLE tasks(
+	task_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	task_name TEXT COLLATE utf8mb4_0900_ai_ci NOT NULL,
+	deleted_at DATETIME(6),
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	version INTEGER NOT NULL DEFAULT 1,
+	due_date DATE,
+	priority INTEGER NOT NULL DEFAULT 3,
+	parent_task_id INTEGER,
+	recurrence TEXT,
+	recurrence_paused BOOLEAN NOT NULL DEFAULT FALSE,
+	next_occurrence_id INTEGER,
+	project_id INTEGER,
+	tenant_id INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY(parent_task_id) REFERENCES tasks(task_id),
+	FOREIGN KEY(next_occurrence_id) REFERENCES tasks(task_id),
+	FOREIGN KEY(project_id) REFERENCES projects(project_id),
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX tasks_parent_task_id ON tasks(parent_task_id);
+CREATE INDEX tasks_project_id ON tasks(project_id);
+CREATE INDEX tasks_tenant_id ON tasks(tenant_id);
+CREATE FULLTEXT INDEX tasks_search ON tasks(task_name);
+
+CREATE TABLE user_tasks(
+	user_id INTEGER NOT NULL,
+	task_id INTEGER NOT NULL,
+	task_status_id INTEGER NOT NULL,
+	deleted_at DATETIME(6),
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1,
+	PRIMARY KEY(user_id, task_id),
+	FOREIGN KEY(user_id) REFERENCES users(user_id),
+	FOREIGN KEY(task_id) REFERENCES tasks(task_id),
+	FOREIGN KEY(task_status_id) REFERENCES task_statuses(task_status_id),
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX user_tasks_task_id ON user_tasks(task_id);
+CREATE INDEX user_tasks_tenant_id ON user_tasks(tenant_id);
+
+CREATE TABLE api_keys(
+	api_key_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	user_id INTEGER NOT NULL,
+	name TEXT NOT NULL,
+	key_prefix TEXT NOT NULL,
+	key_hash VARCHAR(255) NOT NULL UNIQUE,
+	revoked BOOLEAN NOT NULL DEFAULT FALSE,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	daily_quota INTEGER,
+	monthly_quota INTEGER,
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE api_key_usage(
+	api_key_id INTEGER NOT NULL,
+	day DATE NOT NULL,
+	requests INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY(api_key_id, day),
+	FOREIGN KEY(api_key_id) REFERENCES api_keys(api_key_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE credentials(
+	user_id INTEGER NOT NULL PRIMARY KEY,
+	password_hash TEXT NOT NULL,
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE oauth_identities(
+	oauth_identity_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	user_id INTEGER NOT NULL,
+	provider VARCHAR(255) NOT NULL,
+	provider_user_id VARCHAR(255) NOT NULL,
+	UNIQUE(provider, provider_user_id),
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE refresh_tokens(
+	refresh_token_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	user_id INTEGER NOT NULL,
+	token_hash VARCHAR(255) NOT NULL UNIQUE,
+	expires_at DATETIME(6) NOT NULL,
+	revoked BOOLEAN NOT NULL DEFAULT FALSE,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE revoked_tokens(
+	jti VARCHAR(255) NOT NULL PRIMARY KEY,
+	expires_at DATETIME(6) NOT NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE audit_log(
+	audit_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	actor_user_id INTEGER,
+	action TEXT NOT NULL,
+	entity VARCHAR(255) NOT NULL,
+	entity_id TEXT NOT NULL,
+	before_json TEXT,
+	after_json TEXT,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	tenant_id INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX audit_log_entity_created_at ON audit_log(entity, created_at);
+CREATE INDEX audit_log_tenant_id ON audit_log(tenant_id, audit_id);
+
+CREATE TABLE task_revisions(
+	task_id INTEGER NOT NULL,
+	version INTEGER NOT NULL,
+	task_name TEXT NOT NULL,
+	assignments_json TEXT NOT NULL,
+	actor_user_id INTEGER,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	due_date DATE,
+	priority INTEGER,
+	parent_task_id INTEGER,
+	recurrence TEXT,
+	project_id INTEGER,
+	PRIMARY KEY(task_id, version),
+	FOREIGN KEY(task_id) REFERENCES tasks(task_id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE tags(
+	tag_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	tag_name VARCHAR(255) NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE UNIQUE INDEX tags_tag_name_key ON tags(tenant_id, (lower(tag_name)));
+
+CREATE TABLE task_tags(
+	task_id INTEGER NOT NULL,
+	tag_id INTEGER NOT NULL,
+	PRIMARY KEY(task_id, tag_id),
+	FOREIGN KEY(task_id) REFERENCES tasks(task_id),
+	FOREIGN KEY(tag_id) REFERENCES tags(tag_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX task_tags_tag_id ON task_tags(tag_id);
+
+CREATE TABLE comments(
+	comment_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	task_id INTEGER NOT NULL,
+	author_id INTEGER NOT NULL,
+	body TEXT NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	version INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY(task_id) REFERENCES tasks(task_id),
+	FOREIGN KEY(author_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX comments_task_id ON comments(task_id, created_at);
+
+CREATE TABLE comment_revisions(
+	comment_id INTEGER NOT NULL,
+	version INTEGER NOT NULL,
+	body TEXT NOT NULL,
+	created_at DATETIME(6) NOT NULL,
+	edited_by INTEGER,
+	edited_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	PRIMARY KEY(comment_id, version),
+	FOREIGN KEY(comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE mentions(
+	comment_id INTEGER NOT NULL,
+	user_id INTEGER NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	PRIMARY KEY(comment_id, user_id),
+	FOREIGN KEY(comment_id) REFERENCES comments(comment_id) ON DELETE CASCADE,
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX mentions_user_id ON mentions(user_id, created_at);
+
+CREATE TABLE notifications(
+	notification_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	user_id INTEGER NOT NULL,
+	kind TEXT NOT NULL,
+	task_id INTEGER,
+	comment_id INTEGER,
+	message TEXT NOT NULL,
+	read_at DATETIME(6),
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX notifications_user_id ON notifications(user_id, created_at);
+
+CREATE TABLE attachments(
+	attachment_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	task_id INTEGER NOT NULL,
+	uploaded_by INTEGER NOT NULL,
+	file_name TEXT NOT NULL,
+	content_type TEXT NOT NULL,
+	size_bytes BIGINT NOT NULL,
+	storage_key VARCHAR(255) NOT NULL UNIQUE,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	FOREIGN KEY(task_id) REFERENCES tasks(task_id),
+	FOREIGN KEY(uploaded_by) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX attachments_task_id ON attachments(task_id);
+
+CREATE TABLE task_dependencies(
+	blocking_task_id INTEGER NOT NULL,
+	blocked_task_id INTEGER NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	PRIMARY KEY(blocking_task_id, blocked_task_id),
+	CHECK(blocking_task_id <> blocked_task_id),
+	FOREIGN KEY(blocking_task_id) REFERENCES tasks(task_id),
+	FOREIGN KEY(blocked_task_id) REFERENCES tasks(task_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX task_dependencies_blocked_task_id ON task_dependencies(blocked_task_id);
+
+CREATE TABLE team_members(
+	team_id INTEGER NOT NULL,
+	user_id INTEGER NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	PRIMARY KEY(team_id, user_id),
+	FOREIGN KEY(team_id) REFERENCES teams(team_id),
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX team_members_user_id ON team_members(user_id);
+
+CREATE TABLE task_templates(
+	template_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	template_name VARCHAR(255) NOT NULL,
+	task_name TEXT NOT NULL,
+	priority INTEGER NOT NULL DEFAULT 3,
+	due_in_days INTEGER,
+	recurrence TEXT,
+	assignee_ids_json TEXT NOT NULL DEFAULT ('[]'),
+	subtask_names_json TEXT NOT NULL DEFAULT ('[]'),
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1,
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE UNIQUE INDEX task_templates_template_name_key ON task_templates(tenant_id, (lower(template_name)));
+
+CREATE TABLE task_watchers(
+	task_id INTEGER NOT NULL,
+	user_id INTEGER NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	PRIMARY KEY(task_id, user_id),
+	FOREIGN KEY(task_id) REFERENCES tasks(task_id),
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX task_watchers_user_id ON task_watchers(user_id);
+
+CREATE TABLE starred_tasks(
+	user_id INTEGER NOT NULL,
+	task_id INTEGER NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	PRIMARY KEY(user_id, task_id),
+	FOREIGN KEY(user_id) REFERENCES users(user_id),
+	FOREIGN KEY(task_id) REFERENCES tasks(task_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX starred_tasks_task_id ON starred_tasks(task_id);
+
+CREATE TABLE webhooks(
+	webhook_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	user_id INTEGER NOT NULL,
+	url TEXT NOT NULL,
+	event_type VARCHAR(255) NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	tenant_id INTEGER NOT NULL,
+	secret TEXT NOT NULL DEFAULT (''),
+	FOREIGN KEY(user_id) REFERENCES users(user_id),
+	FOREIGN KEY(tenant_id) REFERENCES tenants(tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX webhooks_event_type ON webhooks(tenant_id, event_type);
+
+CREATE TABLE outbox(
+	outbox_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	webhook_id INTEGER NOT NULL,
+	payload TEXT NOT NULL,
+	attempts INTEGER NOT NULL DEFAULT 0,
+	last_error TEXT,
+	next_attempt_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	FOREIGN KEY(webhook_id) REFERENCES webhooks(webhook_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX outbox_next_attempt_at ON outbox(next_attempt_at);
+
+CREATE TABLE notification_preferences(
+	user_id INTEGER NOT NULL,
+	event_type VARCHAR(255) NOT NULL,
+	email BOOLEAN NOT NULL,
+	webhook BOOLEAN NOT NULL,
+	in_app BOOLEAN NOT NULL,
+	push BOOLEAN NOT NULL DEFAULT TRUE,
+	PRIMARY KEY(user_id, event_type),
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE slack_integrations(
+	project_id INTEGER NOT NULL PRIMARY KEY,
+	webhook_url TEXT NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	FOREIGN KEY(project_id) REFERENCES projects(project_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE push_subscriptions(
+	push_subscription_id INTEGER AUTO_INCREMENT PRIMARY KEY,
+	user_id INTEGER NOT NULL,
+	endpoint VARCHAR(768) NOT NULL UNIQUE,
+	p256dh TEXT NOT NULL,
+	auth TEXT NOT NULL,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+CREATE INDEX push_subscriptions_user_id ON push_subscriptions(user_id);
+
+CREATE TABLE due_reminders(
+	task_id INTEGER NOT NULL,
+	user_id INTEGER NOT NULL,
+	channel VARCHAR(255) NOT NULL,
+	due_date DATE NOT NULL,
+	sent_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	PRIMARY KEY(task_id, user_id, channel),
+	FOREIGN KEY(task_id) REFERENCES tasks(task_id),
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+CREATE TABLE calendar_feeds(
+	user_id INTEGER NOT NULL PRIMARY KEY,
+	token_hash VARCHAR(255) NOT NULL UNIQUE,
+	created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+	FOREIGN KEY(user_id) REFERENCES users(user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_bin;
+
+-- The seed data the SQLite migrations insert.
+INSERT INTO roles(role_id, role_name) VALUES
+	(1, 'admin'),
+	(2, 'manager'),
+	(3, 'member');
+INSERT INTO tenants(tenant_id, tenant_name) VALUES
+	(1, 'Default');
+INSERT INTO users(user_id, name, email, active) VALUES
+	(1, 'Alice', 'alice@example.com', TRUE),
+	(2, 'Bob', 'bob@example.com', TRUE),
+	(3, 'Charlie', 'charlie@example.com', TRUE),
+	(4, 'Diana', 'diana@example.com', FALSE),
+	(5, 'Eve', 'eve@example.com', TRUE),
+	(6, 'Frank', 'frank@example.com', FALSE),
+	(7, 'Grace', 'grace@example.com', TRUE),
+	(8, 'Heidi', 'heidi@example.com', TRUE),
+	(9, 'Ivan', 'ivan@example.com', FALSE),
+	(10, 'Judy', 'judy@example.com', TRUE);
+INSERT INTO task_statuses(task_status_id, status_name) VALUES
+	(1, 'Not Started'),
+	(2, 'In Progress'),
+	(3, 'Completed');
+INSERT INTO tasks(task_id, task_name) VALUES
+	(1, 'Write project proposal'),
+	(2, 'Design database schema'),
+	(3, 'Implement authentication'),
+	(4, 'Set up CI/CD pipeline'),
+	(5, 'Write unit tests'),
+	(6, 'Deploy to staging'),
+	(7, 'Review pull requests'),
+	(8, 'Update documentation'),
+	(9, 'Fix reported bugs'),
+	(10, 'Prepare release notes');
+INSERT INTO user_tasks(user_id, task_id, task_status_id) VALUES
+	(1, 2, 1),
+	(1, 5, 3),
+	(1, 7, 2),
+	(1, 9, 2),
+	(2, 1, 2),
+	(2, 3, 1),
+	(2, 6, 1),
+	(2, 8, 3),
+	(2, 10, 1),
+	(3, 1, 1),
+	(3, 4, 2),
+	(3, 5, 3),
+	(3, 8, 3),
+	(3, 9, 2),
+	(4, 2, 2),
+	(4, 3, 1),
+	(4, 6, 1),
+	(4, 7, 2),
+	(4, 10, 1),
+	(5, 1, 1),
+	(5, 2, 2),
+	(5, 4, 2),
+	(5, 6, 1),
+	(5, 8, 3),
+	(6, 3, 1),
+	(6, 5, 3),
+	(6, 7, 2),
+	(6, 9, 2),
+	(7, 1, 1),
+	(7, 4, 2),
+	(7, 6, 1),
+	(7, 8, 3),
+	(7, 10, 1),
+	(8, 2, 2),
+	(8, 3, 1),
+	(8, 5, 3),
+	(8, 7, 2),
+	(8, 9, 2),
+	(9, 1, 1),
+	(9, 4, 2),
+	(9, 6, 1),
+	(9, 8, 3),
+	(9, 10, 1),
+	(10, 2, 2),
+	(10, 3, 1),
+	(10, 5, 3),
+	(10, 7, 2),
+	(10, 9, 2);
+
+-- Existing tasks start their history at version 1 with what they look like today.
+INSERT INTO task_revisions(task_id, version, task_name, assignments_json, priority)
+SELECT t.task_id, 1, t.task_name, (
+	SELECT CONCAT('[', COALESCE(GROUP_CONCAT(JSON_OBJECT('user_id', a.user_id, 'task_status_id', a.task_status_id) ORDER BY a.user_id SEPARATOR ','), ''), ']')
+	FROM user_tasks AS a
+	WHERE a.task_id = t.task_id AND a.deleted_at IS NULL
+), t.priority
+FROM tasks AS t
+WHERE t.deleted_at IS NULL;
@@ -0,0 +1,465 @@
+-- Your SQL goes here
+-- The whole schema as the SQLite migrations in ../migrations leave it, for a new PostgreSQL
+-- database, with the same seed data. Later changes get a migration in both directories.
+--
+-- Differences from SQLite:
+-- * Names that SQLite compares with NOCASE are unique by lower(name) instead.
+-- * Foreign keys are DEFERRABLE, so a backup restore can check them at commit (see
+--   tasks_db_lib::backup).
+-- * current_tenant() reads a session setting rather than being registered on the connection
+--   (see tasks_db_lib::tenancy).
+-- * Task search reads the tasks table through tasks_search rather than an FTS5 table.
+-- * Defaults are UTC, as CURRENT_TIMESTAMP is in SQLite.
+
+CREATE FUNCTION current_tenant() RETURNS INTEGER LANGUAGE SQL STABLE
+	AS $$ SELECT NULLIF(current_setting('tasks.tenant_id', true), '')::INTEGER $$;
+
+CREATE TABLE tenants(
+	tenant_id SERIAL PRIMARY KEY,
+	tenant_name TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+CREATE UNIQUE INDEX tenants_tenant_name_key ON tenants(lower(tenant_name));
+
+CREATE TABLE roles(
+	role_id SERIAL PRIMARY KEY,
+	role_name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE users(
+	user_id SERIAL PRIMARY KEY,
+	name TEXT NOT NULL,
+	email TEXT NOT NULL,
+	active BOOLEAN NOT NULL,
+	role_id INTEGER NOT NULL DEFAULT 3 REFERENCES roles(role_id) DEFERRABLE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1 REFERENCES tenants(tenant_id) DEFERRABLE
+);
+CREATE INDEX users_tenant_id ON users(tenant_id);
+
+CREATE TABLE task_statuses(
+	task_status_id SERIAL PRIMARY KEY,
+	status_name TEXT NOT NULL,
+	deleted_at TIMESTAMP,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1 REFERENCES tenants(tenant_id) DEFERRABLE
+);
+CREATE UNIQUE INDEX task_statuses_status_name_key ON task_statuses(tenant_id, status_name) WHERE deleted_at IS NULL;
+
+CREATE TABLE teams(
+	team_id SERIAL PRIMARY KEY,
+	team_name TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1 REFERENCES tenants(tenant_id) DEFERRABLE
+);
+CREATE UNIQUE INDEX teams_team_name_key ON teams(tenant_id, lower(team_name));
+
+CREATE TABLE projects(
+	project_id SERIAL PRIMARY KEY,
+	project_name TEXT NOT NULL,
+	description TEXT,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	version INTEGER NOT NULL DEFAULT 1,
+	team_id INTEGER REFERENCES teams(team_id) DEFERRABLE,
+	tenant_id INTEGER NOT NULL DEFAULT 1 REFERENCES tenants(tenant_id) DEFERRABLE
+);
+CREATE UNIQUE INDEX projects_project_name_key ON projects(tenant_id, lower(project_name));
+
+CREATE TABLE tasks(
+	task_id SERIAL PRIMARY KEY,
+	task_name TEXT NOT NULL,
+	deleted_at TIMESTAMP,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	version INTEGER NOT NULL DEFAULT 1,
+	due_date DATE,
+	priority INTEGER NOT NULL DEFAULT 3,
+	parent_task_id INTEGER REFERENCES tasks(task_id) DEFERRABLE,
+	recurrence TEXT,
+	recurrence_paused BOOLEAN NOT NULL DEFAULT FALSE,
+	next_occurrence_id INTEGER REFERENCES tasks(task_id) DEFERRABLE,
+	project_id INTEGER REFERENCES projects(project_id) DEFERRABLE,
+	tenant_id INTEGER NOT NULL DEFAULT 1 REFERENCES tenants(tenant_id) DEFERRABLE
+);
+CREATE INDEX tasks_parent_task_id ON tasks(parent_task_id);
+CREATE INDEX tasks_project_id ON tasks(project_id);
+CREATE INDEX tasks_tenant_id ON tasks(tenant_id);
+CREATE INDEX tasks_search ON tasks USING GIN (to_tsvector('simple', task_name));
+
+CREATE TABLE user_tasks(
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	task_id INTEGER NOT NULL REFERENCES tasks(task_id) DEFERRABLE,
+	task_status_id INTEGER NOT NULL REFERENCES task_statuses(task_status_id) DEFERRABLE,
+	deleted_at TIMESTAMP,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1 REFERENCES tenants(tenant_id) DEFERRABLE,
+	PRIMARY KEY(user_id, task_id)
+);
+CREATE INDEX user_tasks_task_id ON user_tasks(task_id);
+CREATE INDEX user_tasks_tenant_id ON user_tasks(tenant_id);
+
+CREATE TABLE api_keys(
+	api_key_id SERIAL PRIMARY KEY,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	name TEXT NOT NULL,
+	key_prefix TEXT NOT NULL,
+	key_hash TEXT NOT NULL UNIQUE,
+	revoked BOOLEAN NOT NULL DEFAULT FALSE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	daily_quota INTEGER,
+	monthly_quota INTEGER
+);
+
+CREATE TABLE api_key_usage(
+	api_key_id INTEGER NOT NULL REFERENCES api_keys(api_key_id) DEFERRABLE,
+	day DATE NOT NULL,
+	requests INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY(api_key_id, day)
+);
+
+CREATE TABLE credentials(
+	user_id INTEGER NOT NULL PRIMARY KEY REFERENCES users(user_id) DEFERRABLE,
+	password_hash TEXT NOT NULL
+);
+
+CREATE TABLE oauth_identities(
+	oauth_identity_id SERIAL PRIMARY KEY,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	provider TEXT NOT NULL,
+	provider_user_id TEXT NOT NULL,
+	UNIQUE(provider, provider_user_id)
+);
+
+CREATE TABLE refresh_tokens(
+	refresh_token_id SERIAL PRIMARY KEY,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	token_hash TEXT NOT NULL UNIQUE,
+	expires_at TIMESTAMP NOT NULL,
+	revoked BOOLEAN NOT NULL DEFAULT FALSE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+
+CREATE TABLE revoked_tokens(
+	jti TEXT NOT NULL PRIMARY KEY,
+	expires_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE audit_log(
+	audit_id SERIAL PRIMARY KEY,
+	actor_user_id INTEGER,
+	action TEXT NOT NULL,
+	entity TEXT NOT NULL,
+	entity_id TEXT NOT NULL,
+	before_json TEXT,
+	after_json TEXT,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	tenant_id INTEGER NOT NULL DEFAULT 1 REFERENCES tenants(tenant_id) DEFERRABLE
+);
+CREATE INDEX audit_log_entity_created_at ON audit_log(entity, created_at);
+CREATE INDEX audit_log_tenant_id ON audit_log(tenant_id, audit_id);
+
+CREATE TABLE task_revisions(
+	task_id INTEGER NOT NULL REFERENCES tasks(task_id) ON DELETE CASCADE DEFERRABLE,
+	version INTEGER NOT NULL,
+	task_name TEXT NOT NULL,
+	assignments_json TEXT NOT NULL,
+	actor_user_id INTEGER,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	due_date DATE,
+	priority INTEGER,
+	parent_task_id INTEGER,
+	recurrence TEXT,
+	project_id INTEGER,
+	PRIMARY KEY(task_id, version)
+);
+
+CREATE TABLE tags(
+	tag_id SERIAL PRIMARY KEY,
+	tag_name TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1 REFERENCES tenants(tenant_id) DEFERRABLE
+);
+CREATE UNIQUE INDEX tags_tag_name_key ON tags(tenant_id, lower(tag_name));
+
+CREATE TABLE task_tags(
+	task_id INTEGER NOT NULL REFERENCES tasks(task_id) DEFERRABLE,
+	tag_id INTEGER NOT NULL REFERENCES tags(tag_id) DEFERRABLE,
+	PRIMARY KEY(task_id, tag_id)
+);
+CREATE INDEX task_tags_tag_id ON task_tags(tag_id);
+
+CREATE TABLE comments(
+	comment_id SERIAL PRIMARY KEY,
+	task_id INTEGER NOT NULL REFERENCES tasks(task_id) DEFERRABLE,
+	author_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	body TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	version INTEGER NOT NULL DEFAULT 1
+);
+CREATE INDEX comments_task_id ON comments(task_id, created_at);
+
+CREATE TABLE comment_revisions(
+	comment_id INTEGER NOT NULL REFERENCES comments(comment_id) ON DELETE CASCADE DEFERRABLE,
+	version INTEGER NOT NULL,
+	body TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL,
+	edited_by INTEGER,
+	edited_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	PRIMARY KEY(comment_id, version)
+);
+
+CREATE TABLE mentions(
+	comment_id INTEGER NOT NULL REFERENCES comments(comment_id) ON DELETE CASCADE DEFERRABLE,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	PRIMARY KEY(comment_id, user_id)
+);
+CREATE INDEX mentions_user_id ON mentions(user_id, created_at);
+
+CREATE TABLE notifications(
+	notification_id SERIAL PRIMARY KEY,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	kind TEXT NOT NULL,
+	task_id INTEGER,
+	comment_id INTEGER,
+	message TEXT NOT NULL,
+	read_at TIMESTAMP,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+CREATE INDEX notifications_user_id ON notifications(user_id, created_at);
+
+CREATE TABLE attachments(
+	attachment_id SERIAL PRIMARY KEY,
+	task_id INTEGER NOT NULL REFERENCES tasks(task_id) DEFERRABLE,
+	uploaded_by INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	file_name TEXT NOT NULL,
+	content_type TEXT NOT NULL,
+	size_bytes BIGINT NOT NULL,
+	storage_key TEXT NOT NULL UNIQUE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+CREATE INDEX attachments_task_id ON attachments(task_id);
+
+CREATE TABLE task_dependencies(
+	blocking_task_id INTEGER NOT NULL REFERENCES tasks(task_id) DEFERRABLE,
+	blocked_task_id INTEGER NOT NULL REFERENCES tasks(task_id) DEFERRABLE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	PRIMARY KEY(blocking_task_id, blocked_task_id),
+	CHECK(blocking_task_id <> blocked_task_id)
+);
+CREATE INDEX task_dependencies_blocked_task_id ON task_dependencies(blocked_task_id);
+
+CREATE TABLE team_members(
+	team_id INTEGER NOT NULL REFERENCES teams(team_id) DEFERRABLE,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	PRIMARY KEY(team_id, user_id)
+);
+CREATE INDEX team_members_user_id ON team_members(user_id);
+
+CREATE TABLE task_templates(
+	template_id SERIAL PRIMARY KEY,
+	template_name TEXT NOT NULL,
+	task_name TEXT NOT NULL,
+	priority INTEGER NOT NULL DEFAULT 3,
+	due_in_days INTEGER,
+	recurrence TEXT,
+	assignee_ids_json TEXT NOT NULL DEFAULT '[]',
+	subtask_names_json TEXT NOT NULL DEFAULT '[]',
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	version INTEGER NOT NULL DEFAULT 1,
+	tenant_id INTEGER NOT NULL DEFAULT 1 REFERENCES tenants(tenant_id) DEFERRABLE
+);
+CREATE UNIQUE INDEX task_templates_template_name_key ON task_templates(tenant_id, lower(template_name));
+
+CREATE TABLE task_watchers(
+	task_id INTEGER NOT NULL REFERENCES tasks(task_id) DEFERRABLE,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	PRIMARY KEY(task_id, user_id)
+);
+CREATE INDEX task_watchers_user_id ON task_watchers(user_id);
+
+CREATE TABLE starred_tasks(
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	task_id INTEGER NOT NULL REFERENCES tasks(task_id) DEFERRABLE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	PRIMARY KEY(user_id, task_id)
+);
+CREATE INDEX starred_tasks_task_id ON starred_tasks(task_id);
+
+CREATE TABLE webhooks(
+	webhook_id SERIAL PRIMARY KEY,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	url TEXT NOT NULL,
+	event_type TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	tenant_id INTEGER NOT NULL REFERENCES tenants(tenant_id) DEFERRABLE,
+	secret TEXT NOT NULL DEFAULT ''
+);
+CREATE INDEX webhooks_event_type ON webhooks(tenant_id, event_type);
+
+CREATE TABLE outbox(
+	outbox_id SERIAL PRIMARY KEY,
+	webhook_id INTEGER NOT NULL REFERENCES webhooks(webhook_id) DEFERRABLE,
+	payload TEXT NOT NULL,
+	attempts INTEGER NOT NULL DEFAULT 0,
+	last_error TEXT,
+	next_attempt_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+CREATE INDEX outbox_next_attempt_at ON outbox(next_attempt_at);
+
+CREATE TABLE notification_preferences(
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	event_type TEXT NOT NULL,
+	email BOOLEAN NOT NULL,
+	webhook BOOLEAN NOT NULL,
+	in_app BOOLEAN NOT NULL,
+	push BOOLEAN NOT NULL DEFAULT TRUE,
+	PRIMARY KEY(user_id, event_type)
+);
+
+CREATE TABLE slack_integrations(
+	project_id INTEGER NOT NULL PRIMARY KEY REFERENCES projects(project_id) DEFERRABLE,
+	webhook_url TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+
+CREATE TABLE push_subscriptions(
+	push_subscription_id SERIAL PRIMARY KEY,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	endpoint TEXT NOT NULL UNIQUE,
+	p256dh TEXT NOT NULL,
+	auth TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+CREATE INDEX push_subscriptions_user_id ON push_subscriptions(user_id);
+
+CREATE TABLE due_reminders(
+	task_id INTEGER NOT NULL REFERENCES tasks(task_id) DEFERRABLE,
+	user_id INTEGER NOT NULL REFERENCES users(user_id) DEFERRABLE,
+	channel TEXT NOT NULL,
+	due_date DATE NOT NULL,
+	sent_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+	PRIMARY KEY(task_id, user_id, channel)
+);
+
+CREATE TABLE calendar_feeds(
+	user_id INTEGER NOT NULL PRIMARY KEY REFERENCES users(user_id) DEFERRABLE,
+	token_hash TEXT NOT NULL UNIQUE,
+	created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+
+-- The seed data the SQLite migrations insert.
+INSERT INTO roles(role_id, role_name) VALUES
+	(1, 'admin'),
+	(2, 'manager'),
+	(3, 'member');
+INSERT INTO tenants(tenant_id, tenant_name) VALUES
+	(1, 'Default');
+INSERT INTO users(user_id, name, email, active) VALUES
+	(1, 'Alice', 'alice@example.com', TRUE),
+	(2, 'Bob', 'bob@example.com', TRUE),
+	(3, 'Charlie', 'charlie@example.com', TRUE),
+	(4, 'Diana', 'diana@example.com', FALSE),
+	(5, 'Eve', 'eve@example.com', TRUE),
+	(6, 'Frank', 'frank@example.com', FALSE),
+	(7, 'Grace', 'grace@example.com', TRUE),
+	(8, 'Heidi', 'heidi@example.com', TRUE),
+	(9, 'Ivan', 'ivan@example.com', FALSE),
+	(10, 'Judy', 'judy@example.com', TRUE);
+INSERT INTO task_statuses(task_status_id, status_name) VALUES
+	(1, 'Not Started'),
+	(2, 'In Progress'),
+	(3, 'Completed');
+INSERT INTO tasks(task_id, task_name) VALUES
+	(1, 'Write project proposal'),
+	(2, 'Design database schema'),
+	(3, 'Implement authentication'),
+	(4, 'Set up CI/CD pipeline'),
+	(5, 'Write unit tests'),
+	(6, 'Deploy to staging'),
+	(7, 'Review pull requests'),
+	(8, 'Update documentation'),
+	(9, 'Fix reported bugs'),
+	(10, 'Prepare release notes');
+INSERT INTO user_tasks(user_id, task_id, task_status_id) VALUES
+	(1, 2, 1),
+	(1, 5, 3),
+	(1, 7, 2),
+	(1, 9, 2),
+	(2, 1, 2),
+	(2, 3, 1),
+	(2, 6, 1),
+	(2, 8, 3),
+	(2, 10, 1),
+	(3, 1, 1),
+	(3, 4, 2),
+	(3, 5, 3),
+	(3, 8, 3),
+	(3, 9, 2),
+	(4, 2, 2),
+	(4, 3, 1),
+	(4, 6, 1),
+	(4, 7, 2),
+	(4, 10, 1),
+	(5, 1, 1),
+	(5, 2, 2),
+	(5, 4, 2),
+	(5, 6, 1),
+	(5, 8, 3),
+	(6, 3, 1),
+	(6, 5, 3),
+	(6, 7, 2),
+	(6, 9, 2),
+	(7, 1, 1),
+	(7, 4, 2),
+	(7, 6, 1),
+	(7, 8, 3),
+	(7, 10, 1),
+	(8, 2, 2),
+	(8, 3, 1),
+	(8, 5, 3),
+	(8, 7, 2),
+	(8, 9, 2),
+	(9, 1, 1),
+	(9, 4, 2),
+	(9, 6, 1),
+	(9, 8, 3),
+	(9, 10, 1),
+	(10, 2, 2),
+	(10, 3, 1),
+	(10, 5, 3),
+	(10, 7, 2),
+	(10, 9, 2);
+
+-- Existing tasks start their history at version 1 with what they look like today.
+INSERT INTO task_revisions(task_id, version, task_name, assignments_json, priority)
+SELECT t.task_id, 1, t.task_name, (
+	SELECT COALESCE(jsonb_agg(jsonb_build_object('user_id', a.user_id, 'task_status_id', a.task_status_id) ORDER BY a.user_id), '[]')::TEXT
+	FROM user_tasks AS a
+	WHERE a.task_id = t.task_id AND a.deleted_at IS NULL
+), t.priority
+FROM tasks AS t
+WHERE t.deleted_at IS NULL;
+
+SELECT setval(pg_get_serial_sequence('roles', 'role_id'), (SELECT max(role_id) FROM roles));
+SELECT setval(pg_get_serial_sequence('tenants', 'tenant_id'), (SELECT max(tenant_id) FROM tenants));
+SELECT setval(pg_get_serial_sequence('users', 'user_id'), (SELECT max(user_id) FROM users));
+SELECT setval(pg_get_serial_sequence('task_statuses', 'task_status_id'), (SELECT max(task_status_id) FROM task_statuses));
+SELECT setval(pg_get_serial_sequence('tasks', 'task_id'), (SELECT max(task_id) FROM tasks));
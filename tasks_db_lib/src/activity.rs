@@ -0,0 +1,127 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use std::borrow::Cow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::audit::{AuditAction, Auditable};
+use crate::models::{AuditEntry, Comment, Task, UserTask};
+use crate::pagination::CursorPage;
+use crate::schema::audit_log;
+use crate::tenancy;
+
+// The activity feed is the audit log narrowed to what happens on tasks: the tasks themselves,
+// their assignments and their comments. Each entry is read back from its audit row, so the
+// feed needs no writes of its own and covers everything audited before it existed.
+pub const ACTIVITY_ENTITIES: &[&str] = &[Task::ENTITY, UserTask::ENTITY, Comment::ENTITY];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Activity {
+    // the audit row it was read from; also what ?cursor= counts from
+    pub activity_id: i32,
+    // task_created, task_updated, task_deleted, task_restored, assigned, unassigned,
+    // status_changed, assignment_updated, assignment_restored, comment_added, comment_edited
+    // or comment_deleted
+    pub kind: Cow<'static, str>,
+    pub actor_user_id: Option<i32>,
+    pub task_id: Option<i32>,
+    // the assignee, for assignment activity
+    pub user_id: Option<i32>,
+    pub comment_id: Option<i32>,
+    // the assignment's status before and after, where it had one
+    pub from_status_id: Option<i32>,
+    pub to_status_id: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+fn kind(entity: &str, action: AuditAction, from_status_id: Option<i32>, to_status_id: Option<i32>) -> &'static str {
+    match (entity, action) {
+        (Task::ENTITY, AuditAction::Create) => "task_created",
+        (Task::ENTITY, AuditAction::Update) => "task_updated",
+        (Task::ENTITY, AuditAction::Delete) => "task_deleted",
+        (Task::ENTITY, AuditAction::Restore) => "task_restored",
+        (UserTask::ENTITY, AuditAction::Create) => "assigned",
+        (UserTask::ENTITY, AuditAction::Delete) => "unassigned",
+        (UserTask::ENTITY, AuditAction::Restore) => "assignment_restored",
+        (UserTask::ENTITY, AuditAction::Update) if from_status_id != to_status_id => "status_changed",
+        (UserTask::ENTITY, AuditAction::Update) => "assignment_updated",
+        (Comment::ENTITY, AuditAction::Create) => "comment_added",
+        (Comment::ENTITY, AuditAction::Update) => "comment_edited",
+        (Comment::ENTITY, AuditAction::Delete) => "comment_deleted",
+        (_, action) => action.as_str(),
+    }
+}
+
+impl From<AuditEntry> for Activity {
+    fn from(entry: AuditEntry) -> Activity {
+        let parse = |json: Option<String>| json.and_then(|json| serde_json::from_str::<Value>(&json).ok());
+        let before = parse(entry.before_json);
+        let after = parse(entry.after_json);
+        let id = |row: &Option<Value>, field: &str| row.as_ref().and_then(|row| row[field].as_i64()).map(|id| id as i32);
+        let either = |field: &str| id(&after, field).or_else(|| id(&before, field));
+        let is_assignment = entry.entity == UserTask::ENTITY;
+        let from_status_id = if is_assignment { id(&before, "task_status_id") } else { None };
+        let to_status_id = if is_assignment { id(&after, "task_status_id") } else { None };
+        let action = AuditAction::parse(&entry.action).unwrap_or(AuditAction::Update);
+        Activity {
+            activity_id: entry.audit_id,
+            kind: kind(&entry.entity, action, from_status_id, to_status_id).into(),
+            actor_user_id: entry.actor_user_id,
+            task_id: either("task_id"),
+            user_id: if is_assignment { either("user_id") } else { None },
+            comment_id: either("comment_id"),
+            from_status_id,
+            to_status_id,
+            created_at: entry.created_at,
+        }
+    }
+}
+
+// Oldest first, starting after `cursor` when there is one and at `since` when that's given.
+pub async fn read_feed(conn: &mut DbConnection, since: Option<chrono::NaiveDateTime>, cursor: Option<i32>, limit: i64) -> anyhow::Result<CursorPage<Activity>> {
+    let mut query = audit_log::table
+        .filter(audit_log::tenant_id.eq(tenancy::current()))
+        .filter(audit_log::entity.eq_any(ACTIVITY_ENTITIES))
+        .into_boxed();
+    if let Some(since) = since {
+        query = query.filter(audit_log::created_at.ge(since));
+    }
+    if let Some(cursor) = cursor {
+        query = query.filter(audit_log::audit_id.gt(cursor));
+    }
+    // one extra row says whether there is a next page
+    let mut entries = query
+        .order(audit_log::audit_id)
+        .limit(limit + 1)
+        .load::<AuditEntry>(conn).await?;
+    let more = entries.len() as i64 > limit;
+    entries.truncate(limit as usize);
+    let next_cursor = if more { entries.last().map(|entry| entry.audit_id) } else { None };
+    Ok(CursorPage { items: entries.into_iter().map(Activity::from).collect(), next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditedCrud;
+    use crate::models::NewUserTask;
+    use crate::test_support::{self, new_task};
+
+    #[tokio::test]
+    async fn the_feed_reads_task_and_assignment_changes_in_pages() {
+        let mut conn = test_support::conn().await;
+        let task = Task::create_audited(&mut conn, Some(1), new_task("Paint the fence")).await.unwrap();
+        UserTask::create_audited(&mut conn, Some(1), NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        UserTask::update_audited(&mut conn, Some(2), (2, task.task_id), None, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 3 }).await.unwrap();
+        UserTask::delete_audited(&mut conn, Some(1), (2, task.task_id)).await.unwrap();
+
+        let first = read_feed(&mut conn, None, None, 2).await.unwrap();
+        let second = read_feed(&mut conn, None, first.next_cursor, 2).await.unwrap();
+        assert!(second.next_cursor.is_none());
+        let kinds: Vec<&str> = first.items.iter().chain(&second.items).map(|activity| activity.kind.as_ref()).collect();
+        assert_eq!(kinds, ["task_created", "assigned", "status_changed", "unassigned"]);
+        let moved = &second.items[0];
+        assert_eq!((moved.actor_user_id, moved.user_id, moved.from_status_id, moved.to_status_id), (Some(2), Some(2), Some(1), Some(3)));
+        assert!(second.items.iter().all(|activity| activity.task_id == Some(task.task_id)));
+    }
+}
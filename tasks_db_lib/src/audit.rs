@@ -0,0 +1,323 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::{DbBackend, DbConnection};
+use serde::Serialize;
+use crate::crud::CrudOperations;
+use crate::filters::AuditFilter;
+use crate::notifications;
+use crate::models::{AuditEntry, Comment, NewAuditEntry, Project, Tag, Task, TaskRevision, TaskStatus, TaskTemplate, Team, User, UserTask};
+use crate::pagination::{self, Page};
+use crate::schema::audit_log;
+use crate::tenancy;
+use crate::versioning::Versioned;
+use crate::watchers;
+use crate::webhooks;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<AuditAction> {
+        [AuditAction::Create, AuditAction::Update, AuditAction::Delete, AuditAction::Restore]
+            .into_iter()
+            .find(|action| action.as_str() == raw)
+    }
+}
+
+// Rows whose changes end up in audit_log. ENTITY is the value clients filter on
+// with GET /audit?entity=...
+pub trait Auditable: Serialize {
+    const ENTITY: &'static str;
+    fn audit_key(&self) -> String;
+
+    // The task whose revision history this row is part of, if any.
+    fn revised_task(&self) -> Option<i32> {
+        None
+    }
+
+    // What the watchers of a task hear about this change, as (task_id, message), if anything.
+    fn watch_notice(_action: AuditAction, _before: Option<&Self>, _after: Option<&Self>) -> Option<(i32, String)> where Self: Sized {
+        None
+    }
+
+    // What the user the change is about hears of it, as (user_id, task_id, message), if anything.
+    // They get this in place of the watchers' notice.
+    fn assignee_notice(_action: AuditAction, _before: Option<&Self>, _after: Option<&Self>) -> Option<(i32, i32, String)> where Self: Sized {
+        None
+    }
+}
+
+impl Auditable for User {
+    const ENTITY: &'static str = "user";
+    fn audit_key(&self) -> String {
+        self.user_id.to_string()
+    }
+}
+
+impl Auditable for Task {
+    const ENTITY: &'static str = "task";
+    fn audit_key(&self) -> String {
+        self.task_id.to_string()
+    }
+
+    fn revised_task(&self) -> Option<i32> {
+        Some(self.task_id)
+    }
+}
+
+impl Auditable for TaskStatus {
+    const ENTITY: &'static str = "task_status";
+    fn audit_key(&self) -> String {
+        self.task_status_id.to_string()
+    }
+}
+
+impl Auditable for UserTask {
+    const ENTITY: &'static str = "assignment";
+    fn audit_key(&self) -> String {
+        format!("{}/{}", self.user_id, self.task_id)
+    }
+
+    fn revised_task(&self) -> Option<i32> {
+        Some(self.task_id)
+    }
+
+    // Assigning, unassigning and moving between statuses; an update that keeps the status is quiet.
+    fn watch_notice(action: AuditAction, before: Option<&Self>, after: Option<&Self>) -> Option<(i32, String)> {
+        match (action, before, after) {
+            (AuditAction::Create | AuditAction::Restore, _, Some(after)) => {
+                Some((after.task_id, format!("User {} was assigned to task {}", after.user_id, after.task_id)))
+            }
+            (AuditAction::Delete, Some(before), _) => {
+                Some((before.task_id, format!("User {} was unassigned from task {}", before.user_id, before.task_id)))
+            }
+            (AuditAction::Update, Some(before), Some(after)) if before.task_status_id != after.task_status_id => {
+                Some((after.task_id, format!("User {}'s assignment to task {} moved from status {} to {}", after.user_id, after.task_id, before.task_status_id, after.task_status_id)))
+            }
+            _ => None,
+        }
+    }
+
+    fn assignee_notice(action: AuditAction, before: Option<&Self>, after: Option<&Self>) -> Option<(i32, i32, String)> {
+        match (action, before, after) {
+            (AuditAction::Create | AuditAction::Restore, _, Some(after)) => {
+                Some((after.user_id, after.task_id, format!("You were assigned to task {}", after.task_id)))
+            }
+            (AuditAction::Delete, Some(before), _) => {
+                Some((before.user_id, before.task_id, format!("You were unassigned from task {}", before.task_id)))
+            }
+            (AuditAction::Update, Some(before), Some(after)) if before.task_status_id != after.task_status_id => {
+                Some((after.user_id, after.task_id, format!("Your assignment to task {} moved from status {} to {}", after.task_id, before.task_status_id, after.task_status_id)))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Auditable for Tag {
+    const ENTITY: &'static str = "tag";
+    fn audit_key(&self) -> String {
+        self.tag_id.to_string()
+    }
+}
+
+impl Auditable for Project {
+    const ENTITY: &'static str = "project";
+    fn audit_key(&self) -> String {
+        self.project_id.to_string()
+    }
+}
+
+impl Auditable for Team {
+    const ENTITY: &'static str = "team";
+    fn audit_key(&self) -> String {
+        self.team_id.to_string()
+    }
+}
+
+impl Auditable for TaskTemplate {
+    const ENTITY: &'static str = "task_template";
+    fn audit_key(&self) -> String {
+        self.template_id.to_string()
+    }
+}
+
+impl Auditable for Comment {
+    const ENTITY: &'static str = "comment";
+    fn audit_key(&self) -> String {
+        self.comment_id.to_string()
+    }
+}
+
+pub const ENTITIES: &[&str] = &[User::ENTITY, Task::ENTITY, TaskStatus::ENTITY, UserTask::ENTITY, Tag::ENTITY, Comment::ENTITY, TaskTemplate::ENTITY, Project::ENTITY, Team::ENTITY];
+
+// The shared write hook: one audit row, plus a new task revision when the row belongs to
+// a task, notifications for the task's watchers and queued webhook deliveries when the change
+// concerns them. `before` is None for inserts and `after` is None for deletes; callers run
+// this in the same transaction as the change it describes.
+pub async fn record<E: Auditable>(conn: &mut DbConnection, actor: Option<i32>, action: AuditAction, before: Option<&E>, after: Option<&E>) -> anyhow::Result<()> {
+    log(conn, actor, action, before, after).await?;
+    if let Some(task_id) = after.or(before).and_then(Auditable::revised_task) {
+        TaskRevision::snapshot(conn, actor, task_id).await?;
+    }
+    Ok(())
+}
+
+// Everything but the revision snapshot, for callers that take it themselves: the audit row,
+// the webhook deliveries it sets off and the notices for the assignee and the task's watchers.
+pub(crate) async fn log<E: Auditable>(conn: &mut DbConnection, actor: Option<i32>, action: AuditAction, before: Option<&E>, after: Option<&E>) -> anyhow::Result<()> {
+    let Some(subject) = after.or(before) else {
+        return Ok(());
+    };
+    let entry = NewAuditEntry {
+        actor_user_id: actor,
+        action: action.as_str(),
+        entity: E::ENTITY,
+        entity_id: &subject.audit_key(),
+        before_json: before.map(serde_json::to_string).transpose()?,
+        after_json: after.map(serde_json::to_string).transpose()?,
+    };
+    diesel::insert_into(audit_log::table)
+        .values((&entry, audit_log::tenant_id.eq(tenancy::current())))
+        .execute(conn).await?;
+    let entry = audit_log::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+    webhooks::enqueue(conn, &entry).await?;
+    if let Some(event_type) = webhooks::event_type(E::ENTITY, action) {
+        let assignee = E::assignee_notice(action, before, after);
+        if let Some((user_id, task_id, message)) = &assignee {
+            notifications::notify_assignee(conn, actor, *user_id, *task_id, event_type, message).await?;
+        }
+        if let Some((task_id, message)) = E::watch_notice(action, before, after) {
+            watchers::notify(conn, actor, assignee.map(|(user_id, _, _)| user_id), task_id, event_type, &message).await?;
+        }
+    }
+    Ok(())
+}
+
+// The audited counterparts of the CrudOperations writes, available on every entity that is
+// Auditable. `actor` is the user making the change (None for system jobs). An update with
+// `expected_version` set fails with versioning::StaleVersion if the row has moved past it: the
+// version is in the UPDATE's own filter (see CrudOperations::update_versioned), so a write that
+// lands after the audit read still can't be overwritten.
+pub trait AuditedCrud<Id, NewEntity, Entity>: CrudOperations<DbConnection, Id, NewEntity, Entity> {
+    fn create_audited(conn: &mut DbConnection, actor: Option<i32>, new_entity: NewEntity) -> impl Future<Output = anyhow::Result<Entity>> + Send;
+    fn update_audited(conn: &mut DbConnection, actor: Option<i32>, id: Id, expected_version: Option<i32>, updated_entity: NewEntity) -> impl Future<Output = anyhow::Result<Entity>> + Send;
+    fn delete_audited(conn: &mut DbConnection, actor: Option<i32>, id: Id) -> impl Future<Output = anyhow::Result<usize>> + Send;
+}
+
+impl<T, Id, NewEntity, Entity> AuditedCrud<Id, NewEntity, Entity> for T
+where
+    T: CrudOperations<DbConnection, Id, NewEntity, Entity>,
+    Id: Copy + Send + Sync,
+    NewEntity: Send,
+    Entity: Auditable + Versioned + Send + Sync,
+{
+    async fn create_audited(conn: &mut DbConnection, actor: Option<i32>, new_entity: NewEntity) -> anyhow::Result<Entity> {
+        conn.transaction(|conn| async move {
+            let created = T::create(conn, new_entity).await?;
+            record(conn, actor, AuditAction::Create, None, Some(&created)).await?;
+            Ok(created)
+        }.scope_boxed()).await
+    }
+
+    async fn update_audited(conn: &mut DbConnection, actor: Option<i32>, id: Id, expected_version: Option<i32>, updated_entity: NewEntity) -> anyhow::Result<Entity> {
+        conn.transaction(|conn| async move {
+            let before = T::read(conn, id).await?;
+            let updated = T::update_versioned(conn, id, expected_version, updated_entity).await?;
+            record(conn, actor, AuditAction::Update, before.as_ref(), Some(&updated)).await?;
+            Ok(updated)
+        }.scope_boxed()).await
+    }
+
+    async fn delete_audited(conn: &mut DbConnection, actor: Option<i32>, id: Id) -> anyhow::Result<usize> {
+        conn.transaction(|conn| async move {
+            let before = T::read(conn, id).await?;
+            let count = T::delete(conn, id).await?;
+            if count > 0 {
+                record(conn, actor, AuditAction::Delete, before.as_ref(), None).await?;
+            }
+            Ok(count)
+        }.scope_boxed()).await
+    }
+}
+
+impl AuditEntry {
+    fn filtered_query(filter: &AuditFilter) -> audit_log::BoxedQuery<'static, DbBackend> {
+        let mut query = audit_log::table.filter(audit_log::tenant_id.eq(tenancy::current())).into_boxed();
+        if let Some(entity) = &filter.entity {
+            query = query.filter(audit_log::entity.eq(entity.clone()));
+        }
+        if let Some(entity_id) = &filter.entity_id {
+            query = query.filter(audit_log::entity_id.eq(entity_id.clone()));
+        }
+        if let Some(since) = filter.since {
+            query = query.filter(audit_log::created_at.ge(since));
+        }
+        query
+    }
+
+    // Newest first.
+    pub async fn read_page_filtered(conn: &mut DbConnection, filter: &AuditFilter, page: i64, per_page: i64) -> anyhow::Result<Page<AuditEntry>> {
+        let total = Self::filtered_query(filter).count().get_result(conn).await?;
+        let items = Self::filtered_query(filter)
+            .order(audit_log::audit_id.desc())
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<AuditEntry>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, new_task};
+
+    async fn history(conn: &mut DbConnection, entity: &str, entity_id: &str) -> Vec<AuditEntry> {
+        let filter = AuditFilter { entity: Some(entity.to_string()), entity_id: Some(entity_id.to_string()), since: None };
+        AuditEntry::read_page_filtered(conn, &filter, 1, 100).await.unwrap().items
+    }
+
+    #[tokio::test]
+    async fn audited_writes_leave_a_trail_newest_first() {
+        let mut conn = test_support::conn().await;
+        let task = Task::create_audited(&mut conn, Some(1), new_task("Order chairs")).await.unwrap();
+        Task::update_audited(&mut conn, Some(2), task.task_id, None, new_task("Order tables")).await.unwrap();
+        Task::delete_audited(&mut conn, None, task.task_id).await.unwrap();
+        // nothing left to delete, so nothing to record
+        Task::delete_audited(&mut conn, None, task.task_id).await.unwrap();
+
+        let entries = history(&mut conn, Task::ENTITY, &task.task_id.to_string()).await;
+        let actions: Vec<&str> = entries.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions, ["delete", "update", "create"]);
+        assert_eq!(entries[1].actor_user_id, Some(2));
+        assert!(entries[1].before_json.as_deref().unwrap().contains("Order chairs"));
+        assert!(entries[1].after_json.as_deref().unwrap().contains("Order tables"));
+        assert!(entries[0].after_json.is_none());
+        assert!(entries[2].before_json.is_none());
+    }
+
+    #[tokio::test]
+    async fn a_failed_write_records_nothing() {
+        let mut conn = test_support::conn().await;
+        let new_user_task = crate::models::NewUserTask { user_id: 1, task_id: 9999, task_status_id: 1 };
+        assert!(UserTask::create_audited(&mut conn, Some(1), new_user_task).await.is_err());
+        assert!(history(&mut conn, UserTask::ENTITY, "1/9999").await.is_empty());
+    }
+}
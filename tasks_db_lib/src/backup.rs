@@ -0,0 +1,376 @@
+use std::collections::BTreeMap;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::DbConnection;
+use diesel_async::SimpleAsyncConnection;
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+#[cfg(feature = "sqlite")]
+use crate::search;
+
+// Bumped if the shape of the document itself changes, not when tables or columns do; those
+// are checked against the database the backup is restored into.
+pub const BACKUP_FORMAT: i32 = 1;
+
+// Every row of every table, across all tenants, as one document. Rows are JSON objects keyed
+// by column name; tables are read generically from the database's own catalog, so a table added
+// by a later migration is included without changes here. SQLite's task_search index isn't: it's
+// built again from the tasks on restore. Nor are MySQL's generated columns.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Backup {
+    pub format: i32,
+    pub exported_at: chrono::NaiveDateTime,
+    pub tables: BTreeMap<String, BackupTable>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BackupTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<serde_json::Map<String, Value>>,
+}
+
+#[derive(QueryableByName)]
+struct Name {
+    #[diesel(sql_type = Text)]
+    name: String,
+}
+
+#[derive(QueryableByName)]
+struct Row {
+    #[diesel(sql_type = Text)]
+    row: String,
+}
+
+// "name" as an SQL identifier.
+#[cfg(not(feature = "mysql"))]
+fn quoted(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(feature = "mysql")]
+fn quoted(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+// The application's own tables: not SQLite's, not Diesel's migration bookkeeping, and not the
+// search index (a virtual table) or the shadow tables behind it.
+#[cfg(feature = "sqlite")]
+async fn table_names(conn: &mut DbConnection) -> anyhow::Result<Vec<String>> {
+    let names = diesel::sql_query(
+        "SELECT name FROM pragma_table_list WHERE schema = 'main' AND type = 'table' \
+         AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' AND name != '__diesel_schema_migrations' ORDER BY name")
+        .load::<Name>(conn).await?;
+    Ok(names.into_iter().map(|name| name.name).collect())
+}
+
+#[cfg(feature = "postgres")]
+async fn table_names(conn: &mut DbConnection) -> anyhow::Result<Vec<String>> {
+    let names = diesel::sql_query(
+        "SELECT table_name::text AS name FROM information_schema.tables WHERE table_schema = current_schema() \
+         AND table_type = 'BASE TABLE' AND table_name != '__diesel_schema_migrations' ORDER BY table_name")
+        .load::<Name>(conn).await?;
+    Ok(names.into_iter().map(|name| name.name).collect())
+}
+
+#[cfg(feature = "mysql")]
+async fn table_names(conn: &mut DbConnection) -> anyhow::Result<Vec<String>> {
+    let names = diesel::sql_query(
+        "SELECT table_name AS name FROM information_schema.tables WHERE table_schema = DATABASE() \
+         AND table_type = 'BASE TABLE' AND table_name != '__diesel_schema_migrations' ORDER BY table_name")
+        .load::<Name>(conn).await?;
+    Ok(names.into_iter().map(|name| name.name).collect())
+}
+
+#[cfg(feature = "sqlite")]
+async fn column_names(conn: &mut DbConnection, table: &str) -> anyhow::Result<Vec<String>> {
+    let names = diesel::sql_query("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+        .bind::<Text, _>(table)
+        .load::<Name>(conn).await?;
+    Ok(names.into_iter().map(|name| name.name).collect())
+}
+
+#[cfg(feature = "postgres")]
+async fn column_names(conn: &mut DbConnection, table: &str) -> anyhow::Result<Vec<String>> {
+    let names = diesel::sql_query(
+        "SELECT column_name::text AS name FROM information_schema.columns WHERE table_schema = current_schema() \
+         AND table_name = $1 ORDER BY ordinal_position")
+        .bind::<Text, _>(table)
+        .load::<Name>(conn).await?;
+    Ok(names.into_iter().map(|name| name.name).collect())
+}
+
+// Generated columns are left out: they can't be written, and are worked out again on insert.
+#[cfg(feature = "mysql")]
+async fn column_names(conn: &mut DbConnection, table: &str) -> anyhow::Result<Vec<String>> {
+    let names = diesel::sql_query(
+        "SELECT column_name AS name FROM information_schema.columns WHERE table_schema = DATABASE() \
+         AND table_name = ? AND extra NOT IN ('VIRTUAL GENERATED', 'STORED GENERATED') ORDER BY ordinal_position")
+        .bind::<Text, _>(table)
+        .load::<Name>(conn).await?;
+    Ok(names.into_iter().map(|name| name.name).collect())
+}
+
+// Each row of the table as a JSON object, in the order the rows went in.
+#[cfg(feature = "sqlite")]
+fn select_rows(table: &str, columns: &[String]) -> String {
+    let pairs: Vec<String> = columns.iter().map(|column| format!("'{}', {}", column.replace('\'', "''"), quoted(column))).collect();
+    format!("SELECT json_object({}) AS row FROM {} ORDER BY rowid", pairs.join(", "), quoted(table))
+}
+
+// PostgreSQL has no rowid; the first column is the table's id, or the first part of its key.
+#[cfg(feature = "postgres")]
+fn select_rows(table: &str, columns: &[String]) -> String {
+    format!("SELECT row_to_json(t)::text AS row FROM {} t ORDER BY t.{}", quoted(table), quoted(&columns[0]))
+}
+
+#[cfg(feature = "mysql")]
+fn select_rows(table: &str, columns: &[String]) -> String {
+    let pairs: Vec<String> = columns.iter().map(|column| format!("'{}', {}", column.replace('\'', "''"), quoted(column))).collect();
+    format!("SELECT CAST(JSON_OBJECT({}) AS CHAR) AS `row` FROM {} ORDER BY {}", pairs.join(", "), quoted(table), quoted(&columns[0]))
+}
+
+// Inserts the rows of the JSON array bound as the one parameter.
+#[cfg(feature = "sqlite")]
+fn insert_rows(table: &str, columns: &[String]) -> String {
+    let names: Vec<String> = columns.iter().map(|column| quoted(column)).collect();
+    let values: Vec<String> = columns.iter().map(|column| format!("json_extract(value, '$.{}')", quoted(column).replace('\'', "''"))).collect();
+    format!("INSERT INTO {} ({}) SELECT {} FROM json_each(?)", quoted(table), names.join(", "), values.join(", "))
+}
+
+#[cfg(feature = "postgres")]
+fn insert_rows(table: &str, columns: &[String]) -> String {
+    let names: Vec<String> = columns.iter().map(|column| quoted(column)).collect();
+    format!("INSERT INTO {} ({}) SELECT {} FROM json_populate_recordset(NULL::{}, $1::json)", quoted(table), names.join(", "), names.join(", "), quoted(table))
+}
+
+// Every value is read as text and converted to the column's type on insert.
+#[cfg(feature = "mysql")]
+fn insert_rows(table: &str, columns: &[String]) -> String {
+    let names: Vec<String> = columns.iter().map(|column| quoted(column)).collect();
+    let paths: Vec<String> = columns.iter().map(|column| format!("{} LONGTEXT PATH '$.\"{}\"'", quoted(column), column.replace('\'', "''"))).collect();
+    format!("INSERT INTO {} ({}) SELECT {} FROM JSON_TABLE(?, '$[*]' COLUMNS ({})) AS saved", quoted(table), names.join(", "), names.join(", "), paths.join(", "))
+}
+
+// Empties every table, with foreign keys checked at commit rather than statement by statement.
+// All of them go before any row is restored, so a cascading delete can't take restored rows
+// with it.
+#[cfg(feature = "sqlite")]
+async fn clear(conn: &mut DbConnection, tables: &[String]) -> anyhow::Result<()> {
+    conn.batch_execute("PRAGMA defer_foreign_keys = ON").await?;
+    for table in tables {
+        diesel::sql_query(format!("DELETE FROM {}", quoted(table))).execute(conn).await?;
+    }
+    Ok(())
+}
+
+// The schema's foreign keys are all DEFERRABLE for this.
+#[cfg(feature = "postgres")]
+async fn clear(conn: &mut DbConnection, tables: &[String]) -> anyhow::Result<()> {
+    conn.batch_execute("SET CONSTRAINTS ALL DEFERRED").await?;
+    let names: Vec<String> = tables.iter().map(|table| quoted(table)).collect();
+    diesel::sql_query(format!("TRUNCATE {}", names.join(", "))).execute(conn).await?;
+    Ok(())
+}
+
+// MySQL can't defer foreign keys, so they're off until rebuild() checks them by hand. TRUNCATE
+// would commit the transaction, hence DELETE.
+#[cfg(feature = "mysql")]
+async fn clear(conn: &mut DbConnection, tables: &[String]) -> anyhow::Result<()> {
+    conn.batch_execute("SET FOREIGN_KEY_CHECKS = 0").await?;
+    for table in tables {
+        diesel::sql_query(format!("DELETE FROM {}", quoted(table))).execute(conn).await?;
+    }
+    Ok(())
+}
+
+// What's derived from the restored rows: the search index on SQLite, and on PostgreSQL the
+// sequences behind the id columns, which would otherwise hand out ids the backup already uses.
+// PostgreSQL also checks the foreign keys clear() deferred here rather than at commit, so a
+// restore inside an outer transaction fails as well. MySQL moves its AUTO_INCREMENT counters
+// past the restored ids by itself, but turning foreign keys back on doesn't check the rows that
+// went in while they were off, so every foreign key is checked here.
+#[cfg(feature = "sqlite")]
+async fn rebuild(conn: &mut DbConnection) -> anyhow::Result<()> {
+    search::reindex_all(conn).await
+}
+
+#[cfg(feature = "postgres")]
+async fn rebuild(conn: &mut DbConnection) -> anyhow::Result<()> {
+    conn.batch_execute("SET CONSTRAINTS ALL IMMEDIATE").await?;
+    conn.batch_execute("DO $$ DECLARE r record; BEGIN \
+        FOR r IN SELECT table_name, column_name FROM information_schema.columns \
+            WHERE table_schema = current_schema() AND column_default LIKE 'nextval(%' LOOP \
+            EXECUTE format('SELECT setval(pg_get_serial_sequence(%L, %L), COALESCE(max(%I), 0) + 1, false) FROM %I', \
+                r.table_name, r.column_name, r.column_name, r.table_name); \
+        END LOOP; END $$").await?;
+    Ok(())
+}
+
+#[cfg(feature = "mysql")]
+#[derive(QueryableByName)]
+struct ForeignKey {
+    #[diesel(sql_type = Text)]
+    table_name: String,
+    #[diesel(sql_type = Text)]
+    constraint_name: String,
+    #[diesel(sql_type = Text)]
+    column_name: String,
+    #[diesel(sql_type = Text)]
+    referenced_table_name: String,
+    #[diesel(sql_type = Text)]
+    referenced_column_name: String,
+}
+
+#[cfg(feature = "mysql")]
+#[derive(QueryableByName)]
+struct Total {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    total: i64,
+}
+
+#[cfg(feature = "mysql")]
+async fn rebuild(conn: &mut DbConnection) -> anyhow::Result<()> {
+    conn.batch_execute("SET FOREIGN_KEY_CHECKS = 1").await?;
+    let keys = diesel::sql_query(
+        "SELECT table_name AS table_name, constraint_name AS constraint_name, column_name AS column_name, \
+         referenced_table_name AS referenced_table_name, referenced_column_name AS referenced_column_name \
+         FROM information_schema.key_column_usage WHERE table_schema = DATABASE() AND referenced_table_name IS NOT NULL \
+         ORDER BY table_name, constraint_name, ordinal_position")
+        .load::<ForeignKey>(conn).await?;
+    for constraint in keys.chunk_by(|a, b| (&a.table_name, &a.constraint_name) == (&b.table_name, &b.constraint_name)) {
+        let first = &constraint[0];
+        let set: Vec<String> = constraint.iter().map(|key| format!("c.{} IS NOT NULL", quoted(&key.column_name))).collect();
+        let matched: Vec<String> = constraint.iter().map(|key| format!("p.{} = c.{}", quoted(&key.referenced_column_name), quoted(&key.column_name))).collect();
+        let orphans = diesel::sql_query(format!(
+            "SELECT count(*) AS total FROM {} c WHERE {} AND NOT EXISTS (SELECT 1 FROM {} p WHERE {})",
+            quoted(&first.table_name), set.join(" AND "), quoted(&first.referenced_table_name), matched.join(" AND ")))
+            .get_result::<Total>(conn).await?
+            .total;
+        if orphans > 0 {
+            anyhow::bail!("{} rows of {} break the foreign key {}", orphans, first.table_name, first.constraint_name);
+        }
+    }
+    Ok(())
+}
+
+// Reads everything inside one transaction, so the document is a consistent snapshot.
+pub async fn export(conn: &mut DbConnection) -> anyhow::Result<Backup> {
+    conn.transaction(|conn| async move {
+        let mut tables = BTreeMap::new();
+        for table in table_names(conn).await? {
+            let columns = column_names(conn, &table).await?;
+            let rows = diesel::sql_query(select_rows(&table, &columns))
+                .load::<Row>(conn).await?
+                .into_iter()
+                .map(|row| serde_json::from_str(&row.row))
+                .collect::<Result<_, _>>()?;
+            tables.insert(table, BackupTable { columns, rows });
+        }
+        Ok(Backup { format: BACKUP_FORMAT, exported_at: chrono::Utc::now().naive_utc(), tables })
+    }.scope_boxed()).await
+}
+
+// Why a backup can't be restored into this database. Nothing has been changed when it is
+// returned.
+#[derive(Debug)]
+pub struct Mismatch(pub String);
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+// Replaces every row of every table with the backup's, in one transaction: either the whole
+// backup is restored or nothing changes. The backup must have exactly this database's tables
+// and columns, i.e. come from a server at the same migration. Foreign keys are checked once at
+// the end, so tables can go in any order. Returns how many rows went into each table.
+pub async fn restore(conn: &mut DbConnection, backup: &Backup) -> anyhow::Result<BTreeMap<String, usize>> {
+    if backup.format != BACKUP_FORMAT {
+        return Err(Mismatch(format!("Format {} backups can't be restored, only format {}", backup.format, BACKUP_FORMAT)).into());
+    }
+    let restored = conn.transaction(|conn| async move {
+        let tables = table_names(conn).await?;
+        if let Some(extra) = backup.tables.keys().find(|table| !tables.contains(table)) {
+            return Err(Mismatch(format!("The backup has a table this database doesn't: {}", extra)).into());
+        }
+        let mut columns_by_table = Vec::new();
+        for table in &tables {
+            let Some(saved) = backup.tables.get(table) else {
+                return Err(Mismatch(format!("The backup has no {} table", table)).into());
+            };
+            let columns = column_names(conn, table).await?;
+            if saved.columns != columns {
+                return Err(Mismatch(format!("The backup's {} columns ({}) aren't this database's ({})", table, saved.columns.join(", "), columns.join(", "))).into());
+            }
+            columns_by_table.push((table.clone(), columns));
+        }
+        clear(conn, &tables).await?;
+        let mut restored = BTreeMap::new();
+        for (table, columns) in columns_by_table {
+            let rows = &backup.tables[&table].rows;
+            if !rows.is_empty() {
+                diesel::sql_query(insert_rows(&table, &columns))
+                    .bind::<Text, _>(serde_json::to_string(rows)?)
+                    .execute(conn).await?;
+            }
+            restored.insert(table, rows.len());
+        }
+        rebuild(conn).await?;
+        Ok(restored)
+    }.scope_boxed()).await;
+    // the setting belongs to the connection, not the transaction, so it's put back even when
+    // the restore failed
+    #[cfg(feature = "mysql")]
+    conn.batch_execute("SET FOREIGN_KEY_CHECKS = 1").await?;
+    restored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::CrudOperations;
+    use crate::models::{Task, User};
+    use crate::search::search_tasks;
+    use crate::test_support::{conn, create_task};
+
+    #[tokio::test]
+    async fn a_backup_restores_what_was_there_when_it_was_taken() {
+        let mut conn = conn().await;
+        let kept = create_task(&mut conn, "Keep this \"quoted\" task").await;
+        let backup = export(&mut conn).await.unwrap();
+        assert!(backup.tables.contains_key("tasks") && !backup.tables.contains_key("task_search"));
+        let round_trip: Backup = serde_json::from_str(&serde_json::to_string(&backup).unwrap()).unwrap();
+
+        let added_later = create_task(&mut conn, "Added after the backup").await;
+        Task::delete(&mut conn, kept.task_id).await.unwrap();
+        let restored = restore(&mut conn, &round_trip).await.unwrap();
+
+        assert_eq!(restored["tasks"], backup.tables["tasks"].rows.len());
+        assert!(Task::read(&mut conn, added_later.task_id).await.unwrap().is_none());
+        assert_eq!(Task::read(&mut conn, kept.task_id).await.unwrap().unwrap().task_name, kept.task_name);
+        assert_eq!(User::read_all(&mut conn).await.unwrap().len(), backup.tables["users"].rows.iter().filter(|user| user["tenant_id"] == 1).count());
+        assert_eq!(search_tasks(&mut conn, "quoted", 1, 10).await.unwrap().total, 1);
+    }
+
+    #[tokio::test]
+    async fn a_backup_that_doesnt_fit_changes_nothing() {
+        let mut conn = conn().await;
+        let mut backup = export(&mut conn).await.unwrap();
+        backup.tables.get_mut("tasks").unwrap().columns.pop();
+        let task = create_task(&mut conn, "Still here").await;
+        assert!(restore(&mut conn, &backup).await.unwrap_err().is::<Mismatch>());
+
+        #[cfg(feature = "sqlite")]
+        conn.batch_execute("PRAGMA foreign_keys = ON").await.unwrap();
+        let mut backup = export(&mut conn).await.unwrap();
+        backup.tables.get_mut("user_tasks").unwrap().rows[0].insert("task_id".to_string(), Value::from(9999));
+        assert!(restore(&mut conn, &backup).await.is_err());
+        assert!(Task::read(&mut conn, task.task_id).await.unwrap().is_some());
+    }
+}
@@ -1,12 +1,12 @@
-use diesel::prelude::*;
-use diesel::SqliteConnection;  
+use tasks_db_lib::DbConnection;
 use tasks_db_lib::*;           // schema::users
 use tasks_db_lib::crud::CrudOperations;
 use tasks_db_lib::models::{User, NewUser, Task, NewTask, UserTask, NewUserTask, TaskStatus, NewTaskStatus};
 
-fn main() {
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
     println!("Requesting connection...");
-    let mut connection =  establish_connection();
+    let mut connection =  establish_connection().await;
     println!("Received connection...");
 
     // show_users(&mut connection);
@@ -15,25 +15,25 @@ fn main() {
     // Demonstrate User CRUD operations
     // Create
     let new_user = NewUser { name: "Test User", email: "testuser@example.com", active: true };
-    let created_user = match User::create(&mut connection, new_user) {
+    let created_user = match User::create(&mut connection, new_user).await {
         Ok(user) => { println!("Created user: {} (id: {})", user.name, user.user_id); Some(user) },
         Err(e) => { println!("Create failed: {}", e); None }
     };
 
     if let Some(user) = &created_user {
-        let fetched = User::read(&mut connection,user.user_id).unwrap();
+        let fetched = User::read(&mut connection,user.user_id).await.unwrap();
         println!("Fetched user: {:?}", fetched);
     }
     
     // Update
     if let Some(user) = &created_user {
         let updated_user = NewUser { name: "Updated User", email: "updated@example.com", active: false };
-        let updated = User::update(&mut connection, user.user_id, updated_user).unwrap();
+        let updated = User::update(&mut connection, user.user_id, updated_user).await.unwrap();
         println!("Updated user: {:?}", updated);
     }
 
     // Read all
-    let all_users = User::read_all(&mut connection).unwrap();
+    let all_users = User::read_all(&mut connection).await.unwrap();
     println!("All users ({}):", all_users.len());
     for user in &all_users {
         println!("- {} (id: {})", user.name, user.user_id);
@@ -41,7 +41,7 @@ fn main() {
     
     // Delete
     if let Some(user) = &created_user {
-        let deleted = User::delete(&mut connection, user.user_id).unwrap();
+        let deleted = User::delete(&mut connection, user.user_id).await.unwrap();
         println!("Deleted {} user(s) with id {}", deleted, user.user_id);
     }
 
@@ -50,26 +50,26 @@ fn main() {
 //*************************************
     // Demonstrate Task CRUD operations
     // Create
-    let new_task = NewTask { task_name: "Test Task"};
-    let created_task = match Task::create(&mut connection, new_task) {
+    let new_task = NewTask { task_name: "Test Task", due_date: None, priority: enums::TaskPriority::Medium, parent_task_id: None, recurrence: None, project_id: None };
+    let created_task = match Task::create(&mut connection, new_task).await {
         Ok(task) => { println!("Created task: {} (id: {})", task.task_name, task.task_id); Some(task) },
         Err(e) => { println!("Task create failed: {}", e); None }
     };
 
     if let Some(task) = &created_task {
-        let fetched = Task::read(&mut connection,task.task_id).unwrap();
+        let fetched = Task::read(&mut connection,task.task_id).await.unwrap();
         println!("Fetched task: {:?}", fetched);
     }
     
     // Update
     if let Some(task) = &created_task {
-        let updated_task = NewTask { task_name: "Updated Task" };
-        let updated = Task::update(&mut connection,task.task_id,updated_task ).unwrap();
+        let updated_task = NewTask { task_name: "Updated Task", due_date: None, priority: enums::TaskPriority::High, parent_task_id: None, recurrence: None, project_id: None };
+        let updated = Task::update(&mut connection,task.task_id,updated_task ).await.unwrap();
         println!("Updated task: {:?}", updated);
     }
 
     // Read all
-    let all_tasks = Task::read_all(&mut connection).unwrap();
+    let all_tasks = Task::read_all(&mut connection).await.unwrap();
     println!("All tasks ({}):", all_tasks.len());
     for task in &all_tasks {
         println!("- {} (id: {})", task.task_name, task.task_id);
@@ -77,7 +77,7 @@ fn main() {
     
     // Delete
     if let Some(task) = &created_task {
-        let deleted = Task::delete(&mut connection, task.task_id).unwrap();
+        let deleted = Task::delete(&mut connection, task.task_id).await.unwrap();
         println!("Deleted {} task(s) with id {}", deleted, task.task_id);
     }
 
@@ -91,28 +91,26 @@ fn main() {
     let tsid = 1;
 
     let new_user_task = NewUserTask { user_id: uid, task_id: tid, task_status_id: tsid };
-    let created_user_task = match UserTask::create(&mut connection, new_user_task) {
+    let created_user_task = match UserTask::create(&mut connection, new_user_task).await {
         Ok(user_task) => { println!("Created user_task: (user_id: {}, task_id: {})", user_task.user_id, user_task.task_id); Some(user_task) },
         Err(e) => { println!("Create failed: {}", e); None }
     };
 
-    if let Some(user_task) = &created_user_task {
-        let fetched = UserTask::read(&mut connection,(uid,tid)).unwrap();
+    if created_user_task.is_some() {
+        let fetched = UserTask::read(&mut connection,(uid,tid)).await.unwrap();
         println!("Fetched user task: {:?}", fetched);
     }
     
     // Update
-    if let Some(user_task) = &created_user_task {
-            if let Some(task) = &created_task {
-            let tid = created_task.as_ref().unwrap().task_id;
-            let updated_user_task = NewUserTask {user_id: user_task.user_id,task_id: tid, task_status_id: 2 };
-            let updated = UserTask::update(&mut connection, (uid,tid), updated_user_task).unwrap();
-            println!("Updated user task: {:?}", updated);
-        }
+    if let (Some(user_task), Some(task)) = (&created_user_task, &created_task) {
+        let tid = task.task_id;
+        let updated_user_task = NewUserTask {user_id: user_task.user_id,task_id: tid, task_status_id: 2 };
+        let updated = UserTask::update(&mut connection, (uid,tid), updated_user_task).await.unwrap();
+        println!("Updated user task: {:?}", updated);
     }
 
     // Read all
-    let all_user_tasks = UserTask::read_all(&mut connection).unwrap();
+    let all_user_tasks = UserTask::read_all(&mut connection).await.unwrap();
     println!("All users tasks ({}):", all_user_tasks.len());
     for user_task in &all_user_tasks {
         println!("- user_id: {} task_id: {})", user_task.user_id, user_task.task_id );
@@ -120,7 +118,7 @@ fn main() {
     
     // Delete
     if let Some(user_task) = &created_user_task {
-        let deleted = UserTask::delete(&mut connection, (user_task.user_id, user_task.task_id)).unwrap();
+        let deleted = UserTask::delete(&mut connection, (user_task.user_id, user_task.task_id)).await.unwrap();
         println!("Deleted {} user task(s) with user_id: {} and task_id: {}", deleted, user_task.user_id,user_task.task_id);
     }
 
@@ -129,33 +127,26 @@ fn main() {
 //*************************************
    // Demonstrate Task Status CRUD operations
     // Create
-    let uid = created_user.as_ref().unwrap().user_id;
-    let tid = created_task.as_ref().unwrap().task_id;
-    let tsid = 1;
-
     let new_task_status = NewTaskStatus { status_name: "Cancel" };
-    let created_task_status = match TaskStatus::create(&mut connection, new_task_status) {
+    let created_task_status = match TaskStatus::create(&mut connection, new_task_status).await {
         Ok(task_status) => { println!("Created task_status: {} (id: {})",task_status.status_name, task_status.task_status_id); Some(task_status) },
         Err(e) => { println!("Create failed: {}", e); None }
     };
 
     if let Some(task_status) = &created_task_status {
-        let fetched = TaskStatus::read(&mut connection,task_status.task_status_id).unwrap();
+        let fetched = TaskStatus::read(&mut connection,task_status.task_status_id).await.unwrap();
         println!("Fetched task status: {:?}", fetched);
     }
     
     // Update
-    if let Some(task_status) = &created_task_status {
-            if let Some(task) = &created_task {
-            let tid = created_task.unwrap().task_id;
-            let updated_task_status = NewTaskStatus {status_name: "Cancelled"};
-            let updated = TaskStatus::update(&mut connection, task_status.task_status_id, updated_task_status).unwrap();
-            println!("Updated task status: {:?}", updated);
-        }
+    if let (Some(task_status), Some(_task)) = (&created_task_status, &created_task) {
+        let updated_task_status = NewTaskStatus {status_name: "Cancelled"};
+        let updated = TaskStatus::update(&mut connection, task_status.task_status_id, updated_task_status).await.unwrap();
+        println!("Updated task status: {:?}", updated);
     }
 
     // Read all
-    let all_task_statuses = TaskStatus::read_all(&mut connection).unwrap();
+    let all_task_statuses = TaskStatus::read_all(&mut connection).await.unwrap();
     println!("All task statuses ({}):", all_task_statuses.len());
     for task_status in &all_task_statuses {
         println!("- Task Status: {:?} task_status_id: {})", task_status.status_name, task_status.task_status_id );
@@ -163,7 +154,7 @@ fn main() {
     
     // Delete
     if let Some(task_status) = &created_task_status {
-        let deleted = TaskStatus::delete(&mut connection, task_status.task_status_id).unwrap();
+        let deleted = TaskStatus::delete(&mut connection, task_status.task_status_id).await.unwrap();
         println!("Deleted {} user task(s) with id {}", deleted, task_status.task_status_id);
     }
 
@@ -171,13 +162,15 @@ fn main() {
 }
 
 
-fn show_user_tasks(conn: &mut SqliteConnection) {
+#[allow(dead_code)]
+async fn show_user_tasks(conn: &mut DbConnection) {
     
     use tasks_db_lib::schema::users::dsl as users_dsl;
     use tasks_db_lib::schema::user_tasks::dsl as ut_dsl;
     use tasks_db_lib::schema::tasks::dsl as tasks_dsl;
     use tasks_db_lib::schema::task_statuses::dsl as ts_dsl;
     use diesel::prelude::*;
+    use diesel_async::RunQueryDsl;
 
     let results = users_dsl::users
         .inner_join(ut_dsl::user_tasks.on(users_dsl::user_id.eq(ut_dsl::user_id)))
@@ -185,6 +178,7 @@ fn show_user_tasks(conn: &mut SqliteConnection) {
         .inner_join(ts_dsl::task_statuses.on(ut_dsl::task_status_id.eq(ts_dsl::task_status_id)))
         .select((users_dsl::name, tasks_dsl::task_name, ts_dsl::status_name))
         .load::<(String, String, String)>(conn)
+        .await
         .expect("Error loading user tasks");
 
     println!("\nUser | Task | Status");
@@ -194,8 +188,10 @@ fn show_user_tasks(conn: &mut SqliteConnection) {
     }
 }
 
-fn show_users(conn: &mut SqliteConnection) {
+#[allow(dead_code)]
+async fn show_users(conn: &mut DbConnection) {
     use diesel::prelude::*;
+    use diesel_async::RunQueryDsl;
     use tasks_db_lib::models::*;   // users
     use tasks_db_lib::schema::users::dsl::*; // id, name, email, active, star
     
@@ -205,6 +201,7 @@ fn show_users(conn: &mut SqliteConnection) {
         .limit(5)
         .select(User::as_select())
         .load(conn)
+        .await
         .expect("Error loading users");
     
     println!("User query returned...");
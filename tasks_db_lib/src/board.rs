@@ -0,0 +1,90 @@
+use crate::DbConnection;
+use serde::{Deserialize, Serialize};
+use crate::crud::CrudOperations;
+use crate::filters::AssignmentFilter;
+use crate::models::{AssignmentDetail, TaskStatus};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Assignee {
+    pub user_id: i32,
+    pub name: String,
+}
+
+// A task as it appears in one column: only the assignees whose assignment is in that status.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoardCard {
+    pub task_id: i32,
+    pub task_name: String,
+    pub assignees: Vec<Assignee>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoardColumn {
+    pub task_status_id: i32,
+    pub status_name: String,
+    pub cards: Vec<BoardCard>,
+}
+
+// One column per live status, in status id order, empty columns included. Status lives on
+// the assignment rather than the task, so a task whose assignees are at different stages
+// shows up in each of those columns. Tasks nobody is assigned to aren't on the board.
+// `project_id` narrows the cards to that project's tasks; the columns stay the same.
+pub async fn read_board(conn: &mut DbConnection, project_id: Option<i32>) -> anyhow::Result<Vec<BoardColumn>> {
+    let details = AssignmentDetail::read_all(conn, &AssignmentFilter { project_id, ..AssignmentFilter::default() }).await?;
+    let mut statuses = TaskStatus::read_all(conn).await?;
+    statuses.sort_by_key(|status| status.task_status_id);
+    let mut columns: Vec<BoardColumn> = statuses.into_iter()
+        .map(|status| BoardColumn { task_status_id: status.task_status_id, status_name: status.status_name, cards: Vec::new() })
+        .collect();
+    // details come ordered by (status, task, user), so each card's rows are adjacent
+    for detail in details {
+        let Some(column) = columns.iter_mut().find(|column| column.task_status_id == detail.task_status_id) else {
+            continue;
+        };
+        let assignee = Assignee { user_id: detail.user_id, name: detail.user_name };
+        match column.cards.last_mut() {
+            Some(card) if card.task_id == detail.task_id => card.assignees.push(assignee),
+            _ => column.cards.push(BoardCard { task_id: detail.task_id, task_name: detail.task_name, assignees: vec![assignee] }),
+        }
+    }
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewProject, NewTask, NewUserTask, Project, Task, UserTask};
+    use crate::test_support::{self, create_task, new_task};
+
+    #[tokio::test]
+    async fn a_task_shows_up_in_each_of_its_assignees_columns() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        for (user_id, task_status_id) in [(1, 2), (2, 3), (3, 2)] {
+            UserTask::create(&mut conn, NewUserTask { user_id, task_id: task.task_id, task_status_id }).await.unwrap();
+        }
+        let board = read_board(&mut conn, None).await.unwrap();
+        let card = |status: i32| board.iter()
+            .find(|column| column.task_status_id == status).unwrap()
+            .cards.iter().find(|card| card.task_id == task.task_id)
+            .map(|card| card.assignees.iter().map(|assignee| assignee.user_id).collect::<Vec<_>>());
+        assert_eq!(card(1), None);
+        assert_eq!(card(2), Some(vec![1, 3]));
+        assert_eq!(card(3), Some(vec![2]));
+    }
+
+    #[tokio::test]
+    async fn a_projects_board_only_carries_its_tasks() {
+        let mut conn = test_support::conn().await;
+        let project = Project::create(&mut conn, NewProject { project_name: "Kitchen", description: None, team_id: None }).await.unwrap();
+        let inside = Task::create(&mut conn, NewTask { project_id: Some(project.project_id), ..new_task("Tile the splashback") }).await.unwrap();
+        let outside = create_task(&mut conn, "Tile the bathroom").await;
+        for task_id in [inside.task_id, outside.task_id] {
+            UserTask::create(&mut conn, NewUserTask { user_id: 1, task_id, task_status_id: 1 }).await.unwrap();
+        }
+        let board = read_board(&mut conn, Some(project.project_id)).await.unwrap();
+        let task_ids: Vec<i32> = board.iter().flat_map(|column| column.cards.iter().map(|card| card.task_id)).collect();
+        assert_eq!(task_ids, [inside.task_id]);
+        assert_eq!(board.len(), read_board(&mut conn, None).await.unwrap().len());
+    }
+}
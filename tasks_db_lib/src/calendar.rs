@@ -0,0 +1,123 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use serde::Serialize;
+use crate::enums::TaskPriority;
+use crate::models::{CalendarFeed, NewCalendarFeed};
+use crate::schema::{calendar_feeds, task_statuses, tasks, user_tasks, users};
+use crate::tenancy;
+
+// One of a user's assignments that falls due on a day, with what a calendar event shows.
+#[derive(Queryable, Selectable, Debug, Clone, Serialize)]
+#[diesel(table_name = user_tasks)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct CalendarEntry {
+    pub user_id: i32,
+    pub task_id: i32,
+    #[diesel(select_expression = tasks::task_name)]
+    pub task_name: String,
+    #[diesel(select_expression = tasks::due_date.assume_not_null())]
+    #[diesel(select_expression_type = diesel::dsl::AssumeNotNull<tasks::due_date>)]
+    pub due_date: chrono::NaiveDate,
+    #[diesel(select_expression = tasks::priority)]
+    pub priority: TaskPriority,
+    #[diesel(select_expression = task_statuses::status_name)]
+    pub status_name: String,
+    // the later of the task's and the assignment's last change
+    #[diesel(select_expression = tasks::updated_at)]
+    pub task_updated_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+// The user's live assignments to live tasks that have a due date, soonest first.
+pub async fn read_for_user(conn: &mut DbConnection, user_id: i32) -> anyhow::Result<Vec<CalendarEntry>> {
+    let items = user_tasks::table
+        .inner_join(tasks::table)
+        .inner_join(task_statuses::table)
+        .filter(user_tasks::tenant_id.eq(tenancy::current()))
+        .filter(user_tasks::user_id.eq(user_id))
+        .filter(user_tasks::deleted_at.is_null())
+        .filter(tasks::deleted_at.is_null())
+        .filter(tasks::due_date.is_not_null())
+        .order((tasks::due_date, user_tasks::task_id))
+        .select(CalendarEntry::as_select())
+        .load(conn).await?;
+    Ok(items)
+}
+
+// A feed belongs to its user and is only reached through them, so the tenant is the user's.
+impl CalendarFeed {
+    // Turns the user's feed on, or gives it a new token, which stops the old URL working.
+    pub async fn save(conn: &mut DbConnection, new_feed: NewCalendarFeed<'_>) -> anyhow::Result<CalendarFeed> {
+        diesel::insert_into(calendar_feeds::table)
+            .values(&new_feed)
+            .on_conflict(conflict_target!(calendar_feeds::user_id))
+            .do_update()
+            .set((
+                calendar_feeds::token_hash.eq(new_feed.token_hash),
+                calendar_feeds::created_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(conn).await?;
+        let feed = calendar_feeds::table.filter(calendar_feeds::user_id.eq(new_feed.user_id)).first(conn).await?;
+        Ok(feed)
+    }
+
+    // The feed with this token hash, if its user is active. Any tenant: the token is all a
+    // calendar app has to go on, and the caller enters the user's tenant with it.
+    pub async fn read_by_token_hash(conn: &mut DbConnection, token_hash: &str) -> anyhow::Result<Option<(CalendarFeed, i32)>> {
+        let feed = calendar_feeds::table
+            .inner_join(users::table)
+            .filter(calendar_feeds::token_hash.eq(token_hash))
+            .filter(users::active.eq(true))
+            .select((CalendarFeed::as_select(), users::tenant_id))
+            .first(conn).await
+            .optional()?;
+        Ok(feed)
+    }
+
+    // Returns the number of feeds removed (0 when the user had none).
+    pub async fn delete(conn: &mut DbConnection, user_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::delete(calendar_feeds::table.find(user_id)).execute(conn).await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::CrudOperations;
+    use crate::models::{NewTask, NewUserTask, Task, UserTask};
+    use crate::test_support::{conn, create_task, new_task};
+
+    #[tokio::test]
+    async fn only_live_assignments_with_a_due_date_are_on_the_calendar() {
+        let mut conn = conn().await;
+        let due = chrono::NaiveDate::from_ymd_opt(2026, 11, 2).unwrap();
+        let dated = Task::create(&mut conn, NewTask { due_date: Some(due), ..new_task("Dated") }).await.unwrap();
+        let undated = create_task(&mut conn, "Undated").await;
+        let dropped = Task::create(&mut conn, NewTask { due_date: Some(due), ..new_task("Dropped") }).await.unwrap();
+        for task_id in [dated.task_id, undated.task_id, dropped.task_id] {
+            UserTask::create(&mut conn, NewUserTask { user_id: 1, task_id, task_status_id: 1 }).await.unwrap();
+        }
+        UserTask::delete(&mut conn, (1, dropped.task_id)).await.unwrap();
+
+        let entries = read_for_user(&mut conn, 1).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].task_id, entries[0].due_date), (dated.task_id, due));
+        assert_eq!(entries[0].status_name, "Not Started");
+        assert!(read_for_user(&mut conn, 3).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_new_token_replaces_the_old_one() {
+        let mut conn = conn().await;
+        CalendarFeed::save(&mut conn, NewCalendarFeed { user_id: 1, token_hash: "old" }).await.unwrap();
+        CalendarFeed::save(&mut conn, NewCalendarFeed { user_id: 1, token_hash: "new" }).await.unwrap();
+        assert!(CalendarFeed::read_by_token_hash(&mut conn, "old").await.unwrap().is_none());
+        let (feed, tenant_id) = CalendarFeed::read_by_token_hash(&mut conn, "new").await.unwrap().unwrap();
+        assert_eq!((feed.user_id, tenant_id), (1, tenancy::DEFAULT_TENANT));
+
+        assert_eq!(CalendarFeed::delete(&mut conn, 1).await.unwrap(), 1);
+        assert!(CalendarFeed::read_by_token_hash(&mut conn, "new").await.unwrap().is_none());
+    }
+}
@@ -1,161 +1,2951 @@
 use diesel::prelude::*;
-use crate::models::{NewTask, NewTaskStatus, NewUser, NewUserTask, Task, TaskStatus, User, UserTask};
-use crate::schema::{users, tasks, user_tasks, task_statuses};
+use diesel_async::RunQueryDsl;
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::{ScopedBoxFuture, ScopedFutureExt};
+use crate::{DbBackend, DbConnection};
+use crate::models::{ApiKey, AssignmentDetail, Attachment, NewAttachment, Comment, CommentRevision, Credential, NewComment, NewCommentRevision, NewApiKey, NewCredential, NewOAuthIdentity, NewProject, NewTeam, NewTeamMember, NewRefreshToken, NewStarredTask, NewTag, NewTaskDependency, NewTaskTag, NewTaskTemplate, Tag, TaskDependency, TaskTemplate, OAuthIdentity, Project, Team, RefreshToken, RevokedToken, Role, StarredTask, NewTask, NewTaskStatus, Tenant, NewUser, NewUserTask, Task, TaskStatus, TaskStatusChanges, User, UserTask, UserTaskChanges};
+use crate::schema::{api_key_usage, api_keys, attachments, calendar_feeds, comment_revisions, comments, mentions as mention_rows, credentials, due_reminders, notification_preferences, notifications, oauth_identities, outbox, projects, push_subscriptions, refresh_tokens, revoked_tokens, roles, slack_integrations, starred_tasks, tags, task_dependencies, task_tags, task_templates, task_watchers, team_members, teams, users, tasks, user_tasks, task_statuses, webhooks};
+use crate::pagination::{self, Page};
+use crate::filters::{AssignmentFilter, TaskFilter};
+use crate::sorting::{self, Sort};
+use crate::enums::UserRole;
+use crate::audit::{self, AuditAction, AuditedCrud};
+use crate::versioning;
+use crate::search;
+use crate::mentions;
+use crate::tenancy;
 
+// A task as Task::create_many_with_assignments made it, with the assignments it made for it.
+pub type TaskWithAssignments = (Task, Vec<UserTask>);
+
+// user_tasks joined to the three tables it points at.
+type AssignmentJoin = diesel::dsl::InnerJoin<diesel::dsl::InnerJoin<diesel::dsl::InnerJoin<user_tasks::table, users::table>, tasks::table>, task_statuses::table>;
 
 // pub trait CrudOperations<T1, T2, T3, T4>
 pub trait CrudOperations<Conn, Id, NewEntity, Entity> {
-    fn create(conn: &mut Conn, new_entity: NewEntity) -> anyhow::Result<Entity>;
-    fn read(conn: &mut Conn, id: Id) -> anyhow::Result<Option<Entity>>;
-    fn update(conn: &mut Conn, id: Id, updated_entity: NewEntity) -> anyhow::Result<Entity>;
-    fn delete(conn: &mut Conn, id: Id) -> anyhow::Result<usize>;
-    fn read_all(conn: &mut Conn) -> anyhow::Result<Vec<Entity>>;
-}
-
-impl<'a> CrudOperations<SqliteConnection, i32, NewUser<'a>, User> for User {
-    fn create(conn: &mut SqliteConnection, new_user: NewUser<'a>) -> anyhow::Result<User> {
-        let user = diesel::insert_into(users::table)
-            .values(&new_user)
-            .returning(User::as_returning())
-            .get_result(conn)?;
+    fn create(conn: &mut Conn, new_entity: NewEntity) -> impl Future<Output = anyhow::Result<Entity>> + Send;
+    fn read(conn: &mut Conn, id: Id) -> impl Future<Output = anyhow::Result<Option<Entity>>> + Send;
+    fn update(conn: &mut Conn, id: Id, updated_entity: NewEntity) -> impl Future<Output = anyhow::Result<Entity>> + Send {
+        Self::update_versioned(conn, id, None, updated_entity)
+    }
+    // Writes the row only while it is still at `expected_version` (any version when None); the
+    // version is part of the UPDATE's filter, so no other write can land between check and change.
+    // Fails with versioning::StaleVersion when the row has moved on.
+    fn update_versioned(conn: &mut Conn, id: Id, expected_version: Option<i32>, updated_entity: NewEntity) -> impl Future<Output = anyhow::Result<Entity>> + Send;
+    fn delete(conn: &mut Conn, id: Id) -> impl Future<Output = anyhow::Result<usize>> + Send;
+    fn read_all(conn: &mut Conn) -> impl Future<Output = anyhow::Result<Vec<Entity>>> + Send;
+    fn read_page(conn: &mut Conn, page: i64, per_page: i64, sort: &Sort) -> impl Future<Output = anyhow::Result<Page<Entity>>> + Send;
+    // Live rows only, same as read_all and read_page.
+    fn count(conn: &mut Conn) -> impl Future<Output = anyhow::Result<i64>> + Send;
+}
+
+impl<'a> CrudOperations<DbConnection, i32, NewUser<'a>, User> for User {
+    async fn create(conn: &mut DbConnection, new_user: NewUser<'a>) -> anyhow::Result<User> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(users::table)
+            .values((&new_user, users::tenant_id.eq(tenancy::current()), users::created_at.eq(now), users::updated_at.eq(now)))
+            .execute(conn).await?;
+        let user = users::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
         Ok(user)
     }
 
-    fn read(conn: &mut SqliteConnection, id: i32) -> anyhow::Result<Option<User>> {
-        let user = users::table.find(id).first(conn).optional()?;
+    async fn read(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<User>> {
+        let user = users::table.find(id).filter(users::tenant_id.eq(tenancy::current())).first(conn).await.optional()?;
         Ok(user)
     }
 
-    fn update(conn: &mut SqliteConnection, id: i32, updated_user: NewUser<'a>) -> anyhow::Result<User> {
-        diesel::update(users::table.find(id))
-            .set((users::name.eq(updated_user.name), users::email.eq(updated_user.email), users::active.eq(updated_user.active)))
-            .execute(conn)?;
-        let user = users::table.find(id).first(conn)?;
+    async fn update_versioned(conn: &mut DbConnection, id: i32, expected_version: Option<i32>, updated_user: NewUser<'a>) -> anyhow::Result<User> {
+        let count = diesel::update(users::table.find(id).filter(users::tenant_id.eq(tenancy::current())).filter(users::version.eq(versioning::expected_or(expected_version, users::version))))
+            .set((users::name.eq(updated_user.name), users::email.eq(updated_user.email), users::active.eq(updated_user.active), users::updated_at.eq(chrono::Utc::now().naive_utc()), users::version.eq(users::version + 1)))
+            .execute(conn).await?;
+        if count == 0 {
+            return Err(versioning::missed(User::read(conn, id).await?, expected_version));
+        }
+        let user = users::table.find(id).filter(users::tenant_id.eq(tenancy::current())).first(conn).await?;
         Ok(user)
     }
 
-    fn delete(conn: &mut SqliteConnection, id: i32) -> anyhow::Result<usize> {
-        let count = diesel::delete(users::table.find(id)).execute(conn)?;
-        Ok(count)
+    // The user leaves every team they were on, loses their assignments (trashed ones included),
+    // stops watching and starring tasks, and loses their password, API keys, sign-in sessions,
+    // linked OAuth accounts, mentions, notifications, webhooks, push subscriptions and notification
+    // preferences.
+    // Comments and attachments they wrote stay theirs, so a user who has any can't be deleted
+    // (deactivate them instead).
+    async fn delete(conn: &mut DbConnection, id: i32) -> anyhow::Result<usize> {
+        conn.transaction(|conn| async move {
+            if User::read(conn, id).await?.is_none() {
+                return Ok(0);
+            }
+            // everything pointing at the user goes first, or the foreign keys would refuse the delete
+            diesel::delete(user_tasks::table.filter(user_tasks::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(team_members::table.filter(team_members::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(task_watchers::table.filter(task_watchers::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(starred_tasks::table.filter(starred_tasks::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(due_reminders::table.filter(due_reminders::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(credentials::table.find(id)).execute(conn).await?;
+            diesel::delete(api_key_usage::table.filter(api_key_usage::api_key_id.eq_any(api_keys::table.filter(api_keys::user_id.eq(id)).select(api_keys::api_key_id)))).execute(conn).await?;
+            diesel::delete(api_keys::table.filter(api_keys::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(refresh_tokens::table.filter(refresh_tokens::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(oauth_identities::table.filter(oauth_identities::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(mention_rows::table.filter(mention_rows::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(notifications::table.filter(notifications::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(outbox::table.filter(outbox::webhook_id.eq_any(webhooks::table.filter(webhooks::user_id.eq(id)).select(webhooks::webhook_id)))).execute(conn).await?;
+            diesel::delete(webhooks::table.filter(webhooks::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(push_subscriptions::table.filter(push_subscriptions::user_id.eq(id))).execute(conn).await?;
+            diesel::delete(calendar_feeds::table.find(id)).execute(conn).await?;
+            diesel::delete(notification_preferences::table.filter(notification_preferences::user_id.eq(id))).execute(conn).await?;
+            let count = diesel::delete(users::table.find(id).filter(users::tenant_id.eq(tenancy::current()))).execute(conn).await?;
+            Ok(count)
+        }.scope_boxed()).await
     }
 
-    fn read_all(conn: &mut SqliteConnection) -> anyhow::Result<Vec<User>> {
-        let results = users::table.load::<User>(conn)?;
+    async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<User>> {
+        let results = users::table.filter(users::tenant_id.eq(tenancy::current())).load::<User>(conn).await?;
         Ok(results)
     }
+
+    async fn read_page(conn: &mut DbConnection, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<User>> {
+        let total = Self::count(conn).await?;
+        let items = sorted_users(sort)?
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<User>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    async fn count(conn: &mut DbConnection) -> anyhow::Result<i64> {
+        let count = users::table.filter(users::tenant_id.eq(tenancy::current())).count().get_result(conn).await?;
+        Ok(count)
+    }
 }
 
 
-impl<'a> CrudOperations<SqliteConnection, i32, NewTask<'a>, Task> for Task {
-    fn create(conn: &mut SqliteConnection, new_task: NewTask<'a>) -> anyhow::Result<Task> {
-        let task = diesel::insert_into(tasks::table)
-            .values(&new_task)
-            .returning(Task::as_returning())
-            .get_result(conn)?;
+impl<'a> CrudOperations<DbConnection, i32, NewTask<'a>, Task> for Task {
+    async fn create(conn: &mut DbConnection, new_task: NewTask<'a>) -> anyhow::Result<Task> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(tasks::table)
+            .values((&new_task, tasks::tenant_id.eq(tenancy::current()), tasks::created_at.eq(now), tasks::updated_at.eq(now)))
+            .execute(conn).await?;
+        let task = tasks::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        search::index_task(conn, &task).await?;
         Ok(task)
     }
 
-    fn read(conn: &mut SqliteConnection, id: i32) -> anyhow::Result<Option<Task>> {
-        let tasks = tasks::table.find(id).first(conn).optional()?;
+    async fn read(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<Task>> {
+        let tasks = tasks::table.find(id).filter(tasks::tenant_id.eq(tenancy::current())).filter(tasks::deleted_at.is_null()).first(conn).await.optional()?;
         Ok(tasks)
     }
 
-    fn update(conn: &mut SqliteConnection, id: i32, updated_task: NewTask<'a>) -> anyhow::Result<Task> {
-        diesel::update(tasks::table.find(id))
-            .set(tasks::task_name.eq(updated_task.task_name))
-            .execute(conn)?;
-        let task = tasks::table.find(id).first(conn)?;
+    async fn update_versioned(conn: &mut DbConnection, id: i32, expected_version: Option<i32>, updated_task: NewTask<'a>) -> anyhow::Result<Task> {
+        let count = diesel::update(tasks::table.find(id).filter(tasks::tenant_id.eq(tenancy::current())).filter(tasks::deleted_at.is_null()).filter(tasks::version.eq(versioning::expected_or(expected_version, tasks::version))))
+            .set((tasks::task_name.eq(updated_task.task_name), tasks::due_date.eq(updated_task.due_date), tasks::priority.eq(updated_task.priority), tasks::parent_task_id.eq(updated_task.parent_task_id), tasks::recurrence.eq(updated_task.recurrence), tasks::project_id.eq(updated_task.project_id), tasks::updated_at.eq(chrono::Utc::now().naive_utc()), tasks::version.eq(tasks::version + 1)))
+            .execute(conn).await?;
+        if count == 0 {
+            return Err(versioning::missed(Task::read(conn, id).await?, expected_version));
+        }
+        let task = tasks::table.find(id).filter(tasks::tenant_id.eq(tenancy::current())).filter(tasks::deleted_at.is_null()).first(conn).await?;
+        search::index_task(conn, &task).await?;
         Ok(task)
     }
 
-    fn delete(conn: &mut SqliteConnection, id: i32) -> anyhow::Result<usize> {
-        let count = diesel::delete(tasks::table.find(id)).execute(conn)?;
-        Ok(count)
+    // Soft delete: the task and its assignments move to the trash with the same timestamp,
+    // which is how restore knows which assignments to bring back.
+    async fn delete(conn: &mut DbConnection, id: i32) -> anyhow::Result<usize> {
+        let now = chrono::Utc::now().naive_utc();
+        conn.transaction(|conn| async move {
+            let count = diesel::update(tasks::table.find(id).filter(tasks::tenant_id.eq(tenancy::current())).filter(tasks::deleted_at.is_null()))
+                .set(tasks::deleted_at.eq(now))
+                .execute(conn).await?;
+            if count > 0 {
+                diesel::update(user_tasks::table
+                    .filter(user_tasks::task_id.eq(id))
+                    .filter(user_tasks::deleted_at.is_null()))
+                    .set(user_tasks::deleted_at.eq(now))
+                    .execute(conn).await?;
+                search::unindex_task(conn, id).await?;
+            }
+            Ok(count)
+        }.scope_boxed()).await
     }
 
-    fn read_all(conn: &mut SqliteConnection) -> anyhow::Result<Vec<Task>> {
-        let results = tasks::table.load::<Task>(conn)?;
+    async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<Task>> {
+        let results = tasks::table.filter(tasks::tenant_id.eq(tenancy::current())).filter(tasks::deleted_at.is_null()).load::<Task>(conn).await?;
         Ok(results)
     }
+
+    async fn read_page(conn: &mut DbConnection, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<Task>> {
+        Task::read_page_filtered(conn, &TaskFilter::default(), page, per_page, sort).await
+    }
+
+    async fn count(conn: &mut DbConnection) -> anyhow::Result<i64> {
+        let count = tasks::table.filter(tasks::tenant_id.eq(tenancy::current())).filter(tasks::deleted_at.is_null()).count().get_result(conn).await?;
+        Ok(count)
+    }
 }
 
 
-impl<'a> CrudOperations<SqliteConnection, i32, NewTaskStatus<'a>, TaskStatus> for TaskStatus {
-    fn create(conn: &mut SqliteConnection, new_task_status: NewTaskStatus<'a>) -> anyhow::Result<TaskStatus> {
-        let task_status = diesel::insert_into(task_statuses::table)
-            .values(&new_task_status)
-            .returning(TaskStatus::as_returning())
-            .get_result(conn)?;
+impl<'a> CrudOperations<DbConnection, i32, NewTaskStatus<'a>, TaskStatus> for TaskStatus {
+    async fn create(conn: &mut DbConnection, new_task_status: NewTaskStatus<'a>) -> anyhow::Result<TaskStatus> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(task_statuses::table)
+            .values((&new_task_status, task_statuses::tenant_id.eq(tenancy::current()), task_statuses::created_at.eq(now), task_statuses::updated_at.eq(now)))
+            .execute(conn).await?;
+        let task_status = task_statuses::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
         Ok(task_status)
     }
 
-    fn read(conn: &mut SqliteConnection, id: i32) -> anyhow::Result<Option<TaskStatus>> {
-        let task_status = task_statuses::table.find(id).first(conn).optional()?;
+    async fn read(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<TaskStatus>> {
+        let task_status = task_statuses::table.find(id).filter(task_statuses::tenant_id.eq(tenancy::current())).filter(task_statuses::deleted_at.is_null()).first(conn).await.optional()?;
         Ok(task_status)
     }
 
-    fn update(conn: &mut SqliteConnection, id: i32, updated_task_status: NewTaskStatus<'a>) -> anyhow::Result<TaskStatus> {
-        diesel::update(task_statuses::table.find(id))
-            .set(task_statuses::status_name.eq(updated_task_status.status_name))
-            .execute(conn)?;
-        let task_status = task_statuses::table.find(id).first(conn)?;
+    async fn update_versioned(conn: &mut DbConnection, id: i32, expected_version: Option<i32>, updated_task_status: NewTaskStatus<'a>) -> anyhow::Result<TaskStatus> {
+        let count = diesel::update(task_statuses::table.find(id).filter(task_statuses::tenant_id.eq(tenancy::current())).filter(task_statuses::deleted_at.is_null()).filter(task_statuses::version.eq(versioning::expected_or(expected_version, task_statuses::version))))
+            .set((task_statuses::status_name.eq(updated_task_status.status_name), task_statuses::updated_at.eq(chrono::Utc::now().naive_utc()), task_statuses::version.eq(task_statuses::version + 1)))
+            .execute(conn).await?;
+        if count == 0 {
+            return Err(versioning::missed(TaskStatus::read(conn, id).await?, expected_version));
+        }
+        let task_status = task_statuses::table.find(id).filter(task_statuses::tenant_id.eq(tenancy::current())).filter(task_statuses::deleted_at.is_null()).first(conn).await?;
         Ok(task_status)
     }
 
-    fn delete(conn: &mut SqliteConnection, id: i32) -> anyhow::Result<usize> {
-        let count = diesel::delete(task_statuses::table.find(id)).execute(conn)?;
+    async fn delete(conn: &mut DbConnection, id: i32) -> anyhow::Result<usize> {
+        let count = diesel::update(task_statuses::table.find(id).filter(task_statuses::tenant_id.eq(tenancy::current())).filter(task_statuses::deleted_at.is_null()))
+            .set(task_statuses::deleted_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(conn).await?;
         Ok(count)
     }
 
-    fn read_all(conn: &mut SqliteConnection) -> anyhow::Result<Vec<TaskStatus>> {
-        let results = task_statuses::table.load::<TaskStatus>(conn)?;
+    async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<TaskStatus>> {
+        let results = task_statuses::table.filter(task_statuses::tenant_id.eq(tenancy::current())).filter(task_statuses::deleted_at.is_null()).load::<TaskStatus>(conn).await?;
         Ok(results)
     }
+
+    async fn read_page(conn: &mut DbConnection, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<TaskStatus>> {
+        let total = Self::count(conn).await?;
+        let items = sorted_task_statuses(sort)?
+            .filter(task_statuses::deleted_at.is_null())
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<TaskStatus>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    async fn count(conn: &mut DbConnection) -> anyhow::Result<i64> {
+        let count = task_statuses::table.filter(task_statuses::tenant_id.eq(tenancy::current())).filter(task_statuses::deleted_at.is_null()).count().get_result(conn).await?;
+        Ok(count)
+    }
 }
 
 
-impl CrudOperations<SqliteConnection, (i32, i32), NewUserTask, UserTask> for UserTask {
-    fn create(conn: &mut SqliteConnection, new_user_task: NewUserTask) -> anyhow::Result<UserTask> {
-        let user_task = diesel::insert_into(user_tasks::table)
-            .values(&new_user_task)
-            .returning(UserTask::as_returning())
-            .get_result(conn)?;
-        Ok(user_task)
+impl<'a> CrudOperations<DbConnection, i32, NewTag<'a>, Tag> for Tag {
+    async fn create(conn: &mut DbConnection, new_tag: NewTag<'a>) -> anyhow::Result<Tag> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(tags::table)
+            .values((&new_tag, tags::tenant_id.eq(tenancy::current()), tags::created_at.eq(now), tags::updated_at.eq(now)))
+            .execute(conn).await?;
+        let tag = tags::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        Ok(tag)
+    }
+
+    async fn read(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<Tag>> {
+        let tag = tags::table.find(id).filter(tags::tenant_id.eq(tenancy::current())).first(conn).await.optional()?;
+        Ok(tag)
+    }
+
+    async fn update_versioned(conn: &mut DbConnection, id: i32, expected_version: Option<i32>, updated_tag: NewTag<'a>) -> anyhow::Result<Tag> {
+        let count = diesel::update(tags::table.find(id).filter(tags::tenant_id.eq(tenancy::current())).filter(tags::version.eq(versioning::expected_or(expected_version, tags::version))))
+            .set((tags::tag_name.eq(updated_tag.tag_name), tags::updated_at.eq(chrono::Utc::now().naive_utc()), tags::version.eq(tags::version + 1)))
+            .execute(conn).await?;
+        if count == 0 {
+            return Err(versioning::missed(Tag::read(conn, id).await?, expected_version));
+        }
+        let tag = tags::table.find(id).first(conn).await?;
+        Ok(tag)
+    }
+
+    // Tags aren't soft-deleted: the tag is removed from every task and then dropped.
+    async fn delete(conn: &mut DbConnection, id: i32) -> anyhow::Result<usize> {
+        conn.transaction(|conn| async move {
+            // the references go first, or the foreign keys would refuse the delete
+            diesel::delete(task_tags::table.filter(task_tags::tag_id.eq(id)).filter(task_tags::task_id.eq_any(tenancy::task_ids()))).execute(conn).await?;
+            let count = diesel::delete(tags::table.find(id).filter(tags::tenant_id.eq(tenancy::current()))).execute(conn).await?;
+            Ok(count)
+        }.scope_boxed()).await
+    }
+
+    async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<Tag>> {
+        let results = tags::table.filter(tags::tenant_id.eq(tenancy::current())).load::<Tag>(conn).await?;
+        Ok(results)
+    }
+
+    async fn read_page(conn: &mut DbConnection, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<Tag>> {
+        let total = Self::count(conn).await?;
+        let items = sorted_tags(sort)?
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<Tag>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    async fn count(conn: &mut DbConnection) -> anyhow::Result<i64> {
+        let count = tags::table.filter(tags::tenant_id.eq(tenancy::current())).count().get_result(conn).await?;
+        Ok(count)
+    }
+}
+
+
+impl<'a> CrudOperations<DbConnection, i32, NewProject<'a>, Project> for Project {
+    async fn create(conn: &mut DbConnection, new_project: NewProject<'a>) -> anyhow::Result<Project> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(projects::table)
+            .values((&new_project, projects::tenant_id.eq(tenancy::current()), projects::created_at.eq(now), projects::updated_at.eq(now)))
+            .execute(conn).await?;
+        let project = projects::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        Ok(project)
+    }
+
+    async fn read(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<Project>> {
+        let project = projects::table.find(id).filter(projects::tenant_id.eq(tenancy::current())).first(conn).await.optional()?;
+        Ok(project)
+    }
+
+    async fn update_versioned(conn: &mut DbConnection, id: i32, expected_version: Option<i32>, updated_project: NewProject<'a>) -> anyhow::Result<Project> {
+        let count = diesel::update(projects::table.find(id).filter(projects::tenant_id.eq(tenancy::current())).filter(projects::version.eq(versioning::expected_or(expected_version, projects::version))))
+            .set((projects::project_name.eq(updated_project.project_name), projects::description.eq(updated_project.description), projects::team_id.eq(updated_project.team_id), projects::updated_at.eq(chrono::Utc::now().naive_utc()), projects::version.eq(projects::version + 1)))
+            .execute(conn).await?;
+        if count == 0 {
+            return Err(versioning::missed(Project::read(conn, id).await?, expected_version));
+        }
+        let project = projects::table.find(id).first(conn).await?;
+        Ok(project)
+    }
+
+    // Projects aren't soft-deleted: their tasks (trashed ones included) are left without a
+    // project and then the project is dropped, along with its Slack integration.
+    async fn delete(conn: &mut DbConnection, id: i32) -> anyhow::Result<usize> {
+        conn.transaction(|conn| async move {
+            // the references go first, or the foreign keys would refuse the delete
+            diesel::delete(slack_integrations::table.find(id).filter(slack_integrations::project_id.eq_any(tenancy::project_ids()))).execute(conn).await?;
+            diesel::update(tasks::table.filter(tasks::project_id.eq(id)).filter(tasks::tenant_id.eq(tenancy::current())))
+                .set(tasks::project_id.eq(None::<i32>))
+                .execute(conn).await?;
+            let count = diesel::delete(projects::table.find(id).filter(projects::tenant_id.eq(tenancy::current()))).execute(conn).await?;
+            Ok(count)
+        }.scope_boxed()).await
+    }
+
+    async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<Project>> {
+        let results = projects::table.filter(projects::tenant_id.eq(tenancy::current())).load::<Project>(conn).await?;
+        Ok(results)
+    }
+
+    async fn read_page(conn: &mut DbConnection, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<Project>> {
+        let total = Self::count(conn).await?;
+        let items = sorted_projects(sort)?
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<Project>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    async fn count(conn: &mut DbConnection) -> anyhow::Result<i64> {
+        let count = projects::table.filter(projects::tenant_id.eq(tenancy::current())).count().get_result(conn).await?;
+        Ok(count)
+    }
+}
+
+impl<'a> CrudOperations<DbConnection, i32, NewTeam<'a>, Team> for Team {
+    async fn create(conn: &mut DbConnection, new_team: NewTeam<'a>) -> anyhow::Result<Team> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(teams::table)
+            .values((&new_team, teams::tenant_id.eq(tenancy::current()), teams::created_at.eq(now), teams::updated_at.eq(now)))
+            .execute(conn).await?;
+        let team = teams::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        Ok(team)
+    }
+
+    async fn read(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<Team>> {
+        let team = teams::table.find(id).filter(teams::tenant_id.eq(tenancy::current())).first(conn).await.optional()?;
+        Ok(team)
+    }
+
+    async fn update_versioned(conn: &mut DbConnection, id: i32, expected_version: Option<i32>, updated_team: NewTeam<'a>) -> anyhow::Result<Team> {
+        let count = diesel::update(teams::table.find(id).filter(teams::tenant_id.eq(tenancy::current())).filter(teams::version.eq(versioning::expected_or(expected_version, teams::version))))
+            .set((teams::team_name.eq(updated_team.team_name), teams::updated_at.eq(chrono::Utc::now().naive_utc()), teams::version.eq(teams::version + 1)))
+            .execute(conn).await?;
+        if count == 0 {
+            return Err(versioning::missed(Team::read(conn, id).await?, expected_version));
+        }
+        let team = teams::table.find(id).first(conn).await?;
+        Ok(team)
+    }
+
+    // Teams aren't soft-deleted: the memberships go, projects the team owned are opened up to
+    // everyone, and then the team is dropped.
+    async fn delete(conn: &mut DbConnection, id: i32) -> anyhow::Result<usize> {
+        conn.transaction(|conn| async move {
+            // the references go first, or the foreign keys would refuse the delete
+            let team = teams::table.find(id).filter(teams::tenant_id.eq(tenancy::current()));
+            diesel::delete(team_members::table.filter(team_members::team_id.eq_any(team.select(teams::team_id)))).execute(conn).await?;
+            diesel::update(projects::table.filter(projects::team_id.eq(id)).filter(projects::tenant_id.eq(tenancy::current())))
+                .set(projects::team_id.eq(None::<i32>))
+                .execute(conn).await?;
+            let count = diesel::delete(team).execute(conn).await?;
+            Ok(count)
+        }.scope_boxed()).await
+    }
+
+    async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<Team>> {
+        let results = teams::table.filter(teams::tenant_id.eq(tenancy::current())).load::<Team>(conn).await?;
+        Ok(results)
+    }
+
+    async fn read_page(conn: &mut DbConnection, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<Team>> {
+        let total = Self::count(conn).await?;
+        let items = sorted_teams(sort)?
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<Team>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    async fn count(conn: &mut DbConnection) -> anyhow::Result<i64> {
+        let count = teams::table.filter(teams::tenant_id.eq(tenancy::current())).count().get_result(conn).await?;
+        Ok(count)
+    }
+}
+
+impl<'a> CrudOperations<DbConnection, i32, NewComment<'a>, Comment> for Comment {
+    async fn create(conn: &mut DbConnection, new_comment: NewComment<'a>) -> anyhow::Result<Comment> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(comments::table)
+            .values((&new_comment, comments::created_at.eq(now), comments::updated_at.eq(now)))
+            .execute(conn).await?;
+        let comment = comments::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        mentions::sync(conn, &comment).await?;
+        Ok(comment)
+    }
+
+    async fn read(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<Comment>> {
+        let comment = comments::table.find(id).filter(comments::task_id.eq_any(tenancy::task_ids())).first(conn).await.optional()?;
+        Ok(comment)
+    }
+
+    async fn update_versioned(conn: &mut DbConnection, id: i32, expected_version: Option<i32>, updated_comment: NewComment<'a>) -> anyhow::Result<Comment> {
+        let count = diesel::update(comments::table.find(id).filter(comments::task_id.eq_any(tenancy::task_ids())).filter(comments::version.eq(versioning::expected_or(expected_version, comments::version))))
+            .set((comments::body.eq(updated_comment.body), comments::updated_at.eq(chrono::Utc::now().naive_utc()), comments::version.eq(comments::version + 1)))
+            .execute(conn).await?;
+        if count == 0 {
+            return Err(versioning::missed(Comment::read(conn, id).await?, expected_version));
+        }
+        let comment = comments::table.find(id).first(conn).await?;
+        mentions::sync(conn, &comment).await?;
+        Ok(comment)
+    }
+
+    // Takes the comment's edit history and mentions with it.
+    async fn delete(conn: &mut DbConnection, id: i32) -> anyhow::Result<usize> {
+        conn.transaction(|conn| async move {
+            let count = diesel::delete(comments::table.find(id).filter(comments::task_id.eq_any(tenancy::task_ids()))).execute(conn).await?;
+            if count > 0 {
+                diesel::delete(comment_revisions::table.filter(comment_revisions::comment_id.eq(id))).execute(conn).await?;
+                mentions::clear(conn, id).await?;
+            }
+            Ok(count)
+        }.scope_boxed()).await
+    }
+
+    async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<Comment>> {
+        let results = comments::table.filter(comments::task_id.eq_any(tenancy::task_ids())).load::<Comment>(conn).await?;
+        Ok(results)
+    }
+
+    async fn read_page(conn: &mut DbConnection, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<Comment>> {
+        let total = Self::count(conn).await?;
+        let items = sorted_comments(sort)?
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<Comment>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    async fn count(conn: &mut DbConnection) -> anyhow::Result<i64> {
+        let count = comments::table.filter(comments::task_id.eq_any(tenancy::task_ids())).count().get_result(conn).await?;
+        Ok(count)
+    }
+}
+
+
+impl<'a> CrudOperations<DbConnection, i32, NewTaskTemplate<'a>, TaskTemplate> for TaskTemplate {
+    async fn create(conn: &mut DbConnection, new_template: NewTaskTemplate<'a>) -> anyhow::Result<TaskTemplate> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(task_templates::table)
+            .values((&new_template, task_templates::tenant_id.eq(tenancy::current()), task_templates::created_at.eq(now), task_templates::updated_at.eq(now)))
+            .execute(conn).await?;
+        let template = task_templates::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        Ok(template)
+    }
+
+    async fn read(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<TaskTemplate>> {
+        let template = task_templates::table.find(id).filter(task_templates::tenant_id.eq(tenancy::current())).first(conn).await.optional()?;
+        Ok(template)
+    }
+
+    async fn update_versioned(conn: &mut DbConnection, id: i32, expected_version: Option<i32>, updated_template: NewTaskTemplate<'a>) -> anyhow::Result<TaskTemplate> {
+        let count = diesel::update(task_templates::table.find(id).filter(task_templates::tenant_id.eq(tenancy::current())).filter(task_templates::version.eq(versioning::expected_or(expected_version, task_templates::version))))
+            .set((
+                task_templates::template_name.eq(updated_template.template_name),
+                task_templates::task_name.eq(updated_template.task_name),
+                task_templates::priority.eq(updated_template.priority),
+                task_templates::due_in_days.eq(updated_template.due_in_days),
+                task_templates::recurrence.eq(updated_template.recurrence),
+                task_templates::assignee_ids_json.eq(updated_template.assignee_ids_json),
+                task_templates::subtask_names_json.eq(updated_template.subtask_names_json),
+                task_templates::updated_at.eq(chrono::Utc::now().naive_utc()),
+                task_templates::version.eq(task_templates::version + 1),
+            ))
+            .execute(conn).await?;
+        if count == 0 {
+            return Err(versioning::missed(TaskTemplate::read(conn, id).await?, expected_version));
+        }
+        let template = task_templates::table.find(id).first(conn).await?;
+        Ok(template)
     }
 
-    fn read(conn: &mut SqliteConnection, id: (i32, i32)) -> anyhow::Result<Option<UserTask>> {
+    // Templates aren't soft-deleted; tasks already made from one are unaffected.
+    async fn delete(conn: &mut DbConnection, id: i32) -> anyhow::Result<usize> {
+        let count = diesel::delete(task_templates::table.find(id).filter(task_templates::tenant_id.eq(tenancy::current()))).execute(conn).await?;
+        Ok(count)
+    }
+
+    async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<TaskTemplate>> {
+        let results = task_templates::table.filter(task_templates::tenant_id.eq(tenancy::current())).load::<TaskTemplate>(conn).await?;
+        Ok(results)
+    }
+
+    async fn read_page(conn: &mut DbConnection, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<TaskTemplate>> {
+        let total = Self::count(conn).await?;
+        let items = sorted_task_templates(sort)?
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<TaskTemplate>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    async fn count(conn: &mut DbConnection) -> anyhow::Result<i64> {
+        let count = task_templates::table.filter(task_templates::tenant_id.eq(tenancy::current())).count().get_result(conn).await?;
+        Ok(count)
+    }
+}
+
+impl CrudOperations<DbConnection, (i32, i32), NewUserTask, UserTask> for UserTask {
+    // A trashed row still holds the (user_id, task_id) key, so creating the pair again replaces it.
+    async fn create(conn: &mut DbConnection, new_user_task: NewUserTask) -> anyhow::Result<UserTask> {
+        conn.transaction(|conn| async move {
+            diesel::delete(user_tasks::table
+                .filter(user_tasks::tenant_id.eq(tenancy::current()))
+                .filter(user_tasks::user_id.eq(new_user_task.user_id))
+                .filter(user_tasks::task_id.eq(new_user_task.task_id))
+                .filter(user_tasks::deleted_at.is_not_null()))
+                .execute(conn).await?;
+            let now = chrono::Utc::now().naive_utc();
+            diesel::insert_into(user_tasks::table)
+                .values((&new_user_task, user_tasks::tenant_id.eq(tenancy::current()), user_tasks::created_at.eq(now), user_tasks::updated_at.eq(now)))
+                .execute(conn).await?;
+            let user_task = user_tasks::table.find((new_user_task.user_id, new_user_task.task_id)).first(conn).await?;
+            Ok(user_task)
+        }.scope_boxed()).await
+    }
+
+    async fn read(conn: &mut DbConnection, id: (i32, i32)) -> anyhow::Result<Option<UserTask>> {
         let user_task = user_tasks::table
+            .filter(user_tasks::tenant_id.eq(tenancy::current()))
             .filter(user_tasks::user_id.eq(id.0))
             .filter(user_tasks::task_id.eq(id.1))
-            .first(conn)
+            .filter(user_tasks::deleted_at.is_null())
+            .first(conn).await
             .optional()?;
         Ok(user_task)
     }
 
-    fn update(conn: &mut SqliteConnection, id: (i32, i32), updated_user_task: NewUserTask) -> anyhow::Result<UserTask> {
-        diesel::update(user_tasks::table
+    async fn update_versioned(conn: &mut DbConnection, id: (i32, i32), expected_version: Option<i32>, updated_user_task: NewUserTask) -> anyhow::Result<UserTask> {
+        let count = diesel::update(user_tasks::table
+            .filter(user_tasks::tenant_id.eq(tenancy::current()))
             .filter(user_tasks::user_id.eq(id.0))
-            .filter(user_tasks::task_id.eq(id.1)))
-            .set(user_tasks::task_status_id.eq(updated_user_task.task_status_id))
-            .execute(conn)?;
+            .filter(user_tasks::task_id.eq(id.1))
+            .filter(user_tasks::deleted_at.is_null())
+            .filter(user_tasks::version.eq(versioning::expected_or(expected_version, user_tasks::version))))
+            .set((user_tasks::task_status_id.eq(updated_user_task.task_status_id), user_tasks::updated_at.eq(chrono::Utc::now().naive_utc()), user_tasks::version.eq(user_tasks::version + 1)))
+            .execute(conn).await?;
+        if count == 0 {
+            return Err(versioning::missed(UserTask::read(conn, id).await?, expected_version));
+        }
         let user_task = user_tasks::table
+            .filter(user_tasks::tenant_id.eq(tenancy::current()))
             .filter(user_tasks::user_id.eq(id.0))
             .filter(user_tasks::task_id.eq(id.1))
-            .first(conn)?;
+            .filter(user_tasks::deleted_at.is_null())
+            .first(conn).await?;
         Ok(user_task)
     }
 
-    fn delete(conn: &mut SqliteConnection, id: (i32, i32)) -> anyhow::Result<usize> {
-        let count = diesel::delete(user_tasks::table
+    async fn delete(conn: &mut DbConnection, id: (i32, i32)) -> anyhow::Result<usize> {
+        let count = diesel::update(user_tasks::table
+            .filter(user_tasks::tenant_id.eq(tenancy::current()))
             .filter(user_tasks::user_id.eq(id.0))
-            .filter(user_tasks::task_id.eq(id.1)))
-            .execute(conn)?;
+            .filter(user_tasks::task_id.eq(id.1))
+            .filter(user_tasks::deleted_at.is_null()))
+            .set(user_tasks::deleted_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(conn).await?;
+        Ok(count)
+    }
+
+    async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<UserTask>> {
+        let results = user_tasks::table.filter(user_tasks::tenant_id.eq(tenancy::current())).filter(user_tasks::deleted_at.is_null()).load::<UserTask>(conn).await?;
+        Ok(results)
+    }
+
+    async fn read_page(conn: &mut DbConnection, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<UserTask>> {
+        UserTask::read_page_filtered(conn, &AssignmentFilter::default(), page, per_page, sort).await
+    }
+
+    async fn count(conn: &mut DbConnection) -> anyhow::Result<i64> {
+        UserTask::count_filtered(conn, &AssignmentFilter::default()).await
+    }
+}
+
+
+
+impl User {
+    pub async fn read_page_by_ids(conn: &mut DbConnection, ids: &[i32], page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<User>> {
+        let total = users::table.filter(users::tenant_id.eq(tenancy::current())).filter(users::user_id.eq_any(ids)).count().get_result(conn).await?;
+        let items = sorted_users(sort)?
+            .filter(users::user_id.eq_any(ids.to_vec()))
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<User>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    // How many of `ids` name an existing user, so callers can reject unknown ones up front.
+    pub async fn count_existing(conn: &mut DbConnection, ids: &[i32]) -> anyhow::Result<i64> {
+        let count = users::table.filter(users::tenant_id.eq(tenancy::current())).filter(users::user_id.eq_any(ids)).count().get_result(conn).await?;
+        Ok(count)
+    }
+
+    // The tenant's user with this email. Other tenants may have a user with the same address.
+    pub async fn read_by_email(conn: &mut DbConnection, email: &str) -> anyhow::Result<Option<User>> {
+        let user = users::table
+            .filter(users::tenant_id.eq(tenancy::current()))
+            .filter(users::email.eq(email))
+            .select(User::as_select())
+            .first(conn).await
+            .optional()?;
+        Ok(user)
+    }
+
+    // Every active user with this email, in every tenant, in id order. Not scoped: sign-in
+    // uses it to find whose password was given, and so which tenant to sign in to.
+    pub async fn read_active_by_email_in_any_tenant(conn: &mut DbConnection, email: &str) -> anyhow::Result<Vec<User>> {
+        let users = users::table
+            .filter(users::email.eq(email))
+            .filter(users::active.eq(true))
+            .order(users::user_id)
+            .select(User::as_select())
+            .load(conn).await?;
+        Ok(users)
+    }
+
+    // Only reads the role and tenant so auth guards don't need the whole row. Not scoped, since
+    // this is what tells the guard which tenant to enter.
+    pub async fn read_access(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<(UserRole, i32)>> {
+        let access = users::table
+            .find(id)
+            .filter(users::active.eq(true))
+            .select((users::role_id, users::tenant_id))
+            .first::<(UserRole, i32)>(conn).await
+            .optional()?;
+        Ok(access)
+    }
+
+    // Makes the default tenant's user with this email an admin, unless they already are.
+    // Returns None when there's no such user. The connection must be in the default tenant.
+    pub async fn bootstrap_admin(conn: &mut DbConnection, email: &str) -> anyhow::Result<Option<User>> {
+        let Some(user) = User::read_by_email(conn, email).await? else {
+            return Ok(None);
+        };
+        if user.role_id == UserRole::Admin as i32 {
+            return Ok(Some(user));
+        }
+        User::set_role(conn, None, user.user_id, None, UserRole::Admin).await.map(Some)
+    }
+
+    pub async fn set_role(conn: &mut DbConnection, actor: Option<i32>, id: i32, expected_version: Option<i32>, role: UserRole) -> anyhow::Result<User> {
+        conn.transaction(|conn| async move {
+            let before = User::read(conn, id).await?;
+            let count = diesel::update(users::table.find(id).filter(users::tenant_id.eq(tenancy::current())).filter(users::version.eq(versioning::expected_or(expected_version, users::version))))
+                .set((users::role_id.eq(role), users::updated_at.eq(chrono::Utc::now().naive_utc()), users::version.eq(users::version + 1)))
+                .execute(conn).await?;
+            if count == 0 {
+                return Err(versioning::missed(before, expected_version));
+            }
+            let user = users::table.find(id).filter(users::tenant_id.eq(tenancy::current())).first(conn).await?;
+            audit::record(conn, actor, AuditAction::Update, before.as_ref(), Some(&user)).await?;
+            Ok(user)
+        }.scope_boxed()).await
+    }
+}
+
+impl Credential {
+    pub async fn read(conn: &mut DbConnection, user_id: i32) -> anyhow::Result<Option<Credential>> {
+        let credential = credentials::table.find(user_id).first(conn).await.optional()?;
+        Ok(credential)
+    }
+
+    // Inserts the user and their password hash together so a failed insert never leaves
+    // a user without credentials.
+    pub async fn register(conn: &mut DbConnection, new_user: NewUser<'_>, password_hash: &str) -> anyhow::Result<User> {
+        conn.transaction(|conn| async move {
+            let user = User::create(conn, new_user).await?;
+            audit::record(conn, Some(user.user_id), AuditAction::Create, None, Some(&user)).await?;
+            diesel::insert_into(credentials::table)
+                .values(&NewCredential { user_id: user.user_id, password_hash })
+                .execute(conn).await?;
+            Ok(user)
+        }.scope_boxed()).await
+    }
+
+    // Sets or replaces the user's password and signs them out of every session. Returns None
+    // when the user isn't in the connection's tenant.
+    pub async fn set_password(conn: &mut DbConnection, user_id: i32, password_hash: &str) -> anyhow::Result<Option<User>> {
+        conn.transaction(|conn| async move {
+            let Some(user) = User::read(conn, user_id).await? else {
+                return Ok(None);
+            };
+            diesel::insert_into(credentials::table)
+                .values(&NewCredential { user_id, password_hash })
+                .on_conflict(conflict_target!(credentials::user_id))
+                .do_update()
+                .set(credentials::password_hash.eq(password_hash))
+                .execute(conn).await?;
+            RefreshToken::revoke_all_for_user(conn, user_id).await?;
+            Ok(Some(user))
+        }.scope_boxed()).await
+    }
+
+    // Sets up a new tenant with the registrant as its first admin. The connection is left in
+    // the new tenant.
+    pub async fn register_organization(conn: &mut DbConnection, tenant_name: &str, new_user: NewUser<'_>, password_hash: &str) -> anyhow::Result<(Tenant, User)> {
+        conn.transaction(|conn| async move {
+            let tenant = Tenant::create(conn, tenant_name).await?;
+            tenancy::enter(conn, tenant.tenant_id).await?;
+            let user = Credential::register(conn, new_user, password_hash).await?;
+            let user = User::set_role(conn, Some(user.user_id), user.user_id, None, UserRole::Admin).await?;
+            Ok((tenant, user))
+        }.scope_boxed()).await
+    }
+}
+
+impl OAuthIdentity {
+    // Finds the local user for a provider account. On first login the account is linked to
+    // the user with the same email in the connection's tenant, or a new active member of that
+    // tenant is created for it; an address shared with another tenant's user never links to
+    // them. The identity lookup isn't scoped, since provider accounts are unique across tenants.
+    pub async fn find_or_link_user(conn: &mut DbConnection, provider: &str, provider_user_id: &str, email: &str, name: &str) -> anyhow::Result<User> {
+        conn.transaction(|conn| async move {
+            let linked_user_id = oauth_identities::table
+                .filter(oauth_identities::provider.eq(provider))
+                .filter(oauth_identities::provider_user_id.eq(provider_user_id))
+                .select(oauth_identities::user_id)
+                .first::<i32>(conn).await
+                .optional()?;
+            if let Some(user_id) = linked_user_id {
+                let user = users::table.find(user_id).first(conn).await?;
+                return Ok(user);
+            }
+            let user = match User::read_by_email(conn, email).await? {
+                Some(user) => user,
+                None => {
+                    let user = User::create(conn, NewUser { name, email, active: true }).await?;
+                    audit::record(conn, Some(user.user_id), AuditAction::Create, None, Some(&user)).await?;
+                    user
+                }
+            };
+            diesel::insert_into(oauth_identities::table)
+                .values(&NewOAuthIdentity { user_id: user.user_id, provider, provider_user_id })
+                .execute(conn).await?;
+            Ok(user)
+        }.scope_boxed()).await
+    }
+}
+
+impl RefreshToken {
+    pub async fn create(conn: &mut DbConnection, new_refresh_token: NewRefreshToken<'_>) -> anyhow::Result<RefreshToken> {
+        diesel::insert_into(refresh_tokens::table)
+            .values(&new_refresh_token)
+            .execute(conn).await?;
+        let refresh_token = refresh_tokens::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        Ok(refresh_token)
+    }
+
+    // Single-use: the presented token is revoked and replaced in one transaction.
+    // Returns None when the token is unknown, expired, or already used.
+    pub async fn rotate(conn: &mut DbConnection, old_hash: &str, replacement: NewRefreshToken<'_>, now: chrono::NaiveDateTime) -> anyhow::Result<Option<RefreshToken>> {
+        conn.transaction(|conn| async move {
+            let revoked = diesel::update(refresh_tokens::table
+                .filter(refresh_tokens::token_hash.eq(old_hash))
+                .filter(refresh_tokens::user_id.eq(replacement.user_id))
+                .filter(refresh_tokens::revoked.eq(false))
+                .filter(refresh_tokens::expires_at.gt(now)))
+                .set(refresh_tokens::revoked.eq(true))
+                .execute(conn).await?;
+            if revoked == 0 {
+                return Ok(None);
+            }
+            RefreshToken::create(conn, replacement).await.map(Some)
+        }.scope_boxed()).await
+    }
+
+    pub async fn find_active_by_hash(conn: &mut DbConnection, token_hash: &str, now: chrono::NaiveDateTime) -> anyhow::Result<Option<RefreshToken>> {
+        let refresh_token = refresh_tokens::table
+            .filter(refresh_tokens::token_hash.eq(token_hash))
+            .filter(refresh_tokens::revoked.eq(false))
+            .filter(refresh_tokens::expires_at.gt(now))
+            .first(conn).await
+            .optional()?;
+        Ok(refresh_token)
+    }
+
+    pub async fn revoke_by_hash(conn: &mut DbConnection, token_hash: &str, user_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::update(refresh_tokens::table
+            .filter(refresh_tokens::token_hash.eq(token_hash))
+            .filter(refresh_tokens::user_id.eq(user_id)))
+            .set(refresh_tokens::revoked.eq(true))
+            .execute(conn).await?;
+        Ok(count)
+    }
+
+    pub async fn revoke_all_for_user(conn: &mut DbConnection, user_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::update(refresh_tokens::table
+            .filter(refresh_tokens::user_id.eq(user_id))
+            .filter(refresh_tokens::revoked.eq(false)))
+            .set(refresh_tokens::revoked.eq(true))
+            .execute(conn).await?;
+        Ok(count)
+    }
+}
+
+impl RevokedToken {
+    // Also clears out entries whose tokens have expired anyway, so the list stays small.
+    pub async fn revoke(conn: &mut DbConnection, revoked_token: RevokedToken, now: chrono::NaiveDateTime) -> anyhow::Result<()> {
+        diesel::delete(revoked_tokens::table.filter(revoked_tokens::expires_at.lt(now))).execute(conn).await?;
+        diesel::insert_into(revoked_tokens::table)
+            .values(&revoked_token)
+            .on_conflict_do_nothing()
+            .execute(conn).await?;
+        Ok(())
+    }
+
+    pub async fn is_revoked(conn: &mut DbConnection, jti: &str) -> anyhow::Result<bool> {
+        let count: i64 = revoked_tokens::table
+            .filter(revoked_tokens::jti.eq(jti))
+            .count()
+            .get_result(conn).await?;
+        Ok(count > 0)
+    }
+}
+
+// Batch reads for ?ids=1,2,3: the same paging and sorting as read_page, limited to those ids.
+impl Task {
+    pub async fn read_page_by_ids(conn: &mut DbConnection, ids: &[i32], page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<Task>> {
+        let filter = TaskFilter { ids: Some(ids.to_vec()), ..TaskFilter::default() };
+        Task::read_page_filtered(conn, &filter, page, per_page, sort).await
+    }
+
+    fn filter_query(mut query: tasks::BoxedQuery<'static, DbBackend>, filter: &TaskFilter) -> tasks::BoxedQuery<'static, DbBackend> {
+        query = query.filter(tasks::tenant_id.eq(tenancy::current())).filter(tasks::deleted_at.is_null());
+        if let Some(ids) = &filter.ids {
+            query = query.filter(tasks::task_id.eq_any(ids.clone()));
+        }
+        if let Some(due_before) = filter.due_before {
+            query = query.filter(tasks::due_date.lt(due_before));
+        }
+        if let Some(due_after) = filter.due_after {
+            query = query.filter(tasks::due_date.gt(due_after));
+        }
+        if let Some(priority) = filter.priority {
+            query = query.filter(tasks::priority.eq(priority));
+        }
+        if let Some(project_id) = filter.project_id {
+            query = query.filter(tasks::project_id.eq(project_id));
+        }
+        if let Some(tag) = &filter.tag {
+            query = query.filter(tasks::task_id.eq_any(task_tags::table
+                .inner_join(tags::table)
+                .filter(lower(tags::tag_name).eq(lower(tag.clone())))
+                .select(task_tags::task_id)));
+        }
+        if let Some(user_id) = filter.starred_by {
+            query = query.filter(tasks::task_id.eq_any(starred_tasks::table
+                .filter(starred_tasks::user_id.eq(user_id))
+                .select(starred_tasks::task_id)));
+        }
+        query
+    }
+
+    pub async fn read_page_filtered(conn: &mut DbConnection, filter: &TaskFilter, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<Task>> {
+        let total = Self::filter_query(tasks::table.into_boxed(), filter).count().get_result(conn).await?;
+        let items = Self::filter_query(sorted_tasks(sort)?, filter)
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<Task>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    // Takes the task out of the trash along with the assignments that were deleted with it.
+    // Returns None when there is no deleted task with this id.
+    pub async fn restore(conn: &mut DbConnection, actor: Option<i32>, id: i32) -> anyhow::Result<Option<Task>> {
+        conn.transaction(|conn| async move {
+            let trashed = tasks::table.find(id)
+                .filter(tasks::tenant_id.eq(tenancy::current()))
+                .filter(tasks::deleted_at.is_not_null())
+                .first::<Task>(conn).await
+                .optional()?;
+            let Some(trashed) = trashed else {
+                return Ok(None);
+            };
+            diesel::update(tasks::table.find(id))
+                .set(tasks::deleted_at.eq(None::<chrono::NaiveDateTime>))
+                .execute(conn).await?;
+            diesel::update(user_tasks::table
+                .filter(user_tasks::task_id.eq(id))
+                .filter(user_tasks::deleted_at.eq(trashed.deleted_at)))
+                .set(user_tasks::deleted_at.eq(None::<chrono::NaiveDateTime>))
+                .execute(conn).await?;
+            let task = tasks::table.find(id).first(conn).await?;
+            search::index_task(conn, &task).await?;
+            audit::record(conn, actor, AuditAction::Restore, Some(&trashed), Some(&task)).await?;
+            Ok(Some(task))
+        }.scope_boxed()).await
+    }
+
+    // Trash: rows soft-deleted since `since`, newest first.
+    pub async fn read_deleted(conn: &mut DbConnection, since: chrono::NaiveDateTime) -> anyhow::Result<Vec<Task>> {
+        let results = tasks::table
+            .filter(tasks::tenant_id.eq(tenancy::current()))
+            .filter(tasks::deleted_at.ge(since))
+            .order((tasks::deleted_at.desc(), tasks::task_id))
+            .load::<Task>(conn).await?;
+        Ok(results)
+    }
+
+    // Live tasks whose parent is `task_id`, in id order.
+    pub async fn read_subtasks(conn: &mut DbConnection, task_id: i32) -> anyhow::Result<Vec<Task>> {
+        let results = tasks::table
+            .filter(tasks::tenant_id.eq(tenancy::current()))
+            .filter(tasks::parent_task_id.eq(task_id))
+            .filter(tasks::deleted_at.is_null())
+            .order(tasks::task_id)
+            .load::<Task>(conn).await?;
+        Ok(results)
+    }
+
+    // How many of the task's live subtasks are complete: they have at least one live
+    // assignment, and every live assignment is in one of `terminal_statuses` (matched by name).
+    pub async fn count_completed_subtasks(conn: &mut DbConnection, task_id: i32, terminal_statuses: &[String]) -> anyhow::Result<i64> {
+        let count = tasks::table
+            .filter(tasks::tenant_id.eq(tenancy::current()))
+            .filter(tasks::parent_task_id.eq(task_id))
+            .filter(tasks::deleted_at.is_null())
+            .filter(task_is_complete(terminal_statuses))
+            .count()
+            .get_result(conn).await?;
+        Ok(count)
+    }
+
+    // True if making `parent_id` the parent of `task_id` would loop back to `task_id`, i.e.
+    // the would-be parent is the task itself or one of its descendants. Trashed tasks still
+    // count, so restoring one can't close a loop either.
+    pub async fn would_create_cycle(conn: &mut DbConnection, task_id: i32, parent_id: i32) -> anyhow::Result<bool> {
+        let mut current = Some(parent_id);
+        let mut seen = Vec::new();
+        while let Some(id) = current {
+            if id == task_id || seen.contains(&id) {
+                return Ok(true);
+            }
+            seen.push(id);
+            current = tasks::table.find(id).filter(tasks::tenant_id.eq(tenancy::current())).select(tasks::parent_task_id).first::<Option<i32>>(conn).await.optional()?.flatten();
+        }
+        Ok(false)
+    }
+
+    // Audited like any other update, so pausing shows up in the task's history and audit log.
+    // None if there's no such live task.
+    pub async fn set_recurrence_paused(conn: &mut DbConnection, actor: Option<i32>, id: i32, paused: bool) -> anyhow::Result<Option<Task>> {
+        conn.transaction(|conn| async move {
+            let Some(before) = Task::read(conn, id).await? else {
+                return Ok(None);
+            };
+            diesel::update(tasks::table.find(id))
+                .set((tasks::recurrence_paused.eq(paused), tasks::updated_at.eq(chrono::Utc::now().naive_utc()), tasks::version.eq(tasks::version + 1)))
+                .execute(conn).await?;
+            let task = tasks::table.find(id).first(conn).await?;
+            audit::record(conn, actor, AuditAction::Update, Some(&before), Some(&task)).await?;
+            Ok(Some(task))
+        }.scope_boxed()).await
+    }
+
+    // Copies a live task, its tags and all of its live subtasks (recursively), in one
+    // transaction. The copy sits under the same parent as the original. With `with_assignments`
+    // the same users are assigned to each copy, starting again in the first status by id.
+    // Dependencies, comments and attachments are not copied. None if there's no such live task.
+    pub async fn duplicate(conn: &mut DbConnection, actor: Option<i32>, id: i32, with_assignments: bool) -> anyhow::Result<Option<Task>> {
+        conn.transaction(|conn| async move {
+            let Some(source) = Task::read(conn, id).await? else {
+                return Ok(None);
+            };
+            let initial_status = if with_assignments { TaskStatus::initial_id(conn).await? } else { None };
+            let parent_task_id = source.parent_task_id;
+            Ok(Some(duplicate_tree(conn, actor, source, parent_task_id, initial_status).await?))
+        }.scope_boxed()).await
+    }
+
+    // Creates each task with its assignments, given as (user_id, task_status_id), in one
+    // transaction. Each task is its own savepoint, so one that fails (an assignee already
+    // removed, say) is rolled back on its own and the rest still commit together.
+    pub async fn create_many_with_assignments(conn: &mut DbConnection, actor: Option<i32>, items: Vec<(NewTask<'_>, Vec<(i32, i32)>)>) -> anyhow::Result<Vec<anyhow::Result<TaskWithAssignments>>> {
+        each_in_savepoint(conn, items, move |conn, (new_task, assignees)| async move {
+            let task = Task::create_audited(conn, actor, new_task).await?;
+            let mut user_tasks = Vec::with_capacity(assignees.len());
+            for (user_id, task_status_id) in assignees {
+                user_tasks.push(UserTask::create_audited(conn, actor, NewUserTask { user_id, task_id: task.task_id, task_status_id }).await?);
+            }
+            Ok((task, user_tasks))
+        }.scope_boxed()).await
+    }
+
+    // Permanently removes tasks trashed before `before`. A task whose assignments were
+    // restored on their own is still referenced, so it stays until they are gone. Its tags,
+    // watchers, stars, dependencies, comments and their mentions are dropped along with it,
+    // and its subtasks become top-level. Returns how many tasks went, and the rows of their
+    // attachments, which go too, so the caller can remove the stored files.
+    pub async fn purge_deleted(conn: &mut DbConnection, before: chrono::NaiveDateTime) -> anyhow::Result<(usize, Vec<Attachment>)> {
+        let purgeable = tasks::table
+            .filter(tasks::tenant_id.eq(tenancy::current()))
+            .filter(tasks::deleted_at.lt(before))
+            .filter(diesel::dsl::not(diesel::dsl::exists(user_tasks::table.filter(user_tasks::task_id.eq(tasks::task_id)))));
+        conn.transaction(|conn| async move {
+            diesel::delete(task_tags::table.filter(task_tags::task_id.eq_any(purgeable.select(tasks::task_id)))).execute(conn).await?;
+            diesel::delete(task_watchers::table.filter(task_watchers::task_id.eq_any(purgeable.select(tasks::task_id)))).execute(conn).await?;
+            diesel::delete(starred_tasks::table.filter(starred_tasks::task_id.eq_any(purgeable.select(tasks::task_id)))).execute(conn).await?;
+            diesel::delete(due_reminders::table.filter(due_reminders::task_id.eq_any(purgeable.select(tasks::task_id)))).execute(conn).await?;
+            diesel::delete(task_dependencies::table.filter(task_dependencies::blocking_task_id.eq_any(purgeable.select(tasks::task_id)))).execute(conn).await?;
+            diesel::delete(task_dependencies::table.filter(task_dependencies::blocked_task_id.eq_any(purgeable.select(tasks::task_id)))).execute(conn).await?;
+            let purged_comments = comments::table.filter(comments::task_id.eq_any(purgeable.select(tasks::task_id)));
+            diesel::delete(comment_revisions::table.filter(comment_revisions::comment_id.eq_any(purged_comments.select(comments::comment_id)))).execute(conn).await?;
+            diesel::delete(mention_rows::table.filter(mention_rows::comment_id.eq_any(purged_comments.select(comments::comment_id)))).execute(conn).await?;
+            diesel::delete(purged_comments).execute(conn).await?;
+            let purged_attachments = attachments::table.filter(attachments::task_id.eq_any(purgeable.select(tasks::task_id)));
+            let attachments = purged_attachments.order(attachments::attachment_id).load::<Attachment>(conn).await?;
+            diesel::delete(purged_attachments).execute(conn).await?;
+            let purged_ids: Vec<i32> = purgeable.select(tasks::task_id).load(conn).await?;
+            diesel::update(tasks::table.filter(tasks::parent_task_id.eq_any(&purged_ids)))
+                .set(tasks::parent_task_id.eq(None::<i32>))
+                .execute(conn).await?;
+            diesel::update(tasks::table.filter(tasks::next_occurrence_id.eq_any(&purged_ids)))
+                .set(tasks::next_occurrence_id.eq(None::<i32>))
+                .execute(conn).await?;
+            let count = diesel::delete(purgeable).execute(conn).await?;
+            Ok((count, attachments))
+        }.scope_boxed()).await
+    }
+}
+
+impl TaskStatus {
+    pub async fn read_page_by_ids(conn: &mut DbConnection, ids: &[i32], page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<TaskStatus>> {
+        let total = task_statuses::table.filter(task_statuses::tenant_id.eq(tenancy::current())).filter(task_statuses::task_status_id.eq_any(ids)).filter(task_statuses::deleted_at.is_null()).count().get_result(conn).await?;
+        let items = sorted_task_statuses(sort)?
+            .filter(task_statuses::task_status_id.eq_any(ids.to_vec()))
+            .filter(task_statuses::deleted_at.is_null())
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<TaskStatus>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    // Where new work starts out: the live status with the lowest id, if there is one.
+    pub async fn initial_id(conn: &mut DbConnection) -> anyhow::Result<Option<i32>> {
+        let id = task_statuses::table
+            .filter(task_statuses::tenant_id.eq(tenancy::current()))
+            .filter(task_statuses::deleted_at.is_null())
+            .order(task_statuses::task_status_id)
+            .select(task_statuses::task_status_id)
+            .first(conn).await
+            .optional()?;
+        Ok(id)
+    }
+
+    // diesel refuses an UPDATE with nothing in the SET clause, so an empty patch just reads the row back.
+    pub async fn update_partial(conn: &mut DbConnection, actor: Option<i32>, id: i32, expected_version: Option<i32>, changes: TaskStatusChanges<'_>) -> anyhow::Result<TaskStatus> {
+        conn.transaction(|conn| async move {
+            let before = TaskStatus::read(conn, id).await?;
+            if changes.status_name.is_some() {
+                let count = diesel::update(task_statuses::table.find(id).filter(task_statuses::tenant_id.eq(tenancy::current())).filter(task_statuses::deleted_at.is_null()).filter(task_statuses::version.eq(versioning::expected_or(expected_version, task_statuses::version))))
+                    .set((&changes, task_statuses::updated_at.eq(chrono::Utc::now().naive_utc()), task_statuses::version.eq(task_statuses::version + 1)))
+                    .execute(conn).await?;
+                if count == 0 {
+                    return Err(versioning::missed(before, expected_version));
+                }
+            } else {
+                versioning::check(before.as_ref(), expected_version)?;
+            }
+            let task_status = task_statuses::table.find(id).filter(task_statuses::tenant_id.eq(tenancy::current())).filter(task_statuses::deleted_at.is_null()).first(conn).await?;
+            audit::record(conn, actor, AuditAction::Update, before.as_ref(), Some(&task_status)).await?;
+            Ok(task_status)
+        }.scope_boxed()).await
+    }
+
+    pub async fn restore(conn: &mut DbConnection, actor: Option<i32>, id: i32) -> anyhow::Result<Option<TaskStatus>> {
+        conn.transaction(|conn| async move {
+            let trashed = task_statuses::table.find(id)
+                .filter(task_statuses::tenant_id.eq(tenancy::current()))
+                .filter(task_statuses::deleted_at.is_not_null())
+                .first::<TaskStatus>(conn).await
+                .optional()?;
+            let Some(trashed) = trashed else {
+                return Ok(None);
+            };
+            diesel::update(task_statuses::table.find(id))
+                .set(task_statuses::deleted_at.eq(None::<chrono::NaiveDateTime>))
+                .execute(conn).await?;
+            let task_status = task_statuses::table.find(id).first(conn).await?;
+            audit::record(conn, actor, AuditAction::Restore, Some(&trashed), Some(&task_status)).await?;
+            Ok(Some(task_status))
+        }.scope_boxed()).await
+    }
+
+    pub async fn read_deleted(conn: &mut DbConnection, since: chrono::NaiveDateTime) -> anyhow::Result<Vec<TaskStatus>> {
+        let results = task_statuses::table
+            .filter(task_statuses::tenant_id.eq(tenancy::current()))
+            .filter(task_statuses::deleted_at.ge(since))
+            .order((task_statuses::deleted_at.desc(), task_statuses::task_status_id))
+            .load::<TaskStatus>(conn).await?;
+        Ok(results)
+    }
+
+    pub async fn purge_deleted(conn: &mut DbConnection, before: chrono::NaiveDateTime) -> anyhow::Result<usize> {
+        let count = diesel::delete(task_statuses::table
+            .filter(task_statuses::tenant_id.eq(tenancy::current()))
+            .filter(task_statuses::deleted_at.lt(before))
+            .filter(diesel::dsl::not(diesel::dsl::exists(user_tasks::table.filter(user_tasks::task_status_id.eq(task_statuses::task_status_id))))))
+            .execute(conn).await?;
+        Ok(count)
+    }
+}
+
+impl Tag {
+    // Ordered by name.
+    pub async fn read_for_task(conn: &mut DbConnection, task_id: i32) -> anyhow::Result<Vec<Tag>> {
+        let results = task_tags::table
+            .inner_join(tags::table)
+            .filter(task_tags::task_id.eq(task_id))
+            .filter(tags::tenant_id.eq(tenancy::current()))
+            .order(tags::tag_name)
+            .select(Tag::as_select())
+            .load(conn).await?;
+        Ok(results)
+    }
+
+    // How many of `ids` name an existing tag, so callers can reject unknown ones up front.
+    pub async fn count_existing(conn: &mut DbConnection, ids: &[i32]) -> anyhow::Result<i64> {
+        let count = tags::table.filter(tags::tenant_id.eq(tenancy::current())).filter(tags::tag_id.eq_any(ids)).count().get_result(conn).await?;
+        Ok(count)
+    }
+
+    // Adds the tags to the task; ones it already has are left alone. Returns the task's tags afterwards.
+    pub async fn attach_to_task(conn: &mut DbConnection, task_id: i32, tag_ids: &[i32]) -> anyhow::Result<Vec<Tag>> {
+        conn.transaction(|conn| async move {
+            let rows: Vec<NewTaskTag> = tag_ids.iter().map(|&tag_id| NewTaskTag { task_id, tag_id }).collect();
+            for row in &rows {
+                diesel::insert_into(task_tags::table).values(row).on_conflict_do_nothing().execute(conn).await?;
+            }
+            Tag::read_for_task(conn, task_id).await
+        }.scope_boxed()).await
+    }
+
+    pub async fn detach_from_task(conn: &mut DbConnection, task_id: i32, tag_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::delete(task_tags::table.find((task_id, tag_id)).filter(task_tags::task_id.eq_any(tenancy::task_ids()))).execute(conn).await?;
         Ok(count)
     }
+}
 
-    fn read_all(conn: &mut SqliteConnection) -> anyhow::Result<Vec<UserTask>> {
-        let results = user_tasks::table.load::<UserTask>(conn)?;
+impl Team {
+    // Ordered by name.
+    pub async fn read_members(conn: &mut DbConnection, team_id: i32) -> anyhow::Result<Vec<User>> {
+        let results = team_members::table
+            .inner_join(users::table)
+            .filter(team_members::team_id.eq(team_id))
+            .filter(users::tenant_id.eq(tenancy::current()))
+            .order((users::name, users::user_id))
+            .select(User::as_select())
+            .load(conn).await?;
         Ok(results)
     }
+
+    // Adds the users to the team; ones already on it are left alone. Returns the members afterwards.
+    pub async fn add_members(conn: &mut DbConnection, team_id: i32, user_ids: &[i32]) -> anyhow::Result<Vec<User>> {
+        conn.transaction(|conn| async move {
+            let rows: Vec<NewTeamMember> = user_ids.iter().map(|&user_id| NewTeamMember { team_id, user_id }).collect();
+            for row in &rows {
+                diesel::insert_into(team_members::table).values(row).on_conflict_do_nothing().execute(conn).await?;
+            }
+            Team::read_members(conn, team_id).await
+        }.scope_boxed()).await
+    }
+
+    // Assignments the user already has in the team's projects are kept.
+    pub async fn remove_member(conn: &mut DbConnection, team_id: i32, user_id: i32) -> anyhow::Result<usize> {
+        let team_ids = teams::table.filter(teams::tenant_id.eq(tenancy::current())).select(teams::team_id);
+        let count = diesel::delete(team_members::table.find((team_id, user_id)).filter(team_members::team_id.eq_any(team_ids))).execute(conn).await?;
+        Ok(count)
+    }
+
+    // The team whose members alone may be assigned to the task: its project's team. None when
+    // the task has no project, or the project has no team, and anyone may be assigned.
+    pub async fn for_task(conn: &mut DbConnection, task_id: i32) -> anyhow::Result<Option<Team>> {
+        let team = tasks::table
+            .inner_join(projects::table.inner_join(teams::table))
+            .filter(tasks::task_id.eq(task_id))
+            .filter(tasks::tenant_id.eq(tenancy::current()))
+            .select(Team::as_select())
+            .first(conn).await
+            .optional()?;
+        Ok(team)
+    }
+
+    pub async fn is_member(conn: &mut DbConnection, team_id: i32, user_id: i32) -> anyhow::Result<bool> {
+        let count: i64 = team_members::table.find((team_id, user_id)).count().get_result(conn).await?;
+        Ok(count > 0)
+    }
+}
+
+impl Comment {
+    // One task's thread; oldest first unless `sort` says otherwise.
+    pub async fn read_page_for_task(conn: &mut DbConnection, task_id: i32, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<Comment>> {
+        let total = comments::table.filter(comments::task_id.eq(task_id)).filter(comments::task_id.eq_any(tenancy::task_ids())).count().get_result(conn).await?;
+        let items = sorted_comments(sort)?
+            .filter(comments::task_id.eq(task_id))
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<Comment>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    // Audited update that first files the current body away as a revision, so the comment's
+    // earlier wording can still be looked up after `actor` edits it.
+    pub async fn edit(conn: &mut DbConnection, actor: Option<i32>, id: i32, expected_version: Option<i32>, body: &str) -> anyhow::Result<Comment> {
+        conn.transaction(|conn| async move {
+            let before = comments::table.find(id).filter(comments::task_id.eq_any(tenancy::task_ids())).first::<Comment>(conn).await?;
+            let revision = NewCommentRevision {
+                comment_id: id,
+                version: before.version,
+                body: &before.body,
+                created_at: before.updated_at,
+                edited_by: actor,
+                edited_at: chrono::Utc::now().naive_utc(),
+            };
+            diesel::insert_into(comment_revisions::table).values(&revision).execute(conn).await?;
+            let comment = Comment::update_versioned(conn, id, expected_version, NewComment { task_id: before.task_id, author_id: before.author_id, body }).await?;
+            audit::record(conn, actor, AuditAction::Update, Some(&before), Some(&comment)).await?;
+            Ok(comment)
+        }.scope_boxed()).await
+    }
+}
+
+impl CommentRevision {
+    // Newest first.
+    pub async fn read_history(conn: &mut DbConnection, comment_id: i32, page: i64, per_page: i64) -> anyhow::Result<Page<CommentRevision>> {
+        let total = comment_revisions::table.filter(comment_revisions::comment_id.eq(comment_id)).count().get_result(conn).await?;
+        let items = comment_revisions::table
+            .filter(comment_revisions::comment_id.eq(comment_id))
+            .order(comment_revisions::version.desc())
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<CommentRevision>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+}
+
+// Starred tasks are read through TaskFilter::starred_by, so they page and sort like any task list.
+impl StarredTask {
+    // Starring a task twice is not an error; the original row is kept.
+    pub async fn star(conn: &mut DbConnection, user_id: i32, task_id: i32) -> anyhow::Result<StarredTask> {
+        conn.transaction(|conn| async move {
+            diesel::insert_into(starred_tasks::table)
+                .values(NewStarredTask { user_id, task_id })
+                .on_conflict_do_nothing()
+                .execute(conn).await?;
+            let starred = starred_tasks::table.find((user_id, task_id)).first(conn).await?;
+            Ok(starred)
+        }.scope_boxed()).await
+    }
+
+    pub async fn unstar(conn: &mut DbConnection, user_id: i32, task_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::delete(starred_tasks::table.find((user_id, task_id)).filter(starred_tasks::task_id.eq_any(tenancy::task_ids()))).execute(conn).await?;
+        Ok(count)
+    }
 }
 
+// Edges are only ever added or removed. Each one reads "blocking_task_id blocks blocked_task_id".
+impl TaskDependency {
+    // Adding an edge that already exists is not an error.
+    pub async fn link(conn: &mut DbConnection, blocking_task_id: i32, blocked_task_id: i32) -> anyhow::Result<()> {
+        diesel::insert_into(task_dependencies::table)
+            .values(NewTaskDependency { blocking_task_id, blocked_task_id })
+            .on_conflict_do_nothing()
+            .execute(conn).await?;
+        Ok(())
+    }
+
+    pub async fn unlink(conn: &mut DbConnection, blocking_task_id: i32, blocked_task_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::delete(task_dependencies::table.find((blocking_task_id, blocked_task_id)).filter(task_dependencies::blocked_task_id.eq_any(tenancy::task_ids()))).execute(conn).await?;
+        Ok(count)
+    }
+
+    // Live tasks that have to be finished before `task_id` can be, in id order.
+    pub async fn read_blockers(conn: &mut DbConnection, task_id: i32) -> anyhow::Result<Vec<Task>> {
+        let results = tasks::table
+            .filter(tasks::task_id.eq_any(task_dependencies::table
+                .filter(task_dependencies::blocked_task_id.eq(task_id))
+                .select(task_dependencies::blocking_task_id)))
+            .filter(tasks::tenant_id.eq(tenancy::current()))
+            .filter(tasks::deleted_at.is_null())
+            .order(tasks::task_id)
+            .load::<Task>(conn).await?;
+        Ok(results)
+    }
+
+    // Live tasks waiting on `task_id`, in id order.
+    pub async fn read_blocked(conn: &mut DbConnection, task_id: i32) -> anyhow::Result<Vec<Task>> {
+        let results = tasks::table
+            .filter(tasks::task_id.eq_any(task_dependencies::table
+                .filter(task_dependencies::blocking_task_id.eq(task_id))
+                .select(task_dependencies::blocked_task_id)))
+            .filter(tasks::tenant_id.eq(tenancy::current()))
+            .filter(tasks::deleted_at.is_null())
+            .order(tasks::task_id)
+            .load::<Task>(conn).await?;
+        Ok(results)
+    }
+
+    // Ids of the live blockers of `task_id` that aren't complete yet (see task_is_complete).
+    pub async fn open_blocker_ids(conn: &mut DbConnection, task_id: i32, terminal_statuses: &[String]) -> anyhow::Result<Vec<i32>> {
+        let results = tasks::table
+            .filter(tasks::task_id.eq_any(task_dependencies::table
+                .filter(task_dependencies::blocked_task_id.eq(task_id))
+                .select(task_dependencies::blocking_task_id)))
+            .filter(tasks::tenant_id.eq(tenancy::current()))
+            .filter(tasks::deleted_at.is_null())
+            .filter(diesel::dsl::not(task_is_complete(terminal_statuses)))
+            .order(tasks::task_id)
+            .select(tasks::task_id)
+            .load(conn).await?;
+        Ok(results)
+    }
+
+    // True if `blocked_task_id` already blocks `blocking_task_id`, directly or through other
+    // tasks, so adding the edge would leave both waiting on each other forever.
+    pub async fn would_create_cycle(conn: &mut DbConnection, blocking_task_id: i32, blocked_task_id: i32) -> anyhow::Result<bool> {
+        if blocking_task_id == blocked_task_id {
+            return Ok(true);
+        }
+        let mut frontier = vec![blocked_task_id];
+        let mut seen = vec![blocked_task_id];
+        while !frontier.is_empty() {
+            let next: Vec<i32> = task_dependencies::table
+                .filter(task_dependencies::blocking_task_id.eq_any(&frontier))
+                .select(task_dependencies::blocked_task_id)
+                .load(conn).await?;
+            if next.contains(&blocking_task_id) {
+                return Ok(true);
+            }
+            frontier = next.into_iter().filter(|id| !seen.contains(id)).collect();
+            frontier.sort_unstable();
+            frontier.dedup();
+            seen.extend(&frontier);
+        }
+        Ok(false)
+    }
+}
+
+// Attachments are uploaded and deleted but never changed; a new version of a file is a new upload.
+impl Attachment {
+    pub async fn create(conn: &mut DbConnection, new_attachment: NewAttachment<'_>) -> anyhow::Result<Attachment> {
+        diesel::insert_into(attachments::table)
+            .values(&new_attachment)
+            .execute(conn).await?;
+        let attachment = attachments::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        Ok(attachment)
+    }
+
+    pub async fn read(conn: &mut DbConnection, id: i32) -> anyhow::Result<Option<Attachment>> {
+        let attachment = attachments::table.find(id).filter(attachments::task_id.eq_any(tenancy::task_ids())).first::<Attachment>(conn).await.optional()?;
+        Ok(attachment)
+    }
+
+    // Oldest first, in upload order.
+    pub async fn read_for_task(conn: &mut DbConnection, task_id: i32) -> anyhow::Result<Vec<Attachment>> {
+        let results = attachments::table
+            .filter(attachments::task_id.eq(task_id))
+            .filter(attachments::task_id.eq_any(tenancy::task_ids()))
+            .order(attachments::attachment_id)
+            .load::<Attachment>(conn).await?;
+        Ok(results)
+    }
+
+    pub async fn delete(conn: &mut DbConnection, id: i32) -> anyhow::Result<usize> {
+        let count = diesel::delete(attachments::table.find(id).filter(attachments::task_id.eq_any(tenancy::task_ids()))).execute(conn).await?;
+        Ok(count)
+    }
+}
+
+impl Role {
+    pub async fn read_all(conn: &mut DbConnection) -> anyhow::Result<Vec<Role>> {
+        let results = roles::table.order(roles::role_id).load::<Role>(conn).await?;
+        Ok(results)
+    }
+}
+
+// API keys are never updated in place: a key is created, listed, and eventually revoked.
+impl ApiKey {
+    pub async fn create(conn: &mut DbConnection, new_api_key: NewApiKey<'_>) -> anyhow::Result<ApiKey> {
+        diesel::insert_into(api_keys::table)
+            .values(&new_api_key)
+            .execute(conn).await?;
+        let api_key = api_keys::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        Ok(api_key)
+    }
+
+    pub async fn read_all_for_user(conn: &mut DbConnection, user_id: i32) -> anyhow::Result<Vec<ApiKey>> {
+        let results = api_keys::table
+            .filter(api_keys::user_id.eq(user_id))
+            .order(api_keys::api_key_id)
+            .load::<ApiKey>(conn).await?;
+        Ok(results)
+    }
+
+    // The key, if it belongs to the user.
+    pub async fn read_for_user(conn: &mut DbConnection, api_key_id: i32, user_id: i32) -> anyhow::Result<Option<ApiKey>> {
+        let api_key = api_keys::table
+            .filter(api_keys::api_key_id.eq(api_key_id))
+            .filter(api_keys::user_id.eq(user_id))
+            .first(conn).await
+            .optional()?;
+        Ok(api_key)
+    }
+
+    // Replaces both quotas; None lifts that one. Returns None when the key doesn't belong to the user.
+    pub async fn set_quotas(conn: &mut DbConnection, api_key_id: i32, user_id: i32, daily_quota: Option<i32>, monthly_quota: Option<i32>) -> anyhow::Result<Option<ApiKey>> {
+        let count = diesel::update(api_keys::table
+            .filter(api_keys::api_key_id.eq(api_key_id))
+            .filter(api_keys::user_id.eq(user_id)))
+            .set((api_keys::daily_quota.eq(daily_quota), api_keys::monthly_quota.eq(monthly_quota)))
+            .execute(conn).await?;
+        if count == 0 {
+            return Ok(None);
+        }
+        let api_key = api_keys::table.find(api_key_id).first(conn).await?;
+        Ok(Some(api_key))
+    }
+
+    pub async fn find_active_by_hash(conn: &mut DbConnection, key_hash: &str) -> anyhow::Result<Option<ApiKey>> {
+        let api_key = api_keys::table
+            .filter(api_keys::key_hash.eq(key_hash))
+            .filter(api_keys::revoked.eq(false))
+            .first(conn).await
+            .optional()?;
+        Ok(api_key)
+    }
+
+    // Returns the number of keys revoked (0 when the key doesn't belong to the user).
+    pub async fn revoke(conn: &mut DbConnection, api_key_id: i32, user_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::update(api_keys::table
+            .filter(api_keys::api_key_id.eq(api_key_id))
+            .filter(api_keys::user_id.eq(user_id)))
+            .set(api_keys::revoked.eq(true))
+            .execute(conn).await?;
+        Ok(count)
+    }
+}
+
+// Bulk assignment operations return one result per item, in input order.
+impl UserTask {
+    pub async fn create_many(conn: &mut DbConnection, actor: Option<i32>, new_user_tasks: Vec<NewUserTask>) -> anyhow::Result<Vec<anyhow::Result<UserTask>>> {
+        each_in_savepoint(conn, new_user_tasks, move |conn, new_user_task| async move {
+            UserTask::create_audited(conn, actor, new_user_task).await
+        }.scope_boxed()).await
+    }
+
+    pub async fn update_many(conn: &mut DbConnection, actor: Option<i32>, updated_user_tasks: Vec<NewUserTask>) -> anyhow::Result<Vec<anyhow::Result<UserTask>>> {
+        each_in_savepoint(conn, updated_user_tasks, move |conn, updated| async move {
+            UserTask::update_audited(conn, actor, (updated.user_id, updated.task_id), None, updated).await
+        }.scope_boxed()).await
+    }
+
+    pub async fn delete_many(conn: &mut DbConnection, actor: Option<i32>, ids: Vec<(i32, i32)>) -> anyhow::Result<Vec<anyhow::Result<usize>>> {
+        each_in_savepoint(conn, ids, move |conn, id| UserTask::delete_audited(conn, actor, id).scope_boxed()).await
+    }
+
+    // Creates the (user_id, task_id) pair or, if it already exists (or is in the trash), moves it to the new status.
+    pub async fn upsert(conn: &mut DbConnection, actor: Option<i32>, user_task: NewUserTask) -> anyhow::Result<UserTask> {
+        conn.transaction(|conn| async move {
+            let before = UserTask::read(conn, (user_task.user_id, user_task.task_id)).await?;
+            let now = chrono::Utc::now().naive_utc();
+            let id = (user_task.user_id, user_task.task_id);
+            diesel::insert_into(user_tasks::table)
+                .values((&user_task, user_tasks::tenant_id.eq(tenancy::current()), user_tasks::created_at.eq(now), user_tasks::updated_at.eq(now)))
+                .on_conflict(conflict_target!((user_tasks::user_id, user_tasks::task_id)))
+                .do_update()
+                .set((
+                    user_tasks::task_status_id.eq(user_task.task_status_id),
+                    user_tasks::deleted_at.eq(None::<chrono::NaiveDateTime>),
+                    user_tasks::updated_at.eq(now),
+                    user_tasks::version.eq(user_tasks::version + 1),
+                ))
+                .execute(conn).await?;
+            let user_task = user_tasks::table.find(id).first(conn).await?;
+            let action = if before.is_some() { AuditAction::Update } else { AuditAction::Create };
+            audit::record(conn, actor, action, before.as_ref(), Some(&user_task)).await?;
+            Ok(user_task)
+        }.scope_boxed()).await
+    }
+
+    pub async fn update_partial(conn: &mut DbConnection, actor: Option<i32>, id: (i32, i32), expected_version: Option<i32>, changes: UserTaskChanges) -> anyhow::Result<UserTask> {
+        conn.transaction(|conn| async move {
+            let before = UserTask::read(conn, id).await?;
+            if changes.task_status_id.is_some() {
+                let count = diesel::update(user_tasks::table
+                    .filter(user_tasks::tenant_id.eq(tenancy::current()))
+                    .filter(user_tasks::user_id.eq(id.0))
+                    .filter(user_tasks::task_id.eq(id.1))
+                    .filter(user_tasks::deleted_at.is_null())
+                    .filter(user_tasks::version.eq(versioning::expected_or(expected_version, user_tasks::version))))
+                    .set((&changes, user_tasks::updated_at.eq(chrono::Utc::now().naive_utc()), user_tasks::version.eq(user_tasks::version + 1)))
+                    .execute(conn).await?;
+                if count == 0 {
+                    return Err(versioning::missed(before, expected_version));
+                }
+            } else {
+                versioning::check(before.as_ref(), expected_version)?;
+            }
+            let user_task = user_tasks::table
+                .filter(user_tasks::tenant_id.eq(tenancy::current()))
+                .filter(user_tasks::user_id.eq(id.0))
+                .filter(user_tasks::task_id.eq(id.1))
+                .filter(user_tasks::deleted_at.is_null())
+                .first(conn).await?;
+            audit::record(conn, actor, AuditAction::Update, before.as_ref(), Some(&user_task)).await?;
+            Ok(user_task)
+        }.scope_boxed()).await
+    }
+
+    pub async fn restore(conn: &mut DbConnection, actor: Option<i32>, id: (i32, i32)) -> anyhow::Result<Option<UserTask>> {
+        conn.transaction(|conn| async move {
+            let trashed = user_tasks::table
+                .filter(user_tasks::tenant_id.eq(tenancy::current()))
+                .filter(user_tasks::user_id.eq(id.0))
+                .filter(user_tasks::task_id.eq(id.1))
+                .filter(user_tasks::deleted_at.is_not_null())
+                .first::<UserTask>(conn).await
+                .optional()?;
+            let Some(trashed) = trashed else {
+                return Ok(None);
+            };
+            diesel::update(user_tasks::table
+                .filter(user_tasks::tenant_id.eq(tenancy::current()))
+                .filter(user_tasks::user_id.eq(id.0))
+                .filter(user_tasks::task_id.eq(id.1)))
+                .set(user_tasks::deleted_at.eq(None::<chrono::NaiveDateTime>))
+                .execute(conn).await?;
+            let user_task = user_tasks::table.find(id).first(conn).await?;
+            audit::record(conn, actor, AuditAction::Restore, Some(&trashed), Some(&user_task)).await?;
+            Ok(Some(user_task))
+        }.scope_boxed()).await
+    }
+
+    // Hands every live assignment of one user to another in the same status, for when someone
+    // leaves or changes jobs. Where the other user already has the task, theirs is kept as it is.
+    // Returns how many assignments were taken off `from_user_id`.
+    pub async fn reassign_all(conn: &mut DbConnection, actor: Option<i32>, from_user_id: i32, to_user_id: i32) -> anyhow::Result<usize> {
+        conn.transaction(|conn| async move {
+            let assignments = user_tasks::table
+                .filter(user_tasks::tenant_id.eq(tenancy::current()))
+                .filter(user_tasks::user_id.eq(from_user_id))
+                .filter(user_tasks::deleted_at.is_null())
+                .load::<UserTask>(conn).await?;
+            for assignment in &assignments {
+                if UserTask::read(conn, (to_user_id, assignment.task_id)).await?.is_none() {
+                    let new_user_task = NewUserTask { user_id: to_user_id, task_id: assignment.task_id, task_status_id: assignment.task_status_id };
+                    UserTask::create_audited(conn, actor, new_user_task).await?;
+                }
+                UserTask::delete_audited(conn, actor, (from_user_id, assignment.task_id)).await?;
+            }
+            Ok(assignments.len())
+        }.scope_boxed()).await
+    }
+
+    // Live assignments currently in this status; a status can't be deleted while this is non-zero.
+    pub async fn count_with_status(conn: &mut DbConnection, task_status_id: i32) -> anyhow::Result<i64> {
+        let count = user_tasks::table
+            .filter(user_tasks::tenant_id.eq(tenancy::current()))
+            .filter(user_tasks::task_status_id.eq(task_status_id))
+            .filter(user_tasks::deleted_at.is_null())
+            .count()
+            .get_result(conn).await?;
+        Ok(count)
+    }
+
+    fn filtered_query(filter: &AssignmentFilter) -> user_tasks::BoxedQuery<'static, DbBackend> {
+        let mut query = user_tasks::table.filter(user_tasks::tenant_id.eq(tenancy::current())).filter(user_tasks::deleted_at.is_null()).into_boxed();
+        if let Some(user_id) = filter.user_id {
+            query = query.filter(user_tasks::user_id.eq(user_id));
+        }
+        if let Some(task_id) = filter.task_id {
+            query = query.filter(user_tasks::task_id.eq(task_id));
+        }
+        if let Some(task_status_id) = filter.task_status_id {
+            query = query.filter(user_tasks::task_status_id.eq(task_status_id));
+        }
+        if let Some(project_id) = filter.project_id {
+            query = query.filter(user_tasks::task_id.eq_any(tasks::table.filter(tasks::project_id.eq(project_id)).select(tasks::task_id)));
+        }
+        query
+    }
+
+    pub async fn count_filtered(conn: &mut DbConnection, filter: &AssignmentFilter) -> anyhow::Result<i64> {
+        let count = Self::filtered_query(filter).count().get_result(conn).await?;
+        Ok(count)
+    }
+
+    pub async fn read_page_filtered(conn: &mut DbConnection, filter: &AssignmentFilter, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<UserTask>> {
+        let total = Self::count_filtered(conn, filter).await?;
+        let query = match sort.column.as_str() {
+            "user_id" => sorting::order_by(Self::filtered_query(filter), user_tasks::user_id, sort.order),
+            "task_id" => sorting::order_by(Self::filtered_query(filter), user_tasks::task_id, sort.order),
+            "task_status_id" => sorting::order_by(Self::filtered_query(filter), user_tasks::task_status_id, sort.order),
+            "created_at" => sorting::order_by(Self::filtered_query(filter), user_tasks::created_at, sort.order),
+            "updated_at" => sorting::order_by(Self::filtered_query(filter), user_tasks::updated_at, sort.order),
+            other => anyhow::bail!("Unknown sort column for assignments: {}", other),
+        };
+        let items = query
+            // tie-break on the primary key so pages never overlap
+            .then_order_by((user_tasks::user_id, user_tasks::task_id))
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<UserTask>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    // The "my tasks" lookup. The primary key starts with user_id, so SQLite answers it from the index.
+    pub async fn read_by_user(conn: &mut DbConnection, user_id: i32, task_status_id: Option<i32>, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<UserTask>> {
+        let filter = AssignmentFilter { user_id: Some(user_id), task_status_id, ..AssignmentFilter::default() };
+        Self::read_page_filtered(conn, &filter, page, per_page, sort).await
+    }
+
+    // Everyone on one task; uses the user_tasks_task_id index.
+    pub async fn read_by_task(conn: &mut DbConnection, task_id: i32, task_status_id: Option<i32>, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<UserTask>> {
+        let filter = AssignmentFilter { task_id: Some(task_id), task_status_id, ..AssignmentFilter::default() };
+        Self::read_page_filtered(conn, &filter, page, per_page, sort).await
+    }
+
+    fn joined_query(filter: &AssignmentFilter) -> diesel::dsl::IntoBoxed<'static, AssignmentJoin, DbBackend> {
+        let mut query = user_tasks::table
+            .inner_join(users::table)
+            .inner_join(tasks::table)
+            .inner_join(task_statuses::table)
+            .filter(user_tasks::tenant_id.eq(tenancy::current()))
+            .filter(user_tasks::deleted_at.is_null())
+            .into_boxed();
+        if let Some(user_id) = filter.user_id {
+            query = query.filter(user_tasks::user_id.eq(user_id));
+        }
+        if let Some(task_id) = filter.task_id {
+            query = query.filter(user_tasks::task_id.eq(task_id));
+        }
+        if let Some(task_status_id) = filter.task_status_id {
+            query = query.filter(user_tasks::task_status_id.eq(task_status_id));
+        }
+        if let Some(project_id) = filter.project_id {
+            query = query.filter(tasks::project_id.eq(project_id));
+        }
+        query
+    }
+
+    fn sorted_joined_query(filter: &AssignmentFilter, sort: &Sort) -> anyhow::Result<diesel::dsl::IntoBoxed<'static, AssignmentJoin, DbBackend>> {
+        let query = match sort.column.as_str() {
+            "user_id" => sorting::order_by(Self::joined_query(filter), user_tasks::user_id, sort.order),
+            "task_id" => sorting::order_by(Self::joined_query(filter), user_tasks::task_id, sort.order),
+            "task_status_id" => sorting::order_by(Self::joined_query(filter), user_tasks::task_status_id, sort.order),
+            "created_at" => sorting::order_by(Self::joined_query(filter), user_tasks::created_at, sort.order),
+            "updated_at" => sorting::order_by(Self::joined_query(filter), user_tasks::updated_at, sort.order),
+            other => anyhow::bail!("Unknown sort column for assignments: {}", other),
+        };
+        Ok(query.then_order_by((user_tasks::user_id, user_tasks::task_id)))
+    }
+
+    // Same page as read_page_filtered, with each assignment's user, task and status fetched
+    // in the same query.
+    pub async fn read_page_joined(conn: &mut DbConnection, filter: &AssignmentFilter, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<(UserTask, User, Task, TaskStatus)>> {
+        let total = Self::joined_query(filter).count().get_result(conn).await?;
+        let items = Self::sorted_joined_query(filter, sort)?
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .select((UserTask::as_select(), User::as_select(), Task::as_select(), TaskStatus::as_select()))
+            .load(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    pub async fn read_deleted(conn: &mut DbConnection, since: chrono::NaiveDateTime) -> anyhow::Result<Vec<UserTask>> {
+        let results = user_tasks::table
+            .filter(user_tasks::tenant_id.eq(tenancy::current()))
+            .filter(user_tasks::deleted_at.ge(since))
+            .order((user_tasks::deleted_at.desc(), user_tasks::user_id, user_tasks::task_id))
+            .load::<UserTask>(conn).await?;
+        Ok(results)
+    }
+
+    pub async fn purge_deleted(conn: &mut DbConnection, before: chrono::NaiveDateTime) -> anyhow::Result<usize> {
+        let count = diesel::delete(user_tasks::table.filter(user_tasks::tenant_id.eq(tenancy::current())).filter(user_tasks::deleted_at.lt(before))).execute(conn).await?;
+        Ok(count)
+    }
+}
+
+impl AssignmentDetail {
+    // Unpaged, ordered by status, then task, then user.
+    pub async fn read_all(conn: &mut DbConnection, filter: &AssignmentFilter) -> anyhow::Result<Vec<AssignmentDetail>> {
+        let items = UserTask::joined_query(filter)
+            .order((user_tasks::task_status_id, user_tasks::task_id, user_tasks::user_id))
+            .select(AssignmentDetail::as_select())
+            .load(conn).await?;
+        Ok(items)
+    }
+
+    // Up to `limit` rows in (user_id, task_id) order, starting after the `after` assignment.
+    // Unlike pages, batches don't shift when rows are added or removed between reads, so
+    // exports walk the table with them: pass the last row's ids back for the next batch.
+    pub async fn read_batch(conn: &mut DbConnection, filter: &AssignmentFilter, after: Option<(i32, i32)>, limit: i64) -> anyhow::Result<Vec<AssignmentDetail>> {
+        let mut query = UserTask::joined_query(filter);
+        if let Some((user_id, task_id)) = after {
+            query = query.filter(user_tasks::user_id.gt(user_id).or(user_tasks::user_id.eq(user_id).and(user_tasks::task_id.gt(task_id))));
+        }
+        let items = query
+            .order((user_tasks::user_id, user_tasks::task_id))
+            .limit(limit)
+            .select(AssignmentDetail::as_select())
+            .load(conn).await?;
+        Ok(items)
+    }
+
+    pub async fn read_page(conn: &mut DbConnection, filter: &AssignmentFilter, page: i64, per_page: i64, sort: &Sort) -> anyhow::Result<Page<AssignmentDetail>> {
+        let total = UserTask::joined_query(filter).count().get_result(conn).await?;
+        let items = UserTask::sorted_joined_query(filter, sort)?
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .select(AssignmentDetail::as_select())
+            .load(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+}
+
+
+// Runs `op` for every item inside one transaction. Each item gets its own savepoint,
+// so a failing item is rolled back on its own and the rest still commit together.
+async fn each_in_savepoint<'a, T: Send + 'a, R: Send + 'a>(conn: &mut DbConnection, items: Vec<T>, mut op: impl for<'c> FnMut(&'c mut DbConnection, T) -> ScopedBoxFuture<'a, 'c, anyhow::Result<R>> + Send + 'a) -> anyhow::Result<Vec<anyhow::Result<R>>> {
+    conn.transaction(|conn| async move {
+        let mut results = Vec::with_capacity(items.len());
+        for item in items {
+            let op = &mut op;
+            results.push(conn.transaction(|conn| async move { op(conn, item).await }.scope_boxed()).await);
+        }
+        Ok(results)
+    }.scope_boxed()).await
+}
+
+// Task::duplicate's worker. Subtask loops are rejected on write, so the recursion ends.
+async fn duplicate_tree(conn: &mut DbConnection, actor: Option<i32>, source: Task, parent_task_id: Option<i32>, initial_status: Option<i32>) -> anyhow::Result<Task> {
+    let copy = Task::create_audited(conn, actor, NewTask {
+        task_name: &source.task_name,
+        due_date: source.due_date,
+        priority: source.priority,
+        parent_task_id,
+        recurrence: source.recurrence.as_deref(),
+        project_id: source.project_id,
+    }).await?;
+    let tags: Vec<NewTaskTag> = task_tags::table
+        .filter(task_tags::task_id.eq(source.task_id))
+        .select(task_tags::tag_id)
+        .load::<i32>(conn).await?
+        .into_iter()
+        .map(|tag_id| NewTaskTag { task_id: copy.task_id, tag_id })
+        .collect();
+    for tag in &tags {
+        diesel::insert_into(task_tags::table).values(tag).execute(conn).await?;
+    }
+    if let Some(task_status_id) = initial_status {
+        let assignees: Vec<i32> = user_tasks::table
+            .filter(user_tasks::task_id.eq(source.task_id))
+            .filter(user_tasks::deleted_at.is_null())
+            .order(user_tasks::user_id)
+            .select(user_tasks::user_id)
+            .load(conn).await?;
+        for user_id in assignees {
+            UserTask::create_audited(conn, actor, NewUserTask { user_id, task_id: copy.task_id, task_status_id }).await?;
+        }
+    }
+    for subtask in Task::read_subtasks(conn, source.task_id).await? {
+        Box::pin(duplicate_tree(conn, actor, subtask, Some(copy.task_id), initial_status)).await?;
+    }
+    Ok(copy)
+}
+
+// A task is complete once it is assigned and every live assignment is in one of
+// `terminal_statuses` (matched by name).
+pub(crate) fn task_is_complete(terminal_statuses: &[String]) -> Box<dyn BoxableExpression<tasks::table, DbBackend, SqlType = diesel::sql_types::Bool>> {
+    let assigned = user_tasks::table
+        .filter(user_tasks::task_id.eq(tasks::task_id))
+        .filter(user_tasks::deleted_at.is_null());
+    let unfinished = user_tasks::table
+        .inner_join(task_statuses::table)
+        .filter(user_tasks::task_id.eq(tasks::task_id))
+        .filter(user_tasks::deleted_at.is_null())
+        .filter(task_statuses::status_name.ne_all(terminal_statuses.to_vec()));
+    Box::new(diesel::dsl::exists(assigned).and(diesel::dsl::not(diesel::dsl::exists(unfinished))))
+}
+
+// For names that are unique regardless of case. SQLite's schema compares them with NOCASE, but
+// PostgreSQL and MySQL compare text exactly, so lookups lower both sides.
+diesel::define_sql_function! {
+    fn lower(text: diesel::sql_types::Text) -> diesel::sql_types::Text;
+}
+
+// ORDER BY helpers for read_page. The column names match the whitelists in crate::sorting,
+// and the primary key is always appended so ordering is deterministic across pages. They're
+// also where paged reads pick up the tenant scope.
+fn sorted_users(sort: &Sort) -> anyhow::Result<users::BoxedQuery<'static, DbBackend>> {
+    let query = match sort.column.as_str() {
+        "user_id" => sorting::order_by(users::table.into_boxed(), users::user_id, sort.order),
+        "name" => sorting::order_by(users::table.into_boxed(), users::name, sort.order),
+        "email" => sorting::order_by(users::table.into_boxed(), users::email, sort.order),
+        "active" => sorting::order_by(users::table.into_boxed(), users::active, sort.order),
+        "role_id" => sorting::order_by(users::table.into_boxed(), users::role_id, sort.order),
+        "created_at" => sorting::order_by(users::table.into_boxed(), users::created_at, sort.order),
+        "updated_at" => sorting::order_by(users::table.into_boxed(), users::updated_at, sort.order),
+        other => anyhow::bail!("Unknown sort column for users: {}", other),
+    };
+    Ok(query.filter(users::tenant_id.eq(tenancy::current())).then_order_by(users::user_id))
+}
+
+fn sorted_tasks(sort: &Sort) -> anyhow::Result<tasks::BoxedQuery<'static, DbBackend>> {
+    let query = match sort.column.as_str() {
+        "priority" => sorting::order_by(tasks::table.into_boxed(), tasks::priority, sort.order),
+        "task_id" => sorting::order_by(tasks::table.into_boxed(), tasks::task_id, sort.order),
+        "task_name" => sorting::order_by(tasks::table.into_boxed(), tasks::task_name, sort.order),
+        "created_at" => sorting::order_by(tasks::table.into_boxed(), tasks::created_at, sort.order),
+        "updated_at" => sorting::order_by(tasks::table.into_boxed(), tasks::updated_at, sort.order),
+        "due_date" => sorting::order_by(tasks::table.into_boxed(), tasks::due_date, sort.order),
+        other => anyhow::bail!("Unknown sort column for tasks: {}", other),
+    };
+    Ok(query.filter(tasks::tenant_id.eq(tenancy::current())).then_order_by(tasks::task_id))
+}
+
+fn sorted_comments(sort: &Sort) -> anyhow::Result<comments::BoxedQuery<'static, DbBackend>> {
+    let query = match sort.column.as_str() {
+        "comment_id" => sorting::order_by(comments::table.into_boxed(), comments::comment_id, sort.order),
+        "created_at" => sorting::order_by(comments::table.into_boxed(), comments::created_at, sort.order),
+        "updated_at" => sorting::order_by(comments::table.into_boxed(), comments::updated_at, sort.order),
+        other => anyhow::bail!("Unknown sort column for comments: {}", other),
+    };
+    Ok(query.filter(comments::task_id.eq_any(tenancy::task_ids())).then_order_by(comments::comment_id))
+}
+
+fn sorted_projects(sort: &Sort) -> anyhow::Result<projects::BoxedQuery<'static, DbBackend>> {
+    let query = match sort.column.as_str() {
+        "project_id" => sorting::order_by(projects::table.into_boxed(), projects::project_id, sort.order),
+        "project_name" => sorting::order_by(projects::table.into_boxed(), projects::project_name, sort.order),
+        "created_at" => sorting::order_by(projects::table.into_boxed(), projects::created_at, sort.order),
+        "updated_at" => sorting::order_by(projects::table.into_boxed(), projects::updated_at, sort.order),
+        other => anyhow::bail!("Unknown sort column for projects: {}", other),
+    };
+    Ok(query.filter(projects::tenant_id.eq(tenancy::current())).then_order_by(projects::project_id))
+}
+
+fn sorted_teams(sort: &Sort) -> anyhow::Result<teams::BoxedQuery<'static, DbBackend>> {
+    let query = match sort.column.as_str() {
+        "team_id" => sorting::order_by(teams::table.into_boxed(), teams::team_id, sort.order),
+        "team_name" => sorting::order_by(teams::table.into_boxed(), teams::team_name, sort.order),
+        "created_at" => sorting::order_by(teams::table.into_boxed(), teams::created_at, sort.order),
+        "updated_at" => sorting::order_by(teams::table.into_boxed(), teams::updated_at, sort.order),
+        other => anyhow::bail!("Unknown sort column for teams: {}", other),
+    };
+    Ok(query.filter(teams::tenant_id.eq(tenancy::current())).then_order_by(teams::team_id))
+}
+
+fn sorted_tags(sort: &Sort) -> anyhow::Result<tags::BoxedQuery<'static, DbBackend>> {
+    let query = match sort.column.as_str() {
+        "tag_id" => sorting::order_by(tags::table.into_boxed(), tags::tag_id, sort.order),
+        "tag_name" => sorting::order_by(tags::table.into_boxed(), tags::tag_name, sort.order),
+        "created_at" => sorting::order_by(tags::table.into_boxed(), tags::created_at, sort.order),
+        "updated_at" => sorting::order_by(tags::table.into_boxed(), tags::updated_at, sort.order),
+        other => anyhow::bail!("Unknown sort column for tags: {}", other),
+    };
+    Ok(query.filter(tags::tenant_id.eq(tenancy::current())).then_order_by(tags::tag_id))
+}
+
+fn sorted_task_templates(sort: &Sort) -> anyhow::Result<task_templates::BoxedQuery<'static, DbBackend>> {
+    let query = match sort.column.as_str() {
+        "template_id" => sorting::order_by(task_templates::table.into_boxed(), task_templates::template_id, sort.order),
+        "template_name" => sorting::order_by(task_templates::table.into_boxed(), task_templates::template_name, sort.order),
+        "created_at" => sorting::order_by(task_templates::table.into_boxed(), task_templates::created_at, sort.order),
+        "updated_at" => sorting::order_by(task_templates::table.into_boxed(), task_templates::updated_at, sort.order),
+        other => anyhow::bail!("Unknown sort column for task_templates: {}", other),
+    };
+    Ok(query.filter(task_templates::tenant_id.eq(tenancy::current())).then_order_by(task_templates::template_id))
+}
+
+fn sorted_task_statuses(sort: &Sort) -> anyhow::Result<task_statuses::BoxedQuery<'static, DbBackend>> {
+    let query = match sort.column.as_str() {
+        "task_status_id" => sorting::order_by(task_statuses::table.into_boxed(), task_statuses::task_status_id, sort.order),
+        "status_name" => sorting::order_by(task_statuses::table.into_boxed(), task_statuses::status_name, sort.order),
+        "created_at" => sorting::order_by(task_statuses::table.into_boxed(), task_statuses::created_at, sort.order),
+        "updated_at" => sorting::order_by(task_statuses::table.into_boxed(), task_statuses::updated_at, sort.order),
+        other => anyhow::bail!("Unknown sort column for task_statuses: {}", other),
+    };
+    Ok(query.filter(task_statuses::tenant_id.eq(tenancy::current())).then_order_by(task_statuses::task_status_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::TaskPriority;
+    use crate::models::{CalendarFeed, NewCalendarFeed, NewPushSubscription, NewWebhook, PushSubscription, TaskWatcher, Webhook};
+    use crate::overdue;
+    use crate::quotas;
+    use crate::preferences::Channel;
+    use crate::test_support::{self, create_task, new_task};
+
+    fn breaks_a_foreign_key(err: &anyhow::Error) -> bool {
+        matches!(
+            err.downcast_ref::<diesel::result::Error>(),
+            Some(diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::ForeignKeyViolation, _))
+        )
+    }
+
+    #[tokio::test]
+    async fn creates_reads_updates_and_deletes_a_task() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Draft the agenda").await;
+        assert_eq!(Task::read(&mut conn, task.task_id).await.unwrap().unwrap().task_name, "Draft the agenda");
+
+        let updated = Task::update(&mut conn, task.task_id, new_task("Send the agenda")).await.unwrap();
+        assert_eq!(updated.task_name, "Send the agenda");
+        assert_eq!(updated.version, task.version + 1);
+
+        assert_eq!(Task::delete(&mut conn, task.task_id).await.unwrap(), 1);
+        assert!(Task::read(&mut conn, task.task_id).await.unwrap().is_none());
+        assert_eq!(Task::delete(&mut conn, task.task_id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn reading_a_missing_row_is_none_not_an_error() {
+        let mut conn = test_support::conn().await;
+        assert!(Task::read(&mut conn, 9999).await.unwrap().is_none());
+        assert!(User::read(&mut conn, 9999).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn assigning_a_missing_task_breaks_a_foreign_key() {
+        let mut conn = test_support::conn().await;
+        let err = UserTask::create(&mut conn, NewUserTask { user_id: 1, task_id: 9999, task_status_id: 1 }).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<diesel::result::Error>(),
+            Some(diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::ForeignKeyViolation, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn assignments_count_toward_their_status_until_trashed() {
+        let mut conn = test_support::conn().await;
+        let status = TaskStatus::create(&mut conn, NewTaskStatus { status_name: "Parked" }).await.unwrap();
+        let task = create_task(&mut conn, "Book the room").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: status.task_status_id }).await.unwrap();
+        // statuses are only trashed once no live assignment is in them
+        assert_eq!(UserTask::count_with_status(&mut conn, status.task_status_id).await.unwrap(), 1);
+
+        // a trashed task takes its assignments with it, and they go for good with their user
+        assert_eq!(Task::delete(&mut conn, task.task_id).await.unwrap(), 1);
+        assert!(UserTask::read(&mut conn, (2, task.task_id)).await.unwrap().is_none());
+        assert_eq!(UserTask::count_with_status(&mut conn, status.task_status_id).await.unwrap(), 0);
+        assert_eq!(User::delete(&mut conn, 2).await.unwrap(), 1);
+        assert_eq!(user_tasks::table.filter(user_tasks::user_id.eq(2)).count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn a_task_whose_assignment_fails_is_rolled_back_alone() {
+        let mut conn = test_support::conn().await;
+        let before = Task::count(&mut conn).await.unwrap();
+        let outcomes = Task::create_many_with_assignments(&mut conn, None, vec![
+            (new_task("Imported"), vec![(1, 1), (3, 2)]),
+            (new_task("Nobody to do it"), vec![(9999, 1)]),
+        ]).await.unwrap();
+        let (task, user_tasks) = outcomes[0].as_ref().unwrap();
+        assert_eq!(user_tasks.iter().map(|user_task| (user_task.user_id, user_task.task_id)).collect::<Vec<_>>(), vec![(1, task.task_id), (3, task.task_id)]);
+        assert!(outcomes[1].is_err());
+        assert_eq!(Task::count(&mut conn).await.unwrap(), before + 1);
+    }
+
+    #[tokio::test]
+    async fn batches_carry_on_after_the_last_assignment_read() {
+        let mut conn = test_support::conn().await;
+        let all = AssignmentDetail::read_all(&mut conn, &AssignmentFilter::default()).await.unwrap();
+        let mut seen = Vec::new();
+        let mut after = None;
+        loop {
+            let batch = AssignmentDetail::read_batch(&mut conn, &AssignmentFilter::default(), after, 2).await.unwrap();
+            let Some(last) = batch.last() else { break };
+            after = Some((last.user_id, last.task_id));
+            seen.extend(batch.iter().map(|row| (row.user_id, row.task_id)));
+        }
+        let mut expected: Vec<(i32, i32)> = all.iter().map(|row| (row.user_id, row.task_id)).collect();
+        expected.sort();
+        assert!(expected.len() > 2);
+        assert_eq!(seen, expected);
+    }
+
+    #[tokio::test]
+    async fn pages_through_tasks_and_past_the_end() {
+        let mut conn = test_support::conn().await;
+        let total = Task::read_all(&mut conn).await.unwrap().len() as i64;
+        let sort = Sort::parse(None, None, sorting::TASK_SORT_COLUMNS).unwrap();
+
+        let first = Task::read_page(&mut conn, 1, 3, &sort).await.unwrap();
+        assert_eq!(first.items.len(), 3);
+        assert_eq!(first.total, total);
+        assert_eq!(first.total_pages, (total + 2) / 3);
+
+        let last = Task::read_page(&mut conn, first.total_pages, 3, &sort).await.unwrap();
+        assert_eq!(last.items.len() as i64, total - 3 * (first.total_pages - 1));
+
+        let past = Task::read_page(&mut conn, first.total_pages + 1, 3, &sort).await.unwrap();
+        assert!(past.items.is_empty());
+        assert_eq!(past.total, total);
+
+        let huge = Task::read_page(&mut conn, i64::MAX, 3, &sort).await.unwrap();
+        assert!(huge.items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn assignment_filters_combine() {
+        let mut conn = test_support::conn().await;
+        let all = UserTask::read_all(&mut conn).await.unwrap();
+        let sort = Sort::parse(None, None, sorting::USER_TASK_SORT_COLUMNS).unwrap();
+        let filter = AssignmentFilter { user_id: Some(3), task_status_id: Some(2), ..AssignmentFilter::default() };
+        let expected: Vec<_> = all.iter().filter(|ut| ut.user_id == 3 && ut.task_status_id == 2).map(|ut| ut.task_id).collect();
+        assert!(!expected.is_empty());
+
+        let page = UserTask::read_page_filtered(&mut conn, &filter, 1, 100, &sort).await.unwrap();
+        assert_eq!(page.items.iter().map(|ut| ut.task_id).collect::<Vec<_>>(), expected);
+        assert_eq!(page.total, expected.len() as i64);
+
+        let everything = UserTask::read_page_filtered(&mut conn, &AssignmentFilter::default(), 1, 100, &sort).await.unwrap();
+        assert_eq!(everything.total, all.len() as i64);
+    }
+
+    #[tokio::test]
+    async fn sorts_pages_by_the_requested_column() {
+        let mut conn = test_support::conn().await;
+        let sort = Sort::parse(Some("task_name"), Some("desc"), sorting::TASK_SORT_COLUMNS).unwrap();
+        let names: Vec<String> = Task::read_page(&mut conn, 1, 100, &sort).await.unwrap().items.into_iter().map(|t| t.task_name).collect();
+        let mut expected = names.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(names, expected);
+
+        let sort = Sort::parse(Some("task_status_id"), Some("desc"), sorting::USER_TASK_SORT_COLUMNS).unwrap();
+        let statuses: Vec<i32> = UserTask::read_page(&mut conn, 1, 100, &sort).await.unwrap().items.iter().map(|ut| ut.task_status_id).collect();
+        assert!(statuses.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+
+    #[tokio::test]
+    async fn finds_users_by_email() {
+        let mut conn = test_support::conn().await;
+        assert_eq!(User::read_by_email(&mut conn, "bob@example.com").await.unwrap().unwrap().user_id, 2);
+        assert!(User::read_by_email(&mut conn, "nobody@example.com").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn revoked_api_keys_stop_matching() {
+        let mut conn = test_support::conn().await;
+        let key = ApiKey::create(&mut conn, NewApiKey { user_id: 2, name: "ci", key_prefix: "abcd1234", key_hash: "hash-of-the-key", daily_quota: None, monthly_quota: None }).await.unwrap();
+        assert_eq!(ApiKey::find_active_by_hash(&mut conn, "hash-of-the-key").await.unwrap().unwrap().api_key_id, key.api_key_id);
+
+        assert_eq!(ApiKey::revoke(&mut conn, key.api_key_id, 3).await.unwrap(), 0);
+        assert_eq!(ApiKey::revoke(&mut conn, key.api_key_id, 2).await.unwrap(), 1);
+        assert!(ApiKey::find_active_by_hash(&mut conn, "hash-of-the-key").await.unwrap().is_none());
+        assert!(ApiKey::read_all_for_user(&mut conn, 2).await.unwrap()[0].revoked);
+    }
+
+    #[tokio::test]
+    async fn access_is_read_only_for_active_users() {
+        let mut conn = test_support::conn().await;
+        assert_eq!(User::set_role(&mut conn, None, 5, None, UserRole::Manager).await.unwrap().role_id, UserRole::Manager as i32);
+        assert_eq!(User::read_access(&mut conn, 5).await.unwrap(), Some((UserRole::Manager, tenancy::DEFAULT_TENANT)));
+        // Diana is inactive, so there's no role to act with
+        assert_eq!(User::read_access(&mut conn, 4).await.unwrap(), None);
+        assert_eq!(User::read_access(&mut conn, 9999).await.unwrap(), None);
+        let roles: Vec<String> = Role::read_all(&mut conn).await.unwrap().into_iter().map(|role| role.role_name).collect();
+        assert_eq!(roles, ["admin", "manager", "member"]);
+    }
+
+    #[tokio::test]
+    async fn registering_stores_the_user_with_their_credential() {
+        let mut conn = test_support::conn().await;
+        let user = Credential::register(&mut conn, NewUser { name: "Mallory", email: "mallory@example.com", active: true }, "hash").await.unwrap();
+        assert_eq!(User::read_by_email(&mut conn, "mallory@example.com").await.unwrap().unwrap().user_id, user.user_id);
+        assert_eq!(Credential::read(&mut conn, user.user_id).await.unwrap().unwrap().password_hash, "hash");
+    }
+
+    #[tokio::test]
+    async fn oauth_links_by_email_then_by_account() {
+        let mut conn = test_support::conn().await;
+        let charlie = OAuthIdentity::find_or_link_user(&mut conn, "github", "43", "charlie@example.com", "Charlie").await.unwrap();
+        assert_eq!(charlie.user_id, 3);
+        // the account stays linked even after the email on it changes
+        let again = OAuthIdentity::find_or_link_user(&mut conn, "github", "43", "someone@else.com", "Charlie").await.unwrap();
+        assert_eq!(again.user_id, 3);
+
+        let stranger = OAuthIdentity::find_or_link_user(&mut conn, "google", "abc", "new@example.com", "Newcomer").await.unwrap();
+        assert_eq!(stranger.name, "Newcomer");
+        assert!(stranger.active);
+        assert_eq!(OAuthIdentity::find_or_link_user(&mut conn, "google", "abc", "new@example.com", "Newcomer").await.unwrap().user_id, stranger.user_id);
+    }
+
+    #[tokio::test]
+    async fn a_refresh_token_rotates_once() {
+        let mut conn = test_support::conn().await;
+        let now = chrono::Utc::now().naive_utc();
+        let expires_at = now + chrono::Duration::days(1);
+        RefreshToken::create(&mut conn, NewRefreshToken { user_id: 2, token_hash: "first", expires_at }).await.unwrap();
+
+        let second = RefreshToken::rotate(&mut conn, "first", NewRefreshToken { user_id: 2, token_hash: "second", expires_at }, now).await.unwrap().unwrap();
+        assert_eq!(second.token_hash, "second");
+        assert!(RefreshToken::find_active_by_hash(&mut conn, "first", now).await.unwrap().is_none());
+        assert!(RefreshToken::rotate(&mut conn, "first", NewRefreshToken { user_id: 2, token_hash: "third", expires_at }, now).await.unwrap().is_none());
+        // nor can another user's token be rotated into someone else's session
+        assert!(RefreshToken::rotate(&mut conn, "second", NewRefreshToken { user_id: 3, token_hash: "fourth", expires_at }, now).await.unwrap().is_none());
+
+        let later = expires_at + chrono::Duration::seconds(1);
+        assert!(RefreshToken::find_active_by_hash(&mut conn, "second", later).await.unwrap().is_none());
+        assert_eq!(RefreshToken::revoke_all_for_user(&mut conn, 2).await.unwrap(), 1);
+        assert!(RefreshToken::find_active_by_hash(&mut conn, "second", now).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn revoked_access_tokens_are_forgotten_once_they_expire() {
+        let mut conn = test_support::conn().await;
+        let now = chrono::Utc::now().naive_utc();
+        RevokedToken::revoke(&mut conn, RevokedToken { jti: "soon".to_string(), expires_at: now + chrono::Duration::minutes(1) }, now).await.unwrap();
+        RevokedToken::revoke(&mut conn, RevokedToken { jti: "soon".to_string(), expires_at: now + chrono::Duration::minutes(1) }, now).await.unwrap();
+        assert!(RevokedToken::is_revoked(&mut conn, "soon").await.unwrap());
+        assert!(!RevokedToken::is_revoked(&mut conn, "never").await.unwrap());
+
+        RevokedToken::revoke(&mut conn, RevokedToken { jti: "later".to_string(), expires_at: now + chrono::Duration::hours(1) }, now + chrono::Duration::minutes(2)).await.unwrap();
+        assert!(!RevokedToken::is_revoked(&mut conn, "soon").await.unwrap());
+        assert!(RevokedToken::is_revoked(&mut conn, "later").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn status_names_are_unique() {
+        let mut conn = test_support::conn().await;
+        let existing = TaskStatus::read(&mut conn, 1).await.unwrap().unwrap();
+        let err = TaskStatus::create(&mut conn, NewTaskStatus { status_name: &existing.status_name }).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<diesel::result::Error>(),
+            Some(diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn a_partial_update_changes_only_what_is_given() {
+        let mut conn = test_support::conn().await;
+        let unchanged = UserTask::update_partial(&mut conn, None, (1, 2), None, UserTaskChanges::default()).await.unwrap();
+        assert_eq!(unchanged.task_status_id, 1);
+        let moved = UserTask::update_partial(&mut conn, None, (1, 2), None, UserTaskChanges { task_status_id: Some(3) }).await.unwrap();
+        assert_eq!(moved.task_status_id, 3);
+
+        let renamed = TaskStatus::update_partial(&mut conn, None, 1, None, TaskStatusChanges { status_name: Some("Backlog") }).await.unwrap();
+        assert_eq!(renamed.status_name, "Backlog");
+        let err = TaskStatus::update_partial(&mut conn, None, 9999, None, TaskStatusChanges::default()).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<diesel::result::Error>(), Some(diesel::result::Error::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn a_failing_bulk_item_does_not_undo_the_others() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Stock the shelves").await;
+        let outcomes = UserTask::create_many(&mut conn, None, vec![
+            NewUserTask { user_id: 1, task_id: task.task_id, task_status_id: 1 },
+            NewUserTask { user_id: 1, task_id: task.task_id, task_status_id: 2 },
+            NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 },
+        ]).await.unwrap();
+        assert!(outcomes[0].is_ok());
+        assert!(outcomes[1].is_err());
+        assert!(outcomes[2].is_ok());
+        assert_eq!(UserTask::read(&mut conn, (1, task.task_id)).await.unwrap().unwrap().task_status_id, 1);
+        assert!(UserTask::read(&mut conn, (2, task.task_id)).await.unwrap().is_some());
+
+        let deleted = UserTask::delete_many(&mut conn, None, vec![(1, task.task_id), (3, task.task_id)]).await.unwrap();
+        assert_eq!(deleted.into_iter().map(Result::unwrap).collect::<Vec<_>>(), [1, 0]);
+    }
+
+    #[tokio::test]
+    async fn batch_reads_return_only_the_requested_ids() {
+        let mut conn = test_support::conn().await;
+        let sort = Sort::parse(None, None, sorting::TASK_SORT_COLUMNS).unwrap();
+        let page = Task::read_page_by_ids(&mut conn, &[3, 1, 9999], 1, 10, &sort).await.unwrap();
+        let ids: Vec<i32> = page.items.iter().map(|task| task.task_id).collect();
+        assert_eq!(ids, [1, 3]);
+        assert_eq!(page.total, 2);
+    }
+
+    #[tokio::test]
+    async fn upserting_twice_moves_the_one_assignment() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Rotate the logs").await;
+        UserTask::upsert(&mut conn, None, NewUserTask { user_id: 1, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        let moved = UserTask::upsert(&mut conn, None, NewUserTask { user_id: 1, task_id: task.task_id, task_status_id: 2 }).await.unwrap();
+        assert_eq!(moved.task_status_id, 2);
+        let filter = AssignmentFilter { task_id: Some(task.task_id), ..AssignmentFilter::default() };
+        let sort = Sort::parse(None, None, sorting::USER_TASK_SORT_COLUMNS).unwrap();
+        assert_eq!(UserTask::read_page_filtered(&mut conn, &filter, 1, 10, &sort).await.unwrap().total, 1);
+    }
+
+    #[tokio::test]
+    async fn soft_deleted_tasks_come_back_with_their_assignments() {
+        let mut conn = test_support::conn().await;
+        let started = chrono::Utc::now().naive_utc() - chrono::Duration::seconds(1);
+        let task = create_task(&mut conn, "Book the room").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        UserTask::create(&mut conn, NewUserTask { user_id: 3, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        // removed on its own before the task went, so restoring the task leaves it in the trash
+        UserTask::delete(&mut conn, (3, task.task_id)).await.unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        assert_eq!(Task::delete(&mut conn, task.task_id).await.unwrap(), 1);
+        assert!(Task::read(&mut conn, task.task_id).await.unwrap().is_none());
+        assert!(UserTask::read(&mut conn, (2, task.task_id)).await.unwrap().is_none());
+        assert!(Task::read_all(&mut conn).await.unwrap().iter().all(|t| t.task_id != task.task_id));
+        assert_eq!(Task::read_deleted(&mut conn, started).await.unwrap().iter().map(|t| t.task_id).collect::<Vec<_>>(), vec![task.task_id]);
+
+        let restored = Task::restore(&mut conn, None, task.task_id).await.unwrap().unwrap();
+        assert!(restored.deleted_at.is_none());
+        assert!(UserTask::read(&mut conn, (2, task.task_id)).await.unwrap().is_some());
+        assert!(UserTask::read(&mut conn, (3, task.task_id)).await.unwrap().is_none());
+        assert!(Task::restore(&mut conn, None, task.task_id).await.unwrap().is_none());
+        assert!(Task::read_deleted(&mut conn, started).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_trashed_status_frees_its_name() {
+        let mut conn = test_support::conn().await;
+        let status = TaskStatus::create(&mut conn, NewTaskStatus { status_name: "Parked" }).await.unwrap();
+        assert_eq!(TaskStatus::delete(&mut conn, status.task_status_id).await.unwrap(), 1);
+        let again = TaskStatus::create(&mut conn, NewTaskStatus { status_name: "Parked" }).await.unwrap();
+        assert_ne!(again.task_status_id, status.task_status_id);
+        // restoring the old one now clashes with the new name
+        assert!(TaskStatus::restore(&mut conn, None, status.task_status_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn purging_keeps_tasks_that_are_still_referenced() {
+        let mut conn = test_support::conn().await;
+        let lonely = create_task(&mut conn, "Nobody's job").await;
+        let assigned = create_task(&mut conn, "Somebody's job").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: assigned.task_id, task_status_id: 1 }).await.unwrap();
+        Task::delete(&mut conn, lonely.task_id).await.unwrap();
+        Task::delete(&mut conn, assigned.task_id).await.unwrap();
+        UserTask::restore(&mut conn, None, (2, assigned.task_id)).await.unwrap();
+
+        let later = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1);
+        assert_eq!(Task::purge_deleted(&mut conn, later).await.unwrap().0, 1);
+        assert!(Task::restore(&mut conn, None, lonely.task_id).await.unwrap().is_none());
+        assert!(Task::restore(&mut conn, None, assigned.task_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn purging_keeps_statuses_a_trashed_assignment_still_uses() {
+        let mut conn = test_support::conn().await;
+        let status = TaskStatus::create(&mut conn, NewTaskStatus { status_name: "Parked" }).await.unwrap();
+        let task = create_task(&mut conn, "Wait for the parts").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: status.task_status_id }).await.unwrap();
+        UserTask::delete(&mut conn, (2, task.task_id)).await.unwrap();
+        TaskStatus::delete(&mut conn, status.task_status_id).await.unwrap();
+
+        let later = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1);
+        assert_eq!(TaskStatus::purge_deleted(&mut conn, later).await.unwrap(), 0);
+        // the purge route clears assignments first, which frees the status
+        assert_eq!(UserTask::purge_deleted(&mut conn, later).await.unwrap(), 1);
+        assert_eq!(TaskStatus::purge_deleted(&mut conn, later).await.unwrap(), 1);
+        assert!(TaskStatus::restore(&mut conn, None, status.task_status_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn updates_move_updated_at_but_not_created_at() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Water the plants").await;
+        assert_eq!(task.created_at, task.updated_at);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let updated = Task::update(&mut conn, task.task_id, new_task("Water the ferns")).await.unwrap();
+        assert_eq!(updated.created_at, task.created_at);
+        assert!(updated.updated_at > task.updated_at);
+
+        let sort = Sort::parse(Some("updated_at"), Some("desc"), sorting::TASK_SORT_COLUMNS).unwrap();
+        assert_eq!(Task::read_page(&mut conn, 1, 1, &sort).await.unwrap().items[0].task_id, task.task_id);
+    }
+
+    #[tokio::test]
+    async fn updates_only_apply_at_the_expected_version() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Order lunch").await;
+        let moved = Task::update_audited(&mut conn, None, task.task_id, Some(task.version), new_task("Order dinner")).await.unwrap();
+        assert_eq!(moved.version, task.version + 1);
+
+        let err = Task::update_audited(&mut conn, None, task.task_id, Some(task.version), new_task("Order breakfast")).await.unwrap_err();
+        let stale = err.downcast_ref::<versioning::StaleVersion>().unwrap();
+        assert_eq!((stale.expected, stale.current), (task.version, moved.version));
+        assert_eq!(Task::read(&mut conn, task.task_id).await.unwrap().unwrap().task_name, "Order dinner");
+
+        // no If-Match: last write wins
+        assert_eq!(Task::update_audited(&mut conn, None, task.task_id, None, new_task("Order brunch")).await.unwrap().version, moved.version + 1);
+
+        let missing = Task::update_audited(&mut conn, None, 9999, Some(1), new_task("Nothing")).await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<diesel::result::Error>(), Some(diesel::result::Error::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn the_joined_page_matches_the_plain_one() {
+        let mut conn = test_support::conn().await;
+        let filter = AssignmentFilter { user_id: Some(2), ..AssignmentFilter::default() };
+        let sort = Sort::parse(Some("task_id"), Some("desc"), sorting::USER_TASK_SORT_COLUMNS).unwrap();
+        let plain = UserTask::read_page_filtered(&mut conn, &filter, 1, 3, &sort).await.unwrap();
+        let joined = UserTask::read_page_joined(&mut conn, &filter, 1, 3, &sort).await.unwrap();
+        assert_eq!(joined.total, plain.total);
+        for ((user_task, user, task, status), plain) in joined.items.iter().zip(&plain.items) {
+            assert_eq!((user_task.user_id, user_task.task_id), (plain.user_id, plain.task_id));
+            assert_eq!(user.user_id, user_task.user_id);
+            assert_eq!(task.task_id, user_task.task_id);
+            assert_eq!(status.task_status_id, user_task.task_status_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn assignment_details_carry_the_names() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Label the boxes").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        let filter = AssignmentFilter { task_id: Some(task.task_id), ..AssignmentFilter::default() };
+        let sort = Sort::parse(None, None, sorting::USER_TASK_SORT_COLUMNS).unwrap();
+        let page = AssignmentDetail::read_page(&mut conn, &filter, 1, 10, &sort).await.unwrap();
+        assert_eq!(page.total, 1);
+        let detail = &page.items[0];
+        assert_eq!(detail.task_name, "Label the boxes");
+        assert_eq!(detail.user_name, User::read(&mut conn, 2).await.unwrap().unwrap().name);
+        assert_eq!(detail.status_name, TaskStatus::read(&mut conn, 1).await.unwrap().unwrap().status_name);
+    }
+
+    #[tokio::test]
+    async fn a_users_assignments_can_be_narrowed_by_status() {
+        let mut conn = test_support::conn().await;
+        let sort = Sort::parse(None, None, sorting::USER_TASK_SORT_COLUMNS).unwrap();
+        let all = UserTask::read_by_user(&mut conn, 1, None, 1, 100, &sort).await.unwrap();
+        assert!(all.items.iter().all(|ut| ut.user_id == 1));
+        let todo = UserTask::read_by_user(&mut conn, 1, Some(2), 1, 100, &sort).await.unwrap();
+        assert!(todo.items.iter().all(|ut| ut.user_id == 1 && ut.task_status_id == 2));
+        assert!(todo.total > 0 && todo.total < all.total);
+    }
+
+    #[tokio::test]
+    async fn a_tasks_assignments_list_everyone_on_it() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        for user_id in [3, 1, 2] {
+            UserTask::create(&mut conn, NewUserTask { user_id, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        }
+        UserTask::update(&mut conn, (2, task.task_id), NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 3 }).await.unwrap();
+        let sort = Sort::parse(None, None, sorting::USER_TASK_SORT_COLUMNS).unwrap();
+        let users: Vec<i32> = UserTask::read_by_task(&mut conn, task.task_id, None, 1, 10, &sort).await.unwrap().items.iter().map(|ut| ut.user_id).collect();
+        assert_eq!(users, [1, 2, 3]);
+        assert_eq!(UserTask::read_by_task(&mut conn, task.task_id, Some(3), 1, 10, &sort).await.unwrap().total, 1);
+    }
+
+    #[tokio::test]
+    async fn counts_leave_out_deleted_rows() {
+        let mut conn = test_support::conn().await;
+        let before = Task::count(&mut conn).await.unwrap();
+        let task = create_task(&mut conn, "Paint the fence").await;
+        assert_eq!(Task::count(&mut conn).await.unwrap(), before + 1);
+        Task::delete(&mut conn, task.task_id).await.unwrap();
+        assert_eq!(Task::count(&mut conn).await.unwrap(), before);
+        let filter = AssignmentFilter { user_id: Some(1), ..Default::default() };
+        let sort = Sort::parse(None, None, sorting::USER_TASK_SORT_COLUMNS).unwrap();
+        assert_eq!(UserTask::count_filtered(&mut conn, &filter).await.unwrap(), UserTask::read_page_filtered(&mut conn, &filter, 1, 100, &sort).await.unwrap().total);
+    }
+
+    #[tokio::test]
+    async fn due_date_filters_are_exclusive_and_skip_undated_tasks() {
+        let mut conn = test_support::conn().await;
+        let date = |day| chrono::NaiveDate::from_ymd_opt(2031, 3, day).unwrap();
+        let mut ids = Vec::new();
+        for (name, due_date) in [("Early", Some(date(1))), ("Middle", Some(date(10))), ("Late", Some(date(20))), ("Someday", None)] {
+            ids.push(Task::create(&mut conn, NewTask { due_date, ..new_task(name) }).await.unwrap().task_id);
+        }
+        let sort = Sort::parse(None, None, sorting::TASK_SORT_COLUMNS).unwrap();
+        let filter = |due_after, due_before| TaskFilter { ids: Some(ids.clone()), due_before, due_after, ..Default::default() };
+        let names = |tasks: Vec<Task>| tasks.into_iter().map(|task| task.task_name).collect::<Vec<_>>();
+        let page = Task::read_page_filtered(&mut conn, &filter(Some(date(1)), Some(date(20))), 1, 10, &sort).await.unwrap();
+        assert_eq!(names(page.items), ["Middle"]);
+        let page = Task::read_page_filtered(&mut conn, &filter(None, Some(date(10))), 1, 10, &sort).await.unwrap();
+        assert_eq!(names(page.items), ["Early"]);
+        assert_eq!(Task::read_page_filtered(&mut conn, &filter(None, None), 1, 10, &sort).await.unwrap().items.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn tasks_sort_most_urgent_first_by_default() {
+        let mut conn = test_support::conn().await;
+        let mut ids = Vec::new();
+        for priority in [TaskPriority::Low, TaskPriority::Urgent, TaskPriority::Medium, TaskPriority::Urgent] {
+            ids.push(Task::create(&mut conn, NewTask { priority, ..new_task("Triage") }).await.unwrap().task_id);
+        }
+        let sort = Sort::parse(None, None, sorting::TASK_SORT_COLUMNS).unwrap();
+        let filter = TaskFilter { ids: Some(ids.clone()), ..Default::default() };
+        let order: Vec<i32> = Task::read_page_filtered(&mut conn, &filter, 1, 10, &sort).await.unwrap().items.iter().map(|task| task.task_id).collect();
+        assert_eq!(order, [ids[1], ids[3], ids[2], ids[0]]);
+
+        let urgent = TaskFilter { priority: Some(TaskPriority::Urgent), ..filter };
+        assert_eq!(Task::read_page_filtered(&mut conn, &urgent, 1, 10, &sort).await.unwrap().total, 2);
+        assert_eq!(TaskPriority::from_name("URGENT"), Some(TaskPriority::Urgent));
+    }
+
+    #[tokio::test]
+    async fn tags_filter_tasks_without_regard_to_case() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let outdoors = Tag::create(&mut conn, NewTag { tag_name: "Outdoors" }).await.unwrap();
+        let weekend = Tag::create(&mut conn, NewTag { tag_name: "weekend" }).await.unwrap();
+        Tag::attach_to_task(&mut conn, task.task_id, &[weekend.tag_id, outdoors.tag_id]).await.unwrap();
+        let tags = Tag::attach_to_task(&mut conn, task.task_id, &[outdoors.tag_id]).await.unwrap();
+        assert_eq!(tags.iter().map(|tag| tag.tag_name.as_str()).collect::<Vec<_>>(), ["Outdoors", "weekend"]);
+
+        let sort = Sort::parse(None, None, sorting::TASK_SORT_COLUMNS).unwrap();
+        let filter = TaskFilter { tag: Some("outdoors".to_string()), ..Default::default() };
+        let found: Vec<i32> = Task::read_page_filtered(&mut conn, &filter, 1, 10, &sort).await.unwrap().items.iter().map(|task| task.task_id).collect();
+        assert_eq!(found, [task.task_id]);
+        assert!(Tag::create(&mut conn, NewTag { tag_name: "OUTDOORS" }).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn deleting_a_tag_takes_it_off_its_tasks() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let tag = Tag::create(&mut conn, NewTag { tag_name: "Outdoors" }).await.unwrap();
+        Tag::attach_to_task(&mut conn, task.task_id, &[tag.tag_id]).await.unwrap();
+        assert_eq!(Tag::delete(&mut conn, tag.tag_id).await.unwrap(), 1);
+        assert!(Tag::read(&mut conn, tag.tag_id).await.unwrap().is_none());
+        assert!(Tag::read_for_task(&mut conn, task.task_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleting_a_team_opens_its_projects_to_everyone() {
+        let mut conn = test_support::conn().await;
+        let team = Team::create(&mut conn, NewTeam { team_name: "Gardeners" }).await.unwrap();
+        assert_eq!(Team::add_members(&mut conn, team.team_id, &[2, 1, 2]).await.unwrap().len(), 2);
+        let project = Project::create(&mut conn, NewProject { project_name: "Orchard", description: None, team_id: Some(team.team_id) }).await.unwrap();
+        let task = Task::create(&mut conn, NewTask { project_id: Some(project.project_id), ..new_task("Prune the apple trees") }).await.unwrap();
+        assert_eq!(Team::for_task(&mut conn, task.task_id).await.unwrap().unwrap().team_id, team.team_id);
+
+        assert_eq!(Team::delete(&mut conn, team.team_id).await.unwrap(), 1);
+        assert!(Team::read(&mut conn, team.team_id).await.unwrap().is_none());
+        assert_eq!(Project::read(&mut conn, project.project_id).await.unwrap().unwrap().team_id, None);
+        assert!(Team::for_task(&mut conn, task.task_id).await.unwrap().is_none());
+        assert_eq!(team_members::table.filter(team_members::team_id.eq(team.team_id)).count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn purging_a_tagged_task_drops_its_tags() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let tag = Tag::create(&mut conn, NewTag { tag_name: "Outdoors" }).await.unwrap();
+        Tag::attach_to_task(&mut conn, task.task_id, &[tag.tag_id]).await.unwrap();
+        Task::delete(&mut conn, task.task_id).await.unwrap();
+        Task::purge_deleted(&mut conn, chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1)).await.unwrap();
+        assert!(tasks::table.find(task.task_id).first::<Task>(&mut conn).await.optional().unwrap().is_none());
+        assert!(Tag::read(&mut conn, tag.tag_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn a_tasks_thread_reads_oldest_first() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let other = create_task(&mut conn, "Mend the gate").await;
+        for (task_id, body) in [(task.task_id, "Which colour?"), (other.task_id, "Elsewhere"), (task.task_id, "Green")] {
+            Comment::create(&mut conn, NewComment { task_id, author_id: 2, body }).await.unwrap();
+        }
+        let sort = Sort::parse(None, None, sorting::COMMENT_SORT_COLUMNS).unwrap();
+        let thread = Comment::read_page_for_task(&mut conn, task.task_id, 1, 10, &sort).await.unwrap();
+        assert_eq!(thread.total, 2);
+        assert_eq!(thread.items.iter().map(|comment| comment.body.as_str()).collect::<Vec<_>>(), ["Which colour?", "Green"]);
+    }
+
+    #[tokio::test]
+    async fn purging_a_task_drops_its_comments() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let comment = Comment::create(&mut conn, NewComment { task_id: task.task_id, author_id: 2, body: "Which colour?" }).await.unwrap();
+        Comment::edit(&mut conn, Some(2), comment.comment_id, None, "Which colour, @alice?").await.unwrap();
+        Task::delete(&mut conn, task.task_id).await.unwrap();
+        assert_eq!(Task::purge_deleted(&mut conn, chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1)).await.unwrap().0, 1);
+        assert!(Comment::read(&mut conn, comment.comment_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn edits_keep_the_earlier_wording() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let comment = Comment::create(&mut conn, NewComment { task_id: task.task_id, author_id: 2, body: "Red" }).await.unwrap();
+        Comment::edit(&mut conn, Some(2), comment.comment_id, Some(1), "Green").await.unwrap();
+        assert!(Comment::edit(&mut conn, Some(1), comment.comment_id, Some(1), "Blue").await.is_err());
+        let edited = Comment::edit(&mut conn, Some(1), comment.comment_id, Some(2), "Blue").await.unwrap();
+        assert_eq!((edited.body.as_str(), edited.version), ("Blue", 3));
+
+        let history = CommentRevision::read_history(&mut conn, comment.comment_id, 1, 10).await.unwrap();
+        let wording: Vec<(i32, &str, Option<i32>)> = history.items.iter().map(|revision| (revision.version, revision.body.as_str(), revision.edited_by)).collect();
+        assert_eq!(wording, [(2, "Green", Some(1)), (1, "Red", Some(2))]);
+    }
+
+    #[tokio::test]
+    async fn deleting_an_edited_comment_takes_its_history() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let comment = Comment::create(&mut conn, NewComment { task_id: task.task_id, author_id: 2, body: "Red" }).await.unwrap();
+        Comment::edit(&mut conn, Some(2), comment.comment_id, None, "Green").await.unwrap();
+        assert_eq!(Comment::delete(&mut conn, comment.comment_id).await.unwrap(), 1);
+        assert_eq!(CommentRevision::read_history(&mut conn, comment.comment_id, 1, 10).await.unwrap().total, 0);
+    }
+
+    #[tokio::test]
+    async fn a_tasks_attachments_list_in_upload_order() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let upload = |file_name, storage_key| NewAttachment {
+            task_id: task.task_id,
+            uploaded_by: 2,
+            file_name,
+            content_type: "image/png",
+            size_bytes: 12,
+            storage_key,
+        };
+        let before = Attachment::create(&mut conn, upload("before.png", "key-1")).await.unwrap();
+        let after = Attachment::create(&mut conn, upload("after.png", "key-2")).await.unwrap();
+        let names = |attachments: Vec<Attachment>| attachments.into_iter().map(|attachment| attachment.file_name).collect::<Vec<_>>();
+        assert_eq!(names(Attachment::read_for_task(&mut conn, task.task_id).await.unwrap()), ["before.png", "after.png"]);
+
+        assert_eq!(Attachment::delete(&mut conn, before.attachment_id).await.unwrap(), 1);
+        assert_eq!(names(Attachment::read_for_task(&mut conn, task.task_id).await.unwrap()), ["after.png"]);
+        assert!(Attachment::read(&mut conn, after.attachment_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn purging_a_task_takes_its_attachments_and_hands_them_back() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let attachment = Attachment::create(&mut conn, NewAttachment {
+            task_id: task.task_id,
+            uploaded_by: 2,
+            file_name: "before.png",
+            content_type: "image/png",
+            size_bytes: 12,
+            storage_key: "key-1",
+        }).await.unwrap();
+        Task::delete(&mut conn, task.task_id).await.unwrap();
+
+        let (count, attachments) = Task::purge_deleted(&mut conn, chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1)).await.unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(attachments.iter().map(|attachment| attachment.storage_key.as_str()).collect::<Vec<_>>(), ["key-1"]);
+        assert!(Attachment::read(&mut conn, attachment.attachment_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn a_task_cannot_sit_under_its_own_subtasks() {
+        let mut conn = test_support::conn().await;
+        let parent = create_task(&mut conn, "Move house").await;
+        let child = Task::create(&mut conn, NewTask { parent_task_id: Some(parent.task_id), ..new_task("Pack the boxes") }).await.unwrap();
+        let grandchild = Task::create(&mut conn, NewTask { parent_task_id: Some(child.task_id), ..new_task("Buy tape") }).await.unwrap();
+        assert!(Task::would_create_cycle(&mut conn, parent.task_id, grandchild.task_id).await.unwrap());
+        assert!(Task::would_create_cycle(&mut conn, parent.task_id, parent.task_id).await.unwrap());
+        assert!(!Task::would_create_cycle(&mut conn, grandchild.task_id, parent.task_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn subtasks_are_complete_once_every_assignee_is_done() {
+        let mut conn = test_support::conn().await;
+        let parent = create_task(&mut conn, "Move house").await;
+        let mut subtasks = Vec::new();
+        for name in ["Pack the boxes", "Book the van", "Clean the oven"] {
+            subtasks.push(Task::create(&mut conn, NewTask { parent_task_id: Some(parent.task_id), ..new_task(name) }).await.unwrap().task_id);
+        }
+        // done by both; done by one of two; nobody assigned
+        for (user_id, task_id, task_status_id) in [(1, subtasks[0], 3), (2, subtasks[0], 3), (1, subtasks[1], 3), (2, subtasks[1], 2)] {
+            UserTask::create(&mut conn, NewUserTask { user_id, task_id, task_status_id }).await.unwrap();
+        }
+        assert_eq!(Task::read_subtasks(&mut conn, parent.task_id).await.unwrap().len(), 3);
+        assert_eq!(Task::count_completed_subtasks(&mut conn, parent.task_id, &["Completed".to_string()]).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn purging_a_parent_leaves_its_subtasks_at_the_top() {
+        let mut conn = test_support::conn().await;
+        let parent = create_task(&mut conn, "Move house").await;
+        let child = Task::create(&mut conn, NewTask { parent_task_id: Some(parent.task_id), ..new_task("Pack the boxes") }).await.unwrap();
+        Task::delete(&mut conn, parent.task_id).await.unwrap();
+        Task::purge_deleted(&mut conn, chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1)).await.unwrap();
+        assert_eq!(Task::read(&mut conn, child.task_id).await.unwrap().unwrap().parent_task_id, None);
+    }
+
+    #[tokio::test]
+    async fn dependencies_cannot_loop_back() {
+        let mut conn = test_support::conn().await;
+        let design = create_task(&mut conn, "Design").await.task_id;
+        let build = create_task(&mut conn, "Build").await.task_id;
+        let ship = create_task(&mut conn, "Ship").await.task_id;
+        TaskDependency::link(&mut conn, design, build).await.unwrap();
+        TaskDependency::link(&mut conn, build, ship).await.unwrap();
+        TaskDependency::link(&mut conn, build, ship).await.unwrap();
+        assert!(TaskDependency::would_create_cycle(&mut conn, ship, design).await.unwrap());
+        assert!(TaskDependency::would_create_cycle(&mut conn, design, design).await.unwrap());
+        assert!(!TaskDependency::would_create_cycle(&mut conn, design, ship).await.unwrap());
+        assert_eq!(TaskDependency::read_blocked(&mut conn, build).await.unwrap().iter().map(|task| task.task_id).collect::<Vec<_>>(), [ship]);
+    }
+
+    #[tokio::test]
+    async fn a_blocker_stays_open_until_its_assignees_are_done() {
+        let mut conn = test_support::conn().await;
+        let design = create_task(&mut conn, "Design").await.task_id;
+        let build = create_task(&mut conn, "Build").await.task_id;
+        TaskDependency::link(&mut conn, design, build).await.unwrap();
+        let completed = ["Completed".to_string()];
+        assert_eq!(TaskDependency::open_blocker_ids(&mut conn, build, &completed).await.unwrap(), [design]);
+        UserTask::create(&mut conn, NewUserTask { user_id: 1, task_id: design, task_status_id: 3 }).await.unwrap();
+        assert!(TaskDependency::open_blocker_ids(&mut conn, build, &completed).await.unwrap().is_empty());
+        assert_eq!(TaskDependency::unlink(&mut conn, design, build).await.unwrap(), 1);
+        assert!(TaskDependency::read_blockers(&mut conn, build).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn purging_a_blocker_drops_its_dependencies() {
+        let mut conn = test_support::conn().await;
+        let design = create_task(&mut conn, "Design").await.task_id;
+        let build = create_task(&mut conn, "Build").await.task_id;
+        TaskDependency::link(&mut conn, design, build).await.unwrap();
+        Task::delete(&mut conn, design).await.unwrap();
+        Task::purge_deleted(&mut conn, chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1)).await.unwrap();
+        assert!(Task::read(&mut conn, design).await.unwrap().is_none());
+        assert_eq!(task_dependencies::table.filter(task_dependencies::blocked_task_id.eq(build)).count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn cloning_copies_the_subtree_and_tags() {
+        let mut conn = test_support::conn().await;
+        let parent = create_task(&mut conn, "Move house").await;
+        let child = Task::create(&mut conn, NewTask { parent_task_id: Some(parent.task_id), ..new_task("Pack the boxes") }).await.unwrap();
+        Task::create(&mut conn, NewTask { parent_task_id: Some(child.task_id), ..new_task("Buy tape") }).await.unwrap();
+        let tag = Tag::create(&mut conn, NewTag { tag_name: "Home" }).await.unwrap();
+        Tag::attach_to_task(&mut conn, child.task_id, &[tag.tag_id]).await.unwrap();
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: child.task_id, task_status_id: 3 }).await.unwrap();
+
+        let copy = Task::duplicate(&mut conn, Some(1), child.task_id, true).await.unwrap().unwrap();
+        assert_ne!(copy.task_id, child.task_id);
+        assert_eq!(copy.parent_task_id, Some(parent.task_id));
+        assert_eq!(Tag::read_for_task(&mut conn, copy.task_id).await.unwrap().len(), 1);
+        assert_eq!(Task::read_subtasks(&mut conn, copy.task_id).await.unwrap().iter().map(|task| task.task_name.as_str()).collect::<Vec<_>>(), ["Buy tape"]);
+        assert_eq!(UserTask::read(&mut conn, (2, copy.task_id)).await.unwrap().unwrap().task_status_id, 1);
+
+        let bare = Task::duplicate(&mut conn, Some(1), child.task_id, false).await.unwrap().unwrap();
+        assert!(UserTask::read(&mut conn, (2, bare.task_id)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn tasks_filter_by_project() {
+        let mut conn = test_support::conn().await;
+        let project = Project::create(&mut conn, NewProject { project_name: "Garden", description: None, team_id: None }).await.unwrap();
+        Task::create(&mut conn, NewTask { project_id: Some(project.project_id), ..new_task("Plant the roses") }).await.unwrap();
+        let trashed = Task::create(&mut conn, NewTask { project_id: Some(project.project_id), ..new_task("Dig the pond") }).await.unwrap();
+        create_task(&mut conn, "Wash the car").await;
+        Task::delete(&mut conn, trashed.task_id).await.unwrap();
+        let filter = TaskFilter { project_id: Some(project.project_id), ..Default::default() };
+        let sort = Sort::parse(None, None, sorting::TASK_SORT_COLUMNS).unwrap();
+        let page = Task::read_page_filtered(&mut conn, &filter, 1, 10, &sort).await.unwrap();
+        assert_eq!(page.items.iter().map(|task| task.task_name.as_str()).collect::<Vec<_>>(), ["Plant the roses"]);
+    }
+
+    #[tokio::test]
+    async fn a_projects_team_decides_who_it_is_for() {
+        let mut conn = test_support::conn().await;
+        let team = Team::create(&mut conn, NewTeam { team_name: "Gardeners" }).await.unwrap();
+        assert_eq!(Team::add_members(&mut conn, team.team_id, &[2, 1, 2]).await.unwrap().len(), 2);
+        let project = Project::create(&mut conn, NewProject { project_name: "Orchard", description: None, team_id: Some(team.team_id) }).await.unwrap();
+        let task = Task::create(&mut conn, NewTask { project_id: Some(project.project_id), ..new_task("Prune the apple trees") }).await.unwrap();
+        assert_eq!(Team::for_task(&mut conn, task.task_id).await.unwrap().unwrap().team_id, team.team_id);
+        let elsewhere = create_task(&mut conn, "Wash the car").await;
+        assert!(Team::for_task(&mut conn, elsewhere.task_id).await.unwrap().is_none());
+
+        assert_eq!(Team::remove_member(&mut conn, team.team_id, 2).await.unwrap(), 1);
+        assert!(!Team::is_member(&mut conn, team.team_id, 2).await.unwrap());
+        assert_eq!(Team::read_members(&mut conn, team.team_id).await.unwrap().iter().map(|member| member.user_id).collect::<Vec<_>>(), [1]);
+    }
+
+    #[tokio::test]
+    async fn another_tenants_rows_are_out_of_reach() {
+        let mut conn = test_support::conn().await;
+        let ours = create_task(&mut conn, "Ours").await;
+        let other = Tenant::create(&mut conn, "Other").await.unwrap();
+        tenancy::enter(&mut conn, other.tenant_id).await.unwrap();
+
+        assert!(Task::read(&mut conn, ours.task_id).await.unwrap().is_none());
+        assert!(Task::read_all(&mut conn).await.unwrap().is_empty());
+        assert_eq!(Task::count(&mut conn).await.unwrap(), 0);
+        assert!(User::read(&mut conn, 1).await.unwrap().is_none());
+        assert_eq!(Task::delete(&mut conn, ours.task_id).await.unwrap(), 0);
+        assert!(Task::update(&mut conn, ours.task_id, new_task("Theirs now")).await.is_err());
+        assert!(Task::restore(&mut conn, None, ours.task_id).await.unwrap().is_none());
+        let theirs = create_task(&mut conn, "Theirs").await;
+
+        tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await.unwrap();
+        assert_eq!(Task::read(&mut conn, ours.task_id).await.unwrap().unwrap().task_name, "Ours");
+        assert!(Task::read(&mut conn, theirs.task_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn a_connection_that_left_its_tenant_sees_nothing() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Lock up").await;
+        tenancy::leave(&mut conn).await.unwrap();
+        assert!(Task::read(&mut conn, task.task_id).await.unwrap().is_none());
+        assert!(User::read_all(&mut conn).await.unwrap().is_empty());
+        assert!(Task::create(&mut conn, new_task("Sneak in")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn starred_tasks_list_like_any_task_list() {
+        let mut conn = test_support::conn().await;
+        let sort = Sort::parse(Some("task_name"), None, sorting::TASK_SORT_COLUMNS).unwrap();
+        let starred_by = |user_id| TaskFilter { starred_by: Some(user_id), ..Default::default() };
+        let names = |tasks: Vec<Task>| tasks.into_iter().map(|task| task.task_name).collect::<Vec<_>>();
+        let fence = create_task(&mut conn, "Paint the fence").await;
+        let gate = create_task(&mut conn, "Mend the gate").await;
+        StarredTask::star(&mut conn, 2, fence.task_id).await.unwrap();
+        StarredTask::star(&mut conn, 2, fence.task_id).await.unwrap();
+        StarredTask::star(&mut conn, 2, gate.task_id).await.unwrap();
+        assert_eq!(names(Task::read_page_filtered(&mut conn, &starred_by(2), 1, 10, &sort).await.unwrap().items), ["Mend the gate", "Paint the fence"]);
+        assert!(names(Task::read_page_filtered(&mut conn, &starred_by(3), 1, 10, &sort).await.unwrap().items).is_empty());
+
+        assert_eq!(StarredTask::unstar(&mut conn, 2, gate.task_id).await.unwrap(), 1);
+        Task::delete(&mut conn, fence.task_id).await.unwrap();
+        assert!(names(Task::read_page_filtered(&mut conn, &starred_by(2), 1, 10, &sort).await.unwrap().items).is_empty());
+        assert_eq!(Task::purge_deleted(&mut conn, chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1)).await.unwrap().0, 1);
+        assert_eq!(starred_tasks::table.count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn nobody_starts_as_an_admin() {
+        let mut conn = test_support::conn().await;
+        let admins = User::read_all(&mut conn).await.unwrap().into_iter().filter(|user| user.role_id == UserRole::Admin as i32).count();
+        assert_eq!(admins, 0);
+    }
+
+    #[tokio::test]
+    async fn bootstraps_the_named_admin_once() {
+        let mut conn = test_support::conn().await;
+        let admin = User::bootstrap_admin(&mut conn, "charlie@example.com").await.unwrap().unwrap();
+        assert_eq!(admin.role_id, UserRole::Admin as i32);
+        let again = User::bootstrap_admin(&mut conn, "charlie@example.com").await.unwrap().unwrap();
+        assert_eq!(again.version, admin.version);
+        assert!(User::bootstrap_admin(&mut conn, "nobody@example.com").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn seeded_users_have_no_password() {
+        let mut conn = test_support::conn().await;
+        assert!(Credential::read(&mut conn, 1).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn setting_a_password_signs_the_user_out() {
+        let mut conn = test_support::conn().await;
+        let expires_at = chrono::Utc::now().naive_utc() + chrono::Duration::days(1);
+        RefreshToken::create(&mut conn, NewRefreshToken { user_id: 3, token_hash: "abc", expires_at }).await.unwrap();
+        assert!(Credential::set_password(&mut conn, 3, "first").await.unwrap().is_some());
+        assert!(Credential::set_password(&mut conn, 3, "second").await.unwrap().is_some());
+        assert_eq!(Credential::read(&mut conn, 3).await.unwrap().unwrap().password_hash, "second");
+        assert!(RefreshToken::find_active_by_hash(&mut conn, "abc", chrono::Utc::now().naive_utc()).await.unwrap().is_none());
+        assert!(Credential::set_password(&mut conn, 9999, "third").await.unwrap().is_none());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn drop_default_passwords_keeps_everyone_elses() {
+        use diesel_async::SimpleAsyncConnection;
+        let mut conn = test_support::conn().await;
+        let published = "$argon2id$v=19$m=19456,t=2,p=1$Ojet9BRO7av8DuJfAg6YWw$GvWzWvQ8D5a2hA10ArUJMDYzln0wFntmwkjkWYRYb3Q";
+        Credential::set_password(&mut conn, 1, published).await.unwrap();
+        Credential::set_password(&mut conn, 2, "$argon2id$something-else").await.unwrap();
+        conn.batch_execute(include_str!("../migrations/2026-10-14-003500_drop_default_passwords/up.sql")).await.unwrap();
+        assert!(Credential::read(&mut conn, 1).await.unwrap().is_none());
+        assert!(Credential::read(&mut conn, 2).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn oauth_links_only_to_the_same_email_in_the_tenant() {
+        let mut conn = test_support::conn().await;
+        let other = Tenant::create(&mut conn, "Other").await.unwrap();
+        tenancy::enter(&mut conn, other.tenant_id).await.unwrap();
+        let theirs = User::create(&mut conn, NewUser { name: "Dana", email: "dana@example.com", active: true }).await.unwrap();
+        tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await.unwrap();
+
+        let linked = OAuthIdentity::find_or_link_user(&mut conn, "github", "42", "dana@example.com", "Dana").await.unwrap();
+        assert_ne!(linked.user_id, theirs.user_id);
+        assert_eq!(linked.tenant_id, tenancy::DEFAULT_TENANT);
+
+        let ours = OAuthIdentity::find_or_link_user(&mut conn, "github", "43", "charlie@example.com", "Charlie").await.unwrap();
+        assert_eq!(ours.user_id, 3);
+        let again = OAuthIdentity::find_or_link_user(&mut conn, "github", "43", "someone@else.com", "Charlie").await.unwrap();
+        assert_eq!(again.user_id, 3);
+    }
+
+    #[tokio::test]
+    async fn assignment_patches_check_the_version_in_the_update() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Water the plants").await;
+        let assignment = UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        let changes = || UserTaskChanges { task_status_id: Some(2) };
+        let moved = UserTask::update_partial(&mut conn, None, (2, task.task_id), Some(assignment.version), changes()).await.unwrap();
+        assert_eq!(moved.task_status_id, 2);
+        let err = UserTask::update_partial(&mut conn, None, (2, task.task_id), Some(assignment.version), changes()).await.unwrap_err();
+        assert!(err.downcast_ref::<versioning::StaleVersion>().is_some());
+        let err = UserTask::update_partial(&mut conn, None, (2, task.task_id), Some(assignment.version), UserTaskChanges::default()).await.unwrap_err();
+        assert!(err.downcast_ref::<versioning::StaleVersion>().is_some());
+    }
+
+    #[tokio::test]
+    async fn deleting_a_project_leaves_its_tasks_without_one() {
+        let mut conn = test_support::conn().await;
+        let project = Project::create(&mut conn, NewProject { project_name: "Garden", description: None, team_id: None }).await.unwrap();
+        let task = Task::create(&mut conn, NewTask { project_id: Some(project.project_id), ..new_task("Plant the roses") }).await.unwrap();
+        let trashed = Task::create(&mut conn, NewTask { project_id: Some(project.project_id), ..new_task("Dig the pond") }).await.unwrap();
+        Task::delete(&mut conn, trashed.task_id).await.unwrap();
+        let filter = TaskFilter { project_id: Some(project.project_id), ..Default::default() };
+        let sort = Sort::parse(None, None, sorting::TASK_SORT_COLUMNS).unwrap();
+        assert_eq!(Task::read_page_filtered(&mut conn, &filter, 1, 10, &sort).await.unwrap().total, 1);
+
+        assert_eq!(Project::delete(&mut conn, project.project_id).await.unwrap(), 1);
+        assert!(Project::read(&mut conn, project.project_id).await.unwrap().is_none());
+        assert_eq!(Task::read(&mut conn, task.task_id).await.unwrap().unwrap().project_id, None);
+        let trashed = tasks::table.find(trashed.task_id).first::<Task>(&mut conn).await.unwrap();
+        assert_eq!(trashed.project_id, None);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_user_takes_their_assignments_and_sign_in_with_them() {
+        let mut conn = test_support::conn().await;
+        let user = User::create(&mut conn, NewUser { name: "Erin", email: "erin@example.com", active: true }).await.unwrap();
+        let task = create_task(&mut conn, "Sweep up").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: user.user_id, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        Credential::set_password(&mut conn, user.user_id, "hash").await.unwrap();
+        let key = ApiKey::create(&mut conn, NewApiKey { user_id: user.user_id, name: "ci", key_prefix: "abc", key_hash: "def", daily_quota: Some(100), monthly_quota: None }).await.unwrap();
+        quotas::record_request(&mut conn, &key, chrono::Utc::now().date_naive()).await.unwrap();
+
+        assert_eq!(User::delete(&mut conn, user.user_id).await.unwrap(), 1);
+        assert!(UserTask::read(&mut conn, (user.user_id, task.task_id)).await.unwrap().is_none());
+        assert!(UserTask::read(&mut conn, (2, task.task_id)).await.unwrap().is_some());
+        assert!(Credential::read(&mut conn, user.user_id).await.unwrap().is_none());
+        assert!(ApiKey::read_all_for_user(&mut conn, user.user_id).await.unwrap().is_empty());
+        assert_eq!(api_key_usage::table.count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+        assert_eq!(User::delete(&mut conn, user.user_id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn reassigning_keeps_statuses_and_the_new_users_own_assignments() {
+        let mut conn = test_support::conn().await;
+        let shared = create_task(&mut conn, "Shared").await;
+        let handed_over = create_task(&mut conn, "Handed over").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 3, task_id: shared.task_id, task_status_id: 2 }).await.unwrap();
+        UserTask::create(&mut conn, NewUserTask { user_id: 3, task_id: handed_over.task_id, task_status_id: 2 }).await.unwrap();
+        UserTask::create(&mut conn, NewUserTask { user_id: 5, task_id: shared.task_id, task_status_id: 1 }).await.unwrap();
+        let charlies = UserTask::read_all(&mut conn).await.unwrap().into_iter().filter(|assignment| assignment.user_id == 3).count();
+
+        assert_eq!(UserTask::reassign_all(&mut conn, None, 3, 5).await.unwrap(), charlies);
+        assert!(UserTask::read_all(&mut conn).await.unwrap().iter().all(|assignment| assignment.user_id != 3));
+        assert_eq!(UserTask::read(&mut conn, (5, shared.task_id)).await.unwrap().unwrap().task_status_id, 1);
+        assert_eq!(UserTask::read(&mut conn, (5, handed_over.task_id)).await.unwrap().unwrap().task_status_id, 2);
+        assert_eq!(UserTask::reassign_all(&mut conn, None, 3, 5).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn a_user_with_comments_is_kept() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Discuss").await;
+        Comment::create(&mut conn, NewComment { task_id: task.task_id, author_id: 3, body: "Agreed" }).await.unwrap();
+        assert!(breaks_a_foreign_key(&User::delete(&mut conn, 3).await.unwrap_err()));
+        assert!(User::read(&mut conn, 3).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn email_lookups_stay_in_the_tenant() {
+        let mut conn = test_support::conn().await;
+        let other = Tenant::create(&mut conn, "Other").await.unwrap();
+        tenancy::enter(&mut conn, other.tenant_id).await.unwrap();
+        assert!(User::read_by_email(&mut conn, "charlie@example.com").await.unwrap().is_none());
+        let theirs = User::create(&mut conn, NewUser { name: "Charlie", email: "charlie@example.com", active: true }).await.unwrap();
+        assert_eq!(User::read_by_email(&mut conn, "charlie@example.com").await.unwrap().unwrap().user_id, theirs.user_id);
+        assert!(User::bootstrap_admin(&mut conn, "bob@example.com").await.unwrap().is_none());
+
+        tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await.unwrap();
+        assert_eq!(User::read_by_email(&mut conn, "charlie@example.com").await.unwrap().unwrap().user_id, 3);
+        let everywhere = User::read_active_by_email_in_any_tenant(&mut conn, "charlie@example.com").await.unwrap();
+        assert_eq!(everywhere.iter().map(|user| user.user_id).collect::<Vec<_>>(), vec![3, theirs.user_id]);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_user_takes_them_off_their_teams_and_tasks() {
+        let mut conn = test_support::conn().await;
+        let user = User::create(&mut conn, NewUser { name: "mallory", email: "mallory@example.com", active: true }).await.unwrap();
+        let team = Team::create(&mut conn, NewTeam { team_name: "Night shift" }).await.unwrap();
+        Team::add_members(&mut conn, team.team_id, &[1, user.user_id]).await.unwrap();
+        assert!(Team::is_member(&mut conn, team.team_id, user.user_id).await.unwrap());
+        let task = create_task(&mut conn, "Lock the gate").await;
+        TaskWatcher::watch(&mut conn, task.task_id, user.user_id).await.unwrap();
+        StarredTask::star(&mut conn, user.user_id, task.task_id).await.unwrap();
+        overdue::mark_reminded(&mut conn, task.task_id, user.user_id, chrono::NaiveDate::from_ymd_opt(2031, 3, 10).unwrap(), Channel::Email).await.unwrap();
+        Webhook::create(&mut conn, NewWebhook { user_id: user.user_id, url: "https://example.com/hook", event_type: "task.created", secret: "s3cret" }).await.unwrap();
+        PushSubscription::save(&mut conn, NewPushSubscription { user_id: user.user_id, endpoint: "https://push.example.com/abc", p256dh: "BPublicKey", auth: "secret" }).await.unwrap();
+        CalendarFeed::save(&mut conn, NewCalendarFeed { user_id: user.user_id, token_hash: "feed" }).await.unwrap();
+
+        assert_eq!(User::delete(&mut conn, user.user_id).await.unwrap(), 1);
+        assert_eq!(Team::read_members(&mut conn, team.team_id).await.unwrap().iter().map(|member| member.user_id).collect::<Vec<_>>(), [1]);
+        assert!(TaskWatcher::read_watching_users(&mut conn, task.task_id).await.unwrap().is_empty());
+        assert_eq!(starred_tasks::table.count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+        assert_eq!(webhooks::table.filter(webhooks::user_id.eq(user.user_id)).count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+        assert_eq!(push_subscriptions::table.count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+        assert!(CalendarFeed::read_by_token_hash(&mut conn, "feed").await.unwrap().is_none());
+    }
+}
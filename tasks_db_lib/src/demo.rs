@@ -0,0 +1,36 @@
+use diesel_async::AsyncConnection;
+use crate::{seed, tenancy, DbConnection};
+
+// A database that lives in memory, for trying the API out. Every connection this process
+// opens on the URL shares it (SQLite's memdb VFS), and it is gone once the last one closes.
+pub const DATABASE_URL: &str = "file:/tasks-demo?vfs=memdb";
+
+// Creates the database with every migration applied, so it has the sample users, statuses,
+// tasks and assignments from seed_data, then seeds it to give those users passwords (see
+// seed::PASSWORD) and roles. The database lasts as long as the connection this returns stays
+// open.
+pub async fn open() -> anyhow::Result<DbConnection> {
+    let mut conn = DbConnection::establish(DATABASE_URL).await?;
+    crate::migrations::run_pending(DATABASE_URL).await?;
+    crate::configure(&mut conn).await?;
+    tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await?;
+    seed::run(&mut conn).await?;
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::CrudOperations;
+    use crate::models::User;
+    use crate::tenancy;
+
+    #[tokio::test]
+    async fn other_connections_see_the_sample_data_while_the_first_stays_open() {
+        let _demo = open().await.unwrap();
+        let mut conn = DbConnection::establish(DATABASE_URL).await.unwrap();
+        tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await.unwrap();
+        assert_eq!(User::count(&mut conn).await.unwrap(), 10);
+        assert!(crate::migrations::pending(&mut conn).await.unwrap().is_empty());
+    }
+}
@@ -3,9 +3,9 @@
 // use std::io::Write;
 use diesel::deserialize::{self, FromSql};
 use diesel::serialize::{self, Output, ToSql};
-use diesel::AsExpression;
+use diesel::{AsExpression, FromSqlRow};
 use diesel::sql_types::Integer;
-use diesel::sqlite::Sqlite;
+use crate::DbBackend;
 
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, AsExpression, PartialEq, Eq)]
@@ -17,30 +17,28 @@ pub enum TaskStatus {
 }
 
 
-impl ToSql<Integer, diesel::sqlite::Sqlite> for TaskStatus {
-    fn to_sql(&self, out: &mut Output<'_, '_, diesel::sqlite::Sqlite>) -> serialize::Result {
-        let value = match *self {
+impl ToSql<Integer, DbBackend> for TaskStatus {
+    fn to_sql(&self, out: &mut Output<'_, '_, DbBackend>) -> serialize::Result {
+        match *self {
             // you can call a trait method for a specific type using <Type as Trait>::method
-            // ToSql<Integer, Sqlite> is a Trait provided by Diesel
-            // e.g. ToSql<Integer, Sqlite>>::to_sql(&0, out)
+            // ToSql<Integer, DbBackend> is a Trait provided by Diesel
+            // e.g. ToSql<Integer, DbBackend>>::to_sql(&0, out)
             // You can implement from an instance of the data type
             // e.g. 0.to_sql(out)
             // or you can implement explicitly using a type, here the type is i32
-            // e.g. <i32 as ToSql<Integer, Sqlite>>::to_sql(&0, out)
+            // e.g. <i32 as ToSql<Integer, DbBackend>>::to_sql(&0, out)
             // Explanation of <Type as Trait> Rust syntax. 
             //    Use the implementation of the ToSql trait for the type i32
-            //    with the SQL type Integer and the backend Sqlite.
-            TaskStatus::Todo => <i32 as ToSql<Integer, Sqlite>>::to_sql(&0, out),
-            TaskStatus::InProgress => <i32 as ToSql<Integer, Sqlite>>::to_sql(&1, out),
-            TaskStatus::Done => <i32 as ToSql<Integer, Sqlite>>::to_sql(&2, out)
-        };
-        
-        value
+            //    with the SQL type Integer and the backend DbBackend (SQLite or PostgreSQL, see lib.rs).
+            TaskStatus::Todo => <i32 as ToSql<Integer, DbBackend>>::to_sql(&0, out),
+            TaskStatus::InProgress => <i32 as ToSql<Integer, DbBackend>>::to_sql(&1, out),
+            TaskStatus::Done => <i32 as ToSql<Integer, DbBackend>>::to_sql(&2, out)
+        }
     }
 }
 
-impl FromSql<Integer, diesel::sqlite::Sqlite> for TaskStatus {
-    fn from_sql(bytes: <diesel::sqlite::Sqlite as diesel::backend::Backend>::RawValue<'_>) -> deserialize::Result<Self> {
+impl FromSql<Integer, DbBackend> for TaskStatus {
+    fn from_sql(bytes: <DbBackend as diesel::backend::Backend>::RawValue<'_>) -> deserialize::Result<Self> {
         match i32::from_sql(bytes)? {
             0 => Ok(TaskStatus::Todo),
             1 => Ok(TaskStatus::InProgress),
@@ -48,4 +46,141 @@ impl FromSql<Integer, diesel::sqlite::Sqlite> for TaskStatus {
             x => Err(format!("Unknown task status: {}", x).into())
         }
     }
-}
\ No newline at end of file
+}
+
+
+// Matches the rows seeded into the roles table. Lower ids carry more privileges.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, AsExpression, FromSqlRow, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[diesel(sql_type = Integer)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    Admin = 1,
+    Manager = 2,
+    Member = 3,
+}
+
+impl UserRole {
+    pub fn from_id(role_id: i32) -> Option<UserRole> {
+        match role_id {
+            1 => Some(UserRole::Admin),
+            2 => Some(UserRole::Manager),
+            3 => Some(UserRole::Member),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UserRole::Admin => "admin",
+            UserRole::Manager => "manager",
+            UserRole::Member => "member",
+        }
+    }
+
+    // true when this role is `minimum` or something more privileged
+    pub fn at_least(self, minimum: UserRole) -> bool {
+        (self as i32) <= (minimum as i32)
+    }
+}
+
+impl ToSql<Integer, DbBackend> for UserRole {
+    fn to_sql(&self, out: &mut Output<'_, '_, DbBackend>) -> serialize::Result {
+        match *self {
+            UserRole::Admin => <i32 as ToSql<Integer, DbBackend>>::to_sql(&1, out),
+            UserRole::Manager => <i32 as ToSql<Integer, DbBackend>>::to_sql(&2, out),
+            UserRole::Member => <i32 as ToSql<Integer, DbBackend>>::to_sql(&3, out),
+        }
+    }
+}
+
+impl FromSql<Integer, DbBackend> for UserRole {
+    fn from_sql(bytes: <DbBackend as diesel::backend::Backend>::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(UserRole::Admin),
+            2 => Ok(UserRole::Manager),
+            3 => Ok(UserRole::Member),
+            x => Err(format!("Unknown role: {}", x).into())
+        }
+    }
+}
+
+// Stored as an integer where lower means more pressing (like UserRole), so sorting
+// ascending puts urgent work first. New tasks are medium unless told otherwise.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, AsExpression, FromSqlRow, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[diesel(sql_type = Integer)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskPriority {
+    Urgent = 1,
+    High = 2,
+    #[default]
+    Medium = 3,
+    Low = 4,
+}
+
+impl TaskPriority {
+    pub const NAMES: &'static [&'static str] = &["low", "medium", "high", "urgent"];
+
+    pub fn from_name(name: &str) -> Option<TaskPriority> {
+        match name.to_ascii_lowercase().as_str() {
+            "urgent" => Some(TaskPriority::Urgent),
+            "high" => Some(TaskPriority::High),
+            "medium" => Some(TaskPriority::Medium),
+            "low" => Some(TaskPriority::Low),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskPriority::Urgent => "urgent",
+            TaskPriority::High => "high",
+            TaskPriority::Medium => "medium",
+            TaskPriority::Low => "low",
+        }
+    }
+}
+
+impl ToSql<Integer, DbBackend> for TaskPriority {
+    fn to_sql(&self, out: &mut Output<'_, '_, DbBackend>) -> serialize::Result {
+        match *self {
+            TaskPriority::Urgent => <i32 as ToSql<Integer, DbBackend>>::to_sql(&1, out),
+            TaskPriority::High => <i32 as ToSql<Integer, DbBackend>>::to_sql(&2, out),
+            TaskPriority::Medium => <i32 as ToSql<Integer, DbBackend>>::to_sql(&3, out),
+            TaskPriority::Low => <i32 as ToSql<Integer, DbBackend>>::to_sql(&4, out),
+        }
+    }
+}
+
+impl FromSql<Integer, DbBackend> for TaskPriority {
+    fn from_sql(bytes: <DbBackend as diesel::backend::Backend>::RawValue<'_>) -> deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(TaskPriority::Urgent),
+            2 => Ok(TaskPriority::High),
+            3 => Ok(TaskPriority::Medium),
+            4 => Ok(TaskPriority::Low),
+            x => Err(format!("Unknown task priority: {}", x).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admins_outrank_managers_outrank_members() {
+        assert!(UserRole::Admin.at_least(UserRole::Manager));
+        assert!(UserRole::Manager.at_least(UserRole::Manager));
+        assert!(!UserRole::Member.at_least(UserRole::Manager));
+        assert!(!UserRole::Manager.at_least(UserRole::Admin));
+    }
+
+    #[test]
+    fn role_ids_map_to_their_roles() {
+        assert_eq!(UserRole::from_id(1), Some(UserRole::Admin));
+        assert_eq!(UserRole::from_id(3), Some(UserRole::Member));
+        assert_eq!(UserRole::from_id(4), None);
+    }
+}
@@ -0,0 +1,31 @@
+// Optional, server-side filters for list queries. A `None` field means "don't filter on this".
+
+#[derive(Debug, Default, Clone)]
+pub struct AssignmentFilter {
+    pub user_id: Option<i32>,
+    pub task_id: Option<i32>,
+    pub task_status_id: Option<i32>,
+    // assignments on tasks in this project
+    pub project_id: Option<i32>,
+}
+
+// due_before/due_after are exclusive, and tasks without a due date never match them.
+#[derive(Debug, Default, Clone)]
+pub struct TaskFilter {
+    pub ids: Option<Vec<i32>>,
+    pub due_before: Option<chrono::NaiveDate>,
+    pub due_after: Option<chrono::NaiveDate>,
+    pub priority: Option<crate::enums::TaskPriority>,
+    pub project_id: Option<i32>,
+    // tag name, compared without regard to case
+    pub tag: Option<String>,
+    // only the tasks this user has starred
+    pub starred_by: Option<i32>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AuditFilter {
+    pub entity: Option<String>,
+    pub entity_id: Option<String>,
+    pub since: Option<chrono::NaiveDateTime>,
+}
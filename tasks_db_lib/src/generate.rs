@@ -0,0 +1,128 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use fake::Fake;
+use fake::faker::company::en::CatchPhrase;
+use fake::faker::name::en::{FirstName, LastName};
+use fake::rand::{Rng, SeedableRng};
+use fake::rand::distr::weighted::WeightedIndex;
+use fake::rand::rngs::StdRng;
+use crate::DbConnection;
+use crate::crud::CrudOperations;
+use crate::enums::TaskPriority;
+use crate::models::{NewTask, NewUser, NewUserTask, Task, User, UserTask};
+use crate::schema::{task_statuses, users};
+use crate::tenancy;
+
+// Made-up users, tasks and assignments in bulk, for trying pagination, filters and queries
+// against more than the ten rows seeding gives. Unlike seeding it adds to whatever is there
+// every time it runs. The same seed gives the same names, dates and assignments.
+
+// Chance that a generated user is active; inactive users are never assigned.
+const ACTIVE_USERS: f64 = 0.9;
+// Relative odds of a task having 0, 1, 2 or 3 assignees.
+const ASSIGNEE_COUNT_WEIGHTS: [u32; 4] = [15, 55, 20, 10];
+// Relative odds of Urgent, High, Medium and Low.
+const PRIORITY_WEIGHTS: [u32; 4] = [5, 20, 50, 25];
+const PRIORITIES: [TaskPriority; 4] = [TaskPriority::Urgent, TaskPriority::High, TaskPriority::Medium, TaskPriority::Low];
+// Chance that a task has a due date, which falls between a month ago and three months ahead.
+const DUE_DATES: f64 = 0.7;
+
+// How many rows a run added.
+#[derive(Debug, Default, PartialEq)]
+pub struct Generated {
+    pub users: usize,
+    pub tasks: usize,
+    pub assignments: usize,
+}
+
+// Adds `users` users and `tasks` tasks to the connection's tenant, all or nothing. Assignees are
+// drawn from the new active users with a long tail, the first few carrying most of the work as
+// they do in real teams, so per-user listings range from empty to many pages. Assignments
+// spread over the tenant's live statuses, the first of them the most common.
+pub async fn run(conn: &mut DbConnection, users: usize, tasks: usize, seed: u64) -> anyhow::Result<Generated> {
+    conn.transaction(|conn| async move {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut generated = Generated::default();
+        let statuses: Vec<i32> = task_statuses::table
+            .filter(task_statuses::tenant_id.eq(tenancy::current()))
+            .filter(task_statuses::deleted_at.is_null())
+            .order(task_statuses::task_status_id)
+            .select(task_statuses::task_status_id)
+            .load(conn).await?;
+        anyhow::ensure!(tasks == 0 || !statuses.is_empty(), "there are no task statuses to assign tasks in; seed the database first");
+
+        // numbering the emails on from the tenant's user count keeps them unique across runs
+        let existing = users::table.filter(users::tenant_id.eq(tenancy::current())).count().get_result::<i64>(conn).await?;
+        let mut assignable = Vec::new();
+        for n in existing + 1..=existing + users as i64 {
+            let first: String = FirstName().fake_with_rng(&mut rng);
+            let last: String = LastName().fake_with_rng(&mut rng);
+            let name = format!("{} {}", first, last);
+            let email = format!("{}.{}{}@example.net", first, last, n).to_lowercase().replace(' ', "");
+            let active = rng.random_bool(ACTIVE_USERS);
+            let user = User::create(conn, NewUser { name: &name, email: &email, active }).await?;
+            if active {
+                assignable.push(user.user_id);
+            }
+            generated.users += 1;
+        }
+
+        let assignee_counts = WeightedIndex::new(ASSIGNEE_COUNT_WEIGHTS)?;
+        let priorities = WeightedIndex::new(PRIORITY_WEIGHTS)?;
+        let status_weights = WeightedIndex::new((0..statuses.len()).map(|i| if i == 0 { 4 } else { 3 }))?;
+        let assignee_weights = (!assignable.is_empty())
+            .then(|| WeightedIndex::new((1..=assignable.len()).map(|rank| 1.0 / rank as f64)))
+            .transpose()?;
+        let today = chrono::Utc::now().date_naive();
+        for _ in 0..tasks {
+            let task_name: String = CatchPhrase().fake_with_rng(&mut rng);
+            let due_date = rng.random_bool(DUE_DATES).then(|| today + chrono::Duration::days(rng.random_range(-30..=90)));
+            let priority = PRIORITIES[rng.sample(&priorities)];
+            let task = Task::create(conn, NewTask { task_name: &task_name, due_date, priority, parent_task_id: None, recurrence: None, project_id: None }).await?;
+            generated.tasks += 1;
+
+            let Some(assignee_weights) = &assignee_weights else { continue };
+            let mut assignees: Vec<i32> = (0..rng.sample(&assignee_counts)).map(|_| assignable[rng.sample(assignee_weights)]).collect();
+            assignees.sort_unstable();
+            assignees.dedup();
+            for user_id in assignees {
+                let task_status_id = statuses[rng.sample(&status_weights)];
+                UserTask::create(conn, NewUserTask { user_id, task_id: task.task_id, task_status_id }).await?;
+                generated.assignments += 1;
+            }
+        }
+        Ok(generated)
+    }.scope_boxed()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::AssignmentFilter;
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn adds_the_asked_for_volumes_with_unique_emails() {
+        let mut conn = test_support::conn().await;
+        let users_before = User::count(&mut conn).await.unwrap();
+        let tasks_before = Task::count(&mut conn).await.unwrap();
+        let assignments_before = UserTask::count_filtered(&mut conn, &AssignmentFilter::default()).await.unwrap();
+
+        let generated = run(&mut conn, 20, 50, 7).await.unwrap();
+        assert_eq!((generated.users, generated.tasks), (20, 50));
+        assert!(generated.assignments > 0);
+        assert_eq!(User::count(&mut conn).await.unwrap(), users_before + 20);
+        assert_eq!(Task::count(&mut conn).await.unwrap(), tasks_before + 50);
+        assert_eq!(UserTask::count_filtered(&mut conn, &AssignmentFilter::default()).await.unwrap(), assignments_before + generated.assignments as i64);
+
+        // the same seed again makes the same people, numbered on so their emails don't clash
+        let again = run(&mut conn, 20, 0, 7).await.unwrap();
+        assert_eq!(again, Generated { users: 20, tasks: 0, assignments: 0 });
+        let mut emails: Vec<String> = users::table.select(users::email).load(&mut conn).await.unwrap();
+        let count = emails.len();
+        emails.sort();
+        emails.dedup();
+        assert_eq!(emails.len(), count);
+    }
+}
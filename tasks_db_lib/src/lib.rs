@@ -1,19 +1,153 @@
+// The conflict target of an upsert: the key itself, except on MySQL, whose ON DUPLICATE KEY
+// UPDATE can't name one and fires on whichever unique key clashes. None of the tables upserted
+// here has a second key that a new row could clash on.
+#[cfg(not(feature = "mysql"))]
+macro_rules! conflict_target {
+    ($key:expr) => { $key };
+}
+#[cfg(feature = "mysql")]
+macro_rules! conflict_target {
+    ($key:expr) => { diesel::dsl::DuplicatedKeys };
+}
+
 pub mod schema;
 pub mod models;
 pub mod crud;
+pub mod enums;
+pub mod pagination;
+pub mod filters;
+pub mod sorting;
+pub mod audit;
+pub mod revisions;
+pub mod versioning;
+pub mod stats;
+pub mod board;
+pub mod search;
+pub mod overdue;
+pub mod mentions;
+pub mod recurrence;
+pub mod templates;
+pub mod tenancy;
+pub mod watchers;
+pub mod activity;
+pub mod webhooks;
+pub mod preferences;
+pub mod slack;
+pub mod push;
+pub mod notifications;
+pub mod calendar;
+pub mod backup;
+pub mod quotas;
+pub mod telemetry;
+pub mod migrations;
+pub mod seed;
+#[cfg(feature = "generate")]
+pub mod generate;
+#[cfg(feature = "sqlite")]
+pub mod demo;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+
+#[cfg(any(all(feature = "sqlite", feature = "postgres"), all(feature = "sqlite", feature = "mysql"), all(feature = "postgres", feature = "mysql")))]
+compile_error!("only one of the sqlite, postgres and mysql features can be on; build with --no-default-features --features postgres (or mysql)");
+#[cfg(not(any(feature = "sqlite", feature = "postgres", feature = "mysql")))]
+compile_error!("one of the sqlite, postgres and mysql features must be on");
 
 use diesel::prelude::*;
-use diesel::sqlite::SqliteConnection;
+use diesel_async::AsyncConnection;
+#[cfg(any(feature = "sqlite", feature = "mysql"))]
+use diesel_async::SimpleAsyncConnection;
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig, RecyclingMethod};
 use dotenvy::dotenv;
+use futures_util::FutureExt;
 use std::env;
 
-pub fn establish_connection() -> SqliteConnection {
+// Diesel's SQLite connection is blocking, so queries run through diesel_async's wrapper, which
+// hands each one to Tokio's blocking threads and lets callers await it.
+#[cfg(feature = "sqlite")]
+pub type DbConnection = diesel_async::sync_connection_wrapper::SyncConnectionWrapper<diesel::sqlite::SqliteConnection>;
+#[cfg(feature = "sqlite")]
+pub type DbBackend = diesel::sqlite::Sqlite;
+
+#[cfg(feature = "postgres")]
+pub type DbConnection = diesel_async::AsyncPgConnection;
+#[cfg(feature = "postgres")]
+pub type DbBackend = diesel::pg::Pg;
+
+#[cfg(feature = "mysql")]
+pub type DbConnection = diesel_async::AsyncMysqlConnection;
+#[cfg(feature = "mysql")]
+pub type DbBackend = diesel::mysql::Mysql;
+
+pub type DbPool = diesel_async::pooled_connection::bb8::Pool<DbConnection>;
+
+// SQLite only enforces REFERENCES clauses on connections that ask for it. The bundled build
+// turns it on by default, but a system libsqlite3 doesn't, so every connection asks.
+#[cfg(feature = "sqlite")]
+pub async fn configure(conn: &mut DbConnection) -> QueryResult<()> {
+    conn.batch_execute("PRAGMA foreign_keys = ON").await
+}
+
+// PostgreSQL always enforces foreign keys, and the schema's defaults are in UTC whatever the
+// session's time zone is, so there's nothing to set.
+#[cfg(feature = "postgres")]
+pub async fn configure(_conn: &mut DbConnection) -> QueryResult<()> {
+    Ok(())
+}
+
+// MySQL enforces foreign keys on InnoDB tables too. Its DATETIME columns have no time zone, so
+// the session is put in UTC for the schema's CURRENT_TIMESTAMP defaults to match Rust's.
+#[cfg(feature = "mysql")]
+pub async fn configure(conn: &mut DbConnection) -> QueryResult<()> {
+    conn.batch_execute("SET time_zone = '+00:00'").await
+}
+
+// For bb8::Pool::builder().build(...): every connection the pool opens is configured and traces
+// its queries (see telemetry::QuerySpans), and leaves the tenant it last worked for before it's
+// handed out again (see tenancy).
+pub fn connection_manager(database_url: &str) -> AsyncDieselConnectionManager<DbConnection> {
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(|url| {
+        let url = url.to_string();
+        async move {
+            let mut conn = DbConnection::establish(&url).await?;
+            conn.set_instrumentation(telemetry::QuerySpans::default());
+            configure(&mut conn).await.map_err(diesel::ConnectionError::CouldntSetupConfiguration)?;
+            Ok(conn)
+        }.boxed()
+    });
+    config.recycling_method = RecyclingMethod::CustomFunction(Box::new(|conn| tenancy::leave(conn).boxed()));
+    AsyncDieselConnectionManager::new_with_config(database_url, config)
+}
+
+#[derive(QueryableByName)]
+struct InsertedId {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    id: i64,
+}
+
+// The id of the row this connection inserted last, so the row can be read back; MySQL has no
+// RETURNING, so every backend inserts and then reads.
+pub(crate) async fn last_insert_id(conn: &mut DbConnection) -> QueryResult<i32> {
+    #[cfg(feature = "sqlite")]
+    const SQL: &str = "SELECT last_insert_rowid() AS id";
+    #[cfg(feature = "postgres")]
+    const SQL: &str = "SELECT lastval() AS id";
+    #[cfg(feature = "mysql")]
+    const SQL: &str = "SELECT CAST(LAST_INSERT_ID() AS SIGNED) AS id";
+    let inserted = diesel_async::RunQueryDsl::get_result::<InsertedId>(diesel::sql_query(SQL), conn).await?;
+    Ok(inserted.id as i32)
+}
+
+pub async fn establish_connection() -> DbConnection {
     dotenv().ok();
 
     let connection_string = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     println!("Connecting to database at: {}", connection_string);
-    let connection = SqliteConnection::establish(&connection_string)
+    let mut connection = DbConnection::establish(&connection_string)
+        .await
         .unwrap_or_else(|_| panic!("Error connecting to {}", connection_string));
+    configure(&mut connection).await.expect("Failed to configure the connection");
     println!("Connected to database at: {}", connection_string);
     connection
-}
\ No newline at end of file
+}
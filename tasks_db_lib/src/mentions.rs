@@ -0,0 +1,165 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use crate::models::{Comment, Mention, NewMention, NewNotification, NotificationPreference};
+use crate::pagination::{self, Page};
+use crate::preferences::{Channel, MENTIONED};
+use crate::schema::{comments, mentions, notifications, users};
+use crate::tenancy;
+use crate::crud::lower;
+
+pub const MENTION_NOTIFICATION: &str = "mention";
+
+// A user's @handle is the part of their email before the '@', compared without regard to case:
+// bob@example.com is @bob.
+//
+// Handles are the letters, digits and . _ - + right after an '@' that starts a word, so the
+// '@' inside an email address in the text isn't read as a mention. A trailing '.' is left to
+// the sentence. Each handle appears once, lowercased, in the order first written.
+pub fn parse_handles(body: &str) -> Vec<String> {
+    let mut handles: Vec<String> = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut chars = body.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let starts_word = previous.is_none_or(|p| !p.is_alphanumeric());
+        previous = Some(c);
+        if c != '@' || !starts_word {
+            continue;
+        }
+        let mut end = start + 1;
+        while let Some(&(i, next)) = chars.peek() {
+            if !(next.is_alphanumeric() || matches!(next, '.' | '_' | '-' | '+')) {
+                break;
+            }
+            end = i + next.len_utf8();
+            previous = Some(next);
+            chars.next();
+        }
+        let handle = body[start + 1..end].trim_end_matches('.').to_lowercase();
+        if !handle.is_empty() && !handles.contains(&handle) {
+            handles.push(handle);
+        }
+    }
+    handles
+}
+
+// Ids of the users whose email starts with "<handle>@", in any case (handles are lowercased
+// already). The handle's own _ and % are escaped so they only match themselves.
+async fn users_with_handle(conn: &mut DbConnection, handle: &str) -> anyhow::Result<Vec<i32>> {
+    let pattern = format!("{}@%", handle.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+    let ids = users::table
+        .filter(users::tenant_id.eq(tenancy::current()))
+        .filter(lower(users::email).like(pattern).escape('\\'))
+        .select(users::user_id)
+        .load(conn).await?;
+    Ok(ids)
+}
+
+// The handles in `body` that don't name exactly one user, so callers can reject the text
+// before saving it.
+pub async fn unresolved_handles(conn: &mut DbConnection, body: &str) -> anyhow::Result<Vec<String>> {
+    let mut unresolved = Vec::new();
+    for handle in parse_handles(body) {
+        if users_with_handle(conn, &handle).await?.len() != 1 {
+            unresolved.push(handle);
+        }
+    }
+    Ok(unresolved)
+}
+
+// Called by the crud layer after a comment is written. Brings its mention rows in line with
+// the body and notifies each user who is newly mentioned; people mentioned before an edit
+// aren't notified again, and authors aren't notified about mentioning themselves.
+pub(crate) async fn sync(conn: &mut DbConnection, comment: &Comment) -> anyhow::Result<()> {
+    let mut mentioned = Vec::new();
+    for handle in parse_handles(&comment.body) {
+        if let [user_id] = users_with_handle(conn, &handle).await?[..] && !mentioned.contains(&user_id) {
+            mentioned.push(user_id);
+        }
+    }
+    diesel::delete(mentions::table
+        .filter(mentions::comment_id.eq(comment.comment_id))
+        .filter(mentions::user_id.ne_all(mentioned.clone())))
+        .execute(conn).await?;
+    let already: Vec<i32> = mentions::table
+        .filter(mentions::comment_id.eq(comment.comment_id))
+        .select(mentions::user_id)
+        .load(conn).await?;
+    let now = chrono::Utc::now().naive_utc();
+    let message = format!("You were mentioned in a comment on task {}", comment.task_id);
+    let newly_mentioned: Vec<i32> = mentioned.into_iter().filter(|user_id| !already.contains(user_id)).collect();
+    let to_notify = NotificationPreference::allowed(conn, newly_mentioned.clone(), MENTIONED, Channel::InApp).await?;
+    for user_id in newly_mentioned {
+        diesel::insert_into(mentions::table)
+            .values(NewMention { comment_id: comment.comment_id, user_id, created_at: now })
+            .execute(conn).await?;
+        if user_id != comment.author_id && to_notify.contains(&user_id) {
+            diesel::insert_into(notifications::table)
+                .values(NewNotification {
+                    user_id,
+                    kind: MENTION_NOTIFICATION,
+                    task_id: Some(comment.task_id),
+                    comment_id: Some(comment.comment_id),
+                    message: &message,
+                    created_at: now,
+                })
+                .execute(conn).await?;
+        }
+    }
+    Ok(())
+}
+
+// Called when a comment is deleted.
+pub(crate) async fn clear(conn: &mut DbConnection, comment_id: i32) -> anyhow::Result<()> {
+    diesel::delete(mentions::table.filter(mentions::comment_id.eq(comment_id))).execute(conn).await?;
+    Ok(())
+}
+
+impl Mention {
+    // Comments that mention the user, newest mention first.
+    pub async fn read_page_for_user(conn: &mut DbConnection, user_id: i32, page: i64, per_page: i64) -> anyhow::Result<Page<Mention>> {
+        let total = mentions::table.filter(mentions::user_id.eq(user_id)).count().get_result(conn).await?;
+        let items = mentions::table
+            .inner_join(comments::table)
+            .filter(mentions::user_id.eq(user_id))
+            .order((mentions::created_at.desc(), mentions::comment_id.desc()))
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .select(Mention::as_select())
+            .load(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::CrudOperations;
+    use crate::models::NewComment;
+    use crate::test_support::{self, create_task};
+
+    #[test]
+    fn handles_start_a_word_and_leave_the_full_stop() {
+        assert_eq!(parse_handles("@Bob, ask @alice. Not mail@example.com or @bob again"), ["bob", "alice"]);
+        assert!(parse_handles("@ alone").is_empty());
+    }
+
+    async fn notified(conn: &mut DbConnection, user_id: i32) -> i64 {
+        notifications::table.filter(notifications::user_id.eq(user_id)).count().get_result(conn).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn only_newly_mentioned_users_are_notified() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        let comment = Comment::create(&mut conn, NewComment { task_id: task.task_id, author_id: 2, body: "@alice and @bob, thoughts?" }).await.unwrap();
+        assert_eq!((notified(&mut conn, 1).await, notified(&mut conn, 2).await), (1, 0));
+        Comment::update(&mut conn, comment.comment_id, NewComment { task_id: task.task_id, author_id: 2, body: "@alice and @charlie, thoughts?" }).await.unwrap();
+        assert_eq!((notified(&mut conn, 1).await, notified(&mut conn, 3).await), (1, 1));
+        assert_eq!(Mention::read_page_for_user(&mut conn, 2, 1, 10).await.unwrap().total, 0);
+        assert_eq!(unresolved_handles(&mut conn, "@nobody and @alice").await.unwrap(), ["nobody"]);
+
+        assert_eq!(Comment::delete(&mut conn, comment.comment_id).await.unwrap(), 1);
+        assert_eq!(Mention::read_page_for_user(&mut conn, 1, 1, 10).await.unwrap().total, 0);
+    }
+}
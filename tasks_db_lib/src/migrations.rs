@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+use diesel::prelude::*;
+use diesel::migration::MigrationSource;
+#[cfg(feature = "sqlite")]
+use diesel::connection::SimpleConnection;
+use diesel_async::{AsyncConnection, RunQueryDsl, SimpleAsyncConnection};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use crate::{DbBackend, DbConnection};
+
+// Every migration for the backend, built into the binary: migrations/ for SQLite,
+// migrations_postgres/ for PostgreSQL and migrations_mysql/ for MySQL.
+#[cfg(feature = "sqlite")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+#[cfg(feature = "postgres")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_postgres");
+#[cfg(feature = "mysql")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_mysql");
+
+// Where the diesel CLI records the migrations it has run, by version.
+diesel::table! {
+    __diesel_schema_migrations (version) {
+        version -> Text,
+        run_on -> Timestamp,
+    }
+}
+
+// The migrations built in that the database hasn't run yet, oldest first, by version. Like
+// the diesel CLI, it creates the bookkeeping table first if the database is new.
+pub async fn pending(conn: &mut DbConnection) -> anyhow::Result<Vec<String>> {
+    conn.batch_execute("CREATE TABLE IF NOT EXISTS __diesel_schema_migrations (\
+        version VARCHAR(50) PRIMARY KEY NOT NULL, run_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP)").await?;
+    let applied: HashSet<String> = __diesel_schema_migrations::table
+        .select(__diesel_schema_migrations::version)
+        .load(conn).await?
+        .into_iter()
+        .collect();
+    let mut pending: Vec<String> = MigrationSource::<DbBackend>::migrations(&MIGRATIONS)
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .filter(|version| !applied.contains(version))
+        .collect();
+    pending.sort();
+    Ok(pending)
+}
+
+// Runs the migrations pending() lists on a connection of its own, returning their versions.
+// The diesel harness is synchronous, so it runs on a blocking thread.
+#[cfg(feature = "sqlite")]
+pub async fn run_pending(database_url: &str) -> anyhow::Result<Vec<String>> {
+    let mut conn = DbConnection::establish(database_url).await?;
+    let versions = conn.spawn_blocking(|conn| {
+        // the migrations were written for the diesel CLI, which runs them without foreign keys
+        conn.batch_execute("PRAGMA foreign_keys = OFF")?;
+        conn.run_pending_migrations(MIGRATIONS)
+            .map(|versions| versions.iter().map(ToString::to_string).collect())
+            .map_err(diesel::result::Error::QueryBuilderError)
+    }).await?;
+    Ok(versions)
+}
+
+// The other backends have no synchronous connection here, so the async one is wrapped in one.
+#[cfg(not(feature = "sqlite"))]
+pub async fn run_pending(database_url: &str) -> anyhow::Result<Vec<String>> {
+    let conn = DbConnection::establish(database_url).await?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = diesel_async::async_connection_wrapper::AsyncConnectionWrapper::<DbConnection>::from(conn);
+        conn.run_pending_migrations(MIGRATIONS)
+            .map(|versions| versions.iter().map(ToString::to_string).collect())
+            .map_err(|e| anyhow::anyhow!(e.to_string()))
+    }).await?
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_new_database_has_every_migration_pending_until_they_run() {
+        // shared between connections for as long as this one is open; see demo::DATABASE_URL
+        let url = "file:/migrations-test?vfs=memdb";
+        let mut conn = DbConnection::establish(url).await.unwrap();
+        let migrations = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations")).unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().is_dir())
+            .count();
+        let pending = pending(&mut conn).await.unwrap();
+        assert_eq!(pending.len(), migrations);
+        assert_eq!(pending[0], "20250612005042");
+
+        assert_eq!(run_pending(url).await.unwrap(), pending);
+        assert!(super::pending(&mut conn).await.unwrap().is_empty());
+        assert!(run_pending(url).await.unwrap().is_empty());
+    }
+}
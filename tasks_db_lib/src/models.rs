@@ -3,44 +3,433 @@
 
 use diesel::prelude::*;
 use crate::schema::*;
+use crate::enums::TaskPriority;
 
-#[derive(Queryable, Selectable, Debug, serde::Serialize)]
+#[derive(Queryable, Selectable, Debug, serde::Serialize, serde::Deserialize)]
 #[diesel(primary_key(user_id))]
 #[diesel(table_name = users)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(check_for_backend(crate::DbBackend))]
 pub struct User {
     pub user_id: i32,
     pub name: String,
     pub email: String,
     pub active: bool,
+    pub role_id: i32,
+    // both set by the crud layer; updated_at moves on every update
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+    // the organization the user belongs to, and so everything they can see; see crate::tenancy
+    #[serde(skip)]
+    pub tenant_id: i32,
 }
 
-#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize)]
+// An organization. Rows in the tenant-scoped tables carry its id.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(tenant_id))]
+#[diesel(table_name = tenants)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Tenant {
+    pub tenant_id: i32,
+    pub tenant_name: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// Never serialized: the hash should not leave the server.
+#[derive(Queryable, Debug, Selectable,Identifiable)]
+#[diesel(primary_key(user_id))]
+#[diesel(table_name = credentials)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Credential {
+    pub user_id: i32,
+    pub password_hash: String,
+}
+
+// Links a GitHub/Google account to a local user.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(oauth_identity_id))]
+#[diesel(table_name = oauth_identities)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct OAuthIdentity {
+    pub oauth_identity_id: i32,
+    pub user_id: i32,
+    pub provider: String,
+    pub provider_user_id: String,
+}
+
+#[derive(Queryable, Debug, Selectable,Identifiable)]
+#[diesel(primary_key(refresh_token_id))]
+#[diesel(table_name = refresh_tokens)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct RefreshToken {
+    pub refresh_token_id: i32,
+    pub user_id: i32,
+    pub token_hash: String,
+    pub expires_at: chrono::NaiveDateTime,
+    pub revoked: bool,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Debug, Selectable,Identifiable, Insertable)]
+#[diesel(primary_key(jti))]
+#[diesel(table_name = revoked_tokens)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct RevokedToken {
+    pub jti: String,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(role_id))]
+#[diesel(table_name = roles)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Role {
+    pub role_id: i32,
+    pub role_name: String,
+}
+
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
 #[diesel(primary_key(task_status_id))]
 #[diesel(table_name = task_statuses)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(check_for_backend(crate::DbBackend))]
 pub struct TaskStatus {
     pub task_status_id: i32,
     pub status_name: String,
+    // set while the row is in the trash; soft-deleted rows are hidden from normal reads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+    #[serde(skip)]
+    pub tenant_id: i32,
 }
 
-#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize)]
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
 #[diesel(primary_key(task_id))]
 #[diesel(table_name = tasks)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(check_for_backend(crate::DbBackend))]
 pub struct Task {
     pub task_id: i32,
     pub task_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+    pub due_date: Option<chrono::NaiveDate>,
+    pub priority: TaskPriority,
+    pub parent_task_id: Option<i32>,
+    pub recurrence: Option<String>,
+    pub recurrence_paused: bool,
+    pub next_occurrence_id: Option<i32>,
+    pub project_id: Option<i32>,
+    #[serde(skip)]
+    pub tenant_id: i32,
+}
+
+// A body of work that groups tasks; a task belongs to at most one project.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(project_id))]
+#[diesel(table_name = projects)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Project {
+    pub project_id: i32,
+    pub project_name: String,
+    pub description: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+    pub team_id: Option<i32>,
+    #[serde(skip)]
+    pub tenant_id: i32,
+}
+
+// A group of users. A project with a team only takes assignments for the team's members.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(team_id))]
+#[diesel(table_name = teams)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Team {
+    pub team_id: i32,
+    pub team_name: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+    #[serde(skip)]
+    pub tenant_id: i32,
+}
+
+// A label tasks can carry any number of, independent of where their assignments stand.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(tag_id))]
+#[diesel(table_name = tags)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Tag {
+    pub tag_id: i32,
+    pub tag_name: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+    #[serde(skip)]
+    pub tenant_id: i32,
+}
+
+// A stored starting point for a task that gets made over and over. The JSON columns hold the
+// user ids to assign and the names of subtasks to create; see TaskTemplate::assignee_ids.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(template_id))]
+#[diesel(table_name = task_templates)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct TaskTemplate {
+    pub template_id: i32,
+    pub template_name: String,
+    pub task_name: String,
+    pub priority: TaskPriority,
+    pub due_in_days: Option<i32>,
+    pub recurrence: Option<String>,
+    pub assignee_ids_json: String,
+    pub subtask_names_json: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+    #[serde(skip)]
+    pub tenant_id: i32,
+}
+
+// One message in a task's discussion thread.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(comment_id))]
+#[diesel(table_name = comments)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Comment {
+    pub comment_id: i32,
+    pub task_id: i32,
+    pub author_id: i32,
+    pub body: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+}
+
+// A version of a comment from before it was edited.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(comment_id, version))]
+#[diesel(table_name = comment_revisions)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct CommentRevision {
+    pub comment_id: i32,
+    pub version: i32,
+    pub body: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub edited_by: Option<i32>,
+    pub edited_at: chrono::NaiveDateTime,
+}
+
+// A comment that names a user with @handle, read from the user's side.
+#[derive(Queryable, Selectable, Debug, serde::Serialize, serde::Deserialize)]
+#[diesel(table_name = mentions)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Mention {
+    pub comment_id: i32,
+    #[diesel(select_expression = comments::task_id)]
+    pub task_id: i32,
+    #[diesel(select_expression = comments::author_id)]
+    pub author_id: i32,
+    #[diesel(select_expression = comments::body)]
+    pub body: String,
+    // when the mention was first made, which stays put if the comment is edited later
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(notification_id))]
+#[diesel(table_name = notifications)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Notification {
+    pub notification_id: i32,
+    pub user_id: i32,
+    pub kind: String,
+    pub task_id: Option<i32>,
+    pub comment_id: Option<i32>,
+    pub message: String,
+    pub read_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// The channels one user hears about one event type on; see preferences.rs.
+#[derive(Queryable, Debug, Selectable, serde::Serialize, serde::Deserialize)]
+#[diesel(table_name = notification_preferences)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct NotificationPreference {
+    #[serde(skip)]
+    pub user_id: i32,
+    pub event_type: String,
+    pub email: bool,
+    pub webhook: bool,
+    pub in_app: bool,
+    pub push: bool,
+}
+
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(api_key_id))]
+#[diesel(table_name = api_keys)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct ApiKey {
+    pub api_key_id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub key_prefix: String,
+    #[serde(skip)]
+    pub key_hash: String,
+    pub revoked: bool,
+    pub created_at: chrono::NaiveDateTime,
+    // requests allowed per UTC day and per calendar month; None is no limit
+    pub daily_quota: Option<i32>,
+    pub monthly_quota: Option<i32>,
+}
+
+// An edge between two tasks: blocking_task_id has to be finished before blocked_task_id.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(blocking_task_id, blocked_task_id))]
+#[diesel(table_name = task_dependencies)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct TaskDependency {
+    pub blocking_task_id: i32,
+    pub blocked_task_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// A user who hears about changes to a task's assignments.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(task_id, user_id))]
+#[diesel(table_name = task_watchers)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct TaskWatcher {
+    pub task_id: i32,
+    pub user_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// A task a user has starred.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(user_id, task_id))]
+#[diesel(table_name = starred_tasks)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct StarredTask {
+    pub user_id: i32,
+    pub task_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// Where a project's task events are posted in Slack.
+#[derive(Queryable, Debug, Selectable, Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(project_id))]
+#[diesel(table_name = slack_integrations)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct SlackIntegration {
+    pub project_id: i32,
+    pub webhook_url: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+// The token behind a user's calendar feed URL. Only the hash is kept, so it's never shown again.
+#[derive(Queryable, Debug, Selectable, Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(user_id))]
+#[diesel(table_name = calendar_feeds)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct CalendarFeed {
+    pub user_id: i32,
+    #[serde(skip)]
+    pub token_hash: String,
+    pub created_at: chrono::NaiveDateTime,
 }
 
-#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize)]
+// A browser that hears about the owner's assignments by Web Push. The keys only matter for
+// encrypting what is sent, so they stay on the server.
+#[derive(Queryable, Debug, Selectable, Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(push_subscription_id))]
+#[diesel(table_name = push_subscriptions)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct PushSubscription {
+    pub push_subscription_id: i32,
+    #[serde(skip)]
+    pub user_id: i32,
+    pub endpoint: String,
+    #[serde(skip)]
+    pub p256dh: String,
+    #[serde(skip)]
+    pub auth: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// A URL that is sent a POST for each event of one type in the owner's tenant.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(webhook_id))]
+#[diesel(table_name = webhooks)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Webhook {
+    pub webhook_id: i32,
+    pub user_id: i32,
+    pub url: String,
+    pub event_type: String,
+    pub created_at: chrono::NaiveDateTime,
+    #[serde(skip)]
+    pub tenant_id: i32,
+    // the HMAC key deliveries are signed with; only shown when the webhook is registered
+    #[serde(skip)]
+    pub secret: String,
+}
+
+// A webhook delivery that hasn't been accepted yet. `payload` is the JSON body to POST.
+#[derive(Queryable, Debug, Selectable,Identifiable)]
+#[diesel(primary_key(outbox_id))]
+#[diesel(table_name = outbox)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct OutboxEntry {
+    pub outbox_id: i32,
+    pub webhook_id: i32,
+    pub payload: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// A file uploaded to a task. Where the bytes are kept is the server's business, so
+// storage_key is left out of responses.
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
+#[diesel(primary_key(attachment_id))]
+#[diesel(table_name = attachments)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct Attachment {
+    pub attachment_id: i32,
+    pub task_id: i32,
+    pub uploaded_by: i32,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    #[serde(skip)]
+    pub storage_key: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Debug, Selectable,Identifiable, serde::Serialize, serde::Deserialize)]
 #[diesel(primary_key(user_id, task_id))]
 #[diesel(table_name = user_tasks)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(check_for_backend(crate::DbBackend))]
 pub struct UserTask {
     pub user_id: i32,
     pub task_id: i32,
     pub task_status_id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+    #[serde(skip)]
+    pub tenant_id: i32,
 }
 
 
@@ -52,10 +441,33 @@ pub struct NewUser<'a> {
     pub active: bool,
 }
 
+#[derive(Insertable)]
+#[diesel(table_name = tenants)]
+pub struct NewTenant<'a> {
+    pub tenant_name: &'a str,
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = tasks)]
 pub struct NewTask<'a> {
     pub task_name: &'a str,
+    pub due_date: Option<chrono::NaiveDate>,
+    pub priority: TaskPriority,
+    pub parent_task_id: Option<i32>,
+    pub recurrence: Option<&'a str>,
+    pub project_id: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = task_templates)]
+pub struct NewTaskTemplate<'a> {
+    pub template_name: &'a str,
+    pub task_name: &'a str,
+    pub priority: TaskPriority,
+    pub due_in_days: Option<i32>,
+    pub recurrence: Option<&'a str>,
+    pub assignee_ids_json: String,
+    pub subtask_names_json: String,
 }
 
 #[derive(Insertable)]
@@ -64,6 +476,145 @@ pub struct NewTaskStatus<'a> {
     pub status_name: &'a str,
 }
 
+#[derive(Insertable)]
+#[diesel(table_name = projects)]
+pub struct NewProject<'a> {
+    pub project_name: &'a str,
+    pub description: Option<&'a str>,
+    pub team_id: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = teams)]
+pub struct NewTeam<'a> {
+    pub team_name: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = team_members)]
+pub struct NewTeamMember {
+    pub team_id: i32,
+    pub user_id: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = tags)]
+pub struct NewTag<'a> {
+    pub tag_name: &'a str,
+}
+
+// On update only the body is changed; the task and author stay as they were.
+#[derive(Insertable)]
+#[diesel(table_name = comments)]
+pub struct NewComment<'a> {
+    pub task_id: i32,
+    pub author_id: i32,
+    pub body: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = comment_revisions)]
+pub struct NewCommentRevision<'a> {
+    pub comment_id: i32,
+    pub version: i32,
+    pub body: &'a str,
+    pub created_at: chrono::NaiveDateTime,
+    pub edited_by: Option<i32>,
+    pub edited_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = attachments)]
+pub struct NewAttachment<'a> {
+    pub task_id: i32,
+    pub uploaded_by: i32,
+    pub file_name: &'a str,
+    pub content_type: &'a str,
+    pub size_bytes: i64,
+    pub storage_key: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = mentions)]
+pub struct NewMention {
+    pub comment_id: i32,
+    pub user_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = notifications)]
+pub struct NewNotification<'a> {
+    pub user_id: i32,
+    pub kind: &'a str,
+    pub task_id: Option<i32>,
+    pub comment_id: Option<i32>,
+    pub message: &'a str,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = notification_preferences)]
+pub struct NewNotificationPreference<'a> {
+    pub user_id: i32,
+    pub event_type: &'a str,
+    pub email: bool,
+    pub webhook: bool,
+    pub in_app: bool,
+    pub push: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = calendar_feeds)]
+pub struct NewCalendarFeed<'a> {
+    pub user_id: i32,
+    pub token_hash: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = push_subscriptions)]
+pub struct NewPushSubscription<'a> {
+    pub user_id: i32,
+    pub endpoint: &'a str,
+    pub p256dh: &'a str,
+    pub auth: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = slack_integrations)]
+pub struct NewSlackIntegration<'a> {
+    pub project_id: i32,
+    pub webhook_url: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = task_dependencies)]
+pub struct NewTaskDependency {
+    pub blocking_task_id: i32,
+    pub blocked_task_id: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = starred_tasks)]
+pub struct NewStarredTask {
+    pub user_id: i32,
+    pub task_id: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = task_watchers)]
+pub struct NewTaskWatcher {
+    pub task_id: i32,
+    pub user_id: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = task_tags)]
+pub struct NewTaskTag {
+    pub task_id: i32,
+    pub tag_id: i32,
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = user_tasks)]
 pub struct NewUserTask {
@@ -72,4 +623,150 @@ pub struct NewUserTask {
     pub task_status_id: i32
 }
 
+// An assignment with the names of its user, task and status, read in one join so a
+// list screen doesn't need a lookup per foreign key.
+#[derive(Queryable, Selectable, Debug, serde::Serialize, serde::Deserialize)]
+#[diesel(table_name = user_tasks)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct AssignmentDetail {
+    pub user_id: i32,
+    #[diesel(select_expression = users::name)]
+    pub user_name: String,
+    #[diesel(select_expression = users::email)]
+    pub user_email: String,
+    pub task_id: i32,
+    #[diesel(select_expression = tasks::task_name)]
+    pub task_name: String,
+    pub task_status_id: i32,
+    #[diesel(select_expression = task_statuses::status_name)]
+    pub status_name: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub version: i32,
+}
+
+// One create/update/delete/restore; before/after hold the row serialized as JSON.
+#[derive(Queryable, Debug, Selectable,Identifiable)]
+#[diesel(primary_key(audit_id))]
+#[diesel(table_name = audit_log)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct AuditEntry {
+    pub audit_id: i32,
+    pub actor_user_id: Option<i32>,
+    pub action: String,
+    pub entity: String,
+    pub entity_id: String,
+    pub before_json: Option<String>,
+    pub after_json: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub tenant_id: i32,
+}
+
+// A numbered snapshot of a task and its live assignments, taken after each change.
+#[derive(Queryable, Debug, Selectable,Identifiable)]
+#[diesel(primary_key(task_id, version))]
+#[diesel(table_name = task_revisions)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct TaskRevision {
+    pub task_id: i32,
+    pub version: i32,
+    pub task_name: String,
+    pub assignments_json: String,
+    pub actor_user_id: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+    // the rest of the task; priority is None only on revisions written before these were kept
+    pub due_date: Option<chrono::NaiveDate>,
+    pub priority: Option<TaskPriority>,
+    pub parent_task_id: Option<i32>,
+    pub recurrence: Option<String>,
+    pub project_id: Option<i32>,
+}
+
+// PATCH bodies: a None field leaves that column unchanged.
+#[derive(AsChangeset, Default)]
+#[diesel(table_name = task_statuses)]
+pub struct TaskStatusChanges<'a> {
+    pub status_name: Option<&'a str>,
+}
+
+#[derive(AsChangeset, Default)]
+#[diesel(table_name = user_tasks)]
+pub struct UserTaskChanges {
+    pub task_status_id: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = api_keys)]
+pub struct NewApiKey<'a> {
+    pub user_id: i32,
+    pub name: &'a str,
+    pub key_prefix: &'a str,
+    pub key_hash: &'a str,
+    pub daily_quota: Option<i32>,
+    pub monthly_quota: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = webhooks)]
+pub struct NewWebhook<'a> {
+    pub user_id: i32,
+    pub url: &'a str,
+    pub event_type: &'a str,
+    pub secret: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = outbox)]
+pub struct NewOutboxEntry<'a> {
+    pub webhook_id: i32,
+    pub payload: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = credentials)]
+pub struct NewCredential<'a> {
+    pub user_id: i32,
+    pub password_hash: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = oauth_identities)]
+pub struct NewOAuthIdentity<'a> {
+    pub user_id: i32,
+    pub provider: &'a str,
+    pub provider_user_id: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = audit_log)]
+pub struct NewAuditEntry<'a> {
+    pub actor_user_id: Option<i32>,
+    pub action: &'a str,
+    pub entity: &'a str,
+    pub entity_id: &'a str,
+    pub before_json: Option<String>,
+    pub after_json: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = task_revisions)]
+pub struct NewTaskRevision<'a> {
+    pub task_id: i32,
+    pub version: i32,
+    pub task_name: &'a str,
+    pub assignments_json: String,
+    pub actor_user_id: Option<i32>,
+    pub due_date: Option<chrono::NaiveDate>,
+    pub priority: Option<TaskPriority>,
+    pub parent_task_id: Option<i32>,
+    pub recurrence: Option<&'a str>,
+    pub project_id: Option<i32>,
+}
 
+#[derive(Insertable)]
+#[diesel(table_name = refresh_tokens)]
+pub struct NewRefreshToken<'a> {
+    pub user_id: i32,
+    pub token_hash: &'a str,
+    pub expires_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,116 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use crate::models::{NewNotification, Notification, NotificationPreference};
+use crate::pagination::{self, Page};
+use crate::preferences::Channel;
+use crate::schema::notifications;
+
+pub const ASSIGNMENT_NOTIFICATION: &str = "assignment";
+
+// Called by audit::record when one of the user's own assignments is made, taken away or moved.
+// Nothing is written when the user made the change themselves or turned in-app notifications
+// off for `event_type`.
+pub(crate) async fn notify_assignee(conn: &mut DbConnection, actor: Option<i32>, user_id: i32, task_id: i32, event_type: &str, message: &str) -> anyhow::Result<()> {
+    if Some(user_id) == actor || NotificationPreference::allowed(conn, vec![user_id], event_type, Channel::InApp).await?.is_empty() {
+        return Ok(());
+    }
+    diesel::insert_into(notifications::table)
+        .values(NewNotification {
+            user_id,
+            kind: ASSIGNMENT_NOTIFICATION,
+            task_id: Some(task_id),
+            comment_id: None,
+            message,
+            created_at: chrono::Utc::now().naive_utc(),
+        })
+        .execute(conn).await?;
+    Ok(())
+}
+
+// A user's inbox. Notifications are only ever reached through the user they're for, so the
+// tenant is the user's.
+impl Notification {
+    // Newest first; with `unread_only`, just the ones not marked read yet.
+    pub async fn read_page_for_user(conn: &mut DbConnection, user_id: i32, unread_only: bool, page: i64, per_page: i64) -> anyhow::Result<Page<Notification>> {
+        let inbox = || {
+            let mut query = notifications::table.filter(notifications::user_id.eq(user_id)).into_boxed();
+            if unread_only {
+                query = query.filter(notifications::read_at.is_null());
+            }
+            query
+        };
+        let total = inbox().count().get_result(conn).await?;
+        let items = inbox()
+            .order((notifications::created_at.desc(), notifications::notification_id.desc()))
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<Notification>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    pub async fn count_unread(conn: &mut DbConnection, user_id: i32) -> anyhow::Result<i64> {
+        let count = notifications::table
+            .filter(notifications::user_id.eq(user_id))
+            .filter(notifications::read_at.is_null())
+            .count()
+            .get_result(conn).await?;
+        Ok(count)
+    }
+
+    // Marks the notification read, keeping the time it was first read if it already was. None
+    // when it isn't the user's.
+    pub async fn mark_read(conn: &mut DbConnection, notification_id: i32, user_id: i32) -> anyhow::Result<Option<Notification>> {
+        let mine = notifications::table
+            .find(notification_id)
+            .filter(notifications::user_id.eq(user_id));
+        diesel::update(mine.filter(notifications::read_at.is_null()))
+            .set(notifications::read_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(conn).await?;
+        let notification = mine.first(conn).await.optional()?;
+        Ok(notification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditedCrud;
+    use crate::models::{NewUserTask, TaskWatcher, UserTask};
+    use crate::test_support::{conn, create_task};
+    use crate::watchers::WATCH_NOTIFICATION;
+
+    #[tokio::test]
+    async fn assignees_hear_about_their_assignments_and_watchers_about_the_rest() {
+        let mut conn = conn().await;
+        let task = create_task(&mut conn, "Ship it").await;
+        TaskWatcher::watch(&mut conn, task.task_id, 1).await.unwrap();
+        TaskWatcher::watch(&mut conn, task.task_id, 3).await.unwrap();
+        UserTask::create_audited(&mut conn, Some(2), NewUserTask { user_id: 1, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+
+        let inbox = Notification::read_page_for_user(&mut conn, 1, false, 1, 10).await.unwrap();
+        assert_eq!(inbox.items.iter().map(|notification| notification.kind.as_str()).collect::<Vec<_>>(), [ASSIGNMENT_NOTIFICATION]);
+        let watcher = Notification::read_page_for_user(&mut conn, 3, false, 1, 10).await.unwrap();
+        assert_eq!(watcher.items.iter().map(|notification| notification.kind.as_str()).collect::<Vec<_>>(), [WATCH_NOTIFICATION]);
+
+        // moving your own assignment isn't news to you
+        UserTask::update_audited(&mut conn, Some(1), (1, task.task_id), None, NewUserTask { user_id: 1, task_id: task.task_id, task_status_id: 2 }).await.unwrap();
+        assert_eq!(Notification::count_unread(&mut conn, 1).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn reading_a_notification_takes_it_off_the_unread_count() {
+        let mut conn = conn().await;
+        let task = create_task(&mut conn, "Ship it").await;
+        UserTask::create_audited(&mut conn, Some(2), NewUserTask { user_id: 1, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        let notification_id = Notification::read_page_for_user(&mut conn, 1, true, 1, 10).await.unwrap().items[0].notification_id;
+
+        assert!(Notification::mark_read(&mut conn, notification_id, 3).await.unwrap().is_none());
+        let read = Notification::mark_read(&mut conn, notification_id, 1).await.unwrap().unwrap();
+        assert!(read.read_at.is_some());
+        assert_eq!(Notification::mark_read(&mut conn, notification_id, 1).await.unwrap().unwrap().read_at, read.read_at);
+        assert_eq!(Notification::count_unread(&mut conn, 1).await.unwrap(), 0);
+        assert!(Notification::read_page_for_user(&mut conn, 1, true, 1, 10).await.unwrap().items.is_empty());
+        assert_eq!(Notification::read_page_for_user(&mut conn, 1, false, 1, 10).await.unwrap().total, 1);
+    }
+}
@@ -0,0 +1,162 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use serde::{Deserialize, Serialize};
+use crate::schema::{due_reminders, task_statuses, tasks, user_tasks, users};
+use crate::preferences::Channel;
+use crate::tenancy;
+
+// A live assignment whose task is past its due date while the assignment is still in a
+// status that doesn't count as finished.
+#[derive(Queryable, Selectable, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = user_tasks)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct OverdueAssignment {
+    pub user_id: i32,
+    #[diesel(select_expression = users::name)]
+    pub user_name: String,
+    pub task_id: i32,
+    #[diesel(select_expression = tasks::task_name)]
+    pub task_name: String,
+    #[diesel(select_expression = tasks::due_date.assume_not_null())]
+    #[diesel(select_expression_type = diesel::dsl::AssumeNotNull<tasks::due_date>)]
+    pub due_date: chrono::NaiveDate,
+    pub task_status_id: i32,
+    #[diesel(select_expression = task_statuses::status_name)]
+    pub status_name: String,
+}
+
+// Assignments due strictly before `today` whose status is not one of `terminal_statuses`
+// (matched by name), most overdue first. Tasks without a due date are never overdue.
+pub async fn find_overdue(conn: &mut DbConnection, today: chrono::NaiveDate, terminal_statuses: &[String]) -> anyhow::Result<Vec<OverdueAssignment>> {
+    let items = user_tasks::table
+        .inner_join(users::table)
+        .inner_join(tasks::table)
+        .inner_join(task_statuses::table)
+        .filter(user_tasks::tenant_id.eq(tenancy::current()))
+        .filter(user_tasks::deleted_at.is_null())
+        .filter(tasks::deleted_at.is_null())
+        .filter(tasks::due_date.lt(today))
+        .filter(task_statuses::status_name.ne_all(terminal_statuses))
+        .order((tasks::due_date, user_tasks::task_id, user_tasks::user_id))
+        .select(OverdueAssignment::as_select())
+        .load(conn).await?;
+    Ok(items)
+}
+
+// An unfinished assignment whose task falls due soon, with what a reminder to the assignee needs.
+#[derive(Queryable, Selectable, Debug, Clone, Serialize)]
+#[diesel(table_name = user_tasks)]
+#[diesel(check_for_backend(crate::DbBackend))]
+pub struct DueSoonAssignment {
+    pub user_id: i32,
+    #[diesel(select_expression = users::name)]
+    pub user_name: String,
+    #[diesel(select_expression = users::email)]
+    pub user_email: String,
+    pub task_id: i32,
+    #[diesel(select_expression = tasks::task_name)]
+    pub task_name: String,
+    #[diesel(select_expression = tasks::due_date.assume_not_null())]
+    #[diesel(select_expression_type = diesel::dsl::AssumeNotNull<tasks::due_date>)]
+    pub due_date: chrono::NaiveDate,
+}
+
+// Assignments due from `today` through `until` whose status is not one of `terminal_statuses`
+// and that haven't been reminded of this due date on `channel` yet (see mark_reminded),
+// soonest first.
+pub async fn find_due_soon(conn: &mut DbConnection, today: chrono::NaiveDate, until: chrono::NaiveDate, terminal_statuses: &[String], channel: Channel) -> anyhow::Result<Vec<DueSoonAssignment>> {
+    let reminded = due_reminders::table
+        .filter(due_reminders::task_id.eq(user_tasks::task_id))
+        .filter(due_reminders::user_id.eq(user_tasks::user_id))
+        .filter(due_reminders::channel.eq(channel.name()))
+        .filter(due_reminders::due_date.nullable().eq(tasks::due_date));
+    let items = user_tasks::table
+        .inner_join(users::table)
+        .inner_join(tasks::table)
+        .inner_join(task_statuses::table)
+        .filter(user_tasks::tenant_id.eq(tenancy::current()))
+        .filter(user_tasks::deleted_at.is_null())
+        .filter(tasks::deleted_at.is_null())
+        .filter(users::active.eq(true))
+        .filter(tasks::due_date.between(today, until))
+        .filter(task_statuses::status_name.ne_all(terminal_statuses))
+        .filter(diesel::dsl::not(diesel::dsl::exists(reminded)))
+        .order((tasks::due_date, user_tasks::task_id, user_tasks::user_id))
+        .select(DueSoonAssignment::as_select())
+        .load(conn).await?;
+    Ok(items)
+}
+
+// Records that the assignee has been reminded on `channel` of the task falling due on `due_date`.
+pub async fn mark_reminded(conn: &mut DbConnection, task_id: i32, user_id: i32, due_date: chrono::NaiveDate, channel: Channel) -> anyhow::Result<()> {
+    diesel::insert_into(due_reminders::table)
+        .values((
+            due_reminders::task_id.eq(task_id),
+            due_reminders::user_id.eq(user_id),
+            due_reminders::channel.eq(channel.name()),
+            due_reminders::due_date.eq(due_date),
+        ))
+        .on_conflict(conflict_target!((due_reminders::task_id, due_reminders::user_id, due_reminders::channel)))
+        .do_update()
+        .set((due_reminders::due_date.eq(due_date), due_reminders::sent_at.eq(chrono::Utc::now().naive_utc())))
+        .execute(conn).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::CrudOperations;
+    use crate::models::{NewTask, NewUserTask, Task, UserTask};
+    use crate::test_support::{self, new_task};
+
+    #[tokio::test]
+    async fn only_unfinished_assignments_past_their_due_date_are_overdue() {
+        let mut conn = test_support::conn().await;
+        let today = chrono::NaiveDate::from_ymd_opt(2031, 3, 10).unwrap();
+        let late = Task::create(&mut conn, NewTask { due_date: today.pred_opt(), ..new_task("Late") }).await.unwrap();
+        let due_today = Task::create(&mut conn, NewTask { due_date: Some(today), ..new_task("Due today") }).await.unwrap();
+        for (user_id, task_id, task_status_id) in [(1, late.task_id, 2), (2, late.task_id, 3), (1, due_today.task_id, 1)] {
+            UserTask::create(&mut conn, NewUserTask { user_id, task_id, task_status_id }).await.unwrap();
+        }
+        let overdue: Vec<(i32, i32)> = find_overdue(&mut conn, today, &["Completed".to_string()]).await.unwrap()
+            .iter()
+            .filter(|row| [late.task_id, due_today.task_id].contains(&row.task_id))
+            .map(|row| (row.user_id, row.task_id))
+            .collect();
+        assert_eq!(overdue, [(1, late.task_id)]);
+    }
+
+    async fn due_soon(conn: &mut DbConnection, today: chrono::NaiveDate, task_id: i32, channel: Channel) -> Vec<(i32, i32)> {
+        find_due_soon(conn, today, today.succ_opt().unwrap(), &["Completed".to_string()], channel).await.unwrap()
+            .iter()
+            .filter(|row| row.task_id == task_id)
+            .map(|row| (row.user_id, row.task_id))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn each_due_date_gets_one_reminder() {
+        let mut conn = test_support::conn().await;
+        let today = chrono::NaiveDate::from_ymd_opt(2031, 3, 10).unwrap();
+        let tomorrow = today.succ_opt().unwrap();
+        let task = Task::create(&mut conn, NewTask { due_date: Some(tomorrow), ..new_task("Due tomorrow") }).await.unwrap();
+        UserTask::create(&mut conn, NewUserTask { user_id: 1, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        assert_eq!(due_soon(&mut conn, today, task.task_id, Channel::Email).await, [(1, task.task_id)]);
+        mark_reminded(&mut conn, task.task_id, 1, today, Channel::Email).await.unwrap();
+        assert_eq!(due_soon(&mut conn, today, task.task_id, Channel::Email).await, [(1, task.task_id)], "a reminder of another due date doesn't count");
+        mark_reminded(&mut conn, task.task_id, 1, tomorrow, Channel::Email).await.unwrap();
+        assert!(due_soon(&mut conn, today, task.task_id, Channel::Email).await.is_empty());
+        // each channel reminds once of its own
+        assert_eq!(due_soon(&mut conn, today, task.task_id, Channel::Push).await, [(1, task.task_id)]);
+        mark_reminded(&mut conn, task.task_id, 1, tomorrow, Channel::Push).await.unwrap();
+        assert!(due_soon(&mut conn, today, task.task_id, Channel::Push).await.is_empty());
+
+        let later = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1);
+        Task::delete(&mut conn, task.task_id).await.unwrap();
+        UserTask::purge_deleted(&mut conn, later).await.unwrap();
+        assert_eq!(Task::purge_deleted(&mut conn, later).await.unwrap().0, 1);
+        assert_eq!(due_reminders::table.count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PER_PAGE: i64 = 25;
+pub const MAX_PER_PAGE: i64 = 100;
+
+// One page of a list query plus the metadata a client needs to ask for the next one.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+    pub total_pages: i64,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, page: i64, per_page: i64, total: i64) -> Page<T> {
+        let total_pages = (total + per_page - 1) / per_page;
+        Page { items, page, per_page, total, total_pages }
+    }
+}
+
+// For feeds that grow while they're read: pass next_cursor back as ?cursor= to continue after
+// the last item. None means there was nothing more when this page was read.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<i32>,
+}
+
+// Pages are 1-based, so page 1 starts at row 0. Callers reject pages past i64::MAX rows (see
+// PageQuery in rocket_app); this saturates rather than panic if one gets through.
+pub fn offset(page: i64, per_page: i64) -> i64 {
+    (page - 1).saturating_mul(per_page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn first_page_starts_at_row_zero() {
+        assert_eq!(offset(1, 25), 0);
+        assert_eq!(offset(3, 25), 50);
+    }
+
+    #[tokio::test]
+    async fn huge_pages_saturate() {
+        assert_eq!(offset(i64::MAX, 100), i64::MAX);
+    }
+
+    #[tokio::test]
+    async fn total_pages_rounds_up() {
+        assert_eq!(Page::new(Vec::<i32>::new(), 1, 10, 0).total_pages, 0);
+        assert_eq!(Page::new(Vec::<i32>::new(), 1, 10, 10).total_pages, 1);
+        assert_eq!(Page::new(Vec::<i32>::new(), 1, 10, 11).total_pages, 2);
+    }
+}
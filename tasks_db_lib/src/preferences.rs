@@ -0,0 +1,126 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::DbConnection;
+use crate::models::{NewNotificationPreference, NotificationPreference};
+use crate::schema::notification_preferences;
+
+// Everything a user can be notified about, named like the webhook events they share where
+// there is one. Not every channel carries every event: webhooks only have task and assignment
+// changes, emails only new assignments, finished ones and due dates, in-app notifications
+// only the user's own assignments, what happens on watched tasks and mentions, and Web Push
+// only the user's own assignments and their due dates.
+pub const DUE_SOON: &str = "assignment.due_soon";
+pub const MENTIONED: &str = "comment.mentioned";
+pub const NOTIFICATION_EVENT_TYPES: &[&str] = &[
+    "task.created", "task.updated", "task.deleted",
+    "assignment.created", "assignment.updated", "assignment.deleted",
+    DUE_SOON, MENTIONED,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Email,
+    Webhook,
+    InApp,
+    Push,
+}
+
+impl Channel {
+    // How the channel is recorded where it's stored, as in due_reminders.
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::Email => "email",
+            Channel::Webhook => "webhook",
+            Channel::InApp => "in_app",
+            Channel::Push => "push",
+        }
+    }
+}
+
+impl NotificationPreference {
+    // One entry per event type, in NOTIFICATION_EVENT_TYPES order; event types the user
+    // hasn't changed have every channel on.
+    pub async fn read_all_for_user(conn: &mut DbConnection, user_id: i32) -> anyhow::Result<Vec<NotificationPreference>> {
+        let mut saved = notification_preferences::table
+            .filter(notification_preferences::user_id.eq(user_id))
+            .load::<NotificationPreference>(conn).await?;
+        let preferences = NOTIFICATION_EVENT_TYPES.iter()
+            .map(|&event_type| match saved.iter().position(|saved| saved.event_type == event_type) {
+                Some(index) => saved.swap_remove(index),
+                None => NotificationPreference { user_id, event_type: event_type.to_string(), email: true, webhook: true, in_app: true, push: true },
+            })
+            .collect();
+        Ok(preferences)
+    }
+
+    // Replaces all of the user's settings: event types missing from `preferences` go back
+    // to every channel on.
+    pub async fn replace_all(conn: &mut DbConnection, user_id: i32, preferences: Vec<NewNotificationPreference<'_>>) -> anyhow::Result<Vec<NotificationPreference>> {
+        conn.transaction(|conn| async move {
+            diesel::delete(notification_preferences::table.filter(notification_preferences::user_id.eq(user_id))).execute(conn).await?;
+            let changed: Vec<NewNotificationPreference> = preferences.into_iter()
+                .filter(|preference| !(preference.email && preference.webhook && preference.in_app && preference.push))
+                .collect();
+            for preference in &changed {
+                diesel::insert_into(notification_preferences::table).values(preference).execute(conn).await?;
+            }
+            NotificationPreference::read_all_for_user(conn, user_id).await
+        }.scope_boxed()).await
+    }
+
+    // The users among `user_ids` who haven't turned `channel` off for `event_type`, in the
+    // order given.
+    pub async fn allowed(conn: &mut DbConnection, user_ids: Vec<i32>, event_type: &str, channel: Channel) -> anyhow::Result<Vec<i32>> {
+        if user_ids.is_empty() {
+            return Ok(user_ids);
+        }
+        let query = notification_preferences::table
+            .filter(notification_preferences::event_type.eq(event_type))
+            .filter(notification_preferences::user_id.eq_any(&user_ids))
+            .select(notification_preferences::user_id)
+            .into_boxed();
+        let opted_out: Vec<i32> = match channel {
+            Channel::Email => query.filter(notification_preferences::email.eq(false)),
+            Channel::Webhook => query.filter(notification_preferences::webhook.eq(false)),
+            Channel::InApp => query.filter(notification_preferences::in_app.eq(false)),
+            Channel::Push => query.filter(notification_preferences::push.eq(false)),
+        }.load(conn).await?;
+        Ok(user_ids.into_iter().filter(|user_id| !opted_out.contains(user_id)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn opt_out<'a>(user_id: i32, event_type: &'a str) -> NewNotificationPreference<'a> {
+        NewNotificationPreference { user_id, event_type, email: true, webhook: true, in_app: false, push: true }
+    }
+
+    #[tokio::test]
+    async fn only_changed_preferences_are_kept() {
+        let mut conn = test_support::conn().await;
+        let everything_on = NewNotificationPreference { user_id: 1, event_type: "task.created", email: true, webhook: true, in_app: true, push: true };
+        let preferences = NotificationPreference::replace_all(&mut conn, 1, vec![opt_out(1, MENTIONED), everything_on]).await.unwrap();
+        assert_eq!(preferences.len(), NOTIFICATION_EVENT_TYPES.len());
+        let off: Vec<&str> = preferences.iter().filter(|preference| !preference.in_app).map(|preference| preference.event_type.as_str()).collect();
+        assert_eq!(off, [MENTIONED]);
+        assert_eq!(notification_preferences::table.count().get_result::<i64>(&mut conn).await.unwrap(), 1);
+
+        NotificationPreference::replace_all(&mut conn, 1, vec![opt_out(1, DUE_SOON)]).await.unwrap();
+        let saved: Vec<String> = notification_preferences::table.select(notification_preferences::event_type).load(&mut conn).await.unwrap();
+        assert_eq!(saved, [DUE_SOON]);
+    }
+
+    #[tokio::test]
+    async fn only_the_channel_turned_off_is_skipped() {
+        let mut conn = test_support::conn().await;
+        NotificationPreference::replace_all(&mut conn, 2, vec![opt_out(2, MENTIONED)]).await.unwrap();
+        assert_eq!(NotificationPreference::allowed(&mut conn, vec![3, 2, 1], MENTIONED, Channel::InApp).await.unwrap(), [3, 1]);
+        assert_eq!(NotificationPreference::allowed(&mut conn, vec![3, 2, 1], MENTIONED, Channel::Email).await.unwrap(), [3, 2, 1]);
+        assert_eq!(NotificationPreference::allowed(&mut conn, vec![3, 2, 1], DUE_SOON, Channel::InApp).await.unwrap(), [3, 2, 1]);
+    }
+}
@@ -0,0 +1,88 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use crate::models::{NewPushSubscription, PushSubscription};
+use crate::schema::{push_subscriptions, users};
+use crate::tenancy;
+
+// Subscriptions belong to a user and are only reached through them, so the tenant is the
+// user's.
+impl PushSubscription {
+    // Registers the browser for the user. An endpoint that is already registered (to this user
+    // or another) is taken over, with the keys it was just given.
+    pub async fn save(conn: &mut DbConnection, new_subscription: NewPushSubscription<'_>) -> anyhow::Result<PushSubscription> {
+        diesel::insert_into(push_subscriptions::table)
+            .values(&new_subscription)
+            .on_conflict(conflict_target!(push_subscriptions::endpoint))
+            .do_update()
+            .set((
+                push_subscriptions::user_id.eq(new_subscription.user_id),
+                push_subscriptions::p256dh.eq(new_subscription.p256dh),
+                push_subscriptions::auth.eq(new_subscription.auth),
+            ))
+            .execute(conn).await?;
+        let subscription = push_subscriptions::table.filter(push_subscriptions::endpoint.eq(new_subscription.endpoint)).first(conn).await?;
+        Ok(subscription)
+    }
+
+    // The user's browsers, as long as the user is in the current tenant.
+    pub async fn read_all_for_user(conn: &mut DbConnection, user_id: i32) -> anyhow::Result<Vec<PushSubscription>> {
+        let results = push_subscriptions::table
+            .inner_join(users::table)
+            .filter(push_subscriptions::user_id.eq(user_id))
+            .filter(users::tenant_id.eq(tenancy::current()))
+            .order(push_subscriptions::push_subscription_id)
+            .select(PushSubscription::as_select())
+            .load(conn).await?;
+        Ok(results)
+    }
+
+    // Returns the number of subscriptions removed (0 when it isn't the user's).
+    pub async fn delete(conn: &mut DbConnection, push_subscription_id: i32, user_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::delete(push_subscriptions::table
+            .find(push_subscription_id)
+            .filter(push_subscriptions::user_id.eq(user_id)))
+            .execute(conn).await?;
+        Ok(count)
+    }
+
+    // For when the push service says the endpoint is gone (the browser unsubscribed or the
+    // subscription expired): nobody is listening there any more.
+    pub async fn delete_by_endpoint(conn: &mut DbConnection, endpoint: &str) -> anyhow::Result<usize> {
+        let count = diesel::delete(push_subscriptions::table.filter(push_subscriptions::endpoint.eq(endpoint))).execute(conn).await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Tenant;
+    use crate::test_support::conn;
+
+    fn subscription(user_id: i32, endpoint: &str) -> NewPushSubscription<'_> {
+        NewPushSubscription { user_id, endpoint, p256dh: "BPublicKey", auth: "secret" }
+    }
+
+    #[tokio::test]
+    async fn an_endpoint_belongs_to_whoever_subscribed_it_last() {
+        let mut conn = conn().await;
+        let first = PushSubscription::save(&mut conn, subscription(1, "https://push.example.com/abc")).await.unwrap();
+        let again = PushSubscription::save(&mut conn, subscription(3, "https://push.example.com/abc")).await.unwrap();
+        assert_eq!(first.push_subscription_id, again.push_subscription_id);
+        assert!(PushSubscription::read_all_for_user(&mut conn, 1).await.unwrap().is_empty());
+        assert_eq!(PushSubscription::read_all_for_user(&mut conn, 3).await.unwrap().len(), 1);
+
+        assert_eq!(PushSubscription::delete(&mut conn, again.push_subscription_id, 1).await.unwrap(), 0);
+        assert_eq!(PushSubscription::delete_by_endpoint(&mut conn, "https://push.example.com/abc").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn other_tenants_subscriptions_are_out_of_reach() {
+        let mut conn = conn().await;
+        PushSubscription::save(&mut conn, subscription(1, "https://push.example.com/abc")).await.unwrap();
+        let other = Tenant::create(&mut conn, "Other").await.unwrap();
+        tenancy::enter(&mut conn, other.tenant_id).await.unwrap();
+        assert!(PushSubscription::read_all_for_user(&mut conn, 1).await.unwrap().is_empty());
+    }
+}
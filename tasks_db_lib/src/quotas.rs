@@ -0,0 +1,150 @@
+use std::fmt;
+use chrono::{Datelike, Months, NaiveDate};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::DbConnection;
+use serde::{Deserialize, Serialize};
+use crate::models::ApiKey;
+use crate::schema::api_key_usage;
+#[cfg(not(feature = "sqlite"))]
+use crate::schema::api_keys;
+#[cfg(not(feature = "sqlite"))]
+use diesel_async::AsyncConnection;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaPeriod {
+    Day,
+    Month,
+}
+
+// Returned (inside anyhow) when a request would take an API key past one of its quotas.
+#[derive(Debug, PartialEq)]
+pub struct QuotaExceeded {
+    pub period: QuotaPeriod,
+    pub quota: i32,
+    // the first day the key can be used again
+    pub resets_on: NaiveDate,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let period = match self.period {
+            QuotaPeriod::Day => "daily",
+            QuotaPeriod::Month => "monthly",
+        };
+        write!(f, "{} quota of {} requests is used up until {}", period, self.quota, self.resets_on)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    pub requests: i32,
+}
+
+// What an API key has used so far this day and month (UTC), next to what it's allowed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Usage {
+    pub api_key_id: i32,
+    pub today: i64,
+    pub daily_quota: Option<i32>,
+    pub this_month: i64,
+    pub monthly_quota: Option<i32>,
+    // every day of the month the key was used, oldest first
+    pub days: Vec<DailyUsage>,
+}
+
+fn month_start(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).expect("every month has a first day")
+}
+
+pub async fn usage(conn: &mut DbConnection, api_key: &ApiKey, today: NaiveDate) -> anyhow::Result<Usage> {
+    let days: Vec<DailyUsage> = api_key_usage::table
+        .filter(api_key_usage::api_key_id.eq(api_key.api_key_id))
+        .filter(api_key_usage::day.between(month_start(today), today))
+        .order(api_key_usage::day)
+        .select((api_key_usage::day, api_key_usage::requests))
+        .load::<(NaiveDate, i32)>(conn).await?
+        .into_iter()
+        .map(|(day, requests)| DailyUsage { day, requests })
+        .collect();
+    Ok(Usage {
+        api_key_id: api_key.api_key_id,
+        today: days.iter().filter(|d| d.day == today).map(|d| i64::from(d.requests)).sum(),
+        daily_quota: api_key.daily_quota,
+        this_month: days.iter().map(|d| i64::from(d.requests)).sum(),
+        monthly_quota: api_key.monthly_quota,
+        days,
+    })
+}
+
+// Counts one request against the key, unless that would take it past a quota; then nothing is
+// counted and the error is a QuotaExceeded. Requests that were refused don't use up anything.
+// Two requests on the same key can't both read the usage before either is counted: SQLite's
+// immediate transaction takes the write lock up front, and PostgreSQL and MySQL lock the key's row.
+#[cfg(feature = "sqlite")]
+pub async fn record_request(conn: &mut DbConnection, api_key: &ApiKey, today: NaiveDate) -> anyhow::Result<()> {
+    conn.immediate_transaction(|conn| count_request(conn, api_key, today).scope_boxed()).await
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub async fn record_request(conn: &mut DbConnection, api_key: &ApiKey, today: NaiveDate) -> anyhow::Result<()> {
+    conn.transaction(|conn| async move {
+        api_keys::table.find(api_key.api_key_id).select(api_keys::api_key_id).for_update().first::<i32>(conn).await?;
+        count_request(conn, api_key, today).await
+    }.scope_boxed()).await
+}
+
+async fn count_request(conn: &mut DbConnection, api_key: &ApiKey, today: NaiveDate) -> anyhow::Result<()> {
+    let used = usage(conn, api_key, today).await?;
+    if let Some(quota) = api_key.daily_quota.filter(|quota| used.today >= i64::from(*quota)) {
+        let resets_on = today.succ_opt().unwrap_or(today);
+        return Err(QuotaExceeded { period: QuotaPeriod::Day, quota, resets_on }.into());
+    }
+    if let Some(quota) = api_key.monthly_quota.filter(|quota| used.this_month >= i64::from(*quota)) {
+        let resets_on = month_start(today).checked_add_months(Months::new(1)).unwrap_or(today);
+        return Err(QuotaExceeded { period: QuotaPeriod::Month, quota, resets_on }.into());
+    }
+    diesel::insert_into(api_key_usage::table)
+        .values((api_key_usage::api_key_id.eq(api_key.api_key_id), api_key_usage::day.eq(today), api_key_usage::requests.eq(1)))
+        .on_conflict(conflict_target!((api_key_usage::api_key_id, api_key_usage::day)))
+        .do_update()
+        .set(api_key_usage::requests.eq(api_key_usage::requests + 1))
+        .execute(conn).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NewApiKey;
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn requests_count_until_a_quota_is_used_up() {
+        let mut conn = test_support::conn().await;
+        let new_key = NewApiKey { user_id: 1, name: "ci", key_prefix: "abc", key_hash: "def", daily_quota: Some(2), monthly_quota: Some(3) };
+        let api_key = ApiKey::create(&mut conn, new_key).await.unwrap();
+        let first = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let second = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+
+        record_request(&mut conn, &api_key, first).await.unwrap();
+        record_request(&mut conn, &api_key, first).await.unwrap();
+        let refused = record_request(&mut conn, &api_key, first).await.unwrap_err();
+        assert_eq!(refused.downcast_ref::<QuotaExceeded>(), Some(&QuotaExceeded { period: QuotaPeriod::Day, quota: 2, resets_on: second }));
+
+        record_request(&mut conn, &api_key, second).await.unwrap();
+        let refused = record_request(&mut conn, &api_key, second).await.unwrap_err();
+        let november = NaiveDate::from_ymd_opt(2026, 11, 1).unwrap();
+        assert_eq!(refused.downcast_ref::<QuotaExceeded>(), Some(&QuotaExceeded { period: QuotaPeriod::Month, quota: 3, resets_on: november }));
+
+        let used = usage(&mut conn, &api_key, second).await.unwrap();
+        assert_eq!((used.today, used.this_month), (1, 3));
+        assert_eq!(used.days, vec![DailyUsage { day: first, requests: 2 }, DailyUsage { day: second, requests: 1 }]);
+        assert_eq!(usage(&mut conn, &api_key, november).await.unwrap().this_month, 0);
+    }
+}
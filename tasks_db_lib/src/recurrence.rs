@@ -0,0 +1,205 @@
+use std::fmt;
+use chrono::{Months, NaiveDate};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::DbConnection;
+use crate::audit::AuditedCrud;
+use crate::crud::task_is_complete;
+use crate::models::{NewTask, NewTaskTag, NewUserTask, Task, TaskStatus, UserTask};
+use crate::schema::{task_tags, tasks, user_tasks};
+use crate::tenancy;
+
+pub const MAX_INTERVAL: u32 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// A cut-down RRULE: FREQ (DAILY, WEEKLY, MONTHLY or YEARLY) and an optional INTERVAL,
+// e.g. "FREQ=WEEKLY;INTERVAL=2" for every other week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+}
+
+impl Recurrence {
+    // Parts may come in any order and in any case. Errors are meant for the client.
+    pub fn parse(rule: &str) -> Result<Recurrence, String> {
+        let mut frequency = None;
+        let mut interval = 1;
+        for part in rule.split(';').map(str::trim).filter(|part| !part.is_empty()) {
+            let Some((key, value)) = part.split_once('=') else {
+                return Err(format!("'{}' is not a KEY=VALUE pair", part));
+            };
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.trim().to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(format!("FREQ must be DAILY, WEEKLY, MONTHLY or YEARLY, not '{}'", other)),
+                    });
+                }
+                "INTERVAL" => {
+                    interval = value.trim().parse().ok()
+                        .filter(|n| (1..=MAX_INTERVAL).contains(n))
+                        .ok_or_else(|| format!("INTERVAL must be a whole number from 1 to {}", MAX_INTERVAL))?;
+                }
+                other => return Err(format!("'{}' is not supported; use FREQ and INTERVAL", other)),
+            }
+        }
+        let frequency = frequency.ok_or("FREQ is required")?;
+        Ok(Recurrence { frequency, interval })
+    }
+
+    // Monthly and yearly steps land on the last day of a shorter month rather than overflowing.
+    pub fn next_after(&self, date: NaiveDate) -> NaiveDate {
+        let next = match self.frequency {
+            Frequency::Daily => date.checked_add_days(chrono::Days::new(self.interval.into())),
+            Frequency::Weekly => date.checked_add_days(chrono::Days::new(7 * u64::from(self.interval))),
+            Frequency::Monthly => date.checked_add_months(Months::new(self.interval)),
+            Frequency::Yearly => date.checked_add_months(Months::new(12 * self.interval)),
+        };
+        next.unwrap_or(date)
+    }
+}
+
+// The normalized form that gets stored, e.g. "FREQ=MONTHLY;INTERVAL=1".
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={};INTERVAL={}", frequency, self.interval)
+    }
+}
+
+// Creates the next occurrence of every recurring, unpaused task that is complete (see
+// crud::task_is_complete) and doesn't have one yet. The new task copies the name, priority,
+// parent, rule and tags; its due date moves on by one step from the old one, or from `today`
+// if there wasn't one. Assignees carry over, starting again in the first status by id.
+// Returns the tasks created.
+pub async fn materialize_next(conn: &mut DbConnection, today: NaiveDate, terminal_statuses: &[String]) -> anyhow::Result<Vec<Task>> {
+    let finished: Vec<Task> = tasks::table
+        .filter(tasks::tenant_id.eq(tenancy::current()))
+        .filter(tasks::recurrence.is_not_null())
+        .filter(tasks::recurrence_paused.eq(false))
+        .filter(tasks::next_occurrence_id.is_null())
+        .filter(tasks::deleted_at.is_null())
+        .filter(task_is_complete(terminal_statuses))
+        .order(tasks::task_id)
+        .load(conn).await?;
+    let first_status = TaskStatus::initial_id(conn).await?;
+    let mut created = Vec::new();
+    for task in finished {
+        // a rule that no longer parses stops the series rather than failing every scan
+        let Some(recurrence) = task.recurrence.as_deref().and_then(|rule| Recurrence::parse(rule).ok()) else {
+            continue;
+        };
+        let next = conn.transaction(|conn| async move {
+            let next = Task::create_audited(conn, None, NewTask {
+                task_name: &task.task_name,
+                due_date: Some(recurrence.next_after(task.due_date.unwrap_or(today))),
+                priority: task.priority,
+                parent_task_id: task.parent_task_id,
+                recurrence: task.recurrence.as_deref(),
+                project_id: task.project_id,
+            }).await?;
+            let tags: Vec<NewTaskTag> = task_tags::table
+                .filter(task_tags::task_id.eq(task.task_id))
+                .select(task_tags::tag_id)
+                .load::<i32>(conn).await?
+                .into_iter()
+                .map(|tag_id| NewTaskTag { task_id: next.task_id, tag_id })
+                .collect();
+            for tag in &tags {
+                diesel::insert_into(task_tags::table).values(tag).execute(conn).await?;
+            }
+            if let Some(task_status_id) = first_status {
+                let assignees: Vec<i32> = user_tasks::table
+                    .filter(user_tasks::task_id.eq(task.task_id))
+                    .filter(user_tasks::deleted_at.is_null())
+                    .select(user_tasks::user_id)
+                    .load(conn).await?;
+                for user_id in assignees {
+                    UserTask::create_audited(conn, None, NewUserTask { user_id, task_id: next.task_id, task_status_id }).await?;
+                }
+            }
+            diesel::update(tasks::table.find(task.task_id))
+                .set(tasks::next_occurrence_id.eq(next.task_id))
+                .execute(conn).await?;
+            anyhow::Ok(next)
+        }.scope_boxed()).await?;
+        created.push(next);
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::CrudOperations;
+    use crate::test_support::{self, new_task};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn rules_parse_in_any_order_and_case() {
+        assert_eq!(Recurrence::parse("interval=2; freq=weekly").unwrap(), Recurrence { frequency: Frequency::Weekly, interval: 2 });
+        assert_eq!(Recurrence::parse("FREQ=MONTHLY").unwrap().to_string(), "FREQ=MONTHLY;INTERVAL=1");
+        assert!(Recurrence::parse("INTERVAL=2").is_err());
+        assert!(Recurrence::parse("FREQ=DAILY;INTERVAL=0").is_err());
+        assert!(Recurrence::parse("FREQ=DAILY;BYDAY=MO").is_err());
+    }
+
+    #[test]
+    fn monthly_steps_stop_at_the_end_of_a_short_month() {
+        let monthly = Recurrence { frequency: Frequency::Monthly, interval: 1 };
+        assert_eq!(monthly.next_after(date(2031, 1, 31)), date(2031, 2, 28));
+        assert_eq!(Recurrence { frequency: Frequency::Weekly, interval: 2 }.next_after(date(2031, 1, 31)), date(2031, 2, 14));
+    }
+
+    #[tokio::test]
+    async fn a_finished_occurrence_gets_one_successor() {
+        let mut conn = test_support::conn().await;
+        let completed = ["Completed".to_string()];
+        let weekly = Task::create(&mut conn, NewTask { recurrence: Some("FREQ=WEEKLY;INTERVAL=1"), due_date: Some(date(2031, 3, 3)), ..new_task("Water the plants") }).await.unwrap();
+        let paused = Task::create(&mut conn, NewTask { recurrence: Some("FREQ=DAILY;INTERVAL=1"), ..new_task("Feed the cat") }).await.unwrap();
+        Task::set_recurrence_paused(&mut conn, None, paused.task_id, true).await.unwrap();
+        for task_id in [weekly.task_id, paused.task_id] {
+            UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id, task_status_id: 3 }).await.unwrap();
+        }
+
+        let created = materialize_next(&mut conn, date(2031, 3, 5), &completed).await.unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!((created[0].task_name.as_str(), created[0].due_date), ("Water the plants", Some(date(2031, 3, 10))));
+        assert_eq!(UserTask::read(&mut conn, (2, created[0].task_id)).await.unwrap().unwrap().task_status_id, 1);
+        assert!(materialize_next(&mut conn, date(2031, 3, 5), &completed).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn purging_an_occurrence_unlinks_it_from_the_one_before() {
+        let mut conn = test_support::conn().await;
+        let first = Task::create(&mut conn, NewTask { recurrence: Some("FREQ=DAILY;INTERVAL=1"), ..new_task("Feed the cat") }).await.unwrap();
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: first.task_id, task_status_id: 3 }).await.unwrap();
+        let next = materialize_next(&mut conn, date(2031, 3, 5), &["Completed".to_string()]).await.unwrap().remove(0);
+        UserTask::delete(&mut conn, (2, next.task_id)).await.unwrap();
+        Task::delete(&mut conn, next.task_id).await.unwrap();
+        UserTask::purge_deleted(&mut conn, chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1)).await.unwrap();
+        Task::purge_deleted(&mut conn, chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1)).await.unwrap();
+        assert_eq!(Task::read(&mut conn, first.task_id).await.unwrap().unwrap().next_occurrence_id, None);
+    }
+}
@@ -0,0 +1,251 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::DbConnection;
+use serde::{Deserialize, Serialize};
+use crate::audit::{self, AuditAction};
+use crate::crud::CrudOperations;
+use crate::models::{NewTask, NewTaskRevision, NewUserTask, Project, Task, TaskRevision, UserTask};
+use crate::pagination::{self, Page};
+use crate::schema::{task_revisions, user_tasks};
+
+// What a revision remembers about one assignment. Stored as a JSON array ordered by user_id.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AssignmentSnapshot {
+    pub user_id: i32,
+    pub task_status_id: i32,
+}
+
+impl TaskRevision {
+    pub fn assignments(&self) -> anyhow::Result<Vec<AssignmentSnapshot>> {
+        Ok(serde_json::from_str(&self.assignments_json)?)
+    }
+
+    // Whether the task's own fields are the ones this revision recorded.
+    fn matches(&self, task: &Task) -> bool {
+        self.task_name == task.task_name
+            && self.due_date == task.due_date
+            && self.priority == Some(task.priority)
+            && self.parent_task_id == task.parent_task_id
+            && self.recurrence == task.recurrence
+            && self.project_id == task.project_id
+    }
+
+    // The task's fields as this revision recorded them. A parent that is no longer a live task
+    // (or has since become one of this task's subtasks) and a project that has since been deleted
+    // are dropped rather than restored. Revisions that predate the full snapshot only know the
+    // name, so the rest is kept from `current`.
+    async fn fields<'a>(&'a self, conn: &mut DbConnection, current: &'a Task) -> anyhow::Result<NewTask<'a>> {
+        let Some(priority) = self.priority else {
+            return Ok(NewTask { task_name: &self.task_name, due_date: current.due_date, priority: current.priority, parent_task_id: current.parent_task_id, recurrence: current.recurrence.as_deref(), project_id: current.project_id });
+        };
+        let parent_task_id = match self.parent_task_id {
+            Some(parent_id) if Task::read(conn, parent_id).await?.is_some() && !Task::would_create_cycle(conn, self.task_id, parent_id).await? => Some(parent_id),
+            _ => None,
+        };
+        let project_id = match self.project_id {
+            Some(project_id) if Project::read(conn, project_id).await?.is_some() => Some(project_id),
+            _ => None,
+        };
+        Ok(NewTask { task_name: &self.task_name, due_date: self.due_date, priority, parent_task_id, recurrence: self.recurrence.as_deref(), project_id })
+    }
+
+    async fn latest(conn: &mut DbConnection, task_id: i32) -> anyhow::Result<Option<TaskRevision>> {
+        let revision = task_revisions::table
+            .filter(task_revisions::task_id.eq(task_id))
+            .order(task_revisions::version.desc())
+            .first(conn).await
+            .optional()?;
+        Ok(revision)
+    }
+
+    // Called by audit::record after any change to a task or one of its assignments. Writes
+    // the next version unless the task is in the trash or nothing it tracks has changed.
+    pub async fn snapshot(conn: &mut DbConnection, actor: Option<i32>, task_id: i32) -> anyhow::Result<()> {
+        let Some(task) = Task::read(conn, task_id).await? else {
+            return Ok(());
+        };
+        let assignments: Vec<AssignmentSnapshot> = user_tasks::table
+            .filter(user_tasks::task_id.eq(task_id))
+            .filter(user_tasks::deleted_at.is_null())
+            .order(user_tasks::user_id)
+            .load::<UserTask>(conn).await?
+            .into_iter()
+            .map(|ut| AssignmentSnapshot { user_id: ut.user_id, task_status_id: ut.task_status_id })
+            .collect();
+        let latest = Self::latest(conn, task_id).await?;
+        if let Some(latest) = &latest && latest.matches(&task) && latest.assignments()? == assignments {
+            return Ok(());
+        }
+        let revision = NewTaskRevision {
+            task_id,
+            version: latest.map_or(1, |latest| latest.version + 1),
+            task_name: &task.task_name,
+            assignments_json: serde_json::to_string(&assignments)?,
+            actor_user_id: actor,
+            due_date: task.due_date,
+            priority: Some(task.priority),
+            parent_task_id: task.parent_task_id,
+            recurrence: task.recurrence.as_deref(),
+            project_id: task.project_id,
+        };
+        diesel::insert_into(task_revisions::table).values(&revision).execute(conn).await?;
+        Ok(())
+    }
+
+    // Newest first.
+    pub async fn read_history(conn: &mut DbConnection, task_id: i32, page: i64, per_page: i64) -> anyhow::Result<Page<TaskRevision>> {
+        let total = task_revisions::table.filter(task_revisions::task_id.eq(task_id)).count().get_result(conn).await?;
+        let items = task_revisions::table
+            .filter(task_revisions::task_id.eq(task_id))
+            .order(task_revisions::version.desc())
+            .limit(per_page)
+            .offset(pagination::offset(page, per_page))
+            .load::<TaskRevision>(conn).await?;
+        Ok(Page::new(items, page, per_page, total))
+    }
+
+    pub async fn read_version(conn: &mut DbConnection, task_id: i32, version: i32) -> anyhow::Result<Option<TaskRevision>> {
+        let revision = task_revisions::table
+            .find((task_id, version))
+            .first(conn).await
+            .optional()?;
+        Ok(revision)
+    }
+
+    // Puts the task and its assignments back the way they were at `version`. Each change is
+    // audited on its own, then the result becomes one new version (history is never rewritten).
+    // Returns None when the task or that version does not exist.
+    pub async fn revert(conn: &mut DbConnection, actor: Option<i32>, task_id: i32, version: i32) -> anyhow::Result<Option<Task>> {
+        conn.transaction(|conn| async move {
+            let Some(revision) = Self::read_version(conn, task_id, version).await? else {
+                return Ok(None);
+            };
+            let Some(current) = Task::read(conn, task_id).await? else {
+                return Ok(None);
+            };
+            let fields = revision.fields(conn, &current).await?;
+            let unchanged = fields.task_name == current.task_name
+                && fields.due_date == current.due_date
+                && fields.priority == current.priority
+                && fields.parent_task_id == current.parent_task_id
+                && fields.recurrence == current.recurrence.as_deref()
+                && fields.project_id == current.project_id;
+            let task = if unchanged {
+                current
+            } else {
+                let task = Task::update(conn, task_id, fields).await?;
+                audit::log(conn, actor, AuditAction::Update, Some(&current), Some(&task)).await?;
+                task
+            };
+
+            let target = revision.assignments()?;
+            let live = user_tasks::table
+                .filter(user_tasks::task_id.eq(task_id))
+                .filter(user_tasks::deleted_at.is_null())
+                .load::<UserTask>(conn).await?;
+            for before in &live {
+                match target.iter().find(|snapshot| snapshot.user_id == before.user_id) {
+                    None => {
+                        UserTask::delete(conn, (before.user_id, task_id)).await?;
+                        audit::log(conn, actor, AuditAction::Delete, Some(before), None).await?;
+                    }
+                    Some(snapshot) if snapshot.task_status_id != before.task_status_id => {
+                        let after = UserTask::update(conn, (before.user_id, task_id), NewUserTask {
+                            user_id: before.user_id,
+                            task_id,
+                            task_status_id: snapshot.task_status_id,
+                        }).await?;
+                        audit::log(conn, actor, AuditAction::Update, Some(before), Some(&after)).await?;
+                    }
+                    Some(_) => {}
+                }
+            }
+            for snapshot in target.iter().filter(|snapshot| live.iter().all(|ut| ut.user_id != snapshot.user_id)) {
+                let created = UserTask::create(conn, NewUserTask {
+                    user_id: snapshot.user_id,
+                    task_id,
+                    task_status_id: snapshot.task_status_id,
+                }).await?;
+                audit::log(conn, actor, AuditAction::Create, None, Some(&created)).await?;
+            }
+
+            Self::snapshot(conn, actor, task_id).await?;
+            Ok(Some(task))
+        }.scope_boxed()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditedCrud;
+    use crate::enums::TaskPriority;
+    use crate::models::NewProject;
+    use crate::test_support::{self, new_task};
+
+    async fn versions(conn: &mut DbConnection, task_id: i32) -> Vec<i32> {
+        TaskRevision::read_history(conn, task_id, 1, 100).await.unwrap().items.iter().map(|revision| revision.version).collect()
+    }
+
+    #[tokio::test]
+    async fn each_real_change_is_a_new_version() {
+        let mut conn = test_support::conn().await;
+        let task = Task::create_audited(&mut conn, Some(1), new_task("Print badges")).await.unwrap();
+        UserTask::create_audited(&mut conn, Some(1), NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        // same name as before: nothing new to remember
+        Task::update_audited(&mut conn, Some(1), task.task_id, None, new_task("Print badges")).await.unwrap();
+        assert_eq!(versions(&mut conn, task.task_id).await, [2, 1]);
+
+        let second = TaskRevision::read_version(&mut conn, task.task_id, 2).await.unwrap().unwrap();
+        assert_eq!(second.assignments().unwrap(), [AssignmentSnapshot { user_id: 2, task_status_id: 1 }]);
+        assert!(TaskRevision::read_version(&mut conn, task.task_id, 3).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn reverting_restores_the_name_and_assignments_as_a_new_version() {
+        let mut conn = test_support::conn().await;
+        let task = Task::create_audited(&mut conn, Some(1), new_task("Print badges")).await.unwrap();
+        UserTask::create_audited(&mut conn, Some(1), NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        Task::update_audited(&mut conn, Some(1), task.task_id, None, new_task("Print lanyards")).await.unwrap();
+        UserTask::delete_audited(&mut conn, Some(1), (2, task.task_id)).await.unwrap();
+        UserTask::create_audited(&mut conn, Some(1), NewUserTask { user_id: 3, task_id: task.task_id, task_status_id: 2 }).await.unwrap();
+
+        let reverted = TaskRevision::revert(&mut conn, Some(1), task.task_id, 2).await.unwrap().unwrap();
+        assert_eq!(reverted.task_name, "Print badges");
+        assert!(UserTask::read(&mut conn, (2, task.task_id)).await.unwrap().is_some());
+        assert!(UserTask::read(&mut conn, (3, task.task_id)).await.unwrap().is_none());
+        assert_eq!(versions(&mut conn, task.task_id).await, [6, 5, 4, 3, 2, 1]);
+        assert!(TaskRevision::revert(&mut conn, Some(1), task.task_id, 99).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn reverting_restores_the_whole_task() {
+        let mut conn = test_support::conn().await;
+        let project = Project::create(&mut conn, NewProject { project_name: "Launch", description: None, team_id: None }).await.unwrap();
+        let task = Task::create_audited(&mut conn, None, new_task("Write the notes")).await.unwrap();
+        let due_date = chrono::NaiveDate::from_ymd_opt(2026, 12, 1);
+        let changed = NewTask { due_date, priority: TaskPriority::Urgent, recurrence: Some("weekly"), project_id: Some(project.project_id), ..new_task("Write the notes") };
+        Task::update_audited(&mut conn, None, task.task_id, None, changed).await.unwrap();
+
+        let history = TaskRevision::read_history(&mut conn, task.task_id, 1, 10).await.unwrap();
+        assert_eq!(history.total, 2);
+        assert_eq!(history.items[0].priority, Some(TaskPriority::Urgent));
+        assert_eq!(history.items[0].due_date, due_date);
+
+        let reverted = TaskRevision::revert(&mut conn, None, task.task_id, 1).await.unwrap().unwrap();
+        assert_eq!(reverted.priority, TaskPriority::Medium);
+        assert_eq!(reverted.due_date, None);
+        assert_eq!(reverted.recurrence, None);
+        assert_eq!(reverted.project_id, None);
+        assert_eq!(TaskRevision::read_history(&mut conn, task.task_id, 1, 10).await.unwrap().total, 3);
+
+        // back to version 2 after the project is gone: everything else returns, the project doesn't
+        Project::delete(&mut conn, project.project_id).await.unwrap();
+        let reverted = TaskRevision::revert(&mut conn, None, task.task_id, 2).await.unwrap().unwrap();
+        assert_eq!(reverted.priority, TaskPriority::Urgent);
+        assert_eq!(reverted.recurrence.as_deref(), Some("weekly"));
+        assert_eq!(reverted.project_id, None);
+    }
+}
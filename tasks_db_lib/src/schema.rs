@@ -1,9 +1,316 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_keys (api_key_id) {
+        api_key_id -> Integer,
+        user_id -> Integer,
+        name -> Text,
+        key_prefix -> Text,
+        key_hash -> Text,
+        revoked -> Bool,
+        created_at -> Timestamp,
+        daily_quota -> Nullable<Integer>,
+        monthly_quota -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    api_key_usage (api_key_id, day) {
+        api_key_id -> Integer,
+        day -> Date,
+        requests -> Integer,
+    }
+}
+
+diesel::table! {
+    attachments (attachment_id) {
+        attachment_id -> Integer,
+        task_id -> Integer,
+        uploaded_by -> Integer,
+        file_name -> Text,
+        content_type -> Text,
+        size_bytes -> BigInt,
+        storage_key -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    audit_log (audit_id) {
+        audit_id -> Integer,
+        actor_user_id -> Nullable<Integer>,
+        action -> Text,
+        entity -> Text,
+        entity_id -> Text,
+        before_json -> Nullable<Text>,
+        after_json -> Nullable<Text>,
+        created_at -> Timestamp,
+        tenant_id -> Integer,
+    }
+}
+
+diesel::table! {
+    calendar_feeds (user_id) {
+        user_id -> Integer,
+        token_hash -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    comment_revisions (comment_id, version) {
+        comment_id -> Integer,
+        version -> Integer,
+        body -> Text,
+        created_at -> Timestamp,
+        edited_by -> Nullable<Integer>,
+        edited_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    comments (comment_id) {
+        comment_id -> Integer,
+        task_id -> Integer,
+        author_id -> Integer,
+        body -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+    }
+}
+
+diesel::table! {
+    credentials (user_id) {
+        user_id -> Integer,
+        password_hash -> Text,
+    }
+}
+
+diesel::table! {
+    due_reminders (task_id, user_id, channel) {
+        task_id -> Integer,
+        user_id -> Integer,
+        channel -> Text,
+        due_date -> Date,
+        sent_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    mentions (comment_id, user_id) {
+        comment_id -> Integer,
+        user_id -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    notification_preferences (user_id, event_type) {
+        user_id -> Integer,
+        event_type -> Text,
+        email -> Bool,
+        webhook -> Bool,
+        in_app -> Bool,
+        push -> Bool,
+    }
+}
+
+diesel::table! {
+    notifications (notification_id) {
+        notification_id -> Integer,
+        user_id -> Integer,
+        kind -> Text,
+        task_id -> Nullable<Integer>,
+        comment_id -> Nullable<Integer>,
+        message -> Text,
+        read_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    oauth_identities (oauth_identity_id) {
+        oauth_identity_id -> Integer,
+        user_id -> Integer,
+        provider -> Text,
+        provider_user_id -> Text,
+    }
+}
+
+diesel::table! {
+    outbox (outbox_id) {
+        outbox_id -> Integer,
+        webhook_id -> Integer,
+        payload -> Text,
+        attempts -> Integer,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    projects (project_id) {
+        project_id -> Integer,
+        project_name -> Text,
+        description -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+        team_id -> Nullable<Integer>,
+        tenant_id -> Integer,
+    }
+}
+
+diesel::table! {
+    push_subscriptions (push_subscription_id) {
+        push_subscription_id -> Integer,
+        user_id -> Integer,
+        endpoint -> Text,
+        p256dh -> Text,
+        auth -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    refresh_tokens (refresh_token_id) {
+        refresh_token_id -> Integer,
+        user_id -> Integer,
+        token_hash -> Text,
+        expires_at -> Timestamp,
+        revoked -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    revoked_tokens (jti) {
+        jti -> Text,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    roles (role_id) {
+        role_id -> Integer,
+        role_name -> Text,
+    }
+}
+
+diesel::table! {
+    slack_integrations (project_id) {
+        project_id -> Integer,
+        webhook_url -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    starred_tasks (user_id, task_id) {
+        user_id -> Integer,
+        task_id -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    tags (tag_id) {
+        tag_id -> Integer,
+        tag_name -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+        tenant_id -> Integer,
+    }
+}
+
+diesel::table! {
+    task_dependencies (blocking_task_id, blocked_task_id) {
+        blocking_task_id -> Integer,
+        blocked_task_id -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    task_revisions (task_id, version) {
+        task_id -> Integer,
+        version -> Integer,
+        task_name -> Text,
+        assignments_json -> Text,
+        actor_user_id -> Nullable<Integer>,
+        created_at -> Timestamp,
+        due_date -> Nullable<Date>,
+        priority -> Nullable<Integer>,
+        parent_task_id -> Nullable<Integer>,
+        recurrence -> Nullable<Text>,
+        project_id -> Nullable<Integer>,
+    }
+}
+
 diesel::table! {
     task_statuses (task_status_id) {
         task_status_id -> Integer,
         status_name -> Text,
+        deleted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+        tenant_id -> Integer,
+    }
+}
+
+diesel::table! {
+    task_watchers (task_id, user_id) {
+        task_id -> Integer,
+        user_id -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    task_templates (template_id) {
+        template_id -> Integer,
+        template_name -> Text,
+        task_name -> Text,
+        priority -> Integer,
+        due_in_days -> Nullable<Integer>,
+        recurrence -> Nullable<Text>,
+        assignee_ids_json -> Text,
+        subtask_names_json -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+        tenant_id -> Integer,
+    }
+}
+
+diesel::table! {
+    task_tags (task_id, tag_id) {
+        task_id -> Integer,
+        tag_id -> Integer,
+    }
+}
+
+diesel::table! {
+    team_members (team_id, user_id) {
+        team_id -> Integer,
+        user_id -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    teams (team_id) {
+        team_id -> Integer,
+        team_name -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+        tenant_id -> Integer,
     }
 }
 
@@ -11,6 +318,26 @@ diesel::table! {
     tasks (task_id) {
         task_id -> Integer,
         task_name -> Text,
+        deleted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+        due_date -> Nullable<Date>,
+        priority -> Integer,
+        parent_task_id -> Nullable<Integer>,
+        recurrence -> Nullable<Text>,
+        recurrence_paused -> Bool,
+        next_occurrence_id -> Nullable<Integer>,
+        project_id -> Nullable<Integer>,
+        tenant_id -> Integer,
+    }
+}
+
+diesel::table! {
+    tenants (tenant_id) {
+        tenant_id -> Integer,
+        tenant_name -> Text,
+        created_at -> Timestamp,
     }
 }
 
@@ -19,6 +346,11 @@ diesel::table! {
         user_id -> Integer,
         task_id -> Integer,
         task_status_id -> Integer,
+        deleted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+        tenant_id -> Integer,
     }
 }
 
@@ -28,16 +360,107 @@ diesel::table! {
         name -> Text,
         email -> Text,
         active -> Bool,
+        role_id -> Integer,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+        tenant_id -> Integer,
+    }
+}
+
+diesel::table! {
+    webhooks (webhook_id) {
+        webhook_id -> Integer,
+        user_id -> Integer,
+        url -> Text,
+        event_type -> Text,
+        created_at -> Timestamp,
+        tenant_id -> Integer,
+        secret -> Text,
     }
 }
 
+diesel::joinable!(api_key_usage -> api_keys (api_key_id));
+diesel::joinable!(api_keys -> users (user_id));
+diesel::joinable!(attachments -> tasks (task_id));
+diesel::joinable!(attachments -> users (uploaded_by));
+diesel::joinable!(audit_log -> tenants (tenant_id));
+diesel::joinable!(calendar_feeds -> users (user_id));
+diesel::joinable!(comment_revisions -> comments (comment_id));
+diesel::joinable!(comments -> tasks (task_id));
+diesel::joinable!(comments -> users (author_id));
+diesel::joinable!(credentials -> users (user_id));
+diesel::joinable!(due_reminders -> tasks (task_id));
+diesel::joinable!(due_reminders -> users (user_id));
+diesel::joinable!(mentions -> comments (comment_id));
+diesel::joinable!(mentions -> users (user_id));
+diesel::joinable!(notification_preferences -> users (user_id));
+diesel::joinable!(notifications -> users (user_id));
+diesel::joinable!(oauth_identities -> users (user_id));
+diesel::joinable!(outbox -> webhooks (webhook_id));
+diesel::joinable!(projects -> teams (team_id));
+diesel::joinable!(projects -> tenants (tenant_id));
+diesel::joinable!(push_subscriptions -> users (user_id));
+diesel::joinable!(refresh_tokens -> users (user_id));
+diesel::joinable!(slack_integrations -> projects (project_id));
+diesel::joinable!(starred_tasks -> tasks (task_id));
+diesel::joinable!(starred_tasks -> users (user_id));
+diesel::joinable!(tags -> tenants (tenant_id));
+diesel::joinable!(task_revisions -> tasks (task_id));
+diesel::joinable!(task_statuses -> tenants (tenant_id));
+diesel::joinable!(task_tags -> tags (tag_id));
+diesel::joinable!(task_tags -> tasks (task_id));
+diesel::joinable!(task_templates -> tenants (tenant_id));
+diesel::joinable!(task_watchers -> tasks (task_id));
+diesel::joinable!(task_watchers -> users (user_id));
+diesel::joinable!(tasks -> projects (project_id));
+diesel::joinable!(tasks -> tenants (tenant_id));
+diesel::joinable!(team_members -> teams (team_id));
+diesel::joinable!(team_members -> users (user_id));
+diesel::joinable!(teams -> tenants (tenant_id));
 diesel::joinable!(user_tasks -> task_statuses (task_status_id));
 diesel::joinable!(user_tasks -> tasks (task_id));
+diesel::joinable!(user_tasks -> tenants (tenant_id));
 diesel::joinable!(user_tasks -> users (user_id));
+diesel::joinable!(users -> roles (role_id));
+diesel::joinable!(users -> tenants (tenant_id));
+diesel::joinable!(webhooks -> tenants (tenant_id));
+diesel::joinable!(webhooks -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    api_key_usage,
+    api_keys,
+    attachments,
+    audit_log,
+    calendar_feeds,
+    comment_revisions,
+    comments,
+    credentials,
+    due_reminders,
+    mentions,
+    notification_preferences,
+    notifications,
+    oauth_identities,
+    outbox,
+    projects,
+    push_subscriptions,
+    refresh_tokens,
+    revoked_tokens,
+    roles,
+    slack_integrations,
+    starred_tasks,
+    tags,
+    task_revisions,
+    task_dependencies,
     task_statuses,
+    task_tags,
+    task_templates,
+    task_watchers,
     tasks,
+    team_members,
+    teams,
+    tenants,
     user_tasks,
     users,
+    webhooks,
 );
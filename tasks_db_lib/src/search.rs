@@ -0,0 +1,339 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use diesel::sql_types::{BigInt, Double, Integer, Text};
+use serde::{Deserialize, Serialize};
+use crate::models::Task;
+use crate::schema::tasks;
+use crate::pagination::{self, Page};
+use crate::tenancy;
+
+// On SQLite, matches come from the task_search FTS5 table, which isn't in schema.rs (Diesel
+// can't describe virtual tables), so everything here is raw SQL with bound parameters. The
+// index covers every tenant, so matches are narrowed to the current one's tasks by hand (see
+// crate::tenancy). PostgreSQL and MySQL search the tasks table itself, through the full-text
+// index on task names in their schemas, so there is no separate index to keep up to date.
+// MySQL's index leaves out words under three letters and common English words like "the", so
+// those can't be searched for there.
+
+#[cfg(feature = "sqlite")]
+mod sql {
+    pub const COUNT: &str = "SELECT count(*) AS total FROM task_search WHERE task_search MATCH ? \
+        AND rowid IN (SELECT task_id FROM tasks WHERE tenant_id = current_tenant())";
+    pub const SEARCH: &str = "SELECT rowid AS task_id, task_name, -bm25(task_search) AS score FROM task_search \
+        WHERE task_search MATCH ? AND rowid IN (SELECT task_id FROM tasks WHERE tenant_id = current_tenant()) ORDER BY bm25(task_search), rowid LIMIT ? OFFSET ?";
+    pub const SUGGEST: &str = "SELECT rowid AS task_id, task_name FROM task_search \
+        WHERE task_search MATCH ? AND rowid IN (SELECT task_id FROM tasks WHERE tenant_id = current_tenant()) ORDER BY bm25(task_search), length(task_name), rowid LIMIT ?";
+}
+
+#[cfg(feature = "postgres")]
+mod sql {
+    pub const COUNT: &str = "SELECT count(*) AS total FROM tasks \
+        WHERE to_tsvector('simple', task_name) @@ to_tsquery('simple', $1) AND deleted_at IS NULL AND tenant_id = current_tenant()";
+    pub const SEARCH: &str = "SELECT task_id, task_name, ts_rank(to_tsvector('simple', task_name), to_tsquery('simple', $1))::float8 AS score FROM tasks \
+        WHERE to_tsvector('simple', task_name) @@ to_tsquery('simple', $1) AND deleted_at IS NULL AND tenant_id = current_tenant() ORDER BY score DESC, task_id LIMIT $2 OFFSET $3";
+    pub const SUGGEST: &str = "SELECT task_id, task_name FROM tasks \
+        WHERE to_tsvector('simple', task_name) @@ to_tsquery('simple', $1) AND deleted_at IS NULL AND tenant_id = current_tenant() \
+        ORDER BY ts_rank(to_tsvector('simple', task_name), to_tsquery('simple', $1)) DESC, length(task_name), task_id LIMIT $2";
+}
+
+#[cfg(feature = "mysql")]
+mod sql {
+    pub const COUNT: &str = "SELECT count(*) AS total FROM tasks \
+        WHERE MATCH(task_name) AGAINST(? IN BOOLEAN MODE) AND deleted_at IS NULL AND tenant_id = current_tenant()";
+    pub const SEARCH: &str = "SELECT task_id, task_name, CAST(MATCH(task_name) AGAINST(? IN BOOLEAN MODE) AS DOUBLE) AS score FROM tasks \
+        WHERE MATCH(task_name) AGAINST(? IN BOOLEAN MODE) AND deleted_at IS NULL AND tenant_id = current_tenant() ORDER BY score DESC, task_id LIMIT ? OFFSET ?";
+    pub const SUGGEST: &str = "SELECT task_id, task_name FROM tasks \
+        WHERE MATCH(task_name) AGAINST(? IN BOOLEAN MODE) AND deleted_at IS NULL AND tenant_id = current_tenant() \
+        ORDER BY MATCH(task_name) AGAINST(? IN BOOLEAN MODE) DESC, char_length(task_name), task_id LIMIT ?";
+}
+
+#[derive(QueryableByName, Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    #[diesel(sql_type = Integer)]
+    pub task_id: i32,
+    #[diesel(sql_type = Text)]
+    pub task_name: String,
+    // higher is a better match: bm25 (ts_rank on PostgreSQL, MATCH's relevance on MySQL) for
+    // plain search, summed word similarity for fuzzy
+    #[diesel(sql_type = Double)]
+    pub score: f64,
+}
+
+// Just enough to fill a picker.
+#[derive(QueryableByName, Debug, Serialize, Deserialize)]
+pub struct Suggestion {
+    #[diesel(sql_type = Integer)]
+    pub task_id: i32,
+    #[diesel(sql_type = Text)]
+    pub task_name: String,
+}
+
+#[derive(QueryableByName)]
+struct Total {
+    #[diesel(sql_type = BigInt)]
+    total: i64,
+}
+
+// Called by the crud layer whenever a task is created, renamed or restored.
+#[cfg(feature = "sqlite")]
+pub(crate) async fn index_task(conn: &mut DbConnection, task: &Task) -> anyhow::Result<()> {
+    unindex_task(conn, task.task_id).await?;
+    diesel::sql_query("INSERT INTO task_search(rowid, task_name) VALUES (?, ?)")
+        .bind::<Integer, _>(task.task_id)
+        .bind::<Text, _>(&task.task_name)
+        .execute(conn).await?;
+    Ok(())
+}
+
+// Called when a task goes to the trash.
+#[cfg(feature = "sqlite")]
+pub(crate) async fn unindex_task(conn: &mut DbConnection, task_id: i32) -> anyhow::Result<()> {
+    diesel::sql_query("DELETE FROM task_search WHERE rowid = ?")
+        .bind::<Integer, _>(task_id)
+        .execute(conn).await?;
+    Ok(())
+}
+
+// Builds the index again from the live tasks, after their rows were replaced wholesale.
+#[cfg(feature = "sqlite")]
+pub(crate) async fn reindex_all(conn: &mut DbConnection) -> anyhow::Result<()> {
+    diesel::sql_query("DELETE FROM task_search").execute(conn).await?;
+    diesel::sql_query("INSERT INTO task_search(rowid, task_name) SELECT task_id, task_name FROM tasks WHERE deleted_at IS NULL")
+        .execute(conn).await?;
+    Ok(())
+}
+
+// PostgreSQL and MySQL search the tasks table itself (through the tasks_search index), so
+// there is nothing to keep in step.
+#[cfg(not(feature = "sqlite"))]
+pub(crate) async fn index_task(_conn: &mut DbConnection, _task: &Task) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub(crate) async fn unindex_task(_conn: &mut DbConnection, _task_id: i32) -> anyhow::Result<()> {
+    Ok(())
+}
+
+// Turns free text into an FTS5 query that matches rows containing every word (or, with
+// `prefix`, a word starting with each). Each word is quoted, so characters like - or "
+// from the client can't be read as FTS5 operators. Returns None when there is nothing to
+// search for.
+#[cfg(feature = "sqlite")]
+fn match_expression(q: &str, prefix: bool) -> Option<String> {
+    let terms: Vec<String> = q.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| if prefix { format!("\"{}\"*", word) } else { format!("\"{}\"", word) })
+        .collect();
+    if terms.is_empty() { None } else { Some(terms.join(" ")) }
+}
+
+// The same for PostgreSQL's to_tsquery: words joined with &, each ending in :* for `prefix`.
+// Only letters and digits are kept, so nothing from the client is read as an operator.
+#[cfg(feature = "postgres")]
+fn match_expression(q: &str, prefix: bool) -> Option<String> {
+    let terms: Vec<String> = q.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| if prefix { format!("{}:*", word) } else { word.to_string() })
+        .collect();
+    if terms.is_empty() { None } else { Some(terms.join(" & ")) }
+}
+
+// And for MySQL's boolean mode: each word required with +, and ending in * for `prefix`.
+#[cfg(feature = "mysql")]
+fn match_expression(q: &str, prefix: bool) -> Option<String> {
+    let terms: Vec<String> = q.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| if prefix { format!("+{}*", word) } else { format!("+{}", word) })
+        .collect();
+    if terms.is_empty() { None } else { Some(terms.join(" ")) }
+}
+
+// Best matches first.
+pub async fn search_tasks(conn: &mut DbConnection, q: &str, page: i64, per_page: i64) -> anyhow::Result<Page<SearchHit>> {
+    let Some(expression) = match_expression(q, false) else {
+        return Ok(Page::new(Vec::new(), page, per_page, 0));
+    };
+    let total = diesel::sql_query(sql::COUNT)
+        .bind::<Text, _>(&expression)
+        .get_result::<Total>(conn).await?
+        .total;
+    let query = diesel::sql_query(sql::SEARCH).bind::<Text, _>(&expression);
+    // MySQL's placeholders are positional, so the expression is bound once for each use
+    #[cfg(feature = "mysql")]
+    let query = query.bind::<Text, _>(&expression);
+    let items = query
+        .bind::<BigInt, _>(per_page)
+        .bind::<BigInt, _>(pagination::offset(page, per_page))
+        .load::<SearchHit>(conn).await?;
+    Ok(Page::new(items, page, per_page, total))
+}
+
+// Typeahead: "rep" matches "Fix reported bugs", "db sch" matches "db schema". Shorter names win ties
+// so the closest completions come first.
+pub async fn suggest_tasks(conn: &mut DbConnection, q: &str, limit: i64) -> anyhow::Result<Vec<Suggestion>> {
+    let Some(expression) = match_expression(q, true) else {
+        return Ok(Vec::new());
+    };
+    let query = diesel::sql_query(sql::SUGGEST).bind::<Text, _>(&expression);
+    #[cfg(feature = "mysql")]
+    let query = query.bind::<Text, _>(&expression);
+    let suggestions = query
+        .bind::<BigInt, _>(limit)
+        .load::<Suggestion>(conn).await?;
+    Ok(suggestions)
+}
+
+// Typo-tolerant search: every word of q must be within a few edits of some word in the name
+// ("databse migration" finds "Database migration"). Full-text search can't do edit distance, so this scores
+// live task names in Rust; fine for thousands of tasks, not millions.
+pub async fn fuzzy_search_tasks(conn: &mut DbConnection, q: &str, page: i64, per_page: i64) -> anyhow::Result<Page<SearchHit>> {
+    let query_words = words(q);
+    if query_words.is_empty() {
+        return Ok(Page::new(Vec::new(), page, per_page, 0));
+    }
+    let names: Vec<(i32, String)> = tasks::table
+        .filter(tasks::tenant_id.eq(tenancy::current()))
+        .filter(tasks::deleted_at.is_null())
+        .select((tasks::task_id, tasks::task_name))
+        .load(conn).await?;
+    let mut hits: Vec<SearchHit> = names.into_iter()
+        .filter_map(|(task_id, task_name)| {
+            let name_words = words(&task_name);
+            let score = query_words.iter()
+                .map(|word| name_words.iter().filter_map(|candidate| similarity(word, candidate)).reduce(f64::max))
+                .sum::<Option<f64>>()?;
+            Some(SearchHit { task_id, task_name, score })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.task_id.cmp(&b.task_id)));
+    let total = hits.len() as i64;
+    let items = hits.into_iter()
+        .skip(pagination::offset(page, per_page) as usize)
+        .take(per_page as usize)
+        .collect();
+    Ok(Page::new(items, page, per_page, total))
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// 1.0 for an exact match, less for each edit, None past the typo budget: no typos for
+// words of up to 3 letters, one up to 7, two beyond that.
+fn similarity(word: &str, candidate: &str) -> Option<f64> {
+    let len = word.chars().count();
+    let allowed = match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let distance = levenshtein(word, candidate);
+    (distance <= allowed).then(|| 1.0 - distance as f64 / len.max(candidate.chars().count()) as f64)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::CrudOperations;
+    use crate::models::Tenant;
+    use crate::test_support::{self, create_task, new_task};
+    use crate::tenancy;
+
+    async fn hits(conn: &mut DbConnection, q: &str) -> Vec<i32> {
+        search_tasks(conn, q, 1, 10).await.unwrap().items.iter().map(|hit| hit.task_id).collect()
+    }
+
+    async fn fuzzy_hits(conn: &mut DbConnection, q: &str) -> Vec<i32> {
+        fuzzy_search_tasks(conn, q, 1, 10).await.unwrap().items.iter().map(|hit| hit.task_id).collect()
+    }
+
+    #[tokio::test]
+    async fn the_index_follows_renames_and_the_trash() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        assert_eq!(hits(&mut conn, "fence paint").await, [task.task_id]);
+
+        Task::update(&mut conn, task.task_id, new_task("Mend the gate")).await.unwrap();
+        assert!(hits(&mut conn, "fence").await.is_empty());
+        assert_eq!(hits(&mut conn, "gate").await, [task.task_id]);
+
+        Task::delete(&mut conn, task.task_id).await.unwrap();
+        assert!(hits(&mut conn, "gate").await.is_empty());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn operators_in_the_query_are_taken_as_words() {
+        assert_eq!(match_expression("fence -gate \"paint", false), Some("\"fence\" \"gate\" \"paint\"".to_string()));
+        assert_eq!(match_expression(" - \" ", false), None);
+        assert_eq!(match_expression("db sch", true), Some("\"db\"* \"sch\"*".to_string()));
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn postgres_joins_the_words_with_and() {
+        assert_eq!(match_expression("fence -gate \"paint", false), Some("fence & gate & paint".to_string()));
+        assert_eq!(match_expression("db sch", true), Some("db:* & sch:*".to_string()));
+        assert_eq!(match_expression(" - \" ", false), None);
+    }
+
+    #[cfg(feature = "mysql")]
+    #[test]
+    fn mysql_requires_every_word() {
+        assert_eq!(match_expression("fence -gate", false), Some("+fence +gate".to_string()));
+        assert_eq!(match_expression("db sch", true), Some("+db* +sch*".to_string()));
+        assert_eq!(match_expression(" - \" ", true), None);
+    }
+
+    #[tokio::test]
+    async fn suggestions_complete_each_word_shortest_name_first() {
+        let mut conn = test_support::conn().await;
+        let long = create_task(&mut conn, "Varnish the wainscot in the hallway").await;
+        let short = create_task(&mut conn, "Varnish the wainscot").await;
+        let names: Vec<i32> = suggest_tasks(&mut conn, "varn wain", 10).await.unwrap().iter().map(|suggestion| suggestion.task_id).collect();
+        assert_eq!(names, [short.task_id, long.task_id]);
+        assert_eq!(suggest_tasks(&mut conn, "varn wain", 1).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn fuzzy_search_forgives_a_typo_per_word() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Varnish the wainscot").await;
+        assert_eq!(fuzzy_hits(&mut conn, "varnsh wainscott").await, [task.task_id]);
+        assert!(fuzzy_hits(&mut conn, "vrnsh wainscot").await.is_empty());
+        assert_eq!(levenshtein("wainscot", "wainscott"), 1);
+        assert_eq!(similarity("the", "tho"), None);
+    }
+
+    #[tokio::test]
+    async fn other_tenants_tasks_never_match() {
+        let mut conn = test_support::conn().await;
+        create_task(&mut conn, "Varnish the wainscot").await;
+        let other = Tenant::create(&mut conn, "Other").await.unwrap();
+        tenancy::enter(&mut conn, other.tenant_id).await.unwrap();
+        let theirs = create_task(&mut conn, "Varnish the wainscot too").await;
+
+        assert_eq!(search_tasks(&mut conn, "wainscot", 1, 10).await.unwrap().items.iter().map(|hit| hit.task_id).collect::<Vec<_>>(), [theirs.task_id]);
+        assert_eq!(suggest_tasks(&mut conn, "wains", 10).await.unwrap().iter().map(|suggestion| suggestion.task_id).collect::<Vec<_>>(), [theirs.task_id]);
+        assert_eq!(fuzzy_search_tasks(&mut conn, "wainscott", 1, 10).await.unwrap().items.iter().map(|hit| hit.task_id).collect::<Vec<_>>(), [theirs.task_id]);
+    }
+}
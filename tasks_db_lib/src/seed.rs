@@ -0,0 +1,126 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::DbConnection;
+use crate::crud::CrudOperations;
+use crate::enums::{TaskPriority, UserRole};
+use crate::models::{Credential, NewTask, NewTaskStatus, NewUser, Task, TaskStatus, User};
+use crate::schema::{task_statuses, tasks};
+use crate::tenancy;
+
+// The standard set a fresh environment needs to be usable: the same statuses, users and tasks
+// the first migrations insert, plus the passwords and roles they leave out. Anything already
+// there, by status name, email or task name, is left as it is.
+pub const STATUSES: [&str; 3] = ["Not Started", "In Progress", "Completed"];
+
+// name, email, active, and the role a user gets when seeding first gives them a password
+pub const USERS: [(&str, &str, bool, UserRole); 10] = [
+    ("Alice", "alice@example.com", true, UserRole::Admin),
+    ("Bob", "bob@example.com", true, UserRole::Manager),
+    ("Charlie", "charlie@example.com", true, UserRole::Member),
+    ("Diana", "diana@example.com", false, UserRole::Member),
+    ("Eve", "eve@example.com", true, UserRole::Member),
+    ("Frank", "frank@example.com", false, UserRole::Member),
+    ("Grace", "grace@example.com", true, UserRole::Member),
+    ("Heidi", "heidi@example.com", true, UserRole::Member),
+    ("Ivan", "ivan@example.com", false, UserRole::Member),
+    ("Judy", "judy@example.com", true, UserRole::Member),
+];
+
+pub const TASKS: [&str; 10] = [
+    "Write project proposal",
+    "Design database schema",
+    "Implement authentication",
+    "Set up CI/CD pipeline",
+    "Write unit tests",
+    "Deploy to staging",
+    "Review pull requests",
+    "Update documentation",
+    "Fix reported bugs",
+    "Prepare release notes",
+];
+
+// What every seeded user signs in with, unless they already had a password. Development only.
+pub const PASSWORD: &str = "password123";
+// PASSWORD hashed as the API hashes passwords, so the library doesn't need argon2; the same
+// hash dev_seed.sql uses.
+const PASSWORD_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$Ojet9BRO7av8DuJfAg6YWw$GvWzWvQ8D5a2hA10ArUJMDYzln0wFntmwkjkWYRYb3Q";
+
+// How many rows a run added.
+#[derive(Debug, Default, PartialEq)]
+pub struct Seeded {
+    pub statuses: usize,
+    pub users: usize,
+    pub passwords: usize,
+    pub tasks: usize,
+}
+
+// Seeds the connection's tenant, all or nothing.
+pub async fn run(conn: &mut DbConnection) -> anyhow::Result<Seeded> {
+    conn.transaction(|conn| async move {
+        let mut seeded = Seeded::default();
+        for status_name in STATUSES {
+            let exists = task_statuses::table
+                .filter(task_statuses::tenant_id.eq(tenancy::current()))
+                .filter(task_statuses::deleted_at.is_null())
+                .filter(task_statuses::status_name.eq(status_name))
+                .count()
+                .get_result::<i64>(conn).await? > 0;
+            if !exists {
+                TaskStatus::create(conn, NewTaskStatus { status_name }).await?;
+                seeded.statuses += 1;
+            }
+        }
+        for (name, email, active, role) in USERS {
+            let user = match User::read_by_email(conn, email).await? {
+                Some(user) => user,
+                None => {
+                    seeded.users += 1;
+                    User::create(conn, NewUser { name, email, active }).await?
+                }
+            };
+            if Credential::read(conn, user.user_id).await?.is_none() {
+                Credential::set_password(conn, user.user_id, PASSWORD_HASH).await?;
+                if user.role_id != role as i32 {
+                    User::set_role(conn, None, user.user_id, None, role).await?;
+                }
+                seeded.passwords += 1;
+            }
+        }
+        for task_name in TASKS {
+            let exists = tasks::table
+                .filter(tasks::tenant_id.eq(tenancy::current()))
+                .filter(tasks::deleted_at.is_null())
+                .filter(tasks::task_name.eq(task_name))
+                .count()
+                .get_result::<i64>(conn).await? > 0;
+            if !exists {
+                let new_task = NewTask { task_name, due_date: None, priority: TaskPriority::Medium, parent_task_id: None, recurrence: None, project_id: None };
+                Task::create(conn, new_task).await?;
+                seeded.tasks += 1;
+            }
+        }
+        Ok(seeded)
+    }.scope_boxed()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn fills_in_what_the_migrations_leave_out_then_does_nothing() {
+        let mut conn = test_support::conn().await;
+        // "Write unit tests", into the trash
+        Task::delete(&mut conn, 5).await.unwrap();
+
+        let seeded = run(&mut conn).await.unwrap();
+        assert_eq!(seeded, Seeded { statuses: 0, users: 0, passwords: USERS.len(), tasks: 1 });
+        let bob = User::read_by_email(&mut conn, "bob@example.com").await.unwrap().unwrap();
+        assert_eq!(bob.role_id, UserRole::Manager as i32);
+        assert!(Credential::read(&mut conn, bob.user_id).await.unwrap().is_some());
+
+        assert_eq!(run(&mut conn).await.unwrap(), Seeded::default());
+    }
+}
@@ -0,0 +1,70 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use crate::models::{NewSlackIntegration, SlackIntegration};
+use crate::schema::slack_integrations;
+use crate::tenancy;
+
+// Each project has at most one Slack integration, reached through the project.
+impl SlackIntegration {
+    pub async fn read(conn: &mut DbConnection, project_id: i32) -> anyhow::Result<Option<SlackIntegration>> {
+        let integration = slack_integrations::table
+            .find(project_id)
+            .filter(slack_integrations::project_id.eq_any(tenancy::project_ids()))
+            .first(conn).await
+            .optional()?;
+        Ok(integration)
+    }
+
+    // Sets the project's URL, replacing the one it had. The caller checks the project exists.
+    pub async fn save(conn: &mut DbConnection, new_integration: NewSlackIntegration<'_>) -> anyhow::Result<SlackIntegration> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(slack_integrations::table)
+            .values(&new_integration)
+            .on_conflict(conflict_target!(slack_integrations::project_id))
+            .do_update()
+            .set((
+                slack_integrations::webhook_url.eq(new_integration.webhook_url),
+                slack_integrations::updated_at.eq(now),
+            ))
+            .execute(conn).await?;
+        let integration = slack_integrations::table.filter(slack_integrations::project_id.eq(new_integration.project_id)).first(conn).await?;
+        Ok(integration)
+    }
+
+    pub async fn delete(conn: &mut DbConnection, project_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::delete(slack_integrations::table
+            .find(project_id)
+            .filter(slack_integrations::project_id.eq_any(tenancy::project_ids())))
+            .execute(conn).await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::CrudOperations;
+    use crate::models::{NewProject, Project, Tenant};
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn a_project_keeps_one_integration_until_it_goes() {
+        let mut conn = test_support::conn().await;
+        let project = Project::create(&mut conn, NewProject { project_name: "Kitchen", description: None, team_id: None }).await.unwrap();
+        let project_id = project.project_id;
+        SlackIntegration::save(&mut conn, NewSlackIntegration { project_id, webhook_url: "https://hooks.slack.com/services/old" }).await.unwrap();
+        SlackIntegration::save(&mut conn, NewSlackIntegration { project_id, webhook_url: "https://hooks.slack.com/services/new" }).await.unwrap();
+        assert_eq!(SlackIntegration::read(&mut conn, project_id).await.unwrap().unwrap().webhook_url, "https://hooks.slack.com/services/new");
+        assert_eq!(slack_integrations::table.count().get_result::<i64>(&mut conn).await.unwrap(), 1);
+
+        let other = Tenant::create(&mut conn, "Other").await.unwrap();
+        tenancy::enter(&mut conn, other.tenant_id).await.unwrap();
+        assert!(SlackIntegration::read(&mut conn, project_id).await.unwrap().is_none());
+        assert_eq!(SlackIntegration::delete(&mut conn, project_id).await.unwrap(), 0);
+
+        tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await.unwrap();
+        assert_eq!(Project::delete(&mut conn, project_id).await.unwrap(), 1);
+        assert_eq!(slack_integrations::table.count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,81 @@
+use diesel::dsl::{Asc, Desc};
+use diesel::query_dsl::methods::OrderDsl;
+use diesel::ExpressionMethods;
+
+// Columns each list endpoint may be sorted by. The first entry is the default sort; tasks
+// default to priority, which sorts ascending as most urgent first.
+pub const USER_SORT_COLUMNS: &[&str] = &["user_id", "name", "email", "active", "role_id", "created_at", "updated_at"];
+pub const TASK_SORT_COLUMNS: &[&str] = &["priority", "task_id", "task_name", "created_at", "updated_at", "due_date"];
+pub const TASK_STATUS_SORT_COLUMNS: &[&str] = &["task_status_id", "status_name", "created_at", "updated_at"];
+pub const PROJECT_SORT_COLUMNS: &[&str] = &["project_name", "project_id", "created_at", "updated_at"];
+pub const TEAM_SORT_COLUMNS: &[&str] = &["team_name", "team_id", "created_at", "updated_at"];
+pub const TAG_SORT_COLUMNS: &[&str] = &["tag_name", "tag_id", "created_at", "updated_at"];
+pub const TASK_TEMPLATE_SORT_COLUMNS: &[&str] = &["template_name", "template_id", "created_at", "updated_at"];
+pub const COMMENT_SORT_COLUMNS: &[&str] = &["created_at", "comment_id", "updated_at"];
+pub const USER_TASK_SORT_COLUMNS: &[&str] = &["user_id", "task_id", "task_status_id", "created_at", "updated_at"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sort {
+    pub column: String,
+    pub order: SortOrder,
+}
+
+impl Sort {
+    // Validates ?sort=&order= against the whitelist for one entity.
+    // The Err message is meant to be shown to the API client.
+    pub fn parse(column: Option<&str>, order: Option<&str>, allowed: &[&str]) -> Result<Sort, String> {
+        let column = match column {
+            Some(c) if allowed.contains(&c) => c.to_string(),
+            Some(c) => return Err(format!("Cannot sort by '{}'; expected one of: {}", c, allowed.join(", "))),
+            None => allowed[0].to_string(),
+        };
+        let order = match order.map(|o| o.to_ascii_lowercase()) {
+            None => SortOrder::Asc,
+            Some(o) if o == "asc" => SortOrder::Asc,
+            Some(o) if o == "desc" => SortOrder::Desc,
+            Some(o) => return Err(format!("Unknown sort order '{}'; expected asc or desc", o)),
+        };
+        Ok(Sort { column, order })
+    }
+}
+
+// Applies ORDER BY <column> ASC|DESC to a boxed query.
+pub fn order_by<Q, C>(query: Q, column: C, order: SortOrder) -> Q
+where
+    C: ExpressionMethods,
+    Q: OrderDsl<Asc<C>, Output = Q> + OrderDsl<Desc<C>, Output = Q>,
+{
+    match order {
+        SortOrder::Asc => OrderDsl::order(query, column.asc()),
+        SortOrder::Desc => OrderDsl::order(query, column.desc()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_the_first_column_ascending() {
+        assert_eq!(Sort::parse(None, None, TASK_SORT_COLUMNS).unwrap(), Sort { column: "priority".to_string(), order: SortOrder::Asc });
+    }
+
+    #[test]
+    fn takes_either_case_of_order() {
+        assert_eq!(Sort::parse(Some("task_name"), Some("DESC"), TASK_SORT_COLUMNS).unwrap().order, SortOrder::Desc);
+        assert_eq!(Sort::parse(Some("task_name"), Some("asc"), TASK_SORT_COLUMNS).unwrap().order, SortOrder::Asc);
+    }
+
+    #[test]
+    fn rejects_columns_and_orders_off_the_list() {
+        assert!(Sort::parse(Some("password"), None, USER_SORT_COLUMNS).is_err());
+        assert!(Sort::parse(Some("name"), Some("sideways"), USER_SORT_COLUMNS).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use serde::{Deserialize, Serialize};
+use crate::crud::CrudOperations;
+use crate::models::{TaskStatus, User};
+use crate::schema::user_tasks;
+use crate::tenancy;
+
+// How many live assignments sit in one status.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusCount {
+    pub task_status_id: i32,
+    pub status_name: String,
+    pub count: i64,
+}
+
+// One row per live status, including those nobody is in (count 0), ordered by status id.
+// With `user_id`, only that user's assignments are counted.
+pub async fn assignments_by_status(conn: &mut DbConnection, user_id: Option<i32>) -> anyhow::Result<Vec<StatusCount>> {
+    let mut query = user_tasks::table
+        .filter(user_tasks::tenant_id.eq(tenancy::current()))
+        .filter(user_tasks::deleted_at.is_null())
+        .group_by(user_tasks::task_status_id)
+        .select((user_tasks::task_status_id, diesel::dsl::count_star()))
+        .into_boxed();
+    if let Some(user_id) = user_id {
+        query = query.filter(user_tasks::user_id.eq(user_id));
+    }
+    let counts: Vec<(i32, i64)> = query.load(conn).await?;
+    let mut statuses = TaskStatus::read_all(conn).await?;
+    statuses.sort_by_key(|status| status.task_status_id);
+    Ok(statuses.into_iter()
+        .map(|status| StatusCount {
+            count: counts.iter().find(|(id, _)| *id == status.task_status_id).map_or(0, |(_, count)| *count),
+            task_status_id: status.task_status_id,
+            status_name: status.status_name,
+        })
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserWorkload {
+    pub user_id: i32,
+    pub name: String,
+    pub total: i64,
+    pub by_status: Vec<StatusCount>,
+}
+
+// Every user with their open assignments (live ones whose status isn't one of
+// `terminal_statuses`, matched by name) counted per open status. Zeros are included, so each
+// by_status list has the same columns, and finished work counts towards nobody's total.
+// Ordered by user id.
+pub async fn workload(conn: &mut DbConnection, terminal_statuses: &[String]) -> anyhow::Result<Vec<UserWorkload>> {
+    let counts: Vec<(i32, i32, i64)> = user_tasks::table
+        .filter(user_tasks::tenant_id.eq(tenancy::current()))
+        .filter(user_tasks::deleted_at.is_null())
+        .group_by((user_tasks::user_id, user_tasks::task_status_id))
+        .select((user_tasks::user_id, user_tasks::task_status_id, diesel::dsl::count_star()))
+        .load(conn).await?;
+    let mut statuses = TaskStatus::read_all(conn).await?;
+    statuses.retain(|status| !terminal_statuses.contains(&status.status_name));
+    statuses.sort_by_key(|status| status.task_status_id);
+    let mut users = User::read_all(conn).await?;
+    users.sort_by_key(|user| user.user_id);
+    Ok(users.into_iter()
+        .map(|user| {
+            let by_status: Vec<StatusCount> = statuses.iter()
+                .map(|status| StatusCount {
+                    task_status_id: status.task_status_id,
+                    status_name: status.status_name.clone(),
+                    count: counts.iter()
+                        .find(|(user_id, task_status_id, _)| *user_id == user.user_id && *task_status_id == status.task_status_id)
+                        .map_or(0, |(_, _, count)| *count),
+                })
+                .collect();
+            UserWorkload { user_id: user.user_id, name: user.name, total: by_status.iter().map(|status| status.count).sum(), by_status }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewTaskStatus, NewUserTask, UserTask};
+    use crate::test_support::{self, create_task};
+
+    #[tokio::test]
+    async fn every_status_is_counted_even_when_empty() {
+        let mut conn = test_support::conn().await;
+        let status = TaskStatus::create(&mut conn, NewTaskStatus { status_name: "Parked" }).await.unwrap();
+        let counts = assignments_by_status(&mut conn, Some(2)).await.unwrap();
+        assert!(counts.windows(2).all(|pair| pair[0].task_status_id < pair[1].task_status_id));
+        let parked = |counts: &[StatusCount]| counts.iter().find(|row| row.task_status_id == status.task_status_id).map(|row| row.count).unwrap();
+        assert_eq!(parked(&counts), 0);
+
+        let task = create_task(&mut conn, "Paint the fence").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: status.task_status_id }).await.unwrap();
+        assert_eq!(parked(&assignments_by_status(&mut conn, Some(2)).await.unwrap()), 1);
+        assert_eq!(parked(&assignments_by_status(&mut conn, Some(3)).await.unwrap()), 0);
+    }
+
+    #[tokio::test]
+    async fn workload_totals_each_users_assignments() {
+        let mut conn = test_support::conn().await;
+        let before = workload(&mut conn, &["Completed".to_string()]).await.unwrap();
+        let task = create_task(&mut conn, "Paint the fence").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+
+        let after = workload(&mut conn, &["Completed".to_string()]).await.unwrap();
+        assert!(after.windows(2).all(|pair| pair[0].user_id < pair[1].user_id));
+        assert!(after.iter().all(|row| row.total == row.by_status.iter().map(|status| status.count).sum::<i64>()));
+        let bob = |rows: &[UserWorkload]| rows.iter().find(|row| row.user_id == 2).map(|row| row.total).unwrap();
+        assert_eq!(bob(&after), bob(&before) + 1);
+    }
+
+    #[tokio::test]
+    async fn workload_leaves_out_finished_assignments() {
+        let mut conn = test_support::conn().await;
+        let before = workload(&mut conn, &["Completed".to_string()]).await.unwrap();
+        let open = create_task(&mut conn, "Still going").await;
+        let done = create_task(&mut conn, "All done").await;
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: open.task_id, task_status_id: 2 }).await.unwrap();
+        UserTask::create(&mut conn, NewUserTask { user_id: 2, task_id: done.task_id, task_status_id: 3 }).await.unwrap();
+
+        let after = workload(&mut conn, &["Completed".to_string()]).await.unwrap();
+        let bob = |rows: &[UserWorkload]| rows.iter().find(|row| row.user_id == 2).map(|row| row.total).unwrap();
+        assert_eq!(bob(&after), bob(&before) + 1);
+        assert!(after.iter().all(|row| row.by_status.iter().all(|status| status.status_name != "Completed")));
+    }
+}
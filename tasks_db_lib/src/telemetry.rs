@@ -0,0 +1,83 @@
+use diesel::connection::{Instrumentation, InstrumentationEvent};
+use tracing::Span;
+
+#[cfg(feature = "sqlite")]
+const DB_SYSTEM: &str = "sqlite";
+#[cfg(feature = "postgres")]
+const DB_SYSTEM: &str = "postgresql";
+#[cfg(feature = "mysql")]
+const DB_SYSTEM: &str = "mysql";
+
+// A tracing span around every query and transaction on the connection, as a child of the span
+// it was made `within` (the request's handler, in the web app), else of whatever span is
+// current. Field names follow OpenTelemetry's database conventions, so a tracing backend shows
+// the statement and how long it took. Bind values are left out: they can hold anything,
+// password hashes included.
+#[derive(Default)]
+pub struct QuerySpans {
+    query: Option<Span>,
+    // open transactions and savepoints, outermost first; queries inside one are its children
+    transactions: Vec<Span>,
+    // queries run on a blocking thread, where the caller's span isn't current
+    within: Option<Span>,
+}
+
+impl QuerySpans {
+    pub fn within(span: Span) -> QuerySpans {
+        QuerySpans { within: Some(span), ..QuerySpans::default() }
+    }
+
+    fn parent(&self) -> Span {
+        self.transactions.last().or(self.within.as_ref()).cloned().unwrap_or_else(Span::current)
+    }
+}
+
+// Diesel shows a query as its SQL followed by ` -- binds: [...]`.
+fn statement(query: &dyn std::fmt::Display) -> String {
+    let text = query.to_string();
+    match text.rsplit_once(" -- binds: ") {
+        Some((sql, _)) => sql.to_string(),
+        None => text,
+    }
+}
+
+impl Instrumentation for QuerySpans {
+    fn on_connection_event(&mut self, event: InstrumentationEvent<'_>) {
+        match event {
+            InstrumentationEvent::StartQuery { query, .. } => {
+                let span = tracing::info_span!(target: "db", parent: &self.parent(), "query",
+                    otel.kind = "client", db.system.name = DB_SYSTEM, db.query.text = tracing::field::Empty,
+                    otel.status_code = tracing::field::Empty, error.message = tracing::field::Empty);
+                if !span.is_disabled() {
+                    span.record("db.query.text", statement(&query));
+                }
+                self.query = Some(span);
+            }
+            InstrumentationEvent::FinishQuery { error, .. } => {
+                if let (Some(span), Some(error)) = (self.query.take(), error) {
+                    span.record("otel.status_code", "ERROR");
+                    span.record("error.message", error.to_string());
+                }
+            }
+            InstrumentationEvent::BeginTransaction { depth, .. } => {
+                let span = tracing::info_span!(target: "db", parent: &self.parent(), "transaction", otel.kind = "client", db.system.name = DB_SYSTEM, depth = depth.get());
+                self.transactions.push(span);
+            }
+            InstrumentationEvent::CommitTransaction { .. } | InstrumentationEvent::RollbackTransaction { .. } => {
+                self.transactions.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements_leave_out_the_bind_values() {
+        assert_eq!(statement(&"SELECT * FROM `users` WHERE `email` = ? -- binds: [\"alice@example.com\"]"), "SELECT * FROM `users` WHERE `email` = ?");
+        assert_eq!(statement(&"PRAGMA foreign_keys = ON"), "PRAGMA foreign_keys = ON");
+    }
+}
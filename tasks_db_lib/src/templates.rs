@@ -0,0 +1,99 @@
+use chrono::{Days, NaiveDate};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::DbConnection;
+use crate::audit::AuditedCrud;
+use crate::crud::CrudOperations;
+use crate::models::{NewTask, NewUserTask, Task, TaskStatus, TaskTemplate, UserTask};
+use crate::schema::users;
+use crate::tenancy;
+
+impl TaskTemplate {
+    pub fn assignee_ids(&self) -> anyhow::Result<Vec<i32>> {
+        Ok(serde_json::from_str(&self.assignee_ids_json)?)
+    }
+
+    pub fn subtask_names(&self) -> anyhow::Result<Vec<String>> {
+        Ok(serde_json::from_str(&self.subtask_names_json)?)
+    }
+
+    // Makes a task from the template, due `due_in_days` after `today` if the template sets
+    // that, plus one subtask per stored name with the same priority and due date. The
+    // template's assignees who still exist are put on the top task in the first status by id.
+    // None if there is no such template.
+    pub async fn instantiate(conn: &mut DbConnection, actor: Option<i32>, template_id: i32, today: NaiveDate) -> anyhow::Result<Option<Task>> {
+        conn.transaction(|conn| async move {
+            let Some(template) = TaskTemplate::read(conn, template_id).await? else {
+                return Ok(None);
+            };
+            let due_date = template.due_in_days
+                .and_then(|days| today.checked_add_days(Days::new(days.try_into().ok()?)));
+            let task = Task::create_audited(conn, actor, NewTask {
+                task_name: &template.task_name,
+                due_date,
+                priority: template.priority,
+                parent_task_id: None,
+                recurrence: template.recurrence.as_deref(),
+                project_id: None,
+            }).await?;
+            for subtask_name in template.subtask_names()? {
+                Task::create_audited(conn, actor, NewTask {
+                    task_name: &subtask_name,
+                    due_date,
+                    priority: template.priority,
+                    parent_task_id: Some(task.task_id),
+                    recurrence: None,
+                    project_id: None,
+                }).await?;
+            }
+            if let Some(task_status_id) = TaskStatus::initial_id(conn).await? {
+                let assignees: Vec<i32> = users::table
+                    .filter(users::tenant_id.eq(tenancy::current()))
+                    .filter(users::user_id.eq_any(template.assignee_ids()?))
+                    .order(users::user_id)
+                    .select(users::user_id)
+                    .load(conn).await?;
+                for user_id in assignees {
+                    UserTask::create_audited(conn, actor, NewUserTask { user_id, task_id: task.task_id, task_status_id }).await?;
+                }
+            }
+            Ok(Some(task))
+        }.scope_boxed()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::TaskPriority;
+    use crate::models::NewTaskTemplate;
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn a_template_makes_the_task_its_subtasks_and_assignments() {
+        let mut conn = test_support::conn().await;
+        let template = TaskTemplate::create(&mut conn, NewTaskTemplate {
+            template_name: "Onboarding",
+            task_name: "Onboard the new starter",
+            priority: TaskPriority::High,
+            due_in_days: Some(3),
+            recurrence: None,
+            assignee_ids_json: "[2, 999, 1]".to_string(),
+            subtask_names_json: r#"["Order a laptop", "Book a desk"]"#.to_string(),
+        }).await.unwrap();
+        let today = NaiveDate::from_ymd_opt(2031, 3, 5).unwrap();
+        let task = TaskTemplate::instantiate(&mut conn, Some(1), template.template_id, today).await.unwrap().unwrap();
+        assert_eq!((task.priority, task.due_date), (TaskPriority::High, NaiveDate::from_ymd_opt(2031, 3, 8)));
+
+        let subtasks = Task::read_subtasks(&mut conn, task.task_id).await.unwrap();
+        assert_eq!(subtasks.iter().map(|subtask| subtask.task_name.as_str()).collect::<Vec<_>>(), ["Order a laptop", "Book a desk"]);
+        assert!(UserTask::read(&mut conn, (1, task.task_id)).await.unwrap().is_some());
+        assert!(UserTask::read(&mut conn, (2, task.task_id)).await.unwrap().is_some());
+
+        assert_eq!(TaskTemplate::delete(&mut conn, template.template_id).await.unwrap(), 1);
+        assert!(Task::read(&mut conn, task.task_id).await.unwrap().is_some());
+        assert!(TaskTemplate::instantiate(&mut conn, Some(1), template.template_id, today).await.unwrap().is_none());
+    }
+}
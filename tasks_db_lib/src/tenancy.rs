@@ -0,0 +1,102 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use crate::DbConnection;
+use crate::models::{NewTenant, Tenant};
+use crate::schema::{projects, tasks, tenants};
+
+// Tenancy is a property of the connection: `enter` says which tenant it works for, every
+// query on a tenant-scoped table (users, tasks, task_statuses, user_tasks, tags, projects,
+// teams, task_templates and audit_log) filters on `current()`, and inserts into them are
+// stamped with it. The other tables hang off one of those and are only reached through it.
+//
+// On SQLite, current_tenant() is registered on the connection by `enter`. A connection that
+// never entered a tenant fails any scoped query, because the function doesn't exist on it yet,
+// and one that has left sees no rows and can't insert any. On PostgreSQL the function is part
+// of the schema and reads a session setting that `enter` sets and `leave` clears, so both of
+// those connections see nothing; MySQL does the same with a user variable. Either way, a
+// pooled connection can't carry one tenant's data into another's request.
+
+diesel::define_sql_function! {
+    fn current_tenant() -> Nullable<Integer>;
+}
+
+#[cfg(feature = "postgres")]
+diesel::define_sql_function! {
+    fn set_config(setting_name: diesel::sql_types::Text, new_value: diesel::sql_types::Text, is_local: diesel::sql_types::Bool) -> diesel::sql_types::Text;
+}
+
+#[cfg(feature = "postgres")]
+const TENANT_SETTING: &str = "tasks.tenant_id";
+
+// Where everything created before multi-tenancy lives, and where self-registered users land.
+pub const DEFAULT_TENANT: i32 = 1;
+
+// The tenant the connection is working for, for use in filters and inserts.
+pub fn current() -> diesel::dsl::AssumeNotNull<current_tenant> {
+    current_tenant().assume_not_null()
+}
+
+// Ids of the tenant's tasks, trashed ones included, for scoping the tables that hang off tasks.
+pub(crate) fn task_ids() -> diesel::dsl::Select<diesel::dsl::Filter<tasks::table, diesel::dsl::Eq<tasks::tenant_id, diesel::dsl::AssumeNotNull<current_tenant>>>, tasks::task_id> {
+    tasks::table.filter(tasks::tenant_id.eq(current())).select(tasks::task_id)
+}
+
+// Ids of the tenant's projects, for the tables that hang off projects.
+pub(crate) fn project_ids() -> diesel::dsl::Select<diesel::dsl::Filter<projects::table, diesel::dsl::Eq<projects::tenant_id, diesel::dsl::AssumeNotNull<current_tenant>>>, projects::project_id> {
+    projects::table.filter(projects::tenant_id.eq(current())).select(projects::project_id)
+}
+
+#[cfg(feature = "sqlite")]
+pub async fn enter(conn: &mut DbConnection, tenant_id: i32) -> anyhow::Result<()> {
+    conn.spawn_blocking(move |conn| current_tenant_utils::register_impl(conn, move || Some(tenant_id))).await?;
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub async fn enter(conn: &mut DbConnection, tenant_id: i32) -> anyhow::Result<()> {
+    diesel::select(set_config(TENANT_SETTING, tenant_id.to_string(), false)).execute(conn).await?;
+    Ok(())
+}
+
+#[cfg(feature = "mysql")]
+pub async fn enter(conn: &mut DbConnection, tenant_id: i32) -> anyhow::Result<()> {
+    diesel::sql_query("SET @tasks_tenant_id = ?").bind::<diesel::sql_types::Integer, _>(tenant_id).execute(conn).await?;
+    Ok(())
+}
+
+// The pool runs this on every connection before handing it out (see connection_manager), so
+// one checked out without entering a tenant sees nothing.
+#[cfg(feature = "sqlite")]
+pub async fn leave(conn: &mut DbConnection) -> QueryResult<()> {
+    conn.spawn_blocking(|conn| current_tenant_utils::register_impl(conn, || None::<i32>)).await
+}
+
+#[cfg(feature = "postgres")]
+pub async fn leave(conn: &mut DbConnection) -> QueryResult<()> {
+    diesel::select(set_config(TENANT_SETTING, "", false)).execute(conn).await?;
+    Ok(())
+}
+
+#[cfg(feature = "mysql")]
+pub async fn leave(conn: &mut DbConnection) -> QueryResult<()> {
+    diesel::sql_query("SET @tasks_tenant_id = NULL").execute(conn).await?;
+    Ok(())
+}
+
+// The tenants table itself isn't scoped.
+impl Tenant {
+    pub async fn create(conn: &mut DbConnection, tenant_name: &str) -> anyhow::Result<Tenant> {
+        diesel::insert_into(tenants::table)
+            .values(NewTenant { tenant_name })
+            .execute(conn)
+            .await?;
+        let tenant = tenants::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        Ok(tenant)
+    }
+
+    // For background jobs, which enter each tenant in turn.
+    pub async fn read_all_ids(conn: &mut DbConnection) -> anyhow::Result<Vec<i32>> {
+        let ids = tenants::table.order(tenants::tenant_id).select(tenants::tenant_id).load(conn).await?;
+        Ok(ids)
+    }
+}
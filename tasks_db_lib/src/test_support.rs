@@ -0,0 +1,82 @@
+use std::path::Path;
+#[cfg(feature = "mysql")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use diesel_async::{AsyncConnection, SimpleAsyncConnection};
+use crate::DbConnection;
+use crate::crud::CrudOperations;
+use crate::enums::TaskPriority;
+use crate::models::{NewTask, Task};
+use crate::tenancy;
+
+// A fresh in-memory database for one test, with every migration applied (the seed data
+// included) and the default tenant entered, just as a pooled connection would be.
+#[cfg(feature = "sqlite")]
+pub async fn conn() -> DbConnection {
+    let mut conn = DbConnection::establish(":memory:").await.expect("Failed to open an in-memory database");
+    // the migrations were written for the diesel CLI, which runs them without foreign keys
+    conn.batch_execute("PRAGMA foreign_keys = OFF").await.expect("Failed to turn off foreign keys");
+    migrate(&mut conn, "migrations").await;
+    crate::configure(&mut conn).await.expect("Failed to configure the connection");
+    tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await.expect("Failed to enter the default tenant");
+    conn
+}
+
+// The same on PostgreSQL, in the empty database at TEST_DATABASE_URL. Each test migrates it
+// inside a transaction that is never committed, so tests leave nothing behind; while one test
+// has the schema, the next waits for it.
+#[cfg(feature = "postgres")]
+pub async fn conn() -> DbConnection {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set to an empty PostgreSQL database");
+    let mut conn = DbConnection::establish(&url).await.expect("Failed to connect to the test database");
+    conn.begin_test_transaction().await.expect("Failed to begin the test transaction");
+    migrate(&mut conn, "migrations_postgres").await;
+    crate::configure(&mut conn).await.expect("Failed to configure the connection");
+    tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await.expect("Failed to enter the default tenant");
+    conn
+}
+
+// MySQL can't roll back a CREATE TABLE, so there each test gets a database of its own, made
+// afresh on the server at TEST_DATABASE_URL. Its user must be allowed to create databases; they
+// are left behind until a later run reuses the name.
+#[cfg(feature = "mysql")]
+pub async fn conn() -> DbConnection {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set to a MySQL server");
+    let mut conn = DbConnection::establish(&url).await.expect("Failed to connect to the test server");
+    let database = format!("tasks_test_{}", NEXT.fetch_add(1, Ordering::Relaxed));
+    conn.batch_execute(&format!("DROP DATABASE IF EXISTS {0}; CREATE DATABASE {0}; USE {0}", database)).await.expect("Failed to create the test database");
+    migrate(&mut conn, "migrations_mysql").await;
+    crate::configure(&mut conn).await.expect("Failed to configure the connection");
+    tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await.expect("Failed to enter the default tenant");
+    conn
+}
+
+// Runs every up.sql under the directory, in order.
+async fn migrate(conn: &mut DbConnection, directory: &str) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(directory);
+    let mut migrations: Vec<_> = std::fs::read_dir(&dir)
+        .expect("Failed to read the migrations directory")
+        .map(|entry| entry.expect("Failed to read a migration").path())
+        .filter(|path| path.is_dir())
+        .collect();
+    migrations.sort();
+    for migration in migrations {
+        let sql = std::fs::read_to_string(migration.join("up.sql")).expect("Failed to read up.sql");
+        conn.batch_execute(&sql).await.unwrap_or_else(|e| panic!("{}: {}", migration.display(), e));
+    }
+}
+
+pub fn new_task(task_name: &str) -> NewTask<'_> {
+    NewTask {
+        task_name,
+        due_date: None,
+        priority: TaskPriority::Medium,
+        parent_task_id: None,
+        recurrence: None,
+        project_id: None,
+    }
+}
+
+pub async fn create_task(conn: &mut DbConnection, task_name: &str) -> Task {
+    Task::create(conn, new_task(task_name)).await.expect("Failed to create a task")
+}
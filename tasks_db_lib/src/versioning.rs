@@ -0,0 +1,103 @@
+use std::fmt;
+use diesel::sql_types::{Integer, Nullable};
+use crate::models::{Comment, Project, Tag, Task, TaskStatus, TaskTemplate, Team, User, UserTask};
+
+// Rows with an optimistic-locking version. The crud layer bumps it on every update, so a
+// client holding an older number knows someone else has written since it last read.
+pub trait Versioned {
+    fn version(&self) -> i32;
+}
+
+impl Versioned for User {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for Task {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for TaskStatus {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for Project {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for Team {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for Tag {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for TaskTemplate {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for Comment {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for UserTask {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+// Returned (inside anyhow) when an update named a version the row is no longer at.
+#[derive(Debug)]
+pub struct StaleVersion {
+    pub expected: i32,
+    pub current: i32,
+}
+
+impl fmt::Display for StaleVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected version {} but the row is at version {}", self.expected, self.current)
+    }
+}
+
+impl std::error::Error for StaleVersion {}
+
+// `expected` is None when the caller doesn't care (bulk and internal writes). A missing row
+// passes so the update itself can report it as not found.
+pub fn check<E: Versioned>(row: Option<&E>, expected: Option<i32>) -> anyhow::Result<()> {
+    match (row, expected) {
+        (Some(row), Some(expected)) if row.version() != expected => {
+            Err(StaleVersion { expected, current: row.version() }.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+diesel::define_sql_function! {
+    #[sql_name = "coalesce"]
+    fn expected_or(expected: Nullable<Integer>, current: Integer) -> Integer;
+}
+
+// What to report when a versioned update matched no row: StaleVersion if the row is there at
+// another version than `expected`, otherwise the NotFound an unversioned update gives.
+pub fn missed<E: Versioned>(row: Option<E>, expected: Option<i32>) -> anyhow::Error {
+    match (row, expected) {
+        (Some(row), Some(expected)) if row.version() != expected => StaleVersion { expected, current: row.version() }.into(),
+        _ => diesel::result::Error::NotFound.into(),
+    }
+}
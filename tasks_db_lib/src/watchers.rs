@@ -0,0 +1,134 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::DbConnection;
+use crate::models::{NewNotification, NewTaskWatcher, NotificationPreference, TaskWatcher, User};
+use crate::preferences::Channel;
+use crate::schema::{notifications, task_watchers, users};
+use crate::tenancy;
+
+pub const WATCH_NOTIFICATION: &str = "watch";
+
+// Called by audit::record when a change to one of the task's assignments is worth hearing
+// about. Every watcher gets the message except whoever made the change, the assignee (who is
+// told separately) and those who turned in-app notifications off for `event_type`.
+pub(crate) async fn notify(conn: &mut DbConnection, actor: Option<i32>, assignee: Option<i32>, task_id: i32, event_type: &str, message: &str) -> anyhow::Result<()> {
+    let watchers: Vec<i32> = task_watchers::table
+        .filter(task_watchers::task_id.eq(task_id))
+        .filter(task_watchers::task_id.eq_any(tenancy::task_ids()))
+        .select(task_watchers::user_id)
+        .load(conn).await?;
+    let watchers = watchers.into_iter().filter(|&user_id| Some(user_id) != actor && Some(user_id) != assignee).collect();
+    let now = chrono::Utc::now().naive_utc();
+    for user_id in NotificationPreference::allowed(conn, watchers, event_type, Channel::InApp).await? {
+        diesel::insert_into(notifications::table)
+            .values(NewNotification {
+                user_id,
+                kind: WATCH_NOTIFICATION,
+                task_id: Some(task_id),
+                comment_id: None,
+                message,
+                created_at: now,
+            })
+            .execute(conn).await?;
+    }
+    Ok(())
+}
+
+impl TaskWatcher {
+    // Watching a task twice is not an error; the original row is kept.
+    pub async fn watch(conn: &mut DbConnection, task_id: i32, user_id: i32) -> anyhow::Result<TaskWatcher> {
+        conn.transaction(|conn| async move {
+            diesel::insert_into(task_watchers::table)
+                .values(NewTaskWatcher { task_id, user_id })
+                .on_conflict_do_nothing()
+                .execute(conn).await?;
+            let watcher = task_watchers::table.find((task_id, user_id)).first(conn).await?;
+            Ok(watcher)
+        }.scope_boxed()).await
+    }
+
+    // The active users watching the task.
+    pub async fn read_watching_users(conn: &mut DbConnection, task_id: i32) -> anyhow::Result<Vec<User>> {
+        let results = task_watchers::table
+            .inner_join(users::table)
+            .filter(task_watchers::task_id.eq(task_id))
+            .filter(task_watchers::task_id.eq_any(tenancy::task_ids()))
+            .filter(users::active.eq(true))
+            .order(users::user_id)
+            .select(User::as_select())
+            .load(conn).await?;
+        Ok(results)
+    }
+
+    pub async fn unwatch(conn: &mut DbConnection, task_id: i32, user_id: i32) -> anyhow::Result<usize> {
+        let count = diesel::delete(task_watchers::table.find((task_id, user_id)).filter(task_watchers::task_id.eq_any(tenancy::task_ids()))).execute(conn).await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditedCrud;
+    use crate::crud::CrudOperations;
+    use crate::models::{NewNotificationPreference, NewUserTask, Task, UserTask};
+    use crate::test_support::{self, create_task};
+
+    async fn heard(conn: &mut DbConnection, user_id: i32) -> Vec<String> {
+        notifications::table
+            .filter(notifications::user_id.eq(user_id))
+            .filter(notifications::kind.eq(WATCH_NOTIFICATION))
+            .order(notifications::notification_id)
+            .select(notifications::message)
+            .load(conn).await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn watchers_hear_about_moves_but_not_their_own() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        TaskWatcher::watch(&mut conn, task.task_id, 1).await.unwrap();
+        TaskWatcher::watch(&mut conn, task.task_id, 1).await.unwrap();
+        TaskWatcher::watch(&mut conn, task.task_id, 3).await.unwrap();
+
+        UserTask::create_audited(&mut conn, Some(3), NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        UserTask::update_audited(&mut conn, Some(2), (2, task.task_id), None, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        UserTask::update_audited(&mut conn, Some(2), (2, task.task_id), None, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 3 }).await.unwrap();
+        assert_eq!(heard(&mut conn, 1).await, [
+            format!("User 2 was assigned to task {}", task.task_id),
+            format!("User 2's assignment to task {} moved from status 1 to 3", task.task_id),
+        ]);
+        assert_eq!(heard(&mut conn, 3).await.len(), 1);
+
+        assert_eq!(TaskWatcher::unwatch(&mut conn, task.task_id, 1).await.unwrap(), 1);
+        UserTask::delete_audited(&mut conn, Some(2), (2, task.task_id)).await.unwrap();
+        assert_eq!(heard(&mut conn, 1).await.len(), 2);
+        assert_eq!(heard(&mut conn, 3).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn purging_a_watched_task_drops_its_watchers() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        TaskWatcher::watch(&mut conn, task.task_id, 1).await.unwrap();
+        Task::delete(&mut conn, task.task_id).await.unwrap();
+        assert_eq!(Task::purge_deleted(&mut conn, chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1)).await.unwrap().0, 1);
+        assert_eq!(task_watchers::table.count().get_result::<i64>(&mut conn).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn watchers_who_opted_out_hear_nothing() {
+        let mut conn = test_support::conn().await;
+        let task = create_task(&mut conn, "Paint the fence").await;
+        TaskWatcher::watch(&mut conn, task.task_id, 1).await.unwrap();
+        let opt_out = NewNotificationPreference { user_id: 1, event_type: "assignment.created", email: true, webhook: true, in_app: false, push: true };
+        NotificationPreference::replace_all(&mut conn, 1, vec![opt_out]).await.unwrap();
+        UserTask::create_audited(&mut conn, Some(3), NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        assert!(heard(&mut conn, 1).await.is_empty());
+        UserTask::update_audited(&mut conn, Some(3), (2, task.task_id), None, NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 3 }).await.unwrap();
+        assert_eq!(heard(&mut conn, 1).await.len(), 1);
+    }
+}
@@ -0,0 +1,231 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use crate::DbConnection;
+use serde::Serialize;
+use serde_json::Value;
+use crate::audit::{AuditAction, Auditable};
+use crate::models::{AuditEntry, NewOutboxEntry, NewWebhook, NotificationPreference, OutboxEntry, Task, UserTask, Webhook};
+use crate::preferences::Channel;
+use crate::schema::{outbox, webhooks};
+use crate::tenancy;
+
+// Webhook events come from the audit log: every change to a task or an assignment writes an
+// audit row, and its audit_id doubles as the event id. In the same transaction, audit::log
+// queues one outbox row per subscribed webhook, so a delivery exists if and only if the change
+// committed, and it survives a crash until the receiver has accepted it. Receivers may see an
+// event more than once (if the process dies between sending and recording it) and should
+// use event_id to skip repeats.
+//
+// Names are <entity>.<what happened>. A task or assignment restored from the trash is
+// announced as created again, since to anyone mirroring the data it has reappeared.
+pub const EVENT_TYPES: &[&str] = &[
+    "task.created", "task.updated", "task.deleted",
+    "assignment.created", "assignment.updated", "assignment.deleted",
+];
+
+pub(crate) fn event_type(entity: &str, action: AuditAction) -> Option<&'static str> {
+    let event_type = match (entity, action) {
+        (Task::ENTITY, AuditAction::Create | AuditAction::Restore) => "task.created",
+        (Task::ENTITY, AuditAction::Update) => "task.updated",
+        (Task::ENTITY, AuditAction::Delete) => "task.deleted",
+        (UserTask::ENTITY, AuditAction::Create | AuditAction::Restore) => "assignment.created",
+        (UserTask::ENTITY, AuditAction::Update) => "assignment.updated",
+        (UserTask::ENTITY, AuditAction::Delete) => "assignment.deleted",
+        _ => return None,
+    };
+    Some(event_type)
+}
+
+// The body POSTed to each subscribed URL.
+#[derive(Debug, Serialize)]
+pub struct WebhookEvent {
+    pub event_id: i32,
+    pub event_type: &'static str,
+    pub actor_user_id: Option<i32>,
+    pub occurred_at: chrono::NaiveDateTime,
+    // the task or assignment as it is after the change; as it was before, for deletes
+    pub data: Value,
+}
+
+impl WebhookEvent {
+    fn from_entry(entry: &AuditEntry) -> Option<WebhookEvent> {
+        let event_type = event_type(&entry.entity, AuditAction::parse(&entry.action)?)?;
+        let data = entry.after_json.as_ref().or(entry.before_json.as_ref())?;
+        Some(WebhookEvent {
+            event_id: entry.audit_id,
+            event_type,
+            actor_user_id: entry.actor_user_id,
+            occurred_at: entry.created_at,
+            data: serde_json::from_str(data).ok()?,
+        })
+    }
+}
+
+// Called by audit::log right after it writes `entry`. Webhooks whose owner turned webhooks
+// off for the event type are skipped.
+pub(crate) async fn enqueue(conn: &mut DbConnection, entry: &AuditEntry) -> anyhow::Result<()> {
+    let Some(event) = WebhookEvent::from_entry(entry) else {
+        return Ok(());
+    };
+    let mut subscribed = Webhook::read_subscribed(conn, event.event_type).await?;
+    let owners = subscribed.iter().map(|webhook| webhook.user_id).collect();
+    let allowed = NotificationPreference::allowed(conn, owners, event.event_type, Channel::Webhook).await?;
+    subscribed.retain(|webhook| allowed.contains(&webhook.user_id));
+    if subscribed.is_empty() {
+        return Ok(());
+    }
+    let payload = serde_json::to_string(&event)?;
+    let rows: Vec<NewOutboxEntry> = subscribed.iter()
+        .map(|webhook| NewOutboxEntry { webhook_id: webhook.webhook_id, payload: &payload })
+        .collect();
+    for row in &rows {
+        diesel::insert_into(outbox::table).values(row).execute(conn).await?;
+    }
+    Ok(())
+}
+
+impl OutboxEntry {
+    // Deliveries in the current tenant that are due by `now` and haven't used up their
+    // attempts, oldest first, each with the webhook it goes to.
+    pub async fn read_due(conn: &mut DbConnection, now: chrono::NaiveDateTime, max_attempts: i32, limit: i64) -> anyhow::Result<Vec<(OutboxEntry, Webhook)>> {
+        let due = outbox::table
+            .inner_join(webhooks::table)
+            .filter(webhooks::tenant_id.eq(tenancy::current()))
+            .filter(outbox::next_attempt_at.le(now))
+            .filter(outbox::attempts.lt(max_attempts))
+            .order(outbox::outbox_id)
+            .limit(limit)
+            .select((OutboxEntry::as_select(), Webhook::as_select()))
+            .load(conn).await?;
+        Ok(due)
+    }
+
+    pub async fn delivered(conn: &mut DbConnection, outbox_id: i32) -> anyhow::Result<()> {
+        diesel::delete(outbox::table.find(outbox_id)).execute(conn).await?;
+        Ok(())
+    }
+
+    // Counts the attempt and holds the delivery back until `retry_at`. Once it has used up its
+    // attempts it stays in the table, with the last error, but is no longer read as due.
+    pub async fn failed(conn: &mut DbConnection, outbox_id: i32, error: &str, retry_at: chrono::NaiveDateTime) -> anyhow::Result<()> {
+        diesel::update(outbox::table.find(outbox_id))
+            .set((
+                outbox::attempts.eq(outbox::attempts + 1),
+                outbox::last_error.eq(error),
+                outbox::next_attempt_at.eq(retry_at),
+            ))
+            .execute(conn).await?;
+        Ok(())
+    }
+}
+
+// Webhooks belong to the user who registered them, like API keys.
+impl Webhook {
+    pub async fn create(conn: &mut DbConnection, new_webhook: NewWebhook<'_>) -> anyhow::Result<Webhook> {
+        diesel::insert_into(webhooks::table)
+            .values((&new_webhook, webhooks::tenant_id.eq(tenancy::current())))
+            .execute(conn).await?;
+        let webhook = webhooks::table.find(crate::last_insert_id(conn).await?).first(conn).await?;
+        Ok(webhook)
+    }
+
+    pub async fn read_all_for_user(conn: &mut DbConnection, user_id: i32) -> anyhow::Result<Vec<Webhook>> {
+        let results = webhooks::table
+            .filter(webhooks::tenant_id.eq(tenancy::current()))
+            .filter(webhooks::user_id.eq(user_id))
+            .order(webhooks::webhook_id)
+            .load::<Webhook>(conn).await?;
+        Ok(results)
+    }
+
+    // The tenant's webhooks for one event type, whoever registered them.
+    pub async fn read_subscribed(conn: &mut DbConnection, event_type: &str) -> anyhow::Result<Vec<Webhook>> {
+        let results = webhooks::table
+            .filter(webhooks::tenant_id.eq(tenancy::current()))
+            .filter(webhooks::event_type.eq(event_type))
+            .order(webhooks::webhook_id)
+            .load::<Webhook>(conn).await?;
+        Ok(results)
+    }
+
+    // Returns the number of webhooks deleted (0 when the webhook doesn't belong to the user).
+    // Deliveries still waiting for it are dropped first, since they point at it.
+    pub async fn delete(conn: &mut DbConnection, webhook_id: i32, user_id: i32) -> anyhow::Result<usize> {
+        let owned = webhooks::table
+            .filter(webhooks::tenant_id.eq(tenancy::current()))
+            .filter(webhooks::webhook_id.eq(webhook_id))
+            .filter(webhooks::user_id.eq(user_id));
+        conn.transaction(|conn| async move {
+            diesel::delete(outbox::table.filter(outbox::webhook_id.eq_any(owned.select(webhooks::webhook_id)))).execute(conn).await?;
+            let count = diesel::delete(owned).execute(conn).await?;
+            Ok(count)
+        }.scope_boxed()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditedCrud;
+    use crate::models::{NewUserTask, Tenant};
+    use crate::test_support::{self, new_task};
+
+    async fn hook(conn: &mut DbConnection, event_type: &str) -> Webhook {
+        Webhook::create(conn, NewWebhook { user_id: 2, url: "https://example.com/hook", event_type, secret: "s3cret" }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn changes_queue_a_delivery_per_subscribed_webhook() {
+        let mut conn = test_support::conn().await;
+        let created = hook(&mut conn, "task.created").await;
+        hook(&mut conn, "task.deleted").await;
+        let other = Tenant::create(&mut conn, "Other").await.unwrap();
+        tenancy::enter(&mut conn, other.tenant_id).await.unwrap();
+        hook(&mut conn, "task.created").await;
+        tenancy::enter(&mut conn, tenancy::DEFAULT_TENANT).await.unwrap();
+
+        let task = Task::create_audited(&mut conn, Some(1), new_task("Paint the fence")).await.unwrap();
+        UserTask::create_audited(&mut conn, Some(1), NewUserTask { user_id: 2, task_id: task.task_id, task_status_id: 1 }).await.unwrap();
+        let now = chrono::Utc::now().naive_utc();
+        let due = OutboxEntry::read_due(&mut conn, now, 3, 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        let (entry, webhook) = &due[0];
+        assert_eq!(webhook.webhook_id, created.webhook_id);
+        let payload: serde_json::Value = serde_json::from_str(&entry.payload).unwrap();
+        assert_eq!((payload["event_type"].as_str(), payload["data"]["task_id"].as_i64()), (Some("task.created"), Some(task.task_id as i64)));
+
+        OutboxEntry::failed(&mut conn, entry.outbox_id, "503 Service Unavailable", now + chrono::Duration::minutes(1)).await.unwrap();
+        assert!(OutboxEntry::read_due(&mut conn, now, 3, 10).await.unwrap().is_empty());
+        let later = now + chrono::Duration::minutes(2);
+        assert_eq!(OutboxEntry::read_due(&mut conn, later, 3, 10).await.unwrap()[0].0.attempts, 1);
+        assert!(OutboxEntry::read_due(&mut conn, later, 1, 10).await.unwrap().is_empty());
+        OutboxEntry::delivered(&mut conn, entry.outbox_id).await.unwrap();
+        assert!(OutboxEntry::read_due(&mut conn, later, 3, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_the_owner_deletes_a_webhook() {
+        let mut conn = test_support::conn().await;
+        let webhook = Webhook::create(&mut conn, NewWebhook { user_id: 2, url: "https://example.com/hook", event_type: "task.created", secret: "s3cret" }).await.unwrap();
+        Webhook::create(&mut conn, NewWebhook { user_id: 3, url: "https://example.com/other", event_type: "task.updated", secret: "s3cret" }).await.unwrap();
+        assert_eq!(Webhook::read_subscribed(&mut conn, "task.created").await.unwrap().len(), 1);
+
+        assert_eq!(Webhook::delete(&mut conn, webhook.webhook_id, 3).await.unwrap(), 0);
+        assert_eq!(Webhook::delete(&mut conn, webhook.webhook_id, 2).await.unwrap(), 1);
+        assert!(Webhook::read_all_for_user(&mut conn, 2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleting_a_webhook_drops_its_pending_deliveries() {
+        let mut conn = test_support::conn().await;
+        let webhook = Webhook::create(&mut conn, NewWebhook { user_id: 2, url: "https://example.com/hook", event_type: "task.created", secret: "s3cret" }).await.unwrap();
+        diesel::insert_into(outbox::table).values(&NewOutboxEntry { webhook_id: webhook.webhook_id, payload: "{}" }).execute(&mut conn).await.unwrap();
+
+        assert_eq!(Webhook::delete(&mut conn, webhook.webhook_id, 3).await.unwrap(), 0);
+        assert_eq!(OutboxEntry::read_due(&mut conn, chrono::Utc::now().naive_utc(), 5, 10).await.unwrap().len(), 1);
+        assert_eq!(Webhook::delete(&mut conn, webhook.webhook_id, 2).await.unwrap(), 1);
+        assert!(OutboxEntry::read_due(&mut conn, chrono::Utc::now().naive_utc(), 5, 10).await.unwrap().is_empty());
+    }
+}